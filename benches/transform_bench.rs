@@ -88,6 +88,8 @@ fn bench_fix_chain(c: &mut Criterion) {
         title: Title::new(Namespace::MAIN, "Test"),
         namespace: Namespace::MAIN,
         is_redirect: false,
+        revision: None,
+        cancel: Default::default(),
     };
 
     let mut enabled = HashSet::new();
@@ -106,6 +108,42 @@ fn bench_fix_chain(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_fix_chain_large_page(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fix_chain_large_page");
+
+    // ~600KB page that no enabled fix changes: the pipeline should never
+    // copy it, however many modules run.
+    let clean_page = "Paragraph with [[a link]] and {{cite web|title=Test}}.\n\n".repeat(10_000);
+    // Same page with a single change near the end: one copy, by the one
+    // module that makes it.
+    let mut one_change = clean_page.clone();
+    one_change.push_str("Trailing spaces   \n");
+
+    let registry = FixRegistry::with_defaults();
+    let ctx = FixContext {
+        title: Title::new(Namespace::MAIN, "Test"),
+        namespace: Namespace::MAIN,
+        is_redirect: false,
+        revision: None,
+        cancel: Default::default(),
+    };
+    let enabled: HashSet<String> = registry
+        .all_modules()
+        .iter()
+        .map(|m| m.id().to_string())
+        .collect();
+
+    for (name, text) in [("unchanged", &clean_page), ("one_change", &one_change)] {
+        group.bench_with_input(BenchmarkId::new("apply_all_cow", name), text, |b, text| {
+            b.iter(|| {
+                black_box(registry.apply_all_cow(text, &ctx, &enabled));
+            });
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_diff_computation(c: &mut Criterion) {
     let mut group = c.benchmark_group("diff_computation");
 
@@ -177,6 +215,7 @@ criterion_group!(
     benches,
     bench_plain_rules,
     bench_fix_chain,
+    bench_fix_chain_large_page,
     bench_diff_computation,
     bench_case_insensitive_rules
 );
@@ -1,19 +1,35 @@
 use crate::checkpoint::Checkpoint;
-use crate::config::BotConfig;
-use crate::report::{BotReport, PageAction, PageResult};
-use awb_domain::types::Title;
-use awb_engine::transform::TransformEngine;
+use crate::compliance::ComplianceStamp;
+use crate::config::{BotConfig, LargePageMode};
+use crate::control::{RunControl, RunProgress, Throttle};
+use crate::control_socket;
+use crate::diff_artifacts::DiffArtifacts;
+use crate::report::{BotReport, MemoryStats, PageAction, PageChanges, PageResult, PageSizeStats};
+use crate::snapshots::Snapshots;
+use awb_domain::page_list::{PageList, SampleManifest};
+use awb_domain::session::EditPlan;
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::{PageContent, Title};
+use awb_domain::warnings::Warning;
+use awb_engine::content_guard::ContentLoss;
+use awb_engine::merge::merge3;
+use awb_engine::talk_page::TalkPageMode;
+use awb_engine::transform::{TransformEngine, is_null_edit};
 use awb_mw_api::client::{EditRequest, MediaWikiClient};
 use awb_mw_api::error::MwApiError;
-use awb_security::redact_secrets;
+use awb_security::{redact_secrets, register_secret};
 use awb_telemetry::TelemetryEvent;
 use chrono::Utc;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
 use std::path::Path;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::signal;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Error)]
 pub enum BotError {
@@ -37,6 +53,114 @@ pub enum BotError {
 
     #[error("Interrupted by signal")]
     Interrupted,
+
+    #[error("Compliance violation: {0}")]
+    Compliance(String),
+
+    /// The wiki is read-only; the page can be retried once it is writable
+    #[error("Wiki is read-only: {0}")]
+    ReadOnly(String),
+
+    #[error("Control socket error: {0}")]
+    ControlSocket(String),
+
+    #[error("Snapshot error: {0}")]
+    Snapshot(#[from] crate::snapshots::SnapshotError),
+}
+
+fn dedupe_pages(mut pages: PageList) -> PageList {
+    let removed = pages.dedupe();
+    if removed > 0 {
+        tracing::info!("Dropped {} repeated pages from the page list", removed);
+    }
+    pages
+}
+
+/// A page the bot may edit, fetched and waiting for the engine
+struct FetchedPage {
+    page: PageContent,
+    talk_page_mode: TalkPageMode,
+    by_section: bool,
+    started: Instant,
+}
+
+/// A page once fetched
+enum Fetched {
+    /// Skipped before transforming, e.g. by namespace or bot policy
+    Done(PageResult),
+    Ready(FetchedPage),
+}
+
+/// A page fetched and transformed ahead of its turn in a parallel dry run
+enum Planned {
+    Done(PageResult),
+    Ready(FetchedPage, Box<EditPlan>),
+}
+
+/// Run the engine over a fetched page in the way the config asks for,
+/// recording its memory use in `memory` if the config asks for that
+fn plan_page(
+    engine: &TransformEngine,
+    config: &BotConfig,
+    fetched: &FetchedPage,
+    memory: &Mutex<MemoryStats>,
+) -> EditPlan {
+    let page = &fetched.page;
+    if fetched.talk_page_mode == TalkPageMode::PreserveSignatures {
+        engine.apply_talk(page, &config.bot_name)
+    } else if fetched.by_section {
+        tracing::info!(
+            "Processing page {} ({} bytes) by section",
+            page.title.display,
+            page.wikitext.len()
+        );
+        engine.apply_by_section(page)
+    } else if config.verify_idempotent {
+        engine.verify_idempotent(page)
+    } else if config.profile_memory {
+        let (plan, profile) = engine.apply_profiled(page);
+        memory
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record(&page.title.display, &profile);
+        plan
+    } else {
+        engine.apply(page)
+    }
+}
+
+/// Names of the references `plan` leaves undefined, if any
+fn broken_references(plan: &EditPlan) -> Option<String> {
+    plan.warnings.iter().find_map(|w| match w {
+        Warning::BrokenReferences { names } => Some(names.join(", ")),
+        _ => None,
+    })
+}
+
+/// IDs of the fixes that asked for `plan`'s page to be skipped, if any
+fn skip_requests(plan: &EditPlan) -> Option<String> {
+    plan.warnings.iter().find_map(|w| match w {
+        Warning::SkipRequested { fixes } => Some(fixes.join(", ")),
+        _ => None,
+    })
+}
+
+/// The link targets `plan` changes without a rule meant to, if any, as
+/// `-removed, +added`
+fn changed_link_targets(plan: &EditPlan) -> Option<String> {
+    plan.warnings.iter().find_map(|w| match w {
+        Warning::LinkTargetsChanged { removed, added } => Some(link_target_list(removed, added)),
+        _ => None,
+    })
+}
+
+fn link_target_list(removed: &[String], added: &[String]) -> String {
+    removed
+        .iter()
+        .map(|title| format!("-{}", title))
+        .chain(added.iter().map(|title| format!("+{}", title)))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Bot runner for fully autonomous editing
@@ -44,31 +168,106 @@ pub struct BotRunner<C: MediaWikiClient> {
     config: BotConfig,
     client: Arc<C>,
     engine: TransformEngine,
-    pages: Vec<String>,
+    pages: PageList,
     checkpoint: Checkpoint,
     report: BotReport,
     start_instant: Instant,
     secrets: Vec<String>,
+    diff_artifacts: Option<DiffArtifacts>,
+    snapshots: Option<Snapshots>,
+    /// Namespace names for the content guard
+    site: SiteInfo,
+    /// Sizes of the pages fetched so far, copied into the report at the end
+    page_sizes: Mutex<PageSizeStats>,
+    /// Memory use of the pages planned so far, with `profile_memory` on
+    memory: Mutex<MemoryStats>,
+    /// Cancelled on Ctrl-C, stopping the API call or transform in flight;
+    /// shared with the engine
+    cancel: CancellationToken,
+    /// Pause and emergency stop from outside the run
+    control: RunControl,
+    progress: watch::Sender<RunProgress>,
 }
 
 impl<C: MediaWikiClient> BotRunner<C> {
-    /// Create a new bot runner
-    pub fn new(config: BotConfig, client: C, engine: TransformEngine, pages: Vec<String>) -> Self {
+    /// Create a new bot runner. Repeated pages are dropped from `pages`.
+    pub fn new(config: BotConfig, client: C, engine: TransformEngine, pages: PageList) -> Self {
+        let pages = dedupe_pages(pages);
         let start_time = Utc::now();
+        let diff_artifacts = config.diff_dir.clone().map(DiffArtifacts::new);
+        let snapshots = config.snapshot_dir.clone().map(Snapshots::new);
+        let cancel = CancellationToken::new();
         Self {
             config,
             client: Arc::new(client),
-            engine,
+            engine: engine.with_cancellation(cancel.clone()),
             pages,
             checkpoint: Checkpoint::new(),
             report: BotReport::new(start_time),
             start_instant: Instant::now(),
             secrets: Vec::new(),
+            diff_artifacts,
+            snapshots,
+            site: SiteInfo::default(),
+            page_sizes: Mutex::new(PageSizeStats::default()),
+            memory: Mutex::new(MemoryStats::default()),
+            control: RunControl::new(cancel.clone()),
+            progress: watch::Sender::new(RunProgress::default()),
+            cancel,
+        }
+    }
+
+    /// Note in the report (and the diff index) that `pages` is a sample of
+    /// a larger list
+    #[must_use]
+    pub fn with_sample(mut self, sample: SampleManifest) -> Self {
+        self.diff_artifacts = self
+            .diff_artifacts
+            .take()
+            .map(|artifacts| artifacts.with_sample(sample.clone()));
+        self.report.sample = Some(sample);
+        self
+    }
+
+    /// Use the wiki's namespace names, so the content guard knows its
+    /// category links
+    #[must_use]
+    pub fn with_site(mut self, site: SiteInfo) -> Self {
+        self.site = site;
+        self
+    }
+
+    /// Token that interrupts the run as Ctrl-C does: the API call or
+    /// transform in flight is abandoned, the checkpoint is saved without
+    /// that page, and [`run`](Self::run) returns [`BotError::Interrupted`]
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Handle to pause, resume or stop the run from another task
+    pub fn control(&self) -> RunControl {
+        self.control.clone()
+    }
+
+    /// Progress of the run, updated as each page starts and ends
+    pub fn progress(&self) -> watch::Receiver<RunProgress> {
+        self.progress.subscribe()
+    }
+
+    /// Run `call`, giving up with [`BotError::Interrupted`] as soon as the
+    /// run is cancelled. An edit given up on may still have been saved;
+    /// its page is redone on resume, where it no longer needs the change.
+    async fn cancellable<T>(&self, call: impl Future<Output = T>) -> Result<T, BotError> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(BotError::Interrupted),
+            out = call => Ok(out),
         }
     }
 
-    /// Add a secret to be redacted from error messages
+    /// Add a secret to be redacted from error messages and log output
     pub fn add_secret(&mut self, secret: String) {
+        register_secret(&secret);
         self.secrets.push(secret);
     }
 
@@ -78,24 +277,73 @@ impl<C: MediaWikiClient> BotRunner<C> {
         redact_secrets(message, &secret_refs)
     }
 
+    /// Three-way merge `ours` (planned against `base`) into the latest revision
+    /// of the page, resolving conflicting lines with the configured policy.
+    ///
+    /// Returns `None` if the merge is refused or leaves nothing to save.
+    async fn merge_after_conflict(
+        &self,
+        title: &Title,
+        base: &PageContent,
+        ours: &EditPlan,
+    ) -> Result<Option<(PageContent, EditPlan)>, BotError> {
+        let latest = self
+            .cancellable(self.client.get_page(title))
+            .await?
+            .map_err(|e| {
+                let msg = e.to_string();
+                BotError::ApiError(self.redact_error_message(&msg))
+            })?;
+        let merge = merge3(&base.wikitext, &ours.new_wikitext, &latest.wikitext);
+        let Some(text) = merge.resolve(self.config.conflict_merge_policy) else {
+            tracing::debug!(
+                "Not merging {}: {} conflicting hunks",
+                title.display,
+                merge.conflict_count()
+            );
+            return Ok(None);
+        };
+        if text == latest.wikitext {
+            return Ok(None);
+        }
+
+        let mut plan = ours.clone();
+        plan.diff_ops = awb_engine::diff_engine::compute_diff(&latest.wikitext, &text);
+        plan.new_wikitext = text;
+        plan.page = latest.clone();
+        Ok(Some((latest, plan)))
+    }
+
     /// Create a bot runner with existing checkpoint
     pub fn with_checkpoint(
         config: BotConfig,
         client: C,
         engine: TransformEngine,
-        pages: Vec<String>,
+        pages: PageList,
         checkpoint: Checkpoint,
     ) -> Self {
+        let pages = dedupe_pages(pages);
         let start_time = Utc::now();
+        let diff_artifacts = config.diff_dir.clone().map(DiffArtifacts::new);
+        let snapshots = config.snapshot_dir.clone().map(Snapshots::new);
+        let cancel = CancellationToken::new();
         Self {
             config,
             client: Arc::new(client),
-            engine,
+            engine: engine.with_cancellation(cancel.clone()),
             pages,
             checkpoint,
             report: BotReport::new(start_time),
             start_instant: Instant::now(),
             secrets: Vec::new(),
+            diff_artifacts,
+            snapshots,
+            site: SiteInfo::default(),
+            page_sizes: Mutex::new(PageSizeStats::default()),
+            memory: Mutex::new(MemoryStats::default()),
+            control: RunControl::new(cancel.clone()),
+            progress: watch::Sender::new(RunProgress::default()),
+            cancel,
         }
     }
 
@@ -106,50 +354,138 @@ impl<C: MediaWikiClient> BotRunner<C> {
     ))]
     pub async fn run(&mut self) -> Result<BotReport, BotError> {
         tracing::info!("Starting bot run with {} pages", self.pages.len());
+        self.enforce_compliance()?;
+        self.report.tags = self.config.tags.clone();
+        self.report.provenance = self.engine.provenance();
         self.emit_telemetry(TelemetryEvent::session_started("bot"));
 
+        // Closed again when the run returns
+        let _control_socket = match &self.config.control_socket {
+            Some(path) => {
+                let socket = control_socket::listen(path, self.control(), self.progress())
+                    .map_err(|e| BotError::ControlSocket(format!("{}: {}", path.display(), e)))?;
+                tracing::info!("Accepting control commands on {}", path.display());
+                Some(socket)
+            }
+            None => None,
+        };
+
         // Setup signal handler for graceful shutdown
-        let shutdown_flag = Arc::new(AtomicBool::new(false));
-        let shutdown_flag_clone = shutdown_flag.clone();
+        let cancel = self.cancel.clone();
         tokio::spawn(async move {
             if let Ok(()) = signal::ctrl_c().await {
                 tracing::info!("Received interrupt signal");
-                shutdown_flag_clone.store(true, Ordering::SeqCst);
+                cancel.cancel();
             }
         });
 
+        let done = self
+            .pages
+            .iter()
+            .filter(|t| self.checkpoint.is_completed(&t.display))
+            .count();
+        self.progress.send_modify(|progress| {
+            progress.total = self.pages.len();
+            progress.done = done;
+        });
+
         let mut pages_since_save: u32 = 0;
+        // Pages fetched and transformed ahead in a parallel dry run
+        let pool = self.transform_pool()?;
+        let mut planned = VecDeque::new();
 
-        for (index, page_title) in self.pages.iter().enumerate() {
+        for (index, title) in self.pages.iter().enumerate() {
+            let page_title = &title.display;
             // Identity-based resume: skip pages already completed in a previous run.
             // This is safe even if the page list is reordered between runs.
             if self.checkpoint.is_completed(page_title) {
                 continue;
             }
+            // Hold here while paused from outside
+            if self.control.is_paused() {
+                tracing::info!("Paused before {}", page_title);
+                self.progress.send_modify(|progress| progress.paused = true);
+                let resumed = self.control.wait_while_paused().await;
+                self.progress
+                    .send_modify(|progress| progress.paused = false);
+                if !resumed {
+                    return Err(self.stop_interrupted().await);
+                }
+                tracing::info!("Resumed");
+            }
             // Check stop conditions
             if let Some(reason) = self.should_stop()? {
                 tracing::info!("Stopping bot: {}", reason);
                 self.persist_checkpoint().await;
-                self.report.finalize(false, Some(reason));
+                self.finalize_report(false, reason);
                 return Ok(self.report.clone());
             }
 
             // Check for interrupt
-            if shutdown_flag.load(Ordering::SeqCst) {
-                tracing::info!("Graceful shutdown initiated");
-                self.persist_checkpoint().await;
-                self.report
-                    .finalize(false, Some("Interrupted by user".to_string()));
-                return Err(BotError::Interrupted);
-            }
+            if self.cancel.is_cancelled() {
+                return Err(self.stop_interrupted().await);
+            }
+
+            let mut ahead = match &pool {
+                Some(pool) => {
+                    if planned.is_empty() {
+                        let batch: Vec<Title> = self.pages.titles()[index..]
+                            .iter()
+                            .filter(|t| !self.checkpoint.is_completed(&t.display))
+                            .take(self.config.jobs)
+                            .cloned()
+                            .collect();
+                        planned = self.plan_batch(&batch, pool).await;
+                    }
+                    planned.pop_front()
+                }
+                None => None,
+            };
 
-            // Process page
-            let page_span = tracing::info_span!(
-                "process_page",
-                page_title = %page_title,
-                namespace = tracing::field::Empty
-            );
-            match self.process_page_instrumented(page_title, page_span).await {
+            self.progress
+                .send_modify(|progress| progress.current = Some(page_title.clone()));
+
+            // Process page, pausing while the wiki is read-only
+            let mut read_only_wait = self.config.read_only_wait;
+            let outcome = loop {
+                let page_span = tracing::info_span!(
+                    "process_page",
+                    page_title = %page_title,
+                    namespace = tracing::field::Empty
+                );
+                match self
+                    .process_page_instrumented(title, page_span, ahead.take())
+                    .await
+                {
+                    Err(BotError::ReadOnly(reason)) => {
+                        tracing::warn!(
+                            "Wiki is read-only ({}); retrying {} in {:?}",
+                            reason,
+                            page_title,
+                            read_only_wait
+                        );
+                        self.emit_telemetry(TelemetryEvent::ReadOnly {
+                            reason,
+                            wait_ms: read_only_wait.as_millis() as u64,
+                            timestamp: Utc::now(),
+                        });
+                        if !self.pause(read_only_wait).await {
+                            return Err(self.stop_interrupted().await);
+                        }
+                        if let Some(reason) = self.should_stop()? {
+                            tracing::info!("Stopping bot: {}", reason);
+                            self.persist_checkpoint().await;
+                            self.finalize_report(false, reason);
+                            return Ok(self.report.clone());
+                        }
+                        read_only_wait = (read_only_wait * 2).min(self.config.read_only_max_wait);
+                    }
+                    outcome => break outcome,
+                }
+            };
+            match outcome {
+                // The page is left out of the checkpoint, to be redone
+                Err(BotError::Interrupted) => return Err(self.stop_interrupted().await),
                 Ok(result) => {
                     self.report.record_page(result.clone());
                     let (edited, skipped, errored) = match result.action {
@@ -171,6 +507,8 @@ impl<C: MediaWikiClient> BotRunner<C> {
                         warnings: vec![],
                         error: Some(redacted_msg),
                         timestamp: Utc::now(),
+                        changes: None,
+                        metadata: BTreeMap::new(),
                     };
                     self.report.record_page(result);
                     self.checkpoint
@@ -178,6 +516,14 @@ impl<C: MediaWikiClient> BotRunner<C> {
                 }
             }
 
+            self.progress.send_modify(|progress| {
+                progress.done += 1;
+                progress.edited = self.report.pages_edited;
+                progress.skipped = self.report.pages_skipped;
+                progress.errored = self.report.pages_errored;
+                progress.current = None;
+            });
+
             // Periodic checkpoint persistence (every save_every_n pages)
             pages_since_save += 1;
             if pages_since_save >= self.config.save_every_n {
@@ -200,8 +546,7 @@ impl<C: MediaWikiClient> BotRunner<C> {
 
         tracing::info!("Bot run completed successfully");
         self.persist_checkpoint().await;
-        self.report
-            .finalize(true, Some("All pages processed".to_string()));
+        self.finalize_report(true, "All pages processed".to_string());
         self.emit_telemetry(TelemetryEvent::session_completed(
             self.report.pages_processed,
             self.report.pages_edited,
@@ -213,55 +558,263 @@ impl<C: MediaWikiClient> BotRunner<C> {
         Ok(self.report.clone())
     }
 
-    /// Process a single page with instrumentation
+    /// Threads to transform pages on, in a dry run with more than one job
+    fn transform_pool(&self) -> Result<Option<rayon::ThreadPool>, BotError> {
+        if !self.config.dry_run || self.config.jobs <= 1 {
+            return Ok(None);
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.config.jobs)
+            .build()
+            .map(Some)
+            .map_err(|e| BotError::EngineError(e.to_string()))
+    }
+
+    /// Sleep for `wait`, waking early if the run is cancelled. Returns
+    /// false if the run was interrupted.
+    async fn pause(&self, wait: Duration) -> bool {
+        self.cancellable(tokio::time::sleep(wait)).await.is_ok()
+    }
+
+    /// Save the checkpoint and close the report after an interrupt
+    async fn stop_interrupted(&mut self) -> BotError {
+        tracing::info!("Graceful shutdown initiated");
+        self.persist_checkpoint().await;
+        self.finalize_report(false, "Interrupted by user".to_string());
+        BotError::Interrupted
+    }
+
+    /// Apply the compliance conditions over the rest of the config and stamp
+    /// them into the report. Fails before any page is touched if the engine
+    /// runs a fix above the approved tier.
+    fn enforce_compliance(&mut self) -> Result<(), BotError> {
+        let compliance = self.config.compliance.clone();
+        if !compliance.enabled {
+            return Ok(());
+        }
+        compliance.validate().map_err(BotError::Compliance)?;
+
+        if let Some(max) = compliance.max_tier {
+            if let Some(fix) = self
+                .engine
+                .enabled_fixes()
+                .into_iter()
+                .find(|fix| fix.min_tier > max)
+            {
+                return Err(BotError::Compliance(format!(
+                    "fix '{}' needs tier {}, above the approved tier {}",
+                    fix.id, fix.min_tier, max
+                )));
+            }
+        }
+
+        let today = Utc::now().date_naive();
+        let dry_run_enforced = compliance.requires_dry_run(today);
+        if dry_run_enforced && !self.config.dry_run {
+            tracing::warn!(
+                "Dry-run enforced until {} by compliance mode",
+                compliance.dry_run_until().unwrap_or(today)
+            );
+            self.config.dry_run = true;
+        }
+
+        self.report.compliance = Some(ComplianceStamp {
+            conditions: compliance,
+            dry_run_enforced,
+            edits_today: self.checkpoint.edits_on(today),
+        });
+        Ok(())
+    }
+
+    fn finalize_report(&mut self, completed: bool, reason: String) {
+        if let Some(stamp) = &mut self.report.compliance {
+            stamp.edits_today = self.checkpoint.edits_on(Utc::now().date_naive());
+        }
+        self.report.page_sizes = self
+            .page_sizes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        self.report.prefilter = self.engine.prefilter_stats();
+        self.report.memory = self
+            .memory
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        self.report.finalize(completed, Some(reason));
+    }
+
+    /// Process a single page with instrumentation, finishing it from
+    /// `planned` if it was fetched and transformed ahead of time
     async fn process_page_instrumented(
         &self,
-        page_title: &str,
+        title: &Title,
         span: tracing::Span,
+        planned: Option<Result<Planned, BotError>>,
     ) -> Result<PageResult, BotError> {
         let _guard = span.enter();
-        self.process_page(page_title).await
+        match planned {
+            Some(Ok(Planned::Done(result))) => Ok(result),
+            Some(Ok(Planned::Ready(fetched, plan))) => {
+                self.finish_page(title, fetched, *plan).await
+            }
+            Some(Err(e)) => Err(e),
+            None => self.process_page(title).await,
+        }
+    }
+
+    /// Process a single page. Titles in the page list are already
+    /// canonical, so the namespace comes straight from the title.
+    async fn process_page(&self, title: &Title) -> Result<PageResult, BotError> {
+        match self.fetch_page(title).await? {
+            Fetched::Done(result) => Ok(result),
+            Fetched::Ready(fetched) => {
+                let plan = plan_page(&self.engine, &self.config, &fetched, &self.memory);
+                self.finish_page(title, fetched, plan).await
+            }
+        }
+    }
+
+    /// Fetch and transform the next `titles` for a dry run. Pages are
+    /// fetched one after another; the engine runs on up to `pool`'s threads
+    /// at once. Results are in the order of `titles`.
+    async fn plan_batch(
+        &self,
+        titles: &[Title],
+        pool: &rayon::ThreadPool,
+    ) -> VecDeque<Result<Planned, BotError>> {
+        let mut fetched = Vec::with_capacity(titles.len());
+        for title in titles {
+            fetched.push(self.fetch_page(title).await);
+        }
+        let (engine, config, memory) = (&self.engine, &self.config, &self.memory);
+        pool.install(|| {
+            fetched
+                .into_par_iter()
+                .map(|fetched| {
+                    fetched.map(|fetched| match fetched {
+                        Fetched::Done(result) => Planned::Done(result),
+                        Fetched::Ready(fetched) => {
+                            let plan = plan_page(engine, config, &fetched, memory);
+                            Planned::Ready(fetched, Box::new(plan))
+                        }
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .into()
     }
 
-    /// Process a single page
-    async fn process_page(&self, page_title: &str) -> Result<PageResult, BotError> {
+    /// Fetch a page and check whether the bot may edit it
+    async fn fetch_page(&self, title: &Title) -> Result<Fetched, BotError> {
         let page_start = Instant::now();
+        let page_title = title.display.as_str();
         tracing::debug!("Processing page: {}", page_title);
 
-        // Parse title using namespace_util for proper namespace detection
-        let parsed = awb_engine::namespace_util::parse_title(page_title);
-
         // Record namespace in current span
-        tracing::Span::current().record("namespace", format!("{:?}", parsed.namespace));
+        tracing::Span::current().record("namespace", format!("{:?}", title.namespace));
 
         // Enforce namespace policy
-        if !self.config.is_namespace_allowed(parsed.namespace) {
+        if !self.config.is_namespace_allowed(title.namespace) {
             tracing::debug!(
                 "Skipping page {} (namespace {:?} not allowed)",
                 page_title,
-                parsed.namespace
+                title.namespace
             );
-            return Ok(PageResult {
+            return Ok(Fetched::Done(PageResult {
                 title: page_title.to_string(),
                 action: PageAction::Skipped,
                 diff_summary: Some(format!(
                     "Namespace {:?} not in allowed list",
-                    parsed.namespace
+                    title.namespace
                 )),
                 warnings: vec![],
                 error: None,
                 timestamp: Utc::now(),
-            });
+                changes: None,
+                metadata: BTreeMap::new(),
+            }));
         }
 
-        let title = Title::new(parsed.namespace, &parsed.name);
+        // Article-space fixes would rewrite other people's comments
+        let talk_page_mode = if title.namespace.is_talk() {
+            self.config.talk_page_mode
+        } else {
+            TalkPageMode::Full
+        };
+        if talk_page_mode == TalkPageMode::Skip {
+            tracing::debug!("Skipping page {} (talk page)", page_title);
+            return Ok(Fetched::Done(PageResult {
+                title: page_title.to_string(),
+                action: PageAction::Skipped,
+                diff_summary: Some("Talk page; set talk_page_mode to edit talk pages".to_string()),
+                warnings: vec![],
+                error: None,
+                timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
+            }));
+        }
 
         // Fetch page content
-        let page = self.client.get_page(&title).await.map_err(|e| {
-            let msg = e.to_string();
-            let redacted = self.redact_error_message(&msg);
-            BotError::ApiError(redacted)
-        })?;
+        let page = self
+            .cancellable(self.client.get_page(title))
+            .await?
+            .map_err(|e| {
+                let msg = e.to_string();
+                let redacted = self.redact_error_message(&msg);
+                BotError::ApiError(redacted)
+            })?;
+
+        if let Some(discussion) = awb_engine::talk_page::classify(&page) {
+            tracing::info!("Skipping page {} ({})", page_title, discussion);
+            return Ok(Fetched::Done(PageResult {
+                title: page_title.to_string(),
+                action: PageAction::Skipped,
+                diff_summary: Some(format!("Skipped: {}", discussion)),
+                warnings: vec![],
+                error: None,
+                timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
+            }));
+        }
+
+        let size = page.wikitext.len() as u64;
+        let oversized = self.config.max_page_size.filter(|max| size > *max);
+        {
+            let mut sizes = self.page_sizes.lock().unwrap_or_else(|e| e.into_inner());
+            sizes.record(size);
+            match (oversized, self.config.large_pages) {
+                (None, _) => {}
+                (Some(_), LargePageMode::Skip) => sizes.skipped += 1,
+                (Some(_), LargePageMode::Sections) => sizes.sectioned += 1,
+            }
+        }
+        let by_section = oversized.is_some() && self.config.large_pages == LargePageMode::Sections;
+
+        if let Some(max) = oversized.filter(|_| !by_section) {
+            tracing::info!(
+                "Skipping page {} ({} bytes, limit {})",
+                page_title,
+                page.wikitext.len(),
+                max
+            );
+            return Ok(Fetched::Done(PageResult {
+                title: page_title.to_string(),
+                action: PageAction::Skipped,
+                diff_summary: Some(format!(
+                    "Page is {} bytes, over the {} limit",
+                    page.wikitext.len(),
+                    awb_domain::units::format_byte_size(max)
+                )),
+                warnings: vec![],
+                error: None,
+                timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
+            }));
+        }
 
         // Check {{bots}}/{{nobots}} policy before transforming
         let policy_result =
@@ -272,21 +825,77 @@ impl<C: MediaWikiClient> BotRunner<C> {
                 _ => "unknown".to_string(),
             };
             tracing::info!("Skipping page {} (bot policy: {})", page_title, reason);
-            return Ok(PageResult {
+            return Ok(Fetched::Done(PageResult {
                 title: page_title.to_string(),
                 action: PageAction::Skipped,
                 diff_summary: Some(format!("Bot policy denied: {}", reason)),
                 warnings: vec![],
                 error: None,
                 timestamp: Utc::now(),
-            });
+                changes: None,
+                metadata: BTreeMap::new(),
+            }));
+        }
+
+        Ok(Fetched::Ready(FetchedPage {
+            page,
+            talk_page_mode,
+            by_section,
+            started: page_start,
+        }))
+    }
+
+    /// Save (or in a dry run, report) the edit `plan` makes to a fetched
+    /// page. The result carries the metadata plugins set while planning it,
+    /// whatever happens to the edit.
+    async fn finish_page(
+        &self,
+        title: &Title,
+        fetched: FetchedPage,
+        plan: EditPlan,
+    ) -> Result<PageResult, BotError> {
+        let metadata = plan.metadata.clone();
+        let mut result = self.save_planned(title, fetched, plan).await?;
+        result.metadata = metadata;
+        Ok(result)
+    }
+
+    async fn save_planned(
+        &self,
+        title: &Title,
+        fetched: FetchedPage,
+        plan: EditPlan,
+    ) -> Result<PageResult, BotError> {
+        let FetchedPage {
+            page,
+            talk_page_mode,
+            by_section,
+            started: page_start,
+        } = fetched;
+        let page_title = title.display.as_str();
+        let size = page.wikitext.len() as u64;
+
+        // The engine stops part way through a page when cancelled
+        if self.cancel.is_cancelled() {
+            return Err(BotError::Interrupted);
         }
 
-        // Apply transformations
-        let plan = self.engine.apply(&page);
+        if let Some(fixes) = skip_requests(&plan) {
+            tracing::debug!("Skipping page {} (skip requested by {})", page_title, fixes);
+            return Ok(PageResult {
+                title: page_title.to_string(),
+                action: PageAction::Skipped,
+                diff_summary: Some(format!("Skipped: requested by {}", fixes)),
+                warnings: plan.warnings.iter().map(|w| format!("{:?}", w)).collect(),
+                error: None,
+                timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
+            });
+        }
 
         // Check for no changes
-        if plan.new_wikitext == page.wikitext && self.config.skip_no_change {
+        if is_null_edit(&page.wikitext, &plan.new_wikitext) && self.config.skip_no_change {
             tracing::debug!("Skipping page {} (no changes)", page_title);
             return Ok(PageResult {
                 title: page_title.to_string(),
@@ -295,6 +904,8 @@ impl<C: MediaWikiClient> BotRunner<C> {
                 warnings: vec![],
                 error: None,
                 timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
             });
         }
 
@@ -311,12 +922,77 @@ impl<C: MediaWikiClient> BotRunner<C> {
                 warnings: vec![],
                 error: None,
                 timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
             });
         }
 
         // Check for warnings
         let warnings: Vec<String> = plan.warnings.iter().map(|w| format!("{:?}", w)).collect();
 
+        if plan
+            .warnings
+            .iter()
+            .any(|w| matches!(w, Warning::NotIdempotent { .. }))
+        {
+            tracing::warn!(
+                "Skipping page {} (a second pass would change it again)",
+                page_title
+            );
+            return Ok(PageResult {
+                title: page_title.to_string(),
+                action: PageAction::Skipped,
+                diff_summary: Some("Skipped: edit is not idempotent".to_string()),
+                warnings,
+                error: None,
+                timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
+            });
+        }
+
+        if let Some(names) = broken_references(&plan) {
+            tracing::warn!(
+                "Skipping page {} (references left undefined: {})",
+                page_title,
+                names
+            );
+            return Ok(PageResult {
+                title: page_title.to_string(),
+                action: PageAction::Skipped,
+                diff_summary: Some(format!("Skipped: breaks references {}", names)),
+                warnings,
+                error: None,
+                timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
+            });
+        }
+
+        if let Some(links) = changed_link_targets(&plan) {
+            tracing::warn!(
+                "Skipping page {} (link targets changed: {})",
+                page_title,
+                links
+            );
+            return Ok(PageResult {
+                title: page_title.to_string(),
+                action: PageAction::Skipped,
+                diff_summary: Some(format!("Skipped: changes link targets {}", links)),
+                warnings,
+                error: None,
+                timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
+            });
+        }
+
+        if let Some(blocked) =
+            self.guard_content(page_title, &page.wikitext, &plan.new_wikitext, &warnings)
+        {
+            return Ok(blocked);
+        }
+
         if !warnings.is_empty() && self.config.skip_on_warning {
             tracing::debug!("Skipping page {} (warnings present)", page_title);
             return Ok(PageResult {
@@ -326,6 +1002,8 @@ impl<C: MediaWikiClient> BotRunner<C> {
                 warnings: warnings.clone(),
                 error: None,
                 timestamp: Utc::now(),
+                changes: None,
+                metadata: BTreeMap::new(),
             });
         }
 
@@ -333,6 +1011,7 @@ impl<C: MediaWikiClient> BotRunner<C> {
         for warning in &plan.warnings {
             self.emit_telemetry(TelemetryEvent::Warning {
                 message: format!("Page {}: {:?}", page_title, warning),
+                title: Some(page_title.to_string()),
                 timestamp: Utc::now(),
             });
         }
@@ -349,39 +1028,155 @@ impl<C: MediaWikiClient> BotRunner<C> {
             // Retry loop for edit conflicts (max 2 attempts)
             let max_retries = 1; // 1 retry = 2 total attempts
             let mut attempt = 0;
+            let mut merged: Option<(PageContent, EditPlan)> = None;
+            let mut merge_attempted = false;
 
             loop {
-                // Fetch latest page content if this is a retry
-                let current_page = if attempt > 0 {
-                    tracing::debug!("Retrying edit for {} (attempt {})", page_title, attempt + 1);
-                    self.client.get_page(&title).await.map_err(|e| {
-                        let msg = e.to_string();
-                        let redacted = self.redact_error_message(&msg);
-                        BotError::ApiError(redacted)
-                    })?
+                let (current_page, current_plan) = if let Some(salvaged) = merged.take() {
+                    salvaged
                 } else {
-                    page.clone()
-                };
+                    // Fetch latest page content if this is a retry
+                    let current_page = if attempt > 0 {
+                        tracing::debug!(
+                            "Retrying edit for {} (attempt {})",
+                            page_title,
+                            attempt + 1
+                        );
+                        self.cancellable(self.client.get_page(title))
+                            .await?
+                            .map_err(|e| {
+                                let msg = e.to_string();
+                                let redacted = self.redact_error_message(&msg);
+                                BotError::ApiError(redacted)
+                            })?
+                    } else {
+                        page.clone()
+                    };
 
-                // Re-apply transformations if this is a retry (page may have changed)
-                let current_plan = if attempt > 0 {
-                    self.engine.apply(&current_page)
-                } else {
-                    plan.clone()
+                    // Re-apply transformations if this is a retry (page may have
+                    // changed); only the sections edited in between are redone
+                    let current_plan =
+                        if attempt > 0 && talk_page_mode == TalkPageMode::PreserveSignatures {
+                            self.engine.apply_talk(&current_page, &self.config.bot_name)
+                        } else if attempt > 0 && by_section {
+                            self.engine.apply_by_section(&current_page)
+                        } else if attempt > 0 {
+                            let retry = self.engine.apply_incremental(&page, &plan, &current_page);
+                            tracing::debug!(
+                                "Re-planned {} after conflict: {:?}",
+                                page_title,
+                                retry.mode
+                            );
+                            retry.plan
+                        } else {
+                            plan.clone()
+                        };
+
+                    (current_page, current_plan)
                 };
 
+                if self.cancel.is_cancelled() {
+                    return Err(BotError::Interrupted);
+                }
+
+                // Whoever caused the conflict may already have made our change
+                if (attempt > 0 || merge_attempted)
+                    && is_null_edit(&current_page.wikitext, &current_plan.new_wikitext)
+                {
+                    tracing::Span::current().record("action", "skip");
+                    tracing::debug!("Skipping page {} (no changes after conflict)", page_title);
+                    return Ok(PageResult {
+                        title: page_title.to_string(),
+                        action: PageAction::Skipped,
+                        diff_summary: Some("No changes needed after edit conflict".to_string()),
+                        warnings,
+                        error: None,
+                        timestamp: Utc::now(),
+                        changes: None,
+                        metadata: BTreeMap::new(),
+                    });
+                }
+
+                // A re-planned or merged edit is checked again
+                if attempt > 0 || merge_attempted {
+                    let names = awb_engine::references::broken_references(
+                        &current_page.wikitext,
+                        &current_plan.new_wikitext,
+                    );
+                    if !names.is_empty() {
+                        let names = names.join(", ");
+                        tracing::Span::current().record("action", "skip");
+                        return Ok(PageResult {
+                            title: page_title.to_string(),
+                            action: PageAction::Skipped,
+                            diff_summary: Some(format!("Skipped: breaks references {}", names)),
+                            warnings,
+                            error: None,
+                            timestamp: Utc::now(),
+                            changes: None,
+                            metadata: BTreeMap::new(),
+                        });
+                    }
+                    // Only a plan that was allowed to change link targets
+                    // may change them on retry
+                    let links = awb_engine::link_targets::link_target_changes(
+                        &current_page.wikitext,
+                        &current_plan.new_wikitext,
+                    );
+                    if !links.is_empty()
+                        && awb_engine::link_targets::link_target_changes(
+                            &page.wikitext,
+                            &plan.new_wikitext,
+                        )
+                        .is_empty()
+                    {
+                        let links = link_target_list(&links.removed, &links.added);
+                        tracing::Span::current().record("action", "skip");
+                        return Ok(PageResult {
+                            title: page_title.to_string(),
+                            action: PageAction::Skipped,
+                            diff_summary: Some(format!("Skipped: changes link targets {}", links)),
+                            warnings,
+                            error: None,
+                            timestamp: Utc::now(),
+                            changes: None,
+                            metadata: BTreeMap::new(),
+                        });
+                    }
+                    if let Some(blocked) = self.guard_content(
+                        page_title,
+                        &current_page.wikitext,
+                        &current_plan.new_wikitext,
+                        &warnings,
+                    ) {
+                        tracing::Span::current().record("action", "skip");
+                        return Ok(blocked);
+                    }
+                }
+
                 let edit_request = EditRequest {
                     title: title.clone(),
                     text: current_plan.new_wikitext.clone(),
-                    summary: current_plan.summary.clone(),
+                    summary: self.config.compliance.tag_summary(&current_plan.summary),
                     minor: true,
                     bot: true,
                     base_timestamp: current_page.timestamp.to_rfc3339(),
                     start_timestamp: Utc::now().to_rfc3339(),
                     section: None,
+                    tags: self.config.tags.clone(),
+                    watchlist: self.config.watchlist,
+                };
+
+                // Keep the text being replaced before replacing it
+                let snapshot = match &self.snapshots {
+                    Some(snapshots) => Some(snapshots.store(&current_page)?),
+                    None => None,
                 };
 
-                let response = self.client.edit_page(&edit_request).await;
+                // A plan made while cancelled is incomplete; it is never sent
+                let response = self
+                    .cancellable(self.client.edit_page(&edit_request))
+                    .await?;
 
                 match response {
                     Ok(resp) => {
@@ -407,18 +1202,41 @@ impl<C: MediaWikiClient> BotRunner<C> {
                             );
                         }
 
+                        if let (Some(snapshots), Some(file)) = (&self.snapshots, snapshot) {
+                            // The edit is saved, so a missing index line is not a page error
+                            if let Err(e) = snapshots.record(&current_page, file, resp.new_revid) {
+                                tracing::warn!("Failed to index snapshot of {}: {}", page_title, e);
+                            }
+                        }
+
+                        self.emit_telemetry(TelemetryEvent::EditSaved {
+                            title: page_title.to_string(),
+                            revid: resp.new_revid,
+                            summary: edit_request.summary.clone(),
+                            tags: edit_request.tags.clone(),
+                            timestamp: Utc::now(),
+                        });
+
                         let duration = page_start.elapsed().as_millis() as u64;
                         self.emit_telemetry(TelemetryEvent::PageProcessed {
                             title: page_title.to_string(),
                             outcome: "edited".to_string(),
                             duration_ms: duration,
+                            size_bytes: Some(size),
+                            metadata: current_plan.metadata.clone(),
                             timestamp: Utc::now(),
                         });
 
                         tracing::info!("Saved page {} (rev: {:?})", page_title, resp.new_revid);
 
                         // Sleep after successful edit to respect rate limits
-                        tokio::time::sleep(self.config.edit_delay).await;
+                        self.emit_telemetry(TelemetryEvent::Throttled {
+                            reason: "edit_delay".to_string(),
+                            wait_ms: self.config.edit_delay.as_millis() as u64,
+                            timestamp: Utc::now(),
+                        });
+                        // The edit is saved, so an interrupt only cuts the wait short
+                        self.pause(self.config.edit_delay).await;
 
                         return Ok(PageResult {
                             title: page_title.to_string(),
@@ -430,12 +1248,28 @@ impl<C: MediaWikiClient> BotRunner<C> {
                             warnings,
                             error: None,
                             timestamp: Utc::now(),
+                            changes: Some(PageChanges::of(&current_plan, &self.engine)),
+                            metadata: BTreeMap::new(),
                         });
                     }
                     Err(MwApiError::EditConflict {
                         base_rev,
                         current_rev,
                     }) => {
+                        if attempt >= max_retries && !merge_attempted {
+                            // Last resort: merge our edit into the latest revision
+                            merge_attempted = true;
+                            merged = self
+                                .merge_after_conflict(title, &current_page, &current_plan)
+                                .await?;
+                            if merged.is_some() {
+                                tracing::info!(
+                                    "Merged edit for {} into latest revision",
+                                    page_title
+                                );
+                                continue;
+                            }
+                        }
                         if attempt >= max_retries {
                             // Max retries exceeded - skip this page
                             tracing::Span::current().record("action", "skip");
@@ -455,6 +1289,8 @@ impl<C: MediaWikiClient> BotRunner<C> {
                                 warnings,
                                 error: None,
                                 timestamp: Utc::now(),
+                                changes: None,
+                                metadata: BTreeMap::new(),
                             });
                         }
 
@@ -468,6 +1304,10 @@ impl<C: MediaWikiClient> BotRunner<C> {
                         attempt += 1;
                         continue;
                     }
+                    Err(MwApiError::ReadOnly { reason }) => {
+                        tracing::Span::current().record("action", "skip");
+                        return Err(BotError::ReadOnly(reason));
+                    }
                     Err(e) => {
                         // Other errors - fail immediately
                         let msg = e.to_string();
@@ -485,6 +1325,11 @@ impl<C: MediaWikiClient> BotRunner<C> {
             let _dry_run_guard = dry_run_span.enter();
 
             tracing::info!("Dry-run: would edit page {}", page_title);
+            if let Some(artifacts) = &self.diff_artifacts {
+                if let Err(e) = artifacts.record(page_title, &plan) {
+                    tracing::warn!("Failed to write diff for {}: {}", page_title, e);
+                }
+            }
             Ok(PageResult {
                 title: page_title.to_string(),
                 action: PageAction::Skipped,
@@ -495,10 +1340,50 @@ impl<C: MediaWikiClient> BotRunner<C> {
                 warnings,
                 error: None,
                 timestamp: Utc::now(),
+                changes: Some(PageChanges::of(&plan, &self.engine)),
+                metadata: BTreeMap::new(),
             })
         }
     }
 
+    /// The skipped result for a save the content guard blocks, unless
+    /// `allow_content_loss` is set
+    fn guard_content(
+        &self,
+        page_title: &str,
+        old: &str,
+        new: &str,
+        warnings: &[String],
+    ) -> Option<PageResult> {
+        if self.config.allow_content_loss {
+            return None;
+        }
+        let losses = self.config.content_guard.check(&self.site, old, new);
+        if losses.is_empty() {
+            return None;
+        }
+        let reasons = losses
+            .iter()
+            .map(ContentLoss::to_string)
+            .collect::<Vec<_>>()
+            .join("; ");
+        tracing::warn!(
+            "Skipping page {} (possible content loss: {})",
+            page_title,
+            reasons
+        );
+        Some(PageResult {
+            title: page_title.to_string(),
+            action: PageAction::Skipped,
+            diff_summary: Some(format!("Blocked: possible content loss ({})", reasons)),
+            warnings: warnings.to_vec(),
+            error: None,
+            timestamp: Utc::now(),
+            changes: None,
+            metadata: BTreeMap::new(),
+        })
+    }
+
     /// Persist checkpoint to disk using spawn_blocking to avoid blocking the async runtime.
     /// Logs errors but does not fail the run — checkpoint loss is bounded by save_every_n.
     async fn persist_checkpoint(&self) {
@@ -528,6 +1413,18 @@ impl<C: MediaWikiClient> BotRunner<C> {
             }
         }
 
+        // Check the approved daily edit limit
+        if let Some(max) = self
+            .config
+            .compliance
+            .max_edits_per_day
+            .filter(|_| self.config.compliance.enabled)
+        {
+            if self.checkpoint.edits_on(Utc::now().date_naive()) >= max {
+                return Ok(Some(format!("Daily edit limit reached: {}", max)));
+            }
+        }
+
         // Check max runtime
         if let Some(max_duration) = self.config.max_runtime {
             let elapsed = self.start_instant.elapsed();
@@ -541,6 +1438,19 @@ impl<C: MediaWikiClient> BotRunner<C> {
 
     /// Emit telemetry event
     fn emit_telemetry(&self, event: TelemetryEvent) {
+        match &event {
+            TelemetryEvent::Throttled {
+                reason, wait_ms, ..
+            }
+            | TelemetryEvent::ReadOnly {
+                reason, wait_ms, ..
+            } => {
+                let throttle = Throttle::new(reason, Duration::from_millis(*wait_ms));
+                self.progress
+                    .send_modify(|progress| progress.throttle = Some(throttle));
+            }
+            _ => {}
+        }
         // In production, this would use the telemetry system
         tracing::trace!("Telemetry: {:?}", event);
     }
@@ -563,6 +1473,7 @@ mod tests {
     use super::*;
     use async_trait::async_trait;
     use awb_domain::rules::RuleSet;
+    use awb_domain::siteinfo::SiteInfo;
     use awb_domain::types::{
         Namespace, PageContent, PageId, PageProperties, ProtectionInfo, RevisionId,
     };
@@ -571,17 +1482,30 @@ mod tests {
     use awb_mw_api::error::MwApiError;
     use awb_mw_api::oauth::{OAuth1Config, OAuthSession};
     use std::collections::HashSet;
+    use std::sync::atomic::Ordering;
     use std::time::Duration;
 
+    fn page(raw: &str) -> Title {
+        Title::parse(&SiteInfo::default(), raw).unwrap()
+    }
+
     // Mock MediaWiki client for testing
     struct MockClient {
         pages: std::collections::HashMap<String, PageContent>,
+        summaries: std::sync::Mutex<Vec<String>>,
+        /// Edits to refuse as read-only before accepting any
+        read_only_edits: std::sync::atomic::AtomicU32,
+        /// Edit requests are never answered, like a connection left hanging
+        stall_edits: bool,
     }
 
     impl MockClient {
         fn new() -> Self {
             Self {
                 pages: std::collections::HashMap::new(),
+                summaries: std::sync::Mutex::new(Vec::new()),
+                read_only_edits: std::sync::atomic::AtomicU32::new(0),
+                stall_edits: false,
             }
         }
 
@@ -633,7 +1557,20 @@ mod tests {
                 })
         }
 
-        async fn edit_page(&self, _edit: &EditRequest) -> Result<EditResponse, MwApiError> {
+        async fn edit_page(&self, edit: &EditRequest) -> Result<EditResponse, MwApiError> {
+            if self.stall_edits {
+                std::future::pending::<()>().await;
+            }
+            if self
+                .read_only_edits
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(MwApiError::ReadOnly {
+                    reason: "Database maintenance".to_string(),
+                });
+            }
+            self.summaries.lock().unwrap().push(edit.summary.clone());
             Ok(EditResponse {
                 result: "Success".to_string(),
                 new_revid: Some(101),
@@ -661,93 +1598,747 @@ mod tests {
             Ok(vec![])
         }
 
-        async fn get_backlinks(
-            &self,
-            _title: &str,
-            _limit: u32,
-        ) -> Result<Vec<String>, MwApiError> {
-            Ok(vec![])
+        async fn get_backlinks(
+            &self,
+            _title: &str,
+            _limit: u32,
+        ) -> Result<Vec<String>, MwApiError> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_pauses_while_read_only() {
+        let config = BotConfig::default()
+            .with_edit_delay(Duration::ZERO)
+            .with_read_only_wait(Duration::from_millis(10), Duration::from_millis(15));
+        let mut client = MockClient::new();
+        client.add_page("Page1", "teh cat");
+        client.add_page("Page2", "teh dog");
+        client.read_only_edits.store(3, Ordering::SeqCst);
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("teh", "the", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Page1\nPage2").unwrap(),
+        );
+        let report = runner.run().await.unwrap();
+
+        // The page refused while read-only is retried, not counted as an error
+        assert_eq!(report.pages_edited, 2);
+        assert_eq!(report.pages_errored, 0);
+        assert_eq!(runner.client.summaries.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_report_records_what_edits_changed() {
+        let config = BotConfig::default().with_edit_delay(Duration::ZERO);
+        let mut client = MockClient::new();
+        client.add_page("Page1", "teh cat");
+        client.add_page("Page2", "teh dog, teh end. Dogs are loyal.");
+        client.add_page("Page3", "no typos");
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("teh", "the", true));
+        ruleset.add(awb_domain::rules::Rule::new_plain(", the end", "", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Page1\nPage2\nPage3").unwrap(),
+        );
+        let report = runner.run().await.unwrap();
+
+        let changes = &report.changes;
+        assert_eq!(changes.pages, 2);
+        assert_eq!(changes.top_rules(5), vec![("teh", 2), (", the end", 1)]);
+        assert!(changes.bytes_removed > changes.bytes_added);
+        let page2 = report.page_results[1].changes.as_ref().unwrap();
+        assert_eq!(page2.bytes_delta(), -9);
+        assert!(report.page_results[2].changes.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_saved_pages_are_snapshotted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = BotConfig::default()
+            .with_edit_delay(Duration::ZERO)
+            .with_snapshot_dir(dir.path().to_path_buf());
+        let mut client = MockClient::new();
+        client.add_page("Page1", "teh cat");
+        client.add_page("Page2", "no typos");
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("teh", "the", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Page1\nPage2").unwrap(),
+        );
+        runner.run().await.unwrap();
+
+        let index = crate::snapshots::SnapshotIndex::load(dir.path()).unwrap();
+        assert_eq!(index.entries.len(), 1);
+        let entry = index.original("Page1").unwrap();
+        assert_eq!(index.read(entry).unwrap(), "teh cat");
+        assert!(index.original("Page2").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_abandons_edit_in_flight() {
+        let config = BotConfig::default().with_edit_delay(Duration::ZERO);
+        let mut client = MockClient::new();
+        client.add_page("Page1", "teh cat");
+        client.add_page("Page2", "teh dog");
+        client.stall_edits = true;
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("teh", "the", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Page1\nPage2").unwrap(),
+        );
+        let cancel = runner.cancellation_token();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        let result = tokio::time::timeout(Duration::from_secs(10), runner.run())
+            .await
+            .expect("the stalled edit is abandoned");
+
+        assert!(matches!(result, Err(BotError::Interrupted)));
+        assert!(runner.engine.is_cancelled());
+        // The page in flight is left for a resumed run to redo
+        assert!(!runner.checkpoint.is_completed("Page1"));
+        assert_eq!(runner.report().pages_processed, 0);
+        assert_eq!(
+            runner.report().stop_reason.as_deref(),
+            Some("Interrupted by user")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paused_run_waits_for_resume() {
+        let config = BotConfig::default().with_edit_delay(Duration::ZERO);
+        let mut client = MockClient::new();
+        client.add_page("Page1", "teh cat");
+        client.add_page("Page2", "teh dog");
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("teh", "the", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Page1\nPage2").unwrap(),
+        );
+        let control = runner.control();
+        let mut progress = runner.progress();
+        control.pause();
+        tokio::spawn(async move {
+            progress.wait_for(|progress| progress.paused).await.unwrap();
+            control.resume();
+        });
+        let report = tokio::time::timeout(Duration::from_secs(10), runner.run())
+            .await
+            .expect("the run is resumed")
+            .unwrap();
+
+        assert_eq!(report.pages_edited, 2);
+        let progress = runner.progress().borrow().clone();
+        assert_eq!((progress.done, progress.total, progress.edited), (2, 2, 2));
+        assert!(!progress.paused);
+        assert_eq!(progress.current, None);
+        assert_eq!(progress.throttle.unwrap().reason, "edit_delay");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_control_socket_resumes_paused_run() {
+        use crate::control_socket::{ControlCommand, ControlReply, RunState, send};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.sock");
+        let config = BotConfig::default()
+            .with_edit_delay(Duration::ZERO)
+            .with_control_socket(path.clone());
+        let mut client = MockClient::new();
+        client.add_page("Page1", "teh cat");
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("teh", "the", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Page1").unwrap(),
+        );
+        runner.control().pause();
+        let mut progress = runner.progress();
+        let operator = tokio::spawn({
+            let path = path.clone();
+            async move {
+                progress.wait_for(|progress| progress.paused).await.unwrap();
+                let status = send(&path, ControlCommand::Status).await.unwrap();
+                send(&path, ControlCommand::Resume).await.unwrap();
+                status
+            }
+        });
+        let report = tokio::time::timeout(Duration::from_secs(10), runner.run())
+            .await
+            .expect("the run is resumed over the socket")
+            .unwrap();
+
+        let ControlReply::Status(status) = operator.await.unwrap() else {
+            panic!("expected a status");
+        };
+        assert_eq!(status.state, RunState::Paused);
+        assert_eq!(status.progress.total, 1);
+        assert_eq!(report.pages_edited, 1);
+        // The socket goes away with the run
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_stop_while_paused_interrupts_run() {
+        let mut client = MockClient::new();
+        client.add_page("Page1", "teh cat");
+        let engine =
+            TransformEngine::new(&RuleSet::new(), FixRegistry::new(), HashSet::new()).unwrap();
+
+        let mut runner = BotRunner::new(
+            BotConfig::default(),
+            client,
+            engine,
+            PageList::from_lines("Page1").unwrap(),
+        );
+        let control = runner.control();
+        control.pause();
+        control.stop();
+        let result = runner.run().await;
+
+        assert!(matches!(result, Err(BotError::Interrupted)));
+        assert_eq!(runner.report().pages_processed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_skips_null_edit() {
+        let config = BotConfig::default().with_skip_no_change(true);
+        let mut client = MockClient::new();
+        client.add_page("Page1", "the end");
+        let mut ruleset = RuleSet::new();
+        // MediaWiki drops trailing whitespace, so this change saves nothing
+        ruleset.add(awb_domain::rules::Rule::new_plain("end", "end\n\n", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Page1").unwrap(),
+        );
+        let result = runner.process_page(&page("Page1")).await.unwrap();
+
+        assert_eq!(result.action, PageAction::Skipped);
+        assert_eq!(result.diff_summary.as_deref(), Some("No changes needed"));
+        assert!(runner.client.summaries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fix_skip_request_skips_page() {
+        use awb_engine::general_fixes::{FixContext, FixModule};
+        use std::borrow::Cow;
+
+        /// Capitalizes the page but asks for it to be skipped
+        struct SkipAll;
+        impl FixModule for SkipAll {
+            fn id(&self) -> &str {
+                "skip_all"
+            }
+            fn display_name(&self) -> &str {
+                "Skip all"
+            }
+            fn category(&self) -> &str {
+                "Test"
+            }
+            fn description(&self) -> &str {
+                "Asks for every page to be skipped"
+            }
+            fn apply<'a>(&self, text: &'a str, _context: &FixContext) -> Cow<'a, str> {
+                Cow::Owned(text.to_uppercase())
+            }
+            fn requests_skip(&self, _ctx: &FixContext) -> bool {
+                true
+            }
+        }
+
+        let mut client = MockClient::new();
+        client.add_page("Page1", "some text");
+        let mut registry = FixRegistry::new();
+        registry.register(Box::new(SkipAll)).unwrap();
+        let engine = TransformEngine::new(
+            &RuleSet::new(),
+            registry,
+            HashSet::from(["skip_all".to_string()]),
+        )
+        .unwrap();
+
+        let runner = BotRunner::new(
+            BotConfig::default(),
+            client,
+            engine,
+            PageList::from_lines("Page1").unwrap(),
+        );
+        let result = runner.process_page(&page("Page1")).await.unwrap();
+        assert_eq!(result.action, PageAction::Skipped);
+        assert_eq!(
+            result.diff_summary.as_deref(),
+            Some("Skipped: requested by skip_all")
+        );
+        assert!(runner.client.summaries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broken_references_block_the_edit() {
+        let mut client = MockClient::new();
+        client.add_page(
+            "Page1",
+            "Teh fact.<ref name=\"a\">Source.</ref> Again.<ref name=\"a\" /> More text here.",
+        );
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("Teh", "The", true));
+        ruleset.add(awb_domain::rules::Rule::new_regex(
+            r#"<ref name="a">[^<]*</ref>"#,
+            "",
+            true,
+        ));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            BotConfig::default(),
+            client,
+            engine,
+            PageList::from_lines("Page1").unwrap(),
+        );
+        let result = runner.process_page(&page("Page1")).await.unwrap();
+        assert_eq!(result.action, PageAction::Skipped);
+        assert_eq!(
+            result.diff_summary.as_deref(),
+            Some("Skipped: breaks references a")
+        );
+        assert!(runner.client.summaries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_changed_link_targets_block_the_edit() {
+        let text = "Teh [[Foo (band)|Foo]] toured with [[Bar]] in the spring of that year.";
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("Teh", "The", true));
+        ruleset.add(awb_domain::rules::Rule::new_regex(
+            r"\[\[[^|\]]+\|(Foo)\]\]",
+            "[[$1]]",
+            false,
+        ));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+        let mut client = MockClient::new();
+        client.add_page("Page1", text);
+
+        let runner = BotRunner::new(
+            BotConfig::default(),
+            client,
+            engine,
+            PageList::from_lines("Page1").unwrap(),
+        );
+        let result = runner.process_page(&page("Page1")).await.unwrap();
+        assert_eq!(result.action, PageAction::Skipped);
+        assert_eq!(
+            result.diff_summary.as_deref(),
+            Some("Skipped: changes link targets -Foo (band), +Foo")
+        );
+        assert!(runner.client.summaries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_content_guard_blocks_runaway_regex() {
+        let text = "Lead text.\n\n== A ==\nBody.\n\n[[Category:X]]\n";
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_regex(r"(?s)Lead.*", "", true));
+        let engine = || TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+        let mut client = MockClient::new();
+        client.add_page("Page1", text);
+
+        let runner = BotRunner::new(
+            BotConfig::default(),
+            client,
+            engine(),
+            PageList::from_lines("Page1").unwrap(),
+        );
+        let result = runner.process_page(&page("Page1")).await.unwrap();
+        assert_eq!(result.action, PageAction::Skipped);
+        let summary = result.diff_summary.unwrap();
+        assert!(summary.starts_with("Blocked: possible content loss"));
+        assert!(summary.contains("all 1 categories removed"));
+        assert!(runner.client.summaries.lock().unwrap().is_empty());
+
+        // The override lets an intentional removal through
+        let mut client = MockClient::new();
+        client.add_page("Page1", text);
+        let runner = BotRunner::new(
+            BotConfig::default().with_allow_content_loss(true),
+            client,
+            engine(),
+            PageList::from_lines("Page1").unwrap(),
+        );
+        let result = runner.process_page(&page("Page1")).await.unwrap();
+        assert_eq!(result.action, PageAction::Edited);
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_new() {
+        let config = BotConfig::default();
+        let client = MockClient::new();
+        let ruleset = RuleSet::new();
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+        let pages = PageList::from_lines("Page1").unwrap();
+
+        let runner = BotRunner::new(config, client, engine, pages);
+        assert_eq!(runner.pages.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_skip_no_change() {
+        let config = BotConfig::default().with_skip_no_change(true);
+        let mut client = MockClient::new();
+        client.add_page("TestPage", "unchanged content");
+
+        let ruleset = RuleSet::new();
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+        let pages = PageList::from_lines("TestPage").unwrap();
+
+        let runner = BotRunner::new(config, client, engine, pages);
+        let result = runner.process_page(&page("TestPage")).await.unwrap();
+
+        assert_eq!(result.action, PageAction::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_nobots_skips_page() {
+        let config = BotConfig::default().with_bot_name("TestBot");
+        let mut client = MockClient::new();
+        client.add_page("NobotPage", "Some text\n{{nobots}}\nMore text");
+
+        let ruleset = RuleSet::new();
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("NobotPage").unwrap(),
+        );
+        let result = runner.process_page(&page("NobotPage")).await.unwrap();
+
+        assert_eq!(result.action, PageAction::Skipped);
+        assert!(result.diff_summary.unwrap().contains("Bot policy denied"));
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_bots_deny_specific() {
+        let config = BotConfig::default().with_bot_name("AWB-RS");
+        let mut client = MockClient::new();
+        client.add_page("DenyPage", "Text {{bots|deny=AWB-RS}} more");
+
+        let ruleset = RuleSet::new();
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("DenyPage").unwrap(),
+        );
+        let result = runner.process_page(&page("DenyPage")).await.unwrap();
+
+        assert_eq!(result.action, PageAction::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_namespace_enforcement() {
+        // Default config only allows MAIN namespace
+        let config = BotConfig::default();
+        let client = MockClient::new();
+
+        let ruleset = RuleSet::new();
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Talk:SomePage").unwrap(),
+        );
+        let result = runner.process_page(&page("Talk:SomePage")).await.unwrap();
+
+        assert_eq!(result.action, PageAction::Skipped);
+        assert!(result.diff_summary.unwrap().contains("Namespace"));
+    }
+
+    fn talk_runner(mode: TalkPageMode, talk: &[(&str, &str)]) -> BotRunner<MockClient> {
+        let config = BotConfig::default()
+            .with_allowed_namespaces(HashSet::from([Namespace::TALK]))
+            .with_talk_page_mode(mode)
+            .with_dry_run(true);
+        let mut client = MockClient::new();
+        for (name, wikitext) in talk {
+            client.add_page(name, wikitext);
+            let stored = client.pages.get_mut(*name).unwrap();
+            stored.title = page(name);
         }
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("Ann", "Anne", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+        BotRunner::new(config, client, engine, PageList::new())
     }
 
     #[tokio::test]
-    async fn test_bot_runner_new() {
-        let config = BotConfig::default();
-        let client = MockClient::new();
-        let ruleset = RuleSet::new();
-        let registry = FixRegistry::new();
-        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
-        let pages = vec!["Page1".to_string()];
+    async fn test_talk_pages_skipped_unless_allowed() {
+        let runner = talk_runner(TalkPageMode::Skip, &[("Talk:Cats", "Ann")]);
+        let result = runner.process_page(&page("Talk:Cats")).await.unwrap();
+        assert_eq!(result.action, PageAction::Skipped);
+        assert!(result.diff_summary.unwrap().contains("talk_page_mode"));
+    }
 
-        let runner = BotRunner::new(config, client, engine, pages);
-        assert_eq!(runner.pages.len(), 1);
+    #[tokio::test]
+    async fn test_talk_page_mode_edits_only_own_comments() {
+        let by_ann = "== Cats ==\nAnn is right. [[User:Ann|Ann]] 09:15, 3 May 2024 (UTC)\n";
+        let by_bot = "== Cats ==\nAnn is right. [[User:AWB-RS]] 09:15, 3 May 2024 (UTC)\n";
+        let runner = talk_runner(
+            TalkPageMode::PreserveSignatures,
+            &[
+                ("Talk:Cats", by_ann),
+                ("Talk:Dogs", by_bot),
+                ("Talk:Cats/Archive 1", by_bot),
+            ],
+        );
+
+        let result = runner.process_page(&page("Talk:Cats")).await.unwrap();
+        assert_eq!(result.diff_summary.as_deref(), Some("No changes needed"));
+
+        let result = runner.process_page(&page("Talk:Dogs")).await.unwrap();
+        assert!(result.diff_summary.unwrap().contains("Dry-run"));
+
+        let result = runner
+            .process_page(&page("Talk:Cats/Archive 1"))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.diff_summary.as_deref(),
+            Some("Skipped: talk page archive")
+        );
     }
 
     #[tokio::test]
-    async fn test_bot_runner_skip_no_change() {
-        let config = BotConfig::default().with_skip_no_change(true);
+    async fn test_bot_runner_skips_oversized_page() {
+        let config = BotConfig::default().with_max_page_size(10);
         let mut client = MockClient::new();
-        client.add_page("TestPage", "unchanged content");
+        client.add_page("BigPage", "test content that is long");
 
-        let ruleset = RuleSet::new();
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("test", "modified", true));
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
-        let pages = vec!["TestPage".to_string()];
 
-        let runner = BotRunner::new(config, client, engine, pages);
-        let result = runner.process_page("TestPage").await.unwrap();
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("BigPage").unwrap(),
+        );
+        let result = runner.process_page(&page("BigPage")).await.unwrap();
 
         assert_eq!(result.action, PageAction::Skipped);
+        assert!(result.diff_summary.unwrap().contains("over the 10B limit"));
     }
 
     #[tokio::test]
-    async fn test_bot_runner_nobots_skips_page() {
-        let config = BotConfig::default().with_bot_name("TestBot");
+    async fn test_bot_runner_edits_oversized_page_by_section() {
+        let config = BotConfig::default()
+            .with_max_page_size(10)
+            .with_large_pages(LargePageMode::Sections)
+            .with_dry_run(true);
         let mut client = MockClient::new();
-        client.add_page("NobotPage", "Some text\n{{nobots}}\nMore text");
+        client.add_page("BigPage", "test content\n== More ==\nmore test content");
 
-        let ruleset = RuleSet::new();
-        let registry = FixRegistry::new();
-        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("test", "modified", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(config, client, engine, PageList::new());
+        let result = runner.process_page(&page("BigPage")).await.unwrap();
+        assert!(result.diff_summary.unwrap().contains("Dry-run"));
 
-        let runner = BotRunner::new(config, client, engine, vec!["NobotPage".to_string()]);
-        let result = runner.process_page("NobotPage").await.unwrap();
+        let sizes = runner.page_sizes.lock().unwrap().clone();
+        assert_eq!(sizes.pages, 1);
+        assert_eq!(sizes.sectioned, 1);
+        assert_eq!(sizes.skipped, 0);
+    }
 
-        assert_eq!(result.action, PageAction::Skipped);
-        assert!(result.diff_summary.unwrap().contains("Bot policy denied"));
+    fn compliance_runner(
+        compliance: crate::compliance::ComplianceConfig,
+        titles: &[&str],
+    ) -> BotRunner<MockClient> {
+        let config = BotConfig::default()
+            .with_edit_delay(std::time::Duration::ZERO)
+            .with_compliance(compliance);
+        let mut client = MockClient::new();
+        for title in titles {
+            client.add_page(title, "test content");
+        }
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("test", "modified", true));
+        let engine = TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+        let pages = titles.iter().map(|t| page(t)).collect();
+        BotRunner::new(config, client, engine, pages)
     }
 
     #[tokio::test]
-    async fn test_bot_runner_bots_deny_specific() {
-        let config = BotConfig::default().with_bot_name("AWB-RS");
-        let mut client = MockClient::new();
-        client.add_page("DenyPage", "Text {{bots|deny=AWB-RS}} more");
+    async fn test_repeated_pages_are_processed_once() {
+        let mut runner = compliance_runner(Default::default(), &["PageA", "pageA", "PageA"]);
 
-        let ruleset = RuleSet::new();
-        let registry = FixRegistry::new();
-        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+        let report = runner.run().await.unwrap();
 
-        let runner = BotRunner::new(config, client, engine, vec!["DenyPage".to_string()]);
-        let result = runner.process_page("DenyPage").await.unwrap();
+        assert_eq!(report.pages_processed, 1);
+        assert_eq!(report.pages_edited, 1);
+    }
 
-        assert_eq!(result.action, PageAction::Skipped);
+    #[tokio::test]
+    async fn test_parallel_dry_run_matches_sequential() {
+        let titles = ["P0", "P1", "P2", "P3", "P4", "P5", "P6"];
+        let run = |jobs: usize| {
+            let config = BotConfig::default()
+                .with_dry_run(true)
+                .with_jobs(jobs)
+                .with_save_every_n(1);
+            let mut client = MockClient::new();
+            for (i, title) in titles.iter().enumerate() {
+                // P5 is missing, so fetching it fails
+                match i {
+                    5 => {}
+                    _ if i % 2 == 0 => client.add_page(title, "test content"),
+                    _ => client.add_page(title, "nothing to do"),
+                }
+            }
+            let mut ruleset = RuleSet::new();
+            ruleset.add(awb_domain::rules::Rule::new_plain("test", "modified", true));
+            let engine =
+                TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap();
+            let pages = titles.iter().map(|t| page(t)).collect();
+            let mut checkpoint = Checkpoint::new();
+            checkpoint.record_page("P1".to_string(), false, true, false);
+            BotRunner::with_checkpoint(config, client, engine, pages, checkpoint)
+        };
+        let outcomes = |report: BotReport| -> Vec<_> {
+            report
+                .page_results
+                .into_iter()
+                .map(|r| (r.title, r.action, r.diff_summary, r.error.is_some()))
+                .collect()
+        };
+
+        let sequential = outcomes(run(1).run().await.unwrap());
+        let parallel = outcomes(run(3).run().await.unwrap());
+        assert_eq!(sequential.len(), 6, "P1 was done in an earlier run");
+        assert_eq!(parallel, sequential);
+        assert_eq!(
+            parallel.iter().map(|r| r.0.as_str()).collect::<Vec<_>>(),
+            ["P0", "P2", "P3", "P4", "P5", "P6"]
+        );
+        assert!(parallel[4].3, "missing page is an error");
     }
 
     #[tokio::test]
-    async fn test_bot_runner_namespace_enforcement() {
-        // Default config only allows MAIN namespace
-        let config = BotConfig::default();
-        let client = MockClient::new();
+    async fn test_compliance_enforces_dry_run_period() {
+        let mut runner = compliance_runner(
+            crate::compliance::ComplianceConfig {
+                enabled: true,
+                dry_run_days: 7,
+                start_date: Some(Utc::now().date_naive()),
+                ..Default::default()
+            },
+            &["PageA"],
+        );
 
-        let ruleset = RuleSet::new();
-        let registry = FixRegistry::new();
-        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+        let report = runner.run().await.unwrap();
 
-        let runner = BotRunner::new(config, client, engine, vec!["Talk:SomePage".to_string()]);
-        let result = runner.process_page("Talk:SomePage").await.unwrap();
+        assert_eq!(report.pages_edited, 0);
+        assert!(runner.client.summaries.lock().unwrap().is_empty());
+        let stamp = report.compliance.unwrap();
+        assert!(stamp.dry_run_enforced);
+    }
 
-        assert_eq!(result.action, PageAction::Skipped);
-        assert!(result.diff_summary.unwrap().contains("Namespace"));
+    #[tokio::test]
+    async fn test_compliance_tags_summaries_and_caps_daily_edits() {
+        let mut runner = compliance_runner(
+            crate::compliance::ComplianceConfig {
+                enabled: true,
+                summary_tag: Some("[[WP:BRFA/Bot]]".to_string()),
+                max_edits_per_day: Some(1),
+                ..Default::default()
+            },
+            &["PageA", "PageB"],
+        );
+
+        let report = runner.run().await.unwrap();
+
+        assert_eq!(report.pages_edited, 1);
+        assert_eq!(
+            report.stop_reason.as_deref(),
+            Some("Daily edit limit reached: 1")
+        );
+        let summaries = runner.client.summaries.lock().unwrap().clone();
+        assert_eq!(summaries.len(), 1);
+        assert!(summaries[0].ends_with("[[WP:BRFA/Bot]]"));
+        assert_eq!(report.compliance.unwrap().edits_today, 1);
+    }
+
+    #[tokio::test]
+    async fn test_compliance_rejects_fix_above_max_tier() {
+        let registry = FixRegistry::with_defaults();
+        let fix = registry
+            .fix_infos()
+            .into_iter()
+            .find(|fix| fix.min_tier > 0)
+            .unwrap();
+        let engine =
+            TransformEngine::new(&RuleSet::new(), registry, HashSet::from([fix.id.clone()]))
+                .unwrap();
+        let config = BotConfig::default().with_compliance(crate::compliance::ComplianceConfig {
+            enabled: true,
+            max_tier: Some(0),
+            ..Default::default()
+        });
+        let mut runner = BotRunner::new(config, MockClient::new(), engine, PageList::new());
+
+        let err = runner.run().await.unwrap_err();
+        assert!(matches!(err, BotError::Compliance(msg) if msg.contains(&fix.id)));
     }
 
     #[tokio::test]
@@ -760,8 +2351,13 @@ mod tests {
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
-        let runner = BotRunner::new(config, client, engine, vec!["MainPage".to_string()]);
-        let result = runner.process_page("MainPage").await.unwrap();
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("MainPage").unwrap(),
+        );
+        let result = runner.process_page(&page("MainPage")).await.unwrap();
 
         // Should proceed (not skipped for namespace), but skipped for no-change
         assert_eq!(result.action, PageAction::Skipped);
@@ -779,16 +2375,69 @@ mod tests {
 
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
-        let pages = vec!["TestPage".to_string()];
+        let pages = PageList::from_lines("TestPage").unwrap();
 
         let runner = BotRunner::new(config, client, engine, pages);
-        let result = runner.process_page("TestPage").await.unwrap();
+        let result = runner.process_page(&page("TestPage")).await.unwrap();
 
         // In dry-run mode, pages with changes are still "skipped" (not actually saved)
         assert_eq!(result.action, PageAction::Skipped);
         assert!(result.diff_summary.unwrap().contains("Dry-run"));
     }
 
+    #[tokio::test]
+    async fn test_bot_runner_dry_run_writes_diffs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = BotConfig::default()
+            .with_dry_run(true)
+            .with_diff_dir(dir.path().to_path_buf());
+        let mut client = MockClient::new();
+        client.add_page("TestPage", "test content\n");
+
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("test", "modified", true));
+
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("TestPage").unwrap(),
+        );
+        runner.process_page(&page("TestPage")).await.unwrap();
+
+        let diff = std::fs::read_to_string(dir.path().join("0001-TestPage.diff")).unwrap();
+        assert!(diff.contains("+modified content"));
+        assert!(dir.path().join(crate::diff_artifacts::INDEX_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_bot_runner_skips_non_idempotent_edit() {
+        let config = BotConfig::default()
+            .with_dry_run(true)
+            .with_verify_idempotent(true);
+        let mut client = MockClient::new();
+        client.add_page("TestPage", "a");
+
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("a", "aa", true));
+
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("TestPage").unwrap(),
+        );
+        let result = runner.process_page(&page("TestPage")).await.unwrap();
+        assert_eq!(result.action, PageAction::Skipped);
+        assert!(result.warnings.iter().any(|w| w.contains("NotIdempotent")));
+    }
+
     #[tokio::test]
     async fn test_identity_based_resume_skips_completed() {
         // Simulate a checkpoint where "PageA" was already completed
@@ -805,7 +2454,7 @@ mod tests {
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
         // Pages list is reordered: PageB first, PageA second
-        let pages = vec!["PageB".to_string(), "PageA".to_string()];
+        let pages = PageList::from_lines("PageB\nPageA").unwrap();
         let mut runner = BotRunner::with_checkpoint(config, client, engine, pages, checkpoint);
         let report = runner.run().await.unwrap();
 
@@ -826,8 +2475,13 @@ mod tests {
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
-        let runner = BotRunner::new(config, client, engine, vec!["Image:Foo.jpg".to_string()]);
-        let result = runner.process_page("Image:Foo.jpg").await.unwrap();
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("Image:Foo.jpg").unwrap(),
+        );
+        let result = runner.process_page(&page("Image:Foo.jpg")).await.unwrap();
 
         assert_eq!(result.action, PageAction::Skipped);
         assert!(result.diff_summary.unwrap().contains("Namespace"));
@@ -842,8 +2496,13 @@ mod tests {
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
-        let runner = BotRunner::new(config, client, engine, vec!["User:Example".to_string()]);
-        let result = runner.process_page("User:Example").await.unwrap();
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("User:Example").unwrap(),
+        );
+        let result = runner.process_page(&page("User:Example")).await.unwrap();
 
         assert_eq!(result.action, PageAction::Skipped);
         assert!(result.diff_summary.unwrap().contains("Namespace"));
@@ -858,14 +2517,18 @@ mod tests {
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
-        let mut runner =
-            BotRunner::new(config, client, engine, vec!["NonexistentPage".to_string()]);
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("NonexistentPage").unwrap(),
+        );
 
         // Add a secret that might appear in API errors
         runner.add_secret("mysecret123456".to_string());
 
         // Process a page that doesn't exist to trigger an error
-        let result = runner.process_page("NonexistentPage").await;
+        let result = runner.process_page(&page("NonexistentPage")).await;
 
         // The error should occur but not contain the raw secret
         assert!(result.is_err());
@@ -960,10 +2623,15 @@ mod tests {
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
-        let mut runner = BotRunner::new(config, client, engine, vec!["TestPage".to_string()]);
+        let mut runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("TestPage").unwrap(),
+        );
         runner.add_secret("secret987654321".to_string());
 
-        let result = runner.process_page("TestPage").await;
+        let result = runner.process_page(&page("TestPage")).await;
 
         // Should fail due to API error
         assert!(result.is_err());
@@ -1066,7 +2734,7 @@ mod tests {
             config,
             client,
             engine,
-            vec!["Page1".to_string(), "Page2".to_string()],
+            PageList::from_lines("Page1\nPage2").unwrap(),
         );
         runner.add_secret("mypassword12345678".to_string());
 
@@ -1220,7 +2888,7 @@ mod tests {
             config,
             client,
             engine,
-            vec!["Page1".to_string(), "Page2".to_string()],
+            PageList::from_lines("Page1\nPage2").unwrap(),
         );
 
         // Run the bot
@@ -1364,8 +3032,13 @@ mod tests {
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
-        let runner = BotRunner::new(config, client, engine, vec!["TestPage".to_string()]);
-        let result = runner.process_page("TestPage").await.unwrap();
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("TestPage").unwrap(),
+        );
+        let result = runner.process_page(&page("TestPage")).await.unwrap();
 
         // Should succeed after retry
         assert_eq!(result.action, PageAction::Edited);
@@ -1463,8 +3136,13 @@ mod tests {
         let registry = FixRegistry::new();
         let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
 
-        let runner = BotRunner::new(config, client, engine, vec!["TestPage".to_string()]);
-        let result = runner.process_page("TestPage").await.unwrap();
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("TestPage").unwrap(),
+        );
+        let result = runner.process_page(&page("TestPage")).await.unwrap();
 
         // Should be skipped after two conflicts
         assert_eq!(result.action, PageAction::Skipped);
@@ -1475,4 +3153,131 @@ mod tests {
                 .contains("Edit conflict persisted after retry")
         );
     }
+
+    #[tokio::test]
+    async fn test_edit_conflict_salvaged_by_merge() {
+        use std::sync::Mutex;
+
+        // Mock client that conflicts twice, then accepts the merged edit
+        struct ConflictTwiceClient {
+            saved: Mutex<Vec<String>>,
+        }
+
+        #[async_trait]
+        impl MediaWikiClient for ConflictTwiceClient {
+            async fn login_bot_password(
+                &self,
+                _username: &str,
+                _password: &str,
+            ) -> Result<(), MwApiError> {
+                Ok(())
+            }
+
+            async fn login_oauth1(&self, _config: OAuth1Config) -> Result<(), MwApiError> {
+                Ok(())
+            }
+
+            async fn login_oauth2(&self, _session: OAuthSession) -> Result<(), MwApiError> {
+                Ok(())
+            }
+
+            async fn fetch_csrf_token(&self) -> Result<String, MwApiError> {
+                Ok("token".to_string())
+            }
+
+            async fn get_page(&self, title: &Title) -> Result<PageContent, MwApiError> {
+                let edits = self.saved.lock().unwrap().len();
+                let wikitext = if edits == 0 {
+                    "content\nline\n"
+                } else {
+                    "content\nline changed by someone else\n"
+                };
+                Ok(PageContent {
+                    page_id: PageId(1),
+                    title: title.clone(),
+                    revision: RevisionId(100 + edits as u64),
+                    timestamp: Utc::now(),
+                    wikitext: wikitext.to_string(),
+                    size_bytes: wikitext.len() as u64,
+                    is_redirect: false,
+                    protection: ProtectionInfo::default(),
+                    properties: PageProperties::default(),
+                })
+            }
+
+            async fn edit_page(&self, edit: &EditRequest) -> Result<EditResponse, MwApiError> {
+                let mut saved = self.saved.lock().unwrap();
+                saved.push(edit.text.clone());
+                if saved.len() <= 2 {
+                    return Err(MwApiError::EditConflict {
+                        base_rev: RevisionId(100),
+                        current_rev: RevisionId(101),
+                    });
+                }
+                Ok(EditResponse {
+                    result: "Success".to_string(),
+                    new_revid: Some(102),
+                    new_timestamp: Some(Utc::now().to_rfc3339()),
+                })
+            }
+
+            async fn parse_wikitext(
+                &self,
+                _wikitext: &str,
+                _title: &Title,
+            ) -> Result<String, MwApiError> {
+                Ok("<html></html>".to_string())
+            }
+
+            async fn list_category_members(
+                &self,
+                _category: &str,
+                _limit: u32,
+            ) -> Result<Vec<String>, MwApiError> {
+                Ok(vec![])
+            }
+
+            async fn search_pages(
+                &self,
+                _query: &str,
+                _limit: u32,
+            ) -> Result<Vec<String>, MwApiError> {
+                Ok(vec![])
+            }
+
+            async fn get_backlinks(
+                &self,
+                _title: &str,
+                _limit: u32,
+            ) -> Result<Vec<String>, MwApiError> {
+                Ok(vec![])
+            }
+        }
+
+        let config = BotConfig::default()
+            .with_skip_no_change(false)
+            .with_edit_delay(Duration::ZERO);
+        let client = ConflictTwiceClient {
+            saved: Mutex::new(Vec::new()),
+        };
+
+        let mut ruleset = RuleSet::new();
+        ruleset.add(awb_domain::rules::Rule::new_plain("content", "FIXED", true));
+
+        let registry = FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let runner = BotRunner::new(
+            config,
+            client,
+            engine,
+            PageList::from_lines("TestPage").unwrap(),
+        );
+        let result = runner.process_page(&page("TestPage")).await.unwrap();
+
+        assert_eq!(result.action, PageAction::Edited);
+        let saved = runner.client.saved.lock().unwrap();
+        assert_eq!(saved.len(), 3);
+        assert_eq!(saved[2], "FIXED\nline changed by someone else\n");
+    }
 }
@@ -1,3 +1,5 @@
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::Title;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use thiserror::Error;
@@ -11,13 +13,21 @@ pub enum CheckpointError {
     ParseError(#[from] serde_json::Error),
 }
 
+/// Checkpoint format version. Version 0 files stored titles as written;
+/// from version 1 they are stored normalized.
+pub const CHECKPOINT_VERSION: u32 = 1;
+
 /// Checkpoint data for resuming bot runs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Checkpoint {
+    /// Format version, 0 for files written before titles were normalized
+    #[serde(default)]
+    pub version: u32,
+
     /// Index of the last processed page
     pub last_processed_index: usize,
 
-    /// List of completed page titles
+    /// List of completed page titles, normalized and without duplicates
     pub completed_pages: Vec<String>,
 
     /// HashSet for fast lookup of completed pages
@@ -35,12 +45,21 @@ pub struct Checkpoint {
 
     /// Timestamp of last checkpoint save
     pub last_save_time: chrono::DateTime<chrono::Utc>,
+
+    /// Pages edited per UTC day, for daily edit limits that span runs
+    #[serde(default)]
+    pub edits_by_day: std::collections::BTreeMap<chrono::NaiveDate, u32>,
+
+    /// Namespaces and case rules used to normalize titles
+    #[serde(skip)]
+    site: SiteInfo,
 }
 
 impl Checkpoint {
     /// Create a new checkpoint
     pub fn new() -> Self {
         Self {
+            version: CHECKPOINT_VERSION,
             last_processed_index: 0,
             completed_pages: Vec::new(),
             completed_pages_set: std::collections::HashSet::new(),
@@ -48,9 +67,45 @@ impl Checkpoint {
             pages_skipped: 0,
             pages_errored: 0,
             last_save_time: chrono::Utc::now(),
+            edits_by_day: std::collections::BTreeMap::new(),
+            site: SiteInfo::default(),
+        }
+    }
+
+    /// Normalize titles with `site`'s namespaces and case rules rather than
+    /// the defaults, re-keying the pages already completed
+    pub fn with_site(mut self, site: SiteInfo) -> Self {
+        self.site = site;
+        self.renormalize();
+        self
+    }
+
+    /// The key a title is stored under: `Foo_bar`, `foo bar` and
+    /// `Foo  bar` are the same page, as are `WP:X` and `Wikipedia:X`.
+    /// Titles that do not parse are only trimmed and have underscores
+    /// replaced.
+    pub fn normalize(&self, title: &str) -> String {
+        match Title::parse(&self.site, title) {
+            Ok(title) => title.display,
+            Err(_) => title.replace('_', " ").trim().to_string(),
         }
     }
 
+    /// Normalize the completed pages, dropping entries that turn out to be
+    /// the same page, and rebuild the lookup set
+    fn renormalize(&mut self) {
+        let mut seen = std::collections::HashSet::new();
+        let mut pages = Vec::with_capacity(self.completed_pages.len());
+        for title in std::mem::take(&mut self.completed_pages) {
+            let key = self.normalize(&title);
+            if seen.insert(key.clone()) {
+                pages.push(key);
+            }
+        }
+        self.completed_pages = pages;
+        self.completed_pages_set = seen;
+    }
+
     /// Save checkpoint to file atomically (temp file + rename).
     /// This ensures a crash mid-write never leaves a corrupt checkpoint.
     pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
@@ -83,19 +138,36 @@ impl Checkpoint {
     pub fn load(path: &Path) -> Result<Self, CheckpointError> {
         let json = std::fs::read_to_string(path)?;
         let mut checkpoint: Self = serde_json::from_str(&json)?;
-        // Rebuild the HashSet from the Vec after deserialization
-        checkpoint.completed_pages_set = checkpoint.completed_pages.iter().cloned().collect();
+        // Rebuild the HashSet from the Vec after deserialization, migrating
+        // titles written before they were normalized
+        let stored = checkpoint.completed_pages.len();
+        checkpoint.renormalize();
+        if checkpoint.version < CHECKPOINT_VERSION {
+            tracing::info!(
+                "Migrated checkpoint from version {}: {} completed pages, {} duplicates merged",
+                checkpoint.version,
+                checkpoint.completed_pages.len(),
+                stored - checkpoint.completed_pages.len()
+            );
+            checkpoint.version = CHECKPOINT_VERSION;
+        }
         Ok(checkpoint)
     }
 
     /// Update checkpoint with page completion
     pub fn record_page(&mut self, title: String, edited: bool, skipped: bool, errored: bool) {
-        self.completed_pages.push(title.clone());
-        self.completed_pages_set.insert(title);
+        let key = self.normalize(&title);
+        if self.completed_pages_set.insert(key.clone()) {
+            self.completed_pages.push(key);
+        }
         self.last_processed_index += 1;
 
         if edited {
             self.pages_edited += 1;
+            *self
+                .edits_by_day
+                .entry(chrono::Utc::now().date_naive())
+                .or_default() += 1;
         } else if skipped {
             self.pages_skipped += 1;
         } else if errored {
@@ -105,9 +177,14 @@ impl Checkpoint {
         self.last_save_time = chrono::Utc::now();
     }
 
+    /// Pages edited on a given UTC day
+    pub fn edits_on(&self, day: chrono::NaiveDate) -> u32 {
+        self.edits_by_day.get(&day).copied().unwrap_or(0)
+    }
+
     /// Check if a page has been completed
     pub fn is_completed(&self, title: &str) -> bool {
-        self.completed_pages_set.contains(title)
+        self.completed_pages_set.contains(&self.normalize(title))
     }
 
     /// Get the next page index to process
@@ -225,4 +302,68 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_title_variants_are_one_page() {
+        let mut checkpoint = Checkpoint::new();
+        checkpoint.record_page("Foo_bar".to_string(), true, false, false);
+        checkpoint.record_page("WP:Sandbox".to_string(), false, true, false);
+
+        assert!(checkpoint.is_completed("Foo bar"));
+        assert!(checkpoint.is_completed("foo_bar"));
+        assert!(checkpoint.is_completed(" Foo  bar "));
+        assert!(checkpoint.is_completed("Wikipedia:Sandbox"));
+        assert!(checkpoint.is_completed("project:sandbox"));
+        assert!(!checkpoint.is_completed("Foo baz"));
+
+        checkpoint.record_page("Foo bar".to_string(), false, true, false);
+        assert_eq!(checkpoint.completed_pages, ["Foo bar", "Project:Sandbox"]);
+        assert_eq!(checkpoint.last_processed_index, 3);
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore = "Flaky on Windows due to file locking")]
+    fn test_checkpoint_migrates_unnormalized_titles() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = TempDir::new()?;
+        let checkpoint_path = temp_dir.path().join("old_checkpoint.json");
+        std::fs::write(
+            &checkpoint_path,
+            r#"{
+                "last_processed_index": 3,
+                "completed_pages": ["Foo_bar", "foo bar", "WP:AFD"],
+                "pages_edited": 2,
+                "pages_skipped": 1,
+                "pages_errored": 0,
+                "last_save_time": "2024-05-01T00:00:00Z"
+            }"#,
+        )?;
+
+        let loaded = Checkpoint::load(&checkpoint_path)?;
+        assert_eq!(loaded.version, CHECKPOINT_VERSION);
+        assert_eq!(loaded.completed_pages, ["Foo bar", "Project:AFD"]);
+        assert!(loaded.is_completed("Foo bar"));
+        assert!(loaded.is_completed("Wikipedia:AFD"));
+        assert_eq!(loaded.last_processed_index, 3);
+
+        loaded.save(&checkpoint_path)?;
+        let json = std::fs::read_to_string(&checkpoint_path)?;
+        assert!(json.contains("\"version\": 1"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_site_rekeys_completed_pages() {
+        let mut checkpoint = Checkpoint::new();
+        checkpoint.record_page("Shop:Item".to_string(), true, false, false);
+        assert!(!checkpoint.is_completed("Store:Item"));
+
+        let mut site = SiteInfo::default();
+        site.aliases
+            .push(("Store".to_string(), awb_domain::types::Namespace::PROJECT));
+        site.aliases
+            .push(("Shop".to_string(), awb_domain::types::Namespace::PROJECT));
+        let checkpoint = checkpoint.with_site(site);
+        assert!(checkpoint.is_completed("Store:Item"));
+        assert_eq!(checkpoint.completed_pages, ["Project:Item"]);
+    }
 }
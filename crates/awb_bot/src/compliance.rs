@@ -0,0 +1,152 @@
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Conditions of a bot approval (e.g. a BRFA trial), set in the
+/// `[compliance]` block of a bot config.
+///
+/// When enabled, the runner enforces these on top of whatever other options
+/// or command-line flags were given, and stamps them into the report.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComplianceConfig {
+    /// Enforce the conditions below
+    pub enabled: bool,
+
+    /// Highest fix strictness tier (0-3) the bot may run
+    pub max_tier: Option<u8>,
+
+    /// Number of days from `start_date` during which edits are never saved
+    pub dry_run_days: u32,
+
+    /// First day of the approval period; required with `dry_run_days`
+    pub start_date: Option<NaiveDate>,
+
+    /// Text every edit summary must contain, e.g. a link to the approval;
+    /// appended to summaries that lack it
+    pub summary_tag: Option<String>,
+
+    /// Maximum edits per UTC day, counted across runs sharing a checkpoint
+    pub max_edits_per_day: Option<u32>,
+}
+
+impl ComplianceConfig {
+    /// Check the conditions are consistent
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(tier) = self.max_tier.filter(|tier| *tier > 3) {
+            return Err(format!("compliance.max_tier must be 0-3, got {}", tier));
+        }
+        if self.dry_run_days > 0 && self.start_date.is_none() {
+            return Err("compliance.dry_run_days requires compliance.start_date".to_string());
+        }
+        if self
+            .summary_tag
+            .as_deref()
+            .is_some_and(|tag| tag.trim().is_empty())
+        {
+            return Err("compliance.summary_tag must not be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// First day on which edits may be saved, if a dry-run period is set
+    pub fn dry_run_until(&self) -> Option<NaiveDate> {
+        self.start_date
+            .filter(|_| self.dry_run_days > 0)
+            .and_then(|start| start.checked_add_days(Days::new(self.dry_run_days.into())))
+    }
+
+    /// Whether `today` falls inside the mandatory dry-run period
+    pub fn requires_dry_run(&self, today: NaiveDate) -> bool {
+        self.enabled
+            && match (self.start_date, self.dry_run_until()) {
+                (Some(start), Some(until)) => today < until || today < start,
+                _ => false,
+            }
+    }
+
+    /// `summary` with the required tag appended if it is missing
+    pub fn tag_summary(&self, summary: &str) -> String {
+        match self.summary_tag.as_deref().filter(|_| self.enabled) {
+            Some(tag) if !summary.contains(tag) => {
+                if summary.is_empty() {
+                    tag.to_string()
+                } else {
+                    format!("{} {}", summary, tag)
+                }
+            }
+            _ => summary.to_string(),
+        }
+    }
+}
+
+/// Compliance conditions in force for a run, as recorded in the report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplianceStamp {
+    /// The conditions that were enforced
+    pub conditions: ComplianceConfig,
+    /// Saving was turned off because the run fell in the dry-run period
+    pub dry_run_enforced: bool,
+    /// Edits counted against `max_edits_per_day` when the run ended
+    pub edits_today: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn trial() -> ComplianceConfig {
+        ComplianceConfig {
+            enabled: true,
+            dry_run_days: 7,
+            start_date: Some(date(1)),
+            summary_tag: Some("([[WP:BRFA/ExampleBot|BRFA]])".to_string()),
+            ..ComplianceConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_dry_run_period() {
+        let config = trial();
+        assert_eq!(config.dry_run_until(), Some(date(8)));
+        assert!(config.requires_dry_run(date(1)));
+        assert!(config.requires_dry_run(date(7)));
+        assert!(!config.requires_dry_run(date(8)));
+
+        let disabled = ComplianceConfig {
+            enabled: false,
+            ..trial()
+        };
+        assert!(!disabled.requires_dry_run(date(1)));
+    }
+
+    #[test]
+    fn test_tag_summary() {
+        let config = trial();
+        assert_eq!(
+            config.tag_summary("typo fixes"),
+            "typo fixes ([[WP:BRFA/ExampleBot|BRFA]])"
+        );
+        let tagged = config.tag_summary("typo fixes");
+        assert_eq!(config.tag_summary(&tagged), tagged);
+        assert_eq!(config.tag_summary(""), "([[WP:BRFA/ExampleBot|BRFA]])");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(trial().validate().is_ok());
+        let no_start = ComplianceConfig {
+            start_date: None,
+            ..trial()
+        };
+        assert!(no_start.validate().unwrap_err().contains("start_date"));
+        let bad_tier = ComplianceConfig {
+            max_tier: Some(4),
+            ..trial()
+        };
+        assert!(bad_tier.validate().is_err());
+    }
+}
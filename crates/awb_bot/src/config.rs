@@ -1,16 +1,36 @@
+use crate::compliance::ComplianceConfig;
+use awb_domain::types::Watchlist;
+use awb_domain::units;
+use awb_engine::content_guard::ContentLossGuard;
+use awb_engine::merge::MergePolicy;
+use awb_engine::talk_page::TalkPageMode;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
 
-/// Configuration for bot mode operation
+/// Configuration for bot mode operation.
+///
+/// Durations are written like `"12s"` or `"2h30m"` and sizes like `"2MB"`;
+/// see [`awb_domain::units`]. Keys left out take their default value.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BotConfig {
     /// Maximum number of edits to perform (None = unlimited)
     pub max_edits: Option<u32>,
 
     /// Maximum runtime duration (None = unlimited)
+    #[serde(with = "units::option_duration")]
     pub max_runtime: Option<Duration>,
 
+    /// Pages whose wikitext is larger than this many bytes are skipped or
+    /// processed by section, see `large_pages` (None = unlimited)
+    #[serde(with = "units::option_byte_size")]
+    pub max_page_size: Option<u64>,
+
+    /// What to do with pages over `max_page_size`
+    #[serde(default)]
+    pub large_pages: LargePageMode,
+
     /// Skip pages where rules make no changes
     pub skip_no_change: bool,
 
@@ -38,26 +58,131 @@ pub struct BotConfig {
     #[serde(default)]
     pub allowed_namespaces: std::collections::HashSet<awb_domain::types::Namespace>,
 
+    /// How pages in talk namespaces are treated once their namespace is
+    /// allowed (default: skipped). In `preserve_signatures` mode only the
+    /// comments signed as `bot_name` are edited. Flow boards and archives
+    /// are always skipped.
+    #[serde(default)]
+    pub talk_page_mode: TalkPageMode,
+
     /// Path to save checkpoint file for crash recovery
     pub checkpoint_path: Option<PathBuf>,
 
     /// Delay between edits (default: 10 seconds)
-    #[serde(default = "default_edit_delay")]
+    #[serde(default = "default_edit_delay", with = "units::duration")]
     pub edit_delay: Duration,
 
+    /// How long to pause when the wiki reports read-only mode before trying
+    /// the page again (default: 1 minute). The pause doubles each time the
+    /// wiki is still read-only, up to `read_only_max_wait`.
+    #[serde(default = "default_read_only_wait", with = "units::duration")]
+    pub read_only_wait: Duration,
+
+    /// Longest single pause while the wiki is read-only (default: 30 minutes)
+    #[serde(default = "default_read_only_max_wait", with = "units::duration")]
+    pub read_only_max_wait: Duration,
+
     /// Save checkpoint every N pages (default: 25). Set to 1 to save after every page.
     #[serde(default = "default_save_every_n")]
     pub save_every_n: u32,
+
+    /// How to resolve conflicting lines when merging an edit into the latest
+    /// revision after repeated edit conflicts
+    #[serde(default)]
+    pub conflict_merge_policy: MergePolicy,
+
+    /// Directory to write a unified diff per page to in dry-run mode
+    #[serde(default)]
+    pub diff_dir: Option<PathBuf>,
+
+    /// Directory to keep the compressed original wikitext of every saved
+    /// page in, for `awb-rs restore`
+    #[serde(default)]
+    pub snapshot_dir: Option<PathBuf>,
+
+    /// Unix socket (named pipe on Windows) to accept `pause`, `resume`,
+    /// `stop` and `status` commands on while running
+    #[serde(default)]
+    pub control_socket: Option<PathBuf>,
+
+    /// Run the pipeline a second time over each result and skip pages it
+    /// would change again
+    #[serde(default)]
+    pub verify_idempotent: bool,
+
+    /// Skip edits that shrink a page too far, remove all its categories or
+    /// empty its lead section; see [`ContentLossGuard`]
+    #[serde(default)]
+    pub content_guard: ContentLossGuard,
+
+    /// Save edits the content guard would block, for runs meant to remove
+    /// content
+    #[serde(default)]
+    pub allow_content_loss: bool,
+
+    /// Pages transformed at once in dry-run mode (default: 1). Pages are
+    /// still fetched and reported in list order, and edits are always
+    /// saved one at a time.
+    #[serde(default = "default_jobs")]
+    pub jobs: usize,
+
+    /// Record the memory each rule and fix uses on each page, for the
+    /// report; see [`awb_engine::memory`]. Pages edited by section or as a
+    /// talk page are not profiled.
+    #[serde(default)]
+    pub profile_memory: bool,
+
+    /// Change tags applied to every edit, e.g. `awb-rs`, so a run's edits
+    /// can be found and reverted by tag. Each tag must be defined on the
+    /// wiki (Special:Tags) or the edit is refused.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Whether saved pages are added to or removed from the bot account's
+    /// watchlist (default: follow the account's preferences)
+    #[serde(default)]
+    pub watchlist: Watchlist,
+
+    /// Approval conditions that other options cannot loosen
+    #[serde(default)]
+    pub compliance: ComplianceConfig,
 }
 
+/// How [`BotConfig`] handles pages over `max_page_size`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LargePageMode {
+    /// Skip the page.
+    #[default]
+    Skip,
+    /// Run the rules one section at a time, so no rule works on a copy of
+    /// the whole page. General fixes, which need the whole page, do not run.
+    Sections,
+}
+
+/// Placeholder in [`BotConfig::tags`] for the hash of the rules in use
+pub const RULES_TAG_PLACEHOLDER: &str = "{rules}";
+
 fn default_edit_delay() -> Duration {
     Duration::from_secs(10)
 }
 
+fn default_read_only_wait() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_read_only_max_wait() -> Duration {
+    Duration::from_secs(30 * 60)
+}
+
 fn default_save_every_n() -> u32 {
     25
 }
 
+fn default_jobs() -> usize {
+    1
+}
+
 fn default_skip_cosmetic_only() -> bool {
     true
 }
@@ -67,6 +192,8 @@ impl Default for BotConfig {
         Self {
             max_edits: None,
             max_runtime: None,
+            max_page_size: None,
+            large_pages: LargePageMode::default(),
             skip_no_change: true,
             skip_on_warning: false,
             emergency_stop_file: std::env::var_os("HOME")
@@ -83,9 +210,24 @@ impl Default for BotConfig {
                 ns.insert(awb_domain::types::Namespace::MAIN);
                 ns
             },
+            talk_page_mode: TalkPageMode::default(),
             checkpoint_path: None,
             edit_delay: default_edit_delay(),
+            read_only_wait: default_read_only_wait(),
+            read_only_max_wait: default_read_only_max_wait(),
             save_every_n: default_save_every_n(),
+            conflict_merge_policy: MergePolicy::default(),
+            diff_dir: None,
+            snapshot_dir: None,
+            control_socket: None,
+            verify_idempotent: false,
+            content_guard: ContentLossGuard::default(),
+            allow_content_loss: false,
+            jobs: default_jobs(),
+            profile_memory: false,
+            tags: Vec::new(),
+            watchlist: Watchlist::default(),
+            compliance: ComplianceConfig::default(),
         }
     }
 }
@@ -96,6 +238,12 @@ impl BotConfig {
         Self::default()
     }
 
+    /// Parse a bot config from TOML. Errors give the line and column of the
+    /// offending key.
+    pub fn from_toml(source: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(source)
+    }
+
     /// Set maximum number of edits
     #[must_use]
     pub fn with_max_edits(mut self, max: u32) -> Self {
@@ -110,6 +258,20 @@ impl BotConfig {
         self
     }
 
+    /// Set the largest page, in bytes, the bot will edit
+    #[must_use]
+    pub fn with_max_page_size(mut self, bytes: u64) -> Self {
+        self.max_page_size = Some(bytes);
+        self
+    }
+
+    /// Set what happens to pages over `max_page_size`
+    #[must_use]
+    pub fn with_large_pages(mut self, mode: LargePageMode) -> Self {
+        self.large_pages = mode;
+        self
+    }
+
     /// Set whether to skip pages with no changes
     #[must_use]
     pub fn with_skip_no_change(mut self, skip: bool) -> Self {
@@ -176,6 +338,14 @@ impl BotConfig {
         self
     }
 
+    /// Set the first and longest pause while the wiki is read-only
+    #[must_use]
+    pub fn with_read_only_wait(mut self, initial: Duration, max: Duration) -> Self {
+        self.read_only_wait = initial;
+        self.read_only_max_wait = max.max(initial);
+        self
+    }
+
     /// Set checkpoint save cadence
     #[must_use]
     pub fn with_save_every_n(mut self, n: u32) -> Self {
@@ -183,6 +353,109 @@ impl BotConfig {
         self
     }
 
+    /// Set how conflicting lines are resolved when merging after edit conflicts
+    #[must_use]
+    pub fn with_conflict_merge_policy(mut self, policy: MergePolicy) -> Self {
+        self.conflict_merge_policy = policy;
+        self
+    }
+
+    /// Set the directory dry-run diffs are written to
+    #[must_use]
+    pub fn with_diff_dir(mut self, dir: PathBuf) -> Self {
+        self.diff_dir = Some(dir);
+        self
+    }
+
+    /// Keep the original text of saved pages in `dir`
+    #[must_use]
+    pub fn with_snapshot_dir(mut self, dir: PathBuf) -> Self {
+        self.snapshot_dir = Some(dir);
+        self
+    }
+
+    /// Accept control commands on a Unix socket or Windows named pipe
+    #[must_use]
+    pub fn with_control_socket(mut self, path: PathBuf) -> Self {
+        self.control_socket = Some(path);
+        self
+    }
+
+    /// Set how pages in talk namespaces are treated
+    #[must_use]
+    pub fn with_talk_page_mode(mut self, mode: TalkPageMode) -> Self {
+        self.talk_page_mode = mode;
+        self
+    }
+
+    /// Set whether pages whose edit is not idempotent are skipped
+    #[must_use]
+    pub fn with_verify_idempotent(mut self, verify: bool) -> Self {
+        self.verify_idempotent = verify;
+        self
+    }
+
+    /// Set the content loss thresholds
+    #[must_use]
+    pub fn with_content_guard(mut self, guard: ContentLossGuard) -> Self {
+        self.content_guard = guard;
+        self
+    }
+
+    /// Save edits even when the content guard would block them
+    #[must_use]
+    pub fn with_allow_content_loss(mut self, allow: bool) -> Self {
+        self.allow_content_loss = allow;
+        self
+    }
+
+    /// Set how many pages are transformed at once in dry-run mode
+    #[must_use]
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Set whether the memory each rule and fix uses is recorded
+    #[must_use]
+    pub fn with_profile_memory(mut self, profile: bool) -> Self {
+        self.profile_memory = profile;
+        self
+    }
+
+    /// Set the change tags applied to every edit
+    #[must_use]
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set what saving a page does to the bot account's watchlist
+    #[must_use]
+    pub fn with_watchlist(mut self, watchlist: Watchlist) -> Self {
+        self.watchlist = watchlist;
+        self
+    }
+
+    /// Fill the `{rules}` placeholder in the tags with the first 12 hex
+    /// digits of the rule set's hash, e.g. `awb-rs-rules-{rules}` becomes
+    /// `awb-rs-rules-3f2a9c0d41b7`
+    #[must_use]
+    pub fn with_rules_hash(mut self, rules_sha256: &str) -> Self {
+        let short = &rules_sha256[..rules_sha256.len().min(12)];
+        for tag in &mut self.tags {
+            *tag = tag.replace(RULES_TAG_PLACEHOLDER, short);
+        }
+        self
+    }
+
+    /// Set the approval conditions enforced by the runner
+    #[must_use]
+    pub fn with_compliance(mut self, compliance: ComplianceConfig) -> Self {
+        self.compliance = compliance;
+        self
+    }
+
     /// Check if a namespace is allowed under the current policy.
     /// Empty allowed set means all namespaces are permitted.
     pub fn is_namespace_allowed(&self, ns: awb_domain::types::Namespace) -> bool {
@@ -203,6 +476,9 @@ mod tests {
         assert!(!config.skip_on_warning);
         assert_eq!(config.log_every_n, 10);
         assert!(!config.dry_run);
+        assert!(!config.verify_idempotent);
+        assert_eq!(config.jobs, 1);
+        assert!(!config.profile_memory);
     }
 
     #[test]
@@ -210,11 +486,72 @@ mod tests {
         let config = BotConfig::new()
             .with_max_edits(100)
             .with_skip_no_change(false)
-            .with_dry_run(true);
+            .with_dry_run(true)
+            .with_jobs(0);
 
         assert_eq!(config.max_edits, Some(100));
         assert!(!config.skip_no_change);
         assert!(config.dry_run);
+        assert_eq!(config.jobs, 1, "at least one page at a time");
+    }
+
+    #[test]
+    fn test_bot_config_tags() {
+        let config = BotConfig::from_toml(r#"tags = ["awb-rs", "awb-rs-rules-{rules}"]"#)
+            .unwrap()
+            .with_rules_hash("3f2a9c0d41b7e5a8c9d0");
+        assert_eq!(config.tags, vec!["awb-rs", "awb-rs-rules-3f2a9c0d41b7"]);
+
+        let config = BotConfig::new().with_tags(["awb-rs"]);
+        assert_eq!(config.tags, vec!["awb-rs"]);
+        assert!(BotConfig::default().tags.is_empty());
+    }
+
+    #[test]
+    fn test_bot_config_watchlist() {
+        assert_eq!(BotConfig::default().watchlist, Watchlist::Preferences);
+        let config = BotConfig::from_toml(r#"watchlist = "nochange""#).unwrap();
+        assert_eq!(config.watchlist, Watchlist::NoChange);
+        assert!(BotConfig::from_toml(r#"watchlist = "sometimes""#).is_err());
+        assert_eq!(
+            BotConfig::new()
+                .with_watchlist(Watchlist::Unwatch)
+                .watchlist,
+            Watchlist::Unwatch
+        );
+    }
+
+    #[test]
+    fn test_bot_config_content_guard() {
+        let config = BotConfig::default();
+        assert_eq!(config.content_guard, ContentLossGuard::default());
+        assert!(!config.allow_content_loss);
+
+        let config = BotConfig::from_toml(
+            "[content_guard]\nmax_shrink_percent = 30\nmax_removed_bytes = \"10KB\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.content_guard.max_shrink_percent, Some(30));
+        assert_eq!(config.content_guard.max_removed_bytes, Some(10_000));
+        assert!(config.content_guard.keep_categories);
+        assert!(config.content_guard.keep_lead);
+    }
+
+    #[test]
+    fn test_bot_config_read_only_wait() {
+        let config = BotConfig::default();
+        assert_eq!(config.read_only_wait, Duration::from_secs(60));
+        assert_eq!(config.read_only_max_wait, Duration::from_secs(1800));
+
+        let config =
+            BotConfig::from_toml("read_only_wait = \"30s\"\nread_only_max_wait = \"10m\"").unwrap();
+        assert_eq!(config.read_only_wait, Duration::from_secs(30));
+        assert_eq!(config.read_only_max_wait, Duration::from_secs(600));
+
+        // The longest pause is never shorter than the first
+        let config =
+            BotConfig::new().with_read_only_wait(Duration::from_secs(90), Duration::from_secs(5));
+        assert_eq!(config.read_only_max_wait, Duration::from_secs(90));
     }
 
     #[test]
@@ -226,4 +563,49 @@ mod tests {
         assert_eq!(config.max_edits, deserialized.max_edits);
         assert_eq!(config.skip_no_change, deserialized.skip_no_change);
     }
+
+    #[test]
+    fn test_bot_config_from_toml_with_units() {
+        let config = BotConfig::from_toml(
+            r#"
+edit_delay = "12s"
+max_runtime = "2h30m"
+max_page_size = "2MB"
+dry_run = true
+talk_page_mode = "preserve_signatures"
+large_pages = "sections"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.edit_delay, Duration::from_secs(12));
+        assert_eq!(config.max_runtime, Some(Duration::from_secs(9000)));
+        assert_eq!(config.max_page_size, Some(2_000_000));
+        assert!(config.dry_run);
+        assert_eq!(config.talk_page_mode, TalkPageMode::PreserveSignatures);
+        assert_eq!(config.large_pages, LargePageMode::Sections);
+        assert_eq!(config.save_every_n, 25, "missing keys take defaults");
+    }
+
+    #[test]
+    fn test_bot_config_toml_error_points_at_key() {
+        let err = BotConfig::from_toml("dry_run = true\nmax_runtime = \"2 hours\"\n").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("line 2"), "{}", message);
+        assert!(message.contains("max_runtime"), "{}", message);
+        assert!(
+            message.contains("invalid duration '2 hours'"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_bot_config_reads_legacy_durations() {
+        let config: BotConfig =
+            serde_json::from_str(r#"{"edit_delay": {"secs": 7, "nanos": 0}, "max_runtime": 60}"#)
+                .unwrap();
+        assert_eq!(config.edit_delay, Duration::from_secs(7));
+        assert_eq!(config.max_runtime, Some(Duration::from_secs(60)));
+    }
 }
@@ -0,0 +1,143 @@
+//! Watching and steering a bot run from another task, e.g. a dashboard
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+/// Where a bot run has got to, republished as each page starts and ends
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunProgress {
+    /// Pages in the list
+    pub total: usize,
+    /// Pages finished, including those completed by an earlier run
+    pub done: usize,
+    pub edited: usize,
+    pub skipped: usize,
+    pub errored: usize,
+    /// Page being processed, if any
+    pub current: Option<String>,
+    /// The latest wait imposed on the run
+    pub throttle: Option<Throttle>,
+    /// Held before the next page by [`RunControl::pause`]
+    pub paused: bool,
+}
+
+/// A wait between edits, or while the wiki is read-only
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Throttle {
+    /// `edit_delay`, or the reason the wiki gave for being read-only
+    pub reason: String,
+    pub until: DateTime<Utc>,
+}
+
+impl Throttle {
+    pub fn new(reason: impl Into<String>, wait: Duration) -> Self {
+        let wait = chrono::Duration::from_std(wait).unwrap_or(chrono::Duration::MAX);
+        Self {
+            reason: reason.into(),
+            until: Utc::now()
+                .checked_add_signed(wait)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    /// Time left at `now`, or `None` once the wait is over
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<Duration> {
+        (self.until - now)
+            .to_std()
+            .ok()
+            .filter(|left| !left.is_zero())
+    }
+}
+
+/// Handle for pausing, resuming and stopping a run, from
+/// [`BotRunner::control`](crate::BotRunner::control)
+#[derive(Debug, Clone)]
+pub struct RunControl {
+    paused: watch::Sender<bool>,
+    cancel: CancellationToken,
+}
+
+impl RunControl {
+    pub(crate) fn new(cancel: CancellationToken) -> Self {
+        Self {
+            paused: watch::Sender::new(false),
+            cancel,
+        }
+    }
+
+    /// Hold the run before its next page. The page in flight is finished.
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Emergency stop, as on Ctrl-C: the page in flight is abandoned and
+    /// the checkpoint saved without it
+    pub fn stop(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Wait until resumed. Returns false if the run is stopped first.
+    pub(crate) async fn wait_while_paused(&self) -> bool {
+        let mut paused = self.paused.subscribe();
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => false,
+            resumed = paused.wait_for(|paused| !paused) => resumed.is_ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_remaining() {
+        let throttle = Throttle::new("edit_delay", Duration::from_secs(10));
+        let left = throttle.remaining(Utc::now()).unwrap();
+        assert!(left > Duration::from_secs(9) && left <= Duration::from_secs(10));
+        assert_eq!(
+            throttle.remaining(throttle.until + chrono::Duration::seconds(1)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_releases_paused_run() {
+        let control = RunControl::new(CancellationToken::new());
+        control.pause();
+        assert!(control.is_paused());
+
+        let waiting = tokio::spawn({
+            let control = control.clone();
+            async move { control.wait_while_paused().await }
+        });
+        tokio::task::yield_now().await;
+        control.resume();
+        assert!(waiting.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stop_ends_pause() {
+        let control = RunControl::new(CancellationToken::new());
+        control.pause();
+        control.stop();
+        assert!(control.is_stopped());
+        assert!(!control.wait_while_paused().await);
+    }
+}
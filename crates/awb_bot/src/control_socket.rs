@@ -0,0 +1,367 @@
+//! Local control channel for a running bot, on a Unix socket or, on
+//! Windows, a named pipe such as `\\.\pipe\awb-bot`.
+//!
+//! Each line sent is one command, `status`, `pause`, `resume` or `stop`,
+//! answered with one line of JSON: a [`ControlReply`].
+
+use crate::control::{RunControl, RunProgress};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// A command for a running bot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    Status,
+    Pause,
+    Resume,
+    /// Emergency stop, as on Ctrl-C
+    Stop,
+}
+
+impl ControlCommand {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Pause => "pause",
+            Self::Resume => "resume",
+            Self::Stop => "stop",
+        }
+    }
+}
+
+impl FromStr for ControlCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "status" => Ok(Self::Status),
+            "pause" => Ok(Self::Pause),
+            "resume" => Ok(Self::Resume),
+            "stop" => Ok(Self::Stop),
+            other => Err(format!("unknown command '{}'", other)),
+        }
+    }
+}
+
+/// Whether the run is going, as seen from outside
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunState {
+    Running,
+    /// Pause requested; the page in flight is being finished
+    Pausing,
+    Paused,
+    Stopping,
+}
+
+impl std::fmt::Display for RunState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Running => "running",
+            Self::Pausing => "pausing",
+            Self::Paused => "paused",
+            Self::Stopping => "stopping",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlStatus {
+    pub state: RunState,
+    pub progress: RunProgress,
+}
+
+/// Answer to a command: the status after carrying it out, or why it failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlReply {
+    Status(ControlStatus),
+    Error(String),
+}
+
+/// Carry out one command line against the run
+pub fn handle(line: &str, control: &RunControl, progress: &RunProgress) -> ControlReply {
+    let command = match line.parse::<ControlCommand>() {
+        Ok(command) => command,
+        Err(e) => return ControlReply::Error(e),
+    };
+    match command {
+        ControlCommand::Status => {}
+        ControlCommand::Pause => control.pause(),
+        ControlCommand::Resume => control.resume(),
+        ControlCommand::Stop => control.stop(),
+    }
+    if command != ControlCommand::Status {
+        tracing::info!("Control socket: {}", command.as_str());
+    }
+
+    let state = if control.is_stopped() {
+        RunState::Stopping
+    } else if progress.paused {
+        RunState::Paused
+    } else if control.is_paused() {
+        RunState::Pausing
+    } else {
+        RunState::Running
+    };
+    ControlReply::Status(ControlStatus {
+        state,
+        progress: progress.clone(),
+    })
+}
+
+/// The listening socket. Dropping it stops accepting commands and, for a
+/// Unix socket, removes the socket file.
+pub struct ControlSocket {
+    path: PathBuf,
+    task: JoinHandle<()>,
+}
+
+impl ControlSocket {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        self.task.abort();
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Start accepting commands on `path`. Fails if another process is already
+/// listening there; a socket file left by a crashed run is replaced.
+#[cfg(unix)]
+pub fn listen(
+    path: &Path,
+    control: RunControl,
+    progress: watch::Receiver<RunProgress>,
+) -> io::Result<ControlSocket> {
+    use tokio::net::UnixListener;
+
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} is in use by another run", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    let task = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve_connection(stream, control.clone(), progress.clone()));
+                }
+                Err(e) => tracing::warn!("Control socket accept failed: {}", e),
+            }
+        }
+    });
+    Ok(ControlSocket {
+        path: path.to_path_buf(),
+        task,
+    })
+}
+
+/// Start accepting commands on the named pipe `path`. Fails if another
+/// process already has a pipe of that name.
+#[cfg(windows)]
+pub fn listen(
+    path: &Path,
+    control: RunControl,
+    progress: watch::Receiver<RunProgress>,
+) -> io::Result<ControlSocket> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(path)?;
+    let pipe = path.to_path_buf();
+    let task = tokio::spawn(async move {
+        loop {
+            if let Err(e) = server.connect().await {
+                tracing::warn!("Control pipe connect failed: {}", e);
+                continue;
+            }
+            // Open the next instance before handing this one over, so a
+            // client never finds the pipe missing
+            let next = match ServerOptions::new().create(&pipe) {
+                Ok(next) => next,
+                Err(e) => {
+                    tracing::warn!("Control pipe closed: {}", e);
+                    return;
+                }
+            };
+            let connected = std::mem::replace(&mut server, next);
+            tokio::spawn(serve_connection(
+                connected,
+                control.clone(),
+                progress.clone(),
+            ));
+        }
+    });
+    Ok(ControlSocket {
+        path: path.to_path_buf(),
+        task,
+    })
+}
+
+/// Answer command lines until the client hangs up
+async fn serve_connection<S>(stream: S, control: RunControl, progress: watch::Receiver<RunProgress>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = handle(&line, &control, &progress.borrow());
+        let Ok(mut json) = serde_json::to_string(&reply) else {
+            return;
+        };
+        json.push('\n');
+        if writer.write_all(json.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Send one command to the bot listening on `path` and wait for its reply
+pub async fn send(path: &Path, command: ControlCommand) -> io::Result<ControlReply> {
+    #[cfg(unix)]
+    let stream = tokio::net::UnixStream::connect(path).await?;
+    #[cfg(windows)]
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(path)?;
+
+    let (reader, mut writer) = tokio::io::split(stream);
+    writer
+        .write_all(format!("{}\n", command.as_str()).as_bytes())
+        .await?;
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::sync::CancellationToken;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(" Pause\n".parse(), Ok(ControlCommand::Pause));
+        assert_eq!("stop".parse(), Ok(ControlCommand::Stop));
+        assert_eq!(
+            "halt".parse::<ControlCommand>(),
+            Err("unknown command 'halt'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_handle_reports_state() {
+        let control = RunControl::new(CancellationToken::new());
+        let mut progress = RunProgress {
+            total: 10,
+            done: 4,
+            ..RunProgress::default()
+        };
+        let state = |reply| match reply {
+            ControlReply::Status(status) => status.state,
+            ControlReply::Error(e) => panic!("{}", e),
+        };
+
+        assert_eq!(
+            state(handle("status", &control, &progress)),
+            RunState::Running
+        );
+        assert_eq!(
+            state(handle("pause", &control, &progress)),
+            RunState::Pausing
+        );
+        progress.paused = true;
+        assert_eq!(
+            state(handle("status", &control, &progress)),
+            RunState::Paused
+        );
+        assert!(matches!(
+            handle("halt", &control, &progress),
+            ControlReply::Error(_)
+        ));
+        assert_eq!(
+            state(handle("stop", &control, &progress)),
+            RunState::Stopping
+        );
+        assert!(control.is_stopped());
+    }
+
+    #[test]
+    fn test_reply_json() {
+        let reply = ControlReply::Error("unknown command 'x'".to_string());
+        assert_eq!(
+            serde_json::to_string(&reply).unwrap(),
+            r#"{"error":"unknown command 'x'"}"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_socket_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.sock");
+        let control = RunControl::new(CancellationToken::new());
+        let (progress, receiver) = watch::channel(RunProgress {
+            total: 3,
+            ..RunProgress::default()
+        });
+        let socket = listen(&path, control.clone(), receiver.clone()).unwrap();
+        // A second run cannot take over the socket
+        assert_eq!(
+            listen(&path, control.clone(), receiver)
+                .err()
+                .unwrap()
+                .kind(),
+            io::ErrorKind::AddrInUse
+        );
+
+        let ControlReply::Status(status) = send(&path, ControlCommand::Pause).await.unwrap() else {
+            panic!("expected a status");
+        };
+        assert_eq!(status.state, RunState::Pausing);
+        assert_eq!(status.progress.total, 3);
+        assert!(control.is_paused());
+
+        progress.send_modify(|progress| progress.paused = true);
+        send(&path, ControlCommand::Resume).await.unwrap();
+        assert!(!control.is_paused());
+
+        drop(socket);
+        assert!(!path.exists());
+        assert!(send(&path, ControlCommand::Status).await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stale_socket_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.sock");
+        // Bound and dropped without being removed, as after a crash
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let (_progress, receiver) = watch::channel(RunProgress::default());
+        let control = RunControl::new(CancellationToken::new());
+        let _socket = listen(&path, control, receiver).unwrap();
+        assert!(send(&path, ControlCommand::Status).await.is_ok());
+    }
+}
@@ -0,0 +1,330 @@
+//! Cron expressions for scheduled runs.
+//!
+//! Five fields, `minute hour day-of-month month day-of-week`, evaluated in
+//! UTC. Each field takes `*`, values, ranges (`1-5`), lists (`1,15`) and
+//! steps (`*/15`, `0-30/10`); months and weekdays also take English
+//! abbreviations (`jan`, `mon`), and Sunday is `0` or `7`. As in cron, a
+//! day matches if either day field matches when both are restricted. The
+//! aliases `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are
+//! accepted too.
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CronError {
+    #[error("expected 5 fields (minute hour day month weekday), found {0}")]
+    FieldCount(usize),
+    #[error("invalid {field} '{value}'")]
+    Invalid { field: &'static str, value: String },
+    #[error("{field} {value} is outside {min}-{max}")]
+    OutOfRange {
+        field: &'static str,
+        value: u32,
+        min: u32,
+        max: u32,
+    },
+}
+
+/// Years searched for the next match before giving up, e.g. on `0 0 30 2 *`
+const SEARCH_YEARS: i32 = 5;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronExpr {
+    source: String,
+    /// Bit `n` set when value `n` matches
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Whether the day-of-month and day-of-week fields were other than `*`
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronExpr {
+    /// The first matching minute strictly after `after`, or `None` if there
+    /// is none in the next few years
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start.year() + SEARCH_YEARS;
+        let mut t = start;
+        while t.year() <= limit {
+            if !bit(self.months as u64, t.month()) {
+                t = first_of_next_month(t)?;
+            } else if !self.day_matches(t) {
+                t = midnight(t)? + Duration::days(1);
+            } else if !bit(self.hours as u64, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, t: DateTime<Utc>) -> bool {
+        let day = bit(self.days as u64, t.day());
+        let weekday = bit(self.weekdays as u64, t.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, _) => weekday,
+        }
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn midnight(t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    t.with_hour(0)?.with_minute(0)
+}
+
+fn first_of_next_month(t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (year, month) = if t.month() == 12 {
+        (t.year() + 1, 1)
+    } else {
+        (t.year(), t.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+}
+
+impl FromStr for CronExpr {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expanded = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(CronError::FieldCount(fields.len()));
+        };
+
+        // Sunday may be written as 7
+        let mut weekdays = parse_field(weekday, "weekday", 0, 7, &WEEKDAYS)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            source: s.trim().to_string(),
+            minutes: parse_field(minute, "minute", 0, 59, &[])?,
+            hours: parse_field(hour, "hour", 0, 23, &[])? as u32,
+            days: parse_field(day, "day", 1, 31, &[])? as u32,
+            months: parse_field(month, "month", 1, 12, &MONTHS)? as u16,
+            weekdays: weekdays as u8,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+}
+
+/// Parse one field into a bit set over `min..=max`. `names` spell the
+/// values from `min` upwards.
+fn parse_field(
+    field: &str,
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> Result<u64, CronError> {
+    let invalid = || CronError::Invalid {
+        field: name,
+        value: field.to_string(),
+    };
+    let value = |text: &str| -> Result<u32, CronError> {
+        let lower = text.to_ascii_lowercase();
+        let value = match names.iter().position(|n| *n == lower) {
+            Some(index) => index as u32 + min,
+            None => text.parse().map_err(|_| invalid())?,
+        };
+        if value < min || value > max {
+            return Err(CronError::OutOfRange {
+                field: name,
+                value,
+                min,
+                max,
+            });
+        }
+        Ok(value)
+    };
+
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| invalid())?;
+                if step == 0 {
+                    return Err(invalid());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` runs from 5 to the end of the range
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for v in (start..=end).step_by(step as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+impl fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for CronExpr {
+    type Error = CronError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<CronExpr> for String {
+    fn from(expr: CronExpr) -> Self {
+        expr.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(expr: &str, after: &str) -> String {
+        let expr: CronExpr = expr.parse().unwrap();
+        expr.next_after(at(after)).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn test_next_minute_hour_and_day() {
+        assert_eq!(
+            next("*/15 * * * *", "2024-05-01T10:07:30Z"),
+            "2024-05-01T10:15:00+00:00"
+        );
+        // Strictly after: a run due now is not due again
+        assert_eq!(
+            next("30 2 * * *", "2024-05-01T02:30:00Z"),
+            "2024-05-02T02:30:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 1 * *", "2024-12-15T00:00:00Z"),
+            "2025-01-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 9-17/4 * * mon-fri", "2024-05-03T17:30:00Z"),
+            "2024-05-06T09:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_either_day_field_matches() {
+        // The 13th, or any Friday
+        assert_eq!(
+            next("0 0 13 * 5", "2024-05-01T00:00:00Z"),
+            "2024-05-03T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 13 * 5", "2024-05-10T00:00:00Z"),
+            "2024-05-13T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_names_aliases_and_sunday_as_seven() {
+        assert_eq!(
+            next("0 12 * feb sun", "2024-01-01T00:00:00Z"),
+            "2024-02-04T12:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 12 * * 7", "2024-05-01T00:00:00Z"),
+            "2024-05-05T12:00:00+00:00"
+        );
+        assert_eq!(
+            next("@weekly", "2024-05-01T00:00:00Z"),
+            "2024-05-05T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "2025-01-01T00:00:00Z"),
+            "2028-02-29T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_impossible_date_has_no_next_run() {
+        let expr: CronExpr = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(expr.next_after(at("2024-01-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert_eq!("* * * *".parse::<CronExpr>(), Err(CronError::FieldCount(4)));
+        assert_eq!(
+            "60 * * * *".parse::<CronExpr>(),
+            Err(CronError::OutOfRange {
+                field: "minute",
+                value: 60,
+                min: 0,
+                max: 59
+            })
+        );
+        assert!("*/0 * * * *".parse::<CronExpr>().is_err());
+        assert!("5-1 * * * *".parse::<CronExpr>().is_err());
+        assert!("0 0 * smarch *".parse::<CronExpr>().is_err());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        #[derive(Serialize, Deserialize)]
+        struct Task {
+            cron: CronExpr,
+        }
+        let task: Task = toml::from_str("cron = \"0 3 * * *\"").unwrap();
+        assert_eq!(task.cron.to_string(), "0 3 * * *");
+        assert_eq!(
+            toml::to_string(&task).unwrap().trim(),
+            "cron = \"0 3 * * *\""
+        );
+        assert!(toml::from_str::<Task>("cron = \"whenever\"").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use awb_domain::page_list::SampleManifest;
+use awb_domain::session::EditPlan;
+use awb_engine::transform::is_null_edit;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Name of the index file written next to the diffs
+pub const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Error)]
+pub enum DiffArtifactError {
+    #[error("Failed to write diff artifact: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to serialize diff index: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// One page's proposed change, as listed in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffArtifact {
+    pub title: String,
+    /// Diff file name, relative to the index
+    pub file: String,
+    pub summary: String,
+    pub rules_applied: usize,
+    pub fixes_applied: Vec<String>,
+    pub warnings: Vec<String>,
+    pub is_cosmetic_only: bool,
+}
+
+/// Contents of `index.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffIndex {
+    pub generated_at: DateTime<Utc>,
+    pub pages: Vec<DiffArtifact>,
+    /// Set when the run covered a sample of the page list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleManifest>,
+}
+
+/// Writes one unified diff file per page plus an index, so dry-run results
+/// can be reviewed offline or attached to a bot approval request.
+///
+/// The index is rewritten after every page, so an interrupted run still
+/// leaves a consistent directory.
+pub struct DiffArtifacts {
+    dir: PathBuf,
+    pages: Mutex<Vec<DiffArtifact>>,
+    sample: Option<SampleManifest>,
+}
+
+impl DiffArtifacts {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            pages: Mutex::new(Vec::new()),
+            sample: None,
+        }
+    }
+
+    /// Record in the index which sample of the page list was run
+    #[must_use]
+    pub fn with_sample(mut self, sample: SampleManifest) -> Self {
+        self.sample = Some(sample);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of diffs written so far
+    pub fn len(&self) -> usize {
+        self.pages
+            .lock()
+            .expect("diff artifacts lock poisoned")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the diff for `plan`, returning its path. Plans that change
+    /// nothing are not recorded.
+    pub fn record(
+        &self,
+        title: &str,
+        plan: &EditPlan,
+    ) -> Result<Option<PathBuf>, DiffArtifactError> {
+        if is_null_edit(&plan.page.wikitext, &plan.new_wikitext) {
+            return Ok(None);
+        }
+        std::fs::create_dir_all(&self.dir)?;
+
+        let mut pages = self.pages.lock().expect("diff artifacts lock poisoned");
+        let file = format!("{:04}-{}.diff", pages.len() + 1, file_stem(title));
+        let path = self.dir.join(&file);
+        let diff = format!(
+            "--- a/{title}\n+++ b/{title}\n{}",
+            awb_engine::diff_engine::to_unified(&plan.diff_ops, 3)
+        );
+        std::fs::write(&path, diff)?;
+
+        pages.push(DiffArtifact {
+            title: title.to_string(),
+            file,
+            summary: plan.summary.clone(),
+            rules_applied: plan.rules_applied.len(),
+            fixes_applied: plan.fixes_applied.clone(),
+            warnings: plan.warnings.iter().map(|w| format!("{:?}", w)).collect(),
+            is_cosmetic_only: plan.is_cosmetic_only,
+        });
+        let index = DiffIndex {
+            generated_at: Utc::now(),
+            pages: pages.clone(),
+            sample: self.sample.clone(),
+        };
+        std::fs::write(
+            self.dir.join(INDEX_FILE),
+            serde_json::to_string_pretty(&index)?,
+        )?;
+        Ok(Some(path))
+    }
+}
+
+/// A file-name-safe form of `title`
+fn file_stem(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(80)
+        .collect();
+    if stem.is_empty() {
+        "page".to_string()
+    } else {
+        stem
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::types::*;
+    use tempfile::TempDir;
+
+    fn plan(old: &str, new: &str) -> EditPlan {
+        EditPlan {
+            page: PageContent {
+                page_id: PageId(1),
+                title: Title::new(Namespace::MAIN, "Test"),
+                revision: RevisionId(1),
+                timestamp: Utc::now(),
+                wikitext: old.to_string(),
+                size_bytes: old.len() as u64,
+                is_redirect: false,
+                protection: ProtectionInfo::default(),
+                properties: PageProperties::default(),
+            },
+            new_wikitext: new.to_string(),
+            rules_applied: vec![],
+            fixes_applied: vec!["trailing_whitespace".to_string()],
+            diff_ops: awb_engine::diff_engine::compute_diff(old, new),
+            summary: "cleanup".to_string(),
+            warnings: vec![],
+            is_cosmetic_only: true,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem("Talk:Foo/Bar baz"), "Talk_Foo_Bar_baz");
+        assert_eq!(file_stem(""), "page");
+    }
+
+    #[test]
+    fn test_record_writes_diff_and_index() {
+        let dir = TempDir::new().unwrap();
+        let artifacts = DiffArtifacts::new(dir.path().join("out"));
+
+        let path = artifacts
+            .record("Foo/Bar", &plan("a \nb\n", "a\nb\n"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.file_name().unwrap(), "0001-Foo_Bar.diff");
+        let diff = std::fs::read_to_string(&path).unwrap();
+        assert!(diff.starts_with("--- a/Foo/Bar\n+++ b/Foo/Bar\n"));
+        assert!(diff.contains("-a \n"));
+
+        assert!(artifacts.record("Same", &plan("x", "x")).unwrap().is_none());
+        artifacts.record("Foo/Bar", &plan("c", "d")).unwrap();
+
+        let index: DiffIndex = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("out").join(INDEX_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(index.pages.len(), 2);
+        assert_eq!(index.pages[1].file, "0002-Foo_Bar.diff");
+        assert_eq!(index.pages[0].fixes_applied, vec!["trailing_whitespace"]);
+        assert_eq!(artifacts.len(), 2);
+        assert!(index.sample.is_none());
+    }
+
+    #[test]
+    fn test_index_records_sample() {
+        use awb_domain::page_list::{PageList, SampleSize};
+
+        let dir = TempDir::new().unwrap();
+        let (_, manifest) = PageList::from_lines("A\nB\nC\nD")
+            .unwrap()
+            .sample(SampleSize::Count(2), 9);
+        let artifacts = DiffArtifacts::new(dir.path()).with_sample(manifest.clone());
+        artifacts.record("A", &plan("a", "b")).unwrap();
+
+        let index: DiffIndex =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join(INDEX_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(index.sample, Some(manifest));
+    }
+}
@@ -0,0 +1,354 @@
+use crate::bot_runner::{BotError, BotRunner};
+use crate::config::BotConfig;
+use crate::report::BotReport;
+use awb_domain::page_list::PageList;
+use awb_domain::siteinfo::SiteInfo;
+use awb_engine::transform::TransformEngine;
+use awb_mw_api::client::MediaWikiClient;
+use serde::{Deserialize, Serialize};
+
+/// One bot configuration rolled out across several wikis, e.g. a template
+/// change on sister projects.
+///
+/// Each wiki has its own client, so throttling, maxlag and read-only pauses
+/// apply per wiki, and its own page list and report. Wikis run one after
+/// another; an error on one wiki is recorded and the next one starts, but an
+/// emergency stop or interrupt ends the whole run.
+pub struct WikiFarm<C: MediaWikiClient> {
+    config: BotConfig,
+    members: Vec<(String, BotRunner<C>)>,
+    secrets: Vec<String>,
+}
+
+impl<C: MediaWikiClient> WikiFarm<C> {
+    /// Every wiki gets a copy of `config`; limits such as `max_edits` and
+    /// `max_runtime` apply to each wiki separately.
+    pub fn new(config: BotConfig) -> Self {
+        Self {
+            config,
+            members: Vec::new(),
+            secrets: Vec::new(),
+        }
+    }
+
+    /// Add a wiki under `name` (e.g. its host name). Diffs and checkpoints
+    /// are kept apart by writing them under `name`. `site` is the wiki's
+    /// namespace setup, see [`BotRunner::with_site`].
+    pub fn add_wiki(
+        &mut self,
+        name: impl Into<String>,
+        client: C,
+        site: SiteInfo,
+        engine: TransformEngine,
+        pages: PageList,
+    ) -> &mut BotRunner<C> {
+        let name = name.into();
+        let mut config = self.config.clone();
+        config.diff_dir = config.diff_dir.map(|dir| dir.join(&name));
+        config.snapshot_dir = config.snapshot_dir.map(|dir| dir.join(&name));
+        config.checkpoint_path = config.checkpoint_path.map(|path| {
+            let file_name = path
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            path.with_file_name(format!("{}-{}", name, file_name))
+        });
+
+        let mut runner = BotRunner::new(config, client, engine, pages).with_site(site);
+        for secret in &self.secrets {
+            runner.add_secret(secret.clone());
+        }
+        self.members.push((name, runner));
+        &mut self.members.last_mut().expect("just pushed").1
+    }
+
+    /// Add a secret to be redacted from every wiki's error messages
+    pub fn add_secret(&mut self, secret: String) {
+        for (_, runner) in &mut self.members {
+            runner.add_secret(secret.clone());
+        }
+        self.secrets.push(secret);
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Run each wiki in the order added
+    pub async fn run(&mut self) -> FarmReport {
+        let mut wikis = Vec::with_capacity(self.members.len());
+        let mut stop: Option<String> = None;
+
+        for (name, runner) in &mut self.members {
+            if let Some(reason) = &stop {
+                tracing::info!("Not starting {}: {}", name, reason);
+                wikis.push(WikiReport {
+                    wiki: name.clone(),
+                    report: runner.report().clone(),
+                    error: Some(format!("Not started: {}", reason)),
+                });
+                continue;
+            }
+
+            tracing::info!("Starting bot run on {}", name);
+            let outcome = runner.run().await;
+            let error = match outcome {
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::error!("Bot run on {} failed: {}", name, e);
+                    if matches!(e, BotError::EmergencyStop | BotError::Interrupted) {
+                        stop = Some(e.to_string());
+                    }
+                    Some(e.to_string())
+                }
+            };
+            wikis.push(WikiReport {
+                wiki: name.clone(),
+                report: runner.report().clone(),
+                error,
+            });
+        }
+
+        FarmReport { wikis }
+    }
+}
+
+/// How a run went on one wiki of a [`WikiFarm`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiReport {
+    pub wiki: String,
+    pub report: BotReport,
+    /// Why the run on this wiki ended early, if it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Reports of a [`WikiFarm`] run, one per wiki in run order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FarmReport {
+    pub wikis: Vec<WikiReport>,
+}
+
+impl FarmReport {
+    pub fn pages_edited(&self) -> usize {
+        self.wikis.iter().map(|w| w.report.pages_edited).sum()
+    }
+
+    pub fn pages_errored(&self) -> usize {
+        self.wikis.iter().map(|w| w.report.pages_errored).sum()
+    }
+
+    /// Whether every wiki ran without an error
+    pub fn is_success(&self) -> bool {
+        self.wikis.iter().all(|w| w.error.is_none())
+    }
+
+    /// One line per wiki, then the totals
+    pub fn to_summary(&self) -> String {
+        let mut summary = String::new();
+        summary.push_str("=== Multi-Wiki Run Summary ===\n");
+        for wiki in &self.wikis {
+            let report = &wiki.report;
+            summary.push_str(&format!(
+                "{}: {} processed, {} edited, {} skipped, {} errors",
+                wiki.wiki,
+                report.pages_processed,
+                report.pages_edited,
+                report.pages_skipped,
+                report.pages_errored
+            ));
+            if let Some(error) = &wiki.error {
+                summary.push_str(&format!(" ({})", error));
+            } else if let Some(reason) = &report.stop_reason {
+                summary.push_str(&format!(" ({})", reason));
+            }
+            summary.push('\n');
+        }
+        summary.push_str(&format!(
+            "Total: {} wikis, {} edited, {} errors\n",
+            self.wikis.len(),
+            self.pages_edited(),
+            self.pages_errored()
+        ));
+        summary
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_artifacts::INDEX_FILE;
+    use async_trait::async_trait;
+    use awb_domain::rules::{Rule, RuleSet};
+    use awb_domain::types::{
+        PageContent, PageId, PageProperties, ProtectionInfo, RevisionId, Title,
+    };
+    use awb_engine::general_fixes::FixRegistry;
+    use awb_mw_api::client::{EditRequest, EditResponse};
+    use awb_mw_api::error::MwApiError;
+    use awb_mw_api::oauth::{OAuth1Config, OAuthSession};
+    use chrono::Utc;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// A wiki whose pages all read `teh cat`, apart from `missing` ones
+    #[derive(Default)]
+    struct FakeWiki {
+        missing: HashSet<String>,
+    }
+
+    #[async_trait]
+    impl MediaWikiClient for FakeWiki {
+        async fn login_bot_password(&self, _: &str, _: &str) -> Result<(), MwApiError> {
+            Ok(())
+        }
+        async fn login_oauth1(&self, _: OAuth1Config) -> Result<(), MwApiError> {
+            Ok(())
+        }
+        async fn login_oauth2(&self, _: OAuthSession) -> Result<(), MwApiError> {
+            Ok(())
+        }
+        async fn fetch_csrf_token(&self) -> Result<String, MwApiError> {
+            Ok("token".to_string())
+        }
+        async fn get_page(&self, title: &Title) -> Result<PageContent, MwApiError> {
+            if self.missing.contains(&title.display) {
+                return Err(MwApiError::ApiError {
+                    code: "missingtitle".to_string(),
+                    info: "The page you specified doesn't exist.".to_string(),
+                });
+            }
+            Ok(PageContent {
+                page_id: PageId(1),
+                title: title.clone(),
+                revision: RevisionId(100),
+                timestamp: Utc::now(),
+                wikitext: "teh cat".to_string(),
+                size_bytes: 7,
+                is_redirect: false,
+                protection: ProtectionInfo::default(),
+                properties: PageProperties::default(),
+            })
+        }
+        async fn edit_page(&self, _: &EditRequest) -> Result<EditResponse, MwApiError> {
+            Ok(EditResponse {
+                result: "Success".to_string(),
+                new_revid: Some(101),
+                new_timestamp: Some(Utc::now().to_rfc3339()),
+            })
+        }
+        async fn parse_wikitext(&self, _: &str, _: &Title) -> Result<String, MwApiError> {
+            Ok(String::new())
+        }
+        async fn list_category_members(&self, _: &str, _: u32) -> Result<Vec<String>, MwApiError> {
+            Ok(vec![])
+        }
+        async fn search_pages(&self, _: &str, _: u32) -> Result<Vec<String>, MwApiError> {
+            Ok(vec![])
+        }
+        async fn get_backlinks(&self, _: &str, _: u32) -> Result<Vec<String>, MwApiError> {
+            Ok(vec![])
+        }
+    }
+
+    fn engine() -> TransformEngine {
+        let mut rules = RuleSet::new();
+        rules.add(Rule::new_plain("teh", "the", true));
+        TransformEngine::new(&rules, FixRegistry::new(), HashSet::new()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_farm_runs_each_wiki_with_its_own_pages() {
+        let mut farm = WikiFarm::new(BotConfig::new().with_edit_delay(Duration::ZERO));
+        farm.add_wiki(
+            "en.example.org",
+            FakeWiki::default(),
+            SiteInfo::default(),
+            engine(),
+            PageList::from_lines("Alpha\nBeta").unwrap(),
+        );
+        let mut broken = FakeWiki::default();
+        broken.missing.insert("Gamma".to_string());
+        farm.add_wiki(
+            "de.example.org",
+            broken,
+            SiteInfo::default(),
+            engine(),
+            PageList::from_lines("Gamma\nDelta").unwrap(),
+        );
+        assert_eq!(farm.len(), 2);
+
+        let report = farm.run().await;
+
+        assert_eq!(report.wikis.len(), 2);
+        assert_eq!(report.wikis[0].wiki, "en.example.org");
+        assert_eq!(report.wikis[0].report.pages_edited, 2);
+        assert_eq!(report.wikis[1].report.pages_edited, 1);
+        assert_eq!(report.wikis[1].report.pages_errored, 1);
+        assert_eq!(report.pages_edited(), 3);
+        assert!(report.is_success());
+        let summary = report.to_summary();
+        assert!(summary.contains("de.example.org: 2 processed, 1 edited, 0 skipped, 1 errors"));
+        assert!(summary.contains("Total: 2 wikis, 3 edited, 1 errors"));
+    }
+
+    #[tokio::test]
+    async fn test_emergency_stop_ends_the_farm_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let stop_file = dir.path().join("STOP");
+        std::fs::write(&stop_file, "").unwrap();
+        let mut config = BotConfig::new().with_edit_delay(Duration::ZERO);
+        config.emergency_stop_file = stop_file;
+
+        let mut farm = WikiFarm::new(config);
+        for name in ["en.example.org", "fr.example.org"] {
+            farm.add_wiki(
+                name,
+                FakeWiki::default(),
+                SiteInfo::default(),
+                engine(),
+                PageList::from_lines("Alpha").unwrap(),
+            );
+        }
+        let report = farm.run().await;
+
+        assert!(!report.is_success());
+        assert_eq!(report.pages_edited(), 0);
+        assert_eq!(
+            report.wikis[1].error.as_deref(),
+            Some("Not started: Emergency stop triggered")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_diffs_are_kept_per_wiki() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = BotConfig::new()
+            .with_edit_delay(Duration::ZERO)
+            .with_dry_run(true)
+            .with_diff_dir(dir.path().to_path_buf());
+        let mut farm = WikiFarm::new(config);
+        for name in ["en.example.org", "fr.example.org"] {
+            farm.add_wiki(
+                name,
+                FakeWiki::default(),
+                SiteInfo::default(),
+                engine(),
+                PageList::from_lines("Alpha").unwrap(),
+            );
+        }
+        farm.run().await;
+
+        for name in ["en.example.org", "fr.example.org"] {
+            assert!(dir.path().join(name).join(INDEX_FILE).exists());
+        }
+    }
+}
@@ -1,9 +1,25 @@
 pub mod bot_runner;
 pub mod checkpoint;
+pub mod compliance;
 pub mod config;
+pub mod control;
+pub mod control_socket;
+pub mod cron;
+pub mod diff_artifacts;
+pub mod farm;
 pub mod report;
+pub mod snapshots;
 
 pub use bot_runner::BotRunner;
 pub use checkpoint::Checkpoint;
-pub use config::BotConfig;
-pub use report::{BotReport, PageAction, PageResult};
+pub use compliance::{ComplianceConfig, ComplianceStamp};
+pub use config::{BotConfig, LargePageMode};
+pub use control::{RunControl, RunProgress, Throttle};
+pub use control_socket::{ControlCommand, ControlReply, ControlSocket, ControlStatus, RunState};
+pub use cron::{CronError, CronExpr};
+pub use diff_artifacts::DiffArtifacts;
+pub use farm::{FarmReport, WikiFarm, WikiReport};
+pub use report::{
+    BotReport, ChangeStats, MemoryStats, PageAction, PageChanges, PageResult, PageSizeStats,
+};
+pub use snapshots::{SnapshotEntry, SnapshotIndex, Snapshots};
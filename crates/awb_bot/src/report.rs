@@ -1,5 +1,14 @@
+use crate::compliance::ComplianceStamp;
+use awb_domain::diff::DiffOp;
+use awb_domain::page_list::SampleManifest;
+use awb_domain::session::EditPlan;
+use awb_engine::fix_config::FixClassification;
+use awb_engine::memory::MemoryProfile;
+use awb_engine::prefilter::PrefilterStats;
+use awb_engine::transform::TransformEngine;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Action taken on a page
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -32,6 +41,65 @@ pub struct PageResult {
 
     /// Processing timestamp
     pub timestamp: DateTime<Utc>,
+
+    /// What the edit changed (for edited pages, and pages a dry run would
+    /// have edited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<PageChanges>,
+
+    /// Metadata plugins set on the page, see [`EditPlan::metadata`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// The nature of one page's edit
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageChanges {
+    pub bytes_added: u64,
+    pub bytes_removed: u64,
+    /// Fixes that changed the page, by classification
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fixes: BTreeMap<FixClassification, usize>,
+    /// Labels of the rules that changed the page
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<String>,
+}
+
+impl PageChanges {
+    /// Describe `plan`, naming its rules and classifying its fixes with
+    /// `engine`. Fixes the engine does not know count as maintenance.
+    pub fn of(plan: &EditPlan, engine: &TransformEngine) -> Self {
+        let mut changes = Self::default();
+        for op in &plan.diff_ops {
+            let (added, removed) = match op {
+                DiffOp::Equal { .. } => (0, 0),
+                DiffOp::Insert { text, .. } => (text.len(), 0),
+                DiffOp::Delete { text, .. } => (0, text.len()),
+                DiffOp::Replace {
+                    old_text, new_text, ..
+                } => (new_text.len(), old_text.len()),
+            };
+            changes.bytes_added += added as u64;
+            changes.bytes_removed += removed as u64;
+        }
+        for fix in &plan.fixes_applied {
+            let class = engine
+                .fix_classification(fix)
+                .unwrap_or(FixClassification::Maintenance);
+            *changes.fixes.entry(class).or_default() += 1;
+        }
+        changes.rules = plan
+            .rules_applied
+            .iter()
+            .map(|id| engine.rule_label(*id).unwrap_or_else(|| id.to_string()))
+            .collect();
+        changes
+    }
+
+    /// Bytes added less bytes removed
+    pub fn bytes_delta(&self) -> i64 {
+        self.bytes_added as i64 - self.bytes_removed as i64
+    }
 }
 
 /// Complete bot run report
@@ -66,6 +134,218 @@ pub struct BotReport {
 
     /// Reason for stopping
     pub stop_reason: Option<String>,
+
+    /// Approval conditions enforced during the run, if compliance mode was on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ComplianceStamp>,
+
+    /// How the page list was sampled, for pilot runs over part of a list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleManifest>,
+
+    /// Change tags every edit of the run was saved with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// What exactly the run's fixes ran, e.g. plugin source hashes and the
+    /// plugin seed, for reproducing the run in an audit
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub provenance: BTreeMap<String, String>,
+
+    /// Sizes of the pages fetched during the run
+    #[serde(default, skip_serializing_if = "PageSizeStats::is_empty")]
+    pub page_sizes: PageSizeStats,
+
+    /// Plain rules the engine's pre-filter skipped during the run
+    #[serde(default, skip_serializing_if = "PrefilterStats::is_empty")]
+    pub prefilter: PrefilterStats,
+
+    /// Memory used by the rules and fixes, if the run profiled it
+    #[serde(default, skip_serializing_if = "MemoryStats::is_empty")]
+    pub memory: MemoryStats,
+
+    /// What the edits of the run changed, summed over pages
+    #[serde(default, skip_serializing_if = "ChangeStats::is_empty")]
+    pub changes: ChangeStats,
+}
+
+/// Rules listed by name in the summary's changes section
+const TOP_RULES_SHOWN: usize = 5;
+
+/// The nature of a run's edits, from each page's [`PageChanges`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeStats {
+    /// Pages with changes
+    pub pages: usize,
+    pub bytes_added: u64,
+    pub bytes_removed: u64,
+    /// Fixes that changed a page, by classification, once per page
+    pub fixes: BTreeMap<FixClassification, usize>,
+    /// Pages each rule changed, by rule label
+    pub rule_hits: BTreeMap<String, usize>,
+}
+
+impl ChangeStats {
+    pub fn record(&mut self, changes: &PageChanges) {
+        self.pages += 1;
+        self.bytes_added += changes.bytes_added;
+        self.bytes_removed += changes.bytes_removed;
+        for (class, count) in &changes.fixes {
+            *self.fixes.entry(*class).or_default() += count;
+        }
+        for rule in &changes.rules {
+            *self.rule_hits.entry(rule.clone()).or_default() += 1;
+        }
+    }
+
+    /// Bytes added less bytes removed
+    pub fn bytes_delta(&self) -> i64 {
+        self.bytes_added as i64 - self.bytes_removed as i64
+    }
+
+    /// The `count` rules that changed the most pages, most first
+    pub fn top_rules(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut rules: Vec<(&str, usize)> = self
+            .rule_hits
+            .iter()
+            .map(|(rule, hits)| (rule.as_str(), *hits))
+            .collect();
+        rules.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+        rules.truncate(count);
+        rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages == 0
+    }
+}
+
+fn classification_name(class: FixClassification) -> &'static str {
+    match class {
+        FixClassification::Cosmetic => "cosmetic",
+        FixClassification::Maintenance => "maintenance",
+        FixClassification::StyleSensitive => "style-sensitive",
+        FixClassification::Editorial => "editorial",
+    }
+}
+
+/// Upper bounds of the [`PageSizeStats::buckets`], in bytes; the last
+/// bucket holds everything larger
+pub const PAGE_SIZE_BUCKETS: [u64; 3] = [10_000, 100_000, 1_000_000];
+
+/// Distribution of page sizes over a run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageSizeStats {
+    pub pages: usize,
+    pub total_bytes: u64,
+    pub largest_bytes: u64,
+    /// Pages under 10 kB, under 100 kB, under 1 MB, and larger
+    pub buckets: [usize; 4],
+    /// Pages over `max_page_size` that were processed section by section
+    pub sectioned: usize,
+    /// Pages over `max_page_size` that were skipped
+    pub skipped: usize,
+}
+
+impl PageSizeStats {
+    pub fn record(&mut self, bytes: u64) {
+        self.pages += 1;
+        self.total_bytes += bytes;
+        self.largest_bytes = self.largest_bytes.max(bytes);
+        let bucket = PAGE_SIZE_BUCKETS
+            .iter()
+            .position(|bound| bytes < *bound)
+            .unwrap_or(PAGE_SIZE_BUCKETS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    pub fn mean_bytes(&self) -> u64 {
+        self.total_bytes.checked_div(self.pages as u64).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages == 0
+    }
+}
+
+/// Rules and fixes listed by name in the summary's memory section
+const MEMORY_STEPS_SHOWN: usize = 5;
+
+/// The most memory one page, or one rule or fix on some page, needed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryPeak {
+    /// The rule's label or fix's ID; empty for a whole page
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub step: String,
+    /// The page it happened on
+    pub title: String,
+    pub peak_bytes: usize,
+    /// Size of the largest text produced
+    pub text_bytes: usize,
+}
+
+impl MemoryPeak {
+    /// Whether this is more than `other`, by memory held and then by text
+    /// size, which is all there is when allocations are not counted
+    fn exceeds(&self, other: &MemoryPeak) -> bool {
+        (self.peak_bytes, self.text_bytes) > (other.peak_bytes, other.text_bytes)
+    }
+}
+
+/// Memory used over a run with `profile_memory` on
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// Pages profiled
+    pub pages: usize,
+    /// The page that needed the most memory
+    pub worst_page: Option<MemoryPeak>,
+    /// Each rule and fix at the page where it needed the most memory, most
+    /// first
+    pub steps: Vec<MemoryPeak>,
+    /// Whether allocations were counted; if not, the peaks are zero and
+    /// only text sizes are known
+    #[serde(default)]
+    pub peaks_tracked: bool,
+}
+
+impl MemoryStats {
+    /// Add the profile of the page `title`
+    pub fn record(&mut self, title: &str, profile: &MemoryProfile) {
+        self.pages += 1;
+        self.peaks_tracked = profile.tracked;
+        let page = MemoryPeak {
+            step: String::new(),
+            title: title.to_string(),
+            peak_bytes: profile.peak_bytes,
+            text_bytes: profile.largest_text_bytes,
+        };
+        if self
+            .worst_page
+            .as_ref()
+            .is_none_or(|worst| page.exceeds(worst))
+        {
+            self.worst_page = Some(page);
+        }
+        for step in &profile.steps {
+            let peak = MemoryPeak {
+                step: step.step.clone(),
+                title: title.to_string(),
+                peak_bytes: step.peak_bytes,
+                text_bytes: step.output_bytes,
+            };
+            match self.steps.iter_mut().find(|known| known.step == step.step) {
+                Some(known) if peak.exceeds(known) => *known = peak,
+                Some(_) => {}
+                None => self.steps.push(peak),
+            }
+        }
+        self.steps
+            .sort_by_key(|step| std::cmp::Reverse((step.peak_bytes, step.text_bytes)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages == 0
+    }
 }
 
 impl BotReport {
@@ -82,6 +362,14 @@ impl BotReport {
             page_results: Vec::new(),
             completed: false,
             stop_reason: None,
+            compliance: None,
+            sample: None,
+            tags: Vec::new(),
+            provenance: BTreeMap::new(),
+            page_sizes: PageSizeStats::default(),
+            prefilter: PrefilterStats::default(),
+            memory: MemoryStats::default(),
+            changes: ChangeStats::default(),
         }
     }
 
@@ -93,9 +381,37 @@ impl BotReport {
             PageAction::Skipped => self.pages_skipped += 1,
             PageAction::Errored => self.pages_errored += 1,
         }
+        if let Some(changes) = &result.changes {
+            self.changes.record(changes);
+        }
         self.page_results.push(result);
     }
 
+    /// Pages whose metadata has `key`, set to `value` if one is given
+    pub fn pages_with_metadata<'a>(
+        &'a self,
+        key: &'a str,
+        value: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a PageResult> + 'a {
+        self.page_results.iter().filter(move |result| {
+            result
+                .metadata
+                .get(key)
+                .is_some_and(|v| value.is_none_or(|value| v == value))
+        })
+    }
+
+    /// Number of pages per metadata `(key, value)`
+    pub fn metadata_counts(&self) -> BTreeMap<(&str, &str), usize> {
+        let mut counts = BTreeMap::new();
+        for result in &self.page_results {
+            for (key, value) in &result.metadata {
+                *counts.entry((key.as_str(), value.as_str())).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
     /// Finalize the report
     pub fn finalize(&mut self, completed: bool, stop_reason: Option<String>) {
         self.end_time = Utc::now();
@@ -128,6 +444,15 @@ impl BotReport {
         if let Some(reason) = &self.stop_reason {
             summary.push_str(&format!("Reason:   {}\n", reason));
         }
+        if !self.tags.is_empty() {
+            summary.push_str(&format!("Tags:     {}\n", self.tags.join(", ")));
+        }
+        if !self.provenance.is_empty() {
+            summary.push_str("\n--- Provenance ---\n");
+            for (key, value) in &self.provenance {
+                summary.push_str(&format!("{}: {}\n", key, value));
+            }
+        }
         summary.push_str("\n--- Statistics ---\n");
         summary.push_str(&format!("Processed: {}\n", self.pages_processed));
         summary.push_str(&format!("Edited:    {}\n", self.pages_edited));
@@ -144,6 +469,143 @@ impl BotReport {
             summary.push_str(&format!("Speed:     {:.2} pages/sec\n", pages_per_sec));
         }
 
+        if !self.changes.is_empty() {
+            let changes = &self.changes;
+            summary.push_str("\n--- Changes ---\n");
+            summary.push_str(&format!(
+                "Bytes:     +{} -{} (net {:+}) over {} pages\n",
+                changes.bytes_added,
+                changes.bytes_removed,
+                changes.bytes_delta(),
+                changes.pages
+            ));
+            if !changes.fixes.is_empty() {
+                let fixes: Vec<String> = changes
+                    .fixes
+                    .iter()
+                    .map(|(class, count)| format!("{} {}", count, classification_name(*class)))
+                    .collect();
+                summary.push_str(&format!("Fixes:     {}\n", fixes.join(", ")));
+            }
+            let top = changes.top_rules(TOP_RULES_SHOWN);
+            if !top.is_empty() {
+                summary.push_str("Top rules:\n");
+                for (rule, hits) in top {
+                    summary.push_str(&format!("  {}: {} pages\n", rule, hits));
+                }
+            }
+        }
+
+        let metadata = self.metadata_counts();
+        if !metadata.is_empty() {
+            summary.push_str("\n--- Page Metadata ---\n");
+            for ((key, value), pages) in metadata {
+                summary.push_str(&format!("  {} = {}: {} pages\n", key, value, pages));
+            }
+        }
+
+        if !self.page_sizes.is_empty() {
+            let sizes = &self.page_sizes;
+            summary.push_str("\n--- Page Sizes ---\n");
+            summary.push_str(&format!(
+                "Mean:      {} bytes (largest {} bytes)\n",
+                sizes.mean_bytes(),
+                sizes.largest_bytes
+            ));
+            let [small, medium, large, huge] = sizes.buckets;
+            summary.push_str(&format!(
+                "Pages:     {} under 10 kB, {} under 100 kB, {} under 1 MB, {} larger\n",
+                small, medium, large, huge
+            ));
+            if sizes.sectioned + sizes.skipped > 0 {
+                summary.push_str(&format!(
+                    "Oversized: {} by section, {} skipped\n",
+                    sizes.sectioned, sizes.skipped
+                ));
+            }
+        }
+
+        if !self.prefilter.is_empty() {
+            let prefilter = &self.prefilter;
+            summary.push_str("\n--- Rule Pre-filter ---\n");
+            summary.push_str(&format!(
+                "Skipped:   {} of {} plain rule runs ({:.1}%)\n",
+                prefilter.rules_skipped,
+                prefilter.rules_checked,
+                prefilter.skip_ratio() * 100.0
+            ));
+        }
+
+        if !self.memory.is_empty() {
+            summary.push_str("\n--- Memory ---\n");
+            summary.push_str(&format!("Profiled:  {} pages\n", self.memory.pages));
+            let tracked = self.memory.peaks_tracked;
+            if !tracked {
+                summary.push_str("Peaks:     unavailable, allocations were not counted\n");
+            }
+            if let Some(worst) = &self.memory.worst_page {
+                if tracked {
+                    summary.push_str(&format!(
+                        "Worst:     {} (peak {} bytes, largest text {} bytes)\n",
+                        worst.title, worst.peak_bytes, worst.text_bytes
+                    ));
+                } else {
+                    summary.push_str(&format!(
+                        "Worst:     {} (largest text {} bytes)\n",
+                        worst.title, worst.text_bytes
+                    ));
+                }
+            }
+            for step in self.memory.steps.iter().take(MEMORY_STEPS_SHOWN) {
+                if tracked {
+                    summary.push_str(&format!(
+                        "  {}: peak {} bytes, text {} bytes on {}\n",
+                        step.step, step.peak_bytes, step.text_bytes, step.title
+                    ));
+                } else {
+                    summary.push_str(&format!(
+                        "  {}: text {} bytes on {}\n",
+                        step.step, step.text_bytes, step.title
+                    ));
+                }
+            }
+        }
+
+        if let Some(stamp) = &self.compliance {
+            let conditions = &stamp.conditions;
+            summary.push_str("\n--- Compliance ---\n");
+            if let Some(tier) = conditions.max_tier {
+                summary.push_str(&format!("Max tier:  {}\n", tier));
+            }
+            if let Some(until) = conditions.dry_run_until() {
+                summary.push_str(&format!(
+                    "Dry-run:   until {}{}\n",
+                    until,
+                    if stamp.dry_run_enforced {
+                        " (enforced)"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+            if let Some(tag) = &conditions.summary_tag {
+                summary.push_str(&format!("Tag:       {}\n", tag));
+            }
+            if let Some(max) = conditions.max_edits_per_day {
+                summary.push_str(&format!("Today:     {}/{} edits\n", stamp.edits_today, max));
+            }
+        }
+
+        if let Some(sample) = &self.sample {
+            summary.push_str("\n--- Sample ---\n");
+            summary.push_str(&format!(
+                "Pages:     {} of {} (seed {})\n",
+                sample.titles.len(),
+                sample.population,
+                sample.seed
+            ));
+        }
+
         summary
     }
 
@@ -165,6 +627,8 @@ mod tests {
             warnings: vec![],
             error: None,
             timestamp: Utc::now(),
+            changes: None,
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -221,6 +685,75 @@ mod tests {
         assert!(summary.contains("Skipped:   1"));
     }
 
+    #[test]
+    fn test_bot_report_tags() {
+        let mut report = BotReport::new(Utc::now());
+        assert!(!report.to_json().unwrap().contains("tags"));
+
+        report.tags = vec!["awb-rs".to_string(), "awb-rs-rules-0123abcd".to_string()];
+        report.finalize(true, None);
+        assert!(
+            report
+                .to_summary()
+                .contains("Tags:     awb-rs, awb-rs-rules-0123abcd\n")
+        );
+        let back: BotReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(back.tags, report.tags);
+    }
+
+    #[test]
+    fn test_bot_report_provenance() {
+        let mut report = BotReport::new(Utc::now());
+        assert!(!report.to_json().unwrap().contains("provenance"));
+
+        report.provenance = [
+            ("plugin-seed".to_string(), "42".to_string()),
+            ("plugin:dates.lua".to_string(), "ab12".to_string()),
+        ]
+        .into();
+        report.finalize(true, None);
+        assert!(
+            report
+                .to_summary()
+                .contains("--- Provenance ---\nplugin-seed: 42\nplugin:dates.lua: ab12\n")
+        );
+        let back: BotReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(back.provenance, report.provenance);
+    }
+
+    #[test]
+    fn test_page_metadata_in_report() {
+        let mut report = BotReport::new(Utc::now());
+        for (title, review) in [
+            ("A", Some("needs human review")),
+            ("B", None),
+            ("C", Some("ok")),
+        ] {
+            let mut result = create_test_result(title, PageAction::Skipped);
+            if let Some(review) = review {
+                result
+                    .metadata
+                    .insert("review".to_string(), review.to_string());
+            }
+            report.record_page(result);
+        }
+
+        let flagged: Vec<&str> = report
+            .pages_with_metadata("review", Some("needs human review"))
+            .map(|r| r.title.as_str())
+            .collect();
+        assert_eq!(flagged, vec!["A"]);
+        assert_eq!(report.pages_with_metadata("review", None).count(), 2);
+        assert!(
+            report
+                .to_summary()
+                .contains("  review = needs human review: 1 pages\n")
+        );
+
+        let back: BotReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(back.pages_with_metadata("review", Some("ok")).count(), 1);
+    }
+
     #[test]
     fn test_bot_report_json() {
         let mut report = BotReport::new(Utc::now());
@@ -231,4 +764,162 @@ mod tests {
         assert!(json.contains("\"pages_processed\": 1") || json.contains("\"pages_processed\":1"));
         assert!(json.contains("\"pages_edited\": 1") || json.contains("\"pages_edited\":1"));
     }
+
+    #[test]
+    fn test_page_size_stats() {
+        let mut report = BotReport::new(Utc::now());
+        for bytes in [500, 9_999, 10_000, 250_000, 3_000_000] {
+            report.page_sizes.record(bytes);
+        }
+        report.page_sizes.sectioned = 1;
+
+        let sizes = &report.page_sizes;
+        assert_eq!(sizes.buckets, [2, 1, 1, 1]);
+        assert_eq!(sizes.largest_bytes, 3_000_000);
+        assert_eq!(sizes.mean_bytes(), 654_099);
+        let summary = report.to_summary();
+        assert!(summary.contains("2 under 10 kB, 1 under 100 kB, 1 under 1 MB, 1 larger"));
+        assert!(summary.contains("Mean:      654099 bytes (largest 3000000 bytes)"));
+        assert!(summary.contains("Oversized: 1 by section, 0 skipped"));
+
+        let empty = serde_json::to_value(BotReport::new(Utc::now())).unwrap();
+        assert!(empty.get("page_sizes").is_none());
+    }
+
+    #[test]
+    fn test_memory_stats_keep_the_worst_page_per_step() {
+        let step = |name: &str, peak_bytes, output_bytes| awb_engine::memory::StepMemory {
+            step: name.to_string(),
+            peak_bytes,
+            output_bytes,
+        };
+        let mut report = BotReport::new(Utc::now());
+        report.memory.record(
+            "Small",
+            &MemoryProfile {
+                peak_bytes: 5_000,
+                largest_text_bytes: 1_000,
+                steps: vec![
+                    step("teh", 2_000, 1_000),
+                    step("trailing_whitespace", 100, 990),
+                ],
+                tracked: true,
+            },
+        );
+        report.memory.record(
+            "Huge",
+            &MemoryProfile {
+                peak_bytes: 900_000,
+                largest_text_bytes: 400_000,
+                steps: vec![step("teh", 10, 300_000), step("colou?r", 800_000, 400_000)],
+                tracked: true,
+            },
+        );
+
+        let memory = &report.memory;
+        assert_eq!(memory.pages, 2);
+        assert_eq!(memory.worst_page.as_ref().unwrap().title, "Huge");
+        let steps: Vec<(&str, &str)> = memory
+            .steps
+            .iter()
+            .map(|peak| (peak.step.as_str(), peak.title.as_str()))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("colou?r", "Huge"),
+                ("teh", "Small"),
+                ("trailing_whitespace", "Small")
+            ]
+        );
+
+        let summary = report.to_summary();
+        assert!(summary.contains("Worst:     Huge (peak 900000 bytes, largest text 400000 bytes)"));
+        assert!(summary.contains("  colou?r: peak 800000 bytes, text 400000 bytes on Huge"));
+        assert!(!summary.contains("unavailable"));
+
+        // Without the tracking allocator only text sizes are reported
+        let mut untracked = BotReport::new(Utc::now());
+        untracked.memory.record(
+            "Huge",
+            &MemoryProfile {
+                largest_text_bytes: 400_000,
+                steps: vec![step("colou?r", 0, 400_000)],
+                ..Default::default()
+            },
+        );
+        let summary = untracked.to_summary();
+        assert!(summary.contains("Peaks:     unavailable"));
+        assert!(summary.contains("Worst:     Huge (largest text 400000 bytes)"));
+        assert!(summary.contains("  colou?r: text 400000 bytes on Huge"));
+        let empty = serde_json::to_value(BotReport::new(Utc::now())).unwrap();
+        assert!(empty.get("memory").is_none());
+    }
+
+    #[test]
+    fn test_prefilter_stats_in_summary() {
+        let mut report = BotReport::new(Utc::now());
+        assert!(!report.to_summary().contains("Pre-filter"));
+        report.prefilter = PrefilterStats {
+            scans: 10,
+            rules_checked: 400,
+            rules_skipped: 390,
+        };
+        assert!(
+            report
+                .to_summary()
+                .contains("Skipped:   390 of 400 plain rule runs (97.5%)")
+        );
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["prefilter"]["rules_skipped"], 390);
+    }
+
+    #[test]
+    fn test_change_stats_in_summary() {
+        let mut report = BotReport::new(Utc::now());
+        assert!(!report.to_summary().contains("Changes"));
+
+        let page = |added, removed, rules: &[&str], fixes: &[(FixClassification, usize)]| {
+            let mut result = create_test_result("Page", PageAction::Edited);
+            result.changes = Some(PageChanges {
+                bytes_added: added,
+                bytes_removed: removed,
+                fixes: fixes.iter().copied().collect(),
+                rules: rules.iter().map(|rule| rule.to_string()).collect(),
+            });
+            result
+        };
+        report.record_page(page(
+            10,
+            4,
+            &["teh -> the"],
+            &[(FixClassification::Cosmetic, 2)],
+        ));
+        report.record_page(page(
+            0,
+            30,
+            &["teh -> the", "recieve -> receive"],
+            &[(FixClassification::Maintenance, 1)],
+        ));
+        report.record_page(create_test_result("Skipped", PageAction::Skipped));
+
+        let changes = &report.changes;
+        assert_eq!(changes.pages, 2);
+        assert_eq!(changes.bytes_delta(), -24);
+        assert_eq!(
+            changes.top_rules(1),
+            vec![("teh -> the", 2)],
+            "rules are ranked by pages changed"
+        );
+        let summary = report.to_summary();
+        assert!(summary.contains("Bytes:     +10 -34 (net -24) over 2 pages"));
+        assert!(summary.contains("Fixes:     2 cosmetic, 1 maintenance"));
+        assert!(summary.contains("  teh -> the: 2 pages\n  recieve -> receive: 1 pages"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["changes"]["fixes"]["cosmetic"], 2);
+        assert!(json["page_results"][2].get("changes").is_none());
+        let back: BotReport = serde_json::from_value(json).unwrap();
+        assert_eq!(back.changes, report.changes);
+    }
 }
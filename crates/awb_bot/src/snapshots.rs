@@ -0,0 +1,230 @@
+//! Pre-edit snapshots for rolling a run back locally.
+//!
+//! With `snapshot_dir` set, the wikitext of every page is compressed into
+//! `<revid>.wikitext.zst` before it is saved over, `<revid>` being the
+//! revision edited. Once the edit is saved a line is appended to
+//! `snapshots.jsonl`, so an interrupted or resumed run still leaves a
+//! usable index. `awb-rs restore` reads it back.
+
+use awb_domain::types::PageContent;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Name of the index file written next to the snapshots
+pub const SNAPSHOT_INDEX_FILE: &str = "snapshots.jsonl";
+
+/// zstd level; snapshots are written once per edit, so favour speed
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Snapshot I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid snapshot index entry on line {line}: {source}")]
+    Index {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("Failed to serialize snapshot entry: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// One saved edit whose original text was kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub title: String,
+    /// The revision that was edited, whose text the snapshot holds
+    pub base_revid: u64,
+    /// The revision the edit created, if the wiki reported it
+    pub new_revid: Option<u64>,
+    /// Snapshot file name, relative to the index
+    pub file: String,
+    /// Uncompressed size of the original text
+    pub bytes: u64,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// Writes the snapshots of one run's directory
+pub struct Snapshots {
+    dir: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write the compressed text of `page`, returning the file name. Call
+    /// before saving the edit; a revision stored earlier is kept as is.
+    pub fn store(&self, page: &PageContent) -> Result<String, SnapshotError> {
+        std::fs::create_dir_all(&self.dir)?;
+        let file = format!("{}.wikitext.zst", page.revision.0);
+        let path = self.dir.join(&file);
+        if path.exists() {
+            return Ok(file);
+        }
+        let compressed = zstd::encode_all(page.wikitext.as_bytes(), COMPRESSION_LEVEL)?;
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, compressed)?;
+        std::fs::rename(&tmp_path, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp_path);
+        })?;
+        Ok(file)
+    }
+
+    /// Add the saved edit of `page` to the index. `file` is what
+    /// [`store`](Self::store) returned for it.
+    pub fn record(
+        &self,
+        page: &PageContent,
+        file: String,
+        new_revid: Option<u64>,
+    ) -> Result<(), SnapshotError> {
+        let entry = SnapshotEntry {
+            title: page.title.display.clone(),
+            base_revid: page.revision.0,
+            new_revid,
+            file,
+            bytes: page.wikitext.len() as u64,
+            saved_at: Utc::now(),
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut index = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(SNAPSHOT_INDEX_FILE))?;
+        // One write per line, so concurrent appends never interleave
+        index.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// The snapshots of a finished (or interrupted) run
+#[derive(Debug, Clone)]
+pub struct SnapshotIndex {
+    dir: PathBuf,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl SnapshotIndex {
+    /// Read the index in `dir`. A truncated last line, left by a crash
+    /// mid-write, is ignored.
+    pub fn load(dir: &Path) -> Result<Self, SnapshotError> {
+        let text = std::fs::read_to_string(dir.join(SNAPSHOT_INDEX_FILE))?;
+        let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+        let mut entries = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(_) if i + 1 == lines.len() && !text.ends_with('\n') => {}
+                Err(source) => {
+                    return Err(SnapshotError::Index {
+                        line: i + 1,
+                        source,
+                    });
+                }
+            }
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// The first edit of `title` in the run, whose snapshot is the text
+    /// from before the run
+    pub fn original(&self, title: &str) -> Option<&SnapshotEntry> {
+        self.entries.iter().find(|entry| entry.title == title)
+    }
+
+    /// The most recent edit of `title` in the run
+    pub fn latest(&self, title: &str) -> Option<&SnapshotEntry> {
+        self.entries.iter().rev().find(|entry| entry.title == title)
+    }
+
+    /// The original text kept for `entry`
+    pub fn read(&self, entry: &SnapshotEntry) -> Result<String, SnapshotError> {
+        let compressed = std::fs::read(self.dir.join(&entry.file))?;
+        let bytes = zstd::decode_all(compressed.as_slice())?;
+        String::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::types::*;
+    use tempfile::TempDir;
+
+    fn page(title: &str, revision: u64, text: &str) -> PageContent {
+        PageContent {
+            page_id: PageId(1),
+            title: Title::new(Namespace::MAIN, title),
+            revision: RevisionId(revision),
+            timestamp: Utc::now(),
+            wikitext: text.to_string(),
+            size_bytes: text.len() as u64,
+            is_redirect: false,
+            protection: ProtectionInfo::default(),
+            properties: PageProperties::default(),
+        }
+    }
+
+    #[test]
+    fn test_store_record_and_read_back() {
+        let dir = TempDir::new().unwrap();
+        let snapshots = Snapshots::new(dir.path().join("run"));
+
+        let first = page("Foo", 100, &"teh cat\n".repeat(1000));
+        let file = snapshots.store(&first).unwrap();
+        assert_eq!(file, "100.wikitext.zst");
+        let stored = std::fs::metadata(dir.path().join("run").join(&file)).unwrap();
+        assert!(stored.len() < first.wikitext.len() as u64 / 10);
+        snapshots.record(&first, file, Some(101)).unwrap();
+
+        let second = page("Foo", 101, "the cat");
+        let file = snapshots.store(&second).unwrap();
+        snapshots.record(&second, file, Some(102)).unwrap();
+
+        let index = SnapshotIndex::load(snapshots.dir()).unwrap();
+        assert_eq!(index.entries.len(), 2);
+        let original = index.original("Foo").unwrap();
+        assert_eq!(original.base_revid, 100);
+        assert_eq!(index.read(original).unwrap(), first.wikitext);
+        assert_eq!(index.latest("Foo").unwrap().new_revid, Some(102));
+        assert!(index.original("Bar").is_none());
+    }
+
+    #[test]
+    fn test_truncated_last_line_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        let snapshots = Snapshots::new(dir.path());
+        let foo = page("Foo", 7, "text");
+        let file = snapshots.store(&foo).unwrap();
+        snapshots.record(&foo, file, None).unwrap();
+
+        let path = dir.path().join(SNAPSHOT_INDEX_FILE);
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str("{\"title\":\"Ba");
+        std::fs::write(&path, &text).unwrap();
+        assert_eq!(SnapshotIndex::load(dir.path()).unwrap().entries.len(), 1);
+
+        // Anywhere else a bad line is an error
+        std::fs::write(&path, format!("garbage\n{}", text)).unwrap();
+        assert!(matches!(
+            SnapshotIndex::load(dir.path()),
+            Err(SnapshotError::Index { line: 1, .. })
+        ));
+    }
+}
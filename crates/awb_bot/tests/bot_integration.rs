@@ -2,6 +2,7 @@ use awb_bot::checkpoint::Checkpoint;
 use awb_bot::config::BotConfig;
 use awb_bot::report::{BotReport, PageAction, PageResult};
 use chrono::Utc;
+use std::collections::BTreeMap;
 use std::time::Duration;
 use tempfile::TempDir;
 
@@ -48,6 +49,8 @@ fn test_bot_report_multiple_page_results() {
         warnings: vec![],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     report.record_page(PageResult {
@@ -57,6 +60,8 @@ fn test_bot_report_multiple_page_results() {
         warnings: vec!["No changes needed".to_string()],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     report.record_page(PageResult {
@@ -66,6 +71,8 @@ fn test_bot_report_multiple_page_results() {
         warnings: vec![],
         error: Some("Network timeout".to_string()),
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     report.record_page(PageResult {
@@ -75,6 +82,8 @@ fn test_bot_report_multiple_page_results() {
         warnings: vec![],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     // Verify statistics
@@ -97,6 +106,8 @@ fn test_bot_report_summary_format() {
         warnings: vec![],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     report.record_page(PageResult {
@@ -106,6 +117,8 @@ fn test_bot_report_summary_format() {
         warnings: vec![],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     // Sleep briefly to ensure elapsed time is measurable
@@ -143,6 +156,8 @@ fn test_bot_report_json_export() {
         warnings: vec![],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     report.finalize(true, None);
@@ -257,6 +272,8 @@ fn test_page_result_with_warnings() {
         ],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     };
 
     assert_eq!(result.action, PageAction::Edited);
@@ -273,6 +290,8 @@ fn test_page_result_with_error() {
         warnings: vec![],
         error: Some("Edit conflict detected".to_string()),
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     };
 
     assert_eq!(result.action, PageAction::Errored);
@@ -292,6 +311,8 @@ fn test_bot_report_interrupted_run() {
         warnings: vec![],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     // Simulate interruption
@@ -358,6 +379,8 @@ fn test_bot_report_calculates_edit_rate() {
             warnings: vec![],
             error: None,
             timestamp: Utc::now(),
+            changes: None,
+            metadata: BTreeMap::new(),
         });
     }
 
@@ -368,6 +391,8 @@ fn test_bot_report_calculates_edit_rate() {
         warnings: vec![],
         error: None,
         timestamp: Utc::now(),
+        changes: None,
+        metadata: BTreeMap::new(),
     });
 
     report.finalize(true, None);
@@ -0,0 +1,26 @@
+use anyhow::{Context, Result};
+use awb_domain::profile::ThrottlePolicy;
+use awb_mw_api::client::ReqwestMwClient;
+use awb_mw_api::trace::ApiTrace;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use url::Url;
+
+static TRACE: OnceLock<Arc<ApiTrace>> = OnceLock::new();
+
+/// Record every API request this process makes to `path` (`--trace-api`)
+pub fn enable(path: PathBuf) -> Result<()> {
+    let trace = ApiTrace::create(&path)
+        .with_context(|| format!("Failed to create API trace {}", path.display()))?;
+    let _ = TRACE.set(Arc::new(trace));
+    Ok(())
+}
+
+/// API client for `wiki`, recording to the trace when one is active
+pub fn client(wiki: Url, policy: ThrottlePolicy) -> Result<ReqwestMwClient> {
+    let client = ReqwestMwClient::new(wiki, policy).context("Failed to create HTTP client")?;
+    Ok(match TRACE.get() {
+        Some(trace) => client.with_trace(trace.clone()),
+        None => client,
+    })
+}
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use awb_domain::types::*;
+use awb_engine::transform::TransformEngine;
+use awb_plugins::harness::EXPECTED_SUFFIX;
+use console::style;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Pages in the run the estimate is given for
+const ESTIMATE_PAGES: u32 = 100_000;
+
+/// Slowest pages listed by name
+const SLOWEST_SHOWN: usize = 5;
+
+/// Time the profile took on one page
+#[derive(Debug, Clone)]
+pub struct PageTiming {
+    pub name: String,
+    pub bytes: usize,
+    /// Fastest of the runs, to leave out warm-up and scheduling noise
+    pub time: Duration,
+}
+
+/// Totals and percentiles over all pages
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchSummary {
+    pub pages: usize,
+    pub bytes: usize,
+    pub total: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl BenchSummary {
+    /// Time to process `pages` pages like these, leaving out network time
+    pub fn estimate(&self, pages: u32) -> Duration {
+        if self.pages == 0 {
+            return Duration::ZERO;
+        }
+        self.total / self.pages as u32 * pages
+    }
+
+    /// Throughput in megabytes of wikitext per second
+    pub fn megabytes_per_sec(&self) -> f64 {
+        let secs = self.total.as_secs_f64();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.bytes as f64 / 1_000_000.0 / secs
+    }
+}
+
+pub async fn run(
+    profile: PathBuf,
+    input: PathBuf,
+    auth_profile: String,
+    iterations: u32,
+) -> Result<()> {
+    println!("{}", style("AWB-RS Bench").bold().cyan());
+    println!("Profile: {}", profile.display());
+    println!("Input: {}", input.display());
+    println!();
+
+    let engine = super::corpus::load_engine(&profile, &auth_profile, None)?;
+    let pages = load_pages(&input)?;
+    if pages.is_empty() {
+        anyhow::bail!("No .wiki files found in {}", input.display());
+    }
+
+    let mut timings = bench_pages(&engine, &pages, iterations.max(1));
+    let summary = summarize(&timings);
+
+    timings.sort_by_key(|t| std::cmp::Reverse(t.time));
+    println!("Slowest pages:");
+    for timing in timings.iter().take(SLOWEST_SHOWN) {
+        println!(
+            "  {:>10} {} ({} KB)",
+            format!("{:.2?}", timing.time),
+            timing.name,
+            timing.bytes / 1000
+        );
+    }
+    println!();
+    println!(
+        "{} pages, {:.1} MB in {:.2?} ({:.1} MB/s)",
+        summary.pages,
+        summary.bytes as f64 / 1_000_000.0,
+        summary.total,
+        summary.megabytes_per_sec()
+    );
+    println!(
+        "Per page: median {:.2?}, p95 {:.2?}, max {:.2?}",
+        summary.median, summary.p95, summary.max
+    );
+    let prefilter = engine.prefilter_stats();
+    if !prefilter.is_empty() {
+        println!(
+            "Plain rule pre-filter skipped {} of {} rule runs ({:.1}%)",
+            prefilter.rules_skipped,
+            prefilter.rules_checked,
+            prefilter.skip_ratio() * 100.0
+        );
+    }
+    println!(
+        "Estimated engine time for {} pages like these: {}",
+        ESTIMATE_PAGES,
+        style(format_duration(summary.estimate(ESTIMATE_PAGES))).bold()
+    );
+    Ok(())
+}
+
+/// The `.wiki` files in `dir`, by file name, leaving out corpus expected
+/// output so a corpus directory can be benchmarked as it is
+pub fn load_pages(dir: &Path) -> Result<Vec<(String, String)>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            name.ends_with(".wiki") && !name.ends_with(EXPECTED_SUFFIX)
+        })
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default()
+                .to_string();
+            Ok((name, text))
+        })
+        .collect()
+}
+
+/// Plan the edit for each page `iterations` times, keeping the fastest run
+pub fn bench_pages(
+    engine: &TransformEngine,
+    pages: &[(String, String)],
+    iterations: u32,
+) -> Vec<PageTiming> {
+    pages
+        .iter()
+        .map(|(name, wikitext)| {
+            let page = PageContent {
+                page_id: PageId(0),
+                title: Title::new(Namespace::MAIN, "Benchmark"),
+                revision: RevisionId(0),
+                timestamp: chrono::Utc::now(),
+                wikitext: wikitext.clone(),
+                size_bytes: wikitext.len() as u64,
+                is_redirect: false,
+                protection: ProtectionInfo::default(),
+                properties: PageProperties::default(),
+            };
+            let time = (0..iterations)
+                .map(|_| {
+                    let start = Instant::now();
+                    std::hint::black_box(engine.apply(&page));
+                    start.elapsed()
+                })
+                .min()
+                .unwrap_or_default();
+            PageTiming {
+                name: name.clone(),
+                bytes: wikitext.len(),
+                time,
+            }
+        })
+        .collect()
+}
+
+pub fn summarize(timings: &[PageTiming]) -> BenchSummary {
+    let mut times: Vec<Duration> = timings.iter().map(|t| t.time).collect();
+    times.sort();
+    let percentile = |p: usize| {
+        times
+            .get((times.len() * p / 100).min(times.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default()
+    };
+    BenchSummary {
+        pages: timings.len(),
+        bytes: timings.iter().map(|t| t.bytes).sum(),
+        total: times.iter().sum(),
+        median: percentile(50),
+        p95: percentile(95),
+        max: times.last().copied().unwrap_or_default(),
+    }
+}
+
+/// Hours and minutes, or seconds for short runs
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{:.1}s", duration.as_secs_f64()),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::rules::{Rule, RuleSet};
+    use awb_engine::general_fixes::FixRegistry;
+    use std::collections::HashSet;
+
+    fn timing(name: &str, millis: u64) -> PageTiming {
+        PageTiming {
+            name: name.to_string(),
+            bytes: 1000,
+            time: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let timings: Vec<PageTiming> = (1..=20).map(|i| timing("p", i)).collect();
+        let summary = summarize(&timings);
+        assert_eq!(summary.pages, 20);
+        assert_eq!(summary.bytes, 20_000);
+        assert_eq!(summary.total, Duration::from_millis(210));
+        assert_eq!(summary.median, Duration::from_millis(11));
+        assert_eq!(summary.p95, Duration::from_millis(20));
+        assert_eq!(summary.max, Duration::from_millis(20));
+        // 10.5ms per page
+        assert_eq!(summary.estimate(1000), Duration::from_millis(10_500));
+
+        assert_eq!(summarize(&[]).estimate(1000), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_load_pages_skips_expected_output() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("B.wiki"), "b").unwrap();
+        std::fs::write(dir.path().join("A.input.wiki"), "a").unwrap();
+        std::fs::write(dir.path().join("A.expected.wiki"), "x").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "x").unwrap();
+        let pages = load_pages(dir.path()).unwrap();
+        let names: Vec<&str> = pages.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["A.input.wiki", "B.wiki"]);
+    }
+
+    #[test]
+    fn test_bench_pages_times_each_page() {
+        let mut rules = RuleSet::new();
+        rules.add(Rule::new_plain("teh", "the", true));
+        let engine =
+            TransformEngine::new(&rules, FixRegistry::with_defaults(), HashSet::new()).unwrap();
+        let pages = vec![("A.wiki".to_string(), "teh end\n".repeat(100))];
+        let timings = bench_pages(&engine, &pages, 2);
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].bytes, 800);
+        assert!(timings[0].time > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(2500)), "2.5s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_duration(Duration::from_secs(7380)), "2h 3m");
+    }
+}
@@ -1,34 +1,58 @@
+use super::rule_source::ProfileArgs;
+use super::sample::SampleArgs;
 use anyhow::{Context, Result};
-use awb_bot::{BotConfig, BotRunner, Checkpoint};
+use awb_bot::{BotConfig, BotReport, BotRunner, Checkpoint};
+use awb_domain::page_list::PageList;
 use awb_domain::profile::AuthMethod;
-use awb_domain::rules::RuleSet;
-use awb_engine::general_fixes::FixRegistry;
-use awb_engine::transform::TransformEngine;
-use awb_mw_api::client::{MediaWikiClient, ReqwestMwClient};
-use awb_security::{CredentialPort, InMemoryCredentialStore};
-use awb_storage::TomlConfigStore;
+use awb_domain::types::Watchlist;
+use awb_engine::general_fixes::{FixModule, FixRegistry};
+use awb_mw_api::client::MediaWikiClient;
+use awb_plugins::signing::default_trust_store_path;
+use awb_storage::CompiledRuleCache;
+use awb_storage::rule_library::{content_hash, default_cache_dir};
+use awb_telemetry::LogRecord;
 use console::style;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
 use url::Url;
 
 /// Arguments for the bot run command
 pub struct BotRunArgs {
     pub wiki: Url,
-    pub profile_path: PathBuf,
+    pub profile: ProfileArgs,
+    pub config: Option<PathBuf>,
+    pub pages: Option<PathBuf>,
+    pub sample: SampleArgs,
     pub max_edits: Option<u32>,
     pub dry_run: bool,
+    pub diff_dir: Option<PathBuf>,
     pub checkpoint_path: Option<PathBuf>,
     pub auth_profile: String,
     pub skip_no_change: bool,
     pub skip_on_warning: bool,
-    pub log_every_n: u32,
+    pub verify_idempotent: bool,
+    pub allow_content_loss: bool,
+    pub log_every_n: Option<u32>,
+    pub jobs: Option<usize>,
+    pub profile_memory: bool,
+    pub tags: Vec<String>,
+    /// Log records for the `--tui` dashboard, which replaces the log output
+    pub dashboard_logs: Option<Receiver<LogRecord>>,
+    pub control_socket: Option<PathBuf>,
+    pub snapshot_dir: Option<PathBuf>,
+    /// Directory of signed plugins to run after the general fixes
+    pub plugins: Option<PathBuf>,
+    /// Reload plugins edited in `plugins` between pages
+    pub watch_plugins: bool,
+    /// Directory for the JSON report (default: the working directory)
+    pub report_dir: Option<PathBuf>,
 }
 
-pub async fn run(args: BotRunArgs) -> Result<()> {
+pub async fn run(mut args: BotRunArgs) -> Result<BotReport> {
     println!("{}", style("AWB-RS Bot Mode").bold().cyan());
     println!("Wiki: {}", args.wiki);
-    println!("Profile: {}", args.profile_path.display());
+    println!("Profile: {}", args.profile.profile);
     println!(
         "Mode: {}",
         if args.dry_run {
@@ -43,20 +67,24 @@ pub async fn run(args: BotRunArgs) -> Result<()> {
     println!();
 
     // Load profile
-    let config_store = TomlConfigStore::new(&args.profile_path);
+    let config_store = args.profile.config_store();
     let profile = config_store
         .load_profile(&args.auth_profile)
         .context("Failed to load profile. Create one first or use a different auth-profile.")?;
 
     // Get credentials
-    let cred_store = InMemoryCredentialStore::new();
+    let (_, cred_store) = super::credentials::open_store()?;
     let password = cred_store
         .get_password(&args.auth_profile)
         .context("No stored credentials found. Run 'login' command first.")?;
 
     // Create client and login
-    let client = ReqwestMwClient::new(args.wiki.clone(), profile.throttle_policy.clone())
-        .context("Failed to create HTTP client")?;
+    let client = super::credentials::profile_client(
+        &args.wiki,
+        &args.auth_profile,
+        &profile,
+        cred_store.as_ref(),
+    )?;
 
     print!("Logging in... ");
     let username = match &profile.auth_method {
@@ -83,35 +111,104 @@ pub async fn run(args: BotRunArgs) -> Result<()> {
         .context("Failed to fetch CSRF token")?;
     println!("{}", style("✓").green().bold());
 
+    // Namespace names and case rules for reading titles
+    let site = super::site::fetch_with(&client).await;
+
     // Load rules and build engine
-    let ruleset = RuleSet::new(); // In production, load from profile
-    let registry = FixRegistry::with_defaults();
-    let enabled_fixes = HashSet::new(); // In production, load from profile
+    let ruleset = args
+        .profile
+        .load_rules(
+            &config_store,
+            &args.auth_profile,
+            &args.wiki,
+            &client,
+            &site,
+        )
+        .await?;
+    let mut registry = FixRegistry::with_defaults();
+    let mut enabled_fixes = HashSet::new(); // In production, load from profile
+    if let Some(dir) = &args.plugins {
+        let mut plugins = super::plugin::load_signed(dir, &default_trust_store_path())?;
+        if args.watch_plugins {
+            plugins.manager_mut().watch(dir)?;
+        }
+        enabled_fixes.insert(plugins.id().to_string());
+        registry.register(Box::new(plugins))?;
+    }
 
-    let engine = TransformEngine::new(&ruleset, registry, enabled_fixes)
-        .context("Failed to create transform engine")?;
+    let engine = CompiledRuleCache::new(default_cache_dir())
+        .engine(&ruleset, registry, enabled_fixes)
+        .context("Failed to create transform engine")?
+        .with_summary_template(super::site::summary_template(&ruleset, &args.wiki, &site));
 
-    // For demo purposes, generate a simple page list
-    // In real usage, this would come from the profile configuration or a list command
-    let pages = vec![
-        "Test Page 1".to_string(),
-        "Test Page 2".to_string(),
-        "Test Page 3".to_string(),
-    ];
+    let pages = match &args.pages {
+        Some(path) => super::site::load_pages(&site, path)?,
+        // For demo purposes, generate a simple page list
+        None => PageList::parse(&site, "Test Page 1\nTest Page 2\nTest Page 3")?,
+    };
+    let (pages, sample) = args.sample.apply(pages)?;
 
     println!("Processing {} pages...", pages.len());
     println!();
 
-    // Configure bot
-    let mut bot_config = BotConfig::new()
-        .with_skip_no_change(args.skip_no_change)
-        .with_skip_on_warning(args.skip_on_warning)
-        .with_log_every_n(args.log_every_n)
-        .with_dry_run(args.dry_run);
-
+    // Configure bot: flags given on the command line override the config
+    // file; the runner enforces its compliance block regardless
+    let mut bot_config = match &args.config {
+        Some(path) => {
+            let source = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            BotConfig::from_toml(&source)
+                .with_context(|| format!("Invalid bot config {}", path.display()))?
+        }
+        None => BotConfig::new().with_skip_no_change(args.skip_no_change),
+    };
+    bot_config.skip_on_warning |= args.skip_on_warning;
+    bot_config.verify_idempotent |= args.verify_idempotent;
+    bot_config.allow_content_loss |= args.allow_content_loss;
+    bot_config.dry_run |= args.dry_run;
+    bot_config.profile_memory |= args.profile_memory;
+    if bot_config.profile_memory && !awb_engine::memory::is_tracking() {
+        println!(
+            "{} peak memory is unavailable in this build (enable the `profile-memory` \
+             feature); only text sizes will be reported",
+            style("Note:").yellow()
+        );
+    }
+    if let Some(n) = args.log_every_n {
+        bot_config = bot_config.with_log_every_n(n);
+    }
+    if let Some(jobs) = args.jobs {
+        bot_config = bot_config.with_jobs(jobs);
+    }
     if let Some(max) = args.max_edits {
         bot_config = bot_config.with_max_edits(max);
     }
+    if let Some(dir) = &args.diff_dir {
+        bot_config = bot_config.with_diff_dir(dir.clone());
+    }
+    if args.profile.watchlist.is_some() || bot_config.watchlist == Watchlist::Preferences {
+        bot_config =
+            bot_config.with_watchlist(args.profile.watchlist(&config_store, &args.auth_profile));
+    }
+    if let Some(dir) = &args.snapshot_dir {
+        bot_config = bot_config.with_snapshot_dir(dir.clone());
+    }
+    if let Some(path) = &args.control_socket {
+        bot_config = bot_config.with_control_socket(path.clone());
+    }
+    bot_config.tags.extend(args.tags.iter().cloned());
+    let rules_sha256 = content_hash(&serde_json::to_string(&ruleset)?);
+    bot_config = bot_config.with_rules_hash(&rules_sha256);
+    if !bot_config.tags.is_empty() {
+        println!("Tags: {}", bot_config.tags.join(", "));
+    }
+
+    if bot_config.compliance.enabled {
+        println!(
+            "{} Compliance mode: approval conditions are enforced",
+            style("ℹ").cyan()
+        );
+    }
 
     // Load or create checkpoint
     let checkpoint = if let Some(ref path) = args.checkpoint_path {
@@ -123,7 +220,8 @@ pub async fn run(args: BotRunArgs) -> Result<()> {
         }
     } else {
         Checkpoint::new()
-    };
+    }
+    .with_site(site.clone());
 
     // Create and run bot
     let mut bot_runner = if checkpoint.next_index() > 0 {
@@ -134,12 +232,20 @@ pub async fn run(args: BotRunArgs) -> Result<()> {
         BotRunner::with_checkpoint(bot_config, client, engine, pages, checkpoint)
     } else {
         BotRunner::new(bot_config, client, engine, pages)
-    };
+    }
+    .with_site(site);
+    if let Some(sample) = sample {
+        bot_runner = bot_runner.with_sample(sample);
+    }
 
     // Register secrets for redaction in error messages
     bot_runner.add_secret(password.clone());
 
-    let report = match bot_runner.run().await {
+    let result = match args.dashboard_logs.take() {
+        Some(logs) => super::dashboard::run_bot(&mut bot_runner, logs).await?,
+        None => bot_runner.run().await,
+    };
+    let report = match result {
         Ok(report) => report,
         Err(e) => {
             eprintln!("{} Bot error: {}", style("✗").red(), e);
@@ -171,12 +277,26 @@ pub async fn run(args: BotRunArgs) -> Result<()> {
     println!("{}", style("═".repeat(60)).dim());
 
     // Save JSON report
-    let report_path = PathBuf::from(format!(
+    let report_path = args.report_dir.unwrap_or_default().join(format!(
         "bot-report-{}.json",
         chrono::Utc::now().format("%Y%m%d-%H%M%S")
     ));
     std::fs::write(&report_path, report.to_json()?).context("Failed to save report")?;
     println!("Report saved to: {}", report_path.display());
+    if let Some(dir) = &args.diff_dir {
+        println!(
+            "Diffs written to: {} ({})",
+            dir.display(),
+            awb_bot::diff_artifacts::INDEX_FILE
+        );
+    }
+    if let Some(dir) = &args.snapshot_dir {
+        println!(
+            "Snapshots kept in: {} (awb-rs restore --run {} --page <TITLE>)",
+            dir.display(),
+            dir.display()
+        );
+    }
 
-    Ok(())
+    Ok(report)
 }
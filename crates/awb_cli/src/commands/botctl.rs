@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use awb_bot::control_socket::send;
+use awb_bot::{ControlCommand, ControlReply, ControlStatus, RunState};
+use console::style;
+use std::path::PathBuf;
+
+pub async fn run(socket: PathBuf, command: ControlCommand) -> Result<()> {
+    let reply = send(&socket, command)
+        .await
+        .with_context(|| format!("No bot is listening on {}", socket.display()))?;
+    match reply {
+        ControlReply::Status(status) => {
+            print_status(&status);
+            Ok(())
+        }
+        ControlReply::Error(e) => anyhow::bail!("The bot refused '{}': {}", command.as_str(), e),
+    }
+}
+
+fn print_status(status: &ControlStatus) {
+    let state = match status.state {
+        RunState::Running => style(status.state).green(),
+        RunState::Pausing | RunState::Paused => style(status.state).yellow(),
+        RunState::Stopping => style(status.state).red(),
+    };
+    let progress = &status.progress;
+    println!("State:    {}", state.bold());
+    println!(
+        "Progress: {}/{} pages ({} edited, {} skipped, {} errors)",
+        progress.done, progress.total, progress.edited, progress.skipped, progress.errored
+    );
+    if let Some(page) = &progress.current {
+        println!("Page:     {}", page);
+    }
+    if let Some(throttle) = &progress.throttle {
+        if let Some(left) = throttle.remaining(chrono::Utc::now()) {
+            println!(
+                "Throttle: {} ({:.1}s left)",
+                throttle.reason,
+                left.as_secs_f64()
+            );
+        }
+    }
+}
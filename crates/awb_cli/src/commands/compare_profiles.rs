@@ -0,0 +1,280 @@
+use anyhow::{Context, Result};
+use awb_domain::rules::RuleSet;
+use awb_domain::session::EditPlan;
+use awb_domain::types::*;
+use awb_engine::diff_engine::{compute_diff, to_unified};
+use awb_engine::transform::TransformEngine;
+use awb_mw_api::list_endpoints::{MAX_TITLES_PER_QUERY, fetch_page_texts};
+use console::style;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// What one profile did to a page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProfileOutcome {
+    /// Rules that changed the page, by comment or pattern
+    pub rules_applied: Vec<String>,
+    pub fixes_applied: Vec<String>,
+    pub summary: String,
+    pub warnings: Vec<String>,
+    /// Unified diff of the page's text; empty if it is left alone
+    pub diff: String,
+}
+
+/// A page the two profiles leave with different text
+#[derive(Debug, Clone, Serialize)]
+pub struct PageDifference {
+    pub title: String,
+    pub a: ProfileOutcome,
+    pub b: ProfileOutcome,
+    /// Rules and fixes that changed the page under only one of the profiles
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+    /// Unified diff from profile A's diff to profile B's
+    pub diff_of_diffs: String,
+}
+
+/// Result of running both profiles over the same pages
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparisonReport {
+    pub profile_a: PathBuf,
+    pub profile_b: PathBuf,
+    pub pages_compared: usize,
+    /// Listed pages the wiki did not return
+    pub pages_missing: Vec<String>,
+    pub differences: Vec<PageDifference>,
+}
+
+/// A profile's engine with its rules, for naming the rules that fired
+pub struct Side {
+    pub engine: TransformEngine,
+    pub rules: RuleSet,
+}
+
+impl Side {
+    pub fn load(profile: &Path, auth_profile: &str, wiki: &Url) -> Result<Self> {
+        let engine = super::corpus::load_engine(profile, auth_profile, wiki.host_str())
+            .with_context(|| format!("Failed to load profile {}", profile.display()))?;
+        let rules = awb_storage::TomlConfigStore::new(profile)
+            .load_rules(auth_profile)
+            .with_context(|| format!("Failed to load rules from {}", profile.display()))?;
+        Ok(Self { engine, rules })
+    }
+
+    fn outcome(&self, page: &PageContent) -> ProfileOutcome {
+        let plan = self.engine.apply(page);
+        ProfileOutcome {
+            rules_applied: plan
+                .rules_applied
+                .iter()
+                .map(|id| {
+                    self.rules
+                        .rules
+                        .iter()
+                        .find(|rule| rule.id == *id)
+                        .map(super::lint_rules::describe)
+                        .unwrap_or_else(|| id.to_string())
+                })
+                .collect(),
+            fixes_applied: plan.fixes_applied.clone(),
+            summary: plan.summary.clone(),
+            warnings: plan.warnings.iter().map(|w| format!("{:?}", w)).collect(),
+            diff: unified_diff(&page.title.display, &plan),
+        }
+    }
+}
+
+pub async fn run(
+    wiki: Url,
+    profile_a: PathBuf,
+    profile_b: PathBuf,
+    list: PathBuf,
+    auth_profile: String,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    println!("{}", style("AWB-RS Profile Comparison").bold().cyan());
+    println!("Wiki: {}", wiki);
+    println!("A: {}", profile_a.display());
+    println!("B: {}", profile_b.display());
+    println!();
+
+    let a = Side::load(&profile_a, &auth_profile, &wiki)?;
+    let b = Side::load(&profile_b, &auth_profile, &wiki)?;
+    // Pages are fetched with profile A's connection settings
+    let client = super::credentials::stored_profile_client(
+        &awb_storage::TomlConfigStore::new(&profile_a),
+        &wiki,
+        &auth_profile,
+    )?;
+    let site = super::site::fetch_with(&client).await;
+    let titles = super::site::load_pages(&site, &list)?;
+
+    let mut report = ComparisonReport {
+        profile_a,
+        profile_b,
+        pages_compared: 0,
+        pages_missing: Vec::new(),
+        differences: Vec::new(),
+    };
+    for batch in titles.titles().chunks(MAX_TITLES_PER_QUERY) {
+        let pages = fetch_page_texts(client.http(), &wiki, batch)
+            .await
+            .context("Failed to fetch page text")?;
+        for title in batch {
+            if !pages.iter().any(|(fetched, _)| fetched == title) {
+                report.pages_missing.push(title.display.clone());
+            }
+        }
+        for (title, wikitext) in pages {
+            report.pages_compared += 1;
+            if let Some(difference) = compare_page(&a, &b, title, wikitext) {
+                println!("  {} {}", style("≠").yellow(), difference.title);
+                report.differences.push(difference);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} pages compared: {} differ, {} missing",
+        report.pages_compared,
+        report.differences.len(),
+        report.pages_missing.len()
+    );
+    if let Some(path) = &output {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Failed to write report {}", path.display()))?;
+        println!("Report written to {}", path.display());
+    } else {
+        for difference in &report.differences {
+            println!();
+            println!("{}", style(&difference.title).bold());
+            if !difference.only_a.is_empty() {
+                println!("  only A: {}", difference.only_a.join(", "));
+            }
+            if !difference.only_b.is_empty() {
+                println!("  only B: {}", difference.only_b.join(", "));
+            }
+            print!("{}", difference.diff_of_diffs);
+        }
+    }
+
+    if !report.differences.is_empty() {
+        anyhow::bail!(
+            "Profiles differ on {} of {} pages",
+            report.differences.len(),
+            report.pages_compared
+        );
+    }
+    Ok(())
+}
+
+/// Run both profiles over one page; `None` if they leave it with the
+/// same text
+pub fn compare_page(a: &Side, b: &Side, title: Title, wikitext: String) -> Option<PageDifference> {
+    let page = PageContent {
+        page_id: PageId(0),
+        title,
+        revision: RevisionId(0),
+        timestamp: chrono::Utc::now(),
+        size_bytes: wikitext.len() as u64,
+        wikitext,
+        is_redirect: false,
+        protection: ProtectionInfo::default(),
+        properties: PageProperties::default(),
+    };
+    let a = a.outcome(&page);
+    let b = b.outcome(&page);
+    if a.diff == b.diff {
+        return None;
+    }
+    let steps = |outcome: &ProfileOutcome| -> Vec<String> {
+        outcome
+            .rules_applied
+            .iter()
+            .chain(&outcome.fixes_applied)
+            .cloned()
+            .collect()
+    };
+    let (steps_a, steps_b) = (steps(&a), steps(&b));
+    let diff_of_diffs = format!(
+        "--- A\n+++ B\n{}",
+        to_unified(&compute_diff(&a.diff, &b.diff), 3)
+    );
+    Some(PageDifference {
+        title: page.title.display,
+        only_a: steps_a
+            .iter()
+            .filter(|step| !steps_b.contains(step))
+            .cloned()
+            .collect(),
+        only_b: steps_b
+            .iter()
+            .filter(|step| !steps_a.contains(step))
+            .cloned()
+            .collect(),
+        a,
+        b,
+        diff_of_diffs,
+    })
+}
+
+fn unified_diff(title: &str, plan: &EditPlan) -> String {
+    if plan.new_wikitext == plan.page.wikitext {
+        return String::new();
+    }
+    format!(
+        "--- a/{title}\n+++ b/{title}\n{}",
+        to_unified(&plan.diff_ops, 3)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::rules::Rule;
+    use awb_engine::general_fixes::FixRegistry;
+    use std::collections::HashSet;
+
+    fn side(rules: Vec<Rule>) -> Side {
+        let mut set = RuleSet::new();
+        for rule in rules {
+            set.add(rule);
+        }
+        Side {
+            engine: TransformEngine::new(&set, FixRegistry::new(), HashSet::new()).unwrap(),
+            rules: set,
+        }
+    }
+
+    fn title() -> Title {
+        Title::new(Namespace::MAIN, "Foo")
+    }
+
+    #[test]
+    fn test_same_output_is_no_difference() {
+        // Rules written differently but doing the same thing
+        let a = side(vec![Rule::new_plain("teh", "the", true)]);
+        let b = side(vec![Rule::new_regex(r"\bteh\b", "the", false)]);
+        assert!(compare_page(&a, &b, title(), "teh end\n".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_difference_names_the_rules() {
+        let a = side(vec![Rule::new_plain("teh", "the", true)]);
+        let b = side(vec![
+            Rule::new_plain("teh", "the", true),
+            Rule::new_plain("recieve", "receive", true),
+        ]);
+        let difference =
+            compare_page(&a, &b, title(), "teh end\nwe recieve\n".to_string()).unwrap();
+
+        assert_eq!(difference.title, "Foo");
+        assert!(difference.only_a.is_empty());
+        assert_eq!(difference.only_b, vec!["\"recieve\""]);
+        assert!(difference.a.diff.contains("+the end"));
+        assert!(difference.diff_of_diffs.contains("+-we recieve"));
+        assert!(difference.diff_of_diffs.contains("++we receive"));
+    }
+}
@@ -0,0 +1,34 @@
+use super::rule_source::default_config_path;
+use anyhow::{Context, Result};
+use awb_storage::{PreferenceSource, TomlConfigStore};
+use console::style;
+
+pub async fn show(effective: bool, wiki: Option<String>, profile: Option<String>) -> Result<()> {
+    let store = TomlConfigStore::new(default_config_path());
+
+    if !effective {
+        let prefs = store
+            .load_preferences()
+            .context("Failed to load preferences")?;
+        print!(
+            "{}",
+            toml::to_string_pretty(&prefs).context("Failed to format preferences")?
+        );
+        return Ok(());
+    }
+
+    let merged = store
+        .load_effective_preferences(wiki.as_deref(), profile.as_deref())
+        .context("Failed to resolve preferences")?;
+    let entries = merged.entries().context("Failed to format preferences")?;
+    for (path, value) in entries {
+        let source = merged.source(&path);
+        let line = format!("{} = {}", path, value);
+        match source {
+            PreferenceSource::Global => println!("{}  {}", line, style("# global").dim()),
+            source => println!("{}  {}", line, style(format!("# {}", source)).cyan()),
+        }
+    }
+
+    Ok(())
+}
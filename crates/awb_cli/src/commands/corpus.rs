@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use awb_domain::types::*;
+use awb_engine::diff_engine::{compute_diff, to_unified};
+use awb_engine::general_fixes::FixRegistry;
+use awb_engine::spell_check::SpellCheck;
+use awb_engine::transform::TransformEngine;
+use awb_plugins::harness::{EXPECTED_SUFFIX, NamedTestCase, load_case_files};
+use awb_storage::{StorageError, TomlConfigStore};
+use console::style;
+use std::path::{Path, PathBuf};
+
+/// Outcome of running the profile over one saved page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaseOutcome {
+    /// Output matches the expected file
+    Passed,
+    /// Output differs; holds the unified diff from expected to actual
+    Regressed(String),
+    /// No expected file has been committed for this page yet
+    Missing,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseResult {
+    pub name: String,
+    pub output: String,
+    pub outcome: CaseOutcome,
+}
+
+pub async fn run(profile: PathBuf, dir: PathBuf, auth_profile: String, update: bool) -> Result<()> {
+    println!("{}", style("AWB-RS Corpus").bold().cyan());
+    println!("Profile: {}", profile.display());
+    println!("Corpus: {}", dir.display());
+    println!();
+
+    let engine = load_engine(&profile, &auth_profile, None)?;
+
+    let cases = load_case_files(&dir).context("Failed to read corpus")?;
+    if cases.is_empty() {
+        anyhow::bail!(
+            "No pages found. Add <page>.input.wiki files, each paired with \
+             <page>.expected.wiki, to {}",
+            dir.display()
+        );
+    }
+
+    let results = run_cases(&engine, &cases);
+    for result in &results {
+        match &result.outcome {
+            CaseOutcome::Passed => println!("  {} {}", style("✓").green().bold(), result.name),
+            CaseOutcome::Missing => println!(
+                "  {} {} {}",
+                style("?").yellow().bold(),
+                result.name,
+                style("(no expected output)").dim()
+            ),
+            CaseOutcome::Regressed(diff) => {
+                println!("  {} {}", style("✗").red().bold(), result.name);
+                for line in diff.lines() {
+                    let line = match line.chars().next() {
+                        Some('+') => style(line).green(),
+                        Some('-') => style(line).red(),
+                        _ => style(line).dim(),
+                    };
+                    println!("      {}", line);
+                }
+            }
+        }
+    }
+
+    println!();
+    let passed = count(&results, |o| matches!(o, CaseOutcome::Passed));
+    let regressed = count(&results, |o| matches!(o, CaseOutcome::Regressed(_)));
+    let missing = count(&results, |o| matches!(o, CaseOutcome::Missing));
+    println!(
+        "{} passed, {} regressed, {} missing expected output",
+        passed, regressed, missing
+    );
+
+    if update {
+        let written = write_expected(&dir, &results).context("Failed to update corpus")?;
+        println!("Updated {} expected file(s)", written);
+        return Ok(());
+    }
+    if regressed + missing > 0 {
+        anyhow::bail!(
+            "{} of {} corpus pages did not match; rerun with --update to accept the new output",
+            regressed + missing,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+/// The engine for the rules of `auth_profile` in the `profile` file, with
+/// its general fixes. Overrides for `wiki` (a host name), or else for the
+/// profile's own wiki, apply, and the profile's own overrides when it is
+/// defined in the file.
+pub fn load_engine(
+    profile: &Path,
+    auth_profile: &str,
+    wiki: Option<&str>,
+) -> Result<TransformEngine> {
+    let store = TomlConfigStore::new(profile);
+    let rules = store
+        .load_rules(auth_profile)
+        .context("Failed to load rules from profile")?;
+    let preferences = match store.load_effective_preferences(wiki, Some(auth_profile)) {
+        Err(StorageError::NotFound(_)) => store.load_effective_preferences(wiki, None),
+        effective => effective,
+    };
+    let fix_config = preferences
+        .context("Failed to load preferences from profile")?
+        .preferences
+        .fix_config;
+    let mut registry = FixRegistry::with_config(&fix_config);
+    if let Some(spell_check) = &fix_config.spell_check {
+        let checker =
+            SpellCheck::load(spell_check).context("Failed to load spell check dictionary")?;
+        registry
+            .register(Box::new(checker))
+            .context("Failed to register spell check")?;
+    }
+    let enabled = registry
+        .enabled_ids(&fix_config)
+        .context("Invalid fix config")?;
+    TransformEngine::new(&rules, registry, enabled).context("Failed to create transform engine")
+}
+
+/// Run the engine over each saved page and compare with its expected output
+pub fn run_cases(engine: &TransformEngine, cases: &[NamedTestCase]) -> Vec<CaseResult> {
+    cases
+        .iter()
+        .map(|named| {
+            let page = corpus_page(&named.name, &named.case.input);
+            let output = engine.apply(&page).new_wikitext;
+            let outcome = match &named.case.expected {
+                None => CaseOutcome::Missing,
+                Some(expected) if *expected == output => CaseOutcome::Passed,
+                Some(expected) => {
+                    CaseOutcome::Regressed(to_unified(&compute_diff(expected, &output), 3))
+                }
+            };
+            CaseResult {
+                name: named.name.clone(),
+                output,
+                outcome,
+            }
+        })
+        .collect()
+}
+
+/// Write the actual output of every failing case as its expected output
+pub fn write_expected(dir: &Path, results: &[CaseResult]) -> std::io::Result<usize> {
+    let mut written = 0;
+    for result in results {
+        if result.outcome != CaseOutcome::Passed {
+            std::fs::write(
+                dir.join(format!("{}{}", result.name, EXPECTED_SUFFIX)),
+                &result.output,
+            )?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+fn count(results: &[CaseResult], f: impl Fn(&CaseOutcome) -> bool) -> usize {
+    results.iter().filter(|r| f(&r.outcome)).count()
+}
+
+/// A main-namespace page titled after the corpus file, underscores as spaces
+fn corpus_page(name: &str, wikitext: &str) -> PageContent {
+    PageContent {
+        page_id: PageId(0),
+        title: Title::new(Namespace::MAIN, name.replace('_', " ")),
+        revision: RevisionId(0),
+        timestamp: chrono::Utc::now(),
+        wikitext: wikitext.to_string(),
+        size_bytes: wikitext.len() as u64,
+        is_redirect: false,
+        protection: ProtectionInfo::default(),
+        properties: PageProperties::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::rules::{Rule, RuleSet};
+    use std::collections::HashSet;
+
+    fn engine() -> TransformEngine {
+        let mut rules = RuleSet::new();
+        rules.add(Rule::new_plain("teh", "the", true));
+        TransformEngine::new(&rules, FixRegistry::with_defaults(), HashSet::new()).unwrap()
+    }
+
+    fn corpus(dir: &Path) {
+        std::fs::write(dir.join("Good.input.wiki"), "teh end\n").unwrap();
+        std::fs::write(dir.join("Good.expected.wiki"), "the end\n").unwrap();
+        std::fs::write(dir.join("Bad.input.wiki"), "teh start\n").unwrap();
+        std::fs::write(dir.join("Bad.expected.wiki"), "teh start\n").unwrap();
+        std::fs::write(dir.join("New_page.input.wiki"), "plain\n").unwrap();
+    }
+
+    #[test]
+    fn test_run_cases_reports_each_outcome() {
+        let dir = tempfile::TempDir::new().unwrap();
+        corpus(dir.path());
+        let cases = load_case_files(dir.path()).unwrap();
+        let results = run_cases(&engine(), &cases);
+
+        let outcome = |name: &str| {
+            results
+                .iter()
+                .find(|r| r.name == name)
+                .map(|r| r.outcome.clone())
+                .unwrap()
+        };
+        assert_eq!(outcome("Good"), CaseOutcome::Passed);
+        assert_eq!(outcome("New_page"), CaseOutcome::Missing);
+        match outcome("Bad") {
+            CaseOutcome::Regressed(diff) => {
+                assert!(diff.contains("-teh start"));
+                assert!(diff.contains("+the start"));
+            }
+            other => panic!("expected regression, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_write_expected_accepts_new_output() {
+        let dir = tempfile::TempDir::new().unwrap();
+        corpus(dir.path());
+        let cases = load_case_files(dir.path()).unwrap();
+        let written = write_expected(dir.path(), &run_cases(&engine(), &cases)).unwrap();
+        assert_eq!(written, 2);
+
+        let cases = load_case_files(dir.path()).unwrap();
+        let results = run_cases(&engine(), &cases);
+        assert!(results.iter().all(|r| r.outcome == CaseOutcome::Passed));
+    }
+}
@@ -0,0 +1,255 @@
+use super::rule_source::default_config_path;
+use anyhow::{Context, Result};
+use awb_domain::profile::{ConnectionOptions, Profile, ThrottlePolicy};
+use awb_mw_api::client::ReqwestMwClient;
+use awb_security::{CredentialBackend, CredentialPort, migrate_credentials};
+use awb_storage::{StorageError, TomlConfigStore};
+use console::style;
+use url::Url;
+
+/// Credential store named by `credential_backend` in ~/.awb-rs/config.toml
+pub fn open_store() -> Result<(CredentialBackend, Box<dyn CredentialPort>)> {
+    let backend = configured_backend(&TomlConfigStore::new(default_config_path()))?;
+    let store = backend
+        .open()
+        .with_context(|| format!("Failed to open the {} credential store", backend))?;
+    Ok((backend, store))
+}
+
+/// API client for `wiki` using the profile's throttle and connection
+/// settings, with its HTTP basic auth password from `cred_store`
+pub fn profile_client(
+    wiki: &Url,
+    profile_id: &str,
+    profile: &Profile,
+    cred_store: &dyn CredentialPort,
+) -> Result<ReqwestMwClient> {
+    let basic_auth_password = match &profile.connection.basic_auth_user {
+        Some(_) => Some(
+            cred_store
+                .get_password(&ConnectionOptions::basic_auth_key(profile_id))
+                .context("No stored HTTP password found. Run 'login --http-user' first.")?,
+        ),
+        None => None,
+    };
+    super::api_trace::client(wiki.clone(), profile.throttle_policy.clone())?
+        .with_connection(&profile.connection, basic_auth_password.as_deref())
+        .context("Invalid connection settings in profile")
+}
+
+/// API client for `wiki` with the throttle and connection settings of
+/// profile `profile_id` in `config`, without logging in. A config without
+/// that profile gets the defaults, and the credential store is only opened
+/// when the profile uses HTTP basic auth.
+pub fn stored_profile_client(
+    config: &TomlConfigStore,
+    wiki: &Url,
+    profile_id: &str,
+) -> Result<ReqwestMwClient> {
+    let profile = match config.load_profile(profile_id) {
+        Ok(profile) => profile,
+        Err(StorageError::NotFound(_)) => {
+            return super::api_trace::client(wiki.clone(), ThrottlePolicy::default());
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to load profile '{}'", profile_id));
+        }
+    };
+    if profile.connection.basic_auth_user.is_none() {
+        return super::api_trace::client(wiki.clone(), profile.throttle_policy.clone())?
+            .with_connection(&profile.connection, None)
+            .context("Invalid connection settings in profile");
+    }
+    let (_, cred_store) = open_store()?;
+    profile_client(wiki, profile_id, &profile, cred_store.as_ref())
+}
+
+fn configured_backend(config: &TomlConfigStore) -> Result<CredentialBackend> {
+    config
+        .load_preferences()
+        .context("Failed to load preferences")?
+        .credential_backend
+        .parse()
+        .context("Invalid credential_backend preference")
+}
+
+pub async fn migrate(from: CredentialBackend, to: CredentialBackend) -> Result<()> {
+    println!("{}", style("Migrate Credentials").bold().cyan());
+    println!("From: {}", from);
+    println!("To: {}", to);
+    println!();
+
+    if from == to {
+        anyhow::bail!("Source and target credential stores are the same");
+    }
+    if from == CredentialBackend::InMemory || to == CredentialBackend::InMemory {
+        anyhow::bail!(
+            "The memory store does not outlive this process; migrate between file and keyring"
+        );
+    }
+
+    let config = TomlConfigStore::new(default_config_path());
+    let source = from
+        .open()
+        .with_context(|| format!("Failed to open the {} credential store", from))?;
+    let target = to
+        .open()
+        .with_context(|| format!("Failed to open the {} credential store", to))?;
+
+    // The keychain cannot list its entries, so the profiles in the config
+    // file and the CLI's default profile are tried as well
+    let mut profile_ids: Vec<String> = config
+        .list_profiles()
+        .context("Failed to load profiles")?
+        .into_iter()
+        .map(|profile| profile.id)
+        .collect();
+    profile_ids.push("default".to_string());
+    if let Ok(stored) = source.profile_ids() {
+        profile_ids.extend(stored);
+    }
+    let (profile_ids, keys) = migration_keys(profile_ids);
+
+    let report =
+        migrate_credentials(source.as_ref(), target.as_ref(), &keys).context("Migration failed")?;
+
+    let mut moved = 0;
+    for id in &profile_ids {
+        let kinds: Vec<&str> = [
+            (report.passwords.contains(id), "password"),
+            (
+                report
+                    .passwords
+                    .contains(&ConnectionOptions::basic_auth_key(id)),
+                "HTTP password",
+            ),
+            (report.oauth_tokens.contains(id), "OAuth token"),
+        ]
+        .into_iter()
+        .filter_map(|(moved, kind)| moved.then_some(kind))
+        .collect();
+        if kinds.is_empty() {
+            continue;
+        }
+        moved += 1;
+        println!(
+            "  {} {} ({})",
+            style("✓").green().bold(),
+            id,
+            kinds.join(", ")
+        );
+    }
+
+    let mut prefs = config
+        .load_preferences()
+        .context("Failed to load preferences")?;
+    prefs.credential_backend = to.to_string();
+    config
+        .save_preferences(&prefs)
+        .context("Failed to save preferences")?;
+
+    println!();
+    println!(
+        "Moved {} profile(s); the {} store was wiped and {} is now the credential backend",
+        moved, from, to
+    );
+    Ok(())
+}
+
+/// The profiles among `candidates`, and the credential keys to migrate for
+/// them: each profile's own key and that of its HTTP basic auth password.
+/// Basic auth keys listed by the store itself are folded into their profile.
+fn migration_keys(mut candidates: Vec<String>) -> (Vec<String>, Vec<String>) {
+    candidates.sort();
+    candidates.dedup();
+    let basic_auth_keys: Vec<String> = candidates
+        .iter()
+        .map(|id| ConnectionOptions::basic_auth_key(id))
+        .collect();
+    let profile_ids: Vec<String> = candidates
+        .into_iter()
+        .filter(|id| !basic_auth_keys.contains(id))
+        .collect();
+    let keys = profile_ids
+        .iter()
+        .flat_map(|id| [id.clone(), ConnectionOptions::basic_auth_key(id)])
+        .collect();
+    (profile_ids, keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_security::InMemoryCredentialStore;
+
+    #[test]
+    fn test_migration_moves_http_passwords() {
+        let from = InMemoryCredentialStore::new();
+        let to = InMemoryCredentialStore::new();
+        from.set_password("enwiki", "bot-password").unwrap();
+        from.set_password(&ConnectionOptions::basic_auth_key("enwiki"), "http-secret")
+            .unwrap();
+        from.set_password(&ConnectionOptions::basic_auth_key("intranet"), "other")
+            .unwrap();
+
+        // As the keychain would offer them, plus a store listing its keys
+        let (profile_ids, keys) = migration_keys(vec![
+            "enwiki".to_string(),
+            "intranet".to_string(),
+            ConnectionOptions::basic_auth_key("enwiki"),
+        ]);
+        assert_eq!(profile_ids, vec!["enwiki", "intranet"]);
+
+        let report = migrate_credentials(&from, &to, &keys).unwrap();
+
+        assert_eq!(
+            to.get_password(&ConnectionOptions::basic_auth_key("enwiki"))
+                .unwrap(),
+            "http-secret"
+        );
+        assert_eq!(
+            to.get_password(&ConnectionOptions::basic_auth_key("intranet"))
+                .unwrap(),
+            "other"
+        );
+        assert_eq!(to.get_password("enwiki").unwrap(), "bot-password");
+        assert_eq!(report.passwords.len(), 3);
+        assert!(from.profile_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stored_profile_client_uses_connection_settings() {
+        use awb_domain::profile::AuthMethod;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = TomlConfigStore::new(dir.path().join("config.toml"));
+        let wiki = Url::parse("https://wiki.corp.example/mediawiki/api.php").unwrap();
+        // Without the profile the defaults are used
+        assert!(stored_profile_client(&config, &wiki, "intranet").is_ok());
+
+        let mut profile = Profile {
+            id: "intranet".to_string(),
+            name: "Intranet".to_string(),
+            api_url: wiki.clone(),
+            auth_method: AuthMethod::BotPassword {
+                username: "Bot".to_string(),
+            },
+            default_namespaces: Default::default(),
+            throttle_policy: ThrottlePolicy::default(),
+            connection: ConnectionOptions::default(),
+        };
+        profile
+            .connection
+            .headers
+            .insert("X Bad".to_string(), "docs".to_string());
+        config.save_profile(&profile).unwrap();
+        let Err(err) = stored_profile_client(&config, &wiki, "intranet") else {
+            panic!("the profile's headers were not used");
+        };
+        assert!(
+            format!("{:#}", err).contains("Invalid connection settings"),
+            "{:#}",
+            err
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Terminal dashboard for `bot --tui`: progress, counters, throttle and the
+//! latest log lines of a running bot, with keys to pause and stop it
+
+use anyhow::{Context, Result};
+use awb_bot::bot_runner::BotError;
+use awb_bot::{BotReport, BotRunner, RunControl, RunProgress};
+use awb_mw_api::client::MediaWikiClient;
+use awb_telemetry::LogRecord;
+use chrono::{DateTime, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, List, ListItem, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::Level;
+
+/// Log lines kept for the log pane
+const LOG_CAPACITY: usize = 500;
+
+/// How often the screen is redrawn when no key is pressed
+const TICK: Duration = Duration::from_millis(200);
+
+/// Run the bot with the dashboard drawn over the terminal, restoring the
+/// terminal once the run ends
+pub async fn run_bot<C: MediaWikiClient>(
+    runner: &mut BotRunner<C>,
+    logs: Receiver<LogRecord>,
+) -> Result<Result<BotReport, BotError>> {
+    let finished = Arc::new(AtomicBool::new(false));
+    let screen = tokio::task::spawn_blocking({
+        let progress = runner.progress();
+        let control = runner.control();
+        let finished = finished.clone();
+        move || show(progress, control, logs, &finished)
+    });
+
+    let result = runner.run().await;
+    finished.store(true, Ordering::Relaxed);
+    screen
+        .await
+        .context("Dashboard panicked")?
+        .context("Dashboard failed")?;
+    Ok(result)
+}
+
+fn show(
+    progress: watch::Receiver<RunProgress>,
+    control: RunControl,
+    logs: Receiver<LogRecord>,
+    finished: &AtomicBool,
+) -> std::io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, progress, &control, logs, finished);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    progress: watch::Receiver<RunProgress>,
+    control: &RunControl,
+    logs: Receiver<LogRecord>,
+    finished: &AtomicBool,
+) -> std::io::Result<()> {
+    let mut dashboard = Dashboard::new();
+    while !finished.load(Ordering::Relaxed) {
+        dashboard.progress = progress.borrow().clone();
+        while let Ok(record) = logs.try_recv() {
+            dashboard.push_log(&record);
+        }
+        terminal.draw(|frame| draw(frame, &dashboard, control))?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                // Raw mode swallows the interrupt signal, so Ctrl-C is a key here
+                if let KeyCode::Char(c) = key.code {
+                    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                    if let Some(action) = KeyAction::for_key(c, ctrl) {
+                        action.apply(control);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, dashboard: &Dashboard, control: &RunControl) {
+    let [status_area, gauge_area, counters_area, log_area, help_area] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let progress = &dashboard.progress;
+    let label = Style::new().add_modifier(Modifier::BOLD);
+
+    let status = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("Status: ", label),
+            Span::raw(dashboard.status(control.is_paused(), control.is_stopped())),
+            Span::styled("   Page: ", label),
+            Span::raw(progress.current.as_deref().unwrap_or("-")),
+        ]),
+        Line::from(vec![
+            Span::styled("Throttle: ", label),
+            Span::raw(dashboard.throttle_status(Utc::now())),
+        ]),
+    ])
+    .block(Block::bordered().title(" AWB-RS bot "));
+    frame.render_widget(status, status_area);
+
+    let gauge = Gauge::default()
+        .block(Block::bordered().title(" Progress "))
+        .gauge_style(Style::new().fg(Color::Cyan))
+        .ratio(dashboard.ratio())
+        .label(dashboard.progress_label());
+    frame.render_widget(gauge, gauge_area);
+
+    let counters = Paragraph::new(Line::from(vec![
+        Span::styled("Edited ", label),
+        Span::styled(progress.edited.to_string(), Style::new().fg(Color::Green)),
+        Span::styled("   Skipped ", label),
+        Span::styled(progress.skipped.to_string(), Style::new().fg(Color::Yellow)),
+        Span::styled("   Errors ", label),
+        Span::styled(progress.errored.to_string(), Style::new().fg(Color::Red)),
+        Span::styled("   Elapsed ", label),
+        Span::raw(format_duration(dashboard.started.elapsed())),
+    ]))
+    .block(Block::bordered());
+    frame.render_widget(counters, counters_area);
+
+    let visible = log_area.height.saturating_sub(2) as usize;
+    let lines: Vec<ListItem> = dashboard
+        .recent_logs(visible)
+        .map(|(level, line)| {
+            let style = match *level {
+                Level::ERROR => Style::new().fg(Color::Red),
+                Level::WARN => Style::new().fg(Color::Yellow),
+                _ => Style::new(),
+            };
+            ListItem::new(Span::styled(line.as_str(), style))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(lines).block(Block::bordered().title(" Log ")),
+        log_area,
+    );
+
+    frame.render_widget(Paragraph::new(KeyAction::HELP), help_area);
+}
+
+/// What a key press does to the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Pause,
+    Resume,
+    TogglePause,
+    /// Emergency stop: abandon the page in flight and save the checkpoint
+    Stop,
+}
+
+impl KeyAction {
+    const HELP: &str = " p pause · r resume · space toggle pause · s / Ctrl-C emergency stop";
+
+    pub fn for_key(key: char, ctrl: bool) -> Option<Self> {
+        match (key, ctrl) {
+            ('c', true) => Some(Self::Stop),
+            (_, true) => None,
+            ('p', _) => Some(Self::Pause),
+            ('r', _) => Some(Self::Resume),
+            (' ', _) => Some(Self::TogglePause),
+            ('s', _) => Some(Self::Stop),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, control: &RunControl) {
+        match self {
+            Self::Pause => control.pause(),
+            Self::Resume => control.resume(),
+            Self::TogglePause if control.is_paused() => control.resume(),
+            Self::TogglePause => control.pause(),
+            Self::Stop => {
+                tracing::warn!("Emergency stop requested");
+                control.stop();
+            }
+        }
+    }
+}
+
+/// What the dashboard shows, apart from the terminal drawing it
+struct Dashboard {
+    progress: RunProgress,
+    logs: VecDeque<(Level, String)>,
+    started: Instant,
+}
+
+impl Dashboard {
+    fn new() -> Self {
+        Self {
+            progress: RunProgress::default(),
+            logs: VecDeque::with_capacity(LOG_CAPACITY),
+            started: Instant::now(),
+        }
+    }
+
+    fn push_log(&mut self, record: &LogRecord) {
+        if self.logs.len() == LOG_CAPACITY {
+            self.logs.pop_front();
+        }
+        self.logs.push_back((record.level, record.format()));
+    }
+
+    /// The last `count` log lines, oldest first
+    fn recent_logs(&self, count: usize) -> impl Iterator<Item = &(Level, String)> {
+        self.logs.iter().skip(self.logs.len().saturating_sub(count))
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.progress.total == 0 {
+            return 0.0;
+        }
+        (self.progress.done as f64 / self.progress.total as f64).min(1.0)
+    }
+
+    fn progress_label(&self) -> String {
+        format!(
+            "{} / {} pages ({:.0}%)",
+            self.progress.done,
+            self.progress.total,
+            self.ratio() * 100.0
+        )
+    }
+
+    /// `pause_requested` and `stopped` come from the [`RunControl`]; the
+    /// runner only reports itself paused once the page in flight is done
+    fn status(&self, pause_requested: bool, stopped: bool) -> &'static str {
+        if stopped {
+            "STOPPING"
+        } else if self.progress.paused {
+            "PAUSED"
+        } else if pause_requested {
+            "pausing after this page"
+        } else {
+            "running"
+        }
+    }
+
+    fn throttle_status(&self, now: DateTime<Utc>) -> String {
+        match &self.progress.throttle {
+            None => "none".to_string(),
+            Some(throttle) => match throttle.remaining(now) {
+                Some(left) => format!("{} ({:.1}s left)", throttle.reason, left.as_secs_f64()),
+                None => format!("none (last: {})", throttle.reason),
+            },
+        }
+    }
+}
+
+fn format_duration(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_bot::Throttle;
+
+    fn record(level: Level, message: &str) -> LogRecord {
+        LogRecord {
+            timestamp: Utc::now(),
+            level,
+            target: "awb_bot".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(KeyAction::for_key('c', true), Some(KeyAction::Stop));
+        assert_eq!(KeyAction::for_key('s', false), Some(KeyAction::Stop));
+        assert_eq!(KeyAction::for_key('p', false), Some(KeyAction::Pause));
+        assert_eq!(KeyAction::for_key('p', true), None);
+        assert_eq!(KeyAction::for_key('c', false), None);
+    }
+
+    #[test]
+    fn test_log_pane_keeps_latest_lines() {
+        let mut dashboard = Dashboard::new();
+        for i in 0..LOG_CAPACITY + 5 {
+            dashboard.push_log(&record(Level::INFO, &format!("line {}", i)));
+        }
+        dashboard.push_log(&record(Level::WARN, "throttled"));
+
+        assert_eq!(dashboard.logs.len(), LOG_CAPACITY);
+        let recent: Vec<_> = dashboard.recent_logs(2).collect();
+        assert!(recent[0].1.ends_with(&format!("line {}", LOG_CAPACITY + 4)));
+        assert_eq!(recent[1].0, Level::WARN);
+        assert_eq!(dashboard.recent_logs(10_000).count(), LOG_CAPACITY);
+    }
+
+    #[test]
+    fn test_progress_and_status() {
+        let mut dashboard = Dashboard::new();
+        assert_eq!(dashboard.progress_label(), "0 / 0 pages (0%)");
+        dashboard.progress.total = 8;
+        dashboard.progress.done = 2;
+        assert_eq!(dashboard.progress_label(), "2 / 8 pages (25%)");
+
+        assert_eq!(dashboard.status(false, false), "running");
+        assert_eq!(dashboard.status(true, false), "pausing after this page");
+        dashboard.progress.paused = true;
+        assert_eq!(dashboard.status(true, false), "PAUSED");
+        assert_eq!(dashboard.status(true, true), "STOPPING");
+    }
+
+    #[test]
+    fn test_throttle_status() {
+        let mut dashboard = Dashboard::new();
+        assert_eq!(dashboard.throttle_status(Utc::now()), "none");
+
+        let throttle = Throttle::new("edit_delay", Duration::from_secs(10));
+        let until = throttle.until;
+        dashboard.progress.throttle = Some(throttle);
+        assert_eq!(
+            dashboard.throttle_status(until - chrono::Duration::seconds(3)),
+            "edit_delay (3.0s left)"
+        );
+        assert_eq!(dashboard.throttle_status(until), "none (last: edit_delay)");
+    }
+
+    #[test]
+    fn test_elapsed_format() {
+        assert_eq!(format_duration(Duration::from_secs(3725)), "01:02:05");
+    }
+}
@@ -1,34 +1,63 @@
 use anyhow::{Context, Result};
-use awb_telemetry::{ExportFormat as TelemetryFormat, export_log};
+use awb_telemetry::{
+    ExportFilter, ExportFormat as TelemetryFormat, export_log_filtered, export_sqlite, read_events,
+};
 use console::style;
 use std::fs::File;
+use std::io::BufReader;
 use std::path::PathBuf;
 
 use crate::ExportFormat;
 
-pub async fn run(format: ExportFormat, output: PathBuf) -> Result<()> {
+pub async fn run(
+    format: ExportFormat,
+    output: PathBuf,
+    input: Option<PathBuf>,
+    filter: ExportFilter,
+) -> Result<()> {
     println!("{}", style("Export Telemetry Log").bold().cyan());
     println!("Format: {:?}", format);
     println!("Output: {}", output.display());
     println!();
 
-    // For now, we'll use an empty event list as a placeholder
-    // In a real implementation, this would load events from a telemetry store
-    let events = vec![];
+    // Without an input log there is nothing recorded to export yet
+    let events = match &input {
+        Some(path) => {
+            let file = File::open(path)
+                .with_context(|| format!("Failed to open event log {}", path.display()))?;
+            read_events(BufReader::new(file))
+                .with_context(|| format!("Failed to read event log {}", path.display()))?
+        }
+        None => vec![],
+    };
+    let exported = events.iter().filter(|e| filter.matches(e)).count();
 
     let telemetry_format = match format {
         ExportFormat::Json => TelemetryFormat::Json,
         ExportFormat::Csv => TelemetryFormat::Csv,
         ExportFormat::Plain => TelemetryFormat::PlainText,
+        ExportFormat::Sqlite => {
+            export_sqlite(&events, &filter, &output).context("Failed to export log")?;
+            println!(
+                "{} Appended {} of {} events to {}",
+                style("✓").green().bold(),
+                exported,
+                events.len(),
+                output.display()
+            );
+            return Ok(());
+        }
     };
 
     let mut file = File::create(&output).context("Failed to create output file")?;
 
-    export_log(&events, telemetry_format, &mut file).context("Failed to export log")?;
+    export_log_filtered(&events, telemetry_format, &filter, &mut file)
+        .context("Failed to export log")?;
 
     println!(
-        "{} Exported {} events to {}",
+        "{} Exported {} of {} events to {}",
         style("✓").green().bold(),
+        exported,
         events.len(),
         output.display()
     );
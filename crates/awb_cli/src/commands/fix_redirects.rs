@@ -0,0 +1,114 @@
+use super::page_edit::{Tally, connect, fetch_page, save_change};
+use anyhow::{Context, Result};
+use awb_engine::redirects::{
+    RedirectMap, RedirectTarget, bypass_redirects, fix_double_redirect, link_targets,
+    parse_redirect,
+};
+use awb_mw_api::list_endpoints::{
+    MAX_TITLES_PER_QUERY, fetch_double_redirects, fetch_redirect_hops,
+};
+use console::style;
+use std::path::PathBuf;
+use url::Url;
+
+/// Arguments for the fix-redirects command
+pub struct FixRedirectsArgs {
+    pub wiki: Url,
+    pub limit: u32,
+    pub skip_double_redirects: bool,
+    pub bypass_on: Vec<String>,
+    pub bypass_pages: Option<PathBuf>,
+    pub dry_run: bool,
+    pub config: Option<PathBuf>,
+    pub auth_profile: String,
+}
+
+pub async fn run(args: FixRedirectsArgs) -> Result<()> {
+    println!("{}", style("AWB-RS Redirect Fixing").bold().cyan());
+    println!("Wiki: {}", args.wiki);
+    if args.dry_run {
+        println!("Mode: {}", style("DRY-RUN").yellow());
+    }
+    println!();
+
+    let client = connect(
+        &args.wiki,
+        args.config.clone(),
+        &args.auth_profile,
+        "fix-redirects",
+    )
+    .await?;
+    let site = super::site::fetch_with(&client).await;
+
+    let mut tally = Tally::default();
+
+    if !args.skip_double_redirects {
+        let pages = fetch_double_redirects(client.http(), &args.wiki, args.limit)
+            .await
+            .context("Failed to fetch double redirects")?;
+        println!(
+            "{} Found {} double redirects",
+            style("ℹ").cyan(),
+            pages.len()
+        );
+        let names: Vec<String> = pages.iter().map(|t| t.display.clone()).collect();
+        let map = redirect_map(client.http(), &args.wiki, &names).await?;
+        for title in &pages {
+            let Some(page) = fetch_page(&client, title, &mut tally).await else {
+                continue;
+            };
+            let change = match fix_double_redirect(&title.display, &page.wikitext, &map) {
+                Ok(Some(new)) => {
+                    let target = parse_redirect(&new).map(|t| t.link()).unwrap_or_default();
+                    Ok(Some((
+                        new,
+                        format!("Fixing double redirect to [[{}]]", target),
+                    )))
+                }
+                Ok(None) => Ok(None),
+                Err(e) => Err(e.to_string()),
+            };
+            save_change(&client, &page, change, args.dry_run, &mut tally).await;
+        }
+    }
+
+    let mut bypass_on = Vec::new();
+    if let Some(path) = &args.bypass_pages {
+        bypass_on.extend(super::site::load_pages(&site, path)?);
+    }
+    for raw in &args.bypass_on {
+        bypass_on.push(super::site::parse_title(&site, raw)?);
+    }
+    for title in &bypass_on {
+        let Some(page) = fetch_page(&client, title, &mut tally).await else {
+            continue;
+        };
+        let map = redirect_map(client.http(), &args.wiki, &link_targets(&page.wikitext)).await?;
+        let change = match bypass_redirects(&page.wikitext, &map) {
+            (_, 0) => Ok(None),
+            (new, n) => Ok(Some((new, format!("Bypassing {} redirects", n)))),
+        };
+        save_change(&client, &page, change, args.dry_run, &mut tally).await;
+    }
+
+    tally.print(args.dry_run);
+    Ok(())
+}
+
+/// Every redirect reachable from `titles`, looked up in batches
+async fn redirect_map(
+    http: &reqwest::Client,
+    api_url: &Url,
+    titles: &[String],
+) -> Result<RedirectMap> {
+    let mut map = RedirectMap::new();
+    for batch in titles.chunks(MAX_TITLES_PER_QUERY) {
+        let hops = fetch_redirect_hops(http, api_url, batch)
+            .await
+            .context("Failed to resolve redirects")?;
+        for hop in hops {
+            map.insert(&hop.from, RedirectTarget::new(hop.to, hop.fragment));
+        }
+    }
+    Ok(map)
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use awb_domain::rules::{Rule, RuleKind};
+use awb_engine::rule_conflicts::find_conflicts;
+use awb_storage::TomlConfigStore;
+use console::style;
+use std::path::PathBuf;
+
+pub async fn run(profile: PathBuf, auth_profile: String) -> Result<()> {
+    let rules = TomlConfigStore::new(&profile)
+        .load_rules(&auth_profile)
+        .context("Failed to load rules from profile")?;
+    let conflicts = find_conflicts(&rules).context("Invalid rule")?;
+    if conflicts.is_empty() {
+        println!(
+            "{} no conflicts between the rules in {}",
+            style("✓").green().bold(),
+            profile.display()
+        );
+        return Ok(());
+    }
+
+    let describe_id = |id| {
+        rules
+            .rules
+            .iter()
+            .find(|rule| rule.id == id)
+            .map(describe)
+            .unwrap_or_default()
+    };
+    for conflict in &conflicts {
+        println!(
+            "{} {}: {} then {}",
+            style("✗").red().bold(),
+            conflict.kind,
+            describe_id(conflict.first),
+            describe_id(conflict.second)
+        );
+        println!("    e.g. {:?}", conflict.example);
+    }
+    anyhow::bail!(
+        "{} conflicting rule pair(s) in {}",
+        conflicts.len(),
+        profile.display()
+    )
+}
+
+/// A rule's edit summary comment, or else what it looks for
+pub fn describe(rule: &Rule) -> String {
+    if let Some(comment) = &rule.comment_fragment {
+        return format!("{:?}", comment);
+    }
+    match &rule.kind {
+        RuleKind::Plain { find, .. } => format!("{:?}", find),
+        RuleKind::Regex { pattern, .. } => format!("/{}/", pattern),
+        RuleKind::JsonPatch { .. } => rule.id.to_string(),
+    }
+}
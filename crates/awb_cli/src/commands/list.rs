@@ -1,28 +1,167 @@
 use anyhow::{Context, Result};
+use awb_domain::page_list::{PageList, SortMode};
+use awb_domain::siteinfo::SiteInfo;
 use awb_domain::types::Title;
-use awb_mw_api::list_endpoints::{fetch_all_pages, fetch_user_contributions, fetch_watchlist};
+use awb_engine::template_params::ParamPredicate;
+use awb_mw_api::client::ReqwestMwClient;
+use awb_mw_api::list_endpoints::{
+    MAX_TITLES_PER_QUERY, fetch_all_pages, fetch_page_texts, fetch_transclusions,
+    fetch_user_contributions, fetch_watchlist,
+};
+use awb_mw_api::query_tools::{self, PETSCAN_URL, QUARRY_URL, QuarryFormat};
+use awb_storage::TomlConfigStore;
 use console::style;
+use std::path::{Path, PathBuf};
 use url::Url;
 
+use super::rule_source::default_config_path;
 use crate::ListSource;
 
-pub async fn run(wiki: Url, source: ListSource, query: String, limit: usize) -> Result<()> {
+/// What to do with a page list once it is fetched
+#[derive(clap::Args)]
+pub struct ListOptions {
+    /// Sort the list: alphabetical, namespace or random
+    #[arg(long, value_name = "MODE")]
+    sort: Option<SortMode>,
+
+    /// Seed for `--sort random`; the same seed gives the same order
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// Drop pages listed in this file (repeatable)
+    #[arg(long, value_name = "FILE")]
+    exclude: Vec<PathBuf>,
+
+    /// Keep only pages also listed in this file (repeatable)
+    #[arg(long, value_name = "FILE")]
+    intersect: Vec<PathBuf>,
+
+    /// Save the list to this file, one title per line
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+impl ListOptions {
+    /// Whether `--exclude` or `--intersect` lists need reading
+    fn reads_lists(&self) -> bool {
+        !self.exclude.is_empty() || !self.intersect.is_empty()
+    }
+
+    /// Dedupe, filter and sort `pages` as requested
+    fn apply(&self, site: &SiteInfo, mut pages: PageList) -> Result<PageList> {
+        let removed = pages.dedupe();
+        if removed > 0 {
+            println!("{} Removed {} duplicate pages", style("ℹ").cyan(), removed);
+        }
+        for path in &self.intersect {
+            pages = pages.intersection(&super::site::load_pages(site, path)?);
+        }
+        for path in &self.exclude {
+            pages = pages.difference(&super::site::load_pages(site, path)?);
+        }
+        match self.sort {
+            Some(SortMode::Random { .. }) => pages.sort(SortMode::Random { seed: self.seed }),
+            Some(mode) => pages.sort(mode),
+            None => {}
+        }
+        Ok(pages)
+    }
+
+    fn save(&self, pages: &PageList) -> Result<()> {
+        if let Some(path) = &self.output {
+            pages
+                .save(path)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "{} Saved {} pages to {}",
+                style("✓").green().bold(),
+                pages.len(),
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Which auth profile's connection settings reach the wiki
+#[derive(clap::Args)]
+pub struct ListConnection {
+    /// Config file holding auth profiles (default: ~/.awb-rs/config.toml)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Profile ID whose headers, HTTP auth, user agent and TLS settings are
+    /// used; a missing profile means the defaults
+    #[arg(long, default_value = "default")]
+    auth_profile: String,
+}
+
+impl ListConnection {
+    fn client(&self, wiki: &Url) -> Result<ReqwestMwClient> {
+        let config = TomlConfigStore::new(self.config.clone().unwrap_or_else(default_config_path));
+        super::credentials::stored_profile_client(&config, wiki, &self.auth_profile)
+    }
+}
+
+pub async fn run(
+    wiki: Url,
+    connection: ListConnection,
+    source: ListSource,
+    query: String,
+    limit: usize,
+    condition: Option<String>,
+    options: ListOptions,
+) -> Result<()> {
+    let predicate = match (&source, condition) {
+        (ListSource::TemplateParam, Some(condition)) => Some(
+            condition
+                .parse::<ParamPredicate>()
+                .map_err(|e| anyhow::anyhow!("Invalid --where condition: {}", e))?,
+        ),
+        (ListSource::TemplateParam, None) => {
+            anyhow::bail!("--source template-param needs a --where condition")
+        }
+        (_, Some(_)) => anyhow::bail!("--where only applies to --source template-param"),
+        (_, None) => None,
+    };
+
     println!("{}", style("Fetching page list").bold().cyan());
     println!("Wiki: {}", wiki);
     println!("Source: {:?}", source);
     if !matches!(source, ListSource::Watchlist) {
         println!("Query: {}", query);
     }
+    if let Some(predicate) = &predicate {
+        println!("Where: {}", predicate);
+    }
     println!();
 
+    let client = connection.client(&wiki)?;
+    let http = client.http();
+    // Titles typed by the user are read with the wiki's namespace names
+    let site = if matches!(source, ListSource::File) || options.reads_lists() {
+        super::site::fetch_with(&client).await
+    } else {
+        SiteInfo::default()
+    };
+
+    if let Some(predicate) = predicate {
+        let matches = list_template_param(http, &wiki, &query, &predicate, limit).await?;
+        return options.save(&options.apply(&site, matches)?);
+    }
+
     let titles = match source {
-        ListSource::Category => fetch_category_members(&wiki, &query, limit).await?,
-        ListSource::WhatLinksHere => fetch_what_links_here(&wiki, &query, limit).await?,
-        ListSource::Search => fetch_search_results(&wiki, &query, limit).await?,
-        ListSource::File => fetch_from_file(&query).await?,
-        ListSource::Watchlist => fetch_watchlist_pages(&wiki, limit).await?,
-        ListSource::UserContribs => fetch_user_contribs(&wiki, &query, limit).await?,
+        ListSource::Category => fetch_category_members(http, &wiki, &query, limit).await?,
+        ListSource::WhatLinksHere => fetch_what_links_here(http, &wiki, &query, limit).await?,
+        ListSource::Search => fetch_search_results(http, &wiki, &query, limit).await?,
+        ListSource::File => fetch_from_file(&site, &query).await?,
+        ListSource::Watchlist => fetch_watchlist_pages(http, &wiki, limit).await?,
+        ListSource::UserContribs => fetch_user_contribs(http, &wiki, &query, limit).await?,
+        ListSource::Petscan => fetch_petscan(&query, limit).await?,
+        ListSource::Quarry => fetch_quarry(&query, limit).await?,
+        ListSource::TemplateParam => unreachable!("handled above"),
     };
+    let titles = options.apply(&site, titles.into())?;
 
     println!(
         "{} Found {} pages:",
@@ -45,14 +184,15 @@ pub async fn run(wiki: Url, source: ListSource, query: String, limit: usize) ->
         );
     }
 
-    Ok(())
+    options.save(&titles)
 }
 
-async fn fetch_category_members(api_url: &Url, category: &str, limit: usize) -> Result<Vec<Title>> {
-    let client = reqwest::Client::builder()
-        .user_agent("AWB-RS/0.1.0")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+async fn fetch_category_members(
+    client: &reqwest::Client,
+    api_url: &Url,
+    category: &str,
+    limit: usize,
+) -> Result<Vec<Title>> {
     let category_title = if category.starts_with("Category:") {
         category.to_string()
     } else {
@@ -67,7 +207,7 @@ async fn fetch_category_members(api_url: &Url, category: &str, limit: usize) ->
     ];
 
     let mut titles = fetch_all_pages(
-        &client,
+        client,
         api_url,
         &base_params,
         "categorymembers",
@@ -83,11 +223,12 @@ async fn fetch_category_members(api_url: &Url, category: &str, limit: usize) ->
     Ok(titles)
 }
 
-async fn fetch_what_links_here(api_url: &Url, page: &str, limit: usize) -> Result<Vec<Title>> {
-    let client = reqwest::Client::builder()
-        .user_agent("AWB-RS/0.1.0")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+async fn fetch_what_links_here(
+    client: &reqwest::Client,
+    api_url: &Url,
+    page: &str,
+    limit: usize,
+) -> Result<Vec<Title>> {
     let base_params = [
         ("action", "query"),
         ("list", "backlinks"),
@@ -95,7 +236,7 @@ async fn fetch_what_links_here(api_url: &Url, page: &str, limit: usize) -> Resul
         ("bllimit", "500"),
     ];
 
-    let mut titles = fetch_all_pages(&client, api_url, &base_params, "backlinks", "blcontinue")
+    let mut titles = fetch_all_pages(client, api_url, &base_params, "backlinks", "blcontinue")
         .await
         .context("Failed to fetch backlinks")?;
 
@@ -107,14 +248,11 @@ async fn fetch_what_links_here(api_url: &Url, page: &str, limit: usize) -> Resul
 }
 
 async fn fetch_search_results(
+    client: &reqwest::Client,
     api_url: &Url,
     search_query: &str,
     limit: usize,
 ) -> Result<Vec<Title>> {
-    let client = reqwest::Client::builder()
-        .user_agent("AWB-RS/0.1.0")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
     let base_params = [
         ("action", "query"),
         ("list", "search"),
@@ -122,7 +260,7 @@ async fn fetch_search_results(
         ("srlimit", "500"),
     ];
 
-    let mut titles = fetch_all_pages(&client, api_url, &base_params, "search", "sroffset")
+    let mut titles = fetch_all_pages(client, api_url, &base_params, "search", "sroffset")
         .await
         .context("Failed to fetch search results")?;
 
@@ -133,7 +271,7 @@ async fn fetch_search_results(
     Ok(titles)
 }
 
-async fn fetch_from_file(file_path: &str) -> Result<Vec<Title>> {
+async fn fetch_from_file(site: &SiteInfo, file_path: &str) -> Result<Vec<Title>> {
     // Verify file exists and is a regular file (not a symlink)
     let metadata = tokio::fs::metadata(file_path)
         .await
@@ -153,40 +291,122 @@ async fn fetch_from_file(file_path: &str) -> Result<Vec<Title>> {
         .await
         .context("Failed to read file")?;
 
-    let titles: Vec<Title> = content
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .map(|line| {
-            let trimmed = line.trim();
-            awb_domain::types::Title::new(awb_domain::types::Namespace::MAIN, trimmed)
-        })
-        .collect();
+    Ok(PageList::parse(site, &content)?.into_iter().collect())
+}
+
+async fn fetch_watchlist_pages(
+    client: &reqwest::Client,
+    api_url: &Url,
+    limit: usize,
+) -> Result<Vec<Title>> {
+    let titles = fetch_watchlist(client, api_url, limit as u32)
+        .await
+        .context("Failed to fetch watchlist")?;
 
     Ok(titles)
 }
 
-async fn fetch_watchlist_pages(api_url: &Url, limit: usize) -> Result<Vec<Title>> {
-    let client = reqwest::Client::builder()
-        .user_agent("AWB-RS/0.1.0")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
-
-    let titles = fetch_watchlist(&client, api_url, limit as u32)
+async fn fetch_user_contribs(
+    client: &reqwest::Client,
+    api_url: &Url,
+    username: &str,
+    limit: usize,
+) -> Result<Vec<Title>> {
+    let titles = fetch_user_contributions(client, api_url, username, limit as u32)
         .await
-        .context("Failed to fetch watchlist")?;
+        .context("Failed to fetch user contributions")?;
 
     Ok(titles)
 }
 
-async fn fetch_user_contribs(api_url: &Url, username: &str, limit: usize) -> Result<Vec<Title>> {
+async fn fetch_petscan(psid: &str, limit: usize) -> Result<Vec<Title>> {
     let client = reqwest::Client::builder()
         .user_agent("AWB-RS/0.1.0")
-        .timeout(std::time::Duration::from_secs(30))
+        // PetScan runs the query on demand, which can take a while
+        .timeout(std::time::Duration::from_secs(120))
         .build()?;
 
-    let titles = fetch_user_contributions(&client, api_url, username, limit as u32)
+    let mut titles = query_tools::fetch_petscan(&client, &Url::parse(PETSCAN_URL)?, psid)
         .await
-        .context("Failed to fetch user contributions")?;
+        .context("Failed to fetch PetScan results")?;
+
+    if limit > 0 && titles.len() > limit {
+        titles.truncate(limit);
+    }
+
+    Ok(titles)
+}
+
+async fn fetch_quarry(source: &str, limit: usize) -> Result<Vec<Title>> {
+    let path = Path::new(source);
+    let mut titles = if path.is_file() {
+        // A result set downloaded from Quarry
+        let format = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(QuarryFormat::from_extension)
+            .context("Quarry result file must be .csv, .tsv or .json")?;
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .context("Failed to read file")?;
+        query_tools::parse_quarry(&content, format).context("Failed to parse Quarry result")?
+    } else {
+        let client = reqwest::Client::builder()
+            .user_agent("AWB-RS/0.1.0")
+            .timeout(std::time::Duration::from_secs(30))
+            .build()?;
+        query_tools::fetch_quarry(&client, &Url::parse(QUARRY_URL)?, source)
+            .await
+            .context("Failed to fetch Quarry results")?
+    };
+
+    if limit > 0 && titles.len() > limit {
+        titles.truncate(limit);
+    }
 
     Ok(titles)
 }
+
+/// Print transclusions of `template` whose parameters satisfy `predicate`,
+/// as each batch of pages is fetched and checked
+async fn list_template_param(
+    client: &reqwest::Client,
+    api_url: &Url,
+    template: &str,
+    predicate: &ParamPredicate,
+    limit: usize,
+) -> Result<PageList> {
+    let transclusions = fetch_transclusions(client, api_url, template)
+        .await
+        .context("Failed to fetch transclusions")?;
+    println!(
+        "Checking {} pages that transclude {}...",
+        style(transclusions.len()).yellow().bold(),
+        template
+    );
+    println!();
+
+    let mut found = PageList::new();
+    'batches: for batch in transclusions.chunks(MAX_TITLES_PER_QUERY) {
+        let pages = fetch_page_texts(client, api_url, batch)
+            .await
+            .context("Failed to fetch page text")?;
+        for (title, wikitext) in pages {
+            if predicate.matches_page(&wikitext, template) {
+                println!("  {}. {}", style(found.len() + 1).dim(), title.display);
+                found.push(title);
+                if limit > 0 && found.len() >= limit {
+                    break 'batches;
+                }
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} Found {} matching pages",
+        style("✓").green().bold(),
+        style(found.len()).yellow().bold()
+    );
+    Ok(found)
+}
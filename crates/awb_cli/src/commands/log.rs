@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use awb_telemetry::query_sqlite;
+use console::style;
+use std::path::PathBuf;
+
+pub async fn query(db: PathBuf, sql: String) -> Result<()> {
+    let result = query_sqlite(&db, &sql)
+        .with_context(|| format!("Query against {} failed", db.display()))?;
+
+    let mut widths: Vec<usize> = result.columns.iter().map(|c| c.chars().count()).collect();
+    for row in &result.rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let line = |values: &[String]| {
+        values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", style(line(&result.columns)).bold());
+    for row in &result.rows {
+        println!("{}", line(row));
+    }
+    println!();
+    println!(
+        "{} {} row{}",
+        style("✓").green().bold(),
+        result.rows.len(),
+        if result.rows.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(())
+}
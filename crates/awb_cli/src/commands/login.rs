@@ -1,11 +1,18 @@
+use super::rule_source::default_config_path;
 use anyhow::{Context, Result};
-use awb_mw_api::client::{MediaWikiClient, ReqwestMwClient};
-use awb_security::{CredentialPort, KeyringCredentialStore};
+use awb_domain::profile::ConnectionOptions;
+use awb_mw_api::client::MediaWikiClient;
+use awb_storage::TomlConfigStore;
 use console::style;
 use dialoguer::Password;
 use url::Url;
 
-pub async fn run(wiki: Url, username: String, profile: String) -> Result<()> {
+pub async fn run(
+    wiki: Url,
+    username: String,
+    profile: String,
+    http_user: Option<String>,
+) -> Result<()> {
     println!("{}", style("Login to MediaWiki").bold().cyan());
     println!("Wiki: {}", wiki);
     println!("Username: {}", username);
@@ -18,9 +25,29 @@ pub async fn run(wiki: Url, username: String, profile: String) -> Result<()> {
         .interact()
         .context("Failed to read password")?;
 
+    // A saved profile's headers, user agent and TLS settings apply here too
+    let mut connection = TomlConfigStore::new(default_config_path())
+        .load_profile(&profile)
+        .map(|saved| saved.connection)
+        .unwrap_or_default();
+    if http_user.is_some() {
+        connection.basic_auth_user = http_user;
+    }
+    let http_password = match &connection.basic_auth_user {
+        Some(user) => Some(
+            Password::new()
+                .with_prompt(format!("HTTP password for {}", user))
+                .interact()
+                .context("Failed to read HTTP password")?,
+        ),
+        None => None,
+    };
+
     // Create client and attempt login
-    let client = ReqwestMwClient::new(wiki.clone(), awb_domain::profile::ThrottlePolicy::default())
-        .context("Failed to create HTTP client")?;
+    let client =
+        super::api_trace::client(wiki.clone(), awb_domain::profile::ThrottlePolicy::default())?
+            .with_connection(&connection, http_password.as_deref())
+            .context("Invalid connection settings")?;
 
     print!("Authenticating... ");
     client
@@ -30,11 +57,16 @@ pub async fn run(wiki: Url, username: String, profile: String) -> Result<()> {
 
     println!("{}", style("✓").green().bold());
 
-    // Store credentials in OS keychain
-    let cred_store = KeyringCredentialStore::new();
+    // Store credentials in the configured credential store
+    let (backend, cred_store) = super::credentials::open_store()?;
     cred_store
         .set_password(&profile, &password)
-        .context("Failed to store credentials in keychain")?;
+        .context("Failed to store credentials")?;
+    if let Some(http_password) = &http_password {
+        cred_store
+            .set_password(&ConnectionOptions::basic_auth_key(&profile), http_password)
+            .context("Failed to store HTTP password")?;
+    }
 
     println!();
     println!("{}", style("Login successful!").green().bold());
@@ -43,7 +75,7 @@ pub async fn run(wiki: Url, username: String, profile: String) -> Result<()> {
         style(&profile).yellow()
     );
     println!();
-    println!("Credentials saved to OS keychain (service: awb-rs)");
+    println!("Credentials saved to the {} credential store", backend);
 
     Ok(())
 }
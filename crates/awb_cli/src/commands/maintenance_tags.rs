@@ -0,0 +1,113 @@
+use super::page_edit::{Tally, connect, fetch_page, save_change};
+use anyhow::{Context, Result};
+use awb_domain::types::Namespace;
+use awb_engine::maintenance_tags::{LinkCounts, MaintenanceTag, update_link_tags};
+use awb_mw_api::list_endpoints::fetch_page_links;
+use chrono::Utc;
+use console::style;
+use std::path::PathBuf;
+use url::Url;
+
+/// Links counted each way; any at all is enough to drop a tag
+const LINK_LIMIT: u32 = 10;
+
+/// Arguments for the maintenance-tags command
+pub struct MaintenanceTagsArgs {
+    pub wiki: Url,
+    pub page: Vec<String>,
+    pub pages: Option<PathBuf>,
+    pub tags: Vec<MaintenanceTag>,
+    pub dry_run: bool,
+    pub config: Option<PathBuf>,
+    pub auth_profile: String,
+}
+
+pub async fn run(args: MaintenanceTagsArgs) -> Result<()> {
+    println!("{}", style("AWB-RS Maintenance Tagging").bold().cyan());
+    println!("Wiki: {}", args.wiki);
+    println!(
+        "Tags: {}",
+        args.tags
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if args.dry_run {
+        println!("Mode: {}", style("DRY-RUN").yellow());
+    }
+    println!();
+
+    if args.tags.is_empty() {
+        anyhow::bail!("No tags selected");
+    }
+
+    let client = connect(
+        &args.wiki,
+        args.config.clone(),
+        &args.auth_profile,
+        "maintenance-tags",
+    )
+    .await?;
+    let site = super::site::fetch_with(&client).await;
+
+    let mut titles = Vec::new();
+    if let Some(path) = &args.pages {
+        titles.extend(super::site::load_pages(&site, path)?);
+    }
+    for raw in &args.page {
+        titles.push(super::site::parse_title(&site, raw)?);
+    }
+    if titles.is_empty() {
+        anyhow::bail!("No pages given; use --page or --pages");
+    }
+
+    let today = Utc::now().date_naive();
+    let mut tally = Tally::default();
+    for title in &titles {
+        if title.namespace != Namespace::MAIN {
+            println!("  {} Not an article: {}", style("→").dim(), title.display);
+            tally.skipped += 1;
+            continue;
+        }
+        let links = fetch_page_links(client.http(), &args.wiki, &title.display, LINK_LIMIT)
+            .await
+            .with_context(|| format!("Failed to count links for {}", title.display))?;
+        let links = match links {
+            Some(links) if !links.redirect && !links.disambiguation => links,
+            Some(_) => {
+                println!(
+                    "  {} Redirect or disambiguation page: {}",
+                    style("→").dim(),
+                    title.display
+                );
+                tally.skipped += 1;
+                continue;
+            }
+            None => {
+                println!("  {} Missing: {}", style("✗").red(), title.display);
+                tally.failed += 1;
+                continue;
+            }
+        };
+
+        let Some(page) = fetch_page(&client, title, &mut tally).await else {
+            continue;
+        };
+        let counts = LinkCounts {
+            incoming: links.incoming,
+            outgoing: links.outgoing,
+        };
+        let update = update_link_tags(&page.wikitext, counts, &args.tags, today);
+        let change = if update.is_changed() {
+            let summary = update.summary();
+            Ok(Some((update.text, summary)))
+        } else {
+            Ok(None)
+        };
+        save_change(&client, &page, change, args.dry_run, &mut tally).await;
+    }
+
+    tally.print(args.dry_run);
+    Ok(())
+}
@@ -1,6 +1,30 @@
+pub mod api_trace;
+pub mod bench;
 pub mod bot;
+pub mod botctl;
+pub mod compare_profiles;
+pub mod config;
+pub mod corpus;
+pub mod credentials;
+pub mod dashboard;
 pub mod export;
+pub mod fix_redirects;
+pub mod lint_rules;
 pub mod list;
+pub mod log;
 pub mod login;
+pub mod maintenance_tags;
+pub mod multirun;
 pub mod oauth;
+pub mod page_edit;
+pub mod plugin;
+pub mod replace_file;
+pub mod replay;
+pub mod restore;
+pub mod rule_source;
 pub mod run;
+pub mod sample;
+pub mod schedule;
+pub mod serve;
+pub mod site;
+pub mod validate;
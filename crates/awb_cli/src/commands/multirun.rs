@@ -0,0 +1,232 @@
+use super::rule_source::ProfileArgs;
+use anyhow::{Context, Result};
+use awb_bot::{BotConfig, WikiFarm};
+use awb_domain::types::Watchlist;
+use awb_engine::general_fixes::FixRegistry;
+use awb_engine::transform::TransformEngine;
+use awb_storage::rule_library::content_hash;
+use console::style;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Arguments for the multirun command
+pub struct MultiRunArgs {
+    pub wikis: PathBuf,
+    pub profile: ProfileArgs,
+    pub bot_config: Option<PathBuf>,
+    pub max_edits: Option<u32>,
+    pub dry_run: bool,
+    pub diff_dir: Option<PathBuf>,
+    pub auth_profile: String,
+    pub tags: Vec<String>,
+}
+
+/// The `--wikis` file: one `[[wiki]]` table per wiki
+#[derive(Debug, Deserialize)]
+struct FarmFile {
+    wiki: Vec<FarmTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FarmTarget {
+    /// Wiki API URL
+    api_url: Url,
+    /// Page list file, relative to the `--wikis` file
+    pages: PathBuf,
+    /// Credentials and connection settings for this wiki (default:
+    /// `--auth-profile`)
+    #[serde(default)]
+    auth_profile: Option<String>,
+}
+
+fn load_targets(path: &Path) -> Result<Vec<FarmTarget>> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut file: FarmFile =
+        toml::from_str(&source).with_context(|| format!("Invalid wiki list {}", path.display()))?;
+    if file.wiki.is_empty() {
+        anyhow::bail!("{} lists no wikis", path.display());
+    }
+    let base = path.parent().unwrap_or(Path::new(""));
+    for target in &mut file.wiki {
+        target.pages = base.join(&target.pages);
+    }
+    Ok(file.wiki)
+}
+
+/// Name a wiki by its host, adding the API path when a host appears twice
+fn wiki_name(url: &Url, taken: &HashSet<String>) -> String {
+    let host = url.host_str().unwrap_or("wiki").to_string();
+    if !taken.contains(&host) {
+        return host;
+    }
+    let path = url.path().trim_matches('/').replace('/', "_");
+    format!("{}_{}", host, path)
+}
+
+pub async fn run(args: MultiRunArgs) -> Result<()> {
+    println!("{}", style("AWB-RS Multi-Wiki Run").bold().cyan());
+    println!("Profile: {}", args.profile.profile);
+    println!(
+        "Mode: {}",
+        if args.dry_run {
+            style("DRY-RUN").yellow()
+        } else {
+            style("AUTONOMOUS").green().bold()
+        }
+    );
+    let targets = load_targets(&args.wikis)?;
+    println!("Wikis: {}", targets.len());
+    println!();
+
+    let mut bot_config = match &args.bot_config {
+        Some(path) => {
+            let source = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            BotConfig::from_toml(&source)
+                .with_context(|| format!("Invalid bot config {}", path.display()))?
+        }
+        None => BotConfig::new(),
+    };
+    bot_config.dry_run |= args.dry_run;
+    if let Some(max) = args.max_edits {
+        bot_config = bot_config.with_max_edits(max);
+    }
+    if let Some(dir) = &args.diff_dir {
+        bot_config = bot_config.with_diff_dir(dir.clone());
+    }
+    let config_store = args.profile.config_store();
+    if args.profile.watchlist.is_some() || bot_config.watchlist == Watchlist::Preferences {
+        bot_config =
+            bot_config.with_watchlist(args.profile.watchlist(&config_store, &args.auth_profile));
+    }
+    bot_config.tags.extend(args.tags.iter().cloned());
+
+    // Every wiki is logged in before the first edit, so a bad credential
+    // stops the run before anything is changed
+    let mut connected = Vec::with_capacity(targets.len());
+    let mut names = HashSet::new();
+    for target in targets {
+        let name = wiki_name(&target.api_url, &names);
+        names.insert(name.clone());
+        let auth_profile = target.auth_profile.as_deref().unwrap_or(&args.auth_profile);
+        println!("{} ({})", style(&name).bold(), target.api_url);
+
+        let client = super::page_edit::connect(
+            &target.api_url,
+            Some(args.profile.config_path()),
+            auth_profile,
+            "multirun",
+        )
+        .await
+        .with_context(|| format!("Failed to connect to {}", name))?;
+        let site = super::site::fetch_with(&client).await;
+        let pages = super::site::load_pages(&site, &target.pages)?;
+        println!("  {} pages from {}", pages.len(), target.pages.display());
+        connected.push((name, target.api_url, client, site, pages));
+    }
+
+    // One rule set for every wiki, read through the first one
+    let (_, first_wiki, first_client, first_site, _) = &connected[0];
+    let ruleset = args
+        .profile
+        .load_rules(
+            &config_store,
+            &args.auth_profile,
+            first_wiki,
+            first_client,
+            first_site,
+        )
+        .await?;
+    let rules_sha256 = content_hash(&serde_json::to_string(&ruleset)?);
+    bot_config = bot_config.with_rules_hash(&rules_sha256);
+    if !bot_config.tags.is_empty() {
+        println!("Tags: {}", bot_config.tags.join(", "));
+    }
+    println!();
+
+    let mut farm = WikiFarm::new(bot_config);
+    for (name, api_url, client, site, pages) in connected {
+        // Summaries are chosen per wiki, so each gets its own template
+        let engine = TransformEngine::new(&ruleset, FixRegistry::with_defaults(), HashSet::new())
+            .context("Failed to create transform engine")?
+            .with_summary_template(super::site::summary_template(&ruleset, &api_url, &site));
+        farm.add_wiki(name, client, site, engine, pages);
+    }
+
+    let report = farm.run().await;
+
+    println!();
+    println!("{}", style("═".repeat(60)).dim());
+    println!("{}", report.to_summary());
+    println!("{}", style("═".repeat(60)).dim());
+
+    // One report per wiki, plus the combined one
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    for wiki in &report.wikis {
+        let path = PathBuf::from(format!("bot-report-{}-{}.json", stamp, wiki.wiki));
+        std::fs::write(&path, wiki.report.to_json()?)
+            .with_context(|| format!("Failed to save report {}", path.display()))?;
+        println!("Report for {} saved to: {}", wiki.wiki, path.display());
+    }
+    let path = PathBuf::from(format!("multirun-report-{}.json", stamp));
+    std::fs::write(&path, report.to_json()?).context("Failed to save report")?;
+    println!("Combined report saved to: {}", path.display());
+
+    if !report.is_success() {
+        anyhow::bail!("The run did not finish on every wiki");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_targets_resolves_page_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("farm.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[wiki]]
+api_url = "https://en.wikipedia.org/w/api.php"
+pages = "en.txt"
+
+[[wiki]]
+api_url = "https://de.wikipedia.org/w/api.php"
+pages = "lists/de.txt"
+auth_profile = "dewiki"
+"#,
+        )
+        .unwrap();
+
+        let targets = load_targets(&path).unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].pages, dir.path().join("en.txt"));
+        assert_eq!(targets[0].auth_profile, None);
+        assert_eq!(targets[1].pages, dir.path().join("lists/de.txt"));
+        assert_eq!(targets[1].auth_profile.as_deref(), Some("dewiki"));
+    }
+
+    #[test]
+    fn test_load_targets_rejects_empty_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("farm.toml");
+        std::fs::write(&path, "wiki = []\n").unwrap();
+        assert!(load_targets(&path).is_err());
+    }
+
+    #[test]
+    fn test_wiki_names_are_unique() {
+        let mut taken = HashSet::new();
+        let en = Url::parse("https://en.wikipedia.org/w/api.php").unwrap();
+        assert_eq!(wiki_name(&en, &taken), "en.wikipedia.org");
+        taken.insert("en.wikipedia.org".to_string());
+        let test = Url::parse("https://en.wikipedia.org/test/api.php").unwrap();
+        assert_eq!(wiki_name(&test, &taken), "en.wikipedia.org_test_api.php");
+    }
+}
@@ -1,5 +1,4 @@
 use anyhow::{Context, Result};
-use awb_security::{CredentialPort, KeyringCredentialStore};
 use dialoguer::{Input, Password};
 use secrecy::SecretString;
 use url::Url;
@@ -10,7 +9,7 @@ pub async fn setup(
     access_token: String,
     profile: String,
 ) -> Result<()> {
-    use awb_domain::profile::{AuthMethod, Profile, ThrottlePolicy};
+    use awb_domain::profile::{AuthMethod, ConnectionOptions, Profile, ThrottlePolicy};
 
     // Validate profile name to prevent path traversal
     if !profile
@@ -50,10 +49,11 @@ pub async fn setup(
         auth_method,
         default_namespaces: std::collections::HashSet::new(),
         throttle_policy: ThrottlePolicy::default(),
+        connection: ConnectionOptions::default(),
     };
 
-    // Store OAuth credentials in OS keychain
-    let store = KeyringCredentialStore::new();
+    // Store OAuth credentials in the configured credential store
+    let (_, store) = super::credentials::open_store()?;
     let token_json = serde_json::json!({
         "consumer_key": consumer_key,
         "consumer_secret": consumer_secret,
@@ -64,7 +64,7 @@ pub async fn setup(
 
     store
         .set_oauth_token(&profile, &token_json)
-        .context("Failed to store OAuth credentials in keychain")?;
+        .context("Failed to store OAuth credentials")?;
 
     // Save profile
     let profile_path = format!(".awb/profiles/{}.toml", profile);
@@ -147,15 +147,15 @@ pub async fn authorize(wiki: Url, client_id: String, profile: String) -> Result<
         .await
         .context("Failed to exchange authorization code")?;
 
-    // Store tokens in OS keychain
-    let store = KeyringCredentialStore::new();
+    // Store tokens in the configured credential store
+    let (_, store) = super::credentials::open_store()?;
     let token_json = serde_json::to_string(&token).context("Failed to serialize token")?;
     store
         .set_oauth_token(&profile, &token_json)
-        .context("Failed to store OAuth token in keychain")?;
+        .context("Failed to store OAuth token")?;
 
     // Create and save profile
-    use awb_domain::profile::{AuthMethod, Profile, ThrottlePolicy};
+    use awb_domain::profile::{AuthMethod, ConnectionOptions, Profile, ThrottlePolicy};
     let auth_method = AuthMethod::OAuth2 {
         client_id,
         client_secret: SecretString::new(client_secret.into()),
@@ -171,6 +171,7 @@ pub async fn authorize(wiki: Url, client_id: String, profile: String) -> Result<
         auth_method,
         default_namespaces: std::collections::HashSet::new(),
         throttle_policy: ThrottlePolicy::default(),
+        connection: ConnectionOptions::default(),
     };
 
     let profile_path = format!(".awb/profiles/{}.toml", profile);
@@ -0,0 +1,167 @@
+//! Login, fetch and save steps shared by the built-in maintenance commands
+//! (fix-redirects, maintenance-tags).
+
+use super::rule_source::default_config_path;
+use anyhow::{Context, Result};
+use awb_domain::types::{PageContent, Title, Watchlist};
+use awb_engine::transform::is_null_edit;
+use awb_mw_api::client::{EditRequest, MediaWikiClient, ReqwestMwClient};
+use awb_storage::TomlConfigStore;
+use chrono::Utc;
+use console::style;
+use std::path::PathBuf;
+use url::Url;
+
+/// Pages changed, left alone and failed
+#[derive(Default)]
+pub struct Tally {
+    pub saved: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl Tally {
+    pub fn print(&self, dry_run: bool) {
+        println!();
+        println!(
+            "{} {} {}, {} unchanged, {} failed",
+            style("✓").green().bold(),
+            self.saved,
+            if dry_run { "would change" } else { "saved" },
+            self.skipped,
+            self.failed
+        );
+    }
+}
+
+/// Log in with the bot password stored for `auth_profile` and fetch a CSRF
+/// token. `command` names the caller in error messages.
+pub async fn connect(
+    wiki: &Url,
+    config: Option<PathBuf>,
+    auth_profile: &str,
+    command: &str,
+) -> Result<ReqwestMwClient> {
+    let config_store = TomlConfigStore::new(config.unwrap_or_else(default_config_path));
+    let auth = config_store
+        .load_profile(auth_profile)
+        .context("Failed to load profile. Create one first or use a different auth-profile.")?;
+    let (_, cred_store) = super::credentials::open_store()?;
+    let password = cred_store
+        .get_password(auth_profile)
+        .context("No stored credentials found. Run 'login' command first.")?;
+
+    let client =
+        super::credentials::profile_client(wiki, auth_profile, &auth, cred_store.as_ref())?;
+
+    print!("Logging in... ");
+    let username = match &auth.auth_method {
+        awb_domain::profile::AuthMethod::BotPassword { username } => username.clone(),
+        _ => anyhow::bail!("Only bot passwords are supported for {}", command),
+    };
+    client
+        .login_bot_password(&username, &password)
+        .await
+        .context("Login failed")?;
+    client
+        .fetch_csrf_token()
+        .await
+        .context("Failed to fetch CSRF token")?;
+    println!("{}", style("✓").green().bold());
+    Ok(client)
+}
+
+pub async fn fetch_page(
+    client: &ReqwestMwClient,
+    title: &Title,
+    tally: &mut Tally,
+) -> Option<PageContent> {
+    match client.get_page(title).await {
+        Ok(page) => Some(page),
+        Err(e) => {
+            println!(
+                "  {} Failed to fetch {}: {}",
+                style("✗").red(),
+                title.display,
+                e
+            );
+            tally.failed += 1;
+            None
+        }
+    }
+}
+
+/// Save the new text and summary computed for `page` (or report it in
+/// dry-run mode). Text that would be a null edit is counted as unchanged
+/// and not sent. Errors are reported and the page is left alone.
+pub async fn save_change(
+    client: &ReqwestMwClient,
+    page: &PageContent,
+    change: std::result::Result<Option<(String, String)>, String>,
+    dry_run: bool,
+    tally: &mut Tally,
+) {
+    let title = &page.title;
+    let change = change.map(|c| c.filter(|(text, _)| !is_null_edit(&page.wikitext, text)));
+    let (text, summary) = match change {
+        Ok(Some(change)) => change,
+        Ok(None) => {
+            println!(
+                "  {} No changes needed: {}",
+                style("→").dim(),
+                title.display
+            );
+            tally.skipped += 1;
+            return;
+        }
+        Err(reason) => {
+            println!("  {} {}: {}", style("✗").red(), title.display, reason);
+            tally.failed += 1;
+            return;
+        }
+    };
+
+    if dry_run {
+        println!(
+            "  {} Would save {} ({})",
+            style("ℹ").cyan(),
+            title.display,
+            summary
+        );
+        tally.saved += 1;
+        return;
+    }
+
+    let edit = EditRequest {
+        title: title.clone(),
+        text,
+        summary,
+        minor: true,
+        bot: true,
+        base_timestamp: page.timestamp.to_rfc3339(),
+        start_timestamp: Utc::now().to_rfc3339(),
+        section: None,
+        tags: Vec::new(),
+        watchlist: Watchlist::default(),
+    };
+    match client.edit_page(&edit).await {
+        Ok(response) => {
+            println!(
+                "  {} Saved: {} (rev {})",
+                style("✓").green().bold(),
+                title.display,
+                response.new_revid.unwrap_or(0)
+            );
+            tally.saved += 1;
+        }
+        Err(e) => {
+            println!(
+                "  {} Failed to save {}: {}",
+                style("✗").red(),
+                title.display,
+                e
+            );
+            tally.failed += 1;
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use anyhow::{Context, Result};
+use awb_plugins::registry::default_plugin_dir;
+use awb_plugins::signing::{PublicKey, default_trust_store_path};
+use awb_plugins::{
+    BundleDescriptor, InstalledPlugin, PluginBundle, PluginFixModule, PluginManager,
+    PluginTestSuite, SandboxConfig, TrustStore,
+};
+use console::style;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+pub async fn test(path: PathBuf) -> Result<()> {
+    println!("{}", style("Plugin Test").bold().cyan());
+    println!("Path: {}", path.display());
+    println!();
+
+    let suite = PluginTestSuite::discover(&path).context("Failed to load plugin")?;
+    if suite.cases.is_empty() {
+        anyhow::bail!(
+            "No test cases found. Add [[tests]] to the plugin manifest or \
+             tests/<case>.input.wiki files next to the plugin."
+        );
+    }
+
+    let report = suite.run();
+    for case in &report.cases {
+        let mark = if case.passed() {
+            style("✓").green().bold()
+        } else {
+            style("✗").red().bold()
+        };
+        println!(
+            "  {} {} {}",
+            mark,
+            case.name,
+            style(format!("({:.1?})", case.elapsed)).dim()
+        );
+        if let Some(error) = &case.error {
+            println!("      {} {}", style("error:").red(), error);
+        }
+        if let Some(diff) = &case.diff {
+            println!("      {}", style("output differs from expected:").red());
+            for line in diff.lines() {
+                let line = match line.chars().next() {
+                    Some('+') => style(line).green(),
+                    Some('-') => style(line).red(),
+                    _ => style(line).dim(),
+                };
+                println!("      {}", line);
+            }
+        }
+        if case.error.is_none() && !case.idempotent {
+            println!(
+                "      {} running the plugin again changed its output",
+                style("not idempotent:").red()
+            );
+        }
+    }
+
+    println!();
+    let failed = report.failed_count();
+    println!(
+        "{}: {} passed, {} failed in {:.1?}",
+        report.plugin,
+        report.cases.len() - failed,
+        failed,
+        report.total_elapsed()
+    );
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} plugin tests failed", failed, report.cases.len());
+    }
+    Ok(())
+}
+
+pub async fn install(
+    bundle: String,
+    registry: Option<Url>,
+    trust_store: Option<PathBuf>,
+    dir: Option<PathBuf>,
+) -> Result<()> {
+    let dir = dir.unwrap_or_else(default_plugin_dir);
+    let store = TrustStore::load(trust_store.unwrap_or_else(default_trust_store_path))?;
+    let origin = bundle_origin(&bundle, registry.as_ref())?;
+    let client = http_client()?;
+    let bundle = fetch_bundle(&client, &origin).await?;
+
+    let name = &bundle.descriptor.name;
+    if PluginManager::installed_plugins(&dir)?
+        .iter()
+        .any(|p| &p.name == name)
+    {
+        anyhow::bail!(
+            "{} is already installed; run `awb-rs plugin update {}` to replace it",
+            name,
+            name
+        );
+    }
+
+    let installed = PluginManager::new()
+        .with_trust_store(store)
+        .install_bundle(&dir, &bundle)
+        .with_context(|| format!("Failed to install {}", name))?;
+    print_installed("Installed", &installed, &dir);
+    warn_unsigned(&installed);
+    Ok(())
+}
+
+pub fn list(dir: Option<PathBuf>) -> Result<()> {
+    let dir = dir.unwrap_or_else(default_plugin_dir);
+    let installed = PluginManager::installed_plugins(&dir)?;
+    if installed.is_empty() {
+        println!("No plugins installed from bundles in {}", dir.display());
+        return Ok(());
+    }
+    for plugin in installed {
+        println!(
+            "{} {} {}",
+            style(&plugin.name).bold(),
+            plugin.version.as_deref().unwrap_or("-"),
+            style(format!(
+                "sha256:{}",
+                &plugin.sha256[..12.min(plugin.sha256.len())]
+            ))
+            .dim()
+        );
+        println!("    from {}", plugin.origin);
+        println!(
+            "    installed {}{}",
+            plugin.installed_at.format("%Y-%m-%d %H:%M UTC"),
+            plugin
+                .signed_by
+                .as_deref()
+                .map(|name| format!(", signed by {}", name))
+                .unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+pub fn remove(name: String, dir: Option<PathBuf>) -> Result<()> {
+    let dir = dir.unwrap_or_else(default_plugin_dir);
+    PluginManager::new()
+        .uninstall(&dir, &name)
+        .with_context(|| format!("Failed to remove {}", name))?;
+    println!("{} Removed {}", style("✓").green(), name);
+    Ok(())
+}
+
+pub async fn update(
+    name: Option<String>,
+    trust_store: Option<PathBuf>,
+    dir: Option<PathBuf>,
+) -> Result<()> {
+    let dir = dir.unwrap_or_else(default_plugin_dir);
+    let store = TrustStore::load(trust_store.unwrap_or_else(default_trust_store_path))?;
+    let mut installed = PluginManager::installed_plugins(&dir)?;
+    if let Some(name) = &name {
+        installed.retain(|p| &p.name == name);
+        if installed.is_empty() {
+            anyhow::bail!("{} was not installed from a bundle", name);
+        }
+    }
+
+    let client = http_client()?;
+    let mut manager = PluginManager::new().with_trust_store(store);
+    for current in installed {
+        let bundle = fetch_bundle(&client, &current.origin).await?;
+        if bundle.descriptor.name != current.name {
+            anyhow::bail!(
+                "{} now describes {} instead of {}",
+                current.origin,
+                bundle.descriptor.name,
+                current.name
+            );
+        }
+        bundle
+            .verify()
+            .with_context(|| format!("Failed to update {}", current.name))?;
+        if current.signed_by.is_some() && bundle.signature.is_none() {
+            anyhow::bail!(
+                "{} was signed but {} no longer is",
+                current.name,
+                current.origin
+            );
+        }
+        let candidate = InstalledPlugin::from_bundle(&bundle, None);
+        if candidate.sha256 == current.sha256
+            && candidate.manifest_sha256 == current.manifest_sha256
+        {
+            println!("{} is up to date", current.name);
+            continue;
+        }
+        let updated = manager
+            .install_bundle(&dir, &bundle)
+            .with_context(|| format!("Failed to update {}", current.name))?;
+        print_installed("Updated", &updated, &dir);
+        warn_unsigned(&updated);
+    }
+    Ok(())
+}
+
+pub fn verify(path: PathBuf, trust_store: Option<PathBuf>) -> Result<()> {
+    let store = TrustStore::load(trust_store.unwrap_or_else(default_trust_store_path))?;
+    let signer = store.verify_file(&path)?;
+    println!(
+        "{} {} is signed by {}",
+        style("✓").green(),
+        path.display(),
+        signer.name
+    );
+    Ok(())
+}
+
+pub fn trust_add(key: String, name: String, trust_store: Option<PathBuf>) -> Result<()> {
+    let text = if Path::new(&key).is_file() {
+        std::fs::read_to_string(&key).with_context(|| format!("Failed to read {}", key))?
+    } else {
+        key
+    };
+    let key = PublicKey::parse(&text)?;
+    let mut store = TrustStore::load(trust_store.unwrap_or_else(default_trust_store_path))?;
+    store.add(&name, &key)?;
+    store.save()?;
+    println!(
+        "{} Trusting key {} ({}) in {}",
+        style("✓").green(),
+        key.key_id(),
+        name,
+        store.path().display()
+    );
+    Ok(())
+}
+
+pub fn trust_list(trust_store: Option<PathBuf>) -> Result<()> {
+    let store = TrustStore::load(trust_store.unwrap_or_else(default_trust_store_path))?;
+    if store.keys().is_empty() {
+        println!("No trusted keys in {}", store.path().display());
+    }
+    for trusted in store.keys() {
+        println!("{} {}", trusted.key()?.key_id(), trusted.name);
+    }
+    Ok(())
+}
+
+pub fn trust_remove(key: String, trust_store: Option<PathBuf>) -> Result<()> {
+    let mut store = TrustStore::load(trust_store.unwrap_or_else(default_trust_store_path))?;
+    let removed = store
+        .remove(&key)
+        .with_context(|| format!("No trusted key with ID or name '{}'", key))?;
+    store.save()?;
+    println!("{} No longer trusting {}", style("✓").green(), removed.name);
+    Ok(())
+}
+
+/// Load the plugins in `dir` for a `run` or `bot` session. Every plugin must be signed by
+/// a key in the trust store at `trust_store`; the run does not start if any
+/// is refused.
+pub fn load_signed(dir: &Path, trust_store: &Path) -> Result<PluginFixModule> {
+    let config = SandboxConfig {
+        require_signed_plugins: true,
+        ..SandboxConfig::default()
+    };
+    let mut manager =
+        PluginManager::with_config(config).with_trust_store(TrustStore::load(trust_store)?);
+    let loaded = manager
+        .load_from_directory(dir)
+        .with_context(|| format!("Failed to load plugins from {}", dir.display()))?;
+    let found = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            matches!(
+                entry.path().extension().and_then(|e| e.to_str()),
+                Some("lua" | "wasm")
+            )
+        })
+        .count();
+    if loaded < found {
+        anyhow::bail!(
+            "{} of {} plugins in {} were refused or failed to load; \
+             editing runs need plugins signed by a trusted key (see `awb-rs plugin verify`)",
+            found - loaded,
+            found,
+            dir.display()
+        );
+    }
+    println!("Plugins: {} signed from {}", loaded, dir.display());
+    Ok(PluginFixModule::new(manager))
+}
+
+fn print_installed(action: &str, plugin: &InstalledPlugin, dir: &Path) {
+    println!(
+        "{} {} {}{} into {}",
+        style("✓").green(),
+        action,
+        plugin.name,
+        plugin
+            .version
+            .as_deref()
+            .map(|v| format!(" {}", v))
+            .unwrap_or_default(),
+        dir.display()
+    );
+    println!("  sha256:{}", plugin.sha256);
+}
+
+fn warn_unsigned(plugin: &InstalledPlugin) {
+    if plugin.signed_by.is_none() {
+        println!(
+            "{} {} is not signed by a trusted key; `run` and `bot` will refuse to load it",
+            style("⚠").yellow(),
+            plugin.name
+        );
+    }
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent("AWB-RS/0.1.0")
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?)
+}
+
+/// Where to fetch the descriptor for `bundle`: a URL, a local path, or a
+/// name looked up in `registry`
+fn bundle_origin(bundle: &str, registry: Option<&Url>) -> Result<String> {
+    if Url::parse(bundle).is_ok_and(|url| is_remote(&url)) {
+        return Ok(bundle.to_string());
+    }
+    let path = Path::new(bundle);
+    if path.is_file() {
+        return Ok(std::path::absolute(path)?.display().to_string());
+    }
+    if bundle.contains(['/', '\\']) {
+        anyhow::bail!("Bundle descriptor {} does not exist", bundle);
+    }
+    let Some(registry) = registry else {
+        anyhow::bail!(
+            "{} is neither a URL nor a file; pass --registry to look it up by name",
+            bundle
+        );
+    };
+    let mut base = registry.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    Ok(base.join(&format!("{}.json", bundle))?.to_string())
+}
+
+fn is_remote(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+}
+
+/// Resolve a file named in a descriptor against the descriptor's location
+fn resolve(origin: &str, reference: &str) -> Result<String> {
+    if Url::parse(reference).is_ok_and(|url| is_remote(&url)) {
+        return Ok(reference.to_string());
+    }
+    match Url::parse(origin) {
+        Ok(base) if is_remote(&base) => Ok(base.join(reference)?.to_string()),
+        _ => Ok(Path::new(origin)
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(reference)
+            .display()
+            .to_string()),
+    }
+}
+
+async fn read_location(client: &reqwest::Client, location: &str) -> Result<Vec<u8>> {
+    match Url::parse(location) {
+        Ok(url) if is_remote(&url) => {
+            let response = client
+                .get(url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .with_context(|| format!("Failed to download {}", location))?;
+            Ok(response.bytes().await?.to_vec())
+        }
+        _ => std::fs::read(location).with_context(|| format!("Failed to read {}", location)),
+    }
+}
+
+/// Download a bundle's descriptor and files; they are verified on install
+async fn fetch_bundle(client: &reqwest::Client, origin: &str) -> Result<PluginBundle> {
+    let descriptor = read_location(client, origin).await?;
+    let descriptor = BundleDescriptor::from_json(&String::from_utf8_lossy(&descriptor))
+        .with_context(|| format!("Failed to read bundle descriptor {}", origin))?;
+    let source = read_location(client, &resolve(origin, &descriptor.source)?).await?;
+    let manifest = match &descriptor.manifest {
+        Some(manifest) => {
+            let bytes = read_location(client, &resolve(origin, manifest)?).await?;
+            Some(String::from_utf8(bytes).context("Plugin manifest is not UTF-8")?)
+        }
+        None => None,
+    };
+    let signature = read_signature(client, origin, descriptor.signature.as_deref()).await?;
+    let manifest_signature =
+        read_signature(client, origin, descriptor.manifest_signature.as_deref()).await?;
+    Ok(PluginBundle {
+        descriptor,
+        origin: origin.to_string(),
+        source,
+        manifest,
+        signature,
+        manifest_signature,
+    })
+}
+
+async fn read_signature(
+    client: &reqwest::Client,
+    origin: &str,
+    location: Option<&str>,
+) -> Result<Option<String>> {
+    let Some(location) = location else {
+        return Ok(None);
+    };
+    let bytes = read_location(client, &resolve(origin, location)?).await?;
+    Ok(Some(
+        String::from_utf8(bytes).context("Plugin signature is not UTF-8")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_storage::rule_library::content_hash;
+
+    #[test]
+    fn test_bundle_origin() {
+        let registry = Url::parse("https://plugins.example.org/awb").unwrap();
+        assert_eq!(
+            bundle_origin("dates", Some(&registry)).unwrap(),
+            "https://plugins.example.org/awb/dates.json"
+        );
+        assert_eq!(
+            bundle_origin("https://example.org/b.json", None).unwrap(),
+            "https://example.org/b.json"
+        );
+        assert!(bundle_origin("dates", None).is_err());
+        assert!(bundle_origin("missing/dates.json", Some(&registry)).is_err());
+    }
+
+    #[test]
+    fn test_resolve_relative_to_descriptor() {
+        assert_eq!(
+            resolve("https://example.org/p/dates.json", "dates.lua").unwrap(),
+            "https://example.org/p/dates.lua"
+        );
+        assert_eq!(
+            resolve("/srv/p/dates.json", "https://cdn.example.org/dates.lua").unwrap(),
+            "https://cdn.example.org/dates.lua"
+        );
+        assert_eq!(
+            resolve("/srv/p/dates.json", "dates.lua").unwrap(),
+            "/srv/p/dates.lua"
+        );
+    }
+
+    #[test]
+    fn test_load_signed_refuses_unsigned_plugins() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = dir.path().join("keys.toml");
+        assert!(load_signed(dir.path(), &store).is_ok());
+
+        std::fs::write(
+            dir.path().join("noop.lua"),
+            "function transform(text) return text end",
+        )
+        .unwrap();
+        let Err(err) = load_signed(dir.path(), &store) else {
+            panic!("unsigned plugin was loaded");
+        };
+        assert!(err.to_string().contains("1 of 1 plugins"), "{}", err);
+    }
+
+    #[test]
+    fn test_trust_add_list_remove() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = dir.path().join("keys.toml");
+        let key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+        trust_add(key.to_string(), "minisign".to_string(), Some(store.clone())).unwrap();
+        assert!(trust_add(key.to_string(), "again".to_string(), Some(store.clone())).is_err());
+        trust_list(Some(store.clone())).unwrap();
+        assert_eq!(TrustStore::load(&store).unwrap().keys().len(), 1);
+
+        trust_remove("minisign".to_string(), Some(store.clone())).unwrap();
+        assert!(TrustStore::load(&store).unwrap().keys().is_empty());
+        assert!(trust_remove("minisign".to_string(), Some(store)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_install_list_update_remove_from_local_bundle() {
+        let publish = tempfile::TempDir::new().unwrap();
+        let plugins = tempfile::TempDir::new().unwrap();
+        let script = "function transform(text) return text end";
+        std::fs::write(publish.path().join("noop.lua"), script).unwrap();
+        let descriptor = publish.path().join("noop.json");
+        std::fs::write(
+            &descriptor,
+            format!(
+                r#"{{"name": "noop.lua", "version": "1", "source": "noop.lua", "sha256": "{}"}}"#,
+                content_hash(script)
+            ),
+        )
+        .unwrap();
+
+        let dir = Some(plugins.path().to_path_buf());
+        let store = Some(publish.path().join("keys.toml"));
+        let bundle = descriptor.display().to_string();
+        install(bundle.clone(), None, store.clone(), dir.clone())
+            .await
+            .unwrap();
+        assert!(plugins.path().join("noop.lua").is_file());
+        assert!(
+            install(bundle, None, store.clone(), dir.clone())
+                .await
+                .is_err()
+        );
+        list(dir.clone()).unwrap();
+
+        update(None, store.clone(), dir.clone()).await.unwrap();
+        let script = "function transform(text) return text .. '!' end";
+        std::fs::write(publish.path().join("noop.lua"), script).unwrap();
+        // The descriptor still has the old hash, so the update is refused
+        assert!(update(None, store.clone(), dir.clone()).await.is_err());
+
+        remove("noop.lua".to_string(), dir.clone()).unwrap();
+        assert!(!plugins.path().join("noop.lua").exists());
+    }
+}
@@ -0,0 +1,79 @@
+use super::page_edit::{Tally, connect, fetch_page, save_change};
+use anyhow::{Context, Result};
+use awb_engine::file_replace::FileReplacement;
+use awb_mw_api::list_endpoints::fetch_file_usage;
+use console::style;
+use std::path::PathBuf;
+use url::Url;
+
+/// Arguments for the replace-file command
+pub struct ReplaceFileArgs {
+    pub wiki: Url,
+    pub old: String,
+    pub new: String,
+    pub page: Vec<String>,
+    pub pages: Option<PathBuf>,
+    pub dry_run: bool,
+    pub config: Option<PathBuf>,
+    pub auth_profile: String,
+}
+
+pub async fn run(args: ReplaceFileArgs) -> Result<()> {
+    let replacement = FileReplacement::new(&args.old, &args.new)?;
+
+    println!("{}", style("AWB-RS File Replacement").bold().cyan());
+    println!("Wiki: {}", args.wiki);
+    println!("File: {} → {}", replacement.old(), replacement.new_name());
+    if args.dry_run {
+        println!("Mode: {}", style("DRY-RUN").yellow());
+    }
+    println!();
+
+    let client = connect(
+        &args.wiki,
+        args.config.clone(),
+        &args.auth_profile,
+        "replace-file",
+    )
+    .await?;
+    let site = super::site::fetch_with(&client).await;
+
+    let mut titles = Vec::new();
+    if let Some(path) = &args.pages {
+        titles.extend(super::site::load_pages(&site, path)?);
+    }
+    for raw in &args.page {
+        titles.push(super::site::parse_title(&site, raw)?);
+    }
+    if titles.is_empty() {
+        titles = fetch_file_usage(client.http(), &args.wiki, replacement.old())
+            .await
+            .context("Failed to fetch file usage")?;
+        println!(
+            "{} Found {} pages using {}",
+            style("ℹ").cyan(),
+            titles.len(),
+            replacement.old()
+        );
+    }
+
+    let summary = format!(
+        "Replacing [[:File:{}]] with [[:File:{}]]",
+        replacement.old(),
+        replacement.new_name()
+    );
+    let mut tally = Tally::default();
+    for title in &titles {
+        let Some(page) = fetch_page(&client, title, &mut tally).await else {
+            continue;
+        };
+        let change = match replacement.apply(&page.wikitext) {
+            (_, 0) => Ok(None),
+            (text, _) => Ok(Some((text, summary.clone()))),
+        };
+        save_change(&client, &page, change, args.dry_run, &mut tally).await;
+    }
+
+    tally.print(args.dry_run);
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use awb_domain::types::*;
+use awb_domain::warnings::Warning;
+use awb_engine::transform::TransformEngine;
+use awb_mw_api::list_endpoints::{PageRevision, fetch_revisions};
+use console::style;
+use std::path::PathBuf;
+use url::Url;
+
+/// How the profile behaved on one past revision
+#[derive(Debug, Clone)]
+pub struct RevisionResult {
+    pub revision: RevisionId,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Rules (by ID) and fixes that changed the revision's text
+    pub rules_applied: Vec<String>,
+    pub fixes_applied: Vec<String>,
+    /// Whether running the profile again over its output changes nothing
+    pub stable: bool,
+}
+
+impl RevisionResult {
+    pub fn changed(&self) -> bool {
+        !self.rules_applied.is_empty() || !self.fixes_applied.is_empty()
+    }
+}
+
+pub async fn run(
+    wiki: Url,
+    page: String,
+    revisions: usize,
+    profile: PathBuf,
+    auth_profile: String,
+) -> Result<()> {
+    println!("{}", style("AWB-RS Replay").bold().cyan());
+    println!("Wiki: {}", wiki);
+    println!("Profile: {}", profile.display());
+    println!();
+
+    let engine = super::corpus::load_engine(&profile, &auth_profile, wiki.host_str())?;
+    let store = awb_storage::TomlConfigStore::new(&profile);
+    let rules = store
+        .load_rules(&auth_profile)
+        .context("Failed to load rules from profile")?;
+    let client = super::credentials::stored_profile_client(&store, &wiki, &auth_profile)?;
+    let site = super::site::fetch_with(&client).await;
+    let title = super::site::parse_title(&site, &page)?;
+
+    let history = fetch_revisions(client.http(), &wiki, &title.display, revisions)
+        .await
+        .with_context(|| format!("Failed to fetch revisions of {}", title.display))?
+        .with_context(|| format!("Page does not exist: {}", title.display))?;
+    if history.is_empty() {
+        anyhow::bail!("No revisions with visible text for {}", title.display);
+    }
+
+    let results = replay_revisions(&engine, &title, &history);
+    // Fix IDs are shown as they are
+    let label = |id: &str| {
+        rules
+            .rules
+            .iter()
+            .find(|rule| rule.id.to_string() == id)
+            .map(super::lint_rules::describe)
+            .unwrap_or_else(|| id.to_string())
+    };
+    for result in &results {
+        let mark = if !result.stable {
+            style("✗").red().bold()
+        } else if result.changed() {
+            style("✎").yellow()
+        } else {
+            style("✓").green()
+        };
+        let fired: Vec<String> = result
+            .rules_applied
+            .iter()
+            .chain(&result.fixes_applied)
+            .map(|id| label(id))
+            .collect();
+        println!(
+            "  {} r{} {} {}{}",
+            mark,
+            result.revision.0,
+            result.timestamp.format("%Y-%m-%d %H:%M"),
+            if fired.is_empty() {
+                "unchanged".to_string()
+            } else {
+                fired.join(", ")
+            },
+            if result.stable {
+                ""
+            } else {
+                " (not stable: a second run changes it again)"
+            }
+        );
+    }
+
+    println!();
+    let changed = results.iter().filter(|r| r.changed()).count();
+    let unstable = results.iter().filter(|r| !r.stable).count();
+    println!(
+        "{} revisions: {} changed, {} not stable",
+        results.len(),
+        changed,
+        unstable
+    );
+    // Rules that only fire on some revisions react to how the page was
+    // written at the time rather than to what it says now
+    let newest = &results[0];
+    for (id, count) in firing_counts(&results) {
+        if !newest.rules_applied.contains(&id) && !newest.fixes_applied.contains(&id) {
+            println!(
+                "  {} {} changes {} older revision(s) but not the current one",
+                style("→").dim(),
+                label(&id),
+                count
+            );
+        }
+    }
+
+    if unstable > 0 {
+        anyhow::bail!(
+            "Output is not stable on {} of {} revisions",
+            unstable,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+/// Run the engine over each revision of `title`, as the page looked then
+pub fn replay_revisions(
+    engine: &TransformEngine,
+    title: &Title,
+    revisions: &[PageRevision],
+) -> Vec<RevisionResult> {
+    revisions
+        .iter()
+        .map(|rev| {
+            let page = PageContent {
+                page_id: PageId(0),
+                title: title.clone(),
+                revision: rev.revision,
+                timestamp: rev.timestamp,
+                wikitext: rev.wikitext.clone(),
+                size_bytes: rev.wikitext.len() as u64,
+                is_redirect: false,
+                protection: ProtectionInfo::default(),
+                properties: PageProperties::default(),
+            };
+            let plan = engine.verify_idempotent(&page);
+            RevisionResult {
+                revision: rev.revision,
+                timestamp: rev.timestamp,
+                rules_applied: plan.rules_applied.iter().map(|id| id.to_string()).collect(),
+                fixes_applied: plan.fixes_applied,
+                stable: !plan
+                    .warnings
+                    .iter()
+                    .any(|w| matches!(w, Warning::NotIdempotent { .. })),
+            }
+        })
+        .collect()
+}
+
+/// How many revisions each rule (by ID) and fix changed, in first-seen order
+pub fn firing_counts(results: &[RevisionResult]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for result in results {
+        for id in result.rules_applied.iter().chain(&result.fixes_applied) {
+            match counts.iter_mut().find(|(seen, _)| seen == id) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((id.clone(), 1)),
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::rules::{Rule, RuleSet};
+    use awb_engine::general_fixes::FixRegistry;
+    use std::collections::HashSet;
+
+    fn revision(id: u64, wikitext: &str) -> PageRevision {
+        PageRevision {
+            revision: RevisionId(id),
+            timestamp: chrono::Utc::now(),
+            wikitext: wikitext.to_string(),
+        }
+    }
+
+    fn engine(rules: Vec<Rule>) -> TransformEngine {
+        let mut set = RuleSet::new();
+        for rule in rules {
+            set.add(rule);
+        }
+        TransformEngine::new(&set, FixRegistry::with_defaults(), HashSet::new()).unwrap()
+    }
+
+    #[test]
+    fn test_rule_firing_only_on_old_revisions() {
+        let typo = Rule::new_plain("teh", "the", true);
+        let typo_id = typo.id.to_string();
+        let history = [
+            revision(3, "the end"),
+            revision(2, "teh end"),
+            revision(1, "teh"),
+        ];
+        let title = Title::new(Namespace::MAIN, "Foo");
+        let results = replay_revisions(&engine(vec![typo]), &title, &history);
+
+        assert!(!results[0].changed());
+        assert!(results[1].changed());
+        assert!(results.iter().all(|r| r.stable));
+        assert_eq!(firing_counts(&results), vec![(typo_id, 2)]);
+    }
+
+    #[test]
+    fn test_unstable_output_detected() {
+        let history = [revision(2, "x"), revision(1, "y")];
+        let title = Title::new(Namespace::MAIN, "Foo");
+        let results = replay_revisions(
+            &engine(vec![Rule::new_plain("x", "xx", true)]),
+            &title,
+            &history,
+        );
+        assert!(!results[0].stable);
+        assert!(results[1].stable);
+    }
+}
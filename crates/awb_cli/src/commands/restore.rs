@@ -0,0 +1,92 @@
+use super::page_edit::{Tally, connect, fetch_page, save_change};
+use anyhow::{Context, Result};
+use awb_bot::SnapshotIndex;
+use console::style;
+use std::path::PathBuf;
+use url::Url;
+
+/// Arguments for the restore command
+pub struct RestoreArgs {
+    pub wiki: Url,
+    pub run: PathBuf,
+    pub pages: Vec<String>,
+    pub force: bool,
+    pub dry_run: bool,
+    pub config: Option<PathBuf>,
+    pub auth_profile: String,
+}
+
+/// Save the text a bot run's snapshots hold for each page from before the
+/// run. A page edited since the run's last edit of it is left alone unless
+/// `force` is set.
+pub async fn run(args: RestoreArgs) -> Result<()> {
+    println!("{}", style("AWB-RS Restore").bold().cyan());
+    println!("Wiki: {}", args.wiki);
+    println!("Run: {}", args.run.display());
+    if args.dry_run {
+        println!("Mode: {}", style("DRY-RUN").yellow());
+    }
+    println!();
+
+    let index = SnapshotIndex::load(&args.run)
+        .with_context(|| format!("Failed to read snapshots in {}", args.run.display()))?;
+    let client = connect(
+        &args.wiki,
+        args.config.clone(),
+        &args.auth_profile,
+        "restore",
+    )
+    .await?;
+    let site = super::site::fetch_with(&client).await;
+
+    let mut tally = Tally::default();
+    for raw in &args.pages {
+        let title = super::site::parse_title(&site, raw)?;
+        let (Some(original), Some(latest)) =
+            (index.original(&title.display), index.latest(&title.display))
+        else {
+            println!(
+                "  {} No snapshot of {} in this run",
+                style("✗").red(),
+                title.display
+            );
+            tally.failed += 1;
+            continue;
+        };
+        let Some(page) = fetch_page(&client, &title, &mut tally).await else {
+            continue;
+        };
+
+        if latest.new_revid != Some(page.revision.0) && !args.force {
+            println!(
+                "  {} {} was edited after the run (now rev {}, run saved {}); use --force to restore anyway",
+                style("✗").red(),
+                title.display,
+                page.revision.0,
+                latest.new_revid.map_or_else(
+                    || "an unknown revision".to_string(),
+                    |rev| format!("rev {}", rev)
+                )
+            );
+            tally.failed += 1;
+            continue;
+        }
+
+        let change = index
+            .read(original)
+            .map(|text| {
+                Some((
+                    text,
+                    format!(
+                        "Restoring revision {} from before an automated run",
+                        original.base_revid
+                    ),
+                ))
+            })
+            .map_err(|e| format!("Failed to read snapshot: {}", e));
+        save_change(&client, &page, change, args.dry_run, &mut tally).await;
+    }
+
+    tally.print(args.dry_run);
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use awb_domain::rules::RuleSet;
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::Watchlist;
+use awb_mw_api::client::MediaWikiClient;
+use awb_storage::rule_library::default_cache_dir;
+use awb_storage::{ProfileSource, RuleLibraryCache, RuleLibraryOptions, TomlConfigStore};
+use console::style;
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+/// Profile options shared by the editing commands
+#[derive(clap::Args)]
+pub struct ProfileArgs {
+    /// Profile file path (TOML), or `wiki:<Title>` to load rules from a wiki page
+    #[arg(long)]
+    pub profile: ProfileSource,
+
+    /// Config file holding auth profiles (default: the profile file, or
+    /// ~/.awb-rs/config.toml for wiki profiles)
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Expected SHA-256 of a wiki profile page; other content is refused
+    #[arg(long)]
+    pub rules_sha256: Option<String>,
+
+    /// Seconds a cached wiki profile is used before fetching it again
+    #[arg(long, default_value = "3600")]
+    pub rules_refresh: u64,
+
+    /// What saving a page does to the watchlist: watch, unwatch, nochange
+    /// or preferences (default: the `watchlist` preference)
+    #[arg(long)]
+    pub watchlist: Option<Watchlist>,
+}
+
+impl ProfileArgs {
+    /// Config store with the auth profiles
+    pub fn config_store(&self) -> TomlConfigStore {
+        TomlConfigStore::new(self.config_path())
+    }
+
+    /// Path of the config file with the auth profiles
+    pub fn config_path(&self) -> PathBuf {
+        match (&self.config, &self.profile) {
+            (Some(path), _) | (None, ProfileSource::File(path)) => path.clone(),
+            (None, ProfileSource::Wiki(_)) => default_config_path(),
+        }
+    }
+
+    /// Watchlist behavior: the `--watchlist` flag, else the preference in
+    /// effect for `auth_profile`
+    pub fn watchlist(&self, store: &TomlConfigStore, auth_profile: &str) -> Watchlist {
+        self.watchlist.unwrap_or_else(|| {
+            store
+                .load_effective_preferences(None, Some(auth_profile))
+                .map(|effective| effective.preferences.watchlist)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Find-and-replace rules: those saved for `auth_profile` in the config
+    /// file, or the ones on the wiki page, read from `wiki` through `client`
+    pub async fn load_rules(
+        &self,
+        store: &TomlConfigStore,
+        auth_profile: &str,
+        wiki: &Url,
+        client: &impl MediaWikiClient,
+        site: &SiteInfo,
+    ) -> Result<RuleSet> {
+        let title = match &self.profile {
+            ProfileSource::File(_) => {
+                return store
+                    .load_rules(auth_profile)
+                    .context("Failed to load rules from profile");
+            }
+            ProfileSource::Wiki(title) => title,
+        };
+
+        let page = super::site::parse_title(site, title)?;
+        let options = RuleLibraryOptions {
+            pin: self.rules_sha256.clone(),
+            refresh: Duration::from_secs(self.rules_refresh),
+        };
+        let loaded = RuleLibraryCache::new(default_cache_dir())
+            .load(wiki, title, &options, || async {
+                client
+                    .get_page(&page)
+                    .await
+                    .map(|page| page.wikitext)
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .with_context(|| format!("Failed to load rules from {}", title))?;
+
+        println!(
+            "Rules: {} from {} ({})",
+            loaded.rules.rules.len(),
+            title,
+            if loaded.from_cache {
+                format!("cached {}", loaded.fetched_at.format("%Y-%m-%d %H:%M UTC"))
+            } else {
+                "fetched".to_string()
+            }
+        );
+        if self.rules_sha256.is_none() {
+            println!(
+                "{} Rules are not pinned; pass --rules-sha256 {} to refuse other revisions",
+                style("⚠").yellow(),
+                loaded.sha256
+            );
+        }
+        Ok(loaded.rules)
+    }
+}
+
+/// Default config file for wiki profiles, `~/.awb-rs/config.toml`
+pub fn default_config_path() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".awb-rs")
+        .join("config.toml")
+}
@@ -1,27 +1,60 @@
+use super::rule_source::ProfileArgs;
+use super::sample::SampleArgs;
 use anyhow::{Context, Result};
+use awb_bot::DiffArtifacts;
+use awb_domain::page_list::PageList;
 use awb_domain::profile::AuthMethod;
-use awb_domain::types::Title;
-use awb_engine::diff_engine::{compute_diff, to_unified};
-use awb_mw_api::client::{EditRequest, MediaWikiClient, ReqwestMwClient};
-use awb_security::{CredentialPort, InMemoryCredentialStore};
-use awb_storage::TomlConfigStore;
+use awb_domain::warnings::Warning;
+use awb_engine::content_guard::ContentLossGuard;
+use awb_engine::diff_engine::to_unified;
+use awb_engine::general_fixes::{FixModule, FixRegistry};
+use awb_engine::transform::is_null_edit;
+use awb_mw_api::client::{EditRequest, MediaWikiClient};
+use awb_plugins::signing::default_trust_store_path;
+use awb_storage::CompiledRuleCache;
+use awb_storage::rule_library::default_cache_dir;
 use chrono::Utc;
 use console::style;
 use dialoguer::Select;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use url::Url;
 
-pub async fn run(
-    wiki: Url,
-    profile_path: PathBuf,
-    batch: bool,
-    dry_run: bool,
-    auth_profile: String,
-) -> Result<()> {
+/// Arguments for the run command
+pub struct RunArgs {
+    pub wiki: Url,
+    pub profile: ProfileArgs,
+    pub batch: bool,
+    pub allow_content_loss: bool,
+    pub dry_run: bool,
+    pub diff_dir: Option<PathBuf>,
+    pub pages: Option<PathBuf>,
+    pub sample: SampleArgs,
+    pub auth_profile: String,
+    /// Directory of signed plugins to run after the general fixes
+    pub plugins: Option<PathBuf>,
+    /// Reload plugins edited in `plugins` between pages
+    pub watch_plugins: bool,
+}
+
+pub async fn run(args: RunArgs) -> Result<()> {
+    let RunArgs {
+        wiki,
+        profile,
+        batch,
+        allow_content_loss,
+        dry_run,
+        diff_dir,
+        pages,
+        sample,
+        auth_profile,
+        plugins,
+        watch_plugins,
+    } = args;
     println!("{}", style("AWB-RS Edit Workflow").bold().cyan());
     println!("Wiki: {}", wiki);
-    println!("Profile: {}", profile_path.display());
+    println!("Profile: {}", profile.profile);
     println!(
         "Mode: {}",
         if dry_run {
@@ -35,23 +68,23 @@ pub async fn run(
     println!();
 
     // Load profile
-    let config_store = TomlConfigStore::new(&profile_path);
-    let profile = config_store
+    let config_store = profile.config_store();
+    let auth = config_store
         .load_profile(&auth_profile)
         .context("Failed to load profile. Create one first or use a different auth-profile.")?;
 
     // Get credentials
-    let cred_store = InMemoryCredentialStore::new();
+    let (_, cred_store) = super::credentials::open_store()?;
     let password = cred_store
         .get_password(&auth_profile)
         .context("No stored credentials found. Run 'login' command first.")?;
 
     // Create client and login
-    let client = ReqwestMwClient::new(wiki.clone(), profile.throttle_policy.clone())
-        .context("Failed to create HTTP client")?;
+    let client =
+        super::credentials::profile_client(&wiki, &auth_profile, &auth, cred_store.as_ref())?;
 
     print!("Logging in... ");
-    let username = match &profile.auth_method {
+    let username = match &auth.auth_method {
         AuthMethod::BotPassword { username } => username.clone(),
         AuthMethod::OAuth2 { .. } => {
             anyhow::bail!("OAuth2 not yet implemented");
@@ -75,12 +108,35 @@ pub async fn run(
         .context("Failed to fetch CSRF token")?;
     println!("{}", style("✓").green().bold());
 
-    // For demo purposes, generate a simple page list
-    // In real usage, this would come from the profile configuration
-    let titles = vec![
-        Title::new(awb_domain::types::Namespace::MAIN, "Test Page 1"),
-        Title::new(awb_domain::types::Namespace::MAIN, "Test Page 2"),
-    ];
+    // Namespace names and case rules for reading titles
+    let site = super::site::fetch_with(&client).await;
+
+    // Load rules and build engine
+    let ruleset = profile
+        .load_rules(&config_store, &auth_profile, &wiki, &client, &site)
+        .await?;
+    let mut registry = FixRegistry::with_defaults();
+    let mut enabled_fixes = HashSet::new();
+    if let Some(dir) = &plugins {
+        let mut plugins = super::plugin::load_signed(dir, &default_trust_store_path())?;
+        if watch_plugins {
+            plugins.manager_mut().watch(dir)?;
+        }
+        enabled_fixes.insert(plugins.id().to_string());
+        registry.register(Box::new(plugins))?;
+    }
+    let engine = CompiledRuleCache::new(default_cache_dir())
+        .engine(&ruleset, registry, enabled_fixes)
+        .context("Failed to create transform engine")?
+        .with_summary_template(super::site::summary_template(&ruleset, &wiki, &site));
+    let watchlist = profile.watchlist(&config_store, &auth_profile);
+
+    let titles = match &pages {
+        Some(path) => super::site::load_pages(&site, path)?,
+        // For demo purposes, generate a simple page list
+        None => PageList::parse(&site, "Test Page 1\nTest Page 2")?,
+    };
+    let (titles, sample) = sample.apply(titles)?;
 
     println!();
     println!("Processing {} pages...", titles.len());
@@ -94,8 +150,13 @@ pub async fn run(
             .progress_chars("#>-"),
     );
 
+    let artifacts = diff_dir.map(|dir| match sample {
+        Some(sample) => DiffArtifacts::new(dir).with_sample(sample),
+        None => DiffArtifacts::new(dir),
+    });
     let mut saved_count = 0;
     let mut skipped_count = 0;
+    let mut unchanged_count = 0;
 
     for title in titles {
         pb.set_message(title.display.clone());
@@ -116,23 +177,36 @@ pub async fn run(
             }
         };
 
-        // Apply transformations (simplified - in real usage would use awb_engine rules)
-        let new_text = apply_simple_transform(&page.wikitext);
+        let plan = engine.apply(&page);
+
+        let skip_requests = plan.warnings.iter().find_map(|w| match w {
+            Warning::SkipRequested { fixes } => Some(fixes.join(", ")),
+            _ => None,
+        });
+        if let Some(fixes) = skip_requests {
+            pb.println(format!(
+                "  {} Skipped {} (requested by {})",
+                style("→").dim(),
+                title.display,
+                fixes
+            ));
+            skipped_count += 1;
+            pb.inc(1);
+            continue;
+        }
 
-        if new_text == page.wikitext {
+        if is_null_edit(&page.wikitext, &plan.new_wikitext) {
             pb.println(format!(
                 "  {} No changes needed: {}",
                 style("→").dim(),
                 title.display
             ));
-            skipped_count += 1;
+            unchanged_count += 1;
             pb.inc(1);
             continue;
         }
 
-        // Compute diff
-        let diff_ops = compute_diff(&page.wikitext, &new_text);
-        let unified_diff = to_unified(&diff_ops, 3);
+        let unified_diff = to_unified(&plan.diff_ops, 3);
 
         // Show diff
         pb.println(format!(
@@ -151,13 +225,72 @@ pub async fn run(
         }
         pb.println(style("─".repeat(60)).dim().to_string());
 
+        let losses = ContentLossGuard::default().check(&site, &page.wikitext, &plan.new_wikitext);
+        for loss in &losses {
+            pb.println(format!(
+                "  {} Possible content loss: {}",
+                style("⚠").yellow(),
+                loss
+            ));
+        }
+        let broken_refs = plan.warnings.iter().find_map(|w| match w {
+            Warning::BrokenReferences { names } => Some(names.join(", ")),
+            _ => None,
+        });
+        if let Some(names) = &broken_refs {
+            pb.println(format!(
+                "  {} References left undefined: {}",
+                style("⚠").yellow(),
+                names
+            ));
+        }
+        let changed_links = plan.warnings.iter().find_map(|w| match w {
+            Warning::LinkTargetsChanged { removed, added } => Some((removed, added)),
+            _ => None,
+        });
+        if let Some((removed, added)) = changed_links {
+            pb.println(format!(
+                "  {} Link targets changed: removed [{}], added [{}]",
+                style("⚠").yellow(),
+                removed.join(", "),
+                added.join(", ")
+            ));
+        }
+
         // Decide action
         let should_save = if dry_run {
+            if let Some(artifacts) = &artifacts {
+                if let Err(e) = artifacts.record(&title.display, &plan) {
+                    pb.println(format!(
+                        "  {} Failed to write diff: {}",
+                        style("✗").red(),
+                        e
+                    ));
+                }
+            }
             pb.println(format!(
                 "  {} Dry-run mode - not saving\n",
                 style("ℹ").cyan()
             ));
             false
+        } else if batch && broken_refs.is_some() {
+            pb.println(format!(
+                "  {} Batch mode - not saving an edit that breaks references\n",
+                style("✗").red()
+            ));
+            false
+        } else if batch && changed_links.is_some() {
+            pb.println(format!(
+                "  {} Batch mode - not saving an edit that changes link targets\n",
+                style("✗").red()
+            ));
+            false
+        } else if batch && !losses.is_empty() && !allow_content_loss {
+            pb.println(format!(
+                "  {} Batch mode - not saving (use --allow-content-loss to save anyway)\n",
+                style("✗").red()
+            ));
+            false
         } else if batch {
             pb.println(format!(
                 "  {} Batch mode - auto-saving\n",
@@ -189,13 +322,15 @@ pub async fn run(
         if should_save {
             let edit_request = EditRequest {
                 title: title.clone(),
-                text: new_text,
-                summary: "AWB-RS automated edit".to_string(),
+                text: plan.new_wikitext,
+                summary: plan.summary,
                 minor: true,
                 bot: true,
                 base_timestamp: page.timestamp.to_rfc3339(),
                 start_timestamp: Utc::now().to_rfc3339(),
                 section: None,
+                tags: Vec::new(),
+                watchlist,
             };
 
             match client.edit_page(&edit_request).await {
@@ -236,12 +371,15 @@ pub async fn run(
     println!("{}", style("Summary").bold().cyan());
     println!("  Saved: {}", style(saved_count).green().bold());
     println!("  Skipped: {}", style(skipped_count).yellow());
+    println!("  Unchanged: {}", style(unchanged_count).dim());
+    if let Some(artifacts) = &artifacts {
+        println!(
+            "  Diffs: {} in {}",
+            artifacts.len(),
+            artifacts.dir().display()
+        );
+    }
     println!();
 
     Ok(())
 }
-
-fn apply_simple_transform(wikitext: &str) -> String {
-    // Simple example transformation: fix double spaces
-    wikitext.replace("  ", " ")
-}
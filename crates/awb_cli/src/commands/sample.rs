@@ -0,0 +1,45 @@
+use anyhow::Result;
+use awb_domain::page_list::{PageList, SampleManifest, SampleSize};
+use console::style;
+
+/// Options for piloting a run on part of the page list
+#[derive(clap::Args)]
+pub struct SampleArgs {
+    /// Only visit this many pages, sampled across namespaces
+    #[arg(long, value_name = "N", conflicts_with = "sample_percent")]
+    pub sample: Option<usize>,
+
+    /// Only visit this percentage of the pages, sampled across namespaces
+    #[arg(long, value_name = "X")]
+    pub sample_percent: Option<f64>,
+
+    /// Seed for the sample; the same seed and list give the same pages
+    #[arg(long, default_value = "0")]
+    pub seed: u64,
+}
+
+impl SampleArgs {
+    /// Sample `pages` if asked to, returning the pages to visit and the
+    /// manifest to record in the report
+    pub fn apply(&self, pages: PageList) -> Result<(PageList, Option<SampleManifest>)> {
+        let size = match (self.sample, self.sample_percent) {
+            (Some(0), _) => anyhow::bail!("--sample must be at least 1"),
+            (Some(count), _) => SampleSize::Count(count),
+            (None, Some(percent)) if !(percent > 0.0 && percent <= 100.0) => {
+                anyhow::bail!("--sample-percent must be greater than 0 and at most 100")
+            }
+            (None, Some(percent)) => SampleSize::Percent(percent),
+            (None, None) => return Ok((pages, None)),
+        };
+
+        let (sample, manifest) = pages.sample(size, self.seed);
+        println!(
+            "{} Sampled {} of {} pages (seed {})",
+            style("ℹ").cyan(),
+            sample.len(),
+            pages.len(),
+            self.seed
+        );
+        Ok((sample, Some(manifest)))
+    }
+}
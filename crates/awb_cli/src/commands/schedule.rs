@@ -0,0 +1,532 @@
+use super::bot::BotRunArgs;
+use super::rule_source::ProfileArgs;
+use super::sample::SampleArgs;
+use anyhow::{Context, Result};
+use awb_bot::{BotReport, CronExpr};
+use awb_storage::ProfileSource;
+use chrono::{DateTime, Utc};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinSet;
+use url::Url;
+
+/// Status file the scheduler keeps in its state directory
+const STATUS_FILE: &str = "status.json";
+
+/// The `--config` file: where state goes and one `[[task]]` table per task
+#[derive(Debug, Deserialize)]
+struct ScheduleFile {
+    /// Checkpoints, reports and the status file, relative to the schedule
+    /// file (default: `schedule-state`)
+    #[serde(default = "default_state_dir")]
+    state_dir: PathBuf,
+    task: Vec<Task>,
+}
+
+fn default_state_dir() -> PathBuf {
+    PathBuf::from("schedule-state")
+}
+
+/// A bot run repeated on a cron schedule. Paths are relative to the
+/// schedule file.
+#[derive(Debug, Clone, Deserialize)]
+struct Task {
+    /// Names the task's directory under the state directory
+    name: String,
+    /// When to run, in UTC; see [`awb_bot::cron`]
+    cron: CronExpr,
+    /// Wiki API URL
+    wiki: Url,
+    /// Profile file, or `wiki:<Title>`
+    profile: String,
+    /// Config file holding auth profiles (default: the profile file)
+    #[serde(default)]
+    config: Option<PathBuf>,
+    /// Page list file
+    pages: PathBuf,
+    #[serde(default)]
+    bot_config: Option<PathBuf>,
+    #[serde(default = "default_auth_profile")]
+    auth_profile: String,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    max_edits: Option<u32>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn default_auth_profile() -> String {
+    "default".to_string()
+}
+
+struct Schedule {
+    state_dir: PathBuf,
+    tasks: Vec<Task>,
+}
+
+impl Schedule {
+    fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: ScheduleFile = toml::from_str(&source)
+            .with_context(|| format!("Invalid schedule {}", path.display()))?;
+        if file.task.is_empty() {
+            anyhow::bail!("{} schedules no tasks", path.display());
+        }
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        let mut names = HashSet::new();
+        let mut tasks = file.task;
+        for task in &mut tasks {
+            let valid_name = !task.name.is_empty()
+                && task
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_name {
+                anyhow::bail!(
+                    "Task name '{}' must be letters, digits, '-' and '_' only",
+                    task.name
+                );
+            }
+            if !names.insert(task.name.clone()) {
+                anyhow::bail!("Task name '{}' is used twice", task.name);
+            }
+            if !task.profile.starts_with("wiki:") {
+                task.profile = base.join(&task.profile).display().to_string();
+            }
+            task.pages = base.join(&task.pages);
+            task.config = task.config.as_ref().map(|path| base.join(path));
+            task.bot_config = task.bot_config.as_ref().map(|path| base.join(path));
+        }
+        Ok(Self {
+            state_dir: base.join(file.state_dir),
+            tasks,
+        })
+    }
+
+    fn task_dir(&self, task: &Task) -> PathBuf {
+        self.state_dir.join(&task.name)
+    }
+
+    fn status_path(&self) -> PathBuf {
+        self.state_dir.join(STATUS_FILE)
+    }
+}
+
+/// What the scheduler has done, saved for `schedule status`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleStatus {
+    updated: Option<DateTime<Utc>>,
+    tasks: BTreeMap<String, TaskStatus>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct TaskStatus {
+    running: bool,
+    next_run: Option<DateTime<Utc>>,
+    last_started: Option<DateTime<Utc>>,
+    last_finished: Option<DateTime<Utc>>,
+    /// Outcome of the last run, e.g. `completed: 3 edited, 1 skipped, 0 errors`
+    last_result: Option<String>,
+    runs: u32,
+    /// Occurrences skipped because the previous run was still going
+    overlaps_skipped: u32,
+}
+
+impl ScheduleStatus {
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&source).with_context(|| format!("Invalid status {}", path.display()))
+    }
+
+    fn save(&mut self, path: &Path) -> Result<()> {
+        self.updated = Some(Utc::now());
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to save status {}", path.display()))
+    }
+
+    /// Start a fresh scheduler: nothing is running any more, tasks no
+    /// longer scheduled are forgotten, and each task is next due at its
+    /// first occurrence after `now`
+    fn reset(&mut self, tasks: &[Task], now: DateTime<Utc>) {
+        self.tasks
+            .retain(|name, _| tasks.iter().any(|task| &task.name == name));
+        for task in tasks {
+            let status = self.tasks.entry(task.name.clone()).or_default();
+            status.running = false;
+            status.next_run = task.cron.next_after(now);
+        }
+    }
+
+    /// Names of the tasks due at `now` that are not already running, marked
+    /// as started. Every due task is rescheduled; a run still going when
+    /// its next one is due is left alone and the occurrence skipped.
+    fn start_due(&mut self, tasks: &[Task], now: DateTime<Utc>) -> Vec<String> {
+        let mut started = Vec::new();
+        for task in tasks {
+            let status = self.tasks.entry(task.name.clone()).or_default();
+            if status.next_run.is_none_or(|next| next > now) {
+                continue;
+            }
+            // Missed occurrences, e.g. while suspended, are not caught up
+            status.next_run = task.cron.next_after(now);
+            if status.running {
+                tracing::warn!(
+                    "Skipping scheduled run of {}: the previous run is still going",
+                    task.name
+                );
+                status.overlaps_skipped += 1;
+                continue;
+            }
+            status.running = true;
+            status.last_started = Some(now);
+            status.runs += 1;
+            started.push(task.name.clone());
+        }
+        started
+    }
+
+    fn finish(&mut self, name: &str, result: String, now: DateTime<Utc>) {
+        let status = self.tasks.entry(name.to_string()).or_default();
+        status.running = false;
+        status.last_finished = Some(now);
+        status.last_result = Some(result);
+    }
+
+    /// When the scheduler should next wake
+    fn next_wake(&self) -> Option<DateTime<Utc>> {
+        self.tasks
+            .values()
+            .filter_map(|status| status.next_run)
+            .min()
+    }
+}
+
+pub async fn run(config: PathBuf) -> Result<()> {
+    let schedule = Arc::new(Schedule::load(&config)?);
+    for task in &schedule.tasks {
+        std::fs::create_dir_all(schedule.task_dir(task))
+            .with_context(|| format!("Failed to create {}", schedule.task_dir(task).display()))?;
+    }
+    let status_path = schedule.status_path();
+    let mut status = ScheduleStatus::load(&status_path)?;
+    status.reset(&schedule.tasks, Utc::now());
+    status.save(&status_path)?;
+    let status = Arc::new(Mutex::new(status));
+
+    println!("{}", style("AWB-RS Scheduler").bold().cyan());
+    println!("State: {}", schedule.state_dir.display());
+    print_tasks(&schedule, &status.lock().unwrap_or_else(|e| e.into_inner()));
+    println!();
+
+    let mut running = JoinSet::new();
+    loop {
+        let wake = status.lock().unwrap_or_else(|e| e.into_inner()).next_wake();
+        let Some(wake) = wake else {
+            println!("No task has another run due");
+            break;
+        };
+        let wait = (wake - Utc::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            // Finished runs are recorded by the runs themselves
+            Some(_) = running.join_next(), if !running.is_empty() => continue,
+            _ = tokio::signal::ctrl_c() => {
+                println!("Stopping the scheduler; running tasks save their checkpoints");
+                break;
+            }
+        }
+
+        let started = {
+            let mut status = status.lock().unwrap_or_else(|e| e.into_inner());
+            let started = status.start_due(&schedule.tasks, Utc::now());
+            status.save(&status_path)?;
+            started
+        };
+        for name in started {
+            let schedule = schedule.clone();
+            let status = status.clone();
+            let status_path = status_path.clone();
+            running.spawn(async move {
+                let Some(task) = schedule.tasks.iter().find(|task| task.name == name) else {
+                    return;
+                };
+                tracing::info!("Starting scheduled run of {}", task.name);
+                let result = describe(run_task(&schedule, task).await);
+                tracing::info!("Scheduled run of {} finished: {}", task.name, result);
+                let mut status = status.lock().unwrap_or_else(|e| e.into_inner());
+                status.finish(&task.name, result, Utc::now());
+                if let Err(e) = status.save(&status_path) {
+                    tracing::error!("{:#}", e);
+                }
+            });
+        }
+    }
+
+    while running.join_next().await.is_some() {}
+    Ok(())
+}
+
+/// One run of `task`, with its own checkpoint and reports. The checkpoint
+/// is kept for the next run to resume from unless this one got through
+/// every page.
+async fn run_task(schedule: &Schedule, task: &Task) -> Result<BotReport> {
+    let dir = schedule.task_dir(task);
+    let checkpoint = dir.join("checkpoint.json");
+    let profile = task
+        .profile
+        .parse::<ProfileSource>()
+        .map_err(anyhow::Error::msg)?;
+    let report = super::bot::run(BotRunArgs {
+        wiki: task.wiki.clone(),
+        profile: ProfileArgs {
+            profile,
+            config: task.config.clone(),
+            rules_sha256: None,
+            rules_refresh: 3600,
+            watchlist: None,
+        },
+        config: task.bot_config.clone(),
+        pages: Some(task.pages.clone()),
+        sample: SampleArgs {
+            sample: None,
+            sample_percent: None,
+            seed: 0,
+        },
+        max_edits: task.max_edits,
+        dry_run: task.dry_run,
+        diff_dir: None,
+        checkpoint_path: Some(checkpoint.clone()),
+        auth_profile: task.auth_profile.clone(),
+        skip_no_change: true,
+        skip_on_warning: false,
+        verify_idempotent: false,
+        allow_content_loss: false,
+        log_every_n: None,
+        jobs: None,
+        profile_memory: false,
+        tags: task.tags.clone(),
+        dashboard_logs: None,
+        control_socket: None,
+        snapshot_dir: None,
+        plugins: None,
+        watch_plugins: false,
+        report_dir: Some(dir),
+    })
+    .await?;
+    if report.completed {
+        std::fs::remove_file(&checkpoint)
+            .with_context(|| format!("Failed to clear {}", checkpoint.display()))?;
+    }
+    Ok(report)
+}
+
+fn describe(result: Result<BotReport>) -> String {
+    match result {
+        Ok(report) => format!(
+            "{}: {} edited, {} skipped, {} errors",
+            if report.completed {
+                "completed"
+            } else {
+                "stopped"
+            },
+            report.pages_edited,
+            report.pages_skipped,
+            report.pages_errored
+        ),
+        Err(e) => format!("failed: {:#}", e),
+    }
+}
+
+/// `schedule status`: each task's last run and when it is next due
+pub async fn status(config: PathBuf) -> Result<()> {
+    let schedule = Schedule::load(&config)?;
+    let status = ScheduleStatus::load(&schedule.status_path())?;
+    match status.updated {
+        Some(updated) => println!("Status as of {}", updated.format("%Y-%m-%d %H:%M:%S UTC")),
+        None => println!("The scheduler has not run yet"),
+    }
+    print_tasks(&schedule, &status);
+    Ok(())
+}
+
+fn print_tasks(schedule: &Schedule, status: &ScheduleStatus) {
+    let format = |time: Option<DateTime<Utc>>| {
+        time.map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| "never".to_string())
+    };
+    for task in &schedule.tasks {
+        let task_status = status.tasks.get(&task.name).cloned().unwrap_or_default();
+        let state = if task_status.running {
+            style("running").green()
+        } else {
+            style("idle").dim()
+        };
+        println!();
+        println!("{} ({}) {}", style(&task.name).bold(), task.cron, state);
+        println!("  Next run:  {}", format(task.cron.next_after(Utc::now())));
+        println!("  Last run:  {}", format(task_status.last_started));
+        if let Some(result) = &task_status.last_result {
+            println!("  Result:    {}", result);
+        }
+        println!(
+            "  Runs:      {} ({} skipped while still running)",
+            task_status.runs, task_status.overlaps_skipped
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn write_schedule(dir: &Path, body: &str) -> PathBuf {
+        let path = dir.join("schedule.toml");
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    const TWO_TASKS: &str = r#"
+[[task]]
+name = "typos"
+cron = "0 * * * *"
+wiki = "https://en.wikipedia.org/w/api.php"
+profile = "typos.toml"
+pages = "lists/typos.txt"
+
+[[task]]
+name = "stubs"
+cron = "30 2 * * *"
+wiki = "https://en.wikipedia.org/w/api.php"
+profile = "wiki:User:ExampleBot/Rules"
+pages = "stubs.txt"
+auth_profile = "stub-bot"
+max_edits = 50
+"#;
+
+    #[test]
+    fn test_load_resolves_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let schedule = Schedule::load(&write_schedule(dir.path(), TWO_TASKS)).unwrap();
+
+        assert_eq!(schedule.state_dir, dir.path().join("schedule-state"));
+        let typos = &schedule.tasks[0];
+        assert_eq!(typos.pages, dir.path().join("lists/typos.txt"));
+        assert_eq!(
+            typos.profile,
+            dir.path().join("typos.toml").display().to_string()
+        );
+        assert_eq!(typos.auth_profile, "default");
+        let stubs = &schedule.tasks[1];
+        assert_eq!(stubs.profile, "wiki:User:ExampleBot/Rules");
+        assert_eq!(stubs.max_edits, Some(50));
+        assert_eq!(
+            schedule.task_dir(stubs),
+            dir.path().join("schedule-state/stubs")
+        );
+    }
+
+    #[test]
+    fn test_load_rejects_bad_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let duplicate = TWO_TASKS.replace("\"stubs\"", "\"typos\"");
+        assert!(Schedule::load(&write_schedule(dir.path(), &duplicate)).is_err());
+        let unsafe_name = TWO_TASKS.replace("\"stubs\"", "\"../stubs\"");
+        assert!(Schedule::load(&write_schedule(dir.path(), &unsafe_name)).is_err());
+        let bad_cron = TWO_TASKS.replace("30 2 * * *", "every night");
+        assert!(Schedule::load(&write_schedule(dir.path(), &bad_cron)).is_err());
+        assert!(Schedule::load(&write_schedule(dir.path(), "task = []\n")).is_err());
+    }
+
+    #[test]
+    fn test_due_tasks_start_and_overlaps_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let schedule = Schedule::load(&write_schedule(dir.path(), TWO_TASKS)).unwrap();
+        let mut status = ScheduleStatus::default();
+        status.reset(&schedule.tasks, at("2024-05-01T00:10:00Z"));
+        assert_eq!(status.next_wake(), Some(at("2024-05-01T01:00:00Z")));
+
+        // Nothing is due early
+        assert!(
+            status
+                .start_due(&schedule.tasks, at("2024-05-01T00:59:00Z"))
+                .is_empty()
+        );
+        assert_eq!(
+            status.start_due(&schedule.tasks, at("2024-05-01T01:00:00Z")),
+            ["typos"]
+        );
+        assert_eq!(
+            status.tasks["typos"].next_run,
+            Some(at("2024-05-01T02:00:00Z"))
+        );
+
+        // Still running at 02:00 and 03:00: both occurrences are skipped,
+        // while the nightly task starts on time
+        assert!(
+            status
+                .start_due(&schedule.tasks, at("2024-05-01T02:00:00Z"))
+                .is_empty()
+        );
+        assert_eq!(
+            status.start_due(&schedule.tasks, at("2024-05-01T02:30:00Z")),
+            ["stubs"]
+        );
+        status.start_due(&schedule.tasks, at("2024-05-01T03:00:00Z"));
+        assert_eq!(status.tasks["typos"].overlaps_skipped, 2);
+
+        status.finish(
+            "typos",
+            "completed: 1 edited, 0 skipped, 0 errors".to_string(),
+            at("2024-05-01T03:05:00Z"),
+        );
+        assert_eq!(
+            status.start_due(&schedule.tasks, at("2024-05-01T04:00:00Z")),
+            ["typos"]
+        );
+        assert_eq!(status.tasks["typos"].runs, 2);
+    }
+
+    #[test]
+    fn test_status_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let schedule = Schedule::load(&write_schedule(dir.path(), TWO_TASKS)).unwrap();
+        std::fs::create_dir_all(&schedule.state_dir).unwrap();
+        let mut status = ScheduleStatus::default();
+        status.reset(&schedule.tasks, at("2024-05-01T00:10:00Z"));
+        status.start_due(&schedule.tasks, at("2024-05-01T01:00:00Z"));
+        status.save(&schedule.status_path()).unwrap();
+
+        // The scheduler died mid-run; a new one forgets the run but keeps
+        // the history
+        let mut reloaded = ScheduleStatus::load(&schedule.status_path()).unwrap();
+        assert!(reloaded.tasks["typos"].running);
+        reloaded.reset(&schedule.tasks, at("2024-05-01T01:30:00Z"));
+        assert!(!reloaded.tasks["typos"].running);
+        assert_eq!(reloaded.tasks["typos"].runs, 1);
+        assert_eq!(
+            reloaded.tasks["typos"].next_run,
+            Some(at("2024-05-01T02:00:00Z"))
+        );
+
+        // A task taken out of the schedule no longer wakes the scheduler
+        reloaded.reset(&schedule.tasks[1..], at("2024-05-01T01:30:00Z"));
+        assert!(!reloaded.tasks.contains_key("typos"));
+        assert_eq!(reloaded.next_wake(), Some(at("2024-05-01T02:30:00Z")));
+    }
+}
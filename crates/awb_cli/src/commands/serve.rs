@@ -0,0 +1,418 @@
+//! `awb-rs serve --stdio`: the engine over JSON-RPC 2.0.
+//!
+//! Requests are read from stdin one JSON document per line and each response
+//! is written to stdout as a single line, so editors and external tools can
+//! drive the engine as a subprocess. Logs go to stderr.
+
+use anyhow::Result;
+use awb_domain::diff::DiffOp;
+use awb_domain::rules::RuleSet;
+use awb_domain::session::EditPlan;
+use awb_domain::types::*;
+use awb_domain::warnings::Warning;
+use awb_engine::diff_engine;
+use awb_engine::fix_config::FixConfig;
+use awb_engine::general_fixes::FixRegistry;
+use awb_engine::transform::TransformEngine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+pub async fn run(stdio: bool) -> Result<()> {
+    if !stdio {
+        anyhow::bail!("Only the stdio transport is supported; pass --stdio");
+    }
+    tracing::info!("Serving JSON-RPC on stdio");
+    tokio::task::spawn_blocking(|| serve(io::stdin().lock(), io::stdout().lock())).await??;
+    Ok(())
+}
+
+/// Answer requests from `input` on `output` until `input` is exhausted.
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&line) {
+            serde_json::to_writer(&mut output, &response)?;
+            output.write_all(b"\n")?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// `None` only when the member is absent (a notification); `"id": null`
+    /// is `Some(Value::Null)` and still gets a response
+    #[serde(default, deserialize_with = "present")]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+fn present<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// Handle one line of input, returning the response to write, if any.
+/// Notifications and batches made up only of notifications get none.
+fn handle_line(line: &str) -> Option<Value> {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)),
+            ));
+        }
+    };
+    match value {
+        Value::Array(batch) if batch.is_empty() => Some(error_response(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Empty batch"),
+        )),
+        Value::Array(batch) => {
+            let responses: Vec<Value> = batch.into_iter().filter_map(handle_request).collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        value => handle_request(value),
+    }
+}
+
+fn handle_request(value: Value) -> Option<Value> {
+    let fallback_id = value.get("id").cloned().unwrap_or(Value::Null);
+    let request = match serde_json::from_value::<Request>(value) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            return Some(error_response(
+                fallback_id,
+                RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+            ));
+        }
+        Err(e) => {
+            return Some(error_response(
+                fallback_id,
+                RpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e)),
+            ));
+        }
+    };
+
+    let result = dispatch(&request.method, request.params);
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_response(id, error),
+    })
+}
+
+fn dispatch(method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "apply_rules" => to_value(apply_rules(parse_params(params)?)?),
+        "compute_diff" => to_value(compute_diff(parse_params(params)?)),
+        "list_fixes" => to_value(FixRegistry::with_defaults().fix_infos()),
+        "lint_page" => to_value(lint_page(parse_params(params)?)?),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+}
+
+/// A page to run the engine over, with the rules and fix config to use.
+#[derive(Deserialize)]
+struct PageParams {
+    wikitext: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    namespace: i32,
+    #[serde(default)]
+    rules: Option<RuleSet>,
+    #[serde(default)]
+    fix_config: Option<FixConfig>,
+}
+
+impl PageParams {
+    fn run(self) -> Result<EditPlan, RpcError> {
+        let fix_config = self.fix_config.unwrap_or_default();
+        let registry = FixRegistry::with_config(&fix_config);
+        let enabled = registry
+            .enabled_ids(&fix_config)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+        let rules = self.rules.unwrap_or_default();
+        let engine = TransformEngine::new(&rules, registry, enabled)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+        let page = PageContent {
+            page_id: PageId(0),
+            title: Title::new(Namespace(self.namespace), &self.title),
+            revision: RevisionId(0),
+            timestamp: chrono::Utc::now(),
+            size_bytes: self.wikitext.len() as u64,
+            wikitext: self.wikitext,
+            is_redirect: false,
+            protection: ProtectionInfo::default(),
+            properties: PageProperties::default(),
+        };
+        Ok(engine.apply(&page))
+    }
+}
+
+#[derive(Serialize)]
+struct ApplyResult {
+    new_wikitext: String,
+    changed: bool,
+    rules_applied: Vec<String>,
+    fixes_applied: Vec<String>,
+    summary: String,
+    warnings: Vec<Warning>,
+    is_cosmetic_only: bool,
+    diff: Vec<DiffOp>,
+}
+
+fn apply_rules(params: PageParams) -> Result<ApplyResult, RpcError> {
+    let plan = params.run()?;
+    Ok(ApplyResult {
+        changed: plan.new_wikitext != plan.page.wikitext,
+        rules_applied: plan.rules_applied.iter().map(|id| id.to_string()).collect(),
+        new_wikitext: plan.new_wikitext,
+        fixes_applied: plan.fixes_applied,
+        summary: plan.summary,
+        warnings: plan.warnings,
+        is_cosmetic_only: plan.is_cosmetic_only,
+        diff: plan.diff_ops,
+    })
+}
+
+#[derive(Deserialize)]
+struct DiffParams {
+    old: String,
+    new: String,
+    #[serde(default = "default_context_lines")]
+    context_lines: usize,
+}
+
+fn default_context_lines() -> usize {
+    3
+}
+
+#[derive(Serialize)]
+struct DiffResult {
+    ops: Vec<DiffOp>,
+    unified: String,
+}
+
+fn compute_diff(params: DiffParams) -> DiffResult {
+    let ops = diff_engine::compute_diff(&params.old, &params.new);
+    let unified = diff_engine::to_unified(&ops, params.context_lines);
+    DiffResult { ops, unified }
+}
+
+/// What the engine would do to a page, without the rewritten text.
+#[derive(Serialize)]
+struct LintResult {
+    /// True when no rule or fix would change the page and nothing was flagged
+    clean: bool,
+    rules: Vec<String>,
+    fixes: Vec<String>,
+    warnings: Vec<Warning>,
+    is_cosmetic_only: bool,
+}
+
+fn lint_page(params: PageParams) -> Result<LintResult, RpcError> {
+    let plan = params.run()?;
+    let warnings: Vec<Warning> = plan
+        .warnings
+        .into_iter()
+        .filter(|w| !matches!(w, Warning::NoChange))
+        .collect();
+    Ok(LintResult {
+        clean: plan.new_wikitext == plan.page.wikitext && warnings.is_empty(),
+        rules: plan.rules_applied.iter().map(|id| id.to_string()).collect(),
+        fixes: plan.fixes_applied,
+        warnings,
+        is_cosmetic_only: plan.is_cosmetic_only,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(request: Value) -> Value {
+        handle_line(&request.to_string()).expect("response")
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let response = call(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "apply_rules",
+            "params": {
+                "title": "Example",
+                "wikitext": "colour and colour\n",
+                "rules": { "rules": [{
+                    "id": "00000000-0000-0000-0000-000000000001",
+                    "enabled": true,
+                    "order": 0,
+                    "kind": { "Plain": { "find": "colour", "replace": "color", "case_sensitive": true } },
+                    "comment_fragment": null
+                }] },
+                "fix_config": { "strictness_tier": 0 }
+            }
+        }));
+        assert_eq!(response["id"], 1);
+        let result = &response["result"];
+        assert_eq!(result["new_wikitext"], "color and color\n");
+        assert_eq!(result["changed"], true);
+        assert_eq!(result["rules_applied"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_compute_diff() {
+        let response = call(json!({
+            "jsonrpc": "2.0",
+            "id": "d",
+            "method": "compute_diff",
+            "params": { "old": "a\nb\n", "new": "a\nc\n" }
+        }));
+        let unified = response["result"]["unified"].as_str().unwrap();
+        assert!(unified.contains("-b"));
+        assert!(unified.contains("+c"));
+        assert!(!response["result"]["ops"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_fixes() {
+        let response = call(json!({ "jsonrpc": "2.0", "id": 2, "method": "list_fixes" }));
+        let fixes = response["result"].as_array().unwrap();
+        assert!(fixes.iter().any(|f| f["id"] == "trailing_whitespace"));
+    }
+
+    #[test]
+    fn test_lint_page_reports_pending_fixes() {
+        let response = call(json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "lint_page",
+            "params": { "title": "Example", "wikitext": "Text   \nMore" }
+        }));
+        let result = &response["result"];
+        assert_eq!(result["clean"], false);
+        assert!(!result["fixes"].as_array().unwrap().is_empty());
+
+        let response = call(json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "lint_page",
+            "params": { "wikitext": "Clean text\n" }
+        }));
+        assert_eq!(response["result"]["clean"], true);
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let response = handle_line("{not json").unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+
+        let response = call(json!({ "jsonrpc": "1.0", "id": 1, "method": "list_fixes" }));
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+
+        let response = call(json!({ "jsonrpc": "2.0", "id": 1, "method": "nope" }));
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = call(json!({ "jsonrpc": "2.0", "id": 1, "method": "compute_diff" }));
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let response = call(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "apply_rules",
+            "params": { "wikitext": "x", "fix_config": { "enabled_fixes": ["no_such_fix"] } }
+        }));
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_notifications_and_batches() {
+        assert!(handle_line(r#"{"jsonrpc":"2.0","method":"list_fixes"}"#).is_none());
+
+        let response = handle_line(
+            r#"[{"jsonrpc":"2.0","id":1,"method":"list_fixes"},{"jsonrpc":"2.0","method":"list_fixes"}]"#,
+        )
+        .unwrap();
+        assert_eq!(response.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_null_id_is_not_a_notification() {
+        let response = handle_line(r#"{"jsonrpc":"2.0","id":null,"method":"list_fixes"}"#)
+            .expect("a request with a null id gets a response");
+        assert_eq!(response["id"], Value::Null);
+        assert!(response["result"].is_array());
+    }
+
+    #[test]
+    fn test_serve_writes_one_line_per_response() {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"list_fixes"}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"compute_diff","params":{"old":"a","new":"b"}}"#,
+            "\n"
+        );
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).unwrap();
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], 1);
+        assert_eq!(lines[1]["id"], 2);
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use awb_domain::page_list::PageList;
+use awb_domain::rules::RuleSet;
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::Title;
+use awb_mw_api::client::ReqwestMwClient;
+use console::style;
+use std::path::Path;
+use url::Url;
+
+/// The wiki's namespace names, case rules and interwiki prefixes, used to
+/// read the titles the user gives, fetched through a client set up with a
+/// profile's connection settings. Falls back to the built-in English
+/// names, with a warning, if the wiki cannot be asked.
+pub async fn fetch_with(client: &ReqwestMwClient) -> SiteInfo {
+    match client.fetch_siteinfo().await {
+        Ok(site) => site,
+        Err(e) => {
+            println!(
+                "{} Could not fetch siteinfo ({}); using default namespace names",
+                style("⚠").yellow(),
+                e
+            );
+            SiteInfo::default()
+        }
+    }
+}
+
+/// Canonicalize a title given on the command line
+pub fn parse_title(site: &SiteInfo, raw: &str) -> Result<Title> {
+    Title::parse(site, raw).with_context(|| format!("Invalid title '{}'", raw))
+}
+
+/// The edit summary template `rules` has for the wiki at `wiki`, going by
+/// its host and content language
+pub fn summary_template(rules: &RuleSet, wiki: &Url, site: &SiteInfo) -> Option<String> {
+    rules
+        .summaries
+        .resolve(wiki.host_str(), site.lang.as_deref())
+        .map(str::to_string)
+}
+
+/// Read a one-title-per-line file
+pub fn load_pages(site: &SiteInfo, path: &Path) -> Result<PageList> {
+    PageList::load(site, path)
+        .with_context(|| format!("Failed to read page list {}", path.display()))
+}
@@ -0,0 +1,33 @@
+use anyhow::{Context, Result};
+use awb_storage::validate_config;
+use console::style;
+use std::path::PathBuf;
+
+pub async fn run(profile: PathBuf) -> Result<()> {
+    let source = std::fs::read_to_string(&profile)
+        .with_context(|| format!("Failed to read {}", profile.display()))?;
+
+    let diagnostics = validate_config(&source);
+    if diagnostics.is_empty() {
+        println!(
+            "{} {} is valid",
+            style("✓").green().bold(),
+            profile.display()
+        );
+        return Ok(());
+    }
+
+    for diagnostic in &diagnostics {
+        println!(
+            "{} {}:{}",
+            style("✗").red().bold(),
+            profile.display(),
+            diagnostic
+        );
+    }
+    anyhow::bail!(
+        "{} problem(s) found in {}",
+        diagnostics.len(),
+        profile.display()
+    )
+}
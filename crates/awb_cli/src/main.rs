@@ -5,10 +5,22 @@ use url::Url;
 
 mod commands;
 
+/// The system allocator, counting what each thread holds so that
+/// `bot --profile-memory` can report peak memory per rule and fix. Only
+/// built with the `profile-memory` feature, as counting slows every
+/// allocation.
+#[cfg(feature = "profile-memory")]
+#[global_allocator]
+static ALLOCATOR: awb_engine::memory::TrackingAllocator = awb_engine::memory::TrackingAllocator;
+
 #[derive(Parser)]
 #[command(name = "awb-rs")]
 #[command(version, about = "AutoWikiBrowser in Rust - Wikipedia bot framework", long_about = None)]
 struct Cli {
+    /// Record sanitized API requests and responses to a HAR file
+    #[arg(long, global = true, value_name = "FILE")]
+    trace_api: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -28,6 +40,12 @@ enum Commands {
         /// Profile ID to save credentials under
         #[arg(long, default_value = "default")]
         profile: String,
+
+        /// HTTP basic auth username for wikis behind a password-protected
+        /// web server; the password is prompted for and stored with the
+        /// profile's credentials
+        #[arg(long, value_name = "USER")]
+        http_user: Option<String>,
     },
 
     /// List pages from various sources
@@ -36,17 +54,29 @@ enum Commands {
         #[arg(long)]
         wiki: Url,
 
+        #[command(flatten)]
+        connection: commands::list::ListConnection,
+
         /// Source type
         #[arg(long)]
         source: ListSource,
 
-        /// Query value (category name, page title, search query, or file path)
+        /// Query value (category name, page title, search query, file path,
+        /// PetScan ID, Quarry query ID/result URL/file, or template name)
         #[arg(long)]
         query: String,
 
         /// Maximum number of pages to fetch (0 = unlimited)
         #[arg(long, default_value = "100")]
         limit: usize,
+
+        /// Parameter condition for template-param: `param` (set), `!param`
+        /// (missing or empty), `param=value` or `param~text`
+        #[arg(long = "where", value_name = "CONDITION")]
+        condition: Option<String>,
+
+        #[command(flatten)]
+        options: commands::list::ListOptions,
     },
 
     /// Run editing workflow with a profile
@@ -55,21 +85,46 @@ enum Commands {
         #[arg(long)]
         wiki: Url,
 
-        /// Profile file path (TOML)
-        #[arg(long)]
-        profile: PathBuf,
+        #[command(flatten)]
+        profile: commands::rule_source::ProfileArgs,
 
         /// Batch mode (auto-save all changes)
         #[arg(long)]
         batch: bool,
 
+        /// In batch mode, save edits that shrink a page sharply, remove all
+        /// its categories or empty its lead section
+        #[arg(long, requires = "batch")]
+        allow_content_loss: bool,
+
         /// Dry-run mode (show diffs without saving)
         #[arg(long)]
         dry_run: bool,
 
+        /// Write a unified diff per page and an index.json to this directory
+        #[arg(long, requires = "dry_run")]
+        diff_dir: Option<PathBuf>,
+
+        /// Page list file, one title per line (e.g. saved by `list --output`)
+        #[arg(long)]
+        pages: Option<PathBuf>,
+
+        #[command(flatten)]
+        sample: commands::sample::SampleArgs,
+
         /// Profile ID for credentials
         #[arg(long, default_value = "default")]
         auth_profile: String,
+
+        /// Run the plugins in this directory after the general fixes. Each
+        /// must be signed by a key in the trust store (`plugin trust add`).
+        #[arg(long, value_name = "DIR")]
+        plugins: Option<PathBuf>,
+
+        /// Reload plugins edited in the `--plugins` directory between pages.
+        /// Edited plugins must be signed again and pass their manifest tests.
+        #[arg(long, requires = "plugins")]
+        watch_plugins: bool,
     },
 
     /// Export telemetry log
@@ -81,6 +136,32 @@ enum Commands {
         /// Output file path
         #[arg(long)]
         output: PathBuf,
+
+        /// JSON event log to read (one event per line)
+        #[arg(long)]
+        input: Option<PathBuf>,
+
+        /// Only export events of this type (repeatable): session, page,
+        /// edit, rule, api, warning, error, throttle
+        #[arg(long = "type", value_name = "TYPE")]
+        kinds: Vec<awb_telemetry::EventKind>,
+
+        /// Only export events at or after this RFC 3339 time
+        #[arg(long)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Only export events before this RFC 3339 time
+        #[arg(long)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Only export events about this page
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Only export page events with this plugin metadata, as `key` or
+        /// `key=value`
+        #[arg(long, value_name = "KEY[=VALUE]")]
+        metadata: Option<String>,
     },
 
     /// Run bot mode (unattended batch editing)
@@ -89,9 +170,20 @@ enum Commands {
         #[arg(long)]
         wiki: Url,
 
-        /// Profile file path (TOML)
+        #[command(flatten)]
+        profile: commands::rule_source::ProfileArgs,
+
+        /// Bot config file (TOML). Flags override its settings, except for
+        /// the conditions in its [compliance] block
         #[arg(long)]
-        profile: PathBuf,
+        config: Option<PathBuf>,
+
+        /// Page list file, one title per line (e.g. saved by `list --output`)
+        #[arg(long)]
+        pages: Option<PathBuf>,
+
+        #[command(flatten)]
+        sample: commands::sample::SampleArgs,
 
         /// Maximum number of edits (default: unlimited)
         #[arg(long)]
@@ -101,6 +193,10 @@ enum Commands {
         #[arg(long)]
         dry_run: bool,
 
+        /// Write a unified diff per page and an index.json to this directory
+        #[arg(long, requires = "dry_run")]
+        diff_dir: Option<PathBuf>,
+
         /// Checkpoint file path for resume capability
         #[arg(long)]
         checkpoint: Option<PathBuf>,
@@ -117,14 +213,552 @@ enum Commands {
         #[arg(long)]
         skip_on_warning: bool,
 
-        /// Log progress every N pages
-        #[arg(long, default_value = "10")]
-        log_every_n: u32,
+        /// Skip pages that a second pass of rules and fixes would change again
+        #[arg(long)]
+        verify_idempotent: bool,
+
+        /// Save edits that shrink a page sharply, remove all its categories
+        /// or empty its lead section, for runs meant to remove content
+        #[arg(long)]
+        allow_content_loss: bool,
+
+        /// Log progress every N pages (default: 10)
+        #[arg(long)]
+        log_every_n: Option<u32>,
+
+        /// Transform up to N pages at once (dry-run only; default: 1)
+        #[arg(long, requires = "dry_run")]
+        jobs: Option<usize>,
+
+        /// Report the memory each rule and fix uses per page. Peaks are only
+        /// counted in builds with the `profile-memory` feature; otherwise
+        /// only text sizes are reported.
+        #[arg(long)]
+        profile_memory: bool,
+
+        /// Change tag to apply to every edit (repeatable); `{rules}` stands
+        /// for the start of the rule set's SHA-256
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Show a terminal dashboard instead of the log: progress, counters,
+        /// throttle and recent log lines, with keys to pause, resume and stop
+        #[arg(long)]
+        tui: bool,
+
+        /// Accept `botctl` commands on this Unix socket (a named pipe such as
+        /// `\\.\pipe\awb-bot` on Windows)
+        #[arg(long)]
+        control_socket: Option<PathBuf>,
+
+        /// Keep the original wikitext of every saved page in this directory,
+        /// compressed, for `restore`
+        #[arg(long, value_name = "DIR", conflicts_with = "dry_run")]
+        snapshot_dir: Option<PathBuf>,
+
+        /// Run the plugins in this directory after the general fixes. Each
+        /// must be signed by a key in the trust store (`plugin trust add`).
+        #[arg(long, value_name = "DIR")]
+        plugins: Option<PathBuf>,
+
+        /// Reload plugins edited in the `--plugins` directory between pages.
+        /// Edited plugins must be signed again and pass their manifest tests.
+        #[arg(long, requires = "plugins")]
+        watch_plugins: bool,
+    },
+
+    /// Put back the text a page had before a bot run, from the run's
+    /// snapshots (`bot --snapshot-dir`)
+    Restore {
+        /// Wiki API URL
+        #[arg(long)]
+        wiki: Url,
+
+        /// Snapshot directory of the run
+        #[arg(long, value_name = "DIR")]
+        run: PathBuf,
+
+        /// Page to restore (repeatable)
+        #[arg(long = "page", value_name = "TITLE", required = true)]
+        pages: Vec<String>,
+
+        /// Restore pages edited since the run too, discarding those edits
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Config file holding auth profiles (default: ~/.awb-rs/config.toml)
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Profile ID for credentials
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+    },
+
+    /// Run bot tasks on cron schedules, each with its own checkpoint and
+    /// reports
+    Schedule {
+        /// Schedule file (TOML): an optional `state_dir` and a `[[task]]`
+        /// table per task with `name`, `cron`, `wiki`, `profile` and `pages`
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+
+        #[command(subcommand)]
+        command: Option<ScheduleCommands>,
+    },
+
+    /// Query or control a bot started with `bot --control-socket`
+    Botctl {
+        /// The running bot's control socket or named pipe
+        #[arg(long)]
+        socket: PathBuf,
+
+        command: BotctlCommand,
+    },
+
+    /// Run one profile across several wikis, each with its own page list,
+    /// throttle and report
+    Multirun {
+        /// Wiki list (TOML): a `[[wiki]]` table per wiki with `api_url`,
+        /// `pages` and an optional `auth_profile`
+        #[arg(long, value_name = "FILE")]
+        wikis: PathBuf,
+
+        #[command(flatten)]
+        profile: commands::rule_source::ProfileArgs,
+
+        /// Bot config file (TOML) shared by every wiki
+        #[arg(long)]
+        bot_config: Option<PathBuf>,
+
+        /// Maximum number of edits per wiki (default: unlimited)
+        #[arg(long)]
+        max_edits: Option<u32>,
+
+        /// Dry-run mode (show diffs without saving)
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write diffs to a subdirectory per wiki of this directory
+        #[arg(long, requires = "dry_run")]
+        diff_dir: Option<PathBuf>,
+
+        /// Profile ID for credentials on wikis without their own
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+
+        /// Change tag to apply to every edit (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+
+    /// Fix double redirects and bypass redirects in links on chosen pages
+    FixRedirects {
+        /// Wiki API URL
+        #[arg(long)]
+        wiki: Url,
+
+        /// Maximum number of double redirects to fix (0 = unlimited)
+        #[arg(long, default_value = "100")]
+        limit: u32,
+
+        /// Only bypass redirects; leave double redirects alone
+        #[arg(long)]
+        skip_double_redirects: bool,
+
+        /// Page whose links to redirects are pointed at their targets
+        /// (repeatable)
+        #[arg(long, value_name = "TITLE")]
+        bypass_on: Vec<String>,
+
+        /// File of pages to bypass redirects on, one title per line
+        #[arg(long, value_name = "FILE")]
+        bypass_pages: Option<PathBuf>,
+
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Config file holding auth profiles (default: ~/.awb-rs/config.toml)
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Profile ID for credentials
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+    },
+
+    /// Add or remove {{Orphan}} and {{Dead end}} tags from link counts
+    MaintenanceTags {
+        /// Wiki API URL
+        #[arg(long)]
+        wiki: Url,
+
+        /// Page to check (repeatable)
+        #[arg(long, value_name = "TITLE")]
+        page: Vec<String>,
+
+        /// File of pages to check, one title per line
+        #[arg(long, value_name = "FILE")]
+        pages: Option<PathBuf>,
+
+        /// Tags to maintain, comma-separated (orphan, dead-end)
+        #[arg(long, value_delimiter = ',', default_value = "orphan,dead-end")]
+        tags: Vec<awb_engine::maintenance_tags::MaintenanceTag>,
+
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Config file holding auth profiles (default: ~/.awb-rs/config.toml)
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Profile ID for credentials
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+    },
+
+    /// Replace one file with another in links, galleries and infoboxes
+    ReplaceFile {
+        /// Wiki API URL
+        #[arg(long)]
+        wiki: Url,
+
+        /// File to replace (with or without the File: prefix)
+        #[arg(long)]
+        old: String,
+
+        /// File to use instead
+        #[arg(long)]
+        new: String,
+
+        /// Page to edit (repeatable); default: every page using the old file
+        #[arg(long, value_name = "TITLE")]
+        page: Vec<String>,
+
+        /// File of pages to edit, one title per line
+        #[arg(long, value_name = "FILE")]
+        pages: Option<PathBuf>,
+
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Config file holding auth profiles (default: ~/.awb-rs/config.toml)
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Profile ID for credentials
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+    },
+
+    /// Run a profile against saved pages and compare with expected output
+    Corpus {
+        /// Profile file path (TOML)
+        #[arg(long)]
+        profile: PathBuf,
+
+        /// Directory of <page>.input.wiki and <page>.expected.wiki files
+        #[arg(long)]
+        dir: PathBuf,
+
+        /// Profile ID whose rules are run
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+
+        /// Write the current output as the expected output instead of failing
+        #[arg(long)]
+        update: bool,
+    },
+
+    /// Time a profile on saved pages and estimate the cost of a large run
+    Bench {
+        /// Profile file path (TOML)
+        #[arg(long)]
+        profile: PathBuf,
+
+        /// Directory of .wiki files
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Profile ID whose rules are run
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+
+        /// Runs per page; the fastest is kept
+        #[arg(long, default_value = "3")]
+        iterations: u32,
+    },
+
+    /// Run a profile over a page's past revisions and check the output is
+    /// stable
+    Replay {
+        /// Wiki API URL
+        #[arg(long)]
+        wiki: Url,
+
+        /// Page whose history is replayed
+        #[arg(long, value_name = "TITLE")]
+        page: String,
+
+        /// How many of the latest revisions to replay
+        #[arg(long, default_value = "50")]
+        revisions: usize,
+
+        /// Profile file path (TOML)
+        #[arg(long)]
+        profile: PathBuf,
+
+        /// Profile ID whose rules are run and whose connection settings
+        /// reach the wiki
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+    },
+
+    /// Dry-run two profiles over the same pages and report where their
+    /// results differ
+    CompareProfiles {
+        /// Wiki API URL
+        #[arg(long)]
+        wiki: Url,
+
+        /// Profile file path of the current profile (TOML)
+        #[arg(long = "a", value_name = "PROFILE")]
+        profile_a: PathBuf,
+
+        /// Profile file path of the profile compared with it (TOML)
+        #[arg(long = "b", value_name = "PROFILE")]
+        profile_b: PathBuf,
+
+        /// Page list file, one title per line
+        #[arg(long)]
+        list: PathBuf,
+
+        /// Profile ID whose rules are run and whose connection settings
+        /// reach the wiki
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+
+        /// Write the report as JSON to this file
+        #[arg(long)]
+        output: Option<PathBuf>,
     },
 
+    /// Check a profile file for unknown keys, invalid values and conflicts
+    Validate {
+        /// Profile file path (TOML)
+        #[arg(long)]
+        profile: PathBuf,
+    },
+
+    /// Report rules that overlap or rewrite each other's output
+    LintRules {
+        /// Profile file path (TOML)
+        #[arg(long)]
+        profile: PathBuf,
+
+        /// Profile ID whose rules are checked
+        #[arg(long, default_value = "default")]
+        auth_profile: String,
+    },
+
+    /// Preferences in ~/.awb-rs/config.toml
+    #[command(subcommand)]
+    Config(ConfigCommands),
+
+    /// Credential store management
+    #[command(subcommand)]
+    Credentials(CredentialsCommands),
+
+    /// Inspect exported telemetry logs
+    #[command(subcommand)]
+    Log(LogCommands),
+
     /// OAuth authentication management
     #[command(subcommand)]
     OAuth(OAuthCommands),
+
+    /// Install, update and test plugins
+    #[command(subcommand)]
+    Plugin(PluginCommands),
+
+    /// Serve the engine over JSON-RPC for editors and other tools
+    Serve {
+        /// Read requests from stdin and write responses to stdout, one per line
+        #[arg(long)]
+        stdio: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// Show each task's last run and when it is next due
+    Status,
+}
+
+#[derive(Subcommand)]
+enum PluginCommands {
+    /// Run a plugin against its test cases without contacting a wiki
+    Test {
+        /// Plugin file or directory containing a single plugin
+        path: PathBuf,
+    },
+
+    /// Download a plugin bundle, verify it and install it
+    Install {
+        /// URL or path of the bundle descriptor, or a plugin name to look
+        /// up in `--registry`
+        bundle: String,
+
+        /// Registry base URL; a name resolves to `<registry>/<name>.json`
+        #[arg(long)]
+        registry: Option<Url>,
+
+        /// Trust store to check the bundle's signatures against (default:
+        /// ~/.awb-rs/trusted-keys.toml)
+        #[arg(long)]
+        trust_store: Option<PathBuf>,
+
+        /// Plugin directory (default: ~/.awb-rs/plugins)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// List the plugins installed from bundles
+    List {
+        /// Plugin directory (default: ~/.awb-rs/plugins)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Remove a plugin installed from a bundle
+    Remove {
+        /// Plugin file name, e.g. dates.lua
+        name: String,
+
+        /// Plugin directory (default: ~/.awb-rs/plugins)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Fetch installed plugins again from where they came from and install
+    /// the ones that changed
+    Update {
+        /// Plugin to update (default: all installed plugins)
+        name: Option<String>,
+
+        /// Trust store to check the bundles' signatures against (default:
+        /// ~/.awb-rs/trusted-keys.toml)
+        #[arg(long)]
+        trust_store: Option<PathBuf>,
+
+        /// Plugin directory (default: ~/.awb-rs/plugins)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Check a plugin file's minisign signature (`<file>.minisig`) against
+    /// the trusted keys
+    Verify {
+        /// Plugin file
+        path: PathBuf,
+
+        /// Trust store file (default: ~/.awb-rs/trusted-keys.toml)
+        #[arg(long)]
+        trust_store: Option<PathBuf>,
+    },
+
+    /// Manage the publisher keys trusted to sign plugins
+    #[command(subcommand)]
+    Trust(TrustCommands),
+}
+
+#[derive(Subcommand)]
+enum TrustCommands {
+    /// Trust a minisign public key
+    Add {
+        /// Public key file (`minisign.pub`) or its base64 key line
+        key: String,
+
+        /// Who the key belongs to
+        #[arg(long)]
+        name: String,
+
+        /// Trust store file (default: ~/.awb-rs/trusted-keys.toml)
+        #[arg(long)]
+        trust_store: Option<PathBuf>,
+    },
+
+    /// List the trusted keys
+    List {
+        /// Trust store file (default: ~/.awb-rs/trusted-keys.toml)
+        #[arg(long)]
+        trust_store: Option<PathBuf>,
+    },
+
+    /// Stop trusting a key
+    Remove {
+        /// Key ID or name
+        key: String,
+
+        /// Trust store file (default: ~/.awb-rs/trusted-keys.toml)
+        #[arg(long)]
+        trust_store: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the preferences
+    Show {
+        /// Merge in the wiki and profile overrides and show where each
+        /// value comes from
+        #[arg(long)]
+        effective: bool,
+
+        /// Profile whose overrides (and wiki) apply
+        #[arg(long, requires = "effective")]
+        profile: Option<String>,
+
+        /// Wiki host whose overrides apply (default: the profile's wiki)
+        #[arg(long, requires = "effective")]
+        wiki: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LogCommands {
+    /// Run a read-only SQL query against a SQLite log export
+    /// (table `events`: type, kind, timestamp, title, details)
+    Query {
+        /// SQL to run, e.g. "SELECT date(timestamp), count(*) FROM events GROUP BY 1"
+        sql: String,
+
+        /// Database written by `export-log --format sqlite`
+        #[arg(long)]
+        db: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum CredentialsCommands {
+    /// Move all stored credentials to another store, verify them, then
+    /// wipe the source
+    Migrate {
+        /// Store to move credentials out of (file or keyring)
+        #[arg(long)]
+        from: awb_security::CredentialBackend,
+
+        /// Store to move credentials into (file or keyring)
+        #[arg(long)]
+        to: awb_security::CredentialBackend,
+    },
 }
 
 #[derive(Subcommand)]
@@ -172,6 +806,34 @@ enum ListSource {
     File,
     Watchlist,
     UserContribs,
+    /// PetScan saved query ID
+    Petscan,
+    /// Quarry query ID, result URL, or downloaded CSV/TSV/JSON result
+    Quarry,
+    /// Transclusions of a template whose parameters meet `--where`
+    TemplateParam,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum BotctlCommand {
+    /// Show progress, the page in flight and any throttle wait
+    Status,
+    /// Hold the run before its next page
+    Pause,
+    Resume,
+    /// Emergency stop: abandon the page in flight and save the checkpoint
+    Stop,
+}
+
+impl From<BotctlCommand> for awb_bot::ControlCommand {
+    fn from(command: BotctlCommand) -> Self {
+        match command {
+            BotctlCommand::Status => Self::Status,
+            BotctlCommand::Pause => Self::Pause,
+            BotctlCommand::Resume => Self::Resume,
+            BotctlCommand::Stop => Self::Stop,
+        }
+    }
 }
 
 #[derive(Clone, Debug, clap::ValueEnum)]
@@ -179,64 +841,326 @@ enum ExportFormat {
     Json,
     Csv,
     Plain,
+    /// SQLite database, appended to if it exists
+    Sqlite,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize telemetry
-    awb_telemetry::init_telemetry(&awb_telemetry::TelemetryConfig {
-        log_dir: "logs".into(),
-        level: tracing::Level::INFO,
-        json_output: true,
-        human_output: true,
-    })?;
+    let mut dashboard_logs = None;
+    if matches!(cli.command, Commands::Bot { tui: true, .. }) {
+        // The dashboard owns the screen and shows the log in a pane
+        use tracing_subscriber::prelude::*;
+        let (log_layer, log_receiver) = awb_telemetry::channel_layer(1024);
+        tracing_subscriber::registry()
+            .with(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+            )
+            .with(log_layer)
+            .init();
+        dashboard_logs = Some(log_receiver);
+    } else if matches!(cli.command, Commands::Serve { .. }) {
+        // stdout carries the JSON-RPC stream, so logs go to stderr
+        tracing_subscriber::fmt()
+            .with_env_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+            )
+            .with_writer(awb_telemetry::redacting(std::io::stderr))
+            .init();
+    } else {
+        awb_telemetry::init_telemetry(&awb_telemetry::TelemetryConfig {
+            log_dir: "logs".into(),
+            level: tracing::Level::INFO,
+            json_output: true,
+            human_output: true,
+        })?;
+    }
 
-    let cli = Cli::parse();
+    if let Some(path) = cli.trace_api {
+        commands::api_trace::enable(path)?;
+    }
 
     match cli.command {
         Commands::Login {
             wiki,
             username,
             profile,
-        } => commands::login::run(wiki, username, profile).await,
+            http_user,
+        } => commands::login::run(wiki, username, profile, http_user).await,
         Commands::List {
             wiki,
+            connection,
             source,
             query,
             limit,
-        } => commands::list::run(wiki, source, query, limit).await,
+            condition,
+            options,
+        } => commands::list::run(wiki, connection, source, query, limit, condition, options).await,
         Commands::Run {
             wiki,
             profile,
             batch,
+            allow_content_loss,
             dry_run,
+            diff_dir,
+            pages,
+            sample,
             auth_profile,
-        } => commands::run::run(wiki, profile, batch, dry_run, auth_profile).await,
-        Commands::ExportLog { format, output } => commands::export::run(format, output).await,
+            plugins,
+            watch_plugins,
+        } => {
+            commands::run::run(commands::run::RunArgs {
+                wiki,
+                profile,
+                batch,
+                allow_content_loss,
+                dry_run,
+                diff_dir,
+                pages,
+                sample,
+                auth_profile,
+                plugins,
+                watch_plugins,
+            })
+            .await
+        }
+        Commands::ExportLog {
+            format,
+            output,
+            input,
+            kinds,
+            since,
+            until,
+            title,
+            metadata,
+        } => {
+            let filter = awb_telemetry::ExportFilter {
+                kinds,
+                since,
+                until,
+                title,
+                metadata,
+            };
+            commands::export::run(format, output, input, filter).await
+        }
         Commands::Bot {
             wiki,
             profile,
+            config,
+            pages,
+            sample,
             max_edits,
             dry_run,
+            diff_dir,
             checkpoint,
             auth_profile,
             skip_no_change,
             skip_on_warning,
+            verify_idempotent,
+            allow_content_loss,
             log_every_n,
+            jobs,
+            profile_memory,
+            tags,
+            tui: _,
+            control_socket,
+            snapshot_dir,
+            plugins,
+            watch_plugins,
+        } => commands::bot::run(commands::bot::BotRunArgs {
+            wiki,
+            profile,
+            config,
+            pages,
+            sample,
+            max_edits,
+            dry_run,
+            diff_dir,
+            checkpoint_path: checkpoint,
+            auth_profile,
+            skip_no_change,
+            skip_on_warning,
+            verify_idempotent,
+            allow_content_loss,
+            log_every_n,
+            jobs,
+            profile_memory,
+            tags,
+            dashboard_logs,
+            control_socket,
+            snapshot_dir,
+            plugins,
+            watch_plugins,
+            report_dir: None,
+        })
+        .await
+        .map(|_| ()),
+        Commands::Restore {
+            wiki,
+            run,
+            pages,
+            force,
+            dry_run,
+            config,
+            auth_profile,
         } => {
-            commands::bot::run(commands::bot::BotRunArgs {
+            commands::restore::run(commands::restore::RestoreArgs {
                 wiki,
-                profile_path: profile,
+                run,
+                pages,
+                force,
+                dry_run,
+                config,
+                auth_profile,
+            })
+            .await
+        }
+        Commands::Schedule { config, command } => match command {
+            None => commands::schedule::run(config).await,
+            Some(ScheduleCommands::Status) => commands::schedule::status(config).await,
+        },
+        Commands::Botctl { socket, command } => commands::botctl::run(socket, command.into()).await,
+        Commands::Multirun {
+            wikis,
+            profile,
+            bot_config,
+            max_edits,
+            dry_run,
+            diff_dir,
+            auth_profile,
+            tags,
+        } => {
+            commands::multirun::run(commands::multirun::MultiRunArgs {
+                wikis,
+                profile,
+                bot_config,
                 max_edits,
                 dry_run,
-                checkpoint_path: checkpoint,
+                diff_dir,
                 auth_profile,
-                skip_no_change,
-                skip_on_warning,
-                log_every_n,
+                tags,
             })
             .await
         }
+        Commands::FixRedirects {
+            wiki,
+            limit,
+            skip_double_redirects,
+            bypass_on,
+            bypass_pages,
+            dry_run,
+            config,
+            auth_profile,
+        } => {
+            commands::fix_redirects::run(commands::fix_redirects::FixRedirectsArgs {
+                wiki,
+                limit,
+                skip_double_redirects,
+                bypass_on,
+                bypass_pages,
+                dry_run,
+                config,
+                auth_profile,
+            })
+            .await
+        }
+        Commands::MaintenanceTags {
+            wiki,
+            page,
+            pages,
+            tags,
+            dry_run,
+            config,
+            auth_profile,
+        } => {
+            commands::maintenance_tags::run(commands::maintenance_tags::MaintenanceTagsArgs {
+                wiki,
+                page,
+                pages,
+                tags,
+                dry_run,
+                config,
+                auth_profile,
+            })
+            .await
+        }
+        Commands::ReplaceFile {
+            wiki,
+            old,
+            new,
+            page,
+            pages,
+            dry_run,
+            config,
+            auth_profile,
+        } => {
+            commands::replace_file::run(commands::replace_file::ReplaceFileArgs {
+                wiki,
+                old,
+                new,
+                page,
+                pages,
+                dry_run,
+                config,
+                auth_profile,
+            })
+            .await
+        }
+        Commands::Corpus {
+            profile,
+            dir,
+            auth_profile,
+            update,
+        } => commands::corpus::run(profile, dir, auth_profile, update).await,
+        Commands::Bench {
+            profile,
+            input,
+            auth_profile,
+            iterations,
+        } => commands::bench::run(profile, input, auth_profile, iterations).await,
+        Commands::Replay {
+            wiki,
+            page,
+            revisions,
+            profile,
+            auth_profile,
+        } => commands::replay::run(wiki, page, revisions, profile, auth_profile).await,
+        Commands::CompareProfiles {
+            wiki,
+            profile_a,
+            profile_b,
+            list,
+            auth_profile,
+            output,
+        } => {
+            commands::compare_profiles::run(wiki, profile_a, profile_b, list, auth_profile, output)
+                .await
+        }
+        Commands::Validate { profile } => commands::validate::run(profile).await,
+        Commands::LintRules {
+            profile,
+            auth_profile,
+        } => commands::lint_rules::run(profile, auth_profile).await,
+        Commands::Config(config_cmd) => match config_cmd {
+            ConfigCommands::Show {
+                effective,
+                profile,
+                wiki,
+            } => commands::config::show(effective, wiki, profile).await,
+        },
+        Commands::Log(log_cmd) => match log_cmd {
+            LogCommands::Query { sql, db } => commands::log::query(db, sql).await,
+        },
+        Commands::Credentials(credentials_cmd) => match credentials_cmd {
+            CredentialsCommands::Migrate { from, to } => {
+                commands::credentials::migrate(from, to).await
+            }
+        },
         Commands::OAuth(oauth_cmd) => match oauth_cmd {
             OAuthCommands::Setup {
                 wiki,
@@ -250,5 +1174,36 @@ async fn main() -> Result<()> {
                 profile,
             } => commands::oauth::authorize(wiki, client_id, profile).await,
         },
+        Commands::Plugin(plugin_cmd) => match plugin_cmd {
+            PluginCommands::Test { path } => commands::plugin::test(path).await,
+            PluginCommands::Install {
+                bundle,
+                registry,
+                trust_store,
+                dir,
+            } => commands::plugin::install(bundle, registry, trust_store, dir).await,
+            PluginCommands::List { dir } => commands::plugin::list(dir),
+            PluginCommands::Remove { name, dir } => commands::plugin::remove(name, dir),
+            PluginCommands::Update {
+                name,
+                trust_store,
+                dir,
+            } => commands::plugin::update(name, trust_store, dir).await,
+            PluginCommands::Verify { path, trust_store } => {
+                commands::plugin::verify(path, trust_store)
+            }
+            PluginCommands::Trust(trust_cmd) => match trust_cmd {
+                TrustCommands::Add {
+                    key,
+                    name,
+                    trust_store,
+                } => commands::plugin::trust_add(key, name, trust_store),
+                TrustCommands::List { trust_store } => commands::plugin::trust_list(trust_store),
+                TrustCommands::Remove { key, trust_store } => {
+                    commands::plugin::trust_remove(key, trust_store)
+                }
+            },
+        },
+        Commands::Serve { stdio } => commands::serve::run(stdio).await,
     }
 }
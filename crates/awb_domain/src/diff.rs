@@ -11,19 +11,39 @@ pub enum DiffOp {
     Insert {
         new_range: Range<usize>,
         text: String,
+        /// The rule or fix that made the change, when known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
     },
     Delete {
         old_range: Range<usize>,
         text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
     },
     Replace {
         old_range: Range<usize>,
         new_range: Range<usize>,
         old_text: String,
         new_text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
     },
 }
 
+impl DiffOp {
+    /// The rule or fix that made this change, if known. Always `None` for
+    /// [`DiffOp::Equal`].
+    pub fn origin(&self) -> Option<&str> {
+        match self {
+            DiffOp::Equal { .. } => None,
+            DiffOp::Insert { origin, .. }
+            | DiffOp::Delete { origin, .. }
+            | DiffOp::Replace { origin, .. } => origin.as_deref(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleDiffStat {
     pub rule_id: uuid::Uuid,
@@ -44,6 +64,9 @@ pub struct DiffLine {
     pub text: String,
     pub change_type: ChangeType,
     pub inline_changes: Vec<Range<usize>>,
+    /// The rule or fix that changed this line, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -85,10 +108,13 @@ mod tests {
         let op = DiffOp::Insert {
             new_range: 5..15,
             text: "inserted".to_string(),
+            origin: None,
         };
 
         match op {
-            DiffOp::Insert { new_range, text } => {
+            DiffOp::Insert {
+                new_range, text, ..
+            } => {
                 assert_eq!(new_range, 5..15);
                 assert_eq!(text, "inserted");
             }
@@ -101,10 +127,13 @@ mod tests {
         let op = DiffOp::Delete {
             old_range: 10..20,
             text: "deleted".to_string(),
+            origin: None,
         };
 
         match op {
-            DiffOp::Delete { old_range, text } => {
+            DiffOp::Delete {
+                old_range, text, ..
+            } => {
                 assert_eq!(old_range, 10..20);
                 assert_eq!(text, "deleted");
             }
@@ -119,6 +148,7 @@ mod tests {
             new_range: 0..8,
             old_text: "old".to_string(),
             new_text: "new text".to_string(),
+            origin: None,
         };
 
         match op {
@@ -127,6 +157,7 @@ mod tests {
                 new_range,
                 old_text,
                 new_text,
+                ..
             } => {
                 assert_eq!(old_range, 0..5);
                 assert_eq!(new_range, 0..8);
@@ -165,6 +196,7 @@ mod tests {
             text: "test line".to_string(),
             change_type: ChangeType::Modified,
             inline_changes: vec![5..10],
+            origin: None,
         };
 
         assert_eq!(line.line_no, 42);
@@ -181,12 +213,14 @@ mod tests {
                 text: "left".to_string(),
                 change_type: ChangeType::Equal,
                 inline_changes: vec![],
+                origin: None,
             }),
             right: Some(DiffLine {
                 line_no: 1,
                 text: "left".to_string(),
                 change_type: ChangeType::Equal,
                 inline_changes: vec![],
+                origin: None,
             }),
         };
 
@@ -202,6 +236,7 @@ mod tests {
                 text: "deleted".to_string(),
                 change_type: ChangeType::Removed,
                 inline_changes: vec![],
+                origin: None,
             }),
             right: None,
         };
@@ -215,17 +250,38 @@ mod tests {
         let op = DiffOp::Insert {
             new_range: 0..5,
             text: "test".to_string(),
+            origin: Some("typo fixing".to_string()),
         };
 
         let json = serde_json::to_string(&op).unwrap();
         let deserialized: DiffOp = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.origin(), Some("typo fixing"));
 
         match deserialized {
-            DiffOp::Insert { new_range, text } => {
+            DiffOp::Insert {
+                new_range, text, ..
+            } => {
                 assert_eq!(new_range, 0..5);
                 assert_eq!(text, "test");
             }
             _ => panic!("Deserialization changed op type"),
         }
     }
+
+    #[test]
+    fn test_diff_op_without_origin_omits_it() {
+        let op = DiffOp::Delete {
+            old_range: 0..4,
+            text: "old\n".to_string(),
+            origin: None,
+        };
+        let json = serde_json::to_string(&op).unwrap();
+        assert!(!json.contains("origin"));
+
+        // Ops serialized before origins existed still load
+        let op: DiffOp =
+            serde_json::from_str(r#"{"Delete":{"old_range":{"start":0,"end":4},"text":"old\n"}}"#)
+                .unwrap();
+        assert_eq!(op.origin(), None);
+    }
 }
@@ -1,6 +1,10 @@
 pub mod diff;
+pub mod page_list;
 pub mod profile;
 pub mod rules;
 pub mod session;
+pub mod siteinfo;
+pub mod summary;
 pub mod types;
+pub mod units;
 pub mod warnings;
@@ -0,0 +1,460 @@
+//! Ordered lists of pages to work on.
+//!
+//! Pages are compared by namespace and normalized name, so `foo_bar`,
+//! `Foo bar` and `Foo_bar` are the same page while `Talk:Foo` is not `Foo`.
+//! Saved lists are plain text, one title per line; blank lines and lines
+//! starting with `#` are ignored when loading.
+
+use crate::siteinfo::SiteInfo;
+use crate::types::{Namespace, Title, TitleError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+/// How [`PageList::sort`] orders pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// By title as displayed
+    Alphabetical,
+    /// By namespace number, then name
+    Namespace,
+    /// Shuffled; the same seed always gives the same order
+    Random { seed: u64 },
+}
+
+/// Accepted names for [`SortMode`] (random uses seed 0 unless set)
+pub const SORT_MODES: &[&str] = &["alphabetical", "namespace", "random"];
+
+impl FromStr for SortMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "alphabetical" | "alpha" => Ok(Self::Alphabetical),
+            "namespace" | "ns" => Ok(Self::Namespace),
+            "random" => Ok(Self::Random { seed: 0 }),
+            other => Err(format!(
+                "unknown sort mode '{}' (expected one of: {})",
+                other,
+                SORT_MODES.join(", ")
+            )),
+        }
+    }
+}
+
+/// How many pages [`PageList::sample`] picks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleSize {
+    Count(usize),
+    /// Percentage of the list, 0-100; rounds to at least one page
+    Percent(f64),
+}
+
+impl SampleSize {
+    /// Number of pages to sample from a list of `population`
+    pub fn of(self, population: usize) -> usize {
+        match self {
+            Self::Count(count) => count.min(population),
+            Self::Percent(percent) if percent <= 0.0 || population == 0 => 0,
+            Self::Percent(percent) => {
+                let count = (population as f64 * percent.min(100.0) / 100.0).round() as usize;
+                count.clamp(1, population)
+            }
+        }
+    }
+}
+
+/// Pages sampled from one namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stratum {
+    pub namespace: i32,
+    pub population: usize,
+    pub sampled: usize,
+}
+
+/// Record of how a sample was drawn, so a pilot run can be reproduced and
+/// reviewed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleManifest {
+    pub size: SampleSize,
+    pub seed: u64,
+    /// Pages in the list the sample was drawn from
+    pub population: usize,
+    pub strata: Vec<Stratum>,
+    /// Sampled titles, in list order
+    pub titles: Vec<String>,
+}
+
+/// Fisher-Yates shuffle driven by splitmix64, so a seed gives the same order
+/// on every platform and release
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    for i in (1..items.len()).rev() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        items.swap(i, (z % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Pages in the order they will be visited
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageList {
+    titles: Vec<Title>,
+}
+
+impl PageList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse one title per line with `site`'s rules, skipping blank lines
+    /// and `#` comments
+    pub fn parse(site: &SiteInfo, text: &str) -> Result<Self, TitleError> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| Title::parse(site, line))
+            .collect()
+    }
+
+    /// [`PageList::parse`] with the default site rules
+    pub fn from_lines(text: &str) -> Result<Self, TitleError> {
+        Self::parse(&SiteInfo::default(), text)
+    }
+
+    /// Read a list written by [`PageList::save`] (or any one-title-per-line
+    /// file)
+    pub fn load(site: &SiteInfo, path: &Path) -> std::io::Result<Self> {
+        Self::parse(site, &std::fs::read_to_string(path)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write one title per line
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_lines())
+    }
+
+    pub fn to_lines(&self) -> String {
+        self.titles
+            .iter()
+            .map(|title| format!("{}\n", title.display))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.titles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.titles.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Title> {
+        self.titles.iter()
+    }
+
+    pub fn titles(&self) -> &[Title] {
+        &self.titles
+    }
+
+    pub fn push(&mut self, title: Title) {
+        self.titles.push(title);
+    }
+
+    pub fn contains(&self, title: &Title) -> bool {
+        let key = title.key();
+        self.titles.iter().any(|t| t.key() == key)
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.titles.truncate(len);
+    }
+
+    /// Drop repeated pages, keeping the first occurrence of each; returns
+    /// how many were removed
+    pub fn dedupe(&mut self) -> usize {
+        let before = self.titles.len();
+        let mut seen = HashSet::new();
+        self.titles.retain(|title| seen.insert(title.key()));
+        before - self.titles.len()
+    }
+
+    pub fn sort(&mut self, mode: SortMode) {
+        match mode {
+            SortMode::Alphabetical => self.titles.sort_by(|a, b| a.display.cmp(&b.display)),
+            SortMode::Namespace => self.titles.sort_by_key(|title| title.key()),
+            SortMode::Random { seed } => shuffle(&mut self.titles, seed),
+        }
+    }
+
+    /// A deterministic sample of the list, stratified by namespace so each
+    /// namespace keeps its share of the pages. Sampled pages keep their
+    /// order in the list.
+    pub fn sample(&self, size: SampleSize, seed: u64) -> (PageList, SampleManifest) {
+        let population = self.titles.len();
+        let wanted = size.of(population);
+
+        let mut groups: BTreeMap<Namespace, Vec<usize>> = BTreeMap::new();
+        for (index, title) in self.titles.iter().enumerate() {
+            groups.entry(title.namespace).or_default().push(index);
+        }
+
+        // Largest-remainder allocation of `wanted` across namespaces
+        let mut quotas: Vec<(Namespace, usize, usize)> = groups
+            .iter()
+            .map(|(ns, members)| {
+                (
+                    *ns,
+                    members.len(),
+                    wanted * members.len() / population.max(1),
+                )
+            })
+            .collect();
+        let mut remaining = wanted - quotas.iter().map(|(_, _, q)| q).sum::<usize>();
+        let mut by_remainder: Vec<usize> = (0..quotas.len()).collect();
+        by_remainder.sort_by_key(|&i| {
+            let (_, members, _) = quotas[i];
+            std::cmp::Reverse(wanted * members % population.max(1))
+        });
+        for i in by_remainder {
+            if remaining == 0 {
+                break;
+            }
+            quotas[i].2 += 1;
+            remaining -= 1;
+        }
+
+        let mut chosen = Vec::with_capacity(wanted);
+        let mut strata = Vec::with_capacity(quotas.len());
+        for (namespace, members, quota) in quotas {
+            let mut group = groups.remove(&namespace).unwrap_or_default();
+            shuffle(
+                &mut group,
+                seed ^ (namespace.0 as u64).wrapping_mul(0x9E37_79B9),
+            );
+            chosen.extend(group.into_iter().take(quota));
+            strata.push(Stratum {
+                namespace: namespace.0,
+                population: members,
+                sampled: quota,
+            });
+        }
+        chosen.sort_unstable();
+
+        let sample: PageList = chosen.iter().map(|&i| self.titles[i].clone()).collect();
+        let manifest = SampleManifest {
+            size,
+            seed,
+            population,
+            strata,
+            titles: sample.iter().map(|t| t.display.clone()).collect(),
+        };
+        (sample, manifest)
+    }
+
+    /// Pages in `self` or `other`, in that order, without repeats
+    pub fn union(&self, other: &PageList) -> PageList {
+        let mut list: PageList = self.iter().chain(other.iter()).cloned().collect();
+        list.dedupe();
+        list
+    }
+
+    /// Pages of `self` that are also in `other`, in `self`'s order
+    pub fn intersection(&self, other: &PageList) -> PageList {
+        let keys = other.keys();
+        self.filter(|title| keys.contains(&title.key()))
+    }
+
+    /// Pages of `self` that are not in `other`, in `self`'s order
+    pub fn difference(&self, other: &PageList) -> PageList {
+        let keys = other.keys();
+        self.filter(|title| !keys.contains(&title.key()))
+    }
+
+    /// Pages in exactly one of the two lists
+    pub fn symmetric_difference(&self, other: &PageList) -> PageList {
+        self.difference(other).union(&other.difference(self))
+    }
+
+    fn keys(&self) -> HashSet<(Namespace, String)> {
+        self.titles.iter().map(Title::key).collect()
+    }
+
+    fn filter(&self, keep: impl Fn(&Title) -> bool) -> PageList {
+        self.titles.iter().filter(|t| keep(t)).cloned().collect()
+    }
+}
+
+impl FromIterator<Title> for PageList {
+    fn from_iter<I: IntoIterator<Item = Title>>(iter: I) -> Self {
+        Self {
+            titles: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Vec<Title>> for PageList {
+    fn from(titles: Vec<Title>) -> Self {
+        Self { titles }
+    }
+}
+
+/// Parse each string as a title with the default site rules
+impl TryFrom<Vec<String>> for PageList {
+    type Error = TitleError;
+
+    fn try_from(titles: Vec<String>) -> Result<Self, Self::Error> {
+        let site = SiteInfo::default();
+        titles.iter().map(|t| Title::parse(&site, t)).collect()
+    }
+}
+
+impl IntoIterator for PageList {
+    type Item = Title;
+    type IntoIter = std::vec::IntoIter<Title>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.titles.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PageList {
+    type Item = &'a Title;
+    type IntoIter = std::slice::Iter<'a, Title>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.titles.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(titles: &[&str]) -> PageList {
+        let site = SiteInfo::default();
+        titles
+            .iter()
+            .map(|t| Title::parse(&site, t).unwrap())
+            .collect()
+    }
+
+    fn displays(list: &PageList) -> Vec<&str> {
+        list.iter().map(|t| t.display.as_str()).collect()
+    }
+
+    #[test]
+    fn test_dedupe_is_namespace_aware() {
+        let mut pages = list(&["Foo bar", "foo_bar", "Talk:Foo bar", "talk:foo bar", "Baz"]);
+        assert_eq!(pages.dedupe(), 2);
+        assert_eq!(displays(&pages), vec!["Foo bar", "Talk:Foo bar", "Baz"]);
+    }
+
+    #[test]
+    fn test_sort_modes() {
+        let mut pages = list(&["Talk:Alpha", "Zulu", "Category:Beta", "Alpha"]);
+        pages.sort(SortMode::Alphabetical);
+        assert_eq!(
+            displays(&pages),
+            vec!["Alpha", "Category:Beta", "Talk:Alpha", "Zulu"]
+        );
+        pages.sort(SortMode::Namespace);
+        assert_eq!(
+            displays(&pages),
+            vec!["Alpha", "Zulu", "Talk:Alpha", "Category:Beta"]
+        );
+
+        let many = list(&["A", "B", "C", "D", "E", "F", "G", "H"]);
+        let shuffle = |seed| {
+            let mut pages = many.clone();
+            pages.sort(SortMode::Random { seed });
+            pages
+        };
+        assert_eq!(shuffle(7), shuffle(7));
+        assert_ne!(shuffle(7), shuffle(8));
+        let mut sorted = shuffle(7);
+        sorted.sort(SortMode::Alphabetical);
+        assert_eq!(sorted, many);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = list(&["A", "B", "C"]);
+        let b = list(&["c", "D", "b_"]);
+        assert_eq!(displays(&a.union(&b)), vec!["A", "B", "C", "D"]);
+        assert_eq!(displays(&a.intersection(&b)), vec!["B", "C"]);
+        assert_eq!(displays(&a.difference(&b)), vec!["A"]);
+        assert_eq!(
+            displays(&a.symmetric_difference(&list(&["C", "D"]))),
+            vec!["A", "B", "D"]
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("awb-page-list-{}.txt", std::process::id()));
+        let pages = list(&["Foo", "Category:Bar baz"]);
+        pages.save(&path).unwrap();
+        let loaded = PageList::load(&SiteInfo::default(), &path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded, pages);
+
+        let parsed = PageList::from_lines("# saved list\n\nFoo\n  Talk:Bar  \n").unwrap();
+        assert_eq!(displays(&parsed), vec!["Foo", "Talk:Bar"]);
+        assert!(PageList::from_lines("Foo\nfr:Bar").is_err());
+    }
+
+    #[test]
+    fn test_sample_is_deterministic_and_stratified() {
+        let mut titles: Vec<String> = (0..80).map(|i| format!("Page {}", i)).collect();
+        titles.extend((0..20).map(|i| format!("Talk:Page {}", i)));
+        let pages = PageList::try_from(titles).unwrap();
+
+        let (sample, manifest) = pages.sample(SampleSize::Percent(10.0), 42);
+        assert_eq!(sample.len(), 10);
+        assert_eq!(
+            sample
+                .iter()
+                .filter(|t| t.namespace == Namespace::TALK)
+                .count(),
+            2
+        );
+        assert_eq!(manifest.population, 100);
+        assert_eq!(manifest.strata.len(), 2);
+        assert_eq!(manifest.strata[0].sampled, 8);
+        assert_eq!(manifest.titles.len(), 10);
+
+        // Same seed, same sample, in list order
+        assert_eq!(pages.sample(SampleSize::Percent(10.0), 42).0, sample);
+        assert_ne!(pages.sample(SampleSize::Percent(10.0), 43).0, sample);
+        let positions: Vec<usize> = sample
+            .iter()
+            .map(|t| pages.iter().position(|p| p == t).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_sample_size() {
+        assert_eq!(SampleSize::Count(5).of(3), 3);
+        assert_eq!(SampleSize::Percent(0.1).of(50), 1);
+        assert_eq!(SampleSize::Percent(150.0).of(50), 50);
+        assert_eq!(SampleSize::Percent(10.0).of(0), 0);
+
+        let pages = PageList::from_lines("A\nB\nTalk:C").unwrap();
+        assert_eq!(pages.sample(SampleSize::Count(2), 1).0.len(), 2);
+        assert_eq!(pages.sample(SampleSize::Count(9), 1).0, pages);
+    }
+
+    #[test]
+    fn test_sort_mode_from_str() {
+        assert_eq!("namespace".parse(), Ok(SortMode::Namespace));
+        assert_eq!("Random".parse(), Ok(SortMode::Random { seed: 0 }));
+        assert!("size".parse::<SortMode>().is_err());
+    }
+}
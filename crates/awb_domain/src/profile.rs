@@ -1,7 +1,7 @@
 use crate::types::Namespace;
 use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +12,39 @@ pub struct Profile {
     pub auth_method: AuthMethod,
     pub default_namespaces: HashSet<Namespace>,
     pub throttle_policy: ThrottlePolicy,
+    #[serde(default, skip_serializing_if = "ConnectionOptions::is_default")]
+    pub connection: ConnectionOptions,
+}
+
+/// How to reach a self-hosted wiki that sits behind HTTP basic auth, a
+/// proxy expecting extra headers, or an intranet certificate
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionOptions {
+    /// Headers sent with every request
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// HTTP basic auth username. The password is kept in the credential
+    /// store under [`ConnectionOptions::basic_auth_key`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub basic_auth_user: Option<String>,
+    /// Replaces the default AWB-RS user agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Accept certificates that fail verification, e.g. self-signed ones.
+    /// Only for wikis on a trusted network.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub accept_invalid_certs: bool,
+}
+
+impl ConnectionOptions {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Credential store entry holding the basic auth password for a profile
+    pub fn basic_auth_key(profile_id: &str) -> String {
+        format!("{}:http-basic", profile_id)
+    }
 }
 
 #[derive(Clone)]
@@ -313,9 +346,11 @@ mod tests {
             },
             default_namespaces: namespaces.clone(),
             throttle_policy: ThrottlePolicy::default(),
+            connection: ConnectionOptions::default(),
         };
 
         let json = serde_json::to_string(&profile).unwrap();
+        assert!(!json.contains("connection"));
         let deserialized: Profile = serde_json::from_str(&json).unwrap();
 
         assert_eq!(deserialized.id, "enwiki");
@@ -324,6 +359,34 @@ mod tests {
         assert!(deserialized.default_namespaces.contains(&Namespace::MAIN));
     }
 
+    #[test]
+    fn test_profile_connection_options() {
+        let profile: Profile = serde_json::from_value(serde_json::json!({
+            "id": "intranet",
+            "name": "Intranet wiki",
+            "api_url": "https://wiki.corp.example/mediawiki/api.php",
+            "auth_method": { "type": "BotPassword", "username": "Bot" },
+            "default_namespaces": [],
+            "throttle_policy": ThrottlePolicy::default(),
+            "connection": {
+                "headers": { "X-Team": "docs" },
+                "basic_auth_user": "gate",
+                "accept_invalid_certs": true
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(profile.connection.headers["X-Team"], "docs");
+        assert_eq!(profile.connection.basic_auth_user.as_deref(), Some("gate"));
+        assert_eq!(profile.connection.user_agent, None);
+        assert!(profile.connection.accept_invalid_certs);
+        assert!(!profile.connection.is_default());
+        assert_eq!(
+            ConnectionOptions::basic_auth_key("intranet"),
+            "intranet:http-basic"
+        );
+    }
+
     #[test]
     fn test_duration_roundtrip() {
         let original = Duration::from_secs_f64(12.5);
@@ -1,3 +1,5 @@
+use crate::summary::SummaryTemplates;
+use crate::types::Namespace;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -8,6 +10,17 @@ pub struct Rule {
     pub order: u32,
     pub kind: RuleKind,
     pub comment_fragment: Option<String>,
+    /// Namespaces the rule applies in (empty = all)
+    #[serde(default)]
+    pub namespaces: Vec<Namespace>,
+    /// Regex the page name (without namespace prefix) must match for the
+    /// rule to apply
+    #[serde(default)]
+    pub title_pattern: Option<String>,
+    /// The rule is meant to change where wikilinks point, so its edits are
+    /// not held back for changing link targets
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub retargets_links: bool,
 }
 
 impl Rule {
@@ -26,6 +39,9 @@ impl Rule {
                 case_sensitive,
             },
             comment_fragment: None,
+            namespaces: Vec::new(),
+            title_pattern: None,
+            retargets_links: false,
         }
     }
 
@@ -44,8 +60,46 @@ impl Rule {
                 case_insensitive,
             },
             comment_fragment: None,
+            namespaces: Vec::new(),
+            title_pattern: None,
+            retargets_links: false,
         }
     }
+
+    /// Structured edit of JSON content-model pages; see [`JsonPatchOp`]
+    pub fn new_json_patch(operations: Vec<JsonPatchOp>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            enabled: true,
+            order: 0,
+            kind: RuleKind::JsonPatch { operations },
+            comment_fragment: None,
+            namespaces: Vec::new(),
+            title_pattern: None,
+            retargets_links: false,
+        }
+    }
+
+    /// Only apply the rule on pages in `namespaces`
+    #[must_use]
+    pub fn with_namespaces(mut self, namespaces: impl IntoIterator<Item = Namespace>) -> Self {
+        self.namespaces = namespaces.into_iter().collect();
+        self
+    }
+
+    /// Only apply the rule on pages whose name matches `pattern`
+    #[must_use]
+    pub fn with_title_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.title_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Mark the rule as one that changes link targets on purpose
+    #[must_use]
+    pub fn with_retargets_links(mut self, retargets_links: bool) -> Self {
+        self.retargets_links = retargets_links;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,16 +114,50 @@ pub enum RuleKind {
         replacement: String,
         case_insensitive: bool,
     },
+    /// RFC 6902 operations applied, all or nothing, to pages with the JSON
+    /// content model. Text rules leave those pages alone, and this one
+    /// leaves every other page alone.
+    #[serde(alias = "json_patch")]
+    JsonPatch { operations: Vec<JsonPatchOp> },
+}
+
+/// One JSON Patch operation. `path` is a JSON Pointer such as `/rows/0/1`;
+/// `-` as the last segment of an `add` appends to an array.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatchOp {
+    Add {
+        path: String,
+        value: serde_json::Value,
+    },
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        value: serde_json::Value,
+    },
+    /// Stop the rule unless `path` holds `value`
+    Test {
+        path: String,
+        value: serde_json::Value,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleSet {
     pub rules: Vec<Rule>,
+    /// Edit summary templates per wiki and language
+    #[serde(default, skip_serializing_if = "SummaryTemplates::is_empty")]
+    pub summaries: SummaryTemplates,
 }
 
 impl RuleSet {
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            rules: Vec::new(),
+            summaries: SummaryTemplates::default(),
+        }
     }
 
     pub fn enabled_rules(&self) -> impl Iterator<Item = &Rule> {
@@ -139,6 +227,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_json_patch_rule_deserializes() {
+        let rule: Rule = serde_json::from_value(serde_json::json!({
+            "id": "6f2c1f9e-3b1a-4a57-9d7e-1c0b5f7a2d10",
+            "enabled": true,
+            "order": 0,
+            "kind": { "json_patch": { "operations": [
+                { "op": "test", "path": "/license", "value": "CC-BY-SA-3.0" },
+                { "op": "replace", "path": "/license", "value": "CC-BY-SA-4.0" }
+            ] } },
+            "comment_fragment": null
+        }))
+        .unwrap();
+
+        match &rule.kind {
+            RuleKind::JsonPatch { operations } => {
+                assert_eq!(operations.len(), 2);
+                assert_eq!(
+                    operations[1],
+                    JsonPatchOp::Replace {
+                        path: "/license".to_string(),
+                        value: serde_json::json!("CC-BY-SA-4.0"),
+                    }
+                );
+            }
+            _ => panic!("Expected JsonPatch rule"),
+        }
+        // Saved under the variant name, like the other kinds
+        let saved = serde_json::to_value(&rule).unwrap();
+        assert!(saved["kind"].get("JsonPatch").is_some());
+    }
+
     #[test]
     fn test_ruleset_new() {
         let ruleset = RuleSet::new();
@@ -216,6 +336,29 @@ mod tests {
         assert_eq!(ruleset.rules.len(), 1);
     }
 
+    #[test]
+    fn test_rule_scope_builders() {
+        let rule = Rule::new_plain("a", "b", true)
+            .with_namespaces([Namespace::MAIN, Namespace::TEMPLATE])
+            .with_title_pattern("^List of ");
+        assert_eq!(rule.namespaces, vec![Namespace::MAIN, Namespace::TEMPLATE]);
+        assert_eq!(rule.title_pattern.as_deref(), Some("^List of "));
+    }
+
+    #[test]
+    fn test_rule_deserializes_without_scope() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "enabled": true,
+            "order": 0,
+            "kind": { "Plain": { "find": "a", "replace": "b", "case_sensitive": true } },
+            "comment_fragment": null
+        }"#;
+        let rule: Rule = serde_json::from_str(json).unwrap();
+        assert!(rule.namespaces.is_empty());
+        assert!(rule.title_pattern.is_none());
+    }
+
     #[test]
     fn test_rule_serialization() {
         let rule = Rule::new_plain("test", "result", true);
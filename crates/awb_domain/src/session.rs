@@ -4,7 +4,7 @@ use crate::types::*;
 use crate::warnings::Warning;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionState {
@@ -69,6 +69,10 @@ pub struct EditPlan {
     /// Used to enforce WP:COSMETIC — bots should not make cosmetic-only edits.
     #[serde(default)]
     pub is_cosmetic_only: bool,
+    /// Key/value metadata about the page set by plugins (`mw.page.set_property`),
+    /// e.g. `review = "needs human review"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,224 @@
+//! How a wiki names its pages: namespace names and aliases, which
+//! namespaces capitalize the first letter of titles, and which prefixes
+//! are interwiki links rather than namespaces.
+//!
+//! [`SiteInfo::default`] describes a stock English-language MediaWiki; the
+//! real values come from `action=query&meta=siteinfo` and can differ per
+//! wiki (e.g. "Wikipedia:" for the project namespace, or case-sensitive
+//! titles on Wiktionary).
+
+use crate::types::{NAMESPACE_ALIASES, NAMESPACE_NAMES, Namespace};
+use serde::{Deserialize, Serialize};
+
+/// One namespace as the wiki describes it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceInfo {
+    pub id: Namespace,
+    /// Local name used in titles; empty for the main namespace
+    pub name: String,
+    /// Language-independent name, e.g. "Project" where `name` is "Wikipedia"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical: Option<String>,
+    /// Whether titles keep a lowercase first letter
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+impl NamespaceInfo {
+    pub fn new(id: Namespace, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            canonical: None,
+            case_sensitive: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_canonical(mut self, canonical: impl Into<String>) -> Self {
+        self.canonical = Some(canonical.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+}
+
+/// Interwiki prefixes recognized when no siteinfo has been fetched: the
+/// larger Wikipedia languages and the Wikimedia sister projects
+const DEFAULT_INTERWIKI: &[&str] = &[
+    "ar",
+    "b",
+    "c",
+    "commons",
+    "d",
+    "de",
+    "en",
+    "es",
+    "fa",
+    "fr",
+    "it",
+    "ja",
+    "m",
+    "meta",
+    "mw",
+    "n",
+    "nl",
+    "pl",
+    "pt",
+    "q",
+    "ru",
+    "s",
+    "species",
+    "sv",
+    "uk",
+    "v",
+    "voy",
+    "w",
+    "wikidata",
+    "wikinews",
+    "wikiquote",
+    "wikisource",
+    "wikispecies",
+    "wikiversity",
+    "wikivoyage",
+    "wikt",
+    "wiktionary",
+    "zh",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteInfo {
+    pub namespaces: Vec<NamespaceInfo>,
+    /// Extra names for namespaces, e.g. "WP" for the project namespace
+    #[serde(default)]
+    pub aliases: Vec<(String, Namespace)>,
+    /// Interwiki prefixes, lowercase
+    #[serde(default)]
+    pub interwiki: Vec<String>,
+    /// Interwiki prefixes that point back at this wiki (e.g. "en" on the
+    /// English Wikipedia) and are dropped from titles, lowercase
+    #[serde(default)]
+    pub local_interwiki: Vec<String>,
+    /// Content language code, e.g. `de`; `None` when not fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+impl Default for SiteInfo {
+    fn default() -> Self {
+        let namespaces = std::iter::once(NamespaceInfo::new(Namespace::MAIN, ""))
+            .chain(
+                NAMESPACE_NAMES
+                    .iter()
+                    .map(|(name, ns)| NamespaceInfo::new(*ns, *name).with_canonical(*name)),
+            )
+            .collect();
+        Self {
+            namespaces,
+            aliases: NAMESPACE_ALIASES
+                .iter()
+                .map(|(name, ns)| (name.to_string(), *ns))
+                .collect(),
+            interwiki: DEFAULT_INTERWIKI.iter().map(|p| p.to_string()).collect(),
+            local_interwiki: Vec::new(),
+            lang: None,
+        }
+    }
+}
+
+impl SiteInfo {
+    pub fn namespace(&self, id: Namespace) -> Option<&NamespaceInfo> {
+        self.namespaces.iter().find(|ns| ns.id == id)
+    }
+
+    /// Namespace with this local name, canonical name or alias, ignoring
+    /// case and treating underscores as spaces
+    pub fn namespace_by_name(&self, name: &str) -> Option<Namespace> {
+        let name = name.trim().replace('_', " ");
+        if name.is_empty() {
+            return None;
+        }
+        let matches = |known: &str| known.replace('_', " ").eq_ignore_ascii_case(&name);
+        self.namespaces
+            .iter()
+            .find(|ns| matches(&ns.name) || ns.canonical.as_deref().is_some_and(matches))
+            .map(|ns| ns.id)
+            .or_else(|| {
+                self.aliases
+                    .iter()
+                    .find(|(alias, _)| matches(alias))
+                    .map(|(_, ns)| *ns)
+            })
+    }
+
+    /// Prefix used when displaying titles in `namespace`; `None` for the
+    /// main namespace and for namespaces the wiki does not have
+    pub fn prefix(&self, namespace: Namespace) -> Option<&str> {
+        self.namespace(namespace)
+            .map(|ns| ns.name.as_str())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Whether titles in `namespace` keep a lowercase first letter
+    pub fn is_case_sensitive(&self, namespace: Namespace) -> bool {
+        self.namespace(namespace)
+            .is_some_and(|ns| ns.case_sensitive)
+    }
+
+    pub fn is_interwiki(&self, prefix: &str) -> bool {
+        let prefix = prefix.trim();
+        self.interwiki
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(prefix))
+    }
+
+    pub fn is_local_interwiki(&self, prefix: &str) -> bool {
+        let prefix = prefix.trim();
+        self.local_interwiki
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_namespaces() {
+        let site = SiteInfo::default();
+        assert_eq!(
+            site.namespace_by_name("category"),
+            Some(Namespace::CATEGORY)
+        );
+        assert_eq!(
+            site.namespace_by_name("User_talk"),
+            Some(Namespace::USER_TALK)
+        );
+        assert_eq!(site.namespace_by_name("WP"), Some(Namespace::PROJECT));
+        assert_eq!(site.namespace_by_name(""), None);
+        assert_eq!(site.prefix(Namespace::MAIN), None);
+        assert_eq!(site.prefix(Namespace::PROJECT), Some("Project"));
+        assert!(site.is_interwiki("EN"));
+        assert!(!site.is_interwiki("Category"));
+    }
+
+    #[test]
+    fn test_local_names_and_case() {
+        let site = SiteInfo {
+            namespaces: vec![
+                NamespaceInfo::new(Namespace::MAIN, "").with_case_sensitive(true),
+                NamespaceInfo::new(Namespace::PROJECT, "Wiktionary").with_canonical("Project"),
+            ],
+            ..SiteInfo::default()
+        };
+        assert_eq!(site.namespace_by_name("project"), Some(Namespace::PROJECT));
+        assert_eq!(site.prefix(Namespace::PROJECT), Some("Wiktionary"));
+        assert!(site.is_case_sensitive(Namespace::MAIN));
+        assert!(!site.is_case_sensitive(Namespace::PROJECT));
+    }
+}
@@ -0,0 +1,149 @@
+//! Edit summary templates, chosen per wiki and language.
+//!
+//! A template is text with placeholders: `{rules}` for the comments of the
+//! rules that changed the page, `{fixes}` for the names of the general
+//! fixes that did, and `{count}` for how many rules and fixes that is.
+//! Anything else in braces, such as `{{tl|...}}`, is left as written.
+//!
+//! For a wiki, the template for its host is used if there is one, then the
+//! one for its content language, then those of the languages listed as
+//! that language's fallbacks (and theirs in turn), then the base language
+//! of a variant (`pt` for `pt-br`), then `default`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Edit summary templates of a rule set
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummaryTemplates {
+    /// Used when no wiki or language template applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Keyed by wiki host, e.g. `de.wikipedia.org`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub wikis: BTreeMap<String, String>,
+    /// Keyed by content language code, e.g. `de` or `pt-br`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub languages: BTreeMap<String, String>,
+    /// Languages to try, in order, when a language has no template of its
+    /// own, e.g. `als = ["gsw", "de"]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fallbacks: BTreeMap<String, Vec<String>>,
+}
+
+impl SummaryTemplates {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The template for the wiki at `host` whose content language is
+    /// `lang`, or `None` to keep the engine's own summary
+    pub fn resolve(&self, host: Option<&str>, lang: Option<&str>) -> Option<&str> {
+        if let Some(template) = host.and_then(|host| self.wikis.get(&host.to_lowercase())) {
+            return Some(template);
+        }
+        if let Some(lang) = lang {
+            let mut tried = Vec::new();
+            if let Some(template) = self.for_language(&lang.to_lowercase(), &mut tried) {
+                return Some(template);
+            }
+        }
+        self.default.as_deref()
+    }
+
+    /// Depth-first through the fallback chains; `tried` stops cycles
+    fn for_language(&self, lang: &str, tried: &mut Vec<String>) -> Option<&str> {
+        if tried.iter().any(|seen| seen == lang) {
+            return None;
+        }
+        tried.push(lang.to_string());
+        if let Some(template) = self.languages.get(lang) {
+            return Some(template);
+        }
+        for fallback in self.fallbacks.get(lang).into_iter().flatten() {
+            if let Some(template) = self.for_language(&fallback.to_lowercase(), tried) {
+                return Some(template);
+            }
+        }
+        match lang.split_once('-') {
+            Some((base, _)) => self.for_language(base, tried),
+            None => None,
+        }
+    }
+}
+
+/// What a summary template's placeholders stand for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SummaryValues {
+    /// Comments of the rules that changed the page
+    pub rules: Vec<String>,
+    /// Names of the general fixes that changed the page
+    pub fixes: Vec<String>,
+    /// Rules and fixes that changed the page, including rules without a
+    /// comment
+    pub count: usize,
+}
+
+/// Fill in `template`'s placeholders
+pub fn render(template: &str, values: &SummaryValues) -> String {
+    template
+        .replace("{rules}", &values.rules.join(", "))
+        .replace("{fixes}", &values.fixes.join(", "))
+        .replace("{count}", &values.count.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates() -> SummaryTemplates {
+        serde_json::from_value(serde_json::json!({
+            "default": "Bot: {rules}",
+            "wikis": {"de.wikibooks.org": "Bot (Wikibooks): {rules}"},
+            "languages": {
+                "de": "Bot: {rules} ({count} Änderungen)",
+                "pt": "Robô: {rules}"
+            },
+            "fallbacks": {"als": ["gsw", "de"], "gsw": ["als"]}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_order() {
+        let templates = templates();
+        assert_eq!(
+            templates.resolve(Some("de.wikibooks.org"), Some("de")),
+            Some("Bot (Wikibooks): {rules}")
+        );
+        assert_eq!(
+            templates.resolve(Some("de.wikipedia.org"), Some("de")),
+            Some("Bot: {rules} ({count} Änderungen)")
+        );
+        // Through a fallback chain with a cycle in it
+        assert_eq!(
+            templates.resolve(None, Some("als")),
+            templates.languages.get("de").map(String::as_str)
+        );
+        // A variant falls back to its base language
+        assert_eq!(
+            templates.resolve(None, Some("pt-BR")),
+            Some("Robô: {rules}")
+        );
+        assert_eq!(templates.resolve(None, Some("fr")), Some("Bot: {rules}"));
+        assert_eq!(SummaryTemplates::default().resolve(None, Some("de")), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let values = SummaryValues {
+            rules: vec!["Tippfehler".to_string(), "Links".to_string()],
+            fixes: vec!["Trailing whitespace".to_string()],
+            count: 3,
+        };
+        assert_eq!(
+            render("Bot: {rules}; {fixes} ({count}) {{tl|Bot}}", &values),
+            "Bot: Tippfehler, Links; Trailing whitespace (3) {{tl|Bot}}"
+        );
+    }
+}
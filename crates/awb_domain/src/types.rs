@@ -1,3 +1,4 @@
+use crate::siteinfo::SiteInfo;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -6,7 +7,7 @@ pub struct PageId(pub u64);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct RevisionId(pub u64);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Namespace(pub i32);
 
 impl Namespace {
@@ -24,8 +25,66 @@ impl Namespace {
     pub const HELP: Self = Self(12);
     pub const CATEGORY: Self = Self(14);
     pub const CATEGORY_TALK: Self = Self(15);
+
+    /// Whether this is a talk namespace (the odd-numbered ones)
+    pub fn is_talk(self) -> bool {
+        self.0 > 0 && self.0 % 2 == 1
+    }
+
+    /// Canonical name of a standard namespace (`None` for main and for
+    /// namespaces this table does not know)
+    pub fn canonical_name(self) -> Option<&'static str> {
+        NAMESPACE_NAMES
+            .iter()
+            .find(|(_, ns)| *ns == self)
+            .map(|(name, _)| *name)
+    }
+
+    /// Namespace with this name or alias, ignoring case and treating
+    /// underscores as spaces
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().replace('_', " ");
+        NAMESPACE_NAMES
+            .iter()
+            .chain(NAMESPACE_ALIASES)
+            .find(|(known, _)| known.eq_ignore_ascii_case(&name))
+            .map(|(_, ns)| *ns)
+    }
 }
 
+/// Canonical names of the standard namespaces. Local names (such as
+/// "Wikipedia" for the project namespace) vary by wiki; the common English
+/// ones are listed in [`NAMESPACE_ALIASES`].
+pub(crate) const NAMESPACE_NAMES: &[(&str, Namespace)] = &[
+    ("Talk", Namespace(1)),
+    ("User", Namespace(2)),
+    ("User talk", Namespace(3)),
+    ("Project", Namespace(4)),
+    ("Project talk", Namespace(5)),
+    ("File", Namespace(6)),
+    ("File talk", Namespace(7)),
+    ("MediaWiki", Namespace(8)),
+    ("MediaWiki talk", Namespace(9)),
+    ("Template", Namespace(10)),
+    ("Template talk", Namespace(11)),
+    ("Help", Namespace(12)),
+    ("Help talk", Namespace(13)),
+    ("Category", Namespace(14)),
+    ("Category talk", Namespace(15)),
+    ("Draft", Namespace(118)),
+    ("Draft talk", Namespace(119)),
+    ("Module", Namespace(828)),
+    ("Module talk", Namespace(829)),
+];
+
+pub(crate) const NAMESPACE_ALIASES: &[(&str, Namespace)] = &[
+    ("Wikipedia", Namespace(4)),
+    ("Wikipedia talk", Namespace(5)),
+    ("WP", Namespace(4)),
+    ("Image", Namespace(6)),
+    ("Image talk", Namespace(7)),
+];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Title {
     pub namespace: Namespace,
@@ -49,6 +108,123 @@ impl Title {
     }
 }
 
+/// Characters MediaWiki never allows in a title
+const INVALID_TITLE_CHARS: &[char] = &['<', '>', '[', ']', '{', '}', '|'];
+
+/// Why a string is not a title of a page on this wiki
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TitleError {
+    #[error("empty title")]
+    Empty,
+    #[error("'{title}' is an interwiki link ({prefix}:), not a page on this wiki")]
+    Interwiki { title: String, prefix: String },
+    #[error("'{title}' contains '{ch}', which is not allowed in titles")]
+    InvalidCharacter { title: String, ch: char },
+}
+
+impl Title {
+    /// Canonicalize a title as written, e.g. `category:Living_people#A`,
+    /// according to `site`'s namespaces and case rules.
+    ///
+    /// Underscores and runs of whitespace become single spaces, a leading
+    /// `:` and any `#fragment` are dropped, a namespace name or alias sets
+    /// the namespace and is replaced by the local name, and the first
+    /// letter is uppercased unless the namespace is case-sensitive.
+    /// Interwiki prefixes pointing back at this wiki are dropped; other
+    /// interwiki prefixes are an error. Unknown prefixes stay part of the
+    /// name.
+    pub fn parse(site: &SiteInfo, raw: &str) -> Result<Self, TitleError> {
+        let text = raw.replace('_', " ");
+        let text = text.split('#').next().unwrap_or_default();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut rest = text.strip_prefix(':').unwrap_or(&text).trim();
+
+        let mut namespace = Namespace::MAIN;
+        let mut local_prefix_seen = false;
+        while let Some((prefix, after)) = rest.split_once(':') {
+            if let Some(ns) = site.namespace_by_name(prefix) {
+                namespace = ns;
+                rest = after.trim();
+                break;
+            }
+            if !local_prefix_seen && site.is_local_interwiki(prefix) {
+                local_prefix_seen = true;
+                rest = after.trim();
+                continue;
+            }
+            if site.is_interwiki(prefix) {
+                return Err(TitleError::Interwiki {
+                    title: raw.trim().to_string(),
+                    prefix: prefix.trim().to_lowercase(),
+                });
+            }
+            break;
+        }
+
+        if rest.is_empty() {
+            return Err(TitleError::Empty);
+        }
+        if let Some(ch) = rest
+            .chars()
+            .find(|c| INVALID_TITLE_CHARS.contains(c) || c.is_control())
+        {
+            return Err(TitleError::InvalidCharacter {
+                title: raw.trim().to_string(),
+                ch,
+            });
+        }
+
+        let name = if site.is_case_sensitive(namespace) {
+            rest.to_string()
+        } else {
+            capitalize(rest)
+        };
+        let display = match site.prefix(namespace) {
+            Some(prefix) => format!("{}:{}", prefix, name),
+            None => name.clone(),
+        };
+        Ok(Self {
+            namespace,
+            name,
+            display,
+        })
+    }
+
+    /// Build a title from a namespace number and page name, using the
+    /// namespace's canonical name as prefix
+    pub fn with_canonical_prefix(namespace: Namespace, name: &str) -> Self {
+        let name = capitalize(name.replace('_', " ").trim());
+        let display = match namespace.canonical_name() {
+            Some(prefix) => format!("{}:{}", prefix, name),
+            None if namespace == Namespace::MAIN => name.clone(),
+            None => format!("{}:{}", namespace.0, name),
+        };
+        Self {
+            namespace,
+            name,
+            display,
+        }
+    }
+
+    /// Identity of the page regardless of how its title was displayed.
+    /// Titles from [`Title::parse`] are canonical, so equal pages have
+    /// equal keys.
+    pub fn key(&self) -> (Namespace, String) {
+        (
+            self.namespace,
+            self.name.replace('_', " ").trim().to_string(),
+        )
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 impl std::fmt::Display for Title {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.display)
@@ -81,15 +257,194 @@ pub enum ProtectionLevel {
     Sysop,
 }
 
+/// What saving an edit does to the page's entry on the editor's watchlist
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Watchlist {
+    /// Add the page
+    Watch,
+    /// Remove the page
+    Unwatch,
+    /// Leave the watchlist as it is
+    NoChange,
+    /// Follow the account's preferences, as MediaWiki does by default
+    #[default]
+    Preferences,
+}
+
+/// Accepted names for [`Watchlist`]
+pub const WATCHLIST_MODES: &[&str] = &["watch", "unwatch", "nochange", "preferences"];
+
+impl Watchlist {
+    /// Value of the `watchlist` parameter of the edit API
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Watch => "watch",
+            Self::Unwatch => "unwatch",
+            Self::NoChange => "nochange",
+            Self::Preferences => "preferences",
+        }
+    }
+}
+
+impl std::fmt::Display for Watchlist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Watchlist {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "watch" => Ok(Self::Watch),
+            "unwatch" => Ok(Self::Unwatch),
+            "nochange" | "no-change" => Ok(Self::NoChange),
+            "preferences" => Ok(Self::Preferences),
+            other => Err(format!(
+                "unknown watchlist mode '{}' (expected one of: {})",
+                other,
+                WATCHLIST_MODES.join(", ")
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PageProperties {
     pub is_disambig: bool,
     pub wikibase_item: Option<String>,
+    #[serde(default)]
+    pub content_model: ContentModel,
+}
+
+/// How MediaWiki stores and renders a page's text. Only wikitext goes
+/// through the rules and general fixes; JSON pages take `JsonPatch` rules.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ContentModel {
+    #[default]
+    Wikitext,
+    /// Lua modules (`Module:` pages)
+    Scribunto,
+    Json,
+    Css,
+    /// TemplateStyles stylesheets
+    SanitizedCss,
+    JavaScript,
+    Text,
+    /// Flow / Structured Discussions boards, which are not edited as text
+    FlowBoard,
+    Other(String),
+}
+
+impl ContentModel {
+    /// The model's name in the API, e.g. `sanitized-css`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Wikitext => "wikitext",
+            Self::Scribunto => "Scribunto",
+            Self::Json => "json",
+            Self::Css => "css",
+            Self::SanitizedCss => "sanitized-css",
+            Self::JavaScript => "javascript",
+            Self::Text => "text",
+            Self::FlowBoard => "flow-board",
+            Self::Other(name) => name,
+        }
+    }
+
+    pub fn is_wikitext(&self) -> bool {
+        *self == Self::Wikitext
+    }
+}
+
+impl From<&str> for ContentModel {
+    fn from(name: &str) -> Self {
+        match name {
+            "wikitext" => Self::Wikitext,
+            "Scribunto" => Self::Scribunto,
+            "json" => Self::Json,
+            "css" => Self::Css,
+            "sanitized-css" => Self::SanitizedCss,
+            "javascript" => Self::JavaScript,
+            "text" => Self::Text,
+            "flow-board" => Self::FlowBoard,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for ContentModel {
+    fn from(name: String) -> Self {
+        Self::from(name.as_str())
+    }
+}
+
+impl From<ContentModel> for String {
+    fn from(model: ContentModel) -> Self {
+        model.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for ContentModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::siteinfo::NamespaceInfo;
+
+    #[test]
+    fn test_content_model_names() {
+        for name in [
+            "wikitext",
+            "Scribunto",
+            "json",
+            "css",
+            "sanitized-css",
+            "javascript",
+            "text",
+            "flow-board",
+            "GeoJSON",
+        ] {
+            let model = ContentModel::from(name);
+            assert_eq!(model.as_str(), name);
+            assert_eq!(
+                serde_json::to_string(&model).unwrap(),
+                format!("\"{}\"", name)
+            );
+        }
+        assert_eq!(
+            ContentModel::from("GeoJSON"),
+            ContentModel::Other("GeoJSON".to_string())
+        );
+        assert!(ContentModel::default().is_wikitext());
+
+        // Pages saved before the content model was recorded are wikitext
+        let props: PageProperties =
+            serde_json::from_str(r#"{"is_disambig":false,"wikibase_item":null}"#).unwrap();
+        assert_eq!(props.content_model, ContentModel::Wikitext);
+    }
+
+    #[test]
+    fn test_watchlist_names() {
+        for name in WATCHLIST_MODES {
+            let mode: Watchlist = name.parse().unwrap();
+            assert_eq!(mode.as_str(), *name);
+            assert_eq!(
+                serde_json::to_string(&mode).unwrap(),
+                format!("\"{}\"", name)
+            );
+        }
+        assert_eq!("No-Change".parse::<Watchlist>(), Ok(Watchlist::NoChange));
+        assert!("follow".parse::<Watchlist>().is_err());
+        assert_eq!(Watchlist::default(), Watchlist::Preferences);
+    }
 
     #[test]
     fn test_namespace_constants() {
@@ -99,6 +454,16 @@ mod tests {
         assert_eq!(Namespace::CATEGORY.0, 14);
     }
 
+    #[test]
+    fn test_namespace_is_talk() {
+        assert!(Namespace::TALK.is_talk());
+        assert!(Namespace::USER_TALK.is_talk());
+        assert!(Namespace(829).is_talk());
+        assert!(!Namespace::MAIN.is_talk());
+        assert!(!Namespace::USER.is_talk());
+        assert!(!Namespace(-1).is_talk());
+    }
+
     #[test]
     fn test_title_new_main_namespace() {
         let title = Title::new(Namespace::MAIN, "Example");
@@ -121,6 +486,68 @@ mod tests {
         assert_eq!(title.to_string(), "14:Rust");
     }
 
+    #[test]
+    fn test_title_parse() {
+        let site = SiteInfo::default();
+        let parse = |raw: &str| Title::parse(&site, raw).unwrap();
+
+        let title = parse("category:living_people");
+        assert_eq!(title.namespace, Namespace::CATEGORY);
+        assert_eq!(title.name, "Living people");
+        assert_eq!(title.display, "Category:Living people");
+
+        let alias = parse("WP:AFD");
+        assert_eq!(alias.namespace, Namespace::PROJECT);
+        assert_eq!(alias.display, "Project:AFD");
+
+        let unknown = parse("Star Wars: Episode IV");
+        assert_eq!(unknown.namespace, Namespace::MAIN);
+        assert_eq!(unknown.name, "Star Wars: Episode IV");
+
+        assert_eq!(parse("Category:bar#Section").display, "Category:Bar");
+        assert_eq!(parse(":Template:  Foo__bar ").display, "Template:Foo bar");
+        assert_eq!(
+            Title::with_canonical_prefix(Namespace::TEMPLATE, "infobox_person").display,
+            "Template:Infobox person"
+        );
+        assert_eq!(parse("foo bar").key(), parse("Foo_bar").key());
+    }
+
+    #[test]
+    fn test_title_parse_rejects_non_titles() {
+        let site = SiteInfo::default();
+        assert_eq!(
+            Title::parse(&site, "en:Foo"),
+            Err(TitleError::Interwiki {
+                title: "en:Foo".to_string(),
+                prefix: "en".to_string(),
+            })
+        );
+        assert_eq!(Title::parse(&site, "#Section"), Err(TitleError::Empty));
+        assert_eq!(Title::parse(&site, "Category:"), Err(TitleError::Empty));
+        assert!(matches!(
+            Title::parse(&site, "Foo|bar"),
+            Err(TitleError::InvalidCharacter { ch: '|', .. })
+        ));
+    }
+
+    #[test]
+    fn test_title_parse_uses_site_rules() {
+        let site = SiteInfo {
+            namespaces: vec![
+                NamespaceInfo::new(Namespace::MAIN, "").with_case_sensitive(true),
+                NamespaceInfo::new(Namespace::PROJECT, "Wikipedia").with_canonical("Project"),
+            ],
+            local_interwiki: vec!["en".to_string()],
+            ..SiteInfo::default()
+        };
+        let title = Title::parse(&site, "en:WP:AFD").unwrap();
+        assert_eq!(title.namespace, Namespace::PROJECT);
+        assert_eq!(title.display, "Wikipedia:AFD");
+        assert_eq!(Title::parse(&site, "iPhone").unwrap().name, "iPhone");
+        assert!(Title::parse(&site, "de:Foo").is_err());
+    }
+
     #[test]
     fn test_page_id_serialization() {
         let page_id = PageId(12345);
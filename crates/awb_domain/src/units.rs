@@ -0,0 +1,429 @@
+//! Human-friendly durations and byte sizes for config files.
+//!
+//! Durations are written as one or more `<integer><unit>` parts, e.g. `12s`,
+//! `2h30m` or `1500ms`, with units `ms`, `s`, `m`, `h` and `d`. Sizes are an
+//! integer or decimal with an optional unit, e.g. `2MB` or `1.5GiB`; `KB`,
+//! `MB` and `GB` are powers of 1000 and `KiB`, `MiB` and `GiB` powers of 1024.
+//! Plain numbers are still accepted as seconds and bytes respectively.
+
+use std::time::Duration;
+
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("ms", 1),
+    ("s", 1_000),
+    ("m", 60_000),
+    ("min", 60_000),
+    ("h", 3_600_000),
+    ("d", 86_400_000),
+];
+
+const SIZE_UNITS: &[(&str, u64)] = &[
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+];
+
+/// Parse a duration such as `12s`, `2h30m` or `1500ms`
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let text = input.trim();
+    if text.is_empty() {
+        return Err("empty duration".to_string());
+    }
+
+    let mut total_ms: u64 = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return Err(format!(
+                "invalid duration '{}': expected a number before '{}'",
+                input, rest
+            ));
+        }
+        let (number, tail) = rest.split_at(digits);
+        let unit_len = tail.len()
+            - tail
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+                .len();
+        let (unit, tail) = tail.split_at(unit_len);
+        if unit.is_empty() {
+            return Err(format!(
+                "invalid duration '{}': missing unit after {} (use ms, s, m, h or d)",
+                input, number
+            ));
+        }
+        let Some(&(_, factor)) = DURATION_UNITS.iter().find(|(name, _)| *name == unit) else {
+            return Err(format!(
+                "invalid duration '{}': unknown unit '{}' (use ms, s, m, h or d)",
+                input, unit
+            ));
+        };
+        let part = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(factor))
+            .and_then(|ms| total_ms.checked_add(ms));
+        total_ms = part.ok_or_else(|| format!("duration '{}' is too large", input))?;
+        rest = tail;
+    }
+    Ok(Duration::from_millis(total_ms))
+}
+
+/// Render a duration the way [`parse_duration`] reads it, e.g. `2h30m`
+pub fn format_duration(duration: Duration) -> String {
+    let mut ms = duration.as_millis();
+    if ms == 0 {
+        return "0s".to_string();
+    }
+    let mut out = String::new();
+    for (unit, factor) in [
+        ("d", 86_400_000),
+        ("h", 3_600_000),
+        ("m", 60_000),
+        ("s", 1_000),
+        ("ms", 1),
+    ] {
+        if ms >= factor {
+            out.push_str(&format!("{}{}", ms / factor, unit));
+            ms %= factor;
+        }
+    }
+    out
+}
+
+/// Parse a byte size such as `2MB`, `512KiB` or `1.5GiB`
+pub fn parse_byte_size(input: &str) -> Result<u64, String> {
+    let text = input.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let unit = unit.trim();
+    if number.is_empty() {
+        return Err(format!("invalid size '{}': expected a number", input));
+    }
+
+    let factor = if unit.is_empty() {
+        1
+    } else {
+        let lower = unit.to_ascii_lowercase();
+        SIZE_UNITS
+            .iter()
+            .find(|(name, _)| *name == lower)
+            .map(|(_, factor)| *factor)
+            .ok_or_else(|| {
+                format!(
+                    "invalid size '{}': unknown unit '{}' (use B, KB, MB, GB, KiB, MiB or GiB)",
+                    input, unit
+                )
+            })?
+    };
+
+    match number.parse::<u64>() {
+        Ok(n) => n
+            .checked_mul(factor)
+            .ok_or_else(|| format!("size '{}' is too large", input)),
+        Err(_) => {
+            let n: f64 = number
+                .parse()
+                .map_err(|_| format!("invalid size '{}': bad number '{}'", input, number))?;
+            let bytes = n * factor as f64;
+            if bytes.fract() != 0.0 {
+                return Err(format!("size '{}' is not a whole number of bytes", input));
+            }
+            if bytes > u64::MAX as f64 {
+                return Err(format!("size '{}' is too large", input));
+            }
+            Ok(bytes as u64)
+        }
+    }
+}
+
+/// Render a byte size with the largest unit that divides it exactly,
+/// e.g. `16MiB` or `2MB`
+pub fn format_byte_size(bytes: u64) -> String {
+    for (unit, factor) in [
+        ("GiB", 1u64 << 30),
+        ("GB", 1_000_000_000),
+        ("MiB", 1 << 20),
+        ("MB", 1_000_000),
+        ("KiB", 1 << 10),
+        ("KB", 1_000),
+    ] {
+        if bytes >= factor && bytes % factor == 0 {
+            return format!("{}{}", bytes / factor, unit);
+        }
+    }
+    format!("{}B", bytes)
+}
+
+/// `#[serde(with = "awb_domain::units::duration")]`: a [`Duration`] written
+/// as `"12s"`. Also reads plain seconds and the `{ secs, nanos }` form serde
+/// uses by default.
+pub mod duration {
+    use super::{format_duration, parse_duration};
+    use serde::de::{self, MapAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&format_duration(*d))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        d.deserialize_any(DurationVisitor)
+    }
+
+    struct DurationVisitor;
+
+    impl<'de> Visitor<'de> for DurationVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a duration such as \"12s\" or \"2h30m\", or a number of seconds")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Duration, E> {
+            parse_duration(v).map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Duration, E> {
+            Ok(Duration::from_secs(v))
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Duration, E> {
+            u64::try_from(v)
+                .map(Duration::from_secs)
+                .map_err(|_| E::custom(format!("negative duration {}", v)))
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<Duration, E> {
+            Duration::try_from_secs_f64(v).map_err(|_| E::custom(format!("invalid duration {}", v)))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Duration, A::Error> {
+            let (mut secs, mut nanos) = (0u64, 0u32);
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "secs" => secs = map.next_value()?,
+                    "nanos" => nanos = map.next_value()?,
+                    other => return Err(de::Error::unknown_field(other, &["secs", "nanos"])),
+                }
+            }
+            // Duration::new carries whole seconds out of `nanos` and panics
+            // if that overflows `secs`
+            secs.checked_add(u64::from(nanos / 1_000_000_000))
+                .ok_or_else(|| de::Error::custom("overflow deserializing duration"))?;
+            Ok(Duration::new(secs, nanos))
+        }
+    }
+}
+
+/// `#[serde(with = "awb_domain::units::option_duration")]`: like
+/// [`duration`] for an optional field
+pub mod option_duration {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+        match d {
+            Some(d) => super::duration::serialize(d, s),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::duration")] Duration);
+
+        Ok(Option::<Wrapper>::deserialize(d)?.map(|Wrapper(d)| d))
+    }
+}
+
+/// `#[serde(with = "awb_domain::units::byte_size")]`: a byte count written as
+/// `"2MB"`. Also reads a plain number of bytes.
+pub mod byte_size {
+    use super::{format_byte_size, parse_byte_size};
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    pub fn serialize<T, S>(bytes: &T, s: S) -> Result<S::Ok, S::Error>
+    where
+        T: Copy + TryInto<u64>,
+        S: Serializer,
+    {
+        let bytes: u64 = (*bytes)
+            .try_into()
+            .map_err(|_| serde::ser::Error::custom("size does not fit in 64 bits"))?;
+        s.serialize_str(&format_byte_size(bytes))
+    }
+
+    pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+    where
+        T: TryFrom<u64>,
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(SizeVisitor(PhantomData))
+    }
+
+    struct SizeVisitor<T>(PhantomData<T>);
+
+    impl<T: TryFrom<u64>> SizeVisitor<T> {
+        fn fit<E: de::Error>(bytes: u64) -> Result<T, E> {
+            T::try_from(bytes).map_err(|_| E::custom(format!("size {} is too large", bytes)))
+        }
+    }
+
+    impl<'de, T: TryFrom<u64>> Visitor<'de> for SizeVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a size such as \"2MB\" or \"512KiB\", or a number of bytes")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+            Self::fit(parse_byte_size(v).map_err(E::custom)?)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+            Self::fit(v)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+            let bytes = u64::try_from(v).map_err(|_| E::custom(format!("negative size {}", v)))?;
+            Self::fit(bytes)
+        }
+    }
+}
+
+/// `#[serde(with = "awb_domain::units::option_byte_size")]`: like
+/// [`byte_size`] for an optional field
+pub mod option_byte_size {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Option<u64>, s: S) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => super::byte_size::serialize(bytes, s),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::byte_size")] u64);
+
+        Ok(Option::<Wrapper>::deserialize(d)?.map(|Wrapper(bytes)| bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Limits {
+        #[serde(with = "duration")]
+        delay: Duration,
+        #[serde(default, with = "option_duration")]
+        runtime: Option<Duration>,
+        #[serde(with = "byte_size")]
+        memory: usize,
+        #[serde(default, with = "option_byte_size")]
+        page: Option<u64>,
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("12s"), Ok(Duration::from_secs(12)));
+        assert_eq!(parse_duration("2h30m"), Ok(Duration::from_secs(9000)));
+        assert_eq!(parse_duration("1500ms"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(86_400)));
+        assert_eq!(parse_duration(" 5min "), Ok(Duration::from_secs(300)));
+        assert!(parse_duration("12").unwrap_err().contains("missing unit"));
+        assert!(
+            parse_duration("12x")
+                .unwrap_err()
+                .contains("unknown unit 'x'")
+        );
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("2MB"), Ok(2_000_000));
+        assert_eq!(parse_byte_size("512KiB"), Ok(512 * 1024));
+        assert_eq!(parse_byte_size("1.5GiB"), Ok(3 << 29));
+        assert_eq!(parse_byte_size("64 kb"), Ok(64_000));
+        assert_eq!(parse_byte_size("100"), Ok(100));
+        assert!(parse_byte_size("2XB").unwrap_err().contains("unknown unit"));
+        assert!(parse_byte_size("0.1B").is_err());
+        assert!(parse_byte_size("MB").is_err());
+    }
+
+    #[test]
+    fn test_format_round_trips() {
+        for d in ["2h30m", "12s", "1m500ms", "0s", "3d"] {
+            assert_eq!(format_duration(parse_duration(d).unwrap()), d);
+        }
+        for s in ["16MiB", "2MB", "999B", "64KiB"] {
+            assert_eq!(format_byte_size(parse_byte_size(s).unwrap()), s);
+        }
+    }
+
+    #[test]
+    fn test_serde_reads_strings_and_numbers() {
+        let limits: Limits = serde_json::from_str(
+            r#"{"delay": "12s", "runtime": "2h", "memory": "16MiB", "page": "2MB"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            limits,
+            Limits {
+                delay: Duration::from_secs(12),
+                runtime: Some(Duration::from_secs(7200)),
+                memory: 16 << 20,
+                page: Some(2_000_000),
+            }
+        );
+
+        let json = serde_json::to_string(&limits).unwrap();
+        assert_eq!(
+            json,
+            r#"{"delay":"12s","runtime":"2h","memory":"16MiB","page":"2MB"}"#
+        );
+
+        let legacy: Limits = serde_json::from_str(
+            r#"{"delay": {"secs": 10, "nanos": 0}, "runtime": null, "memory": 1024}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.delay, Duration::from_secs(10));
+        assert_eq!(legacy.runtime, None);
+        assert_eq!(legacy.memory, 1024);
+        assert_eq!(legacy.page, None);
+    }
+
+    #[test]
+    fn test_serde_error_names_the_value() {
+        let err = serde_json::from_str::<Limits>(r#"{"delay": "12x", "memory": 1}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown unit 'x'"), "{}", err);
+        assert_eq!(err.line(), 1);
+    }
+
+    #[test]
+    fn test_serde_rejects_overflowing_secs_and_nanos() {
+        let err = serde_json::from_str::<Limits>(
+            r#"{"delay": {"secs": 18446744073709551615, "nanos": 1000000000}, "memory": 1}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("overflow"), "{}", err);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::types::ContentModel;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -16,6 +17,49 @@ pub enum Warning {
     SuspiciousPattern {
         description: String,
     },
+    Plugin {
+        plugin: String,
+        message: String,
+    },
+    /// A general fix noticed a problem it left for a human
+    Fix {
+        fix: String,
+        message: String,
+    },
+    /// Running the pipeline again over its own output still changed the text
+    NotIdempotent {
+        rules: Vec<Uuid>,
+        fixes: Vec<String>,
+    },
+    /// The page is not wikitext, so rules and general fixes left it alone
+    UnsupportedContentModel {
+        model: ContentModel,
+    },
+    /// The edit leaves named references in use without a definition, by
+    /// removing the definition or adding a use; the edit must not be saved
+    BrokenReferences {
+        names: Vec<String>,
+    },
+    /// The edit removes templates, or values of template parameters
+    /// (`template|parameter`), that no rule behind the removal names;
+    /// `origins` are the rules and fixes that made those changes
+    TemplateLoss {
+        templates: Vec<String>,
+        parameters: Vec<String>,
+        origins: Vec<String>,
+    },
+    /// The edit changes which pages the wikilinks point to, and no rule
+    /// behind it is marked as retargeting links; unattended runs must not
+    /// save it
+    LinkTargetsChanged {
+        removed: Vec<String>,
+        added: Vec<String>,
+    },
+    /// The fixes in `fixes`, e.g. a plugin, asked for the page to be left
+    /// alone; the edit must not be saved
+    SkipRequested {
+        fixes: Vec<String>,
+    },
 }
 
 #[cfg(test)]
@@ -87,6 +131,106 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_warning_plugin() {
+        let warning = Warning::Plugin {
+            plugin: "dates.lua".to_string(),
+            message: "Ambiguous date format".to_string(),
+        };
+
+        match warning {
+            Warning::Plugin { plugin, message } => {
+                assert_eq!(plugin, "dates.lua");
+                assert_eq!(message, "Ambiguous date format");
+            }
+            _ => panic!("Expected Plugin warning"),
+        }
+    }
+
+    #[test]
+    fn test_warning_not_idempotent() {
+        let rule_id = Uuid::new_v4();
+        let warning = Warning::NotIdempotent {
+            rules: vec![rule_id],
+            fixes: vec!["whitespace".to_string()],
+        };
+
+        let json = serde_json::to_string(&warning).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            Warning::NotIdempotent { rules, fixes } => {
+                assert_eq!(rules, vec![rule_id]);
+                assert_eq!(fixes, vec!["whitespace"]);
+            }
+            _ => panic!("Expected NotIdempotent warning"),
+        }
+    }
+
+    #[test]
+    fn test_warning_broken_references() {
+        let warning = Warning::BrokenReferences {
+            names: vec!["smith2020".to_string()],
+        };
+
+        let json = serde_json::to_string(&warning).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            Warning::BrokenReferences { names } => assert_eq!(names, vec!["smith2020"]),
+            _ => panic!("Expected BrokenReferences warning"),
+        }
+    }
+
+    #[test]
+    fn test_warning_template_loss() {
+        let warning = Warning::TemplateLoss {
+            templates: vec!["Cn".to_string()],
+            parameters: vec!["Infobox person|birth_date".to_string()],
+            origins: vec!["html_to_wikitext".to_string()],
+        };
+
+        let json = serde_json::to_string(&warning).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            Warning::TemplateLoss {
+                templates,
+                parameters,
+                origins,
+            } => {
+                assert_eq!(templates, vec!["Cn"]);
+                assert_eq!(parameters, vec!["Infobox person|birth_date"]);
+                assert_eq!(origins, vec!["html_to_wikitext"]);
+            }
+            _ => panic!("Expected TemplateLoss warning"),
+        }
+    }
+
+    #[test]
+    fn test_warning_link_targets_changed() {
+        let warning = Warning::LinkTargetsChanged {
+            removed: vec!["Foo (band)".to_string()],
+            added: vec!["Foo".to_string()],
+        };
+
+        let json = serde_json::to_string(&warning).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            Warning::LinkTargetsChanged { removed, added } => {
+                assert_eq!(removed, vec!["Foo (band)"]);
+                assert_eq!(added, vec!["Foo"]);
+            }
+            _ => panic!("Expected LinkTargetsChanged warning"),
+        }
+    }
+
+    #[test]
+    fn test_warning_skip_requested() {
+        let warning = Warning::SkipRequested {
+            fixes: vec!["plugins".to_string()],
+        };
+
+        let json = serde_json::to_string(&warning).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            Warning::SkipRequested { fixes } => assert_eq!(fixes, vec!["plugins"]),
+            _ => panic!("Expected SkipRequested warning"),
+        }
+    }
+
     #[test]
     fn test_warning_serialization() {
         let warning = Warning::LargeChange {
@@ -0,0 +1,185 @@
+//! Engine benchmarks on representative large articles.
+//!
+//! Save a baseline before a change and compare against it afterwards:
+//!
+//! ```bash
+//! cargo bench -p awb_engine -- --save-baseline main
+//! cargo bench -p awb_engine -- --baseline main
+//! ```
+
+use awb_domain::rules::{Rule, RuleSet};
+use awb_domain::types::*;
+use awb_engine::diff_engine::compute_diff;
+use awb_engine::general_fixes::{FixContext, FixRegistry};
+use awb_engine::masking::{MaskPolicy, mask_with};
+use awb_engine::transform::TransformEngine;
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use std::collections::HashSet;
+
+/// A long article in the shape of a featured biography: infobox, cited
+/// prose, a table, an image and categories, with `sections` sections of
+/// about 800 bytes each
+fn article(sections: usize) -> String {
+    let mut text = String::from(
+        "{{Short description|Painter}}\n{{Use dmy dates|date=May 2024}}\n\
+         {{Infobox person\n| name = Example Person\n| birth_date = {{birth date|1853|3|30}}\n\
+         | image = Example.jpg\n| occupation = Painter\n}}\n\
+         '''Example Person''' (30 March 1853 – 29 July 1890) was a painter.\n",
+    );
+    for i in 0..sections {
+        text.push_str(&format!(
+            "\n== Section {i} ==\n\
+             In {year} he moved to [[Paris]], where he met [[Paul Gauguin|Gauguin]] and \
+             painted teh [[Sunflowers (series)|sunflower]] series.<ref>{{{{cite book \
+             |last=Smith |first=J. |title=A Life |publisher=Press |year=2001 |page={i}}}}}</ref> \
+             His colour palette recieved praise  from critics,<ref name=\"n{i}\"/> and \
+             several works are held at the [[Van Gogh Museum]].\n\n\
+             <!-- Editors: please discuss before changing this paragraph -->\n\
+             The period is covered in letters to his brother, quoted in <nowiki>[[brackets]]</nowiki> \
+             and in <code>monospace</code> by later sources.<ref>[http://example.org/{i} Letter {i}]</ref>\n\n\
+             {{| class=\"wikitable\"\n|-\n! Year !! Work !! Collection\n|-\n| {year} || ''Still life'' || \
+             [[Kröller-Müller Museum]]\n|-\n| {next} || ''Portrait'' || Private\n|}}\n\n\
+             [[File:Example {i}.jpg|thumb|A painting from {year}]]\n",
+            year = 1880 + i % 10,
+            next = 1881 + i % 10,
+        ));
+    }
+    text.push_str(
+        "\n== References ==\n{{Reflist}}\n\n{{Authority control}}\n\
+         [[Category:1853 births]]\n[[Category:1890 deaths]]\n[[Category:Dutch painters]]\n",
+    );
+    text
+}
+
+fn article_page(wikitext: &str) -> PageContent {
+    PageContent {
+        page_id: PageId(1),
+        title: Title::new(Namespace::MAIN, "Example Person"),
+        revision: RevisionId(1),
+        timestamp: chrono::Utc::now(),
+        wikitext: wikitext.to_string(),
+        size_bytes: wikitext.len() as u64,
+        is_redirect: false,
+        protection: ProtectionInfo::default(),
+        properties: PageProperties::default(),
+    }
+}
+
+fn fix_context() -> FixContext {
+    FixContext {
+        title: Title::new(Namespace::MAIN, "Example Person"),
+        namespace: Namespace::MAIN,
+        is_redirect: false,
+        revision: None,
+        cancel: Default::default(),
+    }
+}
+
+/// A typo-list sized rule set: plain replacements plus word-bounded regexes
+fn rules() -> RuleSet {
+    let mut rules = RuleSet::new();
+    rules.add(Rule::new_plain("teh", "the", true));
+    rules.add(Rule::new_plain("recieved", "received", true));
+    rules.add(Rule::new_regex(r"\b(\w+)  +(\w+)", "$1 $2", false));
+    for i in 0..100 {
+        rules.add(Rule::new_plain(
+            format!("mispeling{}", i),
+            "misspelling",
+            true,
+        ));
+        rules.add(Rule::new_regex(format!(r"\bwrod{}\b", i), "word", true));
+    }
+    rules
+}
+
+/// Small, medium and very large articles, by name
+fn articles() -> Vec<(&'static str, String)> {
+    vec![
+        ("32KB", article(40)),
+        ("330KB", article(400)),
+        ("1.6MB", article(2000)),
+    ]
+}
+
+fn bench_masking(c: &mut Criterion) {
+    let mut group = c.benchmark_group("masking");
+    for (name, text) in articles() {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("mask_unmask", name), &text, |b, text| {
+            b.iter(|| black_box(mask_with(text, MaskPolicy::DEFAULT).unmask()));
+        });
+        group.bench_with_input(BenchmarkId::new("mask_all", name), &text, |b, text| {
+            b.iter(|| black_box(mask_with(text, MaskPolicy::ALL)));
+        });
+    }
+    group.finish();
+}
+
+fn bench_fix_pipeline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fix_pipeline");
+    let registry = FixRegistry::with_defaults();
+    let all: HashSet<String> = registry
+        .all_modules()
+        .iter()
+        .map(|m| m.id().to_string())
+        .collect();
+    let ctx = fix_context();
+    for (name, text) in articles() {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("all_fixes", name), &text, |b, text| {
+            b.iter(|| black_box(registry.apply_all_returning_ids(text, &ctx, &all)));
+        });
+    }
+    group.finish();
+}
+
+fn bench_diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("diff");
+    for (name, text) in articles() {
+        // One small change per section, as a typical bot edit makes
+        let changed = text.replace("teh ", "the ");
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("compute_diff", name),
+            &(text, changed),
+            |b, (old, new)| b.iter(|| black_box(compute_diff(old, new))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_rules(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rules");
+    let rules_only = TransformEngine::new(&rules(), FixRegistry::new(), HashSet::new()).unwrap();
+    let registry = FixRegistry::with_defaults();
+    let all = registry
+        .all_modules()
+        .iter()
+        .map(|m| m.id().to_string())
+        .collect();
+    let full = TransformEngine::new(&rules(), registry, all).unwrap();
+    for (name, text) in articles() {
+        let page = article_page(&text);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("rules_only", name), &page, |b, page| {
+            b.iter(|| black_box(rules_only.apply(page)));
+        });
+        group.bench_with_input(
+            BenchmarkId::new("rules_and_fixes", name),
+            &page,
+            |b, page| {
+                b.iter(|| black_box(full.apply(page)));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_masking,
+    bench_fix_pipeline,
+    bench_diff,
+    bench_rules
+);
+criterion_main!(benches);
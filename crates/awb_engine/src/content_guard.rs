@@ -0,0 +1,212 @@
+//! Guard against saves that lose most of a page.
+//!
+//! A rule whose regex matches far more than intended tends to leave the
+//! same marks: the page is suddenly much shorter, its categories are gone,
+//! or the prose before the first heading has disappeared. [`ContentLossGuard`]
+//! looks for those before an edit is saved; the caller decides whether an
+//! intentional removal may go ahead anyway.
+
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::Namespace;
+use awb_domain::units;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Thresholds for blocking a save. Keys left out take their default value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentLossGuard {
+    /// Block when the text shrinks by more than this percentage of its old
+    /// size (default: 50; 100 or None = no limit)
+    pub max_shrink_percent: Option<u8>,
+
+    /// Block when the text shrinks by more than this many bytes, e.g.
+    /// `"10KB"` (default: no limit)
+    #[serde(with = "units::option_byte_size")]
+    pub max_removed_bytes: Option<u64>,
+
+    /// Block when a page that had categories is left with none
+    pub keep_categories: bool,
+
+    /// Block when the lead section had prose and is left with none
+    pub keep_lead: bool,
+}
+
+impl Default for ContentLossGuard {
+    fn default() -> Self {
+        Self {
+            max_shrink_percent: Some(50),
+            max_removed_bytes: None,
+            keep_categories: true,
+            keep_lead: true,
+        }
+    }
+}
+
+/// A sign that an edit removes more than it should
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentLoss {
+    /// The text shrank past `max_shrink_percent` or `max_removed_bytes`
+    Shrunk { old_bytes: usize, new_bytes: usize },
+    /// Every category link was removed
+    CategoriesRemoved { count: usize },
+    /// The lead section lost all of its prose
+    LeadEmptied,
+}
+
+impl fmt::Display for ContentLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shrunk {
+                old_bytes,
+                new_bytes,
+            } => write!(
+                f,
+                "text shrinks from {} to {} bytes ({}% removed)",
+                old_bytes,
+                new_bytes,
+                (old_bytes - new_bytes) * 100 / old_bytes
+            ),
+            Self::CategoriesRemoved { count } => {
+                write!(f, "all {} categories removed", count)
+            }
+            Self::LeadEmptied => write!(f, "lead section emptied"),
+        }
+    }
+}
+
+impl ContentLossGuard {
+    /// Everything about replacing `old` with `new` that should block the
+    /// save; empty if it may go ahead. `site` names the category namespace.
+    pub fn check(&self, site: &SiteInfo, old: &str, new: &str) -> Vec<ContentLoss> {
+        let mut losses = Vec::new();
+
+        let removed = old.len().saturating_sub(new.len());
+        let too_many_bytes = self
+            .max_removed_bytes
+            .is_some_and(|max| removed as u64 > max);
+        let too_large_share = self
+            .max_shrink_percent
+            .is_some_and(|max| removed * 100 > old.len() * usize::from(max));
+        if removed > 0 && (too_many_bytes || too_large_share) {
+            losses.push(ContentLoss::Shrunk {
+                old_bytes: old.len(),
+                new_bytes: new.len(),
+            });
+        }
+
+        if self.keep_categories {
+            let count = count_categories(site, old);
+            if count > 0 && count_categories(site, new) == 0 {
+                losses.push(ContentLoss::CategoriesRemoved { count });
+            }
+        }
+
+        if self.keep_lead && has_prose(lead(old)) && !has_prose(lead(new)) {
+            losses.push(ContentLoss::LeadEmptied);
+        }
+
+        losses
+    }
+}
+
+/// Category links in `text`; `[[:Category:...]]` links to a category
+/// without adding the page to it, so it does not count
+fn count_categories(site: &SiteInfo, text: &str) -> usize {
+    static LINK_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let re = LINK_RE.get_or_init(|| {
+        regex::Regex::new(r"\[\[\s*([^:\[\]|\n]+?)\s*:").expect("known-valid regex")
+    });
+    re.captures_iter(text)
+        .filter(|caps| site.namespace_by_name(&caps[1]) == Some(Namespace::CATEGORY))
+        .count()
+}
+
+/// Text before the first heading
+fn lead(text: &str) -> &str {
+    static HEADING_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let re = HEADING_RE
+        .get_or_init(|| regex::Regex::new(r"(?m)^=+[^=\n].*=+[ \t]*$").expect("known-valid regex"));
+    re.find(text)
+        .map_or(text, |heading| &text[..heading.start()])
+}
+
+/// Whether any line is more than template, table or comment markup
+fn has_prose(text: &str) -> bool {
+    text.lines().map(str::trim).any(|line| {
+        !line.is_empty()
+            && !["{", "}", "|", "!", "<!--", "__"]
+                .iter()
+                .any(|markup| line.starts_with(markup))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "{{Infobox city\n| name = Foo\n}}\n'''Foo''' is a town.\n\n== History ==\nFounded in 1200.\n\n[[Category:Towns]]\n[[Category:Founded in 1200]]\n";
+
+    fn check(guard: &ContentLossGuard, new: &str) -> Vec<ContentLoss> {
+        guard.check(&SiteInfo::default(), PAGE, new)
+    }
+
+    #[test]
+    fn test_small_edit_passes() {
+        let new = PAGE.replace("town", "small town");
+        assert!(check(&ContentLossGuard::default(), &new).is_empty());
+    }
+
+    #[test]
+    fn test_shrink_thresholds() {
+        let new = &PAGE[..PAGE.len() / 3];
+        let losses = check(&ContentLossGuard::default(), new);
+        assert!(losses.contains(&ContentLoss::Shrunk {
+            old_bytes: PAGE.len(),
+            new_bytes: new.len(),
+        }));
+
+        // An absolute limit catches a removal the percentage allows
+        let new = PAGE.replace("Founded in 1200.\n", "");
+        let guard = ContentLossGuard {
+            max_removed_bytes: Some(10),
+            ..ContentLossGuard::default()
+        };
+        assert!(matches!(
+            check(&guard, &new).as_slice(),
+            [ContentLoss::Shrunk { .. }]
+        ));
+        assert!(check(&ContentLossGuard::default(), &new).is_empty());
+    }
+
+    #[test]
+    fn test_all_categories_removed() {
+        let new = PAGE.replace("[[Category:Towns]]\n", "");
+        assert!(check(&ContentLossGuard::default(), &new).is_empty());
+
+        let new = new.replace("[[Category:Founded in 1200]]\n", "[[:Category:Towns]]\n");
+        let guard = ContentLossGuard {
+            max_shrink_percent: None,
+            ..ContentLossGuard::default()
+        };
+        assert_eq!(
+            check(&guard, &new),
+            vec![ContentLoss::CategoriesRemoved { count: 2 }]
+        );
+    }
+
+    #[test]
+    fn test_lead_emptied() {
+        let new = PAGE.replace("'''Foo''' is a town.\n", "");
+        let losses = check(&ContentLossGuard::default(), &new);
+        assert_eq!(losses, vec![ContentLoss::LeadEmptied]);
+        assert_eq!(losses[0].to_string(), "lead section emptied");
+
+        let guard = ContentLossGuard {
+            keep_lead: false,
+            ..ContentLossGuard::default()
+        };
+        assert!(check(&guard, &new).is_empty());
+    }
+}
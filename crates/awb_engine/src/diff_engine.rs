@@ -24,6 +24,7 @@ pub fn compute_diff(old: &str, new: &str) -> Vec<DiffOp> {
                 ops.push(DiffOp::Delete {
                     old_range: old_pos..old_pos + len,
                     text: change.value().to_string(),
+                    origin: None,
                 });
                 old_pos += len;
             }
@@ -31,6 +32,7 @@ pub fn compute_diff(old: &str, new: &str) -> Vec<DiffOp> {
                 ops.push(DiffOp::Insert {
                     new_range: new_pos..new_pos + len,
                     text: change.value().to_string(),
+                    origin: None,
                 });
                 new_pos += len;
             }
@@ -228,19 +230,21 @@ pub fn to_side_by_side(ops: &[DiffOp]) -> Vec<SideBySideLine> {
                             text: line_text.to_string(),
                             change_type: ChangeType::Equal,
                             inline_changes: vec![],
+                            origin: None,
                         }),
                         right: Some(DiffLine {
                             line_no: right_no,
                             text: line_text.to_string(),
                             change_type: ChangeType::Equal,
                             inline_changes: vec![],
+                            origin: None,
                         }),
                     });
                     left_no += 1;
                     right_no += 1;
                 }
             }
-            DiffOp::Delete { text, .. } => {
+            DiffOp::Delete { text, origin, .. } => {
                 for line_text in text.lines() {
                     lines.push(SideBySideLine {
                         left: Some(DiffLine {
@@ -248,13 +252,14 @@ pub fn to_side_by_side(ops: &[DiffOp]) -> Vec<SideBySideLine> {
                             text: line_text.to_string(),
                             change_type: ChangeType::Removed,
                             inline_changes: vec![],
+                            origin: origin.clone(),
                         }),
                         right: None,
                     });
                     left_no += 1;
                 }
             }
-            DiffOp::Insert { text, .. } => {
+            DiffOp::Insert { text, origin, .. } => {
                 for line_text in text.lines() {
                     lines.push(SideBySideLine {
                         left: None,
@@ -263,13 +268,17 @@ pub fn to_side_by_side(ops: &[DiffOp]) -> Vec<SideBySideLine> {
                             text: line_text.to_string(),
                             change_type: ChangeType::Added,
                             inline_changes: vec![],
+                            origin: origin.clone(),
                         }),
                     });
                     right_no += 1;
                 }
             }
             DiffOp::Replace {
-                old_text, new_text, ..
+                old_text,
+                new_text,
+                origin,
+                ..
             } => {
                 let old_lines: Vec<&str> = old_text.lines().collect();
                 let new_lines: Vec<&str> = new_text.lines().collect();
@@ -282,6 +291,7 @@ pub fn to_side_by_side(ops: &[DiffOp]) -> Vec<SideBySideLine> {
                                 text: t.to_string(),
                                 change_type: ChangeType::Modified,
                                 inline_changes: vec![],
+                                origin: origin.clone(),
                             };
                             left_no += 1;
                             l
@@ -292,6 +302,7 @@ pub fn to_side_by_side(ops: &[DiffOp]) -> Vec<SideBySideLine> {
                                 text: t.to_string(),
                                 change_type: ChangeType::Modified,
                                 inline_changes: vec![],
+                                origin: origin.clone(),
                             };
                             right_no += 1;
                             l
@@ -394,10 +405,12 @@ mod tests {
             DiffOp::Delete {
                 old_range: 10..20,
                 text: "deleted\n".to_string(),
+                origin: None,
             },
             DiffOp::Insert {
                 new_range: 10..20,
                 text: "added\n".to_string(),
+                origin: None,
             },
         ];
 
@@ -418,10 +431,12 @@ mod tests {
             DiffOp::Delete {
                 old_range: 5..10,
                 text: "old\n".to_string(),
+                origin: None,
             },
             DiffOp::Insert {
                 new_range: 5..10,
                 text: "new\n".to_string(),
+                origin: None,
             },
         ];
 
@@ -451,6 +466,38 @@ mod tests {
         assert!(added_line.is_some());
     }
 
+    #[test]
+    fn test_to_side_by_side_keeps_origins() {
+        let ops = vec![
+            DiffOp::Equal {
+                old_range: 0..5,
+                new_range: 0..5,
+                text: "same\n".to_string(),
+            },
+            DiffOp::Delete {
+                old_range: 5..9,
+                text: "teh\n".to_string(),
+                origin: Some("typo fixing".to_string()),
+            },
+            DiffOp::Insert {
+                new_range: 5..9,
+                text: "the\n".to_string(),
+                origin: Some("typo fixing".to_string()),
+            },
+        ];
+
+        let lines = to_side_by_side(&ops);
+        assert_eq!(lines[0].right.as_ref().unwrap().origin, None);
+        assert_eq!(
+            lines[1].left.as_ref().unwrap().origin.as_deref(),
+            Some("typo fixing")
+        );
+        assert_eq!(
+            lines[2].right.as_ref().unwrap().origin.as_deref(),
+            Some("typo fixing")
+        );
+    }
+
     #[test]
     fn test_to_side_by_side_replace() {
         let ops = vec![DiffOp::Replace {
@@ -458,6 +505,7 @@ mod tests {
             new_range: 0..15,
             old_text: "old1\nold2\n".to_string(),
             new_text: "new1\nnew2\nnew3\n".to_string(),
+            origin: None,
         }];
 
         let lines = to_side_by_side(&ops);
@@ -532,10 +580,12 @@ mod tests {
             DiffOp::Delete {
                 old_range: 6..14,
                 text: "deleted\n".to_string(),
+                origin: None,
             },
             DiffOp::Insert {
                 new_range: 6..12,
                 text: "added\n".to_string(),
+                origin: None,
             },
         ];
         let unified = to_unified(&ops, 3);
@@ -551,10 +601,12 @@ mod tests {
             DiffOp::Delete {
                 old_range: 0..4,
                 text: "old\n".to_string(),
+                origin: None,
             },
             DiffOp::Insert {
                 new_range: 0..4,
                 text: "new\n".to_string(),
+                origin: None,
             },
         ];
         let unified = to_unified(&ops, 3);
@@ -572,6 +624,7 @@ mod tests {
             DiffOp::Delete {
                 old_range: 30..40,
                 text: "removed\n".to_string(),
+                origin: None,
             },
             DiffOp::Equal {
                 old_range: 40..70,
@@ -609,6 +662,7 @@ mod tests {
             new_range: 0..20,
             old_text: "a\n".to_string(),
             new_text: "x\ny\nz\n".to_string(),
+            origin: None,
         }];
         let lines = to_side_by_side(&ops);
         assert_eq!(lines.len(), 3, "Should have 3 rows for max(1,3) lines");
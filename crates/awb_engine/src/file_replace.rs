@@ -0,0 +1,243 @@
+//! Replacing one file with another wherever a page uses it, e.g. after a
+//! file is renamed on Commons.
+//!
+//! Three kinds of use are rewritten: `[[File:...]]`, `[[Image:...]]` and
+//! `[[Media:...]]` links, lines of `<gallery>` blocks, and template
+//! parameters whose whole value is the file (`| image = Old.jpg`). Only the
+//! file name changes; the namespace prefix as written, captions, sizes and
+//! other options are kept. Comments and `<nowiki>`-style tags are masked
+//! first, so uses inside them are left alone.
+
+use crate::masking::{MaskPolicy, mask_with};
+use regex::Regex;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FileReplaceError {
+    #[error("file name is empty")]
+    EmptyName,
+    #[error("old and new file are the same: {0}")]
+    SameFile(String),
+}
+
+/// Normalize a file name: drop a `File:`/`Image:` prefix, underscores as
+/// spaces, runs of whitespace collapsed and the first letter uppercased
+pub fn normalize_file_name(name: &str) -> String {
+    let name = name.replace('_', " ");
+    let name = name.trim();
+    let name = match name.split_once(':') {
+        Some((prefix, rest))
+            if prefix.trim().eq_ignore_ascii_case("file")
+                || prefix.trim().eq_ignore_ascii_case("image") =>
+        {
+            rest
+        }
+        _ => name,
+    };
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Pattern matching `name` as it may be written: either case of the first
+/// letter, and spaces or underscores between words
+fn name_pattern(name: &str) -> String {
+    let mut chars = name.chars();
+    let first = chars.next().map_or(String::new(), |c| {
+        let lower: String = c.to_lowercase().collect();
+        let upper: String = c.to_uppercase().collect();
+        if lower == upper {
+            regex::escape(&lower)
+        } else {
+            format!("(?:{}|{})", regex::escape(&upper), regex::escape(&lower))
+        }
+    });
+    let rest: String = chars.collect();
+    let words: Vec<String> = rest.split(' ').map(regex::escape).collect();
+    format!("{}{}", first, words.join("[ _]+"))
+}
+
+/// One file to replace with another
+#[derive(Debug, Clone)]
+pub struct FileReplacement {
+    old: String,
+    new: String,
+    link_re: Regex,
+    gallery_line_re: Regex,
+    param_re: Regex,
+}
+
+impl FileReplacement {
+    pub fn new(old: &str, new: &str) -> Result<Self, FileReplaceError> {
+        let old = normalize_file_name(old);
+        let new = normalize_file_name(new);
+        if old.is_empty() || new.is_empty() {
+            return Err(FileReplaceError::EmptyName);
+        }
+        if old == new {
+            return Err(FileReplaceError::SameFile(old));
+        }
+        let name = name_pattern(&old);
+        let build = |pattern: String| Regex::new(&pattern).expect("escaped file name pattern");
+        Ok(Self {
+            link_re: build(format!(
+                r"\[\[[ \t]*(?i:file|image|media)[ \t]*:[ \t]*(?P<name>{})[ \t]*(?:\||\]\])",
+                name
+            )),
+            gallery_line_re: build(format!(
+                r"(?m)^[ \t]*(?:(?i:file|image)[ \t]*:[ \t]*)?(?P<name>{})[ \t]*(?:\||$)",
+                name
+            )),
+            param_re: build(format!(
+                r"\|[^=|{{}}\[\]]*=[ \t]*(?:(?i:file|image)[ \t]*:[ \t]*)?(?P<name>{})",
+                name
+            )),
+            old,
+            new,
+        })
+    }
+
+    pub fn old(&self) -> &str {
+        &self.old
+    }
+
+    pub fn new_name(&self) -> &str {
+        &self.new
+    }
+
+    /// Rewrite every use of the old file in `wikitext`. Returns the new text
+    /// and the number of uses changed.
+    pub fn apply(&self, wikitext: &str) -> (String, usize) {
+        let mut masked = mask_with(wikitext, MaskPolicy::COMMENTS | MaskPolicy::EXTENSION_TAGS);
+        let mut ranges = Vec::new();
+        for m in self.link_re.captures_iter(&masked.masked) {
+            ranges.push(m.name("name").expect("named group").range());
+        }
+        for block in gallery_blocks(&masked.masked) {
+            let body = &masked.masked[block.clone()];
+            for m in self.gallery_line_re.captures_iter(body) {
+                let name = m.name("name").expect("named group").range();
+                ranges.push(block.start + name.start..block.start + name.end);
+            }
+        }
+        for m in self.param_re.captures_iter(&masked.masked) {
+            let name = m.name("name").expect("named group").range();
+            // The value must be the file name alone
+            let after = masked.masked[name.end..].trim_start();
+            if after.starts_with('|') || after.starts_with("}}") {
+                ranges.push(name);
+            }
+        }
+        if ranges.is_empty() {
+            return (wikitext.to_string(), 0);
+        }
+
+        ranges.sort_by_key(|range| range.start);
+        ranges.dedup();
+        let count = ranges.len();
+        masked.transform(|text| {
+            let mut out = String::with_capacity(text.len());
+            let mut last = 0;
+            for range in &ranges {
+                out.push_str(&text[last..range.start]);
+                out.push_str(&self.new);
+                last = range.end;
+            }
+            out.push_str(&text[last..]);
+            out
+        });
+        (masked.unmask(), count)
+    }
+}
+
+/// Byte ranges of the contents of `<gallery>` blocks
+fn gallery_blocks(text: &str) -> Vec<std::ops::Range<usize>> {
+    let lower = text.to_ascii_lowercase();
+    let mut blocks = Vec::new();
+    let mut pos = 0;
+    while let Some(open) = lower[pos..].find("<gallery") {
+        let open = pos + open;
+        let Some(tag_end) = lower[open..].find('>') else {
+            break;
+        };
+        let body_start = open + tag_end + 1;
+        let Some(close) = lower[body_start..].find("</gallery") else {
+            break;
+        };
+        blocks.push(body_start..body_start + close);
+        pos = body_start + close;
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(text: &str) -> (String, usize) {
+        FileReplacement::new("File:Old photo.jpg", "New_photo.jpg")
+            .unwrap()
+            .apply(text)
+    }
+
+    #[test]
+    fn test_file_links_keep_options_and_caption() {
+        let (text, n) = replace(
+            "[[File:Old photo.jpg|thumb|upright=1.2|A [[link]] here]] \
+             [[image: old_photo.jpg]] [[Media:Old photo.jpg|sound]] [[File:Old photo.jpeg]]",
+        );
+        assert_eq!(n, 3);
+        assert_eq!(
+            text,
+            "[[File:New photo.jpg|thumb|upright=1.2|A [[link]] here]] \
+             [[image: New photo.jpg]] [[Media:New photo.jpg|sound]] [[File:Old photo.jpeg]]"
+        );
+    }
+
+    #[test]
+    fn test_gallery_lines() {
+        let (text, n) = replace(
+            "<gallery mode=packed>\nFile:Old photo.jpg|Caption\nOld_photo.jpg\nOther.png|x\n</gallery>",
+        );
+        assert_eq!(n, 2);
+        assert_eq!(
+            text,
+            "<gallery mode=packed>\nFile:New photo.jpg|Caption\nNew photo.jpg\nOther.png|x\n</gallery>"
+        );
+    }
+
+    #[test]
+    fn test_template_parameters() {
+        let (text, n) = replace(
+            "{{Infobox person\n| image = Old photo.jpg\n| caption = Old photo.jpg in 1990\n\
+             | logo=File:Old_photo.jpg}}",
+        );
+        assert_eq!(n, 2);
+        assert_eq!(
+            text,
+            "{{Infobox person\n| image = New photo.jpg\n| caption = Old photo.jpg in 1990\n\
+             | logo=File:New photo.jpg}}"
+        );
+    }
+
+    #[test]
+    fn test_comments_and_nowiki_untouched() {
+        let text = "<!-- [[File:Old photo.jpg]] --><nowiki>[[File:Old photo.jpg]]</nowiki>";
+        assert_eq!(replace(text), (text.to_string(), 0));
+    }
+
+    #[test]
+    fn test_invalid_replacements() {
+        assert_eq!(
+            FileReplacement::new("File:", "B.jpg").unwrap_err(),
+            FileReplaceError::EmptyName
+        );
+        assert!(matches!(
+            FileReplacement::new("a.jpg", "File:A.jpg"),
+            Err(FileReplaceError::SameFile(_))
+        ));
+    }
+}
@@ -1,8 +1,11 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::path::PathBuf;
 
-/// Classification of a fix module's impact.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Classification of a fix module's impact, ordered from least to most
+/// disruptive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FixClassification {
     /// Pure whitespace/formatting, no semantic change
     Cosmetic,
@@ -14,6 +17,38 @@ pub enum FixClassification {
     Editorial,
 }
 
+/// How the `defaultsort_fix` module derives sort keys for biographies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PersonSortKey {
+    /// Only fold diacritics in the title, as for any other page
+    #[default]
+    Off,
+    /// "Surname, Forename" from the `name` of the page's infobox
+    Infobox,
+    /// From the infobox `name`, falling back to the title
+    InfoboxOrTitle,
+}
+
+impl PersonSortKey {
+    pub fn is_off(&self) -> bool {
+        *self == Self::Off
+    }
+}
+
+/// Where the `spell_check` fix finds the dictionary for a wiki's language.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpellCheckConfig {
+    /// Path of a Hunspell `.dic` file; the `.aff` file of the same name
+    /// must sit next to it
+    pub dictionary: PathBuf,
+    /// Confidence (0-100) at which a misspelling is corrected instead of
+    /// only reported; unset to never correct
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_fix_confidence: Option<u8>,
+}
+
 /// Result of applying fixes with configuration.
 #[derive(Debug, Clone)]
 pub struct ApplyResult {
@@ -26,7 +61,7 @@ pub struct ApplyResult {
 }
 
 /// Configuration for controlling which fixes are applied.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct FixConfig {
     /// Maximum strictness tier to apply (0-3)
@@ -41,6 +76,24 @@ pub struct FixConfig {
     /// If false, reject edits that produce only cosmetic changes
     #[serde(default)]
     pub allow_cosmetic_only: bool,
+    /// Templates the `maintenance_tag_dating` fix dates on this wiki, with
+    /// their redirects (empty for the built-in English Wikipedia list)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dated_templates: Vec<String>,
+    /// Order of interlanguage links on this wiki, as language prefixes;
+    /// languages not listed follow, by prefix (empty to sort by prefix)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interlanguage_order: Vec<String>,
+    /// Whether `defaultsort_fix` sorts biographies as "Surname, Forename"
+    #[serde(default, skip_serializing_if = "PersonSortKey::is_off")]
+    pub person_sort_key: PersonSortKey,
+    /// Words `heading_normalization` keeps capitalised when putting headings
+    /// in sentence case, such as proper nouns, besides month and day names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub heading_case_exceptions: Vec<String>,
+    /// Dictionary for the `spell_check` fix, which only runs when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spell_check: Option<SpellCheckConfig>,
 }
 
 fn default_tier() -> u8 {
@@ -54,6 +107,11 @@ impl Default for FixConfig {
             enabled_fixes: HashSet::new(),
             disabled_fixes: HashSet::new(),
             allow_cosmetic_only: false,
+            dated_templates: Vec::new(),
+            interlanguage_order: Vec::new(),
+            person_sort_key: PersonSortKey::Off,
+            heading_case_exceptions: Vec::new(),
+            spell_check: None,
         }
     }
 }
@@ -63,12 +121,20 @@ impl Default for FixConfig {
 pub enum FixConfigError {
     #[error("strictness_tier must be 0-3, got {0}")]
     InvalidTier(u8),
+    #[error("spell_check.auto_fix_confidence must be 0-100, got {0}")]
+    InvalidConfidence(u8),
     #[error("unknown fix ID in enabled_fixes: {0}")]
     UnknownEnabledId(String),
     #[error("unknown fix ID in disabled_fixes: {0}")]
     UnknownDisabledId(String),
     #[error("TOML parse error: {0}")]
     ParseError(String),
+    #[error("duplicate fix ID: {0}")]
+    DuplicateId(String),
+    #[error("fix ordering cycle involving: {0}")]
+    DependencyCycle(String),
+    #[error("no fix registered with ID: {0}")]
+    UnknownId(String),
 }
 
 impl FixConfig {
@@ -77,11 +143,50 @@ impl FixConfig {
         toml::from_str(s).map_err(|e| FixConfigError::ParseError(e.to_string()))
     }
 
+    /// Whether a fix with the given ID and minimum tier runs under this
+    /// configuration.
+    pub fn allows(&self, id: &str, min_tier: u8) -> bool {
+        // Tier gate
+        if min_tier > self.strictness_tier {
+            return false;
+        }
+        // Explicit disable list
+        if self.disabled_fixes.contains(id) {
+            return false;
+        }
+        // Explicit enable list (if non-empty, only listed IDs run)
+        self.enabled_fixes.is_empty() || self.enabled_fixes.contains(id)
+    }
+
+    /// Turn a single fix on or off, keeping the rest of the selection.
+    ///
+    /// This only edits the enable/disable lists; a fix above the strictness
+    /// tier still does not run.
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) {
+        if enabled {
+            self.disabled_fixes.remove(id);
+            if !self.enabled_fixes.is_empty() {
+                self.enabled_fixes.insert(id.to_string());
+            }
+        } else {
+            self.enabled_fixes.remove(id);
+            self.disabled_fixes.insert(id.to_string());
+        }
+    }
+
     /// Validate that tier is in range and all fix IDs are known.
     pub fn validate(&self, known_ids: &HashSet<&str>) -> Result<(), FixConfigError> {
         if self.strictness_tier > 3 {
             return Err(FixConfigError::InvalidTier(self.strictness_tier));
         }
+        if let Some(confidence) = self
+            .spell_check
+            .as_ref()
+            .and_then(|spell| spell.auto_fix_confidence)
+            .filter(|c| *c > 100)
+        {
+            return Err(FixConfigError::InvalidConfidence(confidence));
+        }
         for id in &self.enabled_fixes {
             if !known_ids.contains(id.as_str()) {
                 return Err(FixConfigError::UnknownEnabledId(id.clone()));
@@ -122,12 +227,23 @@ strictness_tier = 2
 enabled_fixes = ["whitespace_cleanup", "heading_spacing"]
 disabled_fixes = ["citation_formatting"]
 allow_cosmetic_only = true
+dated_templates = ["Belege fehlen", "Quelle"]
+person_sort_key = "infobox_or_title"
+
+[spell_check]
+dictionary = "dictionaries/de_DE.dic"
+auto_fix_confidence = 90
 "#;
         let cfg = FixConfig::from_toml(toml).unwrap();
         assert_eq!(cfg.strictness_tier, 2);
         assert!(cfg.enabled_fixes.contains("whitespace_cleanup"));
         assert!(cfg.disabled_fixes.contains("citation_formatting"));
         assert!(cfg.allow_cosmetic_only);
+        assert_eq!(cfg.dated_templates, vec!["Belege fehlen", "Quelle"]);
+        assert_eq!(cfg.person_sort_key, PersonSortKey::InfoboxOrTitle);
+        let spell = cfg.spell_check.unwrap();
+        assert_eq!(spell.dictionary, PathBuf::from("dictionaries/de_DE.dic"));
+        assert_eq!(spell.auto_fix_confidence, Some(90));
     }
 
     #[test]
@@ -173,9 +289,42 @@ allow_cosmetic_only = true
         assert!(cfg.validate(&known).is_ok());
     }
 
+    #[test]
+    fn test_classification_ordering_and_parsing() {
+        assert!(FixClassification::Cosmetic < FixClassification::Maintenance);
+        assert!(FixClassification::StyleSensitive < FixClassification::Editorial);
+
+        #[derive(Deserialize)]
+        struct Wrapper {
+            c: FixClassification,
+        }
+        let w: Wrapper = toml::from_str("c = \"style_sensitive\"").unwrap();
+        assert_eq!(w.c, FixClassification::StyleSensitive);
+    }
+
     #[test]
     fn test_from_toml_unknown_field_rejected() {
         let result = FixConfig::from_toml("bogus_field = true\n");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_set_enabled() {
+        let mut cfg = FixConfig::default();
+        cfg.set_enabled("a", false);
+        assert!(!cfg.allows("a", 0));
+        assert!(cfg.allows("b", 0));
+        cfg.set_enabled("a", true);
+        assert!(cfg.allows("a", 0));
+        assert!(cfg.disabled_fixes.is_empty());
+
+        // With an explicit enable list, enabling adds to it
+        cfg.enabled_fixes.insert("b".to_string());
+        assert!(!cfg.allows("a", 0));
+        cfg.set_enabled("a", true);
+        assert!(cfg.allows("a", 0));
+
+        // Tier still gates
+        assert!(!cfg.allows("a", 2));
+    }
 }
@@ -1,13 +1,25 @@
-use crate::fix_config::{ApplyResult, FixClassification, FixConfig, FixConfigError};
-use awb_domain::types::{Namespace, Title};
+use crate::fix_config::{ApplyResult, FixClassification, FixConfig, FixConfigError, PersonSortKey};
+use crate::masking::{MaskPolicy, mask_with};
+use crate::memory::MemoryProfile;
+use crate::rope::Rope;
+use crate::template_params::{normalize_template_name, parse_templates, template_spans};
+use awb_domain::types::{Namespace, RevisionId, Title};
+use serde::Serialize;
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::OnceLock;
+use tokio_util::sync::CancellationToken;
 
 pub struct FixContext {
     pub title: Title,
     pub namespace: Namespace,
     pub is_redirect: bool,
+    /// Revision the text was loaded from, if known
+    pub revision: Option<RevisionId>,
+    /// Once cancelled, the pipeline runs no further modules, and long
+    /// running modules such as plugins may stop early, leaving their text
+    /// unchanged
+    pub cancel: CancellationToken,
 }
 
 pub trait FixModule: Send + Sync {
@@ -27,8 +39,140 @@ pub trait FixModule: Send + Sync {
     fn min_tier(&self) -> u8 {
         1
     }
+    /// IDs of modules this module must run before, when they are registered.
+    fn runs_before(&self) -> &[&str] {
+        &[]
+    }
+    /// IDs of modules this module must run after, when they are registered.
+    fn runs_after(&self) -> &[&str] {
+        &[]
+    }
+    /// Protected regions this module needs to see.
+    ///
+    /// Everything else in [`MaskPolicy::ALL`] is masked before `apply` runs.
+    fn required_visibility(&self) -> MaskPolicy {
+        MaskPolicy::NONE
+    }
+    /// Apply the module to a [`Rope`], returning whether the text changed.
+    ///
+    /// The default runs [`apply`](Self::apply) on the whole text, so every
+    /// module works on ropes as it is. A module whose changes never cross a
+    /// line break can override this to rewrite only the chunks it changes,
+    /// with [`Rope::map_lines`]; the pipeline only calls it for modules that
+    /// need no masking.
+    fn apply_rope(&self, text: &mut Rope, context: &FixContext) -> bool {
+        text.map_whole(|text| self.apply(text, context))
+    }
+    /// Problems this module noticed in the final `text` but left for a
+    /// human, e.g. a change it declined to make. Reported on the edit plan.
+    fn warnings(&self, _text: &str, _ctx: &FixContext) -> Vec<String> {
+        Vec::new()
+    }
+    /// Changes this module made to a page that a human should check, worked
+    /// out from the `before` and final `after` text. Reported on the edit
+    /// plan with the [`warnings`](Self::warnings).
+    fn change_warnings(&self, _before: &str, _after: &str, _ctx: &FixContext) -> Vec<String> {
+        Vec::new()
+    }
+    /// Metadata about the page this module worked out the last time it ran
+    /// on it, as key/value pairs. Reported on the edit plan.
+    fn page_metadata(&self, _ctx: &FixContext) -> Vec<(String, String)> {
+        Vec::new()
+    }
+    /// Text to add to the edit summary for the changes this module made the
+    /// last time it ran on the page.
+    fn summary_fragment(&self, _ctx: &FixContext) -> Option<String> {
+        None
+    }
+    /// Whether this module asked, the last time it ran on the page, for the
+    /// page to be left alone. The edit plan then carries a
+    /// [`Warning::SkipRequested`](awb_domain::warnings::Warning::SkipRequested).
+    fn requests_skip(&self, _ctx: &FixContext) -> bool {
+        false
+    }
+    /// Key/value facts pinning down exactly what this module runs, such as
+    /// plugin source hashes or random seeds, for reproducing a run later.
+    /// Recorded in run reports.
+    fn provenance(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+}
+
+/// Descriptive metadata of a fix module, for listing fixes in UIs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FixInfo {
+    pub id: String,
+    pub display_name: String,
+    pub category: String,
+    pub description: String,
+    pub classification: FixClassification,
+    pub min_tier: u8,
+    pub default_enabled: bool,
 }
 
+impl FixInfo {
+    pub fn of(module: &dyn FixModule) -> Self {
+        Self {
+            id: module.id().to_string(),
+            display_name: module.display_name().to_string(),
+            category: module.category().to_string(),
+            description: module.description().to_string(),
+            classification: module.classification(),
+            min_tier: module.min_tier(),
+            default_enabled: module.default_enabled(),
+        }
+    }
+}
+
+/// Apply `module` with every region it does not need to see masked.
+///
+/// Returns `text` borrowed if the module left it unchanged. If the module
+/// damages a mask sentinel, the original text is kept (fail closed).
+pub fn apply_masked<'a>(module: &dyn FixModule, text: &'a str, ctx: &FixContext) -> Cow<'a, str> {
+    let policy = MaskPolicy::ALL.difference(module.required_visibility());
+    if policy.is_empty() {
+        return module.apply(text, ctx);
+    }
+    let mut masked = mask_with(text, policy);
+    if !masked.has_regions() {
+        return module.apply(text, ctx);
+    }
+    let changed = match module.apply(&masked.masked, ctx) {
+        Cow::Borrowed(out) if std::ptr::eq(out, masked.masked.as_str()) => None,
+        out => Some(out.into_owned()),
+    };
+    let Some(changed) = changed else {
+        return Cow::Borrowed(text);
+    };
+    masked.masked = changed;
+    let result = masked.unmask();
+    if result == text {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+/// [`apply_masked`] for a [`Rope`], returning whether the text changed.
+/// A module that needs every region visible gets the rope itself through
+/// [`FixModule::apply_rope`].
+pub fn apply_masked_rope(module: &dyn FixModule, text: &mut Rope, ctx: &FixContext) -> bool {
+    if MaskPolicy::ALL
+        .difference(module.required_visibility())
+        .is_empty()
+    {
+        return module.apply_rope(text, ctx);
+    }
+    text.map_whole(|text| apply_masked(module, text, ctx))
+}
+
+/// Ordered collection of fix modules.
+///
+/// Modules run in registration order: built-in defaults first (when
+/// requested), followed by modules added with [`FixRegistry::register`] in
+/// the order they were added. Declared `runs_before`/`runs_after` relations
+/// override this order; modules without relations between them keep their
+/// relative order. Fix IDs are unique within a registry.
 pub struct FixRegistry {
     modules: Vec<Box<dyn FixModule>>,
 }
@@ -41,30 +185,74 @@ impl FixRegistry {
     }
 
     pub fn with_defaults() -> Self {
-        Self {
-            modules: vec![
-                Box::new(WhitespaceCleanup),
-                Box::new(HeadingSpacing),
-                Box::new(HtmlToWikitext),
-                Box::new(TrailingWhitespace),
-                Box::new(CategorySorting),
-                Box::new(CitationFormatting),
-                Box::new(DuplicateWikilinkRemoval),
-                Box::new(UnicodeNormalization),
-                Box::new(DefaultSortFix),
-            ],
-        }
+        Self::with_config(&FixConfig::default())
     }
 
-    pub fn apply_all(&self, text: &str, ctx: &FixContext, enabled_ids: &HashSet<String>) -> String {
-        let mut result = text.to_string();
-        for module in &self.modules {
-            if enabled_ids.contains(module.id()) {
-                let new_result = module.apply(&result, ctx);
-                result = new_result.into_owned();
+    /// Built-in fixes, with the per-wiki settings in `config` (such as
+    /// [`FixConfig::dated_templates`]) applied. Which fixes run is still
+    /// chosen with [`enabled_ids`](Self::enabled_ids).
+    pub fn with_config(config: &FixConfig) -> Self {
+        let mut registry = Self {
+            modules: default_modules(config),
+        };
+        let order = run_order(&registry.modules).expect("built-in fix ordering is acyclic");
+        registry.reorder(order);
+        registry
+    }
+
+    /// Start building a registry from defaults, plugin and programmatic fixes.
+    pub fn builder() -> FixRegistryBuilder {
+        FixRegistryBuilder::default()
+    }
+
+    /// Append a module to the run order, honoring its declared relations.
+    ///
+    /// Returns an error, leaving the registry unchanged, if a module with
+    /// the same ID is already registered or the relations form a cycle.
+    pub fn register(&mut self, module: Box<dyn FixModule>) -> Result<(), FixConfigError> {
+        if self.contains(module.id()) {
+            return Err(FixConfigError::DuplicateId(module.id().to_string()));
+        }
+        self.modules.push(module);
+        match run_order(&self.modules) {
+            Ok(order) => {
+                self.reorder(order);
+                Ok(())
+            }
+            Err(e) => {
+                self.modules.pop();
+                Err(e)
             }
         }
-        result
+    }
+
+    fn reorder(&mut self, order: Vec<usize>) {
+        let mut slots: Vec<Option<Box<dyn FixModule>>> = self.modules.drain(..).map(Some).collect();
+        self.modules = order.into_iter().filter_map(|i| slots[i].take()).collect();
+    }
+
+    /// Whether a module with the given ID is registered.
+    pub fn contains(&self, id: &str) -> bool {
+        self.modules.iter().any(|m| m.id() == id)
+    }
+
+    pub fn apply_all(&self, text: &str, ctx: &FixContext, enabled_ids: &HashSet<String>) -> String {
+        self.apply_all_cow(text, ctx, enabled_ids).into_owned()
+    }
+
+    /// Apply all enabled fixes, borrowing `text` unless some fix changes it.
+    pub fn apply_all_cow<'a>(
+        &self,
+        text: &'a str,
+        ctx: &FixContext,
+        enabled_ids: &HashSet<String>,
+    ) -> Cow<'a, str> {
+        run_pipeline(
+            self.modules.iter().filter(|m| enabled_ids.contains(m.id())),
+            text,
+            ctx,
+            |_| {},
+        )
     }
 
     /// Apply all enabled fixes, returning the list of fix IDs that made changes
@@ -75,33 +263,157 @@ impl FixRegistry {
         ctx: &FixContext,
         enabled_ids: &HashSet<String>,
     ) -> (Vec<String>, String) {
-        if enabled_ids.is_empty() {
-            return (Vec::new(), text.to_string());
-        }
         let mut changed_ids = Vec::new();
-        let mut current = text.to_string();
-        for module in &self.modules {
-            if enabled_ids.contains(module.id()) {
-                let new = module.apply(&current, ctx);
-                let new_owned = new.into_owned();
-                if new_owned != current {
-                    changed_ids.push(module.id().to_string());
-                    current = new_owned;
-                }
+        let result = run_pipeline(
+            self.modules.iter().filter(|m| enabled_ids.contains(m.id())),
+            text,
+            ctx,
+            |m| changed_ids.push(m.id().to_string()),
+        );
+        (changed_ids, result.into_owned())
+    }
+
+    /// Like [`apply_all_returning_ids`](Self::apply_all_returning_ids),
+    /// recording each fix's changes in `changes` under its ID, and each fix
+    /// that runs in `profile` if given.
+    pub fn apply_all_tracking(
+        &self,
+        text: &str,
+        ctx: &FixContext,
+        enabled_ids: &HashSet<String>,
+        changes: &mut crate::provenance::ChangeMap,
+        profile: Option<&mut MemoryProfile>,
+    ) -> (Vec<String>, String) {
+        let mut changed_ids = Vec::new();
+        let result = run_rope_pipeline(
+            self.modules.iter().filter(|m| enabled_ids.contains(m.id())),
+            Rope::from(text.to_string()),
+            ctx,
+            profile,
+            |m, before, after| {
+                changes.record_rope(before, after, m.id());
+                changed_ids.push(m.id().to_string());
+            },
+        );
+        (changed_ids, result.into_string())
+    }
+
+    /// Warnings of the enabled fixes about `text`, normally the pipeline's
+    /// output, and about their changes to it from `before`, as
+    /// `(fix ID, message)` pairs in run order.
+    pub fn warnings(
+        &self,
+        before: &str,
+        text: &str,
+        ctx: &FixContext,
+        enabled_ids: &HashSet<String>,
+    ) -> Vec<(String, String)> {
+        self.modules
+            .iter()
+            .filter(|m| enabled_ids.contains(m.id()))
+            .flat_map(|m| {
+                m.change_warnings(before, text, ctx)
+                    .into_iter()
+                    .chain(m.warnings(text, ctx))
+                    .map(|message| (m.id().to_string(), message))
+            })
+            .collect()
+    }
+
+    /// Metadata the enabled fixes recorded about the page of `ctx`; a key
+    /// set by more than one module keeps the value of the last to run.
+    pub fn page_metadata(
+        &self,
+        ctx: &FixContext,
+        enabled_ids: &HashSet<String>,
+    ) -> BTreeMap<String, String> {
+        self.modules
+            .iter()
+            .filter(|m| enabled_ids.contains(m.id()))
+            .flat_map(|m| m.page_metadata(ctx))
+            .collect()
+    }
+
+    /// Summary fragments the enabled fixes recorded about the page of
+    /// `ctx`, in run order.
+    pub fn summary_fragments(
+        &self,
+        ctx: &FixContext,
+        enabled_ids: &HashSet<String>,
+    ) -> Vec<String> {
+        self.modules
+            .iter()
+            .filter(|m| enabled_ids.contains(m.id()))
+            .filter_map(|m| m.summary_fragment(ctx))
+            .collect()
+    }
+
+    /// IDs of the enabled fixes that asked for the page of `ctx` to be
+    /// skipped, in run order.
+    pub fn skip_requests(&self, ctx: &FixContext, enabled_ids: &HashSet<String>) -> Vec<String> {
+        self.modules
+            .iter()
+            .filter(|m| enabled_ids.contains(m.id()))
+            .filter(|m| m.requests_skip(ctx))
+            .map(|m| m.id().to_string())
+            .collect()
+    }
+
+    /// Put `module` in place of the registered module with the same ID,
+    /// e.g. one set up with per-run data such as
+    /// [`InterlanguageLinks::with_sitelinks`].
+    ///
+    /// Returns an error, leaving the registry unchanged, if no module has
+    /// that ID or the new relations form a cycle.
+    pub fn replace(&mut self, module: Box<dyn FixModule>) -> Result<(), FixConfigError> {
+        let Some(index) = self.modules.iter().position(|m| m.id() == module.id()) else {
+            return Err(FixConfigError::UnknownId(module.id().to_string()));
+        };
+        let previous = std::mem::replace(&mut self.modules[index], module);
+        match run_order(&self.modules) {
+            Ok(order) => {
+                self.reorder(order);
+                Ok(())
+            }
+            Err(e) => {
+                self.modules[index] = previous;
+                Err(e)
             }
         }
-        (changed_ids, current)
     }
 
+    /// All modules, in run order.
     pub fn all_modules(&self) -> &[Box<dyn FixModule>] {
         &self.modules
     }
 
+    /// Metadata of all modules, in run order.
+    pub fn fix_infos(&self) -> Vec<FixInfo> {
+        self.modules
+            .iter()
+            .map(|m| FixInfo::of(m.as_ref()))
+            .collect()
+    }
+
     /// Returns the set of all known fix IDs.
     pub fn known_ids(&self) -> HashSet<&str> {
         self.modules.iter().map(|m| m.id()).collect()
     }
 
+    /// IDs of the fixes `config` allows to run, for use with
+    /// [`apply_all`](Self::apply_all) or a `TransformEngine`.
+    ///
+    /// Returns an error if the config references unknown fix IDs.
+    pub fn enabled_ids(&self, config: &FixConfig) -> Result<HashSet<String>, FixConfigError> {
+        config.validate(&self.known_ids())?;
+        Ok(self
+            .modules
+            .iter()
+            .filter(|module| config.allows(module.id(), module.min_tier()))
+            .map(|module| module.id().to_string())
+            .collect())
+    }
+
     /// Apply fixes filtered by a `FixConfig`.
     ///
     /// Returns an error if the config references unknown fix IDs.
@@ -113,39 +425,24 @@ impl FixRegistry {
     ) -> Result<ApplyResult, FixConfigError> {
         config.validate(&self.known_ids())?;
 
-        let mut current = text.to_string();
         let mut changed_ids = Vec::new();
         let mut all_cosmetic = true;
 
-        for module in &self.modules {
-            // Tier gate
-            if module.min_tier() > config.strictness_tier {
-                continue;
-            }
-            // Explicit disable list
-            if config.disabled_fixes.contains(module.id()) {
-                continue;
-            }
-            // Explicit enable list (if non-empty, only listed IDs run)
-            if !config.enabled_fixes.is_empty() && !config.enabled_fixes.contains(module.id()) {
-                continue;
-            }
-
-            let new = module.apply(&current, ctx);
-            let new_owned = new.into_owned();
-            if new_owned != current {
-                changed_ids.push(module.id().to_string());
-                if module.classification() != FixClassification::Cosmetic {
-                    all_cosmetic = false;
-                }
-                current = new_owned;
+        let modules = self
+            .modules
+            .iter()
+            .filter(|module| config.allows(module.id(), module.min_tier()));
+        let result = run_pipeline(modules, text, ctx, |module| {
+            changed_ids.push(module.id().to_string());
+            if module.classification() != FixClassification::Cosmetic {
+                all_cosmetic = false;
             }
-        }
+        });
 
         let is_cosmetic_only = !changed_ids.is_empty() && all_cosmetic;
 
         Ok(ApplyResult {
-            final_text: current,
+            final_text: result.into_owned(),
             changed_ids,
             is_cosmetic_only,
         })
@@ -158,6 +455,182 @@ impl Default for FixRegistry {
     }
 }
 
+/// Run `modules` in order over `text`, calling `on_change` with each module
+/// that changed it. Each module runs through [`apply_masked`]; none run
+/// once `ctx.cancel` is cancelled.
+///
+/// The input stays borrowed until the first module makes a change, so a
+/// page no fix touches is returned without being copied.
+fn run_pipeline<'a, 'm>(
+    modules: impl IntoIterator<Item = &'m Box<dyn FixModule>>,
+    text: &'a str,
+    ctx: &FixContext,
+    mut on_change: impl FnMut(&dyn FixModule),
+) -> Cow<'a, str> {
+    let mut modules = modules.into_iter();
+    for module in modules.by_ref() {
+        if ctx.cancel.is_cancelled() {
+            break;
+        }
+        let changed = match apply_masked(module.as_ref(), text, ctx) {
+            Cow::Borrowed(out) if std::ptr::eq(out, text) => continue,
+            Cow::Borrowed(out) => out.to_string(),
+            Cow::Owned(out) if out == text => continue,
+            Cow::Owned(out) => out,
+        };
+        on_change(module.as_ref());
+        let rest = run_rope_pipeline(modules, Rope::from(changed), ctx, None, |m, _, _| {
+            on_change(m)
+        });
+        return Cow::Owned(rest.into_string());
+    }
+    Cow::Borrowed(text)
+}
+
+/// Run `modules` in order over `text`, calling `on_change` with each module
+/// that changed it and the text before and after. Each module runs through
+/// [`apply_masked_rope`], and is added to `profile` if given.
+fn run_rope_pipeline<'m>(
+    modules: impl IntoIterator<Item = &'m Box<dyn FixModule>>,
+    mut text: Rope,
+    ctx: &FixContext,
+    mut profile: Option<&mut MemoryProfile>,
+    mut on_change: impl FnMut(&dyn FixModule, &Rope, &Rope),
+) -> Rope {
+    for module in modules {
+        if ctx.cancel.is_cancelled() {
+            break;
+        }
+        let before = text.clone();
+        let changed = match profile.as_deref_mut() {
+            Some(profile) => profile.step(module.id(), || {
+                let changed = apply_masked_rope(module.as_ref(), &mut text, ctx);
+                (changed, text.len())
+            }),
+            None => apply_masked_rope(module.as_ref(), &mut text, ctx),
+        };
+        if changed {
+            on_change(module.as_ref(), &before, &text);
+        }
+    }
+    text
+}
+
+/// Topologically sort modules by their declared relations.
+///
+/// Among modules that are ready to run, the one earliest in `modules` goes
+/// first, so an order that already satisfies every relation is returned
+/// unchanged. Relations naming unregistered IDs are ignored.
+fn run_order(modules: &[Box<dyn FixModule>]) -> Result<Vec<usize>, FixConfigError> {
+    let index: HashMap<&str, usize> = modules
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (m.id(), i))
+        .collect();
+    let mut successors = vec![Vec::new(); modules.len()];
+    let mut in_degree = vec![0usize; modules.len()];
+    for (i, module) in modules.iter().enumerate() {
+        for &j in module.runs_after().iter().filter_map(|id| index.get(id)) {
+            successors[j].push(i);
+            in_degree[i] += 1;
+        }
+        for &j in module.runs_before().iter().filter_map(|id| index.get(id)) {
+            successors[i].push(j);
+            in_degree[j] += 1;
+        }
+    }
+
+    let mut ready: BTreeSet<usize> = (0..modules.len()).filter(|&i| in_degree[i] == 0).collect();
+    let mut order = Vec::with_capacity(modules.len());
+    while let Some(i) = ready.pop_first() {
+        order.push(i);
+        for &j in &successors[i] {
+            in_degree[j] -= 1;
+            if in_degree[j] == 0 {
+                ready.insert(j);
+            }
+        }
+    }
+
+    if order.len() < modules.len() {
+        let stuck: Vec<&str> = (0..modules.len())
+            .filter(|&i| in_degree[i] > 0)
+            .map(|i| modules[i].id())
+            .collect();
+        return Err(FixConfigError::DependencyCycle(stuck.join(", ")));
+    }
+    Ok(order)
+}
+
+fn default_modules(config: &FixConfig) -> Vec<Box<dyn FixModule>> {
+    vec![
+        Box::new(WhitespaceCleanup),
+        Box::new(HeadingSpacing),
+        Box::new(HeadingNormalization::new(&config.heading_case_exceptions)),
+        Box::new(HtmlToWikitext),
+        Box::new(ListFormatting),
+        Box::new(TrailingWhitespace),
+        Box::new(CategorySorting),
+        Box::new(CitationFormatting),
+        Box::new(DuplicateWikilinkRemoval),
+        Box::new(UnicodeNormalization),
+        Box::new(UnicodeNfc),
+        Box::new(DefaultSortFix::new(config.person_sort_key)),
+        Box::new(MaintenanceTagDating::new(&config.dated_templates)),
+        Box::new(InterlanguageLinks::new(&config.interlanguage_order)),
+    ]
+}
+
+/// Builder merging built-in, plugin-provided and programmatic fixes.
+///
+/// Regardless of call order, the resulting registry runs defaults first,
+/// then plugin fixes, then programmatic fixes; within each group modules
+/// keep the order they were added in.
+#[derive(Default)]
+pub struct FixRegistryBuilder {
+    defaults: Option<FixConfig>,
+    plugin_modules: Vec<Box<dyn FixModule>>,
+    modules: Vec<Box<dyn FixModule>>,
+}
+
+impl FixRegistryBuilder {
+    /// Include the built-in fix modules.
+    pub fn with_defaults(self) -> Self {
+        self.with_config(&FixConfig::default())
+    }
+
+    /// Include the built-in fix modules, set up as in
+    /// [`FixRegistry::with_config`].
+    pub fn with_config(mut self, config: &FixConfig) -> Self {
+        self.defaults = Some(config.clone());
+        self
+    }
+
+    /// Add a fix provided by a plugin host.
+    pub fn plugin(mut self, module: Box<dyn FixModule>) -> Self {
+        self.plugin_modules.push(module);
+        self
+    }
+
+    /// Add a programmatic fix.
+    pub fn module(mut self, module: Box<dyn FixModule>) -> Self {
+        self.modules.push(module);
+        self
+    }
+
+    /// Build the registry, failing on the first duplicate fix ID.
+    pub fn build(self) -> Result<FixRegistry, FixConfigError> {
+        let mut registry = match &self.defaults {
+            Some(config) => FixRegistry::with_config(config),
+            None => FixRegistry::new(),
+        };
+        for module in self.plugin_modules.into_iter().chain(self.modules) {
+            registry.register(module)?;
+        }
+        Ok(registry)
+    }
+}
+
 // --- Built-in fix modules ---
 
 pub struct WhitespaceCleanup;
@@ -180,6 +653,23 @@ impl FixModule for WhitespaceCleanup {
     fn min_tier(&self) -> u8 {
         0
     }
+    /// Other built-in fixes may leave stray blank lines or trailing spaces
+    /// behind, so whitespace is tidied up last.
+    fn runs_after(&self) -> &[&str] {
+        &[
+            "heading_spacing",
+            "html_to_wikitext",
+            "trailing_whitespace",
+            "category_sorting",
+            "citation_formatting",
+            "duplicate_wikilink_removal",
+            "unicode_normalization",
+            "unicode_nfc",
+            "defaultsort_fix",
+            "maintenance_tag_dating",
+            "interlanguage_links",
+        ]
+    }
     fn apply<'a>(&self, text: &'a str, _ctx: &FixContext) -> Cow<'a, str> {
         if text.is_empty() {
             return Cow::Borrowed(text);
@@ -341,6 +831,312 @@ impl FixModule for HeadingSpacing {
     }
 }
 
+/// Words [`HeadingNormalization`] always keeps capitalised: the pronoun "I"
+/// and the names of months and days.
+pub const DEFAULT_HEADING_CASE_EXCEPTIONS: &[&str] = &[
+    "I",
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// Short words title case leaves in lowercase
+const TITLE_CASE_SMALL_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "into", "nor", "of", "on",
+    "or", "the", "to", "with",
+];
+
+/// Normalizes section headings to the Manual of Style.
+///
+/// Bold and links are removed from heading text, a heading more than one
+/// level below its parent is raised (with its subsections) to the next
+/// level, and headings in title case are put in sentence case. Sentence
+/// case leaves the first word, acronyms, mixed-case words and the
+/// exceptions (usually proper nouns) alone, and only applies to headings
+/// where every word after the first is capitalised, apart from short words
+/// such as "of". Headings containing templates, comments, quotes or HTML
+/// are left as they are. Every changed heading is reported as a warning.
+pub struct HeadingNormalization {
+    exceptions: HashSet<String>,
+}
+
+impl HeadingNormalization {
+    /// Keep `exceptions` capitalised as well as
+    /// [`DEFAULT_HEADING_CASE_EXCEPTIONS`]; an entry of several words
+    /// keeps each of them
+    pub fn new(exceptions: &[String]) -> Self {
+        Self {
+            exceptions: DEFAULT_HEADING_CASE_EXCEPTIONS
+                .iter()
+                .copied()
+                .chain(exceptions.iter().flat_map(|e| e.split_whitespace()))
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// The heading text in sentence case, if it is in title case
+    fn sentence_case(&self, inner: &str) -> Option<String> {
+        const UNSAFE: &[char] = &[
+            '\0', '<', '>', '{', '}', '[', ']', '|', '\'', '"', ':', '&', '.', '!', '?',
+        ];
+        if inner.contains(UNSAFE) {
+            return None;
+        }
+        let words: Vec<&str> = inner.split(' ').collect();
+        let first = words.iter().position(|w| !w.is_empty())?;
+
+        let mut capitalised = 0;
+        let mut small_lowercase = 0;
+        for word in &words[first + 1..] {
+            match word_case(word) {
+                WordCase::Capitalised => capitalised += 1,
+                WordCase::Lowercase if TITLE_CASE_SMALL_WORDS.contains(word) => {
+                    small_lowercase += 1
+                }
+                WordCase::Lowercase => return None,
+                WordCase::Other => {}
+            }
+        }
+        if capitalised == 0 || (small_lowercase > 0 && capitalised < 2) {
+            return None;
+        }
+
+        let mut changed = false;
+        let words: Vec<Cow<str>> = words
+            .iter()
+            .enumerate()
+            .map(|(i, &word)| {
+                let core = word.trim_matches(|c: char| !c.is_alphanumeric());
+                if i <= first
+                    || word_case(word) != WordCase::Capitalised
+                    || self.exceptions.contains(core)
+                {
+                    return Cow::Borrowed(word);
+                }
+                changed = true;
+                let mut chars = word.chars();
+                let lowered: String = chars
+                    .by_ref()
+                    .take(1)
+                    .flat_map(char::to_lowercase)
+                    .collect();
+                Cow::Owned(lowered + chars.as_str())
+            })
+            .collect();
+        changed.then(|| words.join(" "))
+    }
+
+    /// The text with its headings normalised, and each changed heading with
+    /// a description of the change
+    fn normalize<'a>(&self, text: &'a str) -> (Cow<'a, str>, Vec<(String, String)>) {
+        // (original level, new level) of the enclosing headings
+        let mut parents: Vec<(usize, usize)> = Vec::new();
+        let mut changes = Vec::new();
+        let mut result = String::with_capacity(text.len());
+        for line in text.split_inclusive('\n') {
+            let Some((level, inner)) = parse_heading(line) else {
+                result.push_str(line);
+                continue;
+            };
+            while parents.last().is_some_and(|&(parent, _)| parent >= level) {
+                parents.pop();
+            }
+            let new_level = match parents.last() {
+                Some(&(_, parent)) if !inner.contains('\0') => level.min(parent + 1),
+                _ => level,
+            };
+            parents.push((level, new_level));
+            if inner.contains('\0') {
+                result.push_str(line);
+                continue;
+            }
+
+            let mut reasons = Vec::new();
+            if new_level != level {
+                reasons.push(format!("level {} to {}", level, new_level));
+            }
+            let mut new_inner = inner.to_string();
+            if let Some(unbolded) = strip_bold(&new_inner) {
+                new_inner = unbolded;
+                reasons.push("bold removed".to_string());
+            }
+            if let Some(unlinked) = strip_links(&new_inner) {
+                new_inner = unlinked;
+                reasons.push("links removed".to_string());
+            }
+            if let Some(cased) = self.sentence_case(&new_inner) {
+                new_inner = cased;
+                reasons.push("sentence case".to_string());
+            }
+            if reasons.is_empty() {
+                result.push_str(line);
+                continue;
+            }
+
+            let marks = "=".repeat(new_level);
+            let heading = format!("{}{}{}", marks, new_inner, marks);
+            let message = format!(
+                "heading {} changed to {} ({})",
+                line.trim_end(),
+                heading,
+                reasons.join(", ")
+            );
+            result.push_str(&heading);
+            result.push_str(&line[line.trim_end().len()..]);
+            changes.push((heading, message));
+        }
+        if result == text {
+            (Cow::Borrowed(text), changes)
+        } else {
+            (Cow::Owned(result), changes)
+        }
+    }
+}
+
+impl Default for HeadingNormalization {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WordCase {
+    /// An uppercase letter followed by lowercase ones, e.g. "Life"
+    Capitalised,
+    /// Starts with a lowercase letter
+    Lowercase,
+    /// Acronyms, mixed case, numbers and punctuation
+    Other,
+}
+
+fn word_case(word: &str) -> WordCase {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(c) if c.is_lowercase() => WordCase::Lowercase,
+        Some(c) if c.is_uppercase() && chars.all(|c| c.is_lowercase()) => WordCase::Capitalised,
+        _ => WordCase::Other,
+    }
+}
+
+/// Level and text of a `== Heading ==` line
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_end();
+    let open = line.len() - line.trim_start_matches('=').len();
+    let close = line.len() - line.trim_end_matches('=').len();
+    if open == 0 || open != close || open > 6 || line.len() <= 2 * open {
+        return None;
+    }
+    let inner = &line[open..line.len() - close];
+    (!inner.trim().is_empty()).then_some((open, inner))
+}
+
+/// The heading text without bold; italics stay. `None` if there is no
+/// bold, or the quotes are not balanced.
+fn strip_bold(inner: &str) -> Option<String> {
+    let mut result = String::with_capacity(inner.len());
+    let mut toggles = 0;
+    let mut rest = inner;
+    while let Some(start) = rest.find("''") {
+        result.push_str(&rest[..start]);
+        let run = rest[start..].len() - rest[start..].trim_start_matches('\'').len();
+        match run {
+            2 => result.push_str("''"),
+            3 => toggles += 1,
+            5 => {
+                toggles += 1;
+                result.push_str("''");
+            }
+            _ => return None,
+        }
+        rest = &rest[start + run..];
+    }
+    result.push_str(rest);
+    (toggles > 0 && toggles % 2 == 0).then_some(result)
+}
+
+/// The heading text with wikilinks and labelled external links replaced by
+/// their text. Links to other namespaces and pipe tricks stay.
+fn strip_links(inner: &str) -> Option<String> {
+    static LINK_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let link_re = LINK_RE.get_or_init(|| {
+        regex::Regex::new(
+            r"\[\[([^\[\]|\n]+)(?:\|([^\[\]\n]*))?\]\]|\[(?:https?:)?//[^\s\[\]]+\s+([^\[\]\n]+)\]",
+        )
+        .expect("known-valid regex")
+    });
+    let mut changed = false;
+    let result = link_re.replace_all(inner, |caps: &regex::Captures| {
+        let whole = &caps[0];
+        let text = if let Some(label) = caps.get(3) {
+            label.as_str()
+        } else if caps[1].contains(':') {
+            whole
+        } else {
+            match caps.get(2) {
+                Some(label) if label.as_str().trim().is_empty() => whole,
+                Some(label) => label.as_str(),
+                None => &caps[1],
+            }
+        };
+        changed |= text != whole;
+        text.to_string()
+    });
+    changed.then(|| result.into_owned())
+}
+
+impl FixModule for HeadingNormalization {
+    fn id(&self) -> &str {
+        "heading_normalization"
+    }
+    fn display_name(&self) -> &str {
+        "Heading Normalization"
+    }
+    fn category(&self) -> &str {
+        "Formatting"
+    }
+    fn description(&self) -> &str {
+        "Puts title-case headings in sentence case, fixes skipped heading levels and removes bold and links from headings"
+    }
+    fn classification(&self) -> FixClassification {
+        FixClassification::StyleSensitive
+    }
+    fn min_tier(&self) -> u8 {
+        2
+    }
+    fn apply<'a>(&self, text: &'a str, _ctx: &FixContext) -> Cow<'a, str> {
+        self.normalize(text).0
+    }
+    /// The changes [`apply`](FixModule::apply) makes to the headings of
+    /// `before` that are still in `after`
+    fn change_warnings(&self, before: &str, after: &str, _ctx: &FixContext) -> Vec<String> {
+        let policy = MaskPolicy::ALL.difference(self.required_visibility());
+        let (_, changes) = self.normalize(&mask_with(before, policy).masked);
+        let headings: HashSet<&str> = after.lines().map(str::trim_end).collect();
+        changes
+            .into_iter()
+            .filter(|(heading, _)| headings.contains(heading.as_str()))
+            .map(|(_, message)| message)
+            .collect()
+    }
+}
+
 pub struct HtmlToWikitext;
 impl FixModule for HtmlToWikitext {
     fn id(&self) -> &str {
@@ -367,29 +1163,214 @@ impl FixModule for HtmlToWikitext {
             return Cow::Borrowed(text);
         }
 
-        static BOLD_RE: OnceLock<regex::Regex> = OnceLock::new();
+        static BOLD_BR_RE: OnceLock<regex::Regex> = OnceLock::new();
         static ITALIC_RE: OnceLock<regex::Regex> = OnceLock::new();
-        static BR_RE: OnceLock<regex::Regex> = OnceLock::new();
 
-        let bold_re = BOLD_RE
-            .get_or_init(|| regex::Regex::new(r"(?i)<b>([^<]*)</b>").expect("known-valid regex"));
+        // Bold and <br> never affect each other's matches, so they share one
+        // scan. Italic needs a second pass: converting bold first lets
+        // `<i><b>x</b></i>` become `'''''x'''''`.
+        let bold_br_re = BOLD_BR_RE.get_or_init(|| {
+            regex::Regex::new(r"(?i)<b>([^<]*)</b>|<br\s*/?>").expect("known-valid regex")
+        });
         let italic_re = ITALIC_RE
             .get_or_init(|| regex::Regex::new(r"(?i)<i>([^<]*)</i>").expect("known-valid regex"));
-        let br_re =
-            BR_RE.get_or_init(|| regex::Regex::new(r"(?i)<br\s*/?>").expect("known-valid regex"));
 
-        // Check if any regex matches before allocating
-        if !bold_re.is_match(text) && !italic_re.is_match(text) && !br_re.is_match(text) {
-            return Cow::Borrowed(text);
+        let result = bold_br_re.replace_all(text, |caps: &regex::Captures| match caps.get(1) {
+            Some(inner) => format!("'''{}'''", inner.as_str()),
+            None => "<br />".to_string(),
+        });
+        let italic = match italic_re.replace_all(&result, "''$1''") {
+            Cow::Owned(italic) => Some(italic),
+            Cow::Borrowed(_) => None,
+        };
+        italic.map(Cow::Owned).unwrap_or(result)
+    }
+}
+
+/// Normalizes wikitext lists.
+///
+/// Simple HTML lists (`<ul>`/`<ol>` of single-line `<li>` items without
+/// attributes, possibly nested) are converted to `*` and `#` lists. Within
+/// a list, items use the same marker as the first item at their depth, so
+/// a stray `#` in a bulleted list becomes `*`, and blank lines between
+/// items of the same kind of list, which split it in two, are removed.
+/// Templates in list items are masked, so one spanning several lines stays
+/// part of its item.
+pub struct ListFormatting;
+
+/// The `*`/`#`/`:`/`;` prefix of a list item line starting with `*` or `#`
+fn list_prefix(line: &str) -> Option<&str> {
+    if !line.starts_with(['*', '#']) {
+        return None;
+    }
+    let end = line
+        .find(|c: char| !matches!(c, '*' | '#' | ':' | ';'))
+        .unwrap_or(line.len());
+    Some(&line[..end])
+}
+
+/// Wikitext lines for the HTML list whose opening tag ends at `pos`, with
+/// `prefix` as the items' markers, and the offset just past the list
+fn html_list(text: &str, mut pos: usize, prefix: &str) -> Option<(Vec<String>, usize)> {
+    static TAG_RE: OnceLock<regex::Regex> = OnceLock::new();
+    static NEXT_TAG_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let tag_re = TAG_RE.get_or_init(|| {
+        regex::Regex::new(r"(?i)\A\s*<(/?)(ul|ol|li)>").expect("known-valid regex")
+    });
+    let next_tag_re = NEXT_TAG_RE
+        .get_or_init(|| regex::Regex::new(r"(?i)</?(?:ul|ol|li)>").expect("known-valid regex"));
+
+    let close = if prefix.ends_with('*') { "ul" } else { "ol" };
+    let mut lines = Vec::new();
+    loop {
+        let caps = tag_re.captures(&text[pos..])?;
+        pos += caps[0].len();
+        let closing = !caps[1].is_empty();
+        if closing && caps[2].eq_ignore_ascii_case(close) {
+            return (!lines.is_empty()).then_some((lines, pos));
+        }
+        if closing || !caps[2].eq_ignore_ascii_case("li") {
+            return None;
         }
 
-        // At least one match found, proceed with replacements
-        let mut result = text.to_string();
-        result = bold_re.replace_all(&result, "'''$1'''").into_owned();
-        result = italic_re.replace_all(&result, "''$1''").into_owned();
-        result = br_re.replace_all(&result, "<br />").into_owned();
+        // The item's text runs to the next list tag
+        let end = pos + next_tag_re.find(&text[pos..])?.start();
+        let item = text[pos..end].trim();
+        if item.is_empty() || item.contains('\n') {
+            return None;
+        }
+        lines.push(format!("{} {}", prefix, item));
+        pos = end;
+
+        // Then come nested lists and `</li>`, which is optional
+        while let Some(caps) = tag_re.captures(&text[pos..]) {
+            let tag = caps[2].to_ascii_lowercase();
+            match (caps[1].is_empty(), tag.as_str()) {
+                (true, "ul" | "ol") => {
+                    let marker = if tag == "ul" { '*' } else { '#' };
+                    let nested_prefix = format!("{}{}", prefix, marker);
+                    let (nested, next) = html_list(text, pos + caps[0].len(), &nested_prefix)?;
+                    lines.extend(nested);
+                    pos = next;
+                }
+                (false, "li") => {
+                    pos += caps[0].len();
+                    break;
+                }
+                _ => break,
+            }
+        }
+    }
+}
 
-        Cow::Owned(result)
+/// Convert HTML lists that stand on their own lines to wikitext
+fn convert_html_lists(text: &str) -> Option<String> {
+    static OPEN_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let open_re = OPEN_RE
+        .get_or_init(|| regex::Regex::new(r"(?im)^[ \t]*<(ul|ol)>").expect("known-valid regex"));
+    let mut result = String::new();
+    let mut last = 0;
+    for m in open_re.find_iter(text) {
+        if m.start() < last {
+            continue;
+        }
+        let marker = if m.as_str().trim_start()[1..3].eq_ignore_ascii_case("ul") {
+            "*"
+        } else {
+            "#"
+        };
+        let Some((lines, end)) = html_list(text, m.end(), marker) else {
+            continue;
+        };
+        let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i);
+        if !text[end..line_end].trim().is_empty() {
+            continue;
+        }
+        result.push_str(&text[last..m.start()]);
+        result.push_str(&lines.join("\n"));
+        last = line_end;
+    }
+    if last == 0 {
+        return None;
+    }
+    result.push_str(&text[last..]);
+    Some(result)
+}
+
+impl FixModule for ListFormatting {
+    fn id(&self) -> &str {
+        "list_formatting"
+    }
+    fn display_name(&self) -> &str {
+        "List Formatting"
+    }
+    fn category(&self) -> &str {
+        "Formatting"
+    }
+    fn description(&self) -> &str {
+        "Converts HTML lists to wikitext, makes list markers consistent and removes blank lines that split lists"
+    }
+    fn classification(&self) -> FixClassification {
+        FixClassification::StyleSensitive
+    }
+    fn min_tier(&self) -> u8 {
+        2
+    }
+    /// Bold and italics in HTML items are converted first
+    fn runs_after(&self) -> &[&str] {
+        &["html_to_wikitext"]
+    }
+    fn apply<'a>(&self, text: &'a str, ctx: &FixContext) -> Cow<'a, str> {
+        // `#REDIRECT` is not a list
+        if ctx.is_redirect || !text.contains(['*', '#', '<']) {
+            return Cow::Borrowed(text);
+        }
+        let converted = convert_html_lists(text);
+        let source = converted.as_deref().unwrap_or(text);
+
+        let lines: Vec<&str> = source.split_inclusive('\n').collect();
+        let mut result = String::with_capacity(source.len());
+        // Marker of the enclosing item at each depth of the current list
+        let mut markers: Vec<char> = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            i += 1;
+            let Some(prefix) = list_prefix(line) else {
+                if line.trim().is_empty() && !markers.is_empty() {
+                    let next = lines[i..].iter().position(|l| !l.trim().is_empty());
+                    let continues = next
+                        .and_then(|n| list_prefix(lines[i + n]))
+                        .is_some_and(|p| p.starts_with(markers[0]));
+                    if let (true, Some(n)) = (continues, next) {
+                        i += n;
+                        continue;
+                    }
+                }
+                markers.clear();
+                result.push_str(line);
+                continue;
+            };
+            for (depth, c) in prefix.chars().enumerate() {
+                let c = match markers.get(depth) {
+                    Some(&m) if matches!((m, c), ('*', '#') | ('#', '*')) => m,
+                    _ => c,
+                };
+                if depth < markers.len() {
+                    markers[depth] = c;
+                } else {
+                    markers.push(c);
+                }
+                result.push(c);
+            }
+            markers.truncate(prefix.len());
+            result.push_str(&line[prefix.len()..]);
+        }
+        if result == text {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(result)
+        }
     }
 }
 
@@ -538,31 +1519,17 @@ impl FixModule for CategorySorting {
 
 /// Citation template parameter formatting (normalize names, fix deprecated params).
 ///
-/// # Design Note: Masking Interaction
-///
-/// This fix is **intentionally ineffective when masking is enabled**. Citation templates
-/// (e.g., `{{cite web|...}}`) are masked as protected regions during high-level masking
-/// operations, because their parameters require specialized understanding of citation syntax.
-///
-/// When masking runs:
-/// - All `{{cite ...}}` templates are replaced with masking sentinels (`\x00\x01AWB_MASK_*`)
-/// - This fix sees only the masked text, not the original template content
-/// - Parameter renames like `accessdate` → `access-date` are **not applied**
-/// - The fix returns the text unchanged
-///
-/// This is **by design**. Proper citation formatting requires:
-/// 1. Unmasking templates to expose their parameters
-/// 2. Specialized template parsing (not regex)
-/// 3. Parameter-aware rewrites
-/// 4. Re-masking the result
+/// # Masking Interaction
 ///
-/// A future dedicated template-aware pass should handle citation reformatting. For now,
-/// this fix only works on unmasked text (or when masking is disabled entirely).
+/// Templates and `<ref>` contents are masked for most fixes. This fix declares
+/// both in [`FixModule::required_visibility`], so it sees citation templates,
+/// including those inside references, while comments and `<nowiki>` stay
+/// protected.
 ///
 /// # Tier Classification
 ///
 /// Classified as **Tier 2 (StyleSensitive)** to prevent accidental use in unattended
-/// bot mode (Tier 0-1), since the fix is currently ineffective on masked text.
+/// bot mode (Tier 0-1), since parameter renames are a matter of citation style.
 pub struct CitationFormatting;
 impl FixModule for CitationFormatting {
     fn id(&self) -> &str {
@@ -583,6 +1550,9 @@ impl FixModule for CitationFormatting {
     fn min_tier(&self) -> u8 {
         2
     }
+    fn required_visibility(&self) -> MaskPolicy {
+        MaskPolicy::TEMPLATES | MaskPolicy::REFS
+    }
     fn apply<'a>(&self, text: &'a str, _ctx: &FixContext) -> Cow<'a, str> {
         static CITE_RE: OnceLock<regex::Regex> = OnceLock::new();
         static ACCESSDATE_RE: OnceLock<regex::Regex> = OnceLock::new();
@@ -745,6 +1715,10 @@ impl FixModule for UnicodeNormalization {
     fn min_tier(&self) -> u8 {
         2
     }
+    /// Curly quotes are only fixed inside templates.
+    fn required_visibility(&self) -> MaskPolicy {
+        MaskPolicy::TEMPLATES | MaskPolicy::REFS
+    }
     fn apply<'a>(&self, text: &'a str, _ctx: &FixContext) -> Cow<'a, str> {
         static ENDASH_RE: OnceLock<regex::Regex> = OnceLock::new();
 
@@ -819,7 +1793,282 @@ impl FixModule for UnicodeNormalization {
     }
 }
 
-pub struct DefaultSortFix;
+/// Composes text to Unicode Normalization Form C, so that e.g. `e` plus a
+/// combining acute accent becomes `é`. Protected regions are left as they
+/// are. Also warns about a title or link target with a word that mixes
+/// Latin, Greek and Cyrillic letters, such as `Pаris` with a Cyrillic `а`:
+/// usually a typo or a spoofed lookalike, and never fixed automatically.
+pub struct UnicodeNfc;
+impl FixModule for UnicodeNfc {
+    fn id(&self) -> &str {
+        "unicode_nfc"
+    }
+    fn display_name(&self) -> &str {
+        "Unicode NFC"
+    }
+    fn category(&self) -> &str {
+        "Formatting"
+    }
+    fn description(&self) -> &str {
+        "Normalizes text to Unicode NFC and flags links mixing lookalike letters from different scripts"
+    }
+    fn classification(&self) -> FixClassification {
+        FixClassification::StyleSensitive
+    }
+    fn min_tier(&self) -> u8 {
+        2
+    }
+    fn apply<'a>(&self, text: &'a str, _ctx: &FixContext) -> Cow<'a, str> {
+        if text.is_ascii() {
+            return Cow::Borrowed(text);
+        }
+        icu_normalizer::ComposingNormalizerBorrowed::new_nfc().normalize(text)
+    }
+    fn warnings(&self, text: &str, ctx: &FixContext) -> Vec<String> {
+        static LINK_RE: OnceLock<regex::Regex> = OnceLock::new();
+        let link_re = LINK_RE
+            .get_or_init(|| regex::Regex::new(r"\[\[([^\[\]|\n]+)").expect("known-valid regex"));
+
+        let mut warnings = Vec::new();
+        if let Some((word, first, second)) = mixed_script_word(&ctx.title.name) {
+            warnings.push(format!(
+                "title mixes {} and {} letters in \"{}\"",
+                first, second, word
+            ));
+        }
+        let mut seen = HashSet::new();
+        for caps in link_re.captures_iter(text) {
+            let target = caps.get(1).map_or("", |m| m.as_str()).trim();
+            if !seen.insert(target) {
+                continue;
+            }
+            if let Some((word, first, second)) = mixed_script_word(target) {
+                warnings.push(format!(
+                    "link [[{}]] mixes {} and {} letters in \"{}\"",
+                    target, first, second, word
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// Scripts whose letters look alike
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+}
+
+impl std::fmt::Display for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Latin => "Latin",
+            Self::Greek => "Greek",
+            Self::Cyrillic => "Cyrillic",
+        })
+    }
+}
+
+fn script_of(c: char) -> Option<Script> {
+    if !c.is_alphabetic() {
+        return None;
+    }
+    match c {
+        'A'..='Z' | 'a'..='z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => {
+            Some(Script::Latin)
+        }
+        '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Some(Script::Greek),
+        '\u{0400}'..='\u{052F}' => Some(Script::Cyrillic),
+        _ => None,
+    }
+}
+
+/// The first word of `text` with letters from two different [`Script`]s,
+/// and those scripts in the order they appear
+fn mixed_script_word(text: &str) -> Option<(&str, Script, Script)> {
+    text.split(|c: char| !c.is_alphanumeric()).find_map(|word| {
+        let mut scripts = word.chars().filter_map(script_of);
+        let first = scripts.next()?;
+        let second = scripts.find(|s| *s != first)?;
+        Some((word, first, second))
+    })
+}
+
+/// Adds `{{DEFAULTSORT:}}` where the title would sort wrongly.
+///
+/// By default only titles with diacritics get one, folded to ASCII. With a
+/// [`PersonSortKey`] other than `Off`, biographies (pages with a person
+/// infobox or a births/deaths category) get "Surname, Forename" instead.
+/// Names the heuristic cannot split reliably, such as ones with surname
+/// particles or family-name-first names, get no key and a warning.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultSortFix {
+    people: PersonSortKey,
+}
+
+impl DefaultSortFix {
+    pub fn new(people: PersonSortKey) -> Self {
+        Self { people }
+    }
+
+    /// Sort key of a biography, or why none could be derived. `None` if
+    /// the page is not a biography or person sort keys are off.
+    fn person_key(&self, text: &str, ctx: &FixContext) -> Option<Result<String, String>> {
+        if self.people.is_off() || ctx.namespace != Namespace::MAIN || ctx.is_redirect {
+            return None;
+        }
+        let infobox = parse_templates(text)
+            .into_iter()
+            .find(|call| normalize_template_name(&call.name).starts_with("Infobox"));
+        let infobox_person = infobox.as_ref().is_some_and(|call| {
+            PERSON_PARAMS
+                .iter()
+                .any(|param| call.get(param).is_some_and(|v| !v.is_empty()))
+        });
+        if !infobox_person && !has_biography_category(text) {
+            return None;
+        }
+
+        let name = infobox_person
+            .then(|| infobox.as_ref().and_then(|call| call.get("name")))
+            .flatten()
+            .filter(|name| !name.is_empty());
+        let title = strip_disambiguator(&ctx.title.name);
+        match (name, self.people) {
+            // The title is the name the article is at; an infobox name
+            // with another surname is more likely a birth or stage name
+            (Some(name), _) => {
+                Some(
+                    name_sort_key(name).and_then(|key| match name_sort_key(title)? {
+                        title_key if surname(&title_key) != surname(&key) => Err(format!(
+                            "infobox name \"{}\" does not match the title",
+                            name
+                        )),
+                        title_key if !key.contains(',') => Ok(title_key),
+                        _ => Ok(key),
+                    }),
+                )
+            }
+            (None, PersonSortKey::InfoboxOrTitle) => Some(name_sort_key(title)),
+            (None, _) => None,
+        }
+    }
+}
+
+/// Infobox parameters that mark the infobox as one about a person
+const PERSON_PARAMS: &[&str] = &["birth_date", "birth_name", "death_date", "birth_place"];
+
+/// Words that may belong to the surname ("van Gogh", "de la Cruz"), which
+/// wikis sort by differently
+const SURNAME_PARTICLES: &[&str] = &[
+    "al", "bin", "da", "de", "del", "della", "den", "der", "di", "du", "el", "ibn", "la", "le",
+    "ten", "ter", "van", "von",
+];
+
+/// Generational suffixes, kept after the forename
+const NAME_SUFFIXES: &[&str] = &["Jr.", "Jr", "Sr.", "Sr", "II", "III", "IV"];
+
+/// Honorifics dropped from the start of a name
+const HONORIFICS: &[&str] = &["Sir", "Dame", "Dr.", "Dr", "Lord", "Lady"];
+
+fn has_biography_category(text: &str) -> bool {
+    static BIO_CAT_RE: OnceLock<regex::Regex> = OnceLock::new();
+    BIO_CAT_RE
+        .get_or_init(|| {
+            regex::Regex::new(
+                r"(?i)\[\[\s*Category\s*:\s*(?:\d+s?(?: BC)? (?:births|deaths)|Living people)\s*(?:\||\]\])",
+            )
+            .expect("known-valid regex")
+        })
+        .is_match(text)
+}
+
+/// The title without a trailing " (disambiguator)"
+fn strip_disambiguator(title: &str) -> &str {
+    match title.strip_suffix(')').and_then(|t| t.rfind(" (")) {
+        Some(open) => &title[..open],
+        None => title,
+    }
+}
+
+fn surname(key: &str) -> &str {
+    key.split(',').next().unwrap_or(key)
+}
+
+/// "Surname, Forename" for a personal name, ASCII-folded, or why the name
+/// is ambiguous
+fn name_sort_key(name: &str) -> Result<String, String> {
+    if name.contains(['[', ']', '{', '}', '<', '>', '|', '\u{0}']) || name.contains("''") {
+        return Err(format!("name \"{}\" contains markup", name));
+    }
+    if name.chars().any(is_cjk) {
+        return Err(format!("name \"{}\" may put the family name first", name));
+    }
+    let name = name.replace(", ", " ");
+    let mut words: Vec<&str> = name.split_whitespace().collect();
+    while words.len() > 1 && HONORIFICS.contains(&words[0]) {
+        words.remove(0);
+    }
+    let suffix = match words.last() {
+        Some(last) if words.len() > 2 && NAME_SUFFIXES.contains(last) => words.pop(),
+        _ => None,
+    };
+    let Some((last, given)) = words.split_last() else {
+        return Err("name is empty".to_string());
+    };
+    if let Some(particle) = given
+        .iter()
+        .skip(1)
+        .find(|word| SURNAME_PARTICLES.contains(&word.to_lowercase().as_str()))
+    {
+        return Err(format!("surname particle \"{}\" in \"{}\"", particle, name));
+    }
+    let mut key = last.to_string();
+    if !given.is_empty() {
+        key.push_str(", ");
+        key.push_str(&given.join(" "));
+    }
+    if let Some(suffix) = suffix {
+        key.push_str(", ");
+        key.push_str(suffix);
+    }
+    Ok(ascii_fold(&key))
+}
+
+/// Han, kana and Hangul, whose names usually put the family name first
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// Insert `{{DEFAULTSORT:key}}` before the first category, or at the end
+fn insert_defaultsort(text: &str, sort_key: &str) -> String {
+    static CAT_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let cat_re = CAT_RE
+        .get_or_init(|| regex::Regex::new(r"(?m)^(\[\[Category:)").expect("known-valid regex"));
+    if let Some(mat) = cat_re.find(text) {
+        let pos = mat.start();
+        let mut result = String::with_capacity(text.len() + sort_key.len() + 20);
+        result.push_str(&text[..pos]);
+        result.push_str(&format!("{{{{DEFAULTSORT:{}}}}}\n", sort_key));
+        result.push_str(&text[pos..]);
+        result
+    } else {
+        // No categories - add at the end
+        format!("{}\n{{{{DEFAULTSORT:{}}}}}\n", text.trim_end(), sort_key)
+    }
+}
+
+fn has_defaultsort(text: &str) -> bool {
+    static DEFAULTSORT_RE: OnceLock<regex::Regex> = OnceLock::new();
+    DEFAULTSORT_RE
+        .get_or_init(|| regex::Regex::new(r"(?i)\{\{DEFAULTSORT:").expect("known-valid regex"))
+        .is_match(text)
+}
+
 impl FixModule for DefaultSortFix {
     fn id(&self) -> &str {
         "defaultsort_fix"
@@ -839,44 +2088,349 @@ impl FixModule for DefaultSortFix {
     fn min_tier(&self) -> u8 {
         1
     }
+    /// Must see an existing `{{DEFAULTSORT:}}` to avoid adding a second one.
+    fn required_visibility(&self) -> MaskPolicy {
+        MaskPolicy::TEMPLATES
+    }
     fn apply<'a>(&self, text: &'a str, ctx: &FixContext) -> Cow<'a, str> {
-        static DEFAULTSORT_RE: OnceLock<regex::Regex> = OnceLock::new();
-        static CAT_RE: OnceLock<regex::Regex> = OnceLock::new();
+        if has_defaultsort(text) {
+            return Cow::Borrowed(text);
+        }
 
-        // Check if DEFAULTSORT already exists
-        let defaultsort_re = DEFAULTSORT_RE
-            .get_or_init(|| regex::Regex::new(r"(?i)\{\{DEFAULTSORT:").expect("known-valid regex"));
-        if defaultsort_re.is_match(text) {
+        let sort_key = match self.person_key(text, ctx) {
+            Some(Ok(key)) if key != ctx.title.name => key,
+            // Ambiguous names are left for a human, see `warnings`
+            Some(_) => return Cow::Borrowed(text),
+            // Titles with diacritics sort by their ASCII-folded form
+            None if !ctx.title.name.is_ascii() => ascii_fold(&ctx.title.name),
+            None => return Cow::Borrowed(text),
+        };
+        Cow::Owned(insert_defaultsort(text, &sort_key))
+    }
+    fn warnings(&self, text: &str, ctx: &FixContext) -> Vec<String> {
+        if has_defaultsort(text) {
+            return Vec::new();
+        }
+        match self.person_key(text, ctx) {
+            Some(Err(reason)) => vec![format!("no DEFAULTSORT added: {}", reason)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Short lowercase interwiki prefixes that are not languages
+const NON_LANGUAGE_PREFIXES: &[&str] = &["doi", "irc", "mw", "rfc", "voy", "wmf"];
+
+/// Whether `prefix` looks like a language edition, e.g. `de`, `zh-yue` or
+/// `simple`
+pub fn is_language_prefix(prefix: &str) -> bool {
+    static LANG_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let lang_re = LANG_RE
+        .get_or_init(|| regex::Regex::new(r"^[a-z]{2,3}(-[a-z]+)*$").expect("known-valid regex"));
+    prefix == "simple" || (lang_re.is_match(prefix) && !NON_LANGUAGE_PREFIXES.contains(&prefix))
+}
+
+/// Language links of pages as recorded on their Wikidata items: page title
+/// → language prefix → title on that wiki
+pub type Sitelinks = HashMap<String, HashMap<String, String>>;
+
+/// Moves interlanguage links (`[[de:Titel]]`) to the end of the page, in
+/// the wiki's order, without duplicates.
+///
+/// Languages are sorted by their position in the configured order, then by
+/// prefix. Given the Wikidata sitelinks of the page, links the item already
+/// provides are removed; links that disagree with the item are kept.
+pub struct InterlanguageLinks {
+    order: Vec<String>,
+    sitelinks: Option<std::sync::Arc<Sitelinks>>,
+}
+
+impl InterlanguageLinks {
+    /// Sort by `order` (language prefixes); an empty list sorts by prefix
+    pub fn new(order: &[String]) -> Self {
+        Self {
+            order: order
+                .iter()
+                .map(|code| code.trim().to_lowercase())
+                .collect(),
+            sitelinks: None,
+        }
+    }
+
+    /// Remove links duplicated by the pages' Wikidata items
+    #[must_use]
+    pub fn with_sitelinks(mut self, sitelinks: Sitelinks) -> Self {
+        let sitelinks = sitelinks
+            .into_iter()
+            .map(|(title, links)| (crate::redirects::normalize_title(&title), links))
+            .collect();
+        self.sitelinks = Some(std::sync::Arc::new(sitelinks));
+        self
+    }
+
+    fn sort_key<'l>(&self, lang: &'l str) -> (usize, &'l str) {
+        let rank = self
+            .order
+            .iter()
+            .position(|code| code == lang)
+            .unwrap_or(self.order.len());
+        (rank, lang)
+    }
+}
+
+impl Default for InterlanguageLinks {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl FixModule for InterlanguageLinks {
+    fn id(&self) -> &str {
+        "interlanguage_links"
+    }
+    fn display_name(&self) -> &str {
+        "Interlanguage Links"
+    }
+    fn category(&self) -> &str {
+        "Links"
+    }
+    fn description(&self) -> &str {
+        "Moves interlanguage links to the end of the page in sorted order and removes duplicates"
+    }
+    fn classification(&self) -> FixClassification {
+        FixClassification::StyleSensitive
+    }
+    fn min_tier(&self) -> u8 {
+        2
+    }
+    fn apply<'a>(&self, text: &'a str, ctx: &FixContext) -> Cow<'a, str> {
+        static LINK_RE: OnceLock<regex::Regex> = OnceLock::new();
+        let link_re = LINK_RE.get_or_init(|| {
+            regex::Regex::new(r"\[\[([a-z][a-z-]*):([^\[\]|\n]*)(?:\|[^\[\]\n]*)?\]\]")
+                .expect("known-valid regex")
+        });
+        let is_lang_link = |caps: &regex::Captures| is_language_prefix(&caps[1]);
+
+        // Current links as (language, title, wikitext) in order, and the
+        // page without them, line by line
+        let mut links: Vec<(String, String, String)> = Vec::new();
+        let mut body = String::with_capacity(text.len());
+        let mut seen_link = false;
+        let mut only_links_at_end = true;
+        for line in text.split_inclusive('\n') {
+            let mut rest = String::new();
+            let mut last = 0;
+            let mut found = false;
+            for caps in link_re.captures_iter(line) {
+                if !is_lang_link(&caps) {
+                    continue;
+                }
+                let m = caps.get(0).expect("whole match");
+                rest.push_str(&line[last..m.start()]);
+                last = m.end();
+                found = true;
+                links.push((
+                    caps[1].to_string(),
+                    crate::redirects::normalize_title(&caps[2]),
+                    m.as_str().to_string(),
+                ));
+            }
+            rest.push_str(&line[last..]);
+            seen_link |= found;
+            if found && rest.trim().is_empty() {
+                continue;
+            }
+            if seen_link && !rest.trim().is_empty() {
+                only_links_at_end = false;
+            }
+            body.push_str(&rest);
+        }
+        if links.is_empty() {
             return Cow::Borrowed(text);
         }
 
-        // Check if title contains diacritics or non-ASCII characters
-        let title_name = &ctx.title.name;
-        if title_name.is_ascii() {
+        let connected = self
+            .sitelinks
+            .as_ref()
+            .and_then(|all| all.get(&crate::redirects::normalize_title(&ctx.title.display)));
+        let mut wanted: Vec<&(String, String, String)> = Vec::new();
+        for link in &links {
+            let (lang, title, _) = link;
+            if wanted.iter().any(|(l, t, _)| l == lang && t == title) {
+                continue;
+            }
+            let on_item = connected
+                .and_then(|item| item.get(lang))
+                .is_some_and(|item_title| crate::redirects::normalize_title(item_title) == *title);
+            if on_item {
+                continue;
+            }
+            wanted.push(link);
+        }
+        wanted.sort_by(|a, b| self.sort_key(&a.0).cmp(&self.sort_key(&b.0)));
+
+        let unchanged = only_links_at_end
+            && wanted.len() == links.len()
+            && wanted.iter().zip(&links).all(|(a, b)| a.2 == b.2);
+        if unchanged {
             return Cow::Borrowed(text);
         }
 
-        // Generate ASCII-folded version for sort key
-        let sort_key = ascii_fold(title_name);
+        let mut result = body.trim_end().to_string();
+        if !wanted.is_empty() {
+            result.push_str("\n\n");
+            let block: Vec<&str> = wanted.iter().map(|(_, _, raw)| raw.as_str()).collect();
+            result.push_str(&block.join("\n"));
+        }
+        if text.ends_with('\n') {
+            result.push('\n');
+        }
+        Cow::Owned(result)
+    }
+}
 
-        // Find the best position to insert DEFAULTSORT (before categories if present)
-        let cat_re = CAT_RE
-            .get_or_init(|| regex::Regex::new(r"(?m)^(\[\[Category:)").expect("known-valid regex"));
-        if let Some(mat) = cat_re.find(text) {
-            let pos = mat.start();
-            let mut result = String::with_capacity(text.len() + sort_key.len() + 20);
-            result.push_str(&text[..pos]);
-            result.push_str(&format!("{{{{DEFAULTSORT:{}}}}}\n", sort_key));
-            result.push_str(&text[pos..]);
-            Cow::Owned(result)
+/// Maintenance templates [`MaintenanceTagDating`] dates when a wiki does not
+/// configure its own list; redirects to them are listed alongside.
+pub const DEFAULT_DATED_TEMPLATES: &[&str] = &[
+    "Citation needed",
+    "Cn",
+    "Fact",
+    "Clarify",
+    "Dubious",
+    "Vague",
+    "Who",
+    "When",
+    "Which",
+    "By whom",
+    "Failed verification",
+    "Better source needed",
+    "Page needed",
+    "Dead link",
+    "Update",
+    "Unreferenced",
+    "Unreferenced section",
+    "More citations needed",
+    "Refimprove",
+    "Original research",
+    "Notability",
+    "Cleanup",
+    "Copy edit",
+    "Expand section",
+    "Orphan",
+    "Dead end",
+    "Uncategorized",
+];
+
+/// Adds `|date=Month YYYY` to undated maintenance templates.
+///
+/// A template with a non-empty `date` is left alone; an empty `date=` is
+/// filled in. Templates nested in `{{Multiple issues}}` are dated too.
+pub struct MaintenanceTagDating {
+    templates: HashSet<String>,
+    date: Option<chrono::NaiveDate>,
+}
+
+impl MaintenanceTagDating {
+    /// Date the given templates; an empty list means
+    /// [`DEFAULT_DATED_TEMPLATES`]
+    pub fn new(templates: &[String]) -> Self {
+        let templates = if templates.is_empty() {
+            DEFAULT_DATED_TEMPLATES
+                .iter()
+                .map(|t| normalize_template_name(t))
+                .collect()
         } else {
-            // No categories - add at the end
-            Cow::Owned(format!(
-                "{}\n{{{{DEFAULTSORT:{}}}}}\n",
-                text.trim_end(),
-                sort_key
-            ))
+            templates
+                .iter()
+                .map(|t| normalize_template_name(t))
+                .collect()
+        };
+        Self {
+            templates,
+            date: None,
+        }
+    }
+
+    /// Date templates with `date`'s month instead of the current one
+    #[must_use]
+    pub fn with_date(mut self, date: chrono::NaiveDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+}
+
+impl Default for MaintenanceTagDating {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl FixModule for MaintenanceTagDating {
+    fn id(&self) -> &str {
+        "maintenance_tag_dating"
+    }
+    fn display_name(&self) -> &str {
+        "Maintenance Tag Dating"
+    }
+    fn category(&self) -> &str {
+        "Maintenance"
+    }
+    fn description(&self) -> &str {
+        "Adds the current month to undated maintenance templates such as {{Citation needed}}"
+    }
+    fn classification(&self) -> FixClassification {
+        FixClassification::Maintenance
+    }
+    fn min_tier(&self) -> u8 {
+        1
+    }
+    /// Tags sit in templates, references and tables.
+    fn required_visibility(&self) -> MaskPolicy {
+        MaskPolicy::TEMPLATES | MaskPolicy::REFS | MaskPolicy::TABLES
+    }
+    fn apply<'a>(&self, text: &'a str, _ctx: &FixContext) -> Cow<'a, str> {
+        static EMPTY_DATE_RE: OnceLock<regex::Regex> = OnceLock::new();
+        let empty_date_re = EMPTY_DATE_RE.get_or_init(|| {
+            regex::Regex::new(r"(?i)\|\s*date\s*=[ \t]*").expect("known-valid regex")
+        });
+
+        let date = self
+            .date
+            .unwrap_or_else(|| chrono::Utc::now().date_naive())
+            .format("%B %Y")
+            .to_string();
+        // Insertions as (offset, text); applied back to front
+        let mut inserts: Vec<(usize, String)> = Vec::new();
+        for (range, call) in template_spans(text) {
+            if !self
+                .templates
+                .contains(&normalize_template_name(&call.name))
+            {
+                continue;
+            }
+            if call.get("date").is_some_and(|d| !d.is_empty()) {
+                continue;
+            }
+            let close = range.end - 2;
+            let body = &text[range.start..close];
+            let empty = empty_date_re
+                .find_iter(body)
+                .filter(|m| body[m.end()..].is_empty() || body[m.end()..].starts_with('|'))
+                .last();
+            match empty {
+                Some(m) => inserts.push((range.start + m.end(), date.clone())),
+                None => inserts.push((close, format!("|date={}", date))),
+            }
         }
+        if inserts.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        inserts.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+        let mut result = text.to_string();
+        for (at, insert) in inserts {
+            result.insert_str(at, &insert);
+        }
+        Cow::Owned(result)
     }
 }
 
@@ -969,11 +2523,176 @@ mod tests {
             title: Title::new(Namespace::MAIN, title_name),
             namespace: Namespace::MAIN,
             is_redirect: false,
+            revision: None,
+            cancel: Default::default(),
         }
     }
 
+    // --- MaintenanceTagDating Tests ---
+
+    fn dating() -> MaintenanceTagDating {
+        MaintenanceTagDating::default()
+            .with_date(chrono::NaiveDate::from_ymd_opt(2024, 5, 20).unwrap())
+    }
+
+    #[test]
+    fn test_tag_dating_adds_date() {
+        let ctx = test_context("Test");
+        let input = "Claim.{{citation needed}} Other.{{Cn|reason=odd}}";
+        assert_eq!(
+            apply_masked(&dating(), input, &ctx),
+            "Claim.{{citation needed|date=May 2024}} Other.{{Cn|reason=odd|date=May 2024}}"
+        );
+    }
+
+    #[test]
+    fn test_tag_dating_keeps_existing_dates() {
+        let ctx = test_context("Test");
+        let input = "{{Orphan|date=March 2020}}\n{{Unreferenced|date=}}\nX{{Infobox|a=b}}";
+        assert_eq!(
+            apply_masked(&dating(), input, &ctx),
+            "{{Orphan|date=March 2020}}\n{{Unreferenced|date=May 2024}}\nX{{Infobox|a=b}}"
+        );
+        let dated = "A{{Citation needed|date=May 2024}}";
+        assert!(matches!(
+            apply_masked(&dating(), dated, &ctx),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_tag_dating_inside_refs_and_multiple_issues() {
+        let ctx = test_context("Test");
+        let input = "{{Multiple issues|\n{{Orphan}}\n{{Dead end}}\n}}\n\
+                     A<ref>[http://x.org X] {{dead link}}</ref> <!-- {{cn}} -->";
+        assert_eq!(
+            apply_masked(&dating(), input, &ctx),
+            "{{Multiple issues|\n{{Orphan|date=May 2024}}\n{{Dead end|date=May 2024}}\n}}\n\
+             A<ref>[http://x.org X] {{dead link|date=May 2024}}</ref> <!-- {{cn}} -->"
+        );
+    }
+
+    #[test]
+    fn test_tag_dating_configured_templates() {
+        let ctx = test_context("Test");
+        let config = FixConfig {
+            dated_templates: vec!["Belege fehlen".to_string()],
+            ..Default::default()
+        };
+        let registry = FixRegistry::with_config(&config);
+        let enabled: HashSet<String> = ["maintenance_tag_dating".to_string()].into();
+        let result = registry.apply_all("{{Belege fehlen}}{{Citation needed}}", &ctx, &enabled);
+        assert!(result.starts_with("{{Belege fehlen|date="));
+        assert!(result.ends_with("{{Citation needed}}"));
+    }
+
+    // --- InterlanguageLinks Tests ---
+
+    #[test]
+    fn test_interlanguage_links_grouped_and_sorted() {
+        let ctx = test_context("Foo");
+        let input = "[[fr:Foo]]Text [[de:Foo]].\n[[Category:X]]\n[[es:Foo]]\n[[de:Foo]]\n";
+        assert_eq!(
+            apply_masked(&InterlanguageLinks::default(), input, &ctx),
+            "Text .\n[[Category:X]]\n\n[[de:Foo]]\n[[es:Foo]]\n[[fr:Foo]]\n"
+        );
+        let sorted = "Text.\n\n[[de:Foo]]\n[[fr:Foo]]\n";
+        assert!(matches!(
+            apply_masked(&InterlanguageLinks::default(), sorted, &ctx),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_interlanguage_links_skip_other_links() {
+        let ctx = test_context("Foo");
+        let input = "[[:de:Foo]] [[wikt:foo]] [[mw:Help]] [[WP:X]] {{x|[[de:Foo]]}}\n";
+        assert!(matches!(
+            apply_masked(&InterlanguageLinks::default(), input, &ctx),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_interlanguage_links_configured_order() {
+        let ctx = test_context("Foo");
+        let module = InterlanguageLinks::new(&["fr".to_string(), "de".to_string()]);
+        assert_eq!(
+            module.apply("A\n[[de:B]]\n[[en:B]]\n[[fr:B]]", &ctx),
+            "A\n\n[[fr:B]]\n[[de:B]]\n[[en:B]]"
+        );
+    }
+
+    #[test]
+    fn test_interlanguage_links_wikidata_duplicates() {
+        let ctx = test_context("Foo");
+        let mut item = HashMap::new();
+        item.insert("de".to_string(), "Foo_bar".to_string());
+        item.insert("fr".to_string(), "Autre".to_string());
+        let sitelinks: Sitelinks = [("foo".to_string(), item)].into_iter().collect();
+        let module = InterlanguageLinks::default().with_sitelinks(sitelinks);
+        // de matches the item and goes; fr disagrees with it and stays
+        assert_eq!(
+            module.apply("A\n\n[[de:Foo bar]]\n[[fr:Foo]]\n", &ctx),
+            "A\n\n[[fr:Foo]]\n"
+        );
+        let other = test_context("Other");
+        assert!(matches!(
+            module.apply("A\n\n[[de:Foo bar]]\n", &other),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_registry_replace() {
+        let mut registry = FixRegistry::with_defaults();
+        let count = registry.all_modules().len();
+        registry
+            .replace(Box::new(InterlanguageLinks::new(&["fr".to_string()])))
+            .unwrap();
+        assert_eq!(registry.all_modules().len(), count);
+        assert!(matches!(
+            registry.replace(Box::new(OrderedFix {
+                id: "missing",
+                before: &[],
+                after: &[],
+            })),
+            Err(FixConfigError::UnknownId(_))
+        ));
+    }
+
     // --- HeadingSpacing Tests ---
 
+    #[test]
+    fn test_fix_infos_match_modules() {
+        let registry = FixRegistry::with_defaults();
+        let infos = registry.fix_infos();
+        assert_eq!(infos.len(), registry.all_modules().len());
+        let ws = infos.iter().find(|i| i.id == "whitespace_cleanup").unwrap();
+        assert_eq!(ws.classification, FixClassification::Cosmetic);
+        assert!(!ws.display_name.is_empty());
+        assert!(!ws.category.is_empty());
+    }
+
+    #[test]
+    fn test_enabled_ids_follow_config() {
+        let registry = FixRegistry::with_defaults();
+        let mut config = FixConfig::default();
+        config.set_enabled("whitespace_cleanup", false);
+        let ids = registry.enabled_ids(&config).unwrap();
+        assert!(!ids.contains("whitespace_cleanup"));
+        assert!(ids.contains("trailing_whitespace"));
+        assert!(
+            registry
+                .fix_infos()
+                .iter()
+                .all(|info| { ids.contains(&info.id) == config.allows(&info.id, info.min_tier) })
+        );
+
+        config.disabled_fixes.insert("bogus".to_string());
+        assert!(registry.enabled_ids(&config).is_err());
+    }
+
     #[test]
     fn test_heading_spacing_adds_blank_line() {
         let fix = HeadingSpacing;
@@ -1023,6 +2742,164 @@ mod tests {
         );
     }
 
+    // --- HeadingNormalization Tests ---
+
+    #[test]
+    fn test_heading_sentence_case() {
+        let ctx = test_context("Test");
+        let fix = HeadingNormalization::new(&["New York".to_string()]);
+        let input = "== Early Life ==\nText.\n== Move to New York in March ==\n\
+                     == Relationship with Madonna ==\n== NATO Membership ==\n\
+                     == Awards and Nominations ==\n";
+        let output = apply_masked(&fix, input, &ctx);
+        assert_eq!(
+            output,
+            "== Early life ==\nText.\n== Move to New York in March ==\n\
+             == Relationship with Madonna ==\n== NATO membership ==\n\
+             == Awards and Nominations ==\n"
+        );
+        assert_eq!(
+            fix.change_warnings(input, &output, &ctx),
+            vec![
+                "heading == Early Life == changed to == Early life == (sentence case)",
+                "heading == NATO Membership == changed to == NATO membership == (sentence case)",
+            ]
+        );
+        // Nothing is carried over from one run to the next
+        assert!(fix.apply(&output, &ctx) == output.as_ref());
+        assert!(fix.change_warnings(&output, &output, &ctx).is_empty());
+        assert!(fix.change_warnings(input, input, &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_heading_skipped_levels() {
+        let ctx = test_context("Test");
+        let input = "== A ==\n==== B ====\n===== C =====\n=== D ===\n== E ==\n=== F ===\n";
+        assert_eq!(
+            apply_masked(&HeadingNormalization::default(), input, &ctx),
+            "== A ==\n=== B ===\n==== C ====\n=== D ===\n== E ==\n=== F ===\n"
+        );
+    }
+
+    #[test]
+    fn test_heading_bold_and_links_removed() {
+        let ctx = test_context("Test");
+        let fix = HeadingNormalization::default();
+        let input = "== '''History''' ==\n== [[Music]] career ==\n\
+                     == Works by [[Jane Doe|Doe]] ==\n== '''''Dune''''' ==\n\
+                     == [[Category:X]] ==\n== [http://example.org Example site] ==\n";
+        let output = apply_masked(&fix, input, &ctx);
+        assert_eq!(
+            output,
+            "== History ==\n== Music career ==\n== Works by Doe ==\n== ''Dune'' ==\n\
+             == [[Category:X]] ==\n== Example site ==\n"
+        );
+        let warnings = fix.change_warnings(input, &output, &ctx);
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(
+            warnings[0],
+            "heading == '''History''' == changed to == History == (bold removed)"
+        );
+    }
+
+    #[test]
+    fn test_heading_normalization_leaves_protected_headings() {
+        let ctx = test_context("Test");
+        let fix = HeadingNormalization::default();
+        let input = "== Early {{Lang|fr|Vie}} ==\n<pre>\n== Early Life ==\n</pre>\n\
+                     == \"Big Song\" Recording ==\n== Title ===\nText\n";
+        assert!(matches!(apply_masked(&fix, input, &ctx), Cow::Borrowed(_)));
+        assert!(fix.change_warnings(input, input, &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_heading_normalization_needs_tier_2() {
+        let registry = FixRegistry::with_defaults();
+        let config = FixConfig {
+            strictness_tier: 1,
+            ..FixConfig::default()
+        };
+        assert!(
+            !registry
+                .enabled_ids(&config)
+                .unwrap()
+                .contains("heading_normalization")
+        );
+        let config = FixConfig {
+            strictness_tier: 2,
+            ..FixConfig::default()
+        };
+        assert!(
+            registry
+                .enabled_ids(&config)
+                .unwrap()
+                .contains("heading_normalization")
+        );
+    }
+
+    // --- ListFormatting Tests ---
+
+    #[test]
+    fn test_list_markers_made_consistent() {
+        let ctx = test_context("Test");
+        let input = "* a\n# b\n*# c\n** d\n* e\n*: note\n*# f\n\nText\n# g\n* h\n";
+        assert_eq!(
+            apply_masked(&ListFormatting, input, &ctx),
+            "* a\n* b\n*# c\n*# d\n* e\n*: note\n*# f\n\nText\n# g\n# h\n"
+        );
+    }
+
+    #[test]
+    fn test_list_blank_lines_removed() {
+        let ctx = test_context("Test");
+        let input = "# one\n\n# two\n\n\n## two.a\n\n* other list\n\nText\n";
+        assert_eq!(
+            apply_masked(&ListFormatting, input, &ctx),
+            "# one\n# two\n## two.a\n\n* other list\n\nText\n"
+        );
+        let separate = "* a\n\nText\n\n* b\n";
+        assert!(matches!(
+            apply_masked(&ListFormatting, separate, &ctx),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_html_lists_converted() {
+        let ctx = test_context("Test");
+        let input = "Intro\n<ul>\n<li>one</li>\n<li>two\n<ol><li>a</li><li>b</li></ol>\n</li>\n\
+                     <LI>three</ul>\nAfter\n<ul class=\"x\"><li>kept</li></ul>\n\
+                     <ul><li>multi\nline</li></ul>\n";
+        assert_eq!(
+            apply_masked(&ListFormatting, input, &ctx),
+            "Intro\n* one\n* two\n*# a\n*# b\n* three\nAfter\n\
+             <ul class=\"x\"><li>kept</li></ul>\n<ul><li>multi\nline</li></ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_list_templates_in_items() {
+        let ctx = test_context("Test");
+        let input = "* a {{Cite web\n|url=http://x.org\n# not a list item\n}}\n# b\n\
+                     <ul><li>{{Flag|FR}} France</li></ul>\n";
+        assert_eq!(
+            apply_masked(&ListFormatting, input, &ctx),
+            "* a {{Cite web\n|url=http://x.org\n# not a list item\n}}\n* b\n\
+             * {{Flag|FR}} France\n"
+        );
+    }
+
+    #[test]
+    fn test_list_formatting_skips_redirects() {
+        let mut ctx = test_context("Test");
+        ctx.is_redirect = true;
+        let input = "#REDIRECT [[Target]]\n* [[Category:Redirects]]\n";
+        assert!(matches!(
+            apply_masked(&ListFormatting, input, &ctx),
+            Cow::Borrowed(_)
+        ));
+    }
+
     // --- CitationFormatting Tests ---
 
     #[test]
@@ -1154,11 +3031,50 @@ mod tests {
         assert_eq!(result.as_ref(), input);
     }
 
+    // --- UnicodeNfc Tests ---
+
+    #[test]
+    fn test_unicode_nfc_composes_outside_protected_regions() {
+        let ctx = test_context("Test");
+
+        let input = "Cafe\u{301} {{Lang|fr|Cafe\u{301}}}";
+        let result = apply_masked(&UnicodeNfc, input, &ctx);
+        assert_eq!(result.as_ref(), "Caf\u{e9} {{Lang|fr|Cafe\u{301}}}");
+
+        let input = "Caf\u{e9} and plain text";
+        assert!(matches!(UnicodeNfc.apply(input, &ctx), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_unicode_nfc_warns_about_mixed_scripts() {
+        // Cyrillic "а" in a Latin word, Latin "o" in a Greek one
+        let ctx = test_context("P\u{430}ris");
+        let text = "[[P\u{430}ris|Paris]], [[Paris]], [[\u{3b1}\u{3b8}\u{3b7}\u{3bd}o]], \
+                    [[\u{41c}\u{43e}\u{441}\u{43a}\u{432}\u{430}]] and [[P\u{430}ris]]";
+        let warnings = UnicodeNfc.warnings(text, &ctx);
+        assert_eq!(
+            warnings,
+            vec![
+                "title mixes Latin and Cyrillic letters in \"P\u{430}ris\"".to_string(),
+                "link [[P\u{430}ris]] mixes Latin and Cyrillic letters in \"P\u{430}ris\""
+                    .to_string(),
+                "link [[\u{3b1}\u{3b8}\u{3b7}\u{3bd}o]] mixes Greek and Latin letters in \"\u{3b1}\u{3b8}\u{3b7}\u{3bd}o\""
+                    .to_string(),
+            ]
+        );
+
+        assert!(
+            UnicodeNfc
+                .warnings("[[Caf\u{e9}]]", &test_context("Test"))
+                .is_empty()
+        );
+    }
+
     // --- DefaultSortFix Tests ---
 
     #[test]
     fn test_defaultsort_adds_for_diacritics() {
-        let fix = DefaultSortFix;
+        let fix = DefaultSortFix::default();
         let ctx = test_context("Café");
 
         let input = "Article text.\n[[Category:Food]]";
@@ -1170,7 +3086,7 @@ mod tests {
 
     #[test]
     fn test_defaultsort_skips_if_already_present() {
-        let fix = DefaultSortFix;
+        let fix = DefaultSortFix::default();
         let ctx = test_context("Café");
 
         let input = "{{DEFAULTSORT:Custom Sort}}\n[[Category:Food]]";
@@ -1182,7 +3098,7 @@ mod tests {
 
     #[test]
     fn test_defaultsort_skips_ascii_only_titles() {
-        let fix = DefaultSortFix;
+        let fix = DefaultSortFix::default();
         let ctx = test_context("Regular Title");
 
         let input = "Article text.\n[[Category:Test]]";
@@ -1193,7 +3109,7 @@ mod tests {
 
     #[test]
     fn test_defaultsort_position_before_categories() {
-        let fix = DefaultSortFix;
+        let fix = DefaultSortFix::default();
         let ctx = test_context("Naïve");
 
         let input = "Article text.\n[[Category:First]]\n[[Category:Second]]";
@@ -1207,7 +3123,7 @@ mod tests {
 
     #[test]
     fn test_defaultsort_at_end_if_no_categories() {
-        let fix = DefaultSortFix;
+        let fix = DefaultSortFix::default();
         let ctx = test_context("Café");
 
         let input = "Article text with no categories.";
@@ -1217,6 +3133,97 @@ mod tests {
         assert!(result.as_ref().ends_with("{{DEFAULTSORT:Cafe}}\n"));
     }
 
+    #[test]
+    fn test_defaultsort_person_from_infobox() {
+        let fix = DefaultSortFix::new(PersonSortKey::Infobox);
+        let ctx = test_context("Zoë Smith (athlete)");
+        let input = "{{Infobox person\n| name = Zoë Jane Smith\n| birth_date = 1990\n}}\n\
+                     Text.\n[[Category:1990 births]]";
+        let result = fix.apply(input, &ctx);
+        assert!(result.contains("{{DEFAULTSORT:Smith, Zoe Jane}}\n[[Category:1990 births]]"));
+        assert!(fix.warnings(&result, &ctx).is_empty());
+        assert_eq!(fix.apply(&result, &ctx), result);
+
+        // Infobox mode does not fall back to the title
+        let ctx = test_context("John Smith");
+        let input = "Text.\n[[Category:Living people]]";
+        assert_eq!(fix.apply(input, &ctx), input);
+    }
+
+    #[test]
+    fn test_defaultsort_person_from_title() {
+        let fix = DefaultSortFix::new(PersonSortKey::InfoboxOrTitle);
+        let ctx = test_context("Sir John Smith Jr. (politician)");
+        let result = fix.apply("Text.\n[[Category:1950 deaths]]", &ctx);
+        assert!(result.contains("{{DEFAULTSORT:Smith, John, Jr.}}"));
+
+        // Not a biography: only diacritics matter
+        let ctx = test_context("Blue Lagoon");
+        let input = "Text.\n[[Category:Lagoons]]";
+        assert_eq!(fix.apply(input, &ctx), input);
+
+        // A mononym sorts as it is
+        let ctx = test_context("Madonna");
+        let input = "Text.\n[[Category:Living people]]";
+        assert_eq!(fix.apply(input, &ctx), input);
+    }
+
+    #[test]
+    fn test_defaultsort_person_ambiguous_warns() {
+        let fix = DefaultSortFix::new(PersonSortKey::InfoboxOrTitle);
+        for (title, input) in [
+            ("Vincent van Gogh", "[[Category:1890 deaths]]"),
+            (
+                "Ludwig van Beethoven",
+                "{{Infobox person|name=Beethoven|birth_date=1770}}",
+            ),
+            ("毛泽东", "[[Category:1893 births]]"),
+        ] {
+            let ctx = test_context(title);
+            assert_eq!(fix.apply(input, &ctx), input, "{}", title);
+            let warnings = fix.warnings(input, &ctx);
+            assert_eq!(warnings.len(), 1, "{}", title);
+            assert!(warnings[0].starts_with("no DEFAULTSORT added"));
+        }
+
+        // An existing DEFAULTSORT settles it
+        let ctx = test_context("Vincent van Gogh");
+        let input = "{{DEFAULTSORT:Gogh, Vincent van}}\n[[Category:1890 deaths]]";
+        assert!(fix.warnings(input, &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_name_sort_key() {
+        assert_eq!(name_sort_key("Conan O'Brien").unwrap(), "O'Brien, Conan");
+        assert_eq!(
+            name_sort_key("Martin Luther King, Jr.").unwrap(),
+            "King, Martin Luther, Jr."
+        );
+        assert_eq!(name_sort_key("Dame Judi Dench").unwrap(), "Dench, Judi");
+        assert!(name_sort_key("Robert De Niro").is_err());
+        assert!(name_sort_key("'''John''' Smith").is_err());
+        assert!(name_sort_key("John Smith<br />").is_err());
+        assert_eq!(strip_disambiguator("John Smith (born 1950)"), "John Smith");
+    }
+
+    #[test]
+    fn test_registry_reports_fix_warnings() {
+        let config = FixConfig {
+            person_sort_key: PersonSortKey::InfoboxOrTitle,
+            ..Default::default()
+        };
+        let registry = FixRegistry::with_config(&config);
+        let enabled = registry.enabled_ids(&config).unwrap();
+        let warnings = registry.warnings(
+            "Text.\n[[Category:1890 deaths]]",
+            "Text.\n[[Category:1890 deaths]]",
+            &test_context("Vincent van Gogh"),
+            &enabled,
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].0, "defaultsort_fix");
+    }
+
     // --- ascii_fold helper tests ---
 
     #[test]
@@ -1279,6 +3286,223 @@ mod tests {
 
     // --- FixRegistry Tests ---
 
+    struct AppendFix(&'static str);
+    impl FixModule for AppendFix {
+        fn id(&self) -> &str {
+            self.0
+        }
+        fn display_name(&self) -> &str {
+            self.0
+        }
+        fn category(&self) -> &str {
+            "Test"
+        }
+        fn description(&self) -> &str {
+            "Appends its ID"
+        }
+        fn apply<'a>(&self, text: &'a str, _context: &FixContext) -> Cow<'a, str> {
+            Cow::Owned(format!("{}[{}]", text, self.0))
+        }
+    }
+
+    #[test]
+    fn test_fix_registry_register_runs_in_order() {
+        let mut registry = FixRegistry::new();
+        registry.register(Box::new(AppendFix("first"))).unwrap();
+        registry.register(Box::new(AppendFix("second"))).unwrap();
+        let enabled: HashSet<String> = ["first", "second"].iter().map(|s| s.to_string()).collect();
+
+        let (ids, text) = registry.apply_all_returning_ids("x", &test_context("Test"), &enabled);
+        assert_eq!(ids, vec!["first", "second"]);
+        assert_eq!(text, "x[first][second]");
+    }
+
+    #[test]
+    fn test_fix_registry_register_rejects_duplicate_id() {
+        let mut registry = FixRegistry::with_defaults();
+        let count = registry.all_modules().len();
+        assert_eq!(
+            registry.register(Box::new(AppendFix("whitespace_cleanup"))),
+            Err(FixConfigError::DuplicateId(
+                "whitespace_cleanup".to_string()
+            ))
+        );
+        assert_eq!(registry.all_modules().len(), count);
+    }
+
+    #[test]
+    fn test_fix_registry_builder_groups_order() {
+        let registry = FixRegistry::builder()
+            .module(Box::new(AppendFix("custom")))
+            .plugin(Box::new(AppendFix("plugins")))
+            .with_defaults()
+            .build()
+            .unwrap();
+        let ids: Vec<&str> = registry.all_modules().iter().map(|m| m.id()).collect();
+        let defaults = FixRegistry::with_defaults().all_modules().len();
+        assert_eq!(ids[defaults - 1], "whitespace_cleanup");
+        assert_eq!(&ids[defaults..], &["plugins", "custom"]);
+        assert!(registry.known_ids().contains("custom"));
+    }
+
+    struct OrderedFix {
+        id: &'static str,
+        before: &'static [&'static str],
+        after: &'static [&'static str],
+    }
+    impl FixModule for OrderedFix {
+        fn id(&self) -> &str {
+            self.id
+        }
+        fn display_name(&self) -> &str {
+            self.id
+        }
+        fn category(&self) -> &str {
+            "Test"
+        }
+        fn description(&self) -> &str {
+            "Declares ordering relations"
+        }
+        fn runs_before(&self) -> &[&str] {
+            self.before
+        }
+        fn runs_after(&self) -> &[&str] {
+            self.after
+        }
+        fn apply<'a>(&self, text: &'a str, _context: &FixContext) -> Cow<'a, str> {
+            Cow::Borrowed(text)
+        }
+    }
+
+    fn ordered(id: &'static str, before: &'static [&'static str]) -> Box<dyn FixModule> {
+        Box::new(OrderedFix {
+            id,
+            before,
+            after: &[],
+        })
+    }
+
+    #[test]
+    fn test_fix_registry_apply_all_cow_borrows_unchanged_text() {
+        let registry = FixRegistry::with_defaults();
+        let ctx = test_context("Test");
+        let enabled: HashSet<String> = registry
+            .all_modules()
+            .iter()
+            .map(|m| m.id().to_string())
+            .collect();
+
+        let clean = "Clean text.\n";
+        assert!(matches!(
+            registry.apply_all_cow(clean, &ctx, &enabled),
+            Cow::Borrowed(_)
+        ));
+        let changed = registry.apply_all_cow("line   \n", &ctx, &enabled);
+        assert!(matches!(changed, Cow::Owned(_)));
+        assert_eq!(changed, "line\n");
+    }
+
+    #[test]
+    fn test_fix_registry_owned_but_equal_is_not_a_change() {
+        let mut registry = FixRegistry::new();
+        registry.register(Box::new(HtmlToWikitext)).unwrap();
+        let enabled: HashSet<String> = ["html_to_wikitext".to_string()].into_iter().collect();
+        let (ids, text) =
+            registry.apply_all_returning_ids("a<br />b", &test_context("Test"), &enabled);
+        assert!(ids.is_empty());
+        assert_eq!(text, "a<br />b");
+    }
+
+    #[test]
+    fn test_apply_masked_protects_invisible_regions() {
+        let mut registry = FixRegistry::new();
+        registry.register(Box::new(HtmlToWikitext)).unwrap();
+        registry.register(Box::new(CitationFormatting)).unwrap();
+        let enabled: HashSet<String> = ["html_to_wikitext", "citation_formatting"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let input = "<b>a</b><ref><b>b</b> {{Cite Web|accessdate=x}}</ref>\n{|\n| <b>c</b>\n|}\n";
+        let (ids, result) =
+            registry.apply_all_returning_ids(input, &test_context("Test"), &enabled);
+        assert_eq!(ids, vec!["html_to_wikitext", "citation_formatting"]);
+        assert_eq!(
+            result,
+            "'''a'''<ref><b>b</b> {{cite web|access-date=x}}</ref>\n{|\n| <b>c</b>\n|}\n"
+        );
+    }
+
+    #[test]
+    fn test_defaultsort_sees_existing_template_through_registry() {
+        let mut registry = FixRegistry::new();
+        registry
+            .register(Box::new(DefaultSortFix::default()))
+            .unwrap();
+        let enabled: HashSet<String> = ["defaultsort_fix".to_string()].into_iter().collect();
+        let input = "{{DEFAULTSORT:Custom}}\n[[Category:Restaurants]]";
+        let result = registry.apply_all(input, &test_context("Café"), &enabled);
+        assert_eq!(result, input);
+    }
+
+    #[test]
+    fn test_fix_registry_whitespace_cleanup_runs_last() {
+        let registry = FixRegistry::with_defaults();
+        let ids: Vec<&str> = registry.all_modules().iter().map(|m| m.id()).collect();
+        assert_eq!(ids.last(), Some(&"whitespace_cleanup"));
+        assert_eq!(ids[0], "heading_spacing");
+    }
+
+    #[test]
+    fn test_fix_registry_runs_before_reorders() {
+        let mut registry = FixRegistry::new();
+        registry.register(ordered("a", &[])).unwrap();
+        registry.register(ordered("b", &[])).unwrap();
+        registry.register(ordered("c", &["a"])).unwrap();
+        registry
+            .register(Box::new(OrderedFix {
+                id: "d",
+                before: &[],
+                after: &["missing"],
+            }))
+            .unwrap();
+        let ids: Vec<&str> = registry.all_modules().iter().map(|m| m.id()).collect();
+        // "a" waits for "c"; "b" is ready first and keeps its place ahead
+        assert_eq!(ids, vec!["b", "c", "a", "d"]);
+    }
+
+    #[test]
+    fn test_fix_registry_rejects_cycle() {
+        let mut registry = FixRegistry::new();
+        registry.register(ordered("a", &["b"])).unwrap();
+        let result = registry.register(ordered("b", &["a"]));
+        assert!(matches!(result, Err(FixConfigError::DependencyCycle(_))));
+        assert!(!registry.contains("b"));
+        assert_eq!(registry.all_modules().len(), 1);
+    }
+
+    #[test]
+    fn test_html_to_wikitext_nested_tags() {
+        let ctx = test_context("Test");
+        assert_eq!(
+            HtmlToWikitext.apply("<i><b>x</b></i> a<BR>b", &ctx),
+            "'''''x''''' a<br />b"
+        );
+        assert!(matches!(
+            HtmlToWikitext.apply("a < b", &ctx),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_fix_registry_builder_duplicate_id() {
+        let result = FixRegistry::builder()
+            .plugin(Box::new(AppendFix("dup")))
+            .module(Box::new(AppendFix("dup")))
+            .build();
+        assert!(matches!(result, Err(FixConfigError::DuplicateId(id)) if id == "dup"));
+    }
+
     #[test]
     fn test_fix_registry_with_defaults() {
         let registry = FixRegistry::with_defaults();
@@ -1673,6 +3897,8 @@ mod tests {
                 title: Title::new(Namespace::MAIN, "Test Article"),
                 namespace: Namespace::MAIN,
                 is_redirect: false,
+                revision: None,
+                cancel: Default::default(),
             }
         }
 
@@ -0,0 +1,281 @@
+//! Incremental re-transform after an edit conflict.
+//!
+//! When a save fails because someone else edited the page, only the sections
+//! they touched need to go through the rules again. Sections of the refetched
+//! page that are identical to a section of the page the previous plan was
+//! computed from keep their already transformed text; in effect a three-way
+//! merge at section granularity, with the refetched page winning wherever
+//! both sides changed.
+
+use crate::transform::TransformEngine;
+use awb_domain::session::EditPlan;
+use awb_domain::types::PageContent;
+
+/// How a retry plan was produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryMode {
+    /// The refetched page was unchanged, so the previous plan was reused.
+    Reused,
+    /// Unchanged sections were reused and only changed sections re-transformed.
+    Incremental { reused: usize, retransformed: usize },
+    /// The whole refetched page was transformed again.
+    Full,
+}
+
+/// A re-computed edit plan and how it was obtained.
+#[derive(Debug, Clone)]
+pub struct RetryPlan {
+    pub plan: EditPlan,
+    pub mode: RetryMode,
+}
+
+impl TransformEngine {
+    /// Re-plan an edit after an edit conflict.
+    ///
+    /// `base` is the page `previous` was computed from and `current` the
+    /// refetched page. Rules run only on sections of `current` that do not
+    /// appear unchanged in `base`; general fixes then run over the whole
+    /// page, since they may move content between sections. Falls back to a
+    /// full transform when the previous plan added or removed sections, as
+    /// its sections can then no longer be paired with those of `base`, and
+    /// for pages that are not wikitext, which have no sections.
+    pub fn apply_incremental(
+        &self,
+        base: &PageContent,
+        previous: &EditPlan,
+        current: &PageContent,
+    ) -> RetryPlan {
+        if current.wikitext == base.wikitext {
+            let mut plan = previous.clone();
+            plan.page = current.clone();
+            return RetryPlan {
+                plan,
+                mode: RetryMode::Reused,
+            };
+        }
+
+        let base_sections = split_sections(&base.wikitext);
+        let ours_sections = split_sections(&previous.new_wikitext);
+        if base_sections.len() != ours_sections.len()
+            || !current.properties.content_model.is_wikitext()
+        {
+            return RetryPlan {
+                plan: self.apply(current),
+                mode: RetryMode::Full,
+            };
+        }
+
+        let mut text = String::with_capacity(current.wikitext.len());
+        let mut rules_applied = Vec::new();
+        let mut reused_changes = false;
+        let mut next_base = 0;
+        let (mut reused, mut retransformed) = (0, 0);
+
+        for section in split_sections(&current.wikitext) {
+            // Sections are matched in order, so a moved section counts as changed
+            match base_sections[next_base..]
+                .iter()
+                .position(|b| *b == section)
+            {
+                Some(offset) => {
+                    let i = next_base + offset;
+                    text.push_str(ours_sections[i]);
+                    reused_changes |= ours_sections[i] != base_sections[i];
+                    next_base = i + 1;
+                    reused += 1;
+                }
+                None => {
+                    let (new_section, ids) = self.apply_rules(Some(&current.title), section);
+                    text.push_str(&new_section);
+                    push_unique(&mut rules_applied, ids);
+                    retransformed += 1;
+                }
+            }
+        }
+
+        let (fixes_applied, final_text) = self.apply_fixes(current, &text);
+
+        // Reused sections carry the previous plan's changes, so credit its
+        // rules and fixes too (summary and WP:COSMETIC depend on them)
+        let (rules_applied, fixes_applied) = if reused_changes {
+            let mut rules = previous.rules_applied.clone();
+            push_unique(&mut rules, rules_applied);
+            let mut fixes = previous.fixes_applied.clone();
+            push_unique(&mut fixes, fixes_applied);
+            (rules, fixes)
+        } else {
+            (rules_applied, fixes_applied)
+        };
+
+        RetryPlan {
+            plan: self.build_plan(current, final_text, rules_applied, fixes_applied),
+            mode: RetryMode::Incremental {
+                reused,
+                retransformed,
+            },
+        }
+    }
+}
+
+fn push_unique<T: PartialEq>(into: &mut Vec<T>, items: Vec<T>) {
+    for item in items {
+        if !into.contains(&item) {
+            into.push(item);
+        }
+    }
+}
+
+/// Split wikitext into sections, each starting at a heading line.
+///
+/// Heading-like lines inside templates or HTML comments do not start a
+/// section, so every section can be masked on its own.
+pub(crate) fn split_sections(text: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut section_start = 0;
+    let mut pos = 0;
+    let mut brace_depth = 0usize;
+    let mut in_comment = false;
+
+    for line in text.split_inclusive('\n') {
+        if pos > section_start && brace_depth == 0 && !in_comment && is_heading(line) {
+            sections.push(&text[section_start..pos]);
+            section_start = pos;
+        }
+
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &bytes[i..];
+            if in_comment {
+                if rest.starts_with(b"-->") {
+                    in_comment = false;
+                    i += 3;
+                    continue;
+                }
+            } else if rest.starts_with(b"<!--") {
+                in_comment = true;
+                i += 4;
+                continue;
+            } else if rest.starts_with(b"{{") {
+                brace_depth += 1;
+                i += 2;
+                continue;
+            } else if rest.starts_with(b"}}") {
+                brace_depth = brace_depth.saturating_sub(1);
+                i += 2;
+                continue;
+            }
+            i += 1;
+        }
+        pos += line.len();
+    }
+
+    if section_start < text.len() || sections.is_empty() {
+        sections.push(&text[section_start..]);
+    }
+    sections
+}
+
+pub(crate) fn is_heading(line: &str) -> bool {
+    let trimmed = line.trim_end();
+    trimmed.len() >= 3 && trimmed.starts_with('=') && trimmed.ends_with('=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::general_fixes::FixRegistry;
+    use awb_domain::rules::{Rule, RuleSet};
+    use awb_domain::types::*;
+    use std::collections::HashSet;
+
+    fn page(wikitext: &str) -> PageContent {
+        PageContent {
+            page_id: PageId(1),
+            title: Title::new(Namespace::MAIN, "Test"),
+            revision: RevisionId(100),
+            timestamp: chrono::Utc::now(),
+            wikitext: wikitext.to_string(),
+            size_bytes: wikitext.len() as u64,
+            is_redirect: false,
+            protection: ProtectionInfo::default(),
+            properties: PageProperties::default(),
+        }
+    }
+
+    fn engine(find: &str, replace: &str) -> TransformEngine {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain(find, replace, true));
+        TransformEngine::new(&ruleset, FixRegistry::new(), HashSet::new()).unwrap()
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let text = "Lead\n== A ==\nBody\n{{box|\n== not a heading ==\n}}\n== B ==\nEnd";
+        assert_eq!(
+            split_sections(text),
+            vec![
+                "Lead\n",
+                "== A ==\nBody\n{{box|\n== not a heading ==\n}}\n",
+                "== B ==\nEnd"
+            ]
+        );
+        assert_eq!(split_sections(""), vec![""]);
+    }
+
+    #[test]
+    fn test_unchanged_page_reuses_plan() {
+        let engine = engine("teh", "the");
+        let base = page("teh cat\n");
+        let previous = engine.apply(&base);
+        let retry = engine.apply_incremental(&base, &previous, &page("teh cat\n"));
+        assert_eq!(retry.mode, RetryMode::Reused);
+        assert_eq!(retry.plan.new_wikitext, "the cat\n");
+    }
+
+    #[test]
+    fn test_only_changed_sections_retransformed() {
+        let engine = engine("teh", "the");
+        let base = page("teh lead\n== A ==\nteh a\n== B ==\nteh b\n");
+        let previous = engine.apply(&base);
+        let current = page("teh lead\n== A ==\nteh a\n== B ==\nteh b, teh c\n");
+
+        let retry = engine.apply_incremental(&base, &previous, &current);
+        assert_eq!(
+            retry.mode,
+            RetryMode::Incremental {
+                reused: 2,
+                retransformed: 1
+            }
+        );
+        assert_eq!(retry.plan.new_wikitext, engine.apply(&current).new_wikitext);
+        assert_eq!(retry.plan.rules_applied.len(), 1);
+        assert_eq!(retry.plan.page.wikitext, current.wikitext);
+    }
+
+    #[test]
+    fn test_section_count_change_falls_back_to_full() {
+        let engine = engine("HEADING", "\n== Added ==\n");
+        let base = page("Lead HEADING\n== A ==\nText\n");
+        let previous = engine.apply(&base);
+        let current = page("Lead HEADING\n== A ==\nNew text\n");
+
+        let retry = engine.apply_incremental(&base, &previous, &current);
+        assert_eq!(retry.mode, RetryMode::Full);
+        assert_eq!(retry.plan.new_wikitext, engine.apply(&current).new_wikitext);
+    }
+
+    #[test]
+    fn test_non_wikitext_page_falls_back_to_full() {
+        let engine = engine("teh", "the");
+        let mut base = page("-- teh\nreturn {}\n");
+        base.properties.content_model = ContentModel::Scribunto;
+        let previous = engine.apply(&base);
+        let mut current = base.clone();
+        current.wikitext = "-- teh\nreturn { 1 }\n".to_string();
+
+        let retry = engine.apply_incremental(&base, &previous, &current);
+        assert_eq!(retry.mode, RetryMode::Full);
+        assert_eq!(retry.plan.new_wikitext, current.wikitext);
+    }
+}
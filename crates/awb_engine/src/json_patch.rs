@@ -0,0 +1,215 @@
+//! JSON Patch (RFC 6902) for `JsonPatch` rules on JSON content-model pages.
+//!
+//! A rule's operations apply all or nothing: if any fails, the document is
+//! left as it was. `move` and `copy` are not supported.
+
+use awb_domain::rules::JsonPatchOp;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum JsonPatchError {
+    #[error("invalid JSON pointer '{0}'")]
+    InvalidPointer(String),
+    #[error("nothing at '{0}'")]
+    Missing(String),
+    #[error("test failed at '{0}'")]
+    TestFailed(String),
+}
+
+/// Apply `operations` to `doc` in order. On error `doc` is unchanged.
+pub fn apply(doc: &mut Value, operations: &[JsonPatchOp]) -> Result<(), JsonPatchError> {
+    let mut patched = doc.clone();
+    for op in operations {
+        apply_one(&mut patched, op)?;
+    }
+    *doc = patched;
+    Ok(())
+}
+
+fn apply_one(doc: &mut Value, op: &JsonPatchOp) -> Result<(), JsonPatchError> {
+    match op {
+        JsonPatchOp::Add { path, value } => {
+            let (parent, key) = parent_mut(doc, path)?;
+            match parent {
+                Value::Object(map) => {
+                    map.insert(key, value.clone());
+                }
+                Value::Array(items) if key == "-" => items.push(value.clone()),
+                Value::Array(items) => {
+                    let index = array_index(&key, path)?;
+                    if index > items.len() {
+                        return Err(JsonPatchError::Missing(path.clone()));
+                    }
+                    items.insert(index, value.clone());
+                }
+                _ => return Err(JsonPatchError::Missing(path.clone())),
+            }
+        }
+        JsonPatchOp::Remove { path } => {
+            let (parent, key) = parent_mut(doc, path)?;
+            let removed = match parent {
+                Value::Object(map) => map.remove(&key).is_some(),
+                Value::Array(items) => {
+                    let index = array_index(&key, path)?;
+                    (index < items.len()).then(|| items.remove(index)).is_some()
+                }
+                _ => false,
+            };
+            if !removed {
+                return Err(JsonPatchError::Missing(path.clone()));
+            }
+        }
+        JsonPatchOp::Replace { path, value } => {
+            let target = pointer_mut(doc, path)?;
+            *target = value.clone();
+        }
+        JsonPatchOp::Test { path, value } => {
+            if pointer_mut(doc, path)? != value {
+                return Err(JsonPatchError::TestFailed(path.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn pointer_mut<'a>(doc: &'a mut Value, path: &str) -> Result<&'a mut Value, JsonPatchError> {
+    if !path.is_empty() && !path.starts_with('/') {
+        return Err(JsonPatchError::InvalidPointer(path.to_string()));
+    }
+    doc.pointer_mut(path)
+        .ok_or_else(|| JsonPatchError::Missing(path.to_string()))
+}
+
+/// The container `path` points into and the (unescaped) last segment
+fn parent_mut<'a>(
+    doc: &'a mut Value,
+    path: &str,
+) -> Result<(&'a mut Value, String), JsonPatchError> {
+    let Some((parent, last)) = path.rsplit_once('/') else {
+        return Err(JsonPatchError::InvalidPointer(path.to_string()));
+    };
+    let key = last.replace("~1", "/").replace("~0", "~");
+    Ok((pointer_mut(doc, parent)?, key))
+}
+
+fn array_index(key: &str, path: &str) -> Result<usize, JsonPatchError> {
+    if key.len() > 1 && key.starts_with('0') {
+        return Err(JsonPatchError::InvalidPointer(path.to_string()));
+    }
+    key.parse()
+        .map_err(|_| JsonPatchError::InvalidPointer(path.to_string()))
+}
+
+/// Serialize `doc` the way MediaWiki stores JSON pages: pretty-printed with
+/// four-space indents, keys in their original order
+pub fn to_page_text(doc: &Value) -> String {
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    doc.serialize(&mut serializer)
+        .expect("a JSON value always serializes");
+    String::from_utf8(out).expect("serde_json writes UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn replace(path: &str, value: Value) -> JsonPatchOp {
+        JsonPatchOp::Replace {
+            path: path.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_add_remove_replace() {
+        let mut doc = json!({ "license": "CC0", "rows": [[1, "a"], [2, "b"]] });
+        apply(
+            &mut doc,
+            &[
+                JsonPatchOp::Add {
+                    path: "/rows/-".to_string(),
+                    value: json!([3, "c"]),
+                },
+                JsonPatchOp::Add {
+                    path: "/rows/0".to_string(),
+                    value: json!([0, "z"]),
+                },
+                JsonPatchOp::Remove {
+                    path: "/rows/2".to_string(),
+                },
+                replace("/license", json!("CC-BY-SA-4.0")),
+                JsonPatchOp::Add {
+                    path: "/a~1b".to_string(),
+                    value: json!(true),
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc,
+            json!({
+                "license": "CC-BY-SA-4.0",
+                "rows": [[0, "z"], [1, "a"], [3, "c"]],
+                "a/b": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_failed_operation_leaves_document_unchanged() {
+        let mut doc = json!({ "license": "CC0" });
+        let before = doc.clone();
+
+        let result = apply(
+            &mut doc,
+            &[
+                replace("/license", json!("CC-BY-SA-4.0")),
+                replace("/missing", json!(1)),
+            ],
+        );
+        assert_eq!(result, Err(JsonPatchError::Missing("/missing".to_string())));
+        assert_eq!(doc, before);
+
+        let result = apply(
+            &mut doc,
+            &[JsonPatchOp::Test {
+                path: "/license".to_string(),
+                value: json!("CC-BY-3.0"),
+            }],
+        );
+        assert_eq!(
+            result,
+            Err(JsonPatchError::TestFailed("/license".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_pointers() {
+        let mut doc = json!({ "rows": [1, 2] });
+        assert_eq!(
+            apply(&mut doc, &[replace("rows", json!(1))]),
+            Err(JsonPatchError::InvalidPointer("rows".to_string()))
+        );
+        assert_eq!(
+            apply(
+                &mut doc,
+                &[JsonPatchOp::Remove {
+                    path: "/rows/01".to_string()
+                }]
+            ),
+            Err(JsonPatchError::InvalidPointer("/rows/01".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_page_text_uses_four_space_indent() {
+        let text = to_page_text(&json!({ "a": [1] }));
+        assert_eq!(text, "{\n    \"a\": [\n        1\n    ]\n}");
+    }
+}
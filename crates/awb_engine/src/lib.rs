@@ -1,11 +1,29 @@
 pub mod bot_policy;
 pub mod category;
+pub mod content_guard;
 pub mod diff_engine;
+pub mod file_replace;
 pub mod fix_config;
 pub mod general_fixes;
+pub mod incremental;
+pub mod json_patch;
+pub mod link_targets;
+pub mod maintenance_tags;
 pub mod masking;
+pub mod memory;
+pub mod merge;
 pub mod namespace_util;
+pub mod prefilter;
+pub mod provenance;
+pub mod redirects;
+pub mod references;
 pub mod review;
+pub mod rope;
+pub mod rule_conflicts;
 pub mod skip;
+pub mod spell_check;
+pub mod talk_page;
+pub mod template_balance;
+pub mod template_params;
 pub mod transform;
 pub mod typo_fix;
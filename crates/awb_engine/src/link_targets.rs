@@ -0,0 +1,85 @@
+//! Wikilink target preservation.
+//!
+//! A regex meant to touch the label of a piped link easily rewrites the
+//! target instead: `[[Foo (band)|Foo]]` becomes `[[Foo]]`, or the pipe ends
+//! up inside the target. [`link_target_changes`] compares the pages an edit
+//! links to before and after, with titles normalized by
+//! [`crate::redirects::normalize_title`], so only edits that really point a
+//! link somewhere else are reported. Category, file and leading-colon links
+//! are left out, as are links inside comments.
+
+use crate::redirects::link_targets;
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+/// Link targets an edit takes away and brings in, sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkTargetChanges {
+    /// Pages linked before the edit but not after
+    pub removed: Vec<String>,
+    /// Pages linked after the edit but not before
+    pub added: Vec<String>,
+}
+
+impl LinkTargetChanges {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+/// How the set of pages linked from `old` differs from that of `new`
+pub fn link_target_changes(old: &str, new: &str) -> LinkTargetChanges {
+    let before = targets(old);
+    let after = targets(new);
+    LinkTargetChanges {
+        removed: before.difference(&after).cloned().collect(),
+        added: after.difference(&before).cloned().collect(),
+    }
+}
+
+fn targets(wikitext: &str) -> BTreeSet<String> {
+    static COMMENT_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let comment_re =
+        COMMENT_RE.get_or_init(|| regex::Regex::new(r"(?s)<!--.*?-->").expect("known-valid regex"));
+    link_targets(&comment_re.replace_all(wikitext, ""))
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "[[Foo (band)|Foo]] played at [[madison_Square Garden]] and \
+        [[Foo (band)|again]].<!-- [[Hidden]] --> [[Category:Bands]] [[File:Foo.jpg|thumb]]";
+
+    #[test]
+    fn test_same_targets_written_differently() {
+        let new = PAGE
+            .replace(
+                "[[madison_Square Garden]]",
+                "[[Madison Square Garden|the Garden]]",
+            )
+            .replace("[[Foo (band)|again]]", "again")
+            .replace("<!-- [[Hidden]] -->", "")
+            .replace("[[Category:Bands]]", "[[Category:Rock bands]]");
+        assert!(link_target_changes(PAGE, &new).is_empty());
+    }
+
+    #[test]
+    fn test_broken_piped_link() {
+        // A label fix whose regex swallowed the target
+        let new = PAGE.replace("[[Foo (band)|Foo]]", "[[Foo]]");
+        let changes = link_target_changes(PAGE, &new);
+        assert_eq!(changes.added, vec!["Foo"]);
+        assert!(changes.removed.is_empty());
+        let new = new.replace("[[Foo (band)|again]]", "again");
+        assert_eq!(
+            link_target_changes(PAGE, &new),
+            LinkTargetChanges {
+                removed: vec!["Foo (band)".to_string()],
+                added: vec!["Foo".to_string()],
+            }
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! `{{Orphan}}` and `{{Dead end}}` tagging.
+//!
+//! An article is an orphan when no other article links to it, and a dead
+//! end when it links to no other article. Given link counts from the API,
+//! [`update_link_tags`] adds the tag a page lacks and removes the tag it no
+//! longer needs. Existing tags are left as they are, with their dates, also
+//! when written with a redirect name or inside `{{Multiple issues}}`.
+
+use crate::template_params::{normalize_template_name, template_spans};
+use chrono::NaiveDate;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaintenanceTag {
+    Orphan,
+    DeadEnd,
+}
+
+/// Accepted names for [`MaintenanceTag::from_str`]
+pub const MAINTENANCE_TAGS: &[&str] = &["orphan", "dead-end"];
+
+const ALL_TAGS: &[MaintenanceTag] = &[MaintenanceTag::Orphan, MaintenanceTag::DeadEnd];
+
+impl MaintenanceTag {
+    /// Template added by this tag
+    pub fn template(self) -> &'static str {
+        match self {
+            Self::Orphan => "Orphan",
+            Self::DeadEnd => "Dead end",
+        }
+    }
+
+    /// Names the template is also transcluded under (redirects to it)
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Self::Orphan => &["Orphaned", "Lonely", "Do-attempt"],
+            Self::DeadEnd => &["Deadend", "Dead-end", "Dep", "Internallinks", "Nuwikify"],
+        }
+    }
+
+    fn matches(self, name: &str) -> bool {
+        let name = normalize_template_name(name);
+        std::iter::once(self.template())
+            .chain(self.aliases().iter().copied())
+            .any(|known| normalize_template_name(known) == name)
+    }
+}
+
+impl FromStr for MaintenanceTag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['_', ' '], "-")
+            .as_str()
+        {
+            "orphan" => Ok(Self::Orphan),
+            "dead-end" | "deadend" => Ok(Self::DeadEnd),
+            other => Err(format!(
+                "unknown tag '{}' (expected one of: {})",
+                other,
+                MAINTENANCE_TAGS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MaintenanceTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{{{}}}}}", self.template())
+    }
+}
+
+/// Article links to and from a page, counted in the main namespace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkCounts {
+    pub incoming: usize,
+    pub outgoing: usize,
+}
+
+impl LinkCounts {
+    /// Whether the page should carry `tag`
+    pub fn needs(&self, tag: MaintenanceTag) -> bool {
+        match tag {
+            MaintenanceTag::Orphan => self.incoming == 0,
+            MaintenanceTag::DeadEnd => self.outgoing == 0,
+        }
+    }
+}
+
+/// Result of [`update_link_tags`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagUpdate {
+    pub text: String,
+    pub added: Vec<MaintenanceTag>,
+    pub removed: Vec<MaintenanceTag>,
+}
+
+impl TagUpdate {
+    pub fn is_changed(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty()
+    }
+
+    /// Edit summary, e.g. "Tagging {{Orphan}}; removing {{Dead end}}"
+    pub fn summary(&self) -> String {
+        let list = |tags: &[MaintenanceTag]| {
+            tags.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            parts.push(format!("Tagging {}", list(&self.added)));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("removing {}", list(&self.removed)));
+        }
+        let summary = parts.join("; ");
+        let mut chars = summary.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => summary,
+        }
+    }
+}
+
+/// Templates kept above maintenance tags at the top of an article
+const TOP_TEMPLATES: &[&str] = &[
+    "Short description",
+    "DISPLAYTITLE",
+    "Use dmy dates",
+    "Use mdy dates",
+];
+
+/// Add or remove each of `tags` in `wikitext` so that it matches `counts`.
+/// New tags are dated `date`'s month and go after a leading
+/// `{{Short description}}` and similar templates.
+pub fn update_link_tags(
+    wikitext: &str,
+    counts: LinkCounts,
+    tags: &[MaintenanceTag],
+    date: NaiveDate,
+) -> TagUpdate {
+    let mut text = wikitext.to_string();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+
+    for &tag in tags {
+        let spans: Vec<_> = template_spans(&text)
+            .into_iter()
+            .filter(|(_, call)| tag.matches(&call.name))
+            .map(|(range, _)| range)
+            .collect();
+        if counts.needs(tag) {
+            if spans.is_empty() {
+                let at = insertion_point(&text);
+                text.insert_str(
+                    at,
+                    &format!("{{{{{}|date={}}}}}\n", tag.template(), date.format("%B %Y")),
+                );
+                added.push(tag);
+            }
+        } else if !spans.is_empty() {
+            // Back to front so earlier ranges stay valid
+            for range in spans.into_iter().rev() {
+                let end = match text[range.end..].strip_prefix('\n') {
+                    Some(_) => range.end + 1,
+                    None => range.end,
+                };
+                text.replace_range(range.start..end, "");
+            }
+            removed.push(tag);
+        }
+    }
+
+    TagUpdate {
+        text,
+        added,
+        removed,
+    }
+}
+
+/// Byte offset after the leading templates listed in [`TOP_TEMPLATES`] and
+/// any tags already there
+fn insertion_point(text: &str) -> usize {
+    let mut at = 0;
+    for (range, call) in template_spans(text) {
+        let between = &text[at..range.start];
+        if !between.trim().is_empty() {
+            break;
+        }
+        let name = normalize_template_name(&call.name);
+        let is_top = TOP_TEMPLATES
+            .iter()
+            .any(|t| normalize_template_name(t) == name)
+            || ALL_TAGS.iter().any(|tag| tag.matches(&call.name));
+        if !is_top {
+            break;
+        }
+        at = range.end;
+        if text[at..].starts_with('\n') {
+            at += 1;
+        }
+    }
+    at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOTH: &[MaintenanceTag] = &[MaintenanceTag::Orphan, MaintenanceTag::DeadEnd];
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 15).unwrap()
+    }
+
+    fn counts(incoming: usize, outgoing: usize) -> LinkCounts {
+        LinkCounts { incoming, outgoing }
+    }
+
+    #[test]
+    fn test_adds_tags_after_short_description() {
+        let text = "{{Short description|A town}}\n'''Foo''' is a town.";
+        let update = update_link_tags(text, counts(0, 0), BOTH, date());
+        assert_eq!(
+            update.text,
+            "{{Short description|A town}}\n{{Orphan|date=October 2026}}\n\
+             {{Dead end|date=October 2026}}\n'''Foo''' is a town."
+        );
+        assert_eq!(update.added, BOTH);
+        assert_eq!(update.summary(), "Tagging {{Orphan}}, {{Dead end}}");
+    }
+
+    #[test]
+    fn test_keeps_existing_dated_tags() {
+        let text =
+            "{{Deadend|date=May 2019}}\n{{Multiple issues|\n{{orphan|date=March 2020}}\n}}\nFoo";
+        let update = update_link_tags(text, counts(0, 0), BOTH, date());
+        assert!(!update.is_changed());
+        assert_eq!(update.text, text);
+    }
+
+    #[test]
+    fn test_removes_tags_no_longer_needed() {
+        let text = "{{Orphan|date=March 2020}}\n{{Dead end|date=May 2019}}\nFoo [[Bar]]";
+        let update = update_link_tags(text, counts(3, 1), BOTH, date());
+        assert_eq!(update.text, "Foo [[Bar]]");
+        assert_eq!(update.removed, BOTH);
+        assert_eq!(update.summary(), "Removing {{Orphan}}, {{Dead end}}");
+
+        // Only the requested tags are touched
+        let update = update_link_tags(text, counts(3, 0), &[MaintenanceTag::Orphan], date());
+        assert_eq!(update.text, "{{Dead end|date=May 2019}}\nFoo [[Bar]]");
+    }
+
+    #[test]
+    fn test_commented_tags_are_ignored() {
+        let text = "<!-- {{Orphan}} -->Foo";
+        let update = update_link_tags(text, counts(0, 1), BOTH, date());
+        assert_eq!(update.added, vec![MaintenanceTag::Orphan]);
+        assert_eq!(
+            update.text,
+            "{{Orphan|date=October 2026}}\n<!-- {{Orphan}} -->Foo"
+        );
+    }
+
+    #[test]
+    fn test_tag_from_str() {
+        assert_eq!("orphan".parse(), Ok(MaintenanceTag::Orphan));
+        assert_eq!("Dead end".parse(), Ok(MaintenanceTag::DeadEnd));
+        assert!("stub".parse::<MaintenanceTag>().is_err());
+    }
+}
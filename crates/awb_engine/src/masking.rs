@@ -3,7 +3,9 @@
 //! The mask→transform→unmask pattern ensures that content inside `<nowiki>`,
 //! `<pre>`, `<code>`, `<syntaxhighlight>`, `<math>`, `<source>`, HTML comments,
 //! `{{templates}}`, and `[[File:…]]/[[Image:…]]` links is never altered by
-//! general fixes or find-and-replace rules.
+//! general fixes or find-and-replace rules. `<gallery>` blocks, `<ref>`
+//! contents, `<references>` and `{| … |}` tables can be protected too by
+//! selecting them in a [`MaskPolicy`], and so can talk page signatures.
 //!
 //! Guarantees:
 //! - Single-pass scan for mask extraction.
@@ -12,6 +14,8 @@
 //! - If any sentinel leaks or restoration count mismatches → return original text (fail closed).
 
 use std::borrow::Cow;
+use std::ops::{BitOr, BitOrAssign};
+use std::sync::OnceLock;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 /// A sentinel prefix that is extremely unlikely in real wikitext.
@@ -22,6 +26,74 @@ const SENTINEL_SUFFIX: &str = "\x00\x02";
 /// SeqCst ordering ensures proper synchronization across threads.
 static MASK_NONCE: AtomicU64 = AtomicU64::new(0);
 
+/// Set of region kinds to protect when masking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaskPolicy(u16);
+
+impl MaskPolicy {
+    /// Protect nothing.
+    pub const NONE: Self = Self(0);
+    /// HTML comments: `<!-- ... -->`
+    pub const COMMENTS: Self = Self(1 << 0);
+    /// `<nowiki>`, `<pre>`, `<code>`, `<syntaxhighlight>`, `<math>`, `<source>`
+    pub const EXTENSION_TAGS: Self = Self(1 << 1);
+    /// Templates: `{{...}}`
+    pub const TEMPLATES: Self = Self(1 << 2);
+    /// `[[File:...]]` and `[[Image:...]]` links
+    pub const FILE_LINKS: Self = Self(1 << 3);
+    /// `<gallery>...</gallery>` blocks
+    pub const GALLERIES: Self = Self(1 << 4);
+    /// `<ref>...</ref>`, `<ref ... />` and `<references>` tags
+    pub const REFS: Self = Self(1 << 5);
+    /// Tables: `{| ... |}` starting at the beginning of a line
+    pub const TABLES: Self = Self(1 << 6);
+    /// Talk page signatures: a user link through the timestamp after it on
+    /// the same line, e.g. `[[User:A|A]] ([[User talk:A|talk]]) 12:00, 1
+    /// May 2024 (UTC)`. Not part of [`ALL`](Self::ALL); only talk page mode
+    /// masks them.
+    pub const SIGNATURES: Self = Self(1 << 7);
+    /// The regions [`mask`] protects.
+    pub const DEFAULT: Self =
+        Self(Self::COMMENTS.0 | Self::EXTENSION_TAGS.0 | Self::TEMPLATES.0 | Self::FILE_LINKS.0);
+    /// Every region kind apart from [`SIGNATURES`](Self::SIGNATURES).
+    pub const ALL: Self = Self(Self::DEFAULT.0 | Self::GALLERIES.0 | Self::REFS.0 | Self::TABLES.0);
+
+    /// Whether every kind in `other` is also in `self`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether no region kind is selected.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Kinds in `self` that are not in `other`.
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl Default for MaskPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl BitOr for MaskPolicy {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for MaskPolicy {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
 /// Holds masked regions and the masked text.
 #[derive(Debug)]
 pub struct MaskedText {
@@ -36,6 +108,11 @@ pub struct MaskedText {
 }
 
 impl MaskedText {
+    /// Whether any region was masked.
+    pub fn has_regions(&self) -> bool {
+        !self.regions.is_empty()
+    }
+
     /// Apply a transformation function to the masked text.
     /// The function receives the masked text (with sentinels in place of protected regions)
     /// and should return the transformed text (leaving sentinels intact).
@@ -48,18 +125,52 @@ impl MaskedText {
 
     /// Restore all sentinels with original content.
     /// If any sentinel is missing or extra sentinels remain, returns the original text unchanged (fail closed).
-    ///
-    /// Uses single-pass assembly to avoid O(n*m) repeated string copies.
     pub fn unmask(self) -> String {
         if self.regions.is_empty() {
             return self.masked;
         }
+        self.restore(&self.masked)
+    }
+
+    /// Like [`unmask`](Self::unmask), but for any text derived from the
+    /// masked text, e.g. an intermediate result while rules run, leaving
+    /// `self` untouched.
+    pub fn restore(&self, masked: &str) -> String {
+        if self.regions.is_empty() {
+            return masked.to_string();
+        }
+        match self.restore_sentinels(masked) {
+            // Every sentinel must be restored exactly once
+            Some((result, restored)) if restored == self.regions.len() => result,
+            _ => self.original.clone(),
+        }
+    }
 
-        // Single-pass: scan through masked text, find sentinels, assemble result
-        let mut result = String::with_capacity(self.masked.len());
+    /// How many lines each line of `masked`, a text derived from the masked
+    /// text, spans once its sentinels are restored. `None` if a sentinel is
+    /// damaged.
+    pub(crate) fn unmasked_line_counts(&self, masked: &str) -> Option<Vec<usize>> {
+        masked
+            .split_inclusive('\n')
+            .map(|line| {
+                if self.regions.is_empty() || !line.contains(&self.sentinel_base) {
+                    return Some(1);
+                }
+                let (restored, _) = self.restore_sentinels(line)?;
+                Some(restored.split_inclusive('\n').count())
+            })
+            .collect()
+    }
+
+    /// Replace the sentinels in `masked` with their regions, returning the
+    /// text and the number of sentinels replaced, or `None` if one is
+    /// malformed.
+    ///
+    /// Uses single-pass assembly to avoid O(n*m) repeated string copies.
+    fn restore_sentinels(&self, masked: &str) -> Option<(String, usize)> {
+        let mut result = String::with_capacity(masked.len());
         let mut pos = 0;
         let mut restored_count = 0;
-        let masked = &self.masked;
 
         while pos < masked.len() {
             // Check if current position starts with the sentinel base
@@ -68,28 +179,16 @@ impl MaskedText {
                 let after_base = pos + self.sentinel_base.len();
 
                 // Find the end of the numeric index (before SENTINEL_SUFFIX)
-                if let Some(suffix_pos) = masked[after_base..].find(SENTINEL_SUFFIX) {
-                    let index_str = &masked[after_base..after_base + suffix_pos];
-
-                    // Parse the index
-                    if let Ok(idx) = index_str.parse::<usize>() {
-                        if idx < self.regions.len() {
-                            // Valid sentinel — restore it
-                            result.push_str(&self.regions[idx]);
-                            pos = after_base + suffix_pos + SENTINEL_SUFFIX.len();
-                            restored_count += 1;
-                        } else {
-                            // Index out of bounds — fail closed
-                            return self.original;
-                        }
-                    } else {
-                        // Malformed index — fail closed
-                        return self.original;
-                    }
-                } else {
-                    // No suffix found — fail closed
-                    return self.original;
-                }
+                let suffix_pos = masked[after_base..].find(SENTINEL_SUFFIX)?;
+                let index_str = &masked[after_base..after_base + suffix_pos];
+                // An index that does not parse or is out of bounds fails closed
+                let idx = index_str
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&idx| idx < self.regions.len())?;
+                result.push_str(&self.regions[idx]);
+                pos = after_base + suffix_pos + SENTINEL_SUFFIX.len();
+                restored_count += 1;
             } else {
                 // Copy character
                 let ch = masked[pos..].chars().next().unwrap();
@@ -98,12 +197,7 @@ impl MaskedText {
             }
         }
 
-        // Verify all sentinels were restored exactly once
-        if restored_count != self.regions.len() {
-            return self.original;
-        }
-
-        result
+        Some((result, restored_count))
     }
 }
 
@@ -119,6 +213,16 @@ impl MaskedText {
 /// If the input already contains the sentinel prefix, returns the text unmasked
 /// (fail closed — we cannot safely mask).
 pub fn mask(text: &str) -> MaskedText {
+    mask_with(text, MaskPolicy::DEFAULT)
+}
+
+/// Mask the region kinds selected by `policy`.
+///
+/// Scan order matches [`mask`], with `<gallery>`, `<ref>` and
+/// `<references>` checked alongside the other tags and tables after
+/// templates. An outer region swallows anything nested inside it, so a
+/// template inside a masked table is part of the table region.
+pub fn mask_with(text: &str, policy: MaskPolicy) -> MaskedText {
     // Fail closed if sentinel already present
     if text.contains(SENTINEL_PREFIX) {
         return MaskedText {
@@ -129,6 +233,17 @@ pub fn mask(text: &str) -> MaskedText {
         };
     }
 
+    let mut tags: Vec<&'static str> = Vec::new();
+    if policy.contains(MaskPolicy::EXTENSION_TAGS) {
+        tags.extend_from_slice(EXTENSION_TAGS);
+    }
+    if policy.contains(MaskPolicy::GALLERIES) {
+        tags.push("gallery");
+    }
+    if policy.contains(MaskPolicy::REFS) {
+        tags.extend_from_slice(&["ref", "references"]);
+    }
+
     let nonce = MASK_NONCE.fetch_add(1, Ordering::SeqCst);
     let sentinel_base = format!("{}{}N", SENTINEL_PREFIX, nonce);
     let mut regions: Vec<String> = Vec::new();
@@ -139,7 +254,7 @@ pub fn mask(text: &str) -> MaskedText {
 
     while i < len {
         // 1. HTML comments: <!-- ... -->
-        if i + 4 <= len && &bytes[i..i + 4] == b"<!--" {
+        if policy.contains(MaskPolicy::COMMENTS) && i + 4 <= len && &bytes[i..i + 4] == b"<!--" {
             if let Some(end) = find_bytes(bytes, i + 4, b"-->") {
                 let end = end + 3; // include -->
                 let region = &text[i..end];
@@ -153,7 +268,7 @@ pub fn mask(text: &str) -> MaskedText {
 
         // 2. Extension tags (case-insensitive)
         if bytes[i] == b'<' {
-            if let Some((tag_name, close_pos)) = try_match_extension_tag(text, i) {
+            if let Some((tag_name, close_pos)) = try_match_extension_tag(text, i, &tags) {
                 let region = &text[i..close_pos];
                 let idx = regions.len();
                 regions.push(region.to_string());
@@ -165,7 +280,11 @@ pub fn mask(text: &str) -> MaskedText {
         }
 
         // 3. Templates: {{ ... }} with brace-depth tracking
-        if i + 1 < len && bytes[i] == b'{' && bytes[i + 1] == b'{' {
+        if policy.contains(MaskPolicy::TEMPLATES)
+            && i + 1 < len
+            && bytes[i] == b'{'
+            && bytes[i + 1] == b'{'
+        {
             if let Some(end) = find_matching_braces(bytes, i) {
                 let region = &text[i..end];
                 let idx = regions.len();
@@ -176,8 +295,40 @@ pub fn mask(text: &str) -> MaskedText {
             }
         }
 
-        // 4. File/Image links: [[File:...]] or [[Image:...]]
-        if i + 2 < len && bytes[i] == b'[' && bytes[i + 1] == b'[' && is_file_or_image_link(text, i)
+        // 4. Tables: {| ... |} at line start, with nesting
+        if policy.contains(MaskPolicy::TABLES) && is_table_start(text, i) {
+            if let Some(end) = find_table_end(text, i) {
+                let region = &text[i..end];
+                let idx = regions.len();
+                regions.push(region.to_string());
+                result.push_str(&format!("{}{}{}", sentinel_base, idx, SENTINEL_SUFFIX));
+                i = end;
+                continue;
+            }
+        }
+
+        // 5. Signatures: [[User:...]] ... timestamp, on one line
+        if policy.contains(MaskPolicy::SIGNATURES)
+            && i + 2 < len
+            && bytes[i] == b'['
+            && bytes[i + 1] == b'['
+        {
+            if let Some(end) = find_signature_end(text, i) {
+                let region = &text[i..end];
+                let idx = regions.len();
+                regions.push(region.to_string());
+                result.push_str(&format!("{}{}{}", sentinel_base, idx, SENTINEL_SUFFIX));
+                i = end;
+                continue;
+            }
+        }
+
+        // 6. File/Image links: [[File:...]] or [[Image:...]]
+        if policy.contains(MaskPolicy::FILE_LINKS)
+            && i + 2 < len
+            && bytes[i] == b'['
+            && bytes[i + 1] == b'['
+            && is_file_or_image_link(text, i)
         {
             if let Some(end) = find_matching_brackets(bytes, i) {
                 let region = &text[i..end];
@@ -248,14 +399,18 @@ fn find_bytes(haystack: &[u8], start: usize, needle: &[u8]) -> Option<usize> {
 /// Extension tags we protect (case-insensitive).
 const EXTENSION_TAGS: &[&str] = &["nowiki", "pre", "code", "syntaxhighlight", "math", "source"];
 
-/// Try to match an extension tag at position `start` (which points to '<').
+/// Try to match one of `tags` at position `start` (which points to '<').
 /// Returns (tag_name, end_position_exclusive) if matched.
 ///
 /// Known limitation: attributes containing a literal `>` (e.g., `<nowiki attr="a>b">`)
 /// may cause the parser to find the closing `>` too early. This is extremely rare in
 /// real wikitext and the fail-closed design means the content would simply not be masked
 /// (i.e., transforms could modify it), which is safe — just suboptimal.
-fn try_match_extension_tag(text: &str, start: usize) -> Option<(&'static str, usize)> {
+fn try_match_extension_tag(
+    text: &str,
+    start: usize,
+    tags: &[&'static str],
+) -> Option<(&'static str, usize)> {
     let rest = &text[start..];
     // Must start with '<'
     if !rest.starts_with('<') {
@@ -263,7 +418,7 @@ fn try_match_extension_tag(text: &str, start: usize) -> Option<(&'static str, us
     }
     let after_lt = &rest[1..];
 
-    for &tag in EXTENSION_TAGS {
+    for &tag in tags {
         // Check opening tag (case-insensitive)
         if after_lt.len() >= tag.len() && after_lt[..tag.len()].eq_ignore_ascii_case(tag) {
             // After tag name must be '>' or whitespace (for attributes) or '/>'
@@ -355,6 +510,39 @@ fn find_matching_braces(bytes: &[u8], start: usize) -> Option<usize> {
     None // Unmatched — don't mask (fail closed: leave as-is)
 }
 
+/// Check if `{|` at `start` opens a table: it must begin a line, optionally
+/// after spaces or tabs.
+fn is_table_start(text: &str, start: usize) -> bool {
+    if !text[start..].starts_with("{|") {
+        return false;
+    }
+    let line_start = text[..start].rfind('\n').map(|p| p + 1).unwrap_or(0);
+    text[line_start..start]
+        .chars()
+        .all(|c| c == ' ' || c == '\t')
+}
+
+/// Find the end of the table opened at `start`, just past its closing `|}`.
+/// Nested tables are tracked line by line.
+fn find_table_end(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut line_start = start;
+    for line in text[start..].split_inclusive('\n') {
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        let indent = line.len() - trimmed.len();
+        if trimmed.starts_with("{|") {
+            depth += 1;
+        } else if trimmed.starts_with("|}") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(line_start + indent + 2);
+            }
+        }
+        line_start += line.len();
+    }
+    None // Unclosed table — don't mask
+}
+
 /// Check if `[[` at `start` is a File: or Image: link (case-insensitive).
 fn is_file_or_image_link(text: &str, start: usize) -> bool {
     let after = &text[start + 2..];
@@ -362,6 +550,38 @@ fn is_file_or_image_link(text: &str, start: usize) -> bool {
         || (after.len() >= 6 && after[..6].eq_ignore_ascii_case("image:"))
 }
 
+/// If a user link starts at `start`, the end of the signature timestamp
+/// that follows it on the same line.
+pub(crate) fn find_signature_end(text: &str, start: usize) -> Option<usize> {
+    static TIMESTAMP_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let timestamp = TIMESTAMP_RE.get_or_init(|| {
+        regex::Regex::new(r"\d{1,2}:\d{2}, \d{1,2} [^\s\d,()]+ \d{4} \([A-Z]{2,5}\)")
+            .expect("known-valid regex")
+    });
+
+    signature_user(text, start)?;
+    let line_end = text[start..].find('\n').map_or(text.len(), |n| start + n);
+    timestamp
+        .find(&text[start..line_end])
+        .map(|m| start + m.end())
+}
+
+/// The user named by a `[[User:…]]`, `[[User talk:…]]` or
+/// `[[Special:Contributions/…]]` link starting at `start`, as written
+pub(crate) fn signature_user(text: &str, start: usize) -> Option<&str> {
+    let after = text.get(start..)?.strip_prefix("[[")?;
+    let prefix = ["user:", "user talk:", "special:contributions/"]
+        .into_iter()
+        .find(|prefix| {
+            after
+                .get(..prefix.len())
+                .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+        })?;
+    let name = &after[prefix.len()..];
+    let end = name.find(['|', ']', '/', '#']).unwrap_or(name.len());
+    Some(name[..end].trim())
+}
+
 /// Find matching `]]` for `[[` at position `start`. Returns exclusive end position.
 /// Handles nested `[[...]]` inside.
 fn find_matching_brackets(bytes: &[u8], start: usize) -> Option<usize> {
@@ -676,6 +896,80 @@ mod tests {
         assert_eq!(masked.unmask(), text);
     }
 
+    // --- Optional region kinds ---
+
+    #[test]
+    fn test_default_policy_leaves_refs_tables_galleries_visible() {
+        let text = "A<ref>x</ref>\n{|\n| cell\n|}\n<gallery>\nA.jpg\n</gallery>";
+        let masked = mask(text);
+        assert!(masked.regions.is_empty());
+    }
+
+    #[test]
+    fn test_mask_refs() {
+        let text = "Fact.<ref name=\"a\">Cite  here</ref> More.<ref name=\"a\" />\n<references />";
+        let masked = mask_with(text, MaskPolicy::REFS);
+        assert_eq!(masked.regions.len(), 3);
+        assert!(!masked.masked.contains("Cite"));
+        assert!(!masked.masked.contains("references"));
+        assert_eq!(masked.unmask(), text);
+    }
+
+    #[test]
+    fn test_mask_references_block() {
+        let text = "<references>\n<ref name=\"a\">A</ref>\n</references>";
+        let masked = mask_with(text, MaskPolicy::REFS);
+        assert_eq!(masked.regions.len(), 1);
+        assert_eq!(masked.unmask(), text);
+    }
+
+    #[test]
+    fn test_mask_gallery() {
+        let text = "Intro\n<gallery mode=\"packed\">\nA.jpg|Caption  one\n</gallery>\nEnd";
+        let masked = mask_with(text, MaskPolicy::GALLERIES);
+        assert_eq!(masked.regions.len(), 1);
+        assert!(!masked.masked.contains("Caption"));
+        assert_eq!(masked.unmask(), text);
+    }
+
+    #[test]
+    fn test_mask_nested_table() {
+        let text =
+            "Intro\n{| class=\"wikitable\"\n| a\n|-\n|\n {|\n | inner\n |}\n|}\nAfter |} text";
+        let masked = mask_with(text, MaskPolicy::TABLES);
+        assert_eq!(masked.regions.len(), 1);
+        assert!(masked.regions[0].ends_with("|}\n|}"));
+        assert!(masked.masked.contains("After |} text"));
+        assert_eq!(masked.unmask(), text);
+    }
+
+    #[test]
+    fn test_table_must_start_line() {
+        let text = "inline {| not a table |}";
+        assert!(mask_with(text, MaskPolicy::TABLES).regions.is_empty());
+        let unclosed = "{|\n| cell";
+        assert!(mask_with(unclosed, MaskPolicy::TABLES).regions.is_empty());
+    }
+
+    #[test]
+    fn test_policy_excludes_templates() {
+        let text = "{{cite web|title=T}} <!-- c -->";
+        let masked = mask_with(text, MaskPolicy::COMMENTS);
+        assert_eq!(masked.regions.len(), 1);
+        assert!(masked.masked.contains("{{cite web"));
+    }
+
+    #[test]
+    fn test_policy_set_operations() {
+        let policy = MaskPolicy::COMMENTS | MaskPolicy::TABLES;
+        assert!(policy.contains(MaskPolicy::TABLES));
+        assert!(!policy.contains(MaskPolicy::REFS));
+        assert!(MaskPolicy::ALL.contains(MaskPolicy::DEFAULT));
+        assert_eq!(policy.difference(MaskPolicy::TABLES), MaskPolicy::COMMENTS);
+        assert!(MaskPolicy::NONE.is_empty());
+        assert_eq!(MaskPolicy::default(), MaskPolicy::DEFAULT);
+    }
+
     #[test]
     fn test_nonce_prevents_cross_mask_collision() {
         // CRITICAL-1: two mask() calls should use different sentinels
@@ -683,4 +977,18 @@ mod tests {
         let m2 = mask("{{b}}");
         assert_ne!(m1.sentinel_base, m2.sentinel_base);
     }
+
+    #[test]
+    fn test_signatures_masked() {
+        let text = "Agreed. [[User:Ann|Ann]] ([[User talk:Ann|talk]]) 09:15, 3 May 2024 (UTC)\n\
+                    See [[User:Bob]] for details.";
+        let masked = mask_with(text, MaskPolicy::SIGNATURES);
+        assert_eq!(masked.regions.len(), 1);
+        assert!(masked.masked.starts_with("Agreed. \x00"));
+        assert!(masked.masked.ends_with("See [[User:Bob]] for details."));
+        assert_eq!(masked.unmask(), text);
+
+        assert!(!MaskPolicy::ALL.contains(MaskPolicy::SIGNATURES));
+        assert!(mask(text).regions.is_empty());
+    }
 }
@@ -0,0 +1,198 @@
+//! Memory use of the transform pipeline, per page and per rule or fix.
+//!
+//! A rule that rewrites a pathological page can allocate many times the
+//! page's size. [`TransformEngine::apply_profiled`] reports, for each rule
+//! and fix that ran, the most memory its step held above what was held
+//! when it started, and the size of the text it produced.
+//!
+//! Allocations are only counted when the program installs
+//! [`TrackingAllocator`] as its global allocator; otherwise the peaks are
+//! zero and only text sizes are reported. Counts are kept per thread, so
+//! pages transformed in parallel do not mix.
+//!
+//! [`TransformEngine::apply_profiled`]: crate::transform::TransformEngine::apply_profiled
+
+use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    /// Bytes allocated and not yet freed by this thread; frees of memory
+    /// another thread allocated can take it below zero
+    static CURRENT: Cell<isize> = const { Cell::new(0) };
+    /// Highest `CURRENT` since the innermost [`measure`] began
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+/// The system allocator, counting the bytes each thread holds.
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: awb_engine::memory::TrackingAllocator =
+///     awb_engine::memory::TrackingAllocator;
+/// ```
+pub struct TrackingAllocator;
+
+fn record(delta: isize) {
+    // Does nothing while the thread's locals are being torn down
+    let _ = CURRENT.try_with(|current| {
+        let now = current.get() + delta;
+        current.set(now);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(now)));
+    });
+}
+
+// SAFETY: every call is forwarded to `System` unchanged; the counting
+// around it neither allocates nor touches the memory.
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: forwarded with the caller's layout
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: forwarded with the caller's layout
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` was allocated by `System` with `layout`
+        unsafe { System.dealloc(ptr, layout) };
+        record(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: `ptr` was allocated by `System` with `layout`
+        let new = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new.is_null() {
+            record(new_size as isize - layout.size() as isize);
+        }
+        new
+    }
+}
+
+/// Whether [`TrackingAllocator`] is counting this program's allocations
+pub fn is_tracking() -> bool {
+    let before = CURRENT.with(Cell::get);
+    let probe = std::hint::black_box(Vec::<u8>::with_capacity(64));
+    let tracking = CURRENT.with(Cell::get) != before;
+    drop(probe);
+    tracking
+}
+
+/// Run `f`, returning its result and the most bytes this thread held
+/// during it above what it held at the start. Calls may nest.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = CURRENT.with(Cell::get);
+    let outer_peak = PEAK.with(|peak| peak.replace(start));
+    let out = f();
+    let peak = PEAK.with(|peak| {
+        let inner = peak.get();
+        peak.set(outer_peak.max(inner));
+        inner
+    });
+    (out, (peak - start).max(0) as usize)
+}
+
+/// Memory used by one rule or fix on a page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepMemory {
+    /// The rule's label, as diffs show it, or the fix's ID
+    pub step: String,
+    /// Most bytes held during the step above what was held before it
+    pub peak_bytes: usize,
+    /// Size of the text the step produced
+    pub output_bytes: usize,
+}
+
+/// Memory used transforming one page
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryProfile {
+    /// Most bytes held while the page was transformed, above what was held
+    /// before
+    pub peak_bytes: usize,
+    /// Size of the largest text any step produced
+    pub largest_text_bytes: usize,
+    /// Each rule and fix that ran, in order
+    pub steps: Vec<StepMemory>,
+    /// Whether allocations were counted; without [`TrackingAllocator`] the
+    /// peaks are zero
+    #[serde(default)]
+    pub tracked: bool,
+}
+
+impl MemoryProfile {
+    /// Run `f` as the step named `step`, which returns what it produced and
+    /// its size
+    pub(crate) fn step<T>(&mut self, step: &str, f: impl FnOnce() -> (T, usize)) -> T {
+        let ((out, output_bytes), peak_bytes) = measure(f);
+        self.largest_text_bytes = self.largest_text_bytes.max(output_bytes);
+        self.steps.push(StepMemory {
+            step: step.to_string(),
+            peak_bytes,
+            output_bytes,
+        });
+        out
+    }
+
+    /// The step that held the most memory
+    pub fn worst_step(&self) -> Option<&StepMemory> {
+        self.steps.iter().max_by_key(|step| step.peak_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_without_tracking_allocator() {
+        // Tests run on the system allocator, so nothing is counted
+        assert!(!is_tracking());
+        let (len, peak) = measure(|| vec![0u8; 1 << 20].len());
+        assert_eq!(len, 1 << 20);
+        assert_eq!(peak, 0);
+    }
+
+    #[test]
+    fn test_nested_measure_keeps_outer_peak() {
+        record(1000);
+        let ((_, inner), outer) = measure(|| {
+            record(500);
+            record(-500);
+            measure(|| {
+                record(200);
+                record(-200);
+            })
+        });
+        record(-1000);
+        assert_eq!(inner, 200);
+        assert_eq!(outer, 500);
+    }
+
+    #[test]
+    fn test_profile_steps() {
+        let mut profile = MemoryProfile::default();
+        let text = profile.step("grow", || {
+            record(300);
+            record(-300);
+            let text = "x".repeat(40);
+            let len = text.len();
+            (text, len)
+        });
+        profile.step("shrink", || ((), 10));
+        assert_eq!(text.len(), 40);
+        assert_eq!(profile.largest_text_bytes, 40);
+        assert_eq!(profile.steps.len(), 2);
+        assert_eq!(profile.worst_step().unwrap().step, "grow");
+        assert_eq!(profile.worst_step().unwrap().peak_bytes, 300);
+    }
+}
@@ -0,0 +1,299 @@
+//! Line-level three-way merge for edit conflicts.
+//!
+//! Given the text an edit was based on (`base`), our edited text (`ours`) and
+//! the text someone else saved in the meantime (`theirs`), changes that touch
+//! different lines are combined. Changes to overlapping lines, or insertions
+//! at the same position, are conflicts, rendered with conflict markers or resolved by a
+//! [`MergePolicy`].
+
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+const MARKER_OURS: &str = "<<<<<<< ours\n";
+const MARKER_SEPARATOR: &str = "=======\n";
+const MARKER_THEIRS: &str = ">>>>>>> theirs\n";
+
+/// How to resolve conflicting hunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MergePolicy {
+    /// Keep the other editor's version of conflicting lines.
+    PreferTheirs,
+    /// Keep our version of conflicting lines.
+    PreferOurs,
+    /// Give up on the merge if any lines conflict.
+    #[default]
+    SkipOnConflict,
+}
+
+/// A run of merged output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeChunk {
+    /// Lines both sides agree on, or changed by only one side.
+    Resolved(String),
+    /// Lines changed differently by both sides.
+    Conflict { ours: String, theirs: String },
+}
+
+/// Result of a three-way merge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    pub chunks: Vec<MergeChunk>,
+}
+
+impl Merge {
+    /// Number of conflicting hunks.
+    pub fn conflict_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|c| matches!(c, MergeChunk::Conflict { .. }))
+            .count()
+    }
+
+    /// Whether the merge has no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflict_count() == 0
+    }
+
+    /// The merged text, with conflicts between `<<<<<<< ours`, `=======`
+    /// and `>>>>>>> theirs` marker lines.
+    pub fn with_markers(&self) -> String {
+        let mut out = String::new();
+        for chunk in &self.chunks {
+            match chunk {
+                MergeChunk::Resolved(text) => out.push_str(text),
+                MergeChunk::Conflict { ours, theirs } => {
+                    out.push_str(MARKER_OURS);
+                    push_line_block(&mut out, ours);
+                    out.push_str(MARKER_SEPARATOR);
+                    push_line_block(&mut out, theirs);
+                    out.push_str(MARKER_THEIRS);
+                }
+            }
+        }
+        out
+    }
+
+    /// The merged text with conflicts resolved by `policy`, or `None` under
+    /// [`MergePolicy::SkipOnConflict`] if there are conflicts.
+    pub fn resolve(&self, policy: MergePolicy) -> Option<String> {
+        let mut out = String::new();
+        for chunk in &self.chunks {
+            match chunk {
+                MergeChunk::Resolved(text) => out.push_str(text),
+                MergeChunk::Conflict { ours, theirs } => match policy {
+                    MergePolicy::PreferOurs => out.push_str(ours),
+                    MergePolicy::PreferTheirs => out.push_str(theirs),
+                    MergePolicy::SkipOnConflict => return None,
+                },
+            }
+        }
+        Some(out)
+    }
+}
+
+/// Push a block of lines, terminating it so a following marker starts a line.
+fn push_line_block(out: &mut String, block: &str) {
+    out.push_str(block);
+    if !block.is_empty() && !block.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// A change to `base[start..end]`, replacing it with `lines`.
+#[derive(Debug)]
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+fn hunks<'a>(base: &[&'a str], other: &[&'a str]) -> Vec<Hunk<'a>> {
+    capture_diff_slices(Algorithm::Myers, base, other)
+        .into_iter()
+        .filter_map(|op| match op {
+            DiffOp::Equal { .. } => None,
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => Some(Hunk {
+                start: old_index,
+                end: old_index + old_len,
+                lines: Vec::new(),
+            }),
+            DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } => Some(Hunk {
+                start: old_index,
+                end: old_index,
+                lines: other[new_index..new_index + new_len].to_vec(),
+            }),
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => Some(Hunk {
+                start: old_index,
+                end: old_index + old_len,
+                lines: other[new_index..new_index + new_len].to_vec(),
+            }),
+        })
+        .collect()
+}
+
+/// Whether a hunk at or after the start of the range `start..end` touches
+/// it: overlapping lines, or an insertion at the same position.
+fn touches(hunk: &Hunk<'_>, start: usize, end: usize) -> bool {
+    hunk.start < end || hunk.start == start
+}
+
+/// One side's version of `base[start..end]`, given its hunks inside that range.
+fn render(base: &[&str], start: usize, end: usize, hunks: &[Hunk<'_>]) -> String {
+    let mut out = String::new();
+    let mut pos = start;
+    for hunk in hunks {
+        out.extend(base[pos..hunk.start].iter().copied());
+        out.extend(hunk.lines.iter().copied());
+        pos = hunk.end;
+    }
+    out.extend(base[pos..end].iter().copied());
+    out
+}
+
+/// Three-way merge `ours` and `theirs`, both derived from `base`, line by line.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Merge {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let ours_hunks = hunks(&base_lines, &ours_lines);
+    let theirs_hunks = hunks(&base_lines, &theirs_lines);
+
+    let mut chunks = Vec::new();
+    let mut resolved = String::new();
+    let mut pos = 0;
+    let (mut oi, mut ti) = (0, 0);
+
+    while oi < ours_hunks.len() || ti < theirs_hunks.len() {
+        // Start a cluster at the earliest remaining hunk
+        let ours_first = match (ours_hunks.get(oi), theirs_hunks.get(ti)) {
+            (Some(o), Some(t)) => o.start <= t.start,
+            (Some(_), None) => true,
+            _ => false,
+        };
+        let (start, mut end) = if ours_first {
+            (ours_hunks[oi].start, ours_hunks[oi].end)
+        } else {
+            (theirs_hunks[ti].start, theirs_hunks[ti].end)
+        };
+        let (o_begin, t_begin) = (oi, ti);
+        if ours_first {
+            oi += 1;
+        } else {
+            ti += 1;
+        }
+
+        // Grow the cluster while further hunks from either side touch it
+        loop {
+            if let Some(h) = ours_hunks.get(oi).filter(|h| touches(h, start, end)) {
+                end = end.max(h.end);
+                oi += 1;
+            } else if let Some(h) = theirs_hunks.get(ti).filter(|h| touches(h, start, end)) {
+                end = end.max(h.end);
+                ti += 1;
+            } else {
+                break;
+            }
+        }
+
+        resolved.extend(base_lines[pos..start].iter().copied());
+        let ours_text = render(&base_lines, start, end, &ours_hunks[o_begin..oi]);
+        let theirs_text = render(&base_lines, start, end, &theirs_hunks[t_begin..ti]);
+        if oi == o_begin {
+            resolved.push_str(&theirs_text);
+        } else if ti == t_begin || ours_text == theirs_text {
+            resolved.push_str(&ours_text);
+        } else {
+            if !resolved.is_empty() {
+                chunks.push(MergeChunk::Resolved(std::mem::take(&mut resolved)));
+            }
+            chunks.push(MergeChunk::Conflict {
+                ours: ours_text,
+                theirs: theirs_text,
+            });
+        }
+        pos = end;
+    }
+
+    resolved.extend(base_lines[pos..].iter().copied());
+    if !resolved.is_empty() {
+        chunks.push(MergeChunk::Resolved(resolved));
+    }
+    Merge { chunks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_overlapping_changes_merge_cleanly() {
+        let merge = merge3("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n");
+        assert!(merge.is_clean());
+        assert_eq!(
+            merge.resolve(MergePolicy::SkipOnConflict).as_deref(),
+            Some("A\nb\nC\n")
+        );
+    }
+
+    #[test]
+    fn test_adjacent_line_changes_merge_cleanly() {
+        let merge = merge3("a\nb", "a\nB", "A\nb");
+        assert!(merge.is_clean());
+        assert_eq!(merge.with_markers(), "A\nB");
+    }
+
+    #[test]
+    fn test_identical_changes_are_not_conflicts() {
+        let merge = merge3("a\nb\n", "a\nX\n", "a\nX\n");
+        assert!(merge.is_clean());
+        assert_eq!(merge.with_markers(), "a\nX\n");
+    }
+
+    #[test]
+    fn test_conflict_markers_and_policies() {
+        let merge = merge3("a\nb\nc\n", "a\nX\nc\n", "a\nY\nc\n");
+        assert_eq!(merge.conflict_count(), 1);
+        assert_eq!(
+            merge.with_markers(),
+            "a\n<<<<<<< ours\nX\n=======\nY\n>>>>>>> theirs\nc\n"
+        );
+        assert_eq!(
+            merge.resolve(MergePolicy::PreferOurs).as_deref(),
+            Some("a\nX\nc\n")
+        );
+        assert_eq!(
+            merge.resolve(MergePolicy::PreferTheirs).as_deref(),
+            Some("a\nY\nc\n")
+        );
+        assert_eq!(merge.resolve(MergePolicy::SkipOnConflict), None);
+    }
+
+    #[test]
+    fn test_insertions_at_same_position_conflict() {
+        let merge = merge3("a\nb\n", "a\nours\nb\n", "a\ntheirs\nb\n");
+        assert_eq!(merge.conflict_count(), 1);
+        assert_eq!(
+            merge.resolve(MergePolicy::PreferTheirs).as_deref(),
+            Some("a\ntheirs\nb\n")
+        );
+    }
+
+    #[test]
+    fn test_one_sided_changes() {
+        let merge = merge3("a\nb\n", "a\nb\n", "a\nb\nc\n");
+        assert_eq!(merge.with_markers(), "a\nb\nc\n");
+        let merge = merge3("a\nb\n", "b\n", "a\nb\n");
+        assert_eq!(merge.with_markers(), "b\n");
+    }
+}
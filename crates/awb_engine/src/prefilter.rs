@@ -0,0 +1,272 @@
+//! Aho-Corasick pre-filter for plain find-and-replace rules.
+//!
+//! A profile with hundreds of plain rules would otherwise search the page
+//! once per rule, though a typical page contains the find string of only a
+//! few. [`PlainPrefilter`] finds all of them in one scan, so the engine can
+//! skip the rules whose find string the page does not contain.
+//!
+//! The scan is sound, never skipping a rule that would have matched:
+//! - It runs over each line-aligned chunk of the text, so find strings
+//!   containing a line break are not covered and always run.
+//! - Case-insensitive rules are matched ASCII-case-insensitively, so only
+//!   ASCII find strings are covered. Their `k` and `s` also match the
+//!   Kelvin sign and the long s; text with either character counts as
+//!   possibly matching every such rule.
+//! - A rule that changes the text can create a match for a later rule, so
+//!   the engine scans again after each change.
+
+use crate::rope::Rope;
+use aho_corasick::AhoCorasick;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Fewest covered rules worth a pre-filter; with fewer, searching for each
+/// find string in turn costs no more than the scan.
+pub const MIN_RULES: usize = 16;
+
+/// Characters outside ASCII that a case-insensitive `k` or `s` matches
+const NON_ASCII_FOLDS: [char; 2] = ['\u{212A}', '\u{17F}'];
+
+/// How much work a [`PlainPrefilter`] saved, over all pages an engine has
+/// transformed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefilterStats {
+    /// Texts scanned, one per page or talk page range or section
+    pub scans: u64,
+    /// Times a covered plain rule was in scope for a text
+    pub rules_checked: u64,
+    /// Of those, times the rule was skipped because the text could not
+    /// contain its find string
+    pub rules_skipped: u64,
+}
+
+impl PrefilterStats {
+    pub fn is_empty(&self) -> bool {
+        self.scans == 0
+    }
+
+    /// Share of covered rule runs skipped, from 0 to 1
+    pub fn skip_ratio(&self) -> f64 {
+        if self.rules_checked == 0 {
+            return 0.0;
+        }
+        self.rules_skipped as f64 / self.rules_checked as f64
+    }
+}
+
+/// One automaton per kind of matching, and the rule each pattern is for
+struct Automaton {
+    searcher: AhoCorasick,
+    rules: Vec<usize>,
+}
+
+impl Automaton {
+    fn build(finds: Vec<(usize, &str)>, ascii_case_insensitive: bool) -> Option<Self> {
+        if finds.is_empty() {
+            return None;
+        }
+        let (rules, patterns): (Vec<usize>, Vec<&str>) = finds.into_iter().unzip();
+        let searcher = AhoCorasick::builder()
+            .ascii_case_insensitive(ascii_case_insensitive)
+            .build(patterns)
+            .ok()?;
+        Some(Self { searcher, rules })
+    }
+
+    fn mark(&self, text: &str, possible: &mut [bool]) {
+        for found in self.searcher.find_overlapping_iter(text) {
+            possible[self.rules[found.pattern().as_usize()]] = true;
+        }
+    }
+}
+
+/// Finds which plain rules can match a text, in one scan.
+pub struct PlainPrefilter {
+    /// Rules, by index, whose matches the scan decides
+    covered: Vec<bool>,
+    exact: Option<Automaton>,
+    folded: Option<Automaton>,
+    /// Case-insensitive rules whose find string has a `k` or `s`
+    folds_beyond_ascii: Vec<usize>,
+    scans: AtomicU64,
+    rules_checked: AtomicU64,
+    rules_skipped: AtomicU64,
+}
+
+impl PlainPrefilter {
+    /// Pre-filter for `rules`, the find string and case sensitivity of each
+    /// plain rule by its index among all the engine's rules. `None` if
+    /// fewer than [`MIN_RULES`] of them can be covered.
+    pub fn new<'a>(
+        rule_count: usize,
+        rules: impl IntoIterator<Item = (usize, &'a str, bool)>,
+    ) -> Option<Self> {
+        let mut covered = vec![false; rule_count];
+        let mut exact = Vec::new();
+        let mut folded = Vec::new();
+        let mut folds_beyond_ascii = Vec::new();
+        for (index, find, case_sensitive) in rules {
+            if find.is_empty() || find.contains('\n') {
+                continue;
+            }
+            if case_sensitive {
+                exact.push((index, find));
+            } else if find.is_ascii() {
+                if find.contains(['k', 'K', 's', 'S']) {
+                    folds_beyond_ascii.push(index);
+                }
+                folded.push((index, find));
+            } else {
+                continue;
+            }
+            covered[index] = true;
+        }
+        if exact.len() + folded.len() < MIN_RULES {
+            return None;
+        }
+        Some(Self {
+            covered,
+            exact: Automaton::build(exact, false),
+            folded: Automaton::build(folded, true),
+            folds_beyond_ascii,
+            scans: AtomicU64::new(0),
+            rules_checked: AtomicU64::new(0),
+            rules_skipped: AtomicU64::new(0),
+        })
+    }
+
+    /// Which rules, by index, `text` may hold a match for. Rules the
+    /// pre-filter does not cover always may.
+    pub fn scan(&self, text: &Rope) -> Vec<bool> {
+        let mut possible: Vec<bool> = self.covered.iter().map(|covered| !covered).collect();
+        for chunk in text.chunks() {
+            for automaton in self.exact.iter().chain(&self.folded) {
+                automaton.mark(chunk, &mut possible);
+            }
+            if chunk.contains(NON_ASCII_FOLDS) {
+                for &index in &self.folds_beyond_ascii {
+                    possible[index] = true;
+                }
+            }
+        }
+        possible
+    }
+
+    /// Count a scan of a new text
+    pub fn record_scan(&self) {
+        self.scans.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether the rule at `index` may run on a text `possible` was
+    /// scanned from, counting it if the pre-filter covers it
+    pub fn check(&self, index: usize, possible: &[bool]) -> bool {
+        if !self.covered[index] {
+            return true;
+        }
+        self.rules_checked.fetch_add(1, Ordering::Relaxed);
+        if !possible[index] {
+            self.rules_skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        possible[index]
+    }
+
+    pub fn stats(&self) -> PrefilterStats {
+        PrefilterStats {
+            scans: self.scans.load(Ordering::Relaxed),
+            rules_checked: self.rules_checked.load(Ordering::Relaxed),
+            rules_skipped: self.rules_skipped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 16] = [
+        "teh",
+        "adn",
+        "hte",
+        "wiht",
+        "taht",
+        "becuase",
+        "recieve",
+        "occured",
+        "untill",
+        "wich",
+        "thier",
+        "beleive",
+        "freind",
+        "goverment",
+        "tommorow",
+        "wierd",
+    ];
+
+    fn prefilter(extra: &[(&str, bool)]) -> PlainPrefilter {
+        let rules: Vec<(&str, bool)> = WORDS
+            .iter()
+            .map(|word| (*word, true))
+            .chain(extra.iter().copied())
+            .collect();
+        PlainPrefilter::new(
+            rules.len(),
+            rules
+                .iter()
+                .enumerate()
+                .map(|(i, (find, case_sensitive))| (i, *find, *case_sensitive)),
+        )
+        .unwrap()
+    }
+
+    fn possible_words(prefilter: &PlainPrefilter, text: &str) -> Vec<usize> {
+        let possible = prefilter.scan(&Rope::from(text));
+        (0..possible.len()).filter(|&i| possible[i]).collect()
+    }
+
+    #[test]
+    fn test_too_few_rules_build_no_prefilter() {
+        let rules = [(0, "teh", true), (1, "adn", true)];
+        assert!(PlainPrefilter::new(2, rules).is_none());
+    }
+
+    #[test]
+    fn test_scan_finds_overlapping_and_case_insensitive_finds() {
+        // 16: case-insensitive; 17: contains a line break, never covered
+        let prefilter = prefilter(&[("Colour", false), ("a\nb", true)]);
+        assert_eq!(
+            possible_words(&prefilter, "wiht teh COLOUR\nrecieved"),
+            vec![0, 3, 6, 16, 17]
+        );
+        // "hte" overlaps "taht" and "teh"
+        assert_eq!(possible_words(&prefilter, "tahteh"), vec![0, 2, 4, 17]);
+    }
+
+    #[test]
+    fn test_non_ascii_folds_count_as_possible() {
+        let prefilter = prefilter(&[("kilo", false), ("ẞtraße", false)]);
+        // The non-ASCII find string is not covered
+        assert_eq!(possible_words(&prefilter, "nothing"), vec![17]);
+        // A Kelvin sign may spell "kilo"
+        assert_eq!(possible_words(&prefilter, "\u{212A}ILO"), vec![16, 17]);
+    }
+
+    #[test]
+    fn test_check_counts_skips() {
+        let prefilter = prefilter(&[]);
+        prefilter.record_scan();
+        let possible = prefilter.scan(&Rope::from("teh end"));
+        let ran: Vec<bool> = (0..WORDS.len())
+            .map(|i| prefilter.check(i, &possible))
+            .collect();
+        assert_eq!(ran.iter().filter(|ran| **ran).count(), 1);
+        assert_eq!(
+            prefilter.stats(),
+            PrefilterStats {
+                scans: 1,
+                rules_checked: 16,
+                rules_skipped: 15,
+            }
+        );
+        assert!((prefilter.stats().skip_ratio() - 15.0 / 16.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,230 @@
+//! Which rule or fix produced each change in an edit.
+//!
+//! A [`ChangeMap`] follows the text line by line as rules and fixes rewrite
+//! it, remembering for each line (and for each gap left by deleted lines)
+//! the steps that touched it. Once the edit is planned,
+//! [`annotate`](ChangeMap::annotate) copies those origins onto the diff, so
+//! a reviewer can see which of several overlapping rules made a change.
+
+use crate::rope::Rope;
+use awb_domain::diff::DiffOp;
+use similar::{ChangeTag, TextDiff};
+
+/// Origins of the lines of a text being transformed
+#[derive(Debug, Clone, Default)]
+pub struct ChangeMap {
+    /// Steps that changed each line of the current text
+    lines: Vec<Vec<String>>,
+    /// Steps that deleted lines just before each line of the current text;
+    /// the last entry is for deletions at the end
+    gaps: Vec<Vec<String>>,
+}
+
+impl ChangeMap {
+    /// A map for `text` before any step has run
+    pub fn new(text: &str) -> Self {
+        let count = text.split_inclusive('\n').count();
+        Self {
+            lines: vec![Vec::new(); count],
+            gaps: vec![Vec::new(); count + 1],
+        }
+    }
+
+    /// Record that the step `origin` turned `before` (the current text) into
+    /// `after`. A line that is rewritten keeps the origins it already had.
+    pub fn record(&mut self, before: &str, after: &str, origin: &str) {
+        self.record_lines(0, before, after, origin);
+    }
+
+    /// [`record`](Self::record) for two versions of a [`Rope`], diffing
+    /// only the chunks they do not share
+    pub fn record_rope(&mut self, before: &Rope, after: &Rope, origin: &str) {
+        let (first_line, before, after) = before.changed_lines(after);
+        self.record_lines(first_line, &before, &after, origin);
+    }
+
+    /// Record that `origin` replaced the lines of the current text starting
+    /// at `first_line` whose text is `before` with `after`
+    fn record_lines(&mut self, first_line: usize, before: &str, after: &str, origin: &str) {
+        let count = before.split_inclusive('\n').count();
+        let end = (first_line + count).min(self.lines.len());
+        let first_line = first_line.min(end);
+        let mut old_lines = self
+            .lines
+            .drain(first_line..end)
+            .collect::<Vec<_>>()
+            .into_iter();
+        let gaps_end = (end + 1).min(self.gaps.len());
+        let mut old_gaps = self
+            .gaps
+            .drain(first_line.min(gaps_end)..gaps_end)
+            .collect::<Vec<_>>()
+            .into_iter();
+        let mut lines = Vec::new();
+        let mut gaps = Vec::new();
+        let mut pending = Vec::new();
+
+        for change in TextDiff::from_lines(before, after).iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Equal => {
+                    merge(&mut pending, old_gaps.next().unwrap_or_default());
+                    gaps.push(std::mem::take(&mut pending));
+                    lines.push(old_lines.next().unwrap_or_default());
+                }
+                ChangeTag::Delete => {
+                    merge(&mut pending, old_gaps.next().unwrap_or_default());
+                    merge(&mut pending, old_lines.next().unwrap_or_default());
+                    merge(&mut pending, vec![origin.to_string()]);
+                }
+                ChangeTag::Insert => {
+                    let mut line = std::mem::take(&mut pending);
+                    merge(&mut line, vec![origin.to_string()]);
+                    gaps.push(Vec::new());
+                    lines.push(line);
+                }
+            }
+        }
+        merge(&mut pending, old_gaps.flatten().collect());
+        gaps.push(pending);
+
+        self.lines.splice(first_line..first_line, lines);
+        self.gaps.splice(first_line..first_line, gaps);
+    }
+
+    /// Turn a map of a masked text into a map of the text once unmasked,
+    /// where masked line `i` becomes `line_counts[i]` lines. Each of those
+    /// lines gets the origins of the masked line. Returns `None` if
+    /// `line_counts` does not cover the masked text.
+    pub(crate) fn unmasked(&self, line_counts: &[usize]) -> Option<Self> {
+        if line_counts.len() != self.lines.len() {
+            return None;
+        }
+        let mut map = Self::default();
+        for ((line, gap), &count) in self.lines.iter().zip(&self.gaps).zip(line_counts) {
+            map.gaps.push(gap.clone());
+            map.lines.push(line.clone());
+            for _ in 1..count {
+                map.gaps.push(Vec::new());
+                map.lines.push(line.clone());
+            }
+        }
+        map.gaps.push(self.gaps.last().cloned().unwrap_or_default());
+        Some(map)
+    }
+
+    /// Set the origin of each change in `ops`, a diff from the text the map
+    /// was created for to the final text. Each run of adjacent changes gets
+    /// the origins of all the lines and gaps it covers, in the order the
+    /// steps ran, joined with `", "`.
+    pub fn annotate(&self, ops: &mut [DiffOp]) {
+        let mut line = 0;
+        let mut i = 0;
+        while i < ops.len() {
+            if let DiffOp::Equal { text, .. } = &ops[i] {
+                line += text.split_inclusive('\n').count();
+                i += 1;
+                continue;
+            }
+
+            let run_start = i;
+            let first_line = line;
+            while i < ops.len() && !matches!(ops[i], DiffOp::Equal { .. }) {
+                line += match &ops[i] {
+                    DiffOp::Insert { text, .. } => text.split_inclusive('\n').count(),
+                    DiffOp::Replace { new_text, .. } => new_text.split_inclusive('\n').count(),
+                    _ => 0,
+                };
+                i += 1;
+            }
+
+            let mut origins = Vec::new();
+            for n in first_line..=line {
+                if let Some(gap) = self.gaps.get(n) {
+                    merge(&mut origins, gap.clone());
+                }
+                if n < line {
+                    if let Some(changed) = self.lines.get(n) {
+                        merge(&mut origins, changed.clone());
+                    }
+                }
+            }
+            let origin = (!origins.is_empty()).then(|| origins.join(", "));
+            for op in &mut ops[run_start..i] {
+                match op {
+                    DiffOp::Insert { origin: o, .. }
+                    | DiffOp::Delete { origin: o, .. }
+                    | DiffOp::Replace { origin: o, .. } => o.clone_from(&origin),
+                    DiffOp::Equal { .. } => {}
+                }
+            }
+        }
+    }
+}
+
+/// Append the origins in `from` that `into` does not have yet
+fn merge(into: &mut Vec<String>, from: Vec<String>) {
+    for origin in from {
+        if !into.contains(&origin) {
+            into.push(origin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_engine::compute_diff;
+
+    fn origins(ops: &[DiffOp]) -> Vec<(&str, Option<&str>)> {
+        ops.iter()
+            .filter_map(|op| match op {
+                DiffOp::Equal { .. } => None,
+                DiffOp::Insert { text, .. } | DiffOp::Delete { text, .. } => {
+                    Some((text.as_str(), op.origin()))
+                }
+                DiffOp::Replace { new_text, .. } => Some((new_text.as_str(), op.origin())),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_changes_keep_their_origin_across_steps() {
+        let original = "a\nb\nc\nd\n";
+        let mut map = ChangeMap::new(original);
+        let step1 = "A\nb\nc\nd\n";
+        map.record(original, step1, "upper a");
+        let step2 = "A\nb\nd\ne\n";
+        map.record(step1, step2, "drop c");
+        let final_text = "A!\nb\nd\ne\n";
+        map.record(step2, final_text, "exclaim");
+
+        let mut ops = compute_diff(original, final_text);
+        map.annotate(&mut ops);
+        assert_eq!(
+            origins(&ops),
+            vec![
+                ("a\n", Some("upper a, exclaim")),
+                ("A!\n", Some("upper a, exclaim")),
+                ("c\n", Some("drop c")),
+                ("e\n", Some("drop c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deletion_at_end() {
+        let mut map = ChangeMap::new("a\nb");
+        map.record("a\nb", "a\n", "trim");
+        let mut ops = compute_diff("a\nb", "a\n");
+        map.annotate(&mut ops);
+        assert_eq!(origins(&ops), vec![("b", Some("trim"))]);
+    }
+
+    #[test]
+    fn test_unrecorded_changes_have_no_origin() {
+        let map = ChangeMap::new("a\n");
+        let mut ops = compute_diff("a\n", "b\n");
+        map.annotate(&mut ops);
+        assert!(ops.iter().all(|op| op.origin().is_none()));
+    }
+}
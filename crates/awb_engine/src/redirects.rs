@@ -0,0 +1,361 @@
+//! Redirect pages: reading `#REDIRECT [[Target]]`, following chains of
+//! redirects to the page they end at, and rewriting double redirects and
+//! links so they point there directly.
+
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Where a redirect points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectTarget {
+    pub title: String,
+    /// Section anchor after `#`, if any
+    pub fragment: Option<String>,
+}
+
+impl RedirectTarget {
+    pub fn new(title: impl Into<String>, fragment: Option<String>) -> Self {
+        Self {
+            title: title.into(),
+            fragment: fragment.filter(|f| !f.is_empty()),
+        }
+    }
+
+    /// Link text for this target, e.g. `Foo#History`
+    pub fn link(&self) -> String {
+        match &self.fragment {
+            Some(fragment) => format!("{}#{}", self.title, fragment),
+            None => self.title.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RedirectError {
+    #[error("redirect loop: {}", chain.join(" → "))]
+    Loop { chain: Vec<String> },
+}
+
+/// Normalize a title for comparison: underscores as spaces, runs of
+/// whitespace collapsed and the first letter uppercased
+pub fn normalize_title(title: &str) -> String {
+    let title = title.replace('_', " ");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Byte range of the link target inside `[[...]]` of a redirect, and the
+/// target itself
+fn redirect_link(wikitext: &str) -> Option<(std::ops::Range<usize>, RedirectTarget)> {
+    let start = wikitext.len() - wikitext.trim_start().len();
+    let rest = &wikitext[start..];
+    let keyword = rest.get(..9)?;
+    if !keyword.eq_ignore_ascii_case("#REDIRECT") {
+        return None;
+    }
+    let after = &rest[9..];
+    let open = after.find("[[")?;
+    // Only whitespace and an optional colon may come between
+    if !after[..open].chars().all(|c| c.is_whitespace() || c == ':') {
+        return None;
+    }
+    let body_start = start + 9 + open + 2;
+    let body_len = wikitext[body_start..].find("]]")?;
+    let body = &wikitext[body_start..body_start + body_len];
+    let target_len = body.find('|').unwrap_or(body.len());
+    let (title, fragment) = split_fragment(&body[..target_len]);
+    if title.is_empty() {
+        return None;
+    }
+    Some((
+        body_start..body_start + target_len,
+        RedirectTarget::new(title, fragment),
+    ))
+}
+
+fn split_fragment(link: &str) -> (String, Option<String>) {
+    match link.split_once('#') {
+        Some((title, fragment)) => (normalize_title(title), Some(fragment.trim().to_string())),
+        None => (normalize_title(link), None),
+    }
+}
+
+/// Target of a redirect page, or `None` if `wikitext` is not a redirect
+pub fn parse_redirect(wikitext: &str) -> Option<RedirectTarget> {
+    redirect_link(wikitext).map(|(_, target)| target)
+}
+
+/// Point a redirect page at `target`, keeping everything else on the page
+/// (categories, `{{R from ...}}` templates). `None` if `wikitext` is not a
+/// redirect.
+pub fn retarget_redirect(wikitext: &str, target: &RedirectTarget) -> Option<String> {
+    let (range, _) = redirect_link(wikitext)?;
+    let mut text = wikitext.to_string();
+    text.replace_range(range, &target.link());
+    Some(text)
+}
+
+/// Known redirects, by normalized source title
+#[derive(Debug, Clone, Default)]
+pub struct RedirectMap {
+    targets: HashMap<String, RedirectTarget>,
+}
+
+impl RedirectMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, from: &str, target: RedirectTarget) {
+        self.targets.insert(normalize_title(from), target);
+    }
+
+    pub fn get(&self, title: &str) -> Option<&RedirectTarget> {
+        self.targets.get(&normalize_title(title))
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Follow redirects from `title` to the page they end at. `Ok(None)` if
+    /// `title` is not a redirect.
+    ///
+    /// The first redirect's own section anchor is kept; without one, the
+    /// anchor of the last redirect in the chain that has one is used.
+    pub fn resolve(&self, title: &str) -> Result<Option<RedirectTarget>, RedirectError> {
+        let Some(first) = self.get(title) else {
+            return Ok(None);
+        };
+        let mut chain = vec![normalize_title(title)];
+        let mut current = first;
+        let mut fragment = first.fragment.clone();
+        loop {
+            let next = normalize_title(&current.title);
+            if chain.contains(&next) {
+                chain.push(next);
+                return Err(RedirectError::Loop { chain });
+            }
+            chain.push(next);
+            match self.get(&current.title) {
+                Some(hop) => {
+                    if first.fragment.is_none() && hop.fragment.is_some() {
+                        fragment = hop.fragment.clone();
+                    }
+                    current = hop;
+                }
+                None => return Ok(Some(RedirectTarget::new(&current.title, fragment))),
+            }
+        }
+    }
+}
+
+/// New text for a double redirect page `title`, pointing straight at the
+/// end of its chain. `Ok(None)` if the page is not a redirect or already
+/// points at a page that is not a redirect.
+///
+/// `map` must hold the chain starting at the page's current target.
+pub fn fix_double_redirect(
+    title: &str,
+    wikitext: &str,
+    map: &RedirectMap,
+) -> Result<Option<String>, RedirectError> {
+    let Some(current) = parse_redirect(wikitext) else {
+        return Ok(None);
+    };
+    let mut map = map.clone();
+    map.insert(title, current.clone());
+    match map.resolve(title)? {
+        Some(end) if end != current => Ok(retarget_redirect(wikitext, &end)),
+        _ => Ok(None),
+    }
+}
+
+/// Namespaces whose links do something other than link: categorize or
+/// embed a file
+const NON_LINK_PREFIXES: &[&str] = &["category", "file", "image", "media"];
+
+/// One `[[target|label]]` link
+struct Link<'a> {
+    range: std::ops::Range<usize>,
+    target: &'a str,
+    label: Option<&'a str>,
+}
+
+fn plain_links(wikitext: &str) -> Vec<Link<'_>> {
+    let mut links = Vec::new();
+    let mut pos = 0;
+    while let Some(open) = wikitext[pos..].find("[[") {
+        let body_start = pos + open + 2;
+        let Some(close) = wikitext[body_start..].find("]]") else {
+            break;
+        };
+        let body = &wikitext[body_start..body_start + close];
+        pos = body_start;
+        if body.contains('[') || body.contains('\n') || body.contains('{') {
+            continue;
+        }
+        let (target, label) = match body.split_once('|') {
+            Some((target, label)) => (target, Some(label)),
+            None => (body, None),
+        };
+        let is_special = target.trim().starts_with(':')
+            || target.split_once(':').is_some_and(|(prefix, _)| {
+                NON_LINK_PREFIXES
+                    .iter()
+                    .any(|p| prefix.trim().eq_ignore_ascii_case(p))
+            });
+        if target.trim().is_empty() || target.trim().starts_with('#') || is_special {
+            continue;
+        }
+        links.push(Link {
+            range: body_start - 2..body_start + close + 2,
+            target,
+            label,
+        });
+        pos = body_start + close + 2;
+    }
+    links
+}
+
+/// Pages linked from `wikitext`, normalized and without duplicates, in
+/// order of first appearance. Category, file and leading-colon links are
+/// left out.
+pub fn link_targets(wikitext: &str) -> Vec<String> {
+    let mut targets: Vec<String> = Vec::new();
+    for link in plain_links(wikitext) {
+        let (title, _) = split_fragment(link.target);
+        if !targets.contains(&title) {
+            targets.push(title);
+        }
+    }
+    targets
+}
+
+/// Rewrite links to redirects in `map` so they point at the page the
+/// redirects end at, keeping the text readers see. Links into redirect
+/// loops are left alone. Returns the new text and the number of links
+/// changed.
+pub fn bypass_redirects(wikitext: &str, map: &RedirectMap) -> (String, usize) {
+    let mut out = String::with_capacity(wikitext.len());
+    let mut last = 0;
+    let mut changed = 0;
+    for link in plain_links(wikitext) {
+        let (title, fragment) = split_fragment(link.target);
+        let Ok(Some(end)) = map.resolve(&title) else {
+            continue;
+        };
+        let end = RedirectTarget::new(end.title, fragment.or(end.fragment));
+        let label = link.label.unwrap_or(link.target.trim());
+        out.push_str(&wikitext[last..link.range.start]);
+        out.push_str(&format!("[[{}|{}]]", end.link(), label));
+        last = link.range.end;
+        changed += 1;
+    }
+    out.push_str(&wikitext[last..]);
+    (out, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(hops: &[(&str, &str)]) -> RedirectMap {
+        let mut map = RedirectMap::new();
+        for (from, to) in hops {
+            let (title, fragment) = split_fragment(to);
+            map.insert(from, RedirectTarget::new(title, fragment));
+        }
+        map
+    }
+
+    #[test]
+    fn test_parse_redirect() {
+        assert_eq!(
+            parse_redirect("#REDIRECT [[foo_bar#History]]\n{{R from move}}"),
+            Some(RedirectTarget::new("Foo bar", Some("History".to_string())))
+        );
+        assert_eq!(
+            parse_redirect("  #redirect: [[Foo]]").map(|t| t.title),
+            Some("Foo".to_string())
+        );
+        assert_eq!(parse_redirect("See [[Foo]]"), None);
+        assert_eq!(parse_redirect("#REDIRECT see [[Foo]]"), None);
+    }
+
+    #[test]
+    fn test_retarget_keeps_rest_of_page() {
+        let text = "#REDIRECT [[B]]\n\n{{R from move}}\n[[Category:X]]";
+        assert_eq!(
+            retarget_redirect(text, &RedirectTarget::new("C", Some("Top".into()))).unwrap(),
+            "#REDIRECT [[C#Top]]\n\n{{R from move}}\n[[Category:X]]"
+        );
+        assert!(retarget_redirect("Foo", &RedirectTarget::new("C", None)).is_none());
+    }
+
+    #[test]
+    fn test_resolve_chains_and_fragments() {
+        let map = map(&[("A", "B"), ("B", "C#Early life"), ("D", "B#Career")]);
+        assert_eq!(
+            map.resolve("a").unwrap(),
+            Some(RedirectTarget::new("C", Some("Early life".into())))
+        );
+        assert_eq!(
+            map.resolve("D").unwrap(),
+            Some(RedirectTarget::new("C", Some("Career".into())))
+        );
+        assert_eq!(map.resolve("C").unwrap(), None);
+    }
+
+    #[test]
+    fn test_resolve_detects_loops() {
+        let map = map(&[("A", "B"), ("B", "C"), ("C", "A"), ("Self", "Self")]);
+        assert_eq!(
+            map.resolve("A"),
+            Err(RedirectError::Loop {
+                chain: vec!["A".into(), "B".into(), "C".into(), "A".into()]
+            })
+        );
+        assert!(map.resolve("Self").is_err());
+    }
+
+    #[test]
+    fn test_fix_double_redirect() {
+        let map = map(&[("B", "C")]);
+        assert_eq!(
+            fix_double_redirect("A", "#REDIRECT [[B]]", &map).unwrap(),
+            Some("#REDIRECT [[C]]".to_string())
+        );
+        assert_eq!(
+            fix_double_redirect("A", "#REDIRECT [[C]]", &map).unwrap(),
+            None
+        );
+        assert!(fix_double_redirect("C", "#REDIRECT [[B]]", &map).is_err());
+    }
+
+    #[test]
+    fn test_bypass_redirects() {
+        let map = map(&[("Colour", "Color"), ("Loop", "Loop")]);
+        let text = "A [[colour]] and [[Colour#Hue|hues]], [[Loop]], \
+                    [[Category:Colour]], [[:Colour]], [[Other]].";
+        let (new, changed) = bypass_redirects(text, &map);
+        assert_eq!(changed, 2);
+        assert_eq!(
+            new,
+            "A [[Color|colour]] and [[Color#Hue|hues]], [[Loop]], \
+             [[Category:Colour]], [[:Colour]], [[Other]]."
+        );
+        assert_eq!(
+            link_targets(text),
+            vec!["Colour".to_string(), "Loop".into(), "Other".into()]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Named reference integrity.
+//!
+//! A reference defined once as `<ref name="x">...</ref>` can be reused as
+//! `<ref name="x" />`. Removing the definition while a reuse remains, or
+//! adding a reuse of a name the page never defines, leaves a "Cite error"
+//! in the article. [`broken_references`] finds the names an edit breaks;
+//! references already broken before the edit are not its doing.
+
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+/// Names of references defined and reused in a page's wikitext. A name in
+/// a group other than the default one is written `group:name`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamedReferences {
+    pub defined: BTreeSet<String>,
+    pub used: BTreeSet<String>,
+}
+
+impl NamedReferences {
+    /// Read the `<ref>` tags of `wikitext`, ignoring those in comments.
+    /// List-defined references inside `<references>` count as definitions.
+    pub fn parse(wikitext: &str) -> Self {
+        static COMMENT_RE: OnceLock<regex::Regex> = OnceLock::new();
+        static REF_RE: OnceLock<regex::Regex> = OnceLock::new();
+        let comment_re = COMMENT_RE
+            .get_or_init(|| regex::Regex::new(r"(?s)<!--.*?-->").expect("known-valid regex"));
+        let ref_re = REF_RE.get_or_init(|| {
+            regex::Regex::new(r"(?is)<ref\b([^>]*?)(?:/\s*>|>(.*?)</ref\s*>)")
+                .expect("known-valid regex")
+        });
+
+        let text = comment_re.replace_all(wikitext, "");
+        let mut refs = Self::default();
+        for caps in ref_re.captures_iter(&text) {
+            let attrs = &caps[1];
+            let Some(name) = attribute(attrs, "name") else {
+                continue;
+            };
+            let key = match attribute(attrs, "group") {
+                Some(group) => format!("{}:{}", group, name),
+                None => name,
+            };
+            match caps.get(2) {
+                Some(content) if !content.as_str().trim().is_empty() => {
+                    refs.defined.insert(key);
+                }
+                _ => {
+                    refs.used.insert(key);
+                }
+            }
+        }
+        refs
+    }
+
+    /// Names reused without a definition
+    pub fn undefined(&self) -> BTreeSet<String> {
+        self.used.difference(&self.defined).cloned().collect()
+    }
+}
+
+/// Value of the attribute `key` in a tag's attribute text, quoted or not
+fn attribute(attrs: &str, key: &str) -> Option<String> {
+    static ATTR_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let re = ATTR_RE.get_or_init(|| {
+        regex::Regex::new(r#"(?i)\b(name|group)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'/>]+))"#)
+            .expect("known-valid regex")
+    });
+    re.captures_iter(attrs)
+        .find(|caps| caps[1].eq_ignore_ascii_case(key))
+        .and_then(|caps| caps.get(2).or(caps.get(3)).or(caps.get(4)))
+        .map(|value| value.as_str().trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Reference names that have no definition after replacing `old` with
+/// `new` but had one before (or were not used), sorted
+pub fn broken_references(old: &str, new: &str) -> Vec<String> {
+    let before = NamedReferences::parse(old).undefined();
+    NamedReferences::parse(new)
+        .undefined()
+        .into_iter()
+        .filter(|name| !before.contains(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "Fact.<ref name=\"smith\">Smith 2020.</ref> More.<ref name=smith /> \
+        Other.<ref name='jones' group=\"note\">Jones.</ref><ref group=note name=jones/>\n\
+        <references>\n<ref name=\"listed\">Listed.</ref>\n</references>\nLater.<ref name=\"listed\"/>";
+
+    #[test]
+    fn test_parse_definitions_and_uses() {
+        let refs = NamedReferences::parse(PAGE);
+        assert_eq!(
+            refs.defined.iter().collect::<Vec<_>>(),
+            ["listed", "note:jones", "smith"]
+        );
+        assert_eq!(refs.used, refs.defined);
+        assert!(refs.undefined().is_empty());
+    }
+
+    #[test]
+    fn test_removed_definition_breaks_reuse() {
+        let new = PAGE.replace("<ref name=\"smith\">Smith 2020.</ref>", "");
+        assert_eq!(broken_references(PAGE, &new), vec!["smith"]);
+
+        // Commenting it out removes it just the same
+        let new = PAGE.replace(
+            "<ref name=\"listed\">Listed.</ref>",
+            "<!-- <ref name=\"listed\">Listed.</ref> -->",
+        );
+        assert_eq!(broken_references(PAGE, &new), vec!["listed"]);
+
+        // Removing every use with the definition is fine
+        let new = PAGE
+            .replace("<ref name=\"smith\">Smith 2020.</ref>", "")
+            .replace("<ref name=smith />", "");
+        assert!(broken_references(PAGE, &new).is_empty());
+    }
+
+    #[test]
+    fn test_added_use_and_existing_breakage() {
+        let new = format!("{} End.<ref name=\"nowhere\" />", PAGE);
+        assert_eq!(broken_references(PAGE, &new), vec!["nowhere"]);
+
+        // Already broken before the edit: not this edit's doing
+        let old = format!("{} End.<ref name=\"nowhere\" />", PAGE);
+        let new = old.replace("Fact.", "A fact.");
+        assert!(broken_references(&old, &new).is_empty());
+
+        // An emptied definition is no definition
+        let new = PAGE.replace("Smith 2020.", " ");
+        assert_eq!(broken_references(PAGE, &new), vec!["smith"]);
+    }
+}
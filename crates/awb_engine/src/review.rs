@@ -1,8 +1,10 @@
 use awb_domain::session::*;
 use awb_domain::types::*;
 use awb_domain::warnings::Warning;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ReviewState {
     Idle,
     LoadingList,
@@ -21,35 +23,55 @@ pub enum ReviewEvent {
     ListLoaded(Vec<Title>),
     PageFetched(PageContent),
     RulesApplied(EditPlan),
+    /// A page requested with [`ReviewSideEffect::PrefetchPage`] has been
+    /// fetched and transformed
+    PagePrepared(EditPlan),
     UserDecision(EditDecision),
     SaveComplete(EditResult),
     SaveFailed(String),
     Pause,
     Resume,
+    /// Return to the previous page in the list
+    Back,
     Stop,
 }
 
 #[derive(Debug, Clone)]
 pub enum ReviewSideEffect {
     FetchPage(Title),
+    /// Fetch and transform an upcoming page in the background, then send
+    /// [`ReviewEvent::PagePrepared`]
+    PrefetchPage(Title),
     ApplyRules(PageContent),
+    /// Check the user's `text` for `page` again, as
+    /// [`TransformEngine::revise`](crate::transform::TransformEngine::revise)
+    /// does, then send [`ReviewEvent::RulesApplied`] with the new plan
+    ReviseEdit {
+        page: PageContent,
+        text: String,
+    },
     PresentForReview(EditPlan),
     ExecuteEdit {
         title: Title,
         new_text: String,
         summary: String,
+        watchlist: Watchlist,
     },
     PersistSession,
     EmitWarning(Warning),
     ShowComplete(SessionStats),
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionStats {
     pub total: usize,
     pub saved: usize,
     pub skipped: usize,
     pub errors: usize,
+    /// Pages approved whose text matched the live revision, so nothing
+    /// was sent
+    #[serde(default)]
+    pub unchanged: usize,
     pub elapsed_secs: f64,
 }
 
@@ -58,6 +80,39 @@ pub struct ReviewStateMachine {
     pub page_list: Vec<Title>,
     pub current_index: usize,
     stats: SessionStats,
+    /// Indices of skipped pages, so going back can undo the skip count
+    skipped_pages: BTreeSet<usize>,
+    /// Indices of pages approved as null edits, so going back can undo the
+    /// unchanged count
+    unchanged_pages: BTreeSet<usize>,
+    /// Number of upcoming pages to prepare while the current one is reviewed
+    queue_depth: usize,
+    /// Indices of pages requested with `PrefetchPage` and not yet prepared
+    prefetching: BTreeSet<usize>,
+    /// Plans for upcoming pages, ready to present without fetching
+    prepared: BTreeMap<usize, EditPlan>,
+    /// Save/skip decisions made so far, in order
+    decisions: Vec<PageDecision>,
+    /// What saving a page does to the user's watchlist
+    watchlist: Watchlist,
+}
+
+/// Everything needed to resume a review where it was left off.
+///
+/// Prefetched pages are not included; they are requested again as the
+/// review advances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSnapshot {
+    pub state: ReviewState,
+    pub page_list: Vec<Title>,
+    pub current_index: usize,
+    pub stats: SessionStats,
+    #[serde(default)]
+    pub skipped_pages: BTreeSet<usize>,
+    #[serde(default)]
+    pub unchanged_pages: BTreeSet<usize>,
+    #[serde(default)]
+    pub decisions: Vec<PageDecision>,
 }
 
 impl ReviewStateMachine {
@@ -71,11 +126,44 @@ impl ReviewStateMachine {
                 saved: 0,
                 skipped: 0,
                 errors: 0,
+                unchanged: 0,
                 elapsed_secs: 0.0,
             },
+            skipped_pages: BTreeSet::new(),
+            unchanged_pages: BTreeSet::new(),
+            queue_depth: 0,
+            prefetching: BTreeSet::new(),
+            prepared: BTreeMap::new(),
+            decisions: Vec::new(),
+            watchlist: Watchlist::default(),
         }
     }
 
+    /// Prepare up to `depth` pages ahead of the one being reviewed, so
+    /// approving a page moves straight to the next without fetch latency.
+    /// A depth of 0 (the default) fetches each page only when it is reached.
+    #[must_use]
+    pub fn with_queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = depth;
+        self
+    }
+
+    /// Add saved pages to the watchlist, remove them, or leave it alone.
+    /// By default the user's preferences decide.
+    #[must_use]
+    pub fn with_watchlist(mut self, watchlist: Watchlist) -> Self {
+        self.watchlist = watchlist;
+        self
+    }
+
+    pub fn watchlist(&self) -> Watchlist {
+        self.watchlist
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth
+    }
+
     pub fn transition(&mut self, event: ReviewEvent) -> Vec<ReviewSideEffect> {
         let mut effects = Vec::new();
 
@@ -90,6 +178,7 @@ impl ReviewStateMachine {
                 if let Some(title) = self.page_list.first() {
                     self.state = ReviewState::FetchingPage { index: 0 };
                     effects.push(ReviewSideEffect::FetchPage(title.clone()));
+                    self.request_prefetch(&mut effects);
                 } else {
                     self.state = ReviewState::Completed {
                         stats: self.stats.clone(),
@@ -97,6 +186,18 @@ impl ReviewStateMachine {
                     effects.push(ReviewSideEffect::ShowComplete(self.stats.clone()));
                 }
             }
+            // A reply for a page left with Back is dropped, so it can't be
+            // presented or saved as the current page
+            (ReviewState::FetchingPage { .. }, ReviewEvent::PageFetched(page))
+                if !self.is_current(&page.title) =>
+            {
+                tracing::debug!("Ignoring stale fetch of {}", page.title);
+            }
+            (ReviewState::ApplyingRules { .. }, ReviewEvent::RulesApplied(plan))
+                if !self.is_current(&plan.page.title) =>
+            {
+                tracing::debug!("Ignoring stale plan for {}", plan.page.title);
+            }
             (ReviewState::FetchingPage { index }, ReviewEvent::PageFetched(page)) => {
                 let idx = *index;
                 self.state = ReviewState::ApplyingRules { index: idx };
@@ -108,19 +209,47 @@ impl ReviewStateMachine {
                 };
                 effects.push(ReviewSideEffect::PresentForReview(plan));
             }
+            (state, ReviewEvent::PagePrepared(plan))
+                if !matches!(
+                    state,
+                    ReviewState::Idle | ReviewState::LoadingList | ReviewState::Completed { .. }
+                ) =>
+            {
+                self.store_prepared(plan);
+            }
             (ReviewState::AwaitingDecision { plan }, ReviewEvent::UserDecision(decision)) => {
                 match decision {
+                    EditDecision::Save
+                        if crate::transform::is_null_edit(
+                            &plan.page.wikitext,
+                            &plan.new_wikitext,
+                        ) =>
+                    {
+                        // Saving would be a null edit; move on without one
+                        let page_id = plan.page.page_id;
+                        self.record(page_id, EditDecision::Skip);
+                        self.stats.unchanged += 1;
+                        self.unchanged_pages.insert(self.current_index);
+                        effects.push(ReviewSideEffect::EmitWarning(Warning::NoChange));
+                        self.advance(&mut effects);
+                    }
                     EditDecision::Save => {
                         let idx = self.current_index;
+                        let page_id = plan.page.page_id;
                         effects.push(ReviewSideEffect::ExecuteEdit {
                             title: plan.page.title.clone(),
                             new_text: plan.new_wikitext.clone(),
                             summary: plan.summary.clone(),
+                            watchlist: self.watchlist,
                         });
+                        self.record(page_id, EditDecision::Save);
                         self.state = ReviewState::Saving { index: idx };
                     }
                     EditDecision::Skip => {
+                        let page_id = plan.page.page_id;
+                        self.record(page_id, EditDecision::Skip);
                         self.stats.skipped += 1;
+                        self.skipped_pages.insert(self.current_index);
                         self.advance(&mut effects);
                     }
                     EditDecision::Pause => {
@@ -132,9 +261,16 @@ impl ReviewStateMachine {
                     EditDecision::OpenInBrowser => {
                         // UI handles this; stay in same state
                     }
-                    EditDecision::ManualEdit(_) => {
-                        self.stats.skipped += 1;
-                        self.advance(&mut effects);
+                    EditDecision::ManualEdit(text) => {
+                        // Have the engine check the user's text like any
+                        // other plan, then ask for confirmation again
+                        effects.push(ReviewSideEffect::ReviseEdit {
+                            page: plan.page.clone(),
+                            text,
+                        });
+                        self.state = ReviewState::ApplyingRules {
+                            index: self.current_index,
+                        };
                     }
                 }
             }
@@ -155,6 +291,15 @@ impl ReviewStateMachine {
             (ReviewState::Paused { .. }, ReviewEvent::Resume) => {
                 self.advance(&mut effects);
             }
+            (
+                ReviewState::FetchingPage { .. }
+                | ReviewState::ApplyingRules { .. }
+                | ReviewState::AwaitingDecision { .. }
+                | ReviewState::Error { .. },
+                ReviewEvent::Back,
+            ) if self.current_index > 0 => {
+                self.back(&mut effects);
+            }
             (_, ReviewEvent::Stop) => {
                 self.state = ReviewState::Completed {
                     stats: self.stats.clone(),
@@ -172,11 +317,21 @@ impl ReviewStateMachine {
     fn advance(&mut self, effects: &mut Vec<ReviewSideEffect>) {
         self.current_index += 1;
         if self.current_index < self.page_list.len() {
-            let title = self.page_list[self.current_index].clone();
-            self.state = ReviewState::FetchingPage {
-                index: self.current_index,
-            };
-            effects.push(ReviewSideEffect::FetchPage(title));
+            if let Some(plan) = self.prepared.remove(&self.current_index) {
+                self.state = ReviewState::AwaitingDecision {
+                    plan: Box::new(plan.clone()),
+                };
+                effects.push(ReviewSideEffect::PresentForReview(plan));
+            } else {
+                // A prefetch still in flight for this page is superseded
+                self.prefetching.remove(&self.current_index);
+                let title = self.page_list[self.current_index].clone();
+                self.state = ReviewState::FetchingPage {
+                    index: self.current_index,
+                };
+                effects.push(ReviewSideEffect::FetchPage(title));
+            }
+            self.request_prefetch(effects);
         } else {
             self.state = ReviewState::Completed {
                 stats: self.stats.clone(),
@@ -186,9 +341,152 @@ impl ReviewStateMachine {
         }
     }
 
+    /// Request prefetches for the pages within `queue_depth` of the current
+    /// one that are neither prepared nor already requested.
+    fn request_prefetch(&mut self, effects: &mut Vec<ReviewSideEffect>) {
+        let end = (self.current_index + 1 + self.queue_depth).min(self.page_list.len());
+        for index in self.current_index + 1..end {
+            if !self.prepared.contains_key(&index) && self.prefetching.insert(index) {
+                effects.push(ReviewSideEffect::PrefetchPage(
+                    self.page_list[index].clone(),
+                ));
+            }
+        }
+    }
+
+    /// Queue a prefetched plan for the requested page it belongs to.
+    fn store_prepared(&mut self, plan: EditPlan) {
+        let index = self
+            .prefetching
+            .iter()
+            .copied()
+            .find(|&i| self.page_list[i] == plan.page.title);
+        match index {
+            Some(index) => {
+                self.prefetching.remove(&index);
+                self.prepared.insert(index, plan);
+            }
+            None => tracing::debug!("Ignoring unrequested prefetch for {}", plan.page.title),
+        }
+    }
+
+    /// Go back to the previous page, discarding any unsaved plan for the
+    /// current one. A skip of the previous page, or its approval as a null
+    /// edit, is undone along with its decision; a save is not.
+    fn back(&mut self, effects: &mut Vec<ReviewSideEffect>) {
+        self.current_index -= 1;
+        let skipped = self.skipped_pages.remove(&self.current_index);
+        if skipped {
+            self.stats.skipped -= 1;
+        }
+        let unchanged = self.unchanged_pages.remove(&self.current_index);
+        if unchanged {
+            self.stats.unchanged -= 1;
+        }
+        if skipped || unchanged {
+            // Nothing was decided for a page after it, so its skip is the
+            // latest one recorded
+            if let Some(i) = self
+                .decisions
+                .iter()
+                .rposition(|d| matches!(d.decision, EditDecision::Skip))
+            {
+                self.decisions.remove(i);
+            }
+        }
+        self.state = ReviewState::FetchingPage {
+            index: self.current_index,
+        };
+        effects.push(ReviewSideEffect::FetchPage(
+            self.page_list[self.current_index].clone(),
+        ));
+    }
+
+    /// Whether `title` is the page under review
+    fn is_current(&self, title: &Title) -> bool {
+        self.page_list.get(self.current_index) == Some(title)
+    }
+
+    fn record(&mut self, page_id: PageId, decision: EditDecision) {
+        self.decisions.push(PageDecision {
+            page_id,
+            decision,
+            timestamp: chrono::Utc::now(),
+        });
+    }
+
     pub fn state(&self) -> &ReviewState {
         &self.state
     }
+
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    /// Save/skip decisions made so far, in order
+    pub fn decisions(&self) -> &[PageDecision] {
+        &self.decisions
+    }
+
+    /// Capture the review so it can be persisted and resumed later
+    pub fn snapshot(&self) -> ReviewSnapshot {
+        ReviewSnapshot {
+            state: self.state.clone(),
+            page_list: self.page_list.clone(),
+            current_index: self.current_index,
+            stats: self.stats.clone(),
+            skipped_pages: self.skipped_pages.clone(),
+            unchanged_pages: self.unchanged_pages.clone(),
+            decisions: self.decisions.clone(),
+        }
+    }
+
+    /// Rebuild a machine from a snapshot, with the side effects needed to
+    /// pick the review up again.
+    ///
+    /// A page that was being fetched, transformed or saved is fetched again,
+    /// since the outcome of that work was not captured; a page awaiting a
+    /// decision is presented again with its saved plan.
+    pub fn restore(snapshot: ReviewSnapshot) -> (Self, Vec<ReviewSideEffect>) {
+        let mut machine = Self {
+            state: snapshot.state,
+            page_list: snapshot.page_list,
+            current_index: snapshot.current_index,
+            stats: snapshot.stats,
+            skipped_pages: snapshot.skipped_pages,
+            unchanged_pages: snapshot.unchanged_pages,
+            decisions: snapshot.decisions,
+            ..Self::new()
+        };
+        let mut effects = Vec::new();
+        match &machine.state {
+            ReviewState::FetchingPage { .. }
+            | ReviewState::ApplyingRules { .. }
+            | ReviewState::Saving { .. } => match machine.page_list.get(machine.current_index) {
+                Some(title) => {
+                    effects.push(ReviewSideEffect::FetchPage(title.clone()));
+                    machine.state = ReviewState::FetchingPage {
+                        index: machine.current_index,
+                    };
+                }
+                None => {
+                    machine.state = ReviewState::Completed {
+                        stats: machine.stats.clone(),
+                    };
+                    effects.push(ReviewSideEffect::ShowComplete(machine.stats.clone()));
+                }
+            },
+            ReviewState::AwaitingDecision { plan } => {
+                effects.push(ReviewSideEffect::PresentForReview((**plan).clone()));
+            }
+            ReviewState::Idle
+            | ReviewState::LoadingList
+            | ReviewState::Paused { .. }
+            | ReviewState::Completed { .. }
+            | ReviewState::Error { .. } => {}
+        }
+        (machine, effects)
+    }
 }
 
 impl Default for ReviewStateMachine {
@@ -229,6 +527,7 @@ mod tests {
             summary: "test edit".to_string(),
             warnings: vec![],
             is_cosmetic_only: false,
+            metadata: Default::default(),
         }
     }
 
@@ -337,7 +636,33 @@ mod tests {
 
         assert!(matches!(machine.state, ReviewState::Saving { index: 0 }));
         assert_eq!(effects.len(), 1);
-        assert!(matches!(effects[0], ReviewSideEffect::ExecuteEdit { .. }));
+        assert!(matches!(
+            effects[0],
+            ReviewSideEffect::ExecuteEdit {
+                watchlist: Watchlist::Preferences,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_save_carries_watchlist_choice() {
+        let mut machine = ReviewStateMachine::new().with_watchlist(Watchlist::NoChange);
+        machine.transition(ReviewEvent::Start);
+        let title = create_test_title("Test");
+        machine.transition(ReviewEvent::ListLoaded(vec![title.clone()]));
+        let page = create_test_page(title);
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page)));
+
+        let effects = machine.transition(ReviewEvent::UserDecision(EditDecision::Save));
+        assert!(matches!(
+            effects[0],
+            ReviewSideEffect::ExecuteEdit {
+                watchlist: Watchlist::NoChange,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -559,4 +884,317 @@ mod tests {
         assert_eq!(machine.stats.saved, 2);
         assert!(matches!(machine.state, ReviewState::Completed { .. }));
     }
+
+    #[test]
+    fn test_manual_edit_is_checked_by_the_engine() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+
+        let title = create_test_title("Test");
+        machine.transition(ReviewEvent::ListLoaded(vec![title.clone()]));
+        let page = create_test_page(title);
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page)));
+
+        let effects = machine.transition(ReviewEvent::UserDecision(EditDecision::ManualEdit(
+            "test content".to_string(),
+        )));
+
+        assert_eq!(machine.stats.skipped, 0);
+        assert!(matches!(
+            machine.state,
+            ReviewState::ApplyingRules { index: 0 }
+        ));
+        assert_eq!(effects.len(), 1);
+        let ReviewSideEffect::ReviseEdit { page, text } = &effects[0] else {
+            panic!("expected ReviseEdit, got {:?}", effects[0]);
+        };
+        assert_eq!(text, "test content");
+
+        let engine = crate::transform::TransformEngine::new(
+            &awb_domain::rules::RuleSet::new(),
+            crate::general_fixes::FixRegistry::new(),
+            Default::default(),
+        )
+        .unwrap();
+        let effects =
+            machine.transition(ReviewEvent::RulesApplied(engine.revise(page, text.clone())));
+        let ReviewSideEffect::PresentForReview(plan) = &effects[0] else {
+            panic!("expected PresentForReview, got {:?}", effects[0]);
+        };
+        assert_eq!(plan.new_wikitext, "test content");
+        assert!(plan.warnings.iter().any(|w| matches!(w, Warning::NoChange)));
+
+        // The revised plan is what gets saved
+        let effects = machine.transition(ReviewEvent::UserDecision(EditDecision::ManualEdit(
+            "edited content".to_string(),
+        )));
+        let ReviewSideEffect::ReviseEdit { page, text } = &effects[0] else {
+            panic!("expected ReviseEdit, got {:?}", effects[0]);
+        };
+        machine.transition(ReviewEvent::RulesApplied(engine.revise(page, text.clone())));
+        let effects = machine.transition(ReviewEvent::UserDecision(EditDecision::Save));
+        assert!(matches!(
+            &effects[0],
+            ReviewSideEffect::ExecuteEdit { new_text, summary, .. }
+                if new_text == "edited content" && summary != "test edit"
+        ));
+    }
+
+    #[test]
+    fn test_save_of_unchanged_text_sends_no_edit() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+        let titles = vec![create_test_title("Page1"), create_test_title("Page2")];
+        machine.transition(ReviewEvent::ListLoaded(titles.clone()));
+        let page = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        let mut plan = create_test_plan(page);
+        plan.new_wikitext = "test content\n".to_string();
+        machine.transition(ReviewEvent::RulesApplied(plan));
+
+        let effects = machine.transition(ReviewEvent::UserDecision(EditDecision::Save));
+
+        assert!(
+            !effects
+                .iter()
+                .any(|e| matches!(e, ReviewSideEffect::ExecuteEdit { .. }))
+        );
+        assert!(matches!(
+            effects[0],
+            ReviewSideEffect::EmitWarning(Warning::NoChange)
+        ));
+        assert!(matches!(
+            machine.state,
+            ReviewState::FetchingPage { index: 1 }
+        ));
+        assert_eq!(machine.stats.unchanged, 1);
+        assert_eq!(machine.stats.saved, 0);
+        assert_eq!(machine.stats.skipped, 0);
+    }
+
+    #[test]
+    fn test_back_returns_to_previous_page() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+
+        let titles = vec![create_test_title("Page1"), create_test_title("Page2")];
+        machine.transition(ReviewEvent::ListLoaded(titles.clone()));
+        let page = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page)));
+        machine.transition(ReviewEvent::UserDecision(EditDecision::Skip));
+        assert_eq!(machine.stats.skipped, 1);
+
+        let page = create_test_page(titles[1].clone());
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page)));
+
+        let effects = machine.transition(ReviewEvent::Back);
+        assert_eq!(machine.current_index, 0);
+        assert_eq!(machine.stats.skipped, 0);
+        assert!(machine.decisions().is_empty());
+        assert!(matches!(
+            machine.state,
+            ReviewState::FetchingPage { index: 0 }
+        ));
+        assert!(matches!(&effects[0], ReviewSideEffect::FetchPage(t) if *t == titles[0]));
+    }
+
+    #[test]
+    fn test_back_drops_stale_replies() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+
+        let titles = vec![create_test_title("Page1"), create_test_title("Page2")];
+        machine.transition(ReviewEvent::ListLoaded(titles.clone()));
+        let page = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page)));
+        machine.transition(ReviewEvent::UserDecision(EditDecision::Skip));
+
+        // Page2 is still being fetched when the user goes back
+        machine.transition(ReviewEvent::Back);
+        let effects = machine.transition(ReviewEvent::PageFetched(create_test_page(
+            titles[1].clone(),
+        )));
+        assert!(effects.is_empty());
+        assert!(matches!(
+            machine.state,
+            ReviewState::FetchingPage { index: 0 }
+        ));
+
+        let page = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        let effects = machine.transition(ReviewEvent::RulesApplied(create_test_plan(
+            create_test_page(titles[1].clone()),
+        )));
+        assert!(effects.is_empty());
+        assert!(matches!(
+            machine.state,
+            ReviewState::ApplyingRules { index: 0 }
+        ));
+
+        let effects = machine.transition(ReviewEvent::RulesApplied(create_test_plan(page)));
+        assert!(matches!(
+            &effects[0],
+            ReviewSideEffect::PresentForReview(plan) if plan.page.title == titles[0]
+        ));
+    }
+
+    #[test]
+    fn test_back_undoes_null_edit_approval() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+        let titles = vec![create_test_title("Page1"), create_test_title("Page2")];
+        machine.transition(ReviewEvent::ListLoaded(titles.clone()));
+        let page = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        let mut plan = create_test_plan(page);
+        plan.new_wikitext = plan.page.wikitext.clone();
+        machine.transition(ReviewEvent::RulesApplied(plan.clone()));
+        machine.transition(ReviewEvent::UserDecision(EditDecision::Save));
+        assert_eq!(machine.stats.unchanged, 1);
+        assert_eq!(machine.decisions().len(), 1);
+
+        machine.transition(ReviewEvent::Back);
+        assert_eq!(machine.stats.unchanged, 0);
+        assert!(machine.decisions().is_empty());
+
+        // Approving it again counts it once
+        machine.transition(ReviewEvent::PageFetched(plan.page.clone()));
+        machine.transition(ReviewEvent::RulesApplied(plan));
+        machine.transition(ReviewEvent::UserDecision(EditDecision::Save));
+        assert_eq!(machine.stats.unchanged, 1);
+        assert_eq!(machine.decisions().len(), 1);
+    }
+
+    #[test]
+    fn test_back_ignored_on_first_page() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+        machine.transition(ReviewEvent::ListLoaded(vec![create_test_title("Page1")]));
+
+        let effects = machine.transition(ReviewEvent::Back);
+        assert!(effects.is_empty());
+        assert!(matches!(
+            machine.state,
+            ReviewState::FetchingPage { index: 0 }
+        ));
+    }
+
+    #[test]
+    fn test_prefetched_page_presented_without_fetch() {
+        let mut machine = ReviewStateMachine::new().with_queue_depth(2);
+        machine.transition(ReviewEvent::Start);
+
+        let titles = vec![
+            create_test_title("Page1"),
+            create_test_title("Page2"),
+            create_test_title("Page3"),
+            create_test_title("Page4"),
+        ];
+        let effects = machine.transition(ReviewEvent::ListLoaded(titles.clone()));
+        assert_eq!(effects.len(), 3);
+        assert!(matches!(&effects[0], ReviewSideEffect::FetchPage(t) if *t == titles[0]));
+        assert!(matches!(&effects[1], ReviewSideEffect::PrefetchPage(t) if *t == titles[1]));
+        assert!(matches!(&effects[2], ReviewSideEffect::PrefetchPage(t) if *t == titles[2]));
+
+        // Page2 is prepared while Page1 is still loading
+        let page2 = create_test_page(titles[1].clone());
+        assert!(
+            machine
+                .transition(ReviewEvent::PagePrepared(create_test_plan(page2)))
+                .is_empty()
+        );
+
+        let page1 = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page1.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page1)));
+        let effects = machine.transition(ReviewEvent::UserDecision(EditDecision::Skip));
+
+        assert!(matches!(
+            machine.state,
+            ReviewState::AwaitingDecision { .. }
+        ));
+        assert!(
+            matches!(&effects[0], ReviewSideEffect::PresentForReview(p) if p.page.title == titles[1])
+        );
+        // Page3 is still in flight, so only Page4 is newly requested
+        assert_eq!(effects.len(), 2);
+        assert!(matches!(&effects[1], ReviewSideEffect::PrefetchPage(t) if *t == titles[3]));
+    }
+
+    #[test]
+    fn test_unrequested_prefetch_ignored() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+
+        let titles = vec![create_test_title("Page1"), create_test_title("Page2")];
+        machine.transition(ReviewEvent::ListLoaded(titles.clone()));
+        let page2 = create_test_page(titles[1].clone());
+        machine.transition(ReviewEvent::PagePrepared(create_test_plan(page2)));
+
+        let page1 = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page1.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page1)));
+        let effects = machine.transition(ReviewEvent::UserDecision(EditDecision::Skip));
+
+        assert!(matches!(
+            machine.state,
+            ReviewState::FetchingPage { index: 1 }
+        ));
+        assert!(matches!(&effects[0], ReviewSideEffect::FetchPage(_)));
+    }
+
+    #[test]
+    fn test_snapshot_restore_awaiting_decision() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+
+        let titles = vec![create_test_title("Page1"), create_test_title("Page2")];
+        machine.transition(ReviewEvent::ListLoaded(titles.clone()));
+        let page1 = create_test_page(titles[0].clone());
+        machine.transition(ReviewEvent::PageFetched(page1.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page1)));
+        machine.transition(ReviewEvent::UserDecision(EditDecision::Skip));
+        let page2 = create_test_page(titles[1].clone());
+        machine.transition(ReviewEvent::PageFetched(page2.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page2)));
+
+        let json = serde_json::to_string(&machine.snapshot()).unwrap();
+        let snapshot: ReviewSnapshot = serde_json::from_str(&json).unwrap();
+        let (mut restored, effects) = ReviewStateMachine::restore(snapshot);
+
+        assert_eq!(restored.current_index, 1);
+        assert_eq!(restored.stats().skipped, 1);
+        assert_eq!(restored.decisions().len(), 1);
+        assert!(
+            matches!(&effects[..], [ReviewSideEffect::PresentForReview(p)] if p.page.title == titles[1])
+        );
+
+        // Going back still undoes the restored skip
+        restored.transition(ReviewEvent::Back);
+        assert_eq!(restored.stats().skipped, 0);
+    }
+
+    #[test]
+    fn test_restore_refetches_interrupted_page() {
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+
+        let title = create_test_title("Page1");
+        machine.transition(ReviewEvent::ListLoaded(vec![title.clone()]));
+        let page = create_test_page(title.clone());
+        machine.transition(ReviewEvent::PageFetched(page.clone()));
+        machine.transition(ReviewEvent::RulesApplied(create_test_plan(page)));
+        machine.transition(ReviewEvent::UserDecision(EditDecision::Save));
+
+        let (restored, effects) = ReviewStateMachine::restore(machine.snapshot());
+        assert!(matches!(
+            restored.state,
+            ReviewState::FetchingPage { index: 0 }
+        ));
+        assert!(matches!(&effects[..], [ReviewSideEffect::FetchPage(t)] if *t == title));
+    }
 }
@@ -0,0 +1,352 @@
+//! Page text held as shared, line-aligned chunks.
+//!
+//! The transform pipeline rewrites a page once per matching rule and fix,
+//! and most of those steps change a handful of lines. A [`Rope`] keeps the
+//! text in reference-counted chunks that end at line breaks, so a step that
+//! works line by line ([`map_lines`](Rope::map_lines)) copies only the
+//! chunks it changes, cloning a rope copies no text, and two versions of a
+//! page can be told apart without comparing them byte by byte.
+//!
+//! Steps that need the whole text at once go through
+//! [`map_whole`](Rope::map_whole), which costs no more than rewriting a
+//! `String`: a rope made from a `String` holds it as a single chunk, and is
+//! only split once a line-by-line step changes it.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Size chunks are split to, in bytes. A line longer than this is kept
+/// whole in one chunk.
+const CHUNK_SIZE: usize = 4096;
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    text: Arc<String>,
+    /// Lines in `text`, the last of which may be unterminated
+    lines: usize,
+}
+
+impl Chunk {
+    fn new(text: String) -> Self {
+        Self {
+            lines: line_count(&text),
+            text: Arc::new(text),
+        }
+    }
+}
+
+/// Text split into line-aligned chunks that clones share
+#[derive(Debug, Clone, Default)]
+pub struct Rope {
+    /// No chunk is empty, and every chunk but the last ends with `\n`
+    chunks: Vec<Chunk>,
+    len: usize,
+}
+
+impl Rope {
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of lines, counted as `str::split_inclusive('\n')` does
+    pub fn line_count(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.lines).sum()
+    }
+
+    /// The chunks, in order; each holds whole lines
+    pub fn chunks(&self) -> impl Iterator<Item = &str> {
+        self.chunks.iter().map(|chunk| chunk.text.as_str())
+    }
+
+    /// The text in one piece, borrowed if it is held in a single chunk
+    pub fn as_cow(&self) -> Cow<'_, str> {
+        match self.chunks.as_slice() {
+            [] => Cow::Borrowed(""),
+            [chunk] => Cow::Borrowed(chunk.text.as_str()),
+            chunks => Cow::Owned(chunks.iter().map(|chunk| chunk.text.as_str()).collect()),
+        }
+    }
+
+    /// The text as a `String`, without copying it if it is held in a
+    /// single chunk no other rope shares
+    pub fn into_string(mut self) -> String {
+        if self.chunks.len() == 1 {
+            let chunk = self.chunks.pop().expect("one chunk");
+            return Arc::try_unwrap(chunk.text).unwrap_or_else(|shared| shared.to_string());
+        }
+        self.as_cow().into_owned()
+    }
+
+    /// Rewrite the text with `f`, which sees it in one piece. Returns
+    /// whether the text changed.
+    pub fn map_whole(&mut self, f: impl FnOnce(&str) -> Cow<'_, str>) -> bool {
+        let text = self.as_cow();
+        let out = match f(&text) {
+            Cow::Borrowed(out) if std::ptr::eq(out, &*text) => return false,
+            Cow::Borrowed(out) => out.to_string(),
+            Cow::Owned(out) if out == *text => return false,
+            Cow::Owned(out) => out,
+        };
+        *self = Self::from(out);
+        true
+    }
+
+    /// Rewrite the text with `f` one chunk at a time. Each chunk is a run
+    /// of whole lines, so `f` must treat lines independently and keep
+    /// their line breaks, as a regex replacement that cannot match `\n` or
+    /// the start or end of the text does. Chunks `f` leaves unchanged are
+    /// kept without copying. Returns whether the text changed.
+    pub fn map_lines(&mut self, mut f: impl FnMut(&str) -> Cow<'_, str>) -> bool {
+        // Built once the first chunk changes
+        let mut rewritten: Option<Vec<Chunk>> = None;
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            let out = match f(&chunk.text) {
+                Cow::Borrowed(out) if std::ptr::eq(out, chunk.text.as_str()) => None,
+                Cow::Owned(out) if out == *chunk.text => None,
+                out => Some(out),
+            };
+            match (out, &mut rewritten) {
+                (None, Some(chunks)) => chunks.push(chunk.clone()),
+                (None, None) => {}
+                (Some(out), chunks) => {
+                    let chunks = chunks.get_or_insert_with(|| self.chunks[..i].to_vec());
+                    push_split(chunks, &out);
+                }
+            }
+        }
+        let Some(chunks) = rewritten else {
+            return false;
+        };
+        self.chunks = join_unterminated(chunks);
+        self.len = self.chunks.iter().map(|chunk| chunk.text.len()).sum();
+        true
+    }
+
+    /// Where `self` and `other`, a later version of it, differ: the line
+    /// the difference starts at, and the text of the differing lines in
+    /// each. Chunks the two share are skipped without being compared.
+    pub fn changed_lines<'a>(&'a self, other: &'a Rope) -> (usize, Cow<'a, str>, Cow<'a, str>) {
+        let shared = |a: &Chunk, b: &Chunk| Arc::ptr_eq(&a.text, &b.text);
+        let prefix = self
+            .chunks
+            .iter()
+            .zip(&other.chunks)
+            .take_while(|(a, b)| shared(a, b))
+            .count();
+        let suffix = self.chunks[prefix..]
+            .iter()
+            .rev()
+            .zip(other.chunks[prefix..].iter().rev())
+            .take_while(|(a, b)| shared(a, b))
+            .count();
+        let first_line = self.chunks[..prefix].iter().map(|chunk| chunk.lines).sum();
+        let join = |chunks: &'a [Chunk]| match chunks {
+            [chunk] => Cow::Borrowed(chunk.text.as_str()),
+            chunks => Cow::Owned(chunks.iter().map(|chunk| chunk.text.as_str()).collect()),
+        };
+        (
+            first_line,
+            join(&self.chunks[prefix..self.chunks.len() - suffix]),
+            join(&other.chunks[prefix..other.chunks.len() - suffix]),
+        )
+    }
+}
+
+impl From<String> for Rope {
+    /// Holds `text` as a single chunk, without copying it
+    fn from(text: String) -> Self {
+        if text.is_empty() {
+            return Self::default();
+        }
+        Self {
+            len: text.len(),
+            chunks: vec![Chunk::new(text)],
+        }
+    }
+}
+
+impl From<&str> for Rope {
+    /// Copies `text` into line-aligned chunks
+    fn from(text: &str) -> Self {
+        let mut chunks = Vec::new();
+        push_split(&mut chunks, text);
+        Self {
+            chunks,
+            len: text.len(),
+        }
+    }
+}
+
+impl std::fmt::Display for Rope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        let mut rest = other;
+        self.len == other.len()
+            && self.chunks().all(|chunk| match rest.strip_prefix(chunk) {
+                Some(after) => {
+                    rest = after;
+                    true
+                }
+                None => false,
+            })
+    }
+}
+
+/// Lines in `text`, as `str::split_inclusive('\n')` counts them
+fn line_count(text: &str) -> usize {
+    let breaks = text.bytes().filter(|&b| b == b'\n').count();
+    breaks + usize::from(!text.is_empty() && !text.ends_with('\n'))
+}
+
+/// Append `text` to `chunks`, split at line breaks into chunks of about
+/// [`CHUNK_SIZE`] bytes
+fn push_split(chunks: &mut Vec<Chunk>, text: &str) {
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = if rest.len() <= CHUNK_SIZE {
+            rest.len()
+        } else {
+            rest.as_bytes()[CHUNK_SIZE..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(rest.len(), |pos| CHUNK_SIZE + pos + 1)
+        };
+        chunks.push(Chunk::new(rest[..end].to_string()));
+        rest = &rest[end..];
+    }
+}
+
+/// Drop empty chunks and join a chunk that does not end with `\n` to the
+/// next, restoring the invariant after a rewrite
+fn join_unterminated(chunks: Vec<Chunk>) -> Vec<Chunk> {
+    let mut out: Vec<Chunk> = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        if chunk.text.is_empty() {
+            continue;
+        }
+        match out.last_mut() {
+            Some(last) if !last.text.ends_with('\n') => {
+                let mut text = last.text.to_string();
+                text.push_str(&chunk.text);
+                *last = Chunk::new(text);
+            }
+            _ => out.push(chunk),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// About 80 KB of numbered lines
+    fn long_text() -> String {
+        (0..4000)
+            .map(|i| format!("line {i:05} teh end\n"))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_into_line_aligned_chunks() {
+        let text = long_text();
+        let rope = Rope::from(text.as_str());
+        assert!(rope.chunks().count() > 10);
+        assert!(rope.chunks().all(|chunk| chunk.ends_with('\n')));
+        assert_eq!(rope.line_count(), 4000);
+        assert_eq!(rope.len(), text.len());
+        assert_eq!(rope.to_string(), text);
+        assert!(rope == *text.as_str());
+        assert!(rope != *"line 00000");
+    }
+
+    #[test]
+    fn test_map_lines_copies_only_changed_chunks() {
+        let mut rope = Rope::from(long_text().as_str());
+        let before = rope.clone();
+        let changed = rope.map_lines(|chunk| {
+            if chunk.contains("line 02000") {
+                Cow::Owned(chunk.replace("line 02000 teh", "line 02000 the"))
+            } else {
+                Cow::Borrowed(chunk)
+            }
+        });
+        assert!(changed);
+        assert_eq!(
+            rope.to_string(),
+            long_text().replace("line 02000 teh", "line 02000 the")
+        );
+        let shared = before
+            .chunks
+            .iter()
+            .zip(&rope.chunks)
+            .filter(|(a, b)| Arc::ptr_eq(&a.text, &b.text))
+            .count();
+        assert_eq!(shared, before.chunks.len() - 1);
+
+        let (first_line, old, new) = before.changed_lines(&rope);
+        assert!(old.contains("line 02000 teh") && new.contains("line 02000 the"));
+        assert!(old.len() < 2 * CHUNK_SIZE);
+        assert_eq!(
+            old.lines().position(|l| l.starts_with("line 02000")),
+            Some(2000 - first_line)
+        );
+    }
+
+    #[test]
+    fn test_unchanged_rope_reports_no_change() {
+        let mut rope = Rope::from(long_text().as_str());
+        assert!(!rope.map_lines(|chunk| Cow::Owned(chunk.to_string())));
+        assert!(!rope.map_whole(|text| Cow::Borrowed(text)));
+        let before = rope.clone();
+        let (_, old, new) = before.changed_lines(&rope);
+        assert!(old.is_empty() && new.is_empty());
+    }
+
+    #[test]
+    fn test_map_whole_keeps_one_chunk_and_lines_split_it() {
+        let mut rope = Rope::from(long_text());
+        assert_eq!(rope.chunks().count(), 1);
+        assert!(rope.map_whole(|text| Cow::Owned(text.replacen("teh", "the", 1))));
+        assert_eq!(rope.chunks().count(), 1);
+        // A line-by-line change to a large chunk splits it
+        assert!(rope.map_lines(|chunk| Cow::Owned(chunk.replace("teh", "the"))));
+        assert!(rope.chunks().count() > 10);
+        assert_eq!(rope.to_string(), long_text().replace("teh", "the"));
+        assert_eq!(rope.into_string(), long_text().replace("teh", "the"));
+    }
+
+    #[test]
+    fn test_removed_line_break_joins_chunks() {
+        let mut rope = Rope::from(long_text().as_str());
+        let first = rope.chunks().next().unwrap().to_string();
+        rope.map_lines(|chunk| {
+            if chunk == first {
+                Cow::Owned(chunk.trim_end().to_string())
+            } else {
+                Cow::Borrowed(chunk)
+            }
+        });
+        assert!(rope.chunks().all(|chunk| chunk.ends_with('\n')));
+        assert_eq!(rope.line_count(), 3999);
+    }
+
+    #[test]
+    fn test_empty() {
+        let rope = Rope::from(String::new());
+        assert!(rope.is_empty());
+        assert_eq!(rope.line_count(), 0);
+        assert_eq!(rope.as_cow(), "");
+        assert_eq!(Rope::from("").chunks().count(), 0);
+    }
+}
@@ -0,0 +1,354 @@
+//! Static checks for a rule set: rules that match the same text, and rules
+//! whose output another rule rewrites again.
+//!
+//! Each rule is probed with an example of the text it matches (the find
+//! string, or a shortest match built from the regex), and the rules are run
+//! over each other's examples and output. Patterns the example builder
+//! cannot follow are skipped, so a clean report is not a proof.
+
+use crate::transform::TransformError;
+use awb_domain::rules::{Rule, RuleKind, RuleSet};
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// How two rules get in each other's way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Both rules match the example, so the one that runs first decides
+    /// what happens to it
+    Overlap,
+    /// The first rule's output is text the second rule then rewrites
+    Retrigger,
+    /// Each rule rewrites the other's output, so the text changes back and
+    /// forth and the page is edited again on every run
+    PingPong,
+}
+
+impl std::fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Overlap => "overlap",
+            Self::Retrigger => "re-trigger",
+            Self::PingPong => "ping-pong",
+        })
+    }
+}
+
+/// A pair of rules that conflict, with an input showing how
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleConflict {
+    pub kind: ConflictKind,
+    /// The rule that acts first on `example`
+    pub first: uuid::Uuid,
+    pub second: uuid::Uuid,
+    pub example: String,
+}
+
+/// Find conflicting pairs among the enabled text rules of `rule_set`, at most
+/// one per pair, in rule order. Rules limited to disjoint namespaces never
+/// see the same page and are not compared.
+///
+/// Returns an error if a rule has an invalid regex.
+pub fn find_conflicts(rule_set: &RuleSet) -> Result<Vec<RuleConflict>, TransformError> {
+    let probes = rule_set
+        .enabled_rules()
+        .filter_map(|rule| Probe::compile(rule).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut conflicts = Vec::new();
+    for (i, a) in probes.iter().enumerate() {
+        for b in &probes[i + 1..] {
+            if a.shares_namespace(b) {
+                conflicts.extend(compare(a, b));
+            }
+        }
+    }
+    Ok(conflicts)
+}
+
+/// The worst conflict between `a` and `b`, where `a` runs first
+fn compare(a: &Probe, b: &Probe) -> Option<RuleConflict> {
+    let conflict = |kind, first: &Probe, second: &Probe, example: &str| RuleConflict {
+        kind,
+        first: first.rule.id,
+        second: second.rule.id,
+        example: example.to_string(),
+    };
+    let examples = [a.example.as_deref(), b.example.as_deref()];
+    let examples = || examples.into_iter().flatten();
+
+    // Either rule may be the one to act first, on a later run
+    for (first, second) in [(a, b), (b, a)] {
+        for example in examples() {
+            let Some(once) = first.rewrite(example) else {
+                continue;
+            };
+            let Some(twice) = second.rewrite(&once) else {
+                continue;
+            };
+            if first.rewrite(&twice).is_some() {
+                return Some(conflict(ConflictKind::PingPong, first, second, example));
+            }
+        }
+    }
+
+    for (first, second) in [(a, b), (b, a)] {
+        for example in examples() {
+            if !second.matches(example) {
+                if let Some(once) = first.rewrite(example) {
+                    if second.matches(&once) {
+                        return Some(conflict(ConflictKind::Retrigger, first, second, example));
+                    }
+                }
+            }
+        }
+    }
+
+    examples()
+        .find(|example| a.matches(example) && b.matches(example))
+        .map(|example| conflict(ConflictKind::Overlap, a, b, example))
+}
+
+/// A rule compiled for probing, with an example of what it matches
+struct Probe<'r> {
+    rule: &'r Rule,
+    regex: regex::Regex,
+    /// Plain rules replace with the text as is; regex rules expand `$1` etc.
+    replacement: &'r str,
+    literal: bool,
+    example: Option<String>,
+}
+
+impl<'r> Probe<'r> {
+    /// `None` for rules that do not edit text
+    fn compile(rule: &'r Rule) -> Result<Option<Self>, TransformError> {
+        let invalid = |source| TransformError::InvalidRegex {
+            rule_id: rule.id,
+            source,
+        };
+        let (regex, replacement, literal, example) = match &rule.kind {
+            RuleKind::Plain {
+                find,
+                replace,
+                case_sensitive,
+            } => {
+                if find.is_empty() {
+                    return Ok(None);
+                }
+                let regex = regex::RegexBuilder::new(&regex::escape(find))
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(invalid)?;
+                (regex, replace.as_str(), true, Some(find.clone()))
+            }
+            RuleKind::Regex {
+                pattern,
+                replacement,
+                case_insensitive,
+            } => {
+                let regex = regex::RegexBuilder::new(pattern)
+                    .case_insensitive(*case_insensitive)
+                    .size_limit(1 << 20)
+                    .dfa_size_limit(1 << 20)
+                    .build()
+                    .map_err(invalid)?;
+                let example = regex_syntax::Parser::new()
+                    .parse(pattern)
+                    .ok()
+                    .and_then(|hir| shortest_match(&hir))
+                    .filter(|example| !example.is_empty() && regex.is_match(example));
+                (regex, replacement.as_str(), false, example)
+            }
+            RuleKind::JsonPatch { .. } => return Ok(None),
+        };
+        Ok(Some(Self {
+            rule,
+            regex,
+            replacement,
+            literal,
+            example,
+        }))
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// The rule's output for `text`, or `None` if it leaves it unchanged
+    fn rewrite(&self, text: &str) -> Option<String> {
+        let out = if self.literal {
+            self.regex
+                .replace_all(text, regex::NoExpand(self.replacement))
+        } else {
+            self.regex.replace_all(text, self.replacement)
+        };
+        match out {
+            Cow::Owned(out) if out != text => Some(out),
+            _ => None,
+        }
+    }
+
+    fn shares_namespace(&self, other: &Probe) -> bool {
+        self.rule.namespaces.is_empty()
+            || other.rule.namespaces.is_empty()
+            || self
+                .rule
+                .namespaces
+                .iter()
+                .any(|ns| other.rule.namespaces.contains(ns))
+    }
+}
+
+/// A short string `hir` matches: the first branch of each alternation,
+/// the fewest repetitions, and a readable member of each class. Returns
+/// `None` for patterns that need too long a match.
+fn shortest_match(hir: &Hir) -> Option<String> {
+    let mut out = String::new();
+    push_match(hir, &mut out)?;
+    Some(out)
+}
+
+fn push_match(hir: &Hir, out: &mut String) -> Option<()> {
+    const MAX_LEN: usize = 256;
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.push_str(std::str::from_utf8(&literal.0).ok()?),
+        HirKind::Class(Class::Unicode(class)) => out.push(readable_char(
+            class.ranges().iter().map(|r| r.start()..=r.end()),
+        )?),
+        HirKind::Class(Class::Bytes(class)) => out.push(readable_char(
+            class
+                .ranges()
+                .iter()
+                .filter(|r| r.start().is_ascii())
+                .map(|r| char::from(r.start())..=char::from(r.end().min(0x7f))),
+        )?),
+        HirKind::Repetition(repetition) => {
+            for _ in 0..repetition.min {
+                push_match(&repetition.sub, out)?;
+                if out.len() > MAX_LEN {
+                    return None;
+                }
+            }
+        }
+        HirKind::Capture(capture) => push_match(&capture.sub, out)?,
+        HirKind::Concat(parts) => {
+            for part in parts {
+                push_match(part, out)?;
+            }
+        }
+        HirKind::Alternation(branches) => push_match(branches.first()?, out)?,
+    }
+    (out.len() <= MAX_LEN).then_some(())
+}
+
+/// A letter, digit or space from `ranges` if there is one, else the first
+/// character that is not a control character
+fn readable_char(ranges: impl Iterator<Item = std::ops::RangeInclusive<char>>) -> Option<char> {
+    let ranges: Vec<_> = ranges.collect();
+    "aeoxAEOX0 "
+        .chars()
+        .find(|c| ranges.iter().any(|r| r.contains(c)))
+        .or_else(|| {
+            ranges
+                .iter()
+                .flat_map(|r| r.clone().take(128))
+                .find(|c| !c.is_control())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::types::Namespace;
+
+    fn rule_set(rules: Vec<Rule>) -> RuleSet {
+        let mut set = RuleSet::new();
+        for rule in rules {
+            set.add(rule);
+        }
+        set
+    }
+
+    #[test]
+    fn test_ping_pong_detected() {
+        let british = Rule::new_plain("color", "colour", true);
+        let american = Rule::new_regex(r"\bcolour\b", "color", false);
+        let (first, second) = (british.id, american.id);
+        let conflicts = find_conflicts(&rule_set(vec![british, american])).unwrap();
+        assert_eq!(
+            conflicts,
+            vec![RuleConflict {
+                kind: ConflictKind::PingPong,
+                first,
+                second,
+                example: "color".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ping_pong_through_entities() {
+        let nbsp = Rule::new_regex(r"([0-9]+) km", "$1&nbsp;km", false);
+        let plain_spaces = Rule::new_plain("&nbsp;", " ", true);
+        let conflicts = find_conflicts(&rule_set(vec![nbsp, plain_spaces])).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::PingPong);
+        assert_eq!(conflicts[0].example, "0 km");
+    }
+
+    #[test]
+    fn test_retrigger_detected() {
+        let capitalize = Rule::new_regex(r"\bthe\b", "The", false);
+        let typo = Rule::new_plain("teh", "the", true);
+        let typo_id = typo.id;
+        let conflicts = find_conflicts(&rule_set(vec![capitalize, typo])).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::Retrigger);
+        // The typo fix runs second, but its output is rewritten on the next run
+        assert_eq!(conflicts[0].first, typo_id);
+        assert_eq!(conflicts[0].example, "teh");
+    }
+
+    #[test]
+    fn test_overlap_detected() {
+        let a = Rule::new_regex(r"([0-9]+) ?kg", "$1 kilograms", false);
+        let b = Rule::new_plain("0 kg", "0&nbsp;kg", true);
+        let conflicts = find_conflicts(&rule_set(vec![a, b])).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::Overlap);
+        assert_eq!(conflicts[0].example, "0 kg");
+    }
+
+    #[test]
+    fn test_unrelated_rules_do_not_conflict() {
+        let set = rule_set(vec![
+            Rule::new_plain("teh", "the", true),
+            Rule::new_regex(r"recieve(d|s)?", "receive$1", false),
+            Rule::new_plain("color", "colour", true).with_namespaces([Namespace::MAIN]),
+            Rule::new_plain("colour", "color", true).with_namespaces([Namespace::USER]),
+        ]);
+        assert!(find_conflicts(&set).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_regex_is_an_error() {
+        let set = rule_set(vec![Rule::new_regex("[", "x", false)]);
+        assert!(matches!(
+            find_conflicts(&set),
+            Err(TransformError::InvalidRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_shortest_match() {
+        let example =
+            |pattern| shortest_match(&regex_syntax::Parser::new().parse(pattern).unwrap());
+        assert_eq!(example(r"colou?r").as_deref(), Some("color"));
+        assert_eq!(example(r"\b(foo|bar)\s+\d{2}").as_deref(), Some("foo 00"));
+        assert_eq!(example(r"[^\]|]+").as_deref(), Some("a"));
+        assert_eq!(example(r"a{300}"), None);
+    }
+}
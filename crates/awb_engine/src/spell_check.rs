@@ -0,0 +1,652 @@
+//! Spell checking against Hunspell dictionaries.
+//!
+//! A dictionary is a `.dic` word list whose entries carry affix flags, and
+//! the `.aff` file defining those flags. Only what is needed to expand the
+//! word list and make suggestions is read from the `.aff` file: `FLAG`,
+//! `PFX`/`SFX` rules (without continuation classes), `TRY` and `REP`.
+//!
+//! Only prose is checked. Protected regions are masked as for any fix, and
+//! links, URLs and HTML tags are skipped. Misspellings are reported as plan
+//! warnings; one is corrected only when its suggestion reaches the
+//! configured confidence.
+
+use crate::fix_config::{FixClassification, SpellCheckConfig};
+use crate::general_fixes::{FixContext, FixModule};
+use crate::masking::{MaskPolicy, mask_with};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Suggestions offered per misspelling
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum SpellCheckError {
+    #[error("cannot read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(".aff line {line}: {message}")]
+    Affix { line: usize, message: String },
+}
+
+/// How affix flags are written in a dictionary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagType {
+    /// One character per flag (the default, and `FLAG UTF-8`)
+    Char,
+    /// Two characters per flag (`FLAG long`)
+    Long,
+    /// Comma-separated numbers (`FLAG num`)
+    Num,
+}
+
+impl FlagType {
+    fn split(self, flags: &str) -> Vec<String> {
+        match self {
+            Self::Char => flags.chars().map(String::from).collect(),
+            Self::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|pair| pair.iter().collect()).collect()
+            }
+            Self::Num => flags.split(',').map(|f| f.trim().to_string()).collect(),
+        }
+    }
+}
+
+/// One character position of an affix condition
+#[derive(Debug, Clone)]
+enum CondChar {
+    Any,
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl CondChar {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+fn parse_condition(condition: &str) -> Option<Vec<CondChar>> {
+    let mut parts = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        parts.push(match c {
+            '.' => CondChar::Any,
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|c| *c != ']').collect();
+                let negated = set.first() == Some(&'^');
+                if negated {
+                    set.remove(0);
+                }
+                CondChar::Set {
+                    chars: set,
+                    negated,
+                }
+            }
+            ']' => return None,
+            c => CondChar::Set {
+                chars: vec![c],
+                negated: false,
+            },
+        });
+    }
+    Some(parts)
+}
+
+#[derive(Debug, Clone)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<CondChar>,
+}
+
+#[derive(Debug, Clone)]
+struct AffixClass {
+    suffix: bool,
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+impl AffixClass {
+    fn apply(&self, rule: &AffixRule, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < rule.condition.len() {
+            return None;
+        }
+        if self.suffix {
+            let tail = &chars[chars.len() - rule.condition.len()..];
+            if !rule
+                .condition
+                .iter()
+                .zip(tail)
+                .all(|(cond, c)| cond.matches(*c))
+            {
+                return None;
+            }
+            let stem = word.strip_suffix(rule.strip.as_str())?;
+            Some(format!("{}{}", stem, rule.add))
+        } else {
+            if !rule
+                .condition
+                .iter()
+                .zip(&chars)
+                .all(|(cond, c)| cond.matches(*c))
+            {
+                return None;
+            }
+            let stem = word.strip_prefix(rule.strip.as_str())?;
+            Some(format!("{}{}", rule.add, stem))
+        }
+    }
+}
+
+/// A word list expanded from a Hunspell dictionary
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    /// Letters tried when looking for suggestions, most common first
+    try_chars: Vec<char>,
+    /// Common misspellings, as (wrong, right) fragments
+    replacements: Vec<(String, String)>,
+}
+
+impl Dictionary {
+    /// Read the `.dic` file at `path` and the `.aff` file next to it
+    pub fn load(path: &Path) -> Result<Self, SpellCheckError> {
+        let read = |path: PathBuf| {
+            std::fs::read_to_string(&path).map_err(|source| SpellCheckError::Io { path, source })
+        };
+        let aff = read(path.with_extension("aff"))?;
+        let dic = read(path.to_path_buf())?;
+        Self::from_hunspell(&aff, &dic)
+    }
+
+    /// Parse a dictionary from the contents of its `.aff` and `.dic` files
+    pub fn from_hunspell(aff: &str, dic: &str) -> Result<Self, SpellCheckError> {
+        let mut dictionary = Self::default();
+        let mut flag_type = FlagType::Char;
+        let mut classes: HashMap<String, AffixClass> = HashMap::new();
+
+        for (index, line) in aff.lines().enumerate() {
+            let error = |message: &str| SpellCheckError::Affix {
+                line: index + 1,
+                message: message.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => flag_type = FlagType::Long,
+                ["FLAG", "num", ..] => flag_type = FlagType::Num,
+                ["TRY", letters, ..] => dictionary.try_chars = letters.chars().collect(),
+                ["REP", count] if count.parse::<usize>().is_ok() => {}
+                ["REP", from, to, ..] => dictionary.replacements.push((
+                    from.trim_matches(['^', '$']).replace('_', " "),
+                    to.trim_matches(['^', '$']).replace('_', " "),
+                )),
+                [kind @ ("PFX" | "SFX"), flag, rest @ ..] => {
+                    let suffix = *kind == "SFX";
+                    let Some(class) = classes.get_mut(*flag) else {
+                        // The first line of a class is its header
+                        let [cross, _count, ..] = rest else {
+                            return Err(error(
+                                "affix header needs a cross product flag and a count",
+                            ));
+                        };
+                        classes.insert(
+                            flag.to_string(),
+                            AffixClass {
+                                suffix,
+                                cross_product: *cross == "Y",
+                                rules: Vec::new(),
+                            },
+                        );
+                        continue;
+                    };
+                    let [strip, add, condition @ ..] = rest else {
+                        return Err(error("affix rule needs strip and add fields"));
+                    };
+                    let condition = condition.first().copied().unwrap_or(".");
+                    // Continuation classes after '/' are not supported
+                    let add = add.split('/').next().unwrap_or_default();
+                    let empty = |field: &str| {
+                        if field == "0" {
+                            String::new()
+                        } else {
+                            field.to_string()
+                        }
+                    };
+                    class.rules.push(AffixRule {
+                        strip: empty(strip),
+                        add: empty(add),
+                        condition: parse_condition(condition)
+                            .ok_or_else(|| error("invalid affix condition"))?,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let mut lines = dic.lines();
+        let first = lines.next().unwrap_or_default();
+        let entries = std::iter::once(first)
+            .filter(|line| line.trim().parse::<usize>().is_err())
+            .chain(lines);
+        for entry in entries {
+            // Morphological fields follow the word after whitespace
+            let Some(entry) = entry.split_whitespace().next() else {
+                continue;
+            };
+            let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            dictionary.add_forms(word, &flag_type.split(flags), &classes);
+        }
+
+        if dictionary.try_chars.is_empty() {
+            dictionary.try_chars = ('a'..='z').collect();
+        }
+        Ok(dictionary)
+    }
+
+    fn add_forms(&mut self, word: &str, flags: &[String], classes: &HashMap<String, AffixClass>) {
+        let classes: Vec<&AffixClass> = flags.iter().filter_map(|f| classes.get(f)).collect();
+        let mut suffixed = Vec::new();
+        for class in classes.iter().filter(|class| class.suffix) {
+            for rule in &class.rules {
+                if let Some(form) = class.apply(rule, word) {
+                    if class.cross_product {
+                        suffixed.push(form.clone());
+                    }
+                    self.words.insert(form);
+                }
+            }
+        }
+        for class in classes.iter().filter(|class| !class.suffix) {
+            for rule in &class.rules {
+                if let Some(form) = class.apply(rule, word) {
+                    self.words.insert(form);
+                }
+                if class.cross_product {
+                    for form in &suffixed {
+                        if let Some(form) = class.apply(rule, form) {
+                            self.words.insert(form);
+                        }
+                    }
+                }
+            }
+        }
+        self.words.insert(word.to_string());
+    }
+
+    /// Whether `word` is spelt correctly. A capitalised or all-caps word
+    /// is also correct if its lowercase form is.
+    pub fn check(&self, word: &str) -> bool {
+        if self.words.contains(word) {
+            return true;
+        }
+        let mut chars = word.chars();
+        let Some(first) = chars.next() else {
+            return true;
+        };
+        let rest = chars.as_str();
+        if first.is_uppercase() && (rest == rest.to_lowercase() || rest == rest.to_uppercase()) {
+            return self.words.contains(&word.to_lowercase());
+        }
+        false
+    }
+
+    /// Correctly spelt words one edit away from `word`, or one `REP`
+    /// replacement away, in that order of preference
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut candidates = Vec::new();
+
+        for (from, to) in self
+            .replacements
+            .iter()
+            .filter(|(from, _)| !from.is_empty())
+        {
+            for (at, _) in word.match_indices(from.as_str()) {
+                candidates.push(format!("{}{}{}", &word[..at], to, &word[at + from.len()..]));
+            }
+        }
+        let join = |chars: &[char]| chars.iter().collect::<String>();
+        for i in 0..chars.len().saturating_sub(1) {
+            let mut swapped = chars.clone();
+            swapped.swap(i, i + 1);
+            candidates.push(join(&swapped));
+        }
+        for i in 0..chars.len() {
+            for &c in &self.try_chars {
+                let mut replaced = chars.clone();
+                replaced[i] = c;
+                candidates.push(join(&replaced));
+            }
+        }
+        for i in 0..chars.len() {
+            let mut deleted = chars.clone();
+            deleted.remove(i);
+            candidates.push(join(&deleted));
+        }
+        for i in 0..=chars.len() {
+            for &c in &self.try_chars {
+                let mut inserted = chars.clone();
+                inserted.insert(i, c);
+                candidates.push(join(&inserted));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate != word && self.check(candidate))
+            .filter(|candidate| seen.insert(candidate.clone()))
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// A word the dictionary does not know
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Misspelling {
+    /// Byte range of the word in the checked text
+    pub range: Range<usize>,
+    pub word: String,
+    pub suggestions: Vec<String>,
+    /// How sure the checker is of the first suggestion (0-100): higher for
+    /// longer words, divided among the suggestions when there are several
+    pub confidence: u8,
+}
+
+/// The `spell_check` fix: reports misspellings in prose as warnings, and
+/// corrects those whose suggestion reaches `auto_fix_confidence`
+#[derive(Debug, Clone)]
+pub struct SpellCheck {
+    dictionary: Dictionary,
+    auto_fix_confidence: Option<u8>,
+}
+
+impl SpellCheck {
+    /// A checker that only reports misspellings
+    pub fn new(dictionary: Dictionary) -> Self {
+        Self {
+            dictionary,
+            auto_fix_confidence: None,
+        }
+    }
+
+    /// Correct misspellings whose suggestion has at least `percent`
+    /// confidence
+    #[must_use]
+    pub fn with_auto_fix_confidence(mut self, percent: u8) -> Self {
+        self.auto_fix_confidence = Some(percent);
+        self
+    }
+
+    /// Load the dictionary named in a wiki's fix config
+    pub fn load(config: &SpellCheckConfig) -> Result<Self, SpellCheckError> {
+        Ok(Self {
+            dictionary: Dictionary::load(&config.dictionary)?,
+            auto_fix_confidence: config.auto_fix_confidence,
+        })
+    }
+
+    /// Misspelt words in the prose of `text`. Words with digits or
+    /// internal capitals, all-caps words and capitalised words the
+    /// dictionary does not know in any form (most likely names) are not
+    /// checked.
+    pub fn misspellings(&self, text: &str) -> Vec<Misspelling> {
+        let mut misspellings = Vec::new();
+        for range in prose_ranges(text) {
+            for (start, word) in words(&text[range.clone()]) {
+                if !is_checkable(word) || self.dictionary.check(word) {
+                    continue;
+                }
+                let suggestions = self.dictionary.suggest(word);
+                if suggestions.is_empty() && word.starts_with(char::is_uppercase) {
+                    continue;
+                }
+                let start = range.start + start;
+                misspellings.push(Misspelling {
+                    range: start..start + word.len(),
+                    word: word.to_string(),
+                    confidence: confidence(word, &suggestions),
+                    suggestions,
+                });
+            }
+        }
+        misspellings
+    }
+}
+
+impl FixModule for SpellCheck {
+    fn id(&self) -> &str {
+        "spell_check"
+    }
+    fn display_name(&self) -> &str {
+        "Spell Check"
+    }
+    fn category(&self) -> &str {
+        "Typos"
+    }
+    fn description(&self) -> &str {
+        "Reports misspelt words in prose, correcting those with a confident suggestion"
+    }
+    fn classification(&self) -> FixClassification {
+        FixClassification::Editorial
+    }
+    fn min_tier(&self) -> u8 {
+        2
+    }
+    fn apply<'a>(&self, text: &'a str, _ctx: &FixContext) -> Cow<'a, str> {
+        let Some(threshold) = self.auto_fix_confidence else {
+            return Cow::Borrowed(text);
+        };
+        let fixes: Vec<Misspelling> = self
+            .misspellings(text)
+            .into_iter()
+            .filter(|m| !m.suggestions.is_empty() && m.confidence >= threshold)
+            .collect();
+        if fixes.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for fix in fixes {
+            result.push_str(&text[last..fix.range.start]);
+            result.push_str(&fix.suggestions[0]);
+            last = fix.range.end;
+        }
+        result.push_str(&text[last..]);
+        Cow::Owned(result)
+    }
+    fn warnings(&self, text: &str, _ctx: &FixContext) -> Vec<String> {
+        let masked = mask_with(text, MaskPolicy::ALL);
+        let mut seen = HashSet::new();
+        self.misspellings(&masked.masked)
+            .into_iter()
+            .filter(|m| seen.insert(m.word.clone()))
+            .map(|m| {
+                if m.suggestions.is_empty() {
+                    format!("possible misspelling \"{}\"", m.word)
+                } else {
+                    format!(
+                        "possible misspelling \"{}\" (did you mean {}?)",
+                        m.word,
+                        m.suggestions.join(", ")
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+fn confidence(word: &str, suggestions: &[String]) -> u8 {
+    if suggestions.is_empty() {
+        return 0;
+    }
+    let len = word.chars().count() as u32;
+    let certainty = 100 - 100 / len.max(1);
+    (certainty / suggestions.len() as u32) as u8
+}
+
+/// Byte ranges of `text` outside links, URLs, HTML tags and mask sentinels
+fn prose_ranges(text: &str) -> Vec<Range<usize>> {
+    static SKIP_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let skip_re = SKIP_RE.get_or_init(|| {
+        regex::Regex::new(
+            r"(?s)\x00\x01.*?\x00\x02|\[\[.*?\]\]|\[(?:https?:)?//[^\]\n]*\]|https?://[^\s\]|]+|<[^<>\n]*>",
+        )
+        .expect("known-valid regex")
+    });
+    let mut ranges = Vec::new();
+    let mut last = 0;
+    for skipped in skip_re.find_iter(text) {
+        ranges.push(last..skipped.start());
+        last = skipped.end();
+    }
+    ranges.push(last..text.len());
+    ranges.retain(|range| !range.is_empty());
+    ranges
+}
+
+/// Words of `text` with their byte offsets. Apostrophes between letters
+/// belong to the word (`don't`); wiki markup apostrophes do not.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let apostrophe = matches!(c, '\'' | '\u{2019}')
+            && start.is_some()
+            && chars.peek().is_some_and(|(_, next)| next.is_alphanumeric());
+        if c.is_alphanumeric() || apostrophe {
+            start.get_or_insert(i);
+        } else if let Some(s) = start.take() {
+            words.push((s, &text[s..i]));
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+    words
+}
+
+fn is_checkable(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(char::is_alphabetic)
+        && word.chars().count() > 1
+        && !word.chars().any(|c| c.is_numeric())
+        && !chars.any(char::is_uppercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::general_fixes::apply_masked;
+    use awb_domain::types::{Namespace, Title};
+
+    const AFF: &str = "SET UTF-8\n\
+                       TRY esianrtolcdugmphbyfvkwz\n\
+                       REP 1\n\
+                       REP f ph\n\
+                       PFX U Y 1\n\
+                       PFX U 0 un .\n\
+                       SFX S Y 2\n\
+                       SFX S y ies [^aeiou]y\n\
+                       SFX S 0 s [^y]\n\
+                       SFX D Y 1\n\
+                       SFX D 0 ed [^e]\n";
+    const DIC: &str = "6\n\
+                       receive/S\n\
+                       city/S\n\
+                       lock/UD\n\
+                       photograph/S\n\
+                       the\n\
+                       of\n";
+
+    fn checker() -> SpellCheck {
+        SpellCheck::new(Dictionary::from_hunspell(AFF, DIC).unwrap())
+    }
+
+    fn ctx() -> FixContext {
+        FixContext {
+            title: Title::new(Namespace::MAIN, "Test"),
+            namespace: Namespace::MAIN,
+            is_redirect: false,
+            revision: None,
+            cancel: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_affixes_expand() {
+        let dictionary = Dictionary::from_hunspell(AFF, DIC).unwrap();
+        for word in ["receives", "cities", "locked", "unlock", "unlocked", "The"] {
+            assert!(dictionary.check(word), "{} should be known", word);
+        }
+        for word in ["citys", "receiveed", "THe", "unreceive"] {
+            assert!(!dictionary.check(word), "{} should be unknown", word);
+        }
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let dictionary = Dictionary::from_hunspell(AFF, DIC).unwrap();
+        assert_eq!(dictionary.suggest("recieve"), vec!["receive"]);
+        assert_eq!(dictionary.suggest("fotograph"), vec!["photograph"]);
+        assert!(dictionary.suggest("zzzz").is_empty());
+    }
+
+    #[test]
+    fn test_only_prose_is_checked() {
+        let text = "The citys of [[Recieve|recieve]] {{Cite|recieve}} <span class=\"recieve\">x</span> \
+                    https://example.org/recieve Paris recieves";
+        let misspellings = checker().misspellings(&mask_with(text, MaskPolicy::ALL).masked);
+        let words: Vec<&str> = misspellings.iter().map(|m| m.word.as_str()).collect();
+        assert_eq!(words, vec!["citys", "recieves"]);
+    }
+
+    #[test]
+    fn test_warnings_unless_confident() {
+        let text = "{{Cite|lockd}} The lockd city of recieve.";
+        let fix = checker();
+        assert_eq!(apply_masked(&fix, text, &ctx()), text);
+        assert_eq!(
+            fix.warnings(text, &ctx()),
+            vec![
+                "possible misspelling \"lockd\" (did you mean lock, locked?)",
+                "possible misspelling \"recieve\" (did you mean receive?)",
+            ]
+        );
+
+        // "recieve" has one suggestion (86%); "lockd" has two (40% each)
+        let fix = checker().with_auto_fix_confidence(80);
+        let fixed = apply_masked(&fix, text, &ctx());
+        assert_eq!(fixed, "{{Cite|lockd}} The lockd city of receive.");
+        assert_eq!(fix.warnings(&fixed, &ctx()).len(), 1);
+    }
+
+    #[test]
+    fn test_aff_errors_have_line_numbers() {
+        let err = Dictionary::from_hunspell("SET UTF-8\nSFX S Y 1\nSFX S y\n", "").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            ".aff line 3: affix rule needs strip and add fields"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Talk page safety: which discussion pages a bot may edit, and how.
+//!
+//! Article-space fixes (general fixes, typo fixing) rewrite other people's
+//! comments when run on a talk page, so by default bots skip talk
+//! namespaces. [`TalkPageMode::PreserveSignatures`] lets the rules run only
+//! on the page header and on comments the bot account signed itself, with
+//! signatures masked and no general fixes. Flow boards and talk page
+//! archives are never edited.
+
+use crate::incremental::{is_heading, split_sections};
+use crate::masking::{find_signature_end, signature_user};
+use awb_domain::types::{ContentModel, PageContent, Title};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// How a bot run treats pages in talk namespaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TalkPageMode {
+    /// Skip talk pages.
+    #[default]
+    Skip,
+    /// Run the rules on the page header and the account's own comments,
+    /// with signatures masked; no general fixes.
+    PreserveSignatures,
+    /// Treat talk pages like any other page.
+    Full,
+}
+
+/// Why a page must not be edited as an ordinary talk page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discussion {
+    /// A Flow / Structured Discussions board
+    FlowBoard,
+    /// An archive of past discussions
+    Archive,
+}
+
+impl std::fmt::Display for Discussion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::FlowBoard => "Flow board",
+            Self::Archive => "talk page archive",
+        })
+    }
+}
+
+/// Templates that mark a page as an archive
+const ARCHIVE_TEMPLATES: &[&str] = &[
+    "Talk archive",
+    "Talkarchive",
+    "Archive",
+    "Automatic archive navigator",
+    "Aan",
+];
+
+/// Whether `page` is a Flow board or an archive. Boards are recognised by
+/// content model in any namespace; archives are talk subpages named
+/// `Archive…` (e.g. `Talk:Foo/Archive 3`) or tagged `{{Talk archive}}`.
+pub fn classify(page: &PageContent) -> Option<Discussion> {
+    if page.properties.content_model == ContentModel::FlowBoard {
+        return Some(Discussion::FlowBoard);
+    }
+    if page.title.namespace.is_talk()
+        && (is_archive_title(&page.title) || has_archive_template(&page.wikitext))
+    {
+        return Some(Discussion::Archive);
+    }
+    None
+}
+
+fn is_archive_title(title: &Title) -> bool {
+    title.name.split('/').skip(1).any(|part| {
+        part.get(..7)
+            .is_some_and(|p| p.eq_ignore_ascii_case("archive"))
+    })
+}
+
+fn has_archive_template(wikitext: &str) -> bool {
+    crate::template_params::parse_templates(wikitext)
+        .iter()
+        .any(|template| {
+            let name = crate::template_params::normalize_template_name(&template.name);
+            ARCHIVE_TEMPLATES.contains(&name.as_str())
+        })
+}
+
+/// Byte ranges of `wikitext` that a bot signing as `user` may change: the
+/// page header before the first heading, and comments signed by `user`. A
+/// comment runs from the end of the previous comment (or the section
+/// heading) through the line with its signature; unsigned text after the
+/// last signature in a section belongs to no one and is left alone.
+pub fn editable_ranges(wikitext: &str, user: &str) -> Vec<Range<usize>> {
+    let user = normalize_user(user);
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+
+    for section in split_sections(wikitext) {
+        let section_start = offset;
+        offset += section.len();
+        let mut lines = section.split_inclusive('\n');
+        let Some(first) = lines.next().filter(|line| is_heading(line)) else {
+            // Only the first section can lack a heading
+            ranges.push(section_start..offset);
+            continue;
+        };
+
+        let mut pos = section_start + first.len();
+        let mut comment_start = pos;
+        for line in lines {
+            let line_end = pos + line.len();
+            if let Some(signer) = last_signer(line) {
+                if normalize_user(signer) == user {
+                    ranges.push(comment_start..line_end);
+                }
+                comment_start = line_end;
+            }
+            pos = line_end;
+        }
+    }
+
+    ranges.retain(|range| !range.is_empty());
+    ranges
+}
+
+/// The user of the last signature on `line`
+fn last_signer(line: &str) -> Option<&str> {
+    line.match_indices("[[")
+        .filter(|(start, _)| find_signature_end(line, *start).is_some())
+        .filter_map(|(start, _)| signature_user(line, start))
+        .last()
+}
+
+/// User names compare with underscores as spaces and the first letter
+/// capitalised
+fn normalize_user(name: &str) -> String {
+    let name = name.trim().replace('_', " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::types::*;
+
+    fn page(namespace: Namespace, name: &str, wikitext: &str) -> PageContent {
+        PageContent {
+            page_id: PageId(1),
+            title: Title::new(namespace, name),
+            revision: RevisionId(1),
+            timestamp: chrono::Utc::now(),
+            wikitext: wikitext.to_string(),
+            size_bytes: wikitext.len() as u64,
+            is_redirect: false,
+            protection: ProtectionInfo::default(),
+            properties: PageProperties::default(),
+        }
+    }
+
+    #[test]
+    fn test_archives_detected() {
+        let archive = page(Namespace::TALK, "Foo/Archive 3", "== Old ==");
+        assert_eq!(classify(&archive), Some(Discussion::Archive));
+
+        let archive = page(Namespace::USER_TALK, "Ann/archives/2020", "");
+        assert_eq!(classify(&archive), Some(Discussion::Archive));
+
+        let tagged = page(Namespace::TALK, "Foo/Old", "{{talk_archive}}\n== Old ==");
+        assert_eq!(classify(&tagged), Some(Discussion::Archive));
+
+        assert_eq!(classify(&page(Namespace::TALK, "Foo", "== New ==")), None);
+        // An article about archives is not an archive
+        assert_eq!(classify(&page(Namespace::MAIN, "Foo/Archive", "")), None);
+    }
+
+    #[test]
+    fn test_flow_boards_detected() {
+        let mut board = page(Namespace::PROJECT_TALK, "Help desk", "");
+        board.properties.content_model = ContentModel::FlowBoard;
+        assert_eq!(classify(&board), Some(Discussion::FlowBoard));
+        assert_eq!(Discussion::FlowBoard.to_string(), "Flow board");
+    }
+
+    #[test]
+    fn test_talk_page_mode_names() {
+        let mode: TalkPageMode = serde_json::from_str("\"preserve_signatures\"").unwrap();
+        assert_eq!(mode, TalkPageMode::PreserveSignatures);
+        assert_eq!(TalkPageMode::default(), TalkPageMode::Skip);
+    }
+
+    #[test]
+    fn test_editable_ranges() {
+        let text = "{{Talk header}}\n\
+                    == Cats ==\n\
+                    Cats are great. [[User:Bob|Bob]] 10:00, 1 May 2024 (UTC)\n\
+                    :Agreed. [[User:AWB_Bot|AWB Bot]] ([[User talk:AWB Bot|talk]]) 11:00, 1 May 2024 (UTC)\n\
+                    ::Unsigned reply\n\
+                    == Dogs ==\n\
+                    [[User:aWB Bot]] 12:00, 2 May 2024 (UTC)\n";
+        let ranges = editable_ranges(text, "AWB Bot");
+        let parts: Vec<&str> = ranges.iter().map(|r| &text[r.clone()]).collect();
+        assert_eq!(
+            parts,
+            vec![
+                "{{Talk header}}\n",
+                ":Agreed. [[User:AWB_Bot|AWB Bot]] ([[User talk:AWB Bot|talk]]) 11:00, 1 May 2024 (UTC)\n",
+                "[[User:aWB Bot]] 12:00, 2 May 2024 (UTC)\n",
+            ]
+        );
+
+        // A page that starts with a heading has no header
+        assert!(
+            editable_ranges(
+                "== A ==\nHi [[User:Bob]] 10:00, 1 May 2024 (UTC)",
+                "AWB Bot"
+            )
+            .is_empty()
+        );
+    }
+}
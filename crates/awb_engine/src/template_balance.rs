@@ -0,0 +1,131 @@
+//! Templates and parameters an edit loses.
+//!
+//! Compares the transclusions of a page before and after an edit, read with
+//! [`crate::template_params`]. A template is lost when the page has fewer
+//! transclusions of it afterwards; a parameter is lost when a value it had
+//! is no longer given to the same transclusion under any name, so renaming
+//! `accessdate` to `access-date` or changing the case of a template name
+//! loses nothing. Magic words such as `{{DEFAULTSORT:...}}` are not
+//! templates and are left out.
+
+use crate::template_params::{TemplateCall, normalize_template_name, parse_templates};
+use std::collections::BTreeMap;
+
+/// What an edit removed from a page's templates
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateLosses {
+    /// Templates with fewer transclusions after the edit, by normalized name
+    pub templates: Vec<String>,
+    /// Parameters whose value was removed, as `(template, parameter)`
+    pub parameters: Vec<(String, String)>,
+}
+
+impl TemplateLosses {
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty() && self.parameters.is_empty()
+    }
+}
+
+/// Templates and parameters in `old` that `new` no longer has. When a
+/// template is transcluded as often before as after, its transclusions are
+/// compared in order.
+pub fn template_losses(old: &str, new: &str) -> TemplateLosses {
+    let before = by_name(old);
+    let after = by_name(new);
+    let mut losses = TemplateLosses::default();
+    for (key, (name, old_calls)) in &before {
+        let new_calls = after
+            .get(key)
+            .map_or(&[][..], |(_, calls)| calls.as_slice());
+        if new_calls.len() < old_calls.len() {
+            losses.templates.push(name.clone());
+            continue;
+        }
+        if new_calls.len() > old_calls.len() {
+            // Cannot tell which transclusions correspond
+            continue;
+        }
+        for (old_call, new_call) in old_calls.iter().zip(new_calls) {
+            for (param, value) in &old_call.params {
+                if value.is_empty() || new_call.get(param).is_some_and(|v| !v.is_empty()) {
+                    continue;
+                }
+                let moved = new_call.params.iter().any(|(_, v)| v == value);
+                let pair = (name.clone(), param.clone());
+                if !moved && !losses.parameters.contains(&pair) {
+                    losses.parameters.push(pair);
+                }
+            }
+        }
+    }
+    losses
+}
+
+/// Transclusions grouped by template, keyed case-insensitively; the value
+/// holds the normalized name and the calls in page order
+fn by_name(wikitext: &str) -> BTreeMap<String, (String, Vec<TemplateCall>)> {
+    let mut groups: BTreeMap<String, (String, Vec<TemplateCall>)> = BTreeMap::new();
+    for call in parse_templates(wikitext) {
+        if is_magic_word(&call.name) {
+            continue;
+        }
+        let name = normalize_template_name(&call.name);
+        groups
+            .entry(name.to_lowercase())
+            .or_insert_with(|| (name, Vec::new()))
+            .1
+            .push(call);
+    }
+    groups
+}
+
+/// `{{DEFAULTSORT:...}}`, `{{DISPLAYTITLE:...}}` and the like
+fn is_magic_word(name: &str) -> bool {
+    name.split_once(':').is_some_and(|(word, _)| {
+        let word = word.trim();
+        !word.is_empty() && word.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "{{Infobox person\n| name = Ada\n| birth_date = 1815\n| image =\n}}\n\
+        Text.{{cn}} More.<ref>{{Cite web|url=http://x.org|accessdate=2020}}</ref>\n\
+        {{DEFAULTSORT:Lovelace, Ada}}";
+
+    #[test]
+    fn test_nothing_lost() {
+        let new = PAGE
+            .replace("{{Cite web", "{{cite web")
+            .replace("accessdate=", "access-date=")
+            .replace("| image =\n", "")
+            .replace("{{DEFAULTSORT:Lovelace, Ada}}", "");
+        assert!(template_losses(PAGE, &new).is_empty());
+    }
+
+    #[test]
+    fn test_template_removed() {
+        let new = PAGE.replace("{{cn}}", "");
+        let losses = template_losses(PAGE, &new);
+        assert_eq!(losses.templates, vec!["Cn"]);
+        assert!(losses.parameters.is_empty());
+    }
+
+    #[test]
+    fn test_parameter_removed_or_emptied() {
+        let new = PAGE
+            .replace("| birth_date = 1815\n", "")
+            .replace("url=http://x.org", "url=");
+        let losses = template_losses(PAGE, &new);
+        assert!(losses.templates.is_empty());
+        assert_eq!(
+            losses.parameters,
+            vec![
+                ("Cite web".to_string(), "url".to_string()),
+                ("Infobox person".to_string(), "birth_date".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Reading template transclusions and their parameters out of wikitext.
+//!
+//! Used to select pages by what a template is given, e.g. every
+//! `{{Infobox person}}` whose `birth_date` is missing. Nested templates and
+//! links inside parameter values are kept intact, and HTML comments are
+//! ignored, so `| birth_date = <!-- {{birth date|...}} -->` counts as empty.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// One transclusion of a template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateCall {
+    /// Name as written, trimmed
+    pub name: String,
+    /// Parameters in order; positional ones are named "1", "2", ...
+    pub params: Vec<(String, String)>,
+}
+
+impl TemplateCall {
+    /// Trimmed value of parameter `name`; the last one wins if repeated,
+    /// as in MediaWiki
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Normalize a template name for comparison: drop a `Template:` prefix,
+/// treat underscores as spaces and ignore the case of the first letter
+pub fn normalize_template_name(name: &str) -> String {
+    let name = name.replace('_', " ");
+    let name = name.trim();
+    let name = match name.split_once(':') {
+        Some((prefix, rest)) if prefix.trim().eq_ignore_ascii_case("template") => rest.trim(),
+        _ => name,
+    };
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Every transclusion in `wikitext`, outermost first, including templates
+/// nested in other templates' parameters. Parser functions and template
+/// parameters (`{{{1}}}`) are skipped.
+pub fn parse_templates(wikitext: &str) -> Vec<TemplateCall> {
+    let text = strip_comments(wikitext);
+    let bytes = text.as_bytes();
+    let mut calls = Vec::new();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'{' && bytes[i + 1] == b'{' {
+            if bytes.get(i + 2) == Some(&b'{') {
+                // Skip the whole run of braces of a {{{parameter}}}
+                while bytes.get(i) == Some(&b'{') {
+                    i += 1;
+                }
+                continue;
+            }
+            if let Some(end) = find_close(bytes, i + 2) {
+                if let Some(call) = parse_call(&text[i + 2..end]) {
+                    calls.push(call);
+                }
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    calls
+}
+
+/// Every transclusion like [`parse_templates`], with the byte range of its
+/// `{{...}}` in `wikitext`, for editing templates in place
+pub fn template_spans(wikitext: &str) -> Vec<(Range<usize>, TemplateCall)> {
+    let text = blank_comments(wikitext);
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'{' && bytes[i + 1] == b'{' {
+            if bytes.get(i + 2) == Some(&b'{') {
+                while bytes.get(i) == Some(&b'{') {
+                    i += 1;
+                }
+                continue;
+            }
+            if let Some(end) = find_close(bytes, i + 2) {
+                if let Some(call) = parse_call(&text[i + 2..end]) {
+                    spans.push((i..end + 2, call));
+                }
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+/// Transclusions of template `name`, matched after normalization
+pub fn find_templates(wikitext: &str, name: &str) -> Vec<TemplateCall> {
+    let wanted = normalize_template_name(name);
+    parse_templates(wikitext)
+        .into_iter()
+        .filter(|call| normalize_template_name(&call.name) == wanted)
+        .collect()
+}
+
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<!--") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("-->") {
+            Some(end) => rest = &rest[start + end + 3..],
+            None => return out,
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `text` with HTML comments replaced by spaces, keeping byte offsets
+fn blank_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<!--") {
+        out.push_str(&rest[..start]);
+        let len = rest[start..]
+            .find("-->")
+            .map_or(rest.len() - start, |end| end + 3);
+        out.push_str(&" ".repeat(len));
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Index of the `}}` closing a template whose body starts at `start`
+fn find_close(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'{', b'{') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'}', b'}') if depth == 0 => return Some(i),
+            (b'}', b'}') => {
+                depth -= 1;
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn parse_call(body: &str) -> Option<TemplateCall> {
+    let mut parts = split_top_level(body, '|').into_iter();
+    let name = parts.next()?.trim();
+    if name.is_empty() || name.starts_with('#') || name.contains('{') {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    let mut position = 0;
+    for part in parts {
+        match split_top_level(part, '=').as_slice() {
+            [key, ..] if key.len() < part.len() => {
+                let value = &part[key.len() + 1..];
+                params.push((key.trim().to_string(), value.trim().to_string()));
+            }
+            _ => {
+                position += 1;
+                params.push((position.to_string(), part.trim().to_string()));
+            }
+        }
+    }
+    Some(TemplateCall {
+        name: name.to_string(),
+        params,
+    })
+}
+
+/// Split on `sep` outside nested templates and links
+fn split_top_level(text: &str, sep: char) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut parts = Vec::new();
+    let (mut braces, mut brackets) = (0usize, 0usize);
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let pair = bytes.get(i..i + 2);
+        match pair {
+            Some(b"{{") => {
+                braces += 1;
+                i += 2;
+                continue;
+            }
+            Some(b"}}") if braces > 0 => {
+                braces -= 1;
+                i += 2;
+                continue;
+            }
+            Some(b"[[") => {
+                brackets += 1;
+                i += 2;
+                continue;
+            }
+            Some(b"]]") if brackets > 0 => {
+                brackets -= 1;
+                i += 2;
+                continue;
+            }
+            _ => {}
+        }
+        if bytes[i] == sep as u8 && braces == 0 && brackets == 0 {
+            parts.push(&text[start..i]);
+            start = i + 1;
+        }
+        i += 1;
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Condition on one template parameter.
+///
+/// Parsed from `param` (set to a non-empty value), `!param` (missing or
+/// empty), `param=value` (equal to `value`) or `param~text` (contains
+/// `text`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamPredicate {
+    Present(String),
+    Missing(String),
+    Equals(String, String),
+    Contains(String, String),
+}
+
+impl ParamPredicate {
+    pub fn matches(&self, call: &TemplateCall) -> bool {
+        let value = |param: &str| call.get(param).filter(|v| !v.is_empty());
+        match self {
+            Self::Present(param) => value(param).is_some(),
+            Self::Missing(param) => value(param).is_none(),
+            Self::Equals(param, expected) => call.get(param) == Some(expected.as_str()),
+            Self::Contains(param, text) => value(param).is_some_and(|v| v.contains(text.as_str())),
+        }
+    }
+
+    /// Whether any transclusion of `template` in `wikitext` satisfies this
+    pub fn matches_page(&self, wikitext: &str, template: &str) -> bool {
+        find_templates(wikitext, template)
+            .iter()
+            .any(|call| self.matches(call))
+    }
+}
+
+impl FromStr for ParamPredicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let predicate = if let Some(param) = s.strip_prefix('!') {
+            Self::Missing(param.trim().to_string())
+        } else if let Some((param, value)) = s.split_once('=') {
+            Self::Equals(param.trim().to_string(), value.trim().to_string())
+        } else if let Some((param, text)) = s.split_once('~') {
+            Self::Contains(param.trim().to_string(), text.trim().to_string())
+        } else {
+            Self::Present(s.to_string())
+        };
+        match &predicate {
+            Self::Present(p) | Self::Missing(p) | Self::Equals(p, _) | Self::Contains(p, _)
+                if p.is_empty() =>
+            {
+                Err(format!("no parameter name in '{}'", s))
+            }
+            _ => Ok(predicate),
+        }
+    }
+}
+
+impl fmt::Display for ParamPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Present(p) => write!(f, "{} is set", p),
+            Self::Missing(p) => write!(f, "{} is missing", p),
+            Self::Equals(p, v) => write!(f, "{} = {:?}", p, v),
+            Self::Contains(p, t) => write!(f, "{} contains {:?}", p, t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "Intro {{Short description|Physicist}}\n\
+        {{Infobox person\n\
+        | name = Ada [[Lovelace|Byron]]\n\
+        | birth_date = <!-- {{birth date|1815|12|10}} -->\n\
+        | spouse = {{marriage|William King|1835}}\n\
+        | signature =\n\
+        }}\n\
+        {{{1}}} {{#if:x|y}}";
+
+    #[test]
+    fn test_parse_templates() {
+        let calls = parse_templates(PAGE);
+        let names: Vec<&str> = calls.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Short description", "Infobox person", "marriage"]
+        );
+
+        let infobox = &calls[1];
+        assert_eq!(infobox.get("name"), Some("Ada [[Lovelace|Byron]]"));
+        assert_eq!(infobox.get("birth_date"), Some(""));
+        assert_eq!(
+            infobox.get("spouse"),
+            Some("{{marriage|William King|1835}}")
+        );
+        assert_eq!(calls[0].get("1"), Some("Physicist"));
+        assert_eq!(calls[2].get("2"), Some("1835"));
+    }
+
+    #[test]
+    fn test_find_templates_normalizes_names() {
+        assert_eq!(find_templates(PAGE, "Template:infobox_person").len(), 1);
+        assert_eq!(find_templates(PAGE, "Infobox  person").len(), 1);
+        assert!(find_templates(PAGE, "Infobox scientist").is_empty());
+    }
+
+    #[test]
+    fn test_predicates() {
+        let check = |p: &str| {
+            p.parse::<ParamPredicate>()
+                .unwrap()
+                .matches_page(PAGE, "Infobox person")
+        };
+        assert!(check("!birth_date"));
+        assert!(check("!signature"));
+        assert!(check("!death_date"));
+        assert!(check("name"));
+        assert!(check("name~Lovelace"));
+        assert!(check("spouse={{marriage|William King|1835}}"));
+        assert!(!check("birth_date"));
+        assert!(!check("!name"));
+        assert!(!check("name=Ada"));
+
+        assert!("!".parse::<ParamPredicate>().is_err());
+        assert!("=x".parse::<ParamPredicate>().is_err());
+    }
+
+    #[test]
+    fn test_template_spans() {
+        let text = "<!-- {{Orphan}} -->{{Orphan|date=May 2020}}x{{a|{{b}}}}";
+        let spans = template_spans(text);
+        let found: Vec<(&str, &str)> = spans
+            .iter()
+            .map(|(range, call)| (call.name.as_str(), &text[range.clone()]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Orphan", "{{Orphan|date=May 2020}}"),
+                ("a", "{{a|{{b}}}}"),
+                ("b", "{{b}}")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_template_is_ignored() {
+        assert!(parse_templates("{{Infobox person | name = x").is_empty());
+    }
+}
@@ -1,8 +1,19 @@
-use awb_domain::rules::{RuleKind, RuleSet};
+use crate::fix_config::FixClassification;
+use crate::memory::MemoryProfile;
+use crate::prefilter::{PlainPrefilter, PrefilterStats};
+use crate::rope::Rope;
+use awb_domain::diff::DiffOp;
+use awb_domain::rules::{JsonPatchOp, Rule, RuleKind, RuleSet};
 use awb_domain::session::EditPlan;
-use awb_domain::types::PageContent;
+use awb_domain::summary::SummaryValues;
+use awb_domain::types::{ContentModel, Namespace, PageContent, Title};
 use awb_domain::warnings::Warning;
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::OnceLock;
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Error)]
 pub enum TransformError {
@@ -11,6 +22,200 @@ pub enum TransformError {
         rule_id: uuid::Uuid,
         source: regex::Error,
     },
+    #[error("Rule {rule_id} has invalid title pattern: {source}")]
+    InvalidTitlePattern {
+        rule_id: uuid::Uuid,
+        source: regex::Error,
+    },
+}
+
+/// Which pages a rule applies to
+struct RuleScope {
+    namespaces: Vec<Namespace>,
+    title: Option<regex::Regex>,
+}
+
+impl RuleScope {
+    fn compile(rule: &Rule) -> Result<Self, TransformError> {
+        let title = rule
+            .title_pattern
+            .as_deref()
+            .map(|pattern| {
+                regex::RegexBuilder::new(pattern)
+                    .size_limit(1 << 20)
+                    .dfa_size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| TransformError::InvalidTitlePattern {
+                        rule_id: rule.id,
+                        source: e,
+                    })
+            })
+            .transpose()?;
+        Ok(Self {
+            namespaces: rule.namespaces.clone(),
+            title,
+        })
+    }
+
+    fn matches(&self, title: &Title) -> bool {
+        (self.namespaces.is_empty() || self.namespaces.contains(&title.namespace))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|re| re.is_match(&title.name))
+    }
+}
+
+/// Parse a rule's pattern as the `regex` crate would
+fn parse(pattern: &str, case_insensitive: bool) -> Option<Hir> {
+    regex_syntax::ParserBuilder::new()
+        .case_insensitive(case_insensitive)
+        .build()
+        .parse(pattern)
+        .ok()
+}
+
+/// Whether a regex finds the same matches in a text run a few lines at a
+/// time as in the whole: it cannot match a line break, the empty string, or
+/// the start or end of the whole text.
+fn is_line_local(hir: &Hir) -> bool {
+    fn may_cross_lines(hir: &Hir) -> bool {
+        match hir.kind() {
+            HirKind::Empty => false,
+            HirKind::Literal(literal) => literal.0.contains(&b'\n'),
+            HirKind::Class(Class::Unicode(class)) => class
+                .ranges()
+                .iter()
+                .any(|r| (r.start()..=r.end()).contains(&'\n')),
+            HirKind::Class(Class::Bytes(class)) => class
+                .ranges()
+                .iter()
+                .any(|r| (r.start()..=r.end()).contains(&b'\n')),
+            HirKind::Look(look) => matches!(look, Look::Start | Look::End),
+            HirKind::Repetition(repetition) => may_cross_lines(&repetition.sub),
+            HirKind::Capture(capture) => may_cross_lines(&capture.sub),
+            HirKind::Concat(parts) | HirKind::Alternation(parts) => {
+                parts.iter().any(may_cross_lines)
+            }
+        }
+    }
+    hir.properties().minimum_len().is_some_and(|len| len > 0) && !may_cross_lines(hir)
+}
+
+/// Literals every match of a regex starts with, so text containing none of
+/// them cannot match. `None` when there is no short list of them.
+fn match_prefixes(hir: &Hir) -> Option<Vec<String>> {
+    let seq = regex_syntax::hir::literal::Extractor::new().extract(hir);
+    let mut prefixes = seq
+        .literals()?
+        .iter()
+        .map(|literal| {
+            // A literal cut short may end part way through a character
+            let bytes = literal.as_bytes();
+            let valid = match std::str::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(e) => std::str::from_utf8(&bytes[..e.valid_up_to()]).expect("valid prefix"),
+            };
+            (!valid.is_empty()).then(|| valid.to_string())
+        })
+        .collect::<Option<Vec<_>>>()?;
+    // Text containing "cat" need not be searched for "cats" too
+    prefixes.sort();
+    let mut kept: Vec<String> = Vec::with_capacity(prefixes.len());
+    for prefix in prefixes {
+        if !kept
+            .last()
+            .is_some_and(|last| prefix.starts_with(last.as_str()))
+        {
+            kept.push(prefix);
+        }
+    }
+    (!kept.is_empty()).then_some(kept)
+}
+
+/// What compiling a rule set works out about each rule besides building
+/// its regex: that the rule is valid, whether it can run a few lines at a
+/// time, and the literals its matches start with.
+///
+/// An engine built by [`TransformEngine::with_prepared`] trusts this
+/// instead of working it out again, and compiles each regex only once a
+/// page contains one of its literals, so a large rule library starts
+/// quickly. It serializes, for keeping next to the rule set it came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreparedRules {
+    rules: Vec<PreparedRule>,
+}
+
+impl PreparedRules {
+    /// Whether this was prepared from `rule_set`'s enabled rules, as far as
+    /// their IDs tell. Callers key saved copies by the rule set's content.
+    fn fits(&self, rule_set: &RuleSet) -> bool {
+        self.rules.len() == rule_set.enabled_rules().count()
+            && self
+                .rules
+                .iter()
+                .zip(rule_set.enabled_rules())
+                .all(|(prepared, rule)| prepared.id == rule.id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PreparedRule {
+    id: uuid::Uuid,
+    line_local: bool,
+    prefixes: Option<Vec<String>>,
+}
+
+/// A rule's regex, built on first use when the rule came prepared
+struct LazyRegex {
+    pattern: String,
+    case_insensitive: bool,
+    regex: OnceLock<Option<regex::Regex>>,
+}
+
+impl LazyRegex {
+    fn build(pattern: &str, case_insensitive: bool) -> Result<regex::Regex, regex::Error> {
+        regex::RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .size_limit(1 << 20)
+            .dfa_size_limit(1 << 20)
+            .build()
+    }
+
+    fn compiled(pattern: String, case_insensitive: bool) -> Result<Self, regex::Error> {
+        let regex = Self::build(&pattern, case_insensitive)?;
+        Ok(Self {
+            pattern,
+            case_insensitive,
+            regex: OnceLock::from(Some(regex)),
+        })
+    }
+
+    fn deferred(pattern: String, case_insensitive: bool) -> Self {
+        Self {
+            pattern,
+            case_insensitive,
+            regex: OnceLock::new(),
+        }
+    }
+
+    /// The regex, or `None` if it was prepared as valid but does not
+    /// compile, in which case the rule does nothing
+    fn get(&self) -> Option<&regex::Regex> {
+        self.regex
+            .get_or_init(|| {
+                Self::build(&self.pattern, self.case_insensitive)
+                    .inspect_err(|e| {
+                        tracing::warn!(
+                            "Prepared rule regex {} does not compile: {}",
+                            self.pattern,
+                            e
+                        )
+                    })
+                    .ok()
+            })
+            .as_ref()
+    }
 }
 
 enum CompiledRule {
@@ -18,22 +223,168 @@ enum CompiledRule {
         find: String,
         replace: String,
         case_sensitive: bool,
-        case_insensitive_regex: Option<regex::Regex>,
+        case_insensitive_regex: Option<LazyRegex>,
+        /// Whether the rule can be run a few lines at a time, see
+        /// [`is_line_local`]
+        line_local: bool,
+        /// See [`match_prefixes`]; case-sensitive rules need none
+        prefixes: Option<Vec<String>>,
         id: uuid::Uuid,
         comment: Option<String>,
+        scope: RuleScope,
     },
     Regex {
-        regex: regex::Regex,
+        regex: LazyRegex,
         replacement: String,
+        line_local: bool,
+        prefixes: Option<Vec<String>>,
         id: uuid::Uuid,
         comment: Option<String>,
+        scope: RuleScope,
+    },
+    /// Only runs on JSON content-model pages
+    JsonPatch {
+        operations: Vec<JsonPatchOp>,
+        id: uuid::Uuid,
+        comment: Option<String>,
+        scope: RuleScope,
     },
 }
 
+impl CompiledRule {
+    fn id(&self) -> uuid::Uuid {
+        match self {
+            CompiledRule::Plain { id, .. }
+            | CompiledRule::Regex { id, .. }
+            | CompiledRule::JsonPatch { id, .. } => *id,
+        }
+    }
+
+    fn comment(&self) -> Option<&str> {
+        match self {
+            CompiledRule::Plain { comment, .. }
+            | CompiledRule::Regex { comment, .. }
+            | CompiledRule::JsonPatch { comment, .. } => comment.as_deref(),
+        }
+    }
+
+    /// What a text rule looks for: the plain text or the regex
+    fn find_text(&self) -> Option<&str> {
+        match self {
+            CompiledRule::Plain { find, .. } => Some(find),
+            CompiledRule::Regex { regex, .. } => Some(&regex.pattern),
+            CompiledRule::JsonPatch { .. } => None,
+        }
+    }
+
+    /// Whether what the rule looks for names `needle`, ignoring case and
+    /// treating underscores as spaces
+    fn mentions(&self, needle: &str) -> bool {
+        self.find_text().is_some_and(|find| {
+            find.replace('_', " ")
+                .to_lowercase()
+                .contains(&needle.replace('_', " ").to_lowercase())
+        })
+    }
+
+    /// How diffs name this rule: its edit summary comment, or else what it
+    /// looks for
+    fn label(&self) -> String {
+        if let Some(comment) = self.comment() {
+            return comment.to_string();
+        }
+        match self {
+            CompiledRule::Plain { find, .. } => find.clone(),
+            CompiledRule::Regex { regex, .. } => regex.pattern.clone(),
+            CompiledRule::JsonPatch { id, .. } => id.to_string(),
+        }
+    }
+
+    /// The rule's output for `text`, borrowed if it does not match
+    fn replace_in<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let (regex, replacement, prefixes) = match self {
+            CompiledRule::Plain {
+                find,
+                replace,
+                case_sensitive: true,
+                ..
+            } => {
+                return if text.contains(find.as_str()) {
+                    Cow::Owned(text.replace(find.as_str(), replace.as_str()))
+                } else {
+                    Cow::Borrowed(text)
+                };
+            }
+            CompiledRule::Plain {
+                case_insensitive_regex,
+                replace,
+                prefixes,
+                ..
+            } => (
+                case_insensitive_regex
+                    .as_ref()
+                    .expect("case_insensitive_regex must be Some when case_sensitive is false"),
+                replace,
+                prefixes,
+            ),
+            CompiledRule::Regex {
+                regex,
+                replacement,
+                prefixes,
+                ..
+            } => (regex, replacement, prefixes),
+            CompiledRule::JsonPatch { .. } => return Cow::Borrowed(text),
+        };
+        // Checked first so that a prepared rule's regex is only built for
+        // text it may match
+        if prefixes
+            .as_ref()
+            .is_some_and(|prefixes| !prefixes.iter().any(|p| text.contains(p.as_str())))
+        {
+            return Cow::Borrowed(text);
+        }
+        match regex.get() {
+            Some(regex) => regex.replace_all(text, replacement.as_str()),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// Apply the rule to `text`, returning whether it changed. Rules that
+    /// cannot match across lines only copy the chunks they change.
+    fn rewrite(&self, text: &mut Rope) -> bool {
+        match self {
+            CompiledRule::Plain { line_local, .. } | CompiledRule::Regex { line_local, .. } => {
+                if *line_local {
+                    text.map_lines(|chunk| self.replace_in(chunk))
+                } else {
+                    text.map_whole(|whole| self.replace_in(whole))
+                }
+            }
+            CompiledRule::JsonPatch { .. } => false,
+        }
+    }
+
+    fn scope(&self) -> &RuleScope {
+        match self {
+            CompiledRule::Plain { scope, .. }
+            | CompiledRule::Regex { scope, .. }
+            | CompiledRule::JsonPatch { scope, .. } => scope,
+        }
+    }
+}
+
 pub struct TransformEngine {
     compiled_rules: Vec<CompiledRule>,
+    /// Built when there are enough plain rules, see [`crate::prefilter`]
+    prefilter: Option<PlainPrefilter>,
     fix_registry: crate::general_fixes::FixRegistry,
     enabled_fixes: std::collections::HashSet<String>,
+    /// Stops a transform in progress; see [`with_cancellation`](Self::with_cancellation)
+    cancel: CancellationToken,
+    /// Edit summary template, see [`with_summary_template`](Self::with_summary_template)
+    summary_template: Option<String>,
+    /// Enabled rules marked [`Rule::retargets_links`]
+    link_retargeting_rules: Vec<uuid::Uuid>,
 }
 
 impl TransformEngine {
@@ -42,156 +393,605 @@ impl TransformEngine {
         fix_registry: crate::general_fixes::FixRegistry,
         enabled_fixes: std::collections::HashSet<String>,
     ) -> Result<Self, TransformError> {
-        // Compile each enabled rule
+        Ok(Self::new_prepared(rule_set, fix_registry, enabled_fixes)?.0)
+    }
+
+    /// Like [`new`](Self::new), also returning what was worked out about
+    /// the rules, for [`with_prepared`](Self::with_prepared) to reuse.
+    pub fn new_prepared(
+        rule_set: &RuleSet,
+        fix_registry: crate::general_fixes::FixRegistry,
+        enabled_fixes: std::collections::HashSet<String>,
+    ) -> Result<(Self, PreparedRules), TransformError> {
+        let mut prepared = Vec::new();
         let compiled = rule_set
             .enabled_rules()
-            .map(|rule| match &rule.kind {
-                RuleKind::Plain {
-                    find,
-                    replace,
-                    case_sensitive,
-                } => {
-                    let case_insensitive_regex = if !case_sensitive {
-                        Some(
-                            regex::RegexBuilder::new(&regex::escape(find))
-                                .case_insensitive(true)
-                                .build()
-                                .expect("known-valid escaped regex"),
-                        )
-                    } else {
-                        None
-                    };
-                    Ok(CompiledRule::Plain {
-                        find: find.clone(),
-                        replace: replace.clone(),
-                        case_sensitive: *case_sensitive,
-                        case_insensitive_regex,
-                        id: rule.id,
-                        comment: rule.comment_fragment.clone(),
-                    })
-                }
-                RuleKind::Regex {
-                    pattern,
-                    replacement,
-                    case_insensitive,
-                } => {
-                    let regex = regex::RegexBuilder::new(pattern)
-                        .case_insensitive(*case_insensitive)
-                        .size_limit(1 << 20)
-                        .dfa_size_limit(1 << 20)
-                        .build()
-                        .map_err(|e| TransformError::InvalidRegex {
-                            rule_id: rule.id,
-                            source: e,
-                        })?;
-                    Ok(CompiledRule::Regex {
-                        regex,
-                        replacement: replacement.clone(),
-                        id: rule.id,
-                        comment: rule.comment_fragment.clone(),
-                    })
-                }
+            .map(|rule| {
+                let (line_local, prefixes) = match &rule.kind {
+                    RuleKind::Plain {
+                        find,
+                        case_sensitive,
+                        ..
+                    } => (
+                        !find.is_empty() && !find.contains('\n'),
+                        (!case_sensitive)
+                            .then(|| parse(&regex::escape(find), true))
+                            .flatten()
+                            .and_then(|hir| match_prefixes(&hir)),
+                    ),
+                    RuleKind::Regex {
+                        pattern,
+                        case_insensitive,
+                        ..
+                    } => parse(pattern, *case_insensitive).map_or((false, None), |hir| {
+                        (is_line_local(&hir), match_prefixes(&hir))
+                    }),
+                    RuleKind::JsonPatch { .. } => (false, None),
+                };
+                let rule_prep = PreparedRule {
+                    id: rule.id,
+                    line_local,
+                    prefixes,
+                };
+                let compiled = compile_rule(rule, &rule_prep, true)?;
+                prepared.push(rule_prep);
+                Ok(compiled)
             })
             .collect::<Result<Vec<_>, _>>()?;
+        let engine = Self {
+            prefilter: plain_prefilter(&compiled),
+            compiled_rules: compiled,
+            fix_registry,
+            enabled_fixes,
+            cancel: CancellationToken::new(),
+            summary_template: rule_set.summaries.resolve(None, None).map(str::to_string),
+            link_retargeting_rules: link_retargeting_rules(rule_set),
+        };
+        Ok((engine, PreparedRules { rules: prepared }))
+    }
+
+    /// Build the engine from rules prepared earlier by
+    /// [`new_prepared`](Self::new_prepared), without checking them again.
+    /// Regexes are compiled the first time they may match. If `prepared`
+    /// does not fit `rule_set`, this is [`new`](Self::new).
+    pub fn with_prepared(
+        rule_set: &RuleSet,
+        prepared: &PreparedRules,
+        fix_registry: crate::general_fixes::FixRegistry,
+        enabled_fixes: std::collections::HashSet<String>,
+    ) -> Result<Self, TransformError> {
+        if !prepared.fits(rule_set) {
+            return Self::new(rule_set, fix_registry, enabled_fixes);
+        }
+        let compiled = rule_set
+            .enabled_rules()
+            .zip(&prepared.rules)
+            .map(|(rule, rule_prep)| compile_rule(rule, rule_prep, false))
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(Self {
+            prefilter: plain_prefilter(&compiled),
             compiled_rules: compiled,
             fix_registry,
             enabled_fixes,
+            cancel: CancellationToken::new(),
+            summary_template: rule_set.summaries.resolve(None, None).map(str::to_string),
+            link_retargeting_rules: link_retargeting_rules(rule_set),
         })
     }
 
+    /// Stop transforming once `cancel` is cancelled: no further rule or
+    /// fix runs, and plugins are asked to stop. The plan of a page
+    /// transformed while cancelled is incomplete and must not be saved;
+    /// check [`is_cancelled`](Self::is_cancelled) before using it.
+    #[must_use]
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Write edit summaries from `template` (see [`awb_domain::summary`])
+    /// instead of the rule set's default one; `None` keeps the engine's
+    /// own summary.
+    #[must_use]
+    pub fn with_summary_template(mut self, template: Option<String>) -> Self {
+        self.summary_template = template;
+        self
+    }
+
+    /// Whether the engine's cancellation token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Plain rules the pre-filter skipped so far, on every page this
+    /// engine transformed; empty if it has too few plain rules for one
+    pub fn prefilter_stats(&self) -> PrefilterStats {
+        self.prefilter
+            .as_ref()
+            .map(PlainPrefilter::stats)
+            .unwrap_or_default()
+    }
+
+    /// Fix modules this engine runs, in registry order
+    pub fn enabled_fixes(&self) -> Vec<crate::general_fixes::FixInfo> {
+        self.fix_registry
+            .all_modules()
+            .iter()
+            .filter(|module| self.enabled_fixes.contains(module.id()))
+            .map(|module| crate::general_fixes::FixInfo::of(module.as_ref()))
+            .collect()
+    }
+
+    /// What the enabled fix modules report about exactly what they run; see
+    /// [`FixModule::provenance`](crate::general_fixes::FixModule::provenance)
+    pub fn provenance(&self) -> std::collections::BTreeMap<String, String> {
+        self.fix_registry
+            .all_modules()
+            .iter()
+            .filter(|module| self.enabled_fixes.contains(module.id()))
+            .flat_map(|module| module.provenance())
+            .collect()
+    }
+
+    /// How diffs and reports name the rule `id`: its edit summary comment,
+    /// or else what it looks for
+    pub fn rule_label(&self, id: uuid::Uuid) -> Option<String> {
+        self.compiled_rules
+            .iter()
+            .find(|rule| rule.id() == id)
+            .map(CompiledRule::label)
+    }
+
+    /// Classification of the fix module `id`, if the registry has it
+    pub fn fix_classification(&self, id: &str) -> Option<FixClassification> {
+        self.fix_registry
+            .all_modules()
+            .iter()
+            .find(|module| module.id() == id)
+            .map(|module| module.classification())
+    }
+
+    /// Plan the edit for `page`. Wikitext pages go through the rules and
+    /// general fixes, and each change in the plan's diff names the rule or
+    /// fix that made it; JSON pages only through `JsonPatch` rules. Pages of any
+    /// other content model (Lua modules, CSS, JavaScript) are left unchanged
+    /// with a [`Warning::UnsupportedContentModel`].
     pub fn apply(&self, page: &PageContent) -> EditPlan {
-        // Mask protected regions (nowiki, pre, code, syntaxhighlight, math,
-        // source, HTML comments, templates, File/Image links) so that
-        // neither find-and-replace rules nor general fixes can alter them.
-        let mut masked = crate::masking::mask(&page.wikitext);
+        self.apply_with_profile(page, None)
+    }
 
-        let mut rules_applied = Vec::new();
-        let mut summaries = Vec::new();
-        let mut warnings = Vec::new();
+    /// Like [`apply`](Self::apply), also reporting the memory each rule and
+    /// fix used; see [`crate::memory`]. Only wikitext pages have steps.
+    pub fn apply_profiled(&self, page: &PageContent) -> (EditPlan, MemoryProfile) {
+        let mut profile = MemoryProfile {
+            tracked: crate::memory::is_tracking(),
+            ..MemoryProfile::default()
+        };
+        let (plan, peak_bytes) =
+            crate::memory::measure(|| self.apply_with_profile(page, Some(&mut profile)));
+        profile.peak_bytes = peak_bytes;
+        (plan, profile)
+    }
 
-        // Apply rules to the masked text
-        let mut text = masked.masked.clone();
-        for rule in &self.compiled_rules {
-            let (new_text, id, comment) = match rule {
-                CompiledRule::Plain {
-                    find,
-                    replace,
-                    case_sensitive,
-                    case_insensitive_regex,
-                    id,
-                    comment,
-                } => {
-                    let new = if *case_sensitive {
-                        text.replace(find.as_str(), replace.as_str())
-                    } else {
-                        // Use pre-compiled case-insensitive regex
-                        case_insensitive_regex
-                            .as_ref()
-                            .expect(
-                                "case_insensitive_regex must be Some when case_sensitive is false",
-                            )
-                            .replace_all(&text, replace.as_str())
-                            .into_owned()
-                    };
-                    (new, *id, comment)
-                }
-                CompiledRule::Regex {
-                    regex,
-                    replacement,
-                    id,
-                    comment,
-                } => {
-                    let new = regex.replace_all(&text, replacement.as_str()).into_owned();
-                    (new, *id, comment)
+    fn apply_with_profile(
+        &self,
+        page: &PageContent,
+        profile: Option<&mut MemoryProfile>,
+    ) -> EditPlan {
+        let mut profile = profile;
+        match &page.properties.content_model {
+            ContentModel::Wikitext => {
+                let mut changes = crate::provenance::ChangeMap::new(&page.wikitext);
+                let (rules_text, rules_applied) = self.apply_rules_masked(
+                    Some(&page.title),
+                    &page.wikitext,
+                    crate::masking::MaskPolicy::DEFAULT,
+                    Some(&mut changes),
+                    profile.as_deref_mut(),
+                );
+                let (fixes_applied, final_text) = self.fix_registry.apply_all_tracking(
+                    &rules_text,
+                    &fix_context(page, &self.cancel),
+                    &self.enabled_fixes,
+                    &mut changes,
+                    profile,
+                );
+                let mut plan = self.build_plan(page, final_text, rules_applied, fixes_applied);
+                changes.annotate(&mut plan.diff_ops);
+                if let Some(warning) = self.template_loss(&page.wikitext, &plan) {
+                    plan.warnings.push(warning);
                 }
+                plan
+            }
+            ContentModel::Json => self.apply_json(page),
+            model => {
+                let mut plan = self.build_plan(page, page.wikitext.clone(), vec![], vec![]);
+                plan.warnings.push(Warning::UnsupportedContentModel {
+                    model: model.clone(),
+                });
+                plan
+            }
+        }
+    }
+
+    /// Apply the in-scope `JsonPatch` rules to a JSON page. A rule whose
+    /// operations fail (e.g. a `test` that does not hold) is skipped.
+    fn apply_json(&self, page: &PageContent) -> EditPlan {
+        let mut doc: serde_json::Value = match serde_json::from_str(&page.wikitext) {
+            Ok(doc) => doc,
+            Err(e) => {
+                let mut plan = self.build_plan(page, page.wikitext.clone(), vec![], vec![]);
+                plan.warnings.push(Warning::SuspiciousPattern {
+                    description: format!("Page is not valid JSON: {}", e),
+                });
+                return plan;
+            }
+        };
+
+        let mut rules_applied = Vec::new();
+        for rule in &self.compiled_rules {
+            let CompiledRule::JsonPatch { operations, id, .. } = rule else {
+                continue;
             };
-            if new_text != text {
-                rules_applied.push(id);
-                if let Some(c) = comment {
-                    summaries.push(c.clone());
+            if !rule.scope().matches(&page.title) {
+                continue;
+            }
+            let before = doc.clone();
+            match crate::json_patch::apply(&mut doc, operations) {
+                Ok(()) if doc != before => rules_applied.push(*id),
+                Ok(()) => {}
+                Err(e) => {
+                    tracing::debug!("JSON patch rule {} skipped on {}: {}", id, page.title, e)
                 }
-                text = new_text;
             }
         }
 
-        // Apply general fixes to masked text
-        let ctx = crate::general_fixes::FixContext {
-            title: page.title.clone(),
-            namespace: page.title.namespace,
-            is_redirect: page.is_redirect,
+        // Re-serializing would reformat the page, so only do it for a change
+        let final_text = if rules_applied.is_empty() {
+            page.wikitext.clone()
+        } else {
+            crate::json_patch::to_page_text(&doc)
         };
+        self.build_plan(page, final_text, rules_applied, vec![])
+    }
 
-        let (fixes_applied, fixed_text) =
-            self.fix_registry
-                .apply_all_returning_ids(&text, &ctx, &self.enabled_fixes);
-        text = fixed_text;
+    /// Plan the edit for a talk page on behalf of `user`: only the rules
+    /// run, and only on the page header and on comments `user` signed (see
+    /// [`editable_ranges`](crate::talk_page::editable_ranges)), with
+    /// signatures masked. Other people's comments and all signatures are
+    /// left exactly as they are, and no general fixes run.
+    pub fn apply_talk(&self, page: &PageContent, user: &str) -> EditPlan {
+        if !page.properties.content_model.is_wikitext() {
+            return self.apply(page);
+        }
+        let policy = crate::masking::MaskPolicy::DEFAULT | crate::masking::MaskPolicy::SIGNATURES;
+        let mut final_text = String::with_capacity(page.wikitext.len());
+        let mut rules_applied = Vec::new();
+        let mut copied = 0;
+        for range in crate::talk_page::editable_ranges(&page.wikitext, user) {
+            final_text.push_str(&page.wikitext[copied..range.start]);
+            let (text, ids) = self.apply_rules_masked(
+                Some(&page.title),
+                &page.wikitext[range.clone()],
+                policy,
+                None,
+                None,
+            );
+            final_text.push_str(&text);
+            for id in ids {
+                if !rules_applied.contains(&id) {
+                    rules_applied.push(id);
+                }
+            }
+            copied = range.end;
+        }
+        final_text.push_str(&page.wikitext[copied..]);
+        self.build_plan(page, final_text, rules_applied, vec![])
+    }
+
+    /// Plan the edit for a very large page: the rules run one section at a
+    /// time, so no rule works on a copy of the whole page, and no general
+    /// fixes run.
+    pub fn apply_by_section(&self, page: &PageContent) -> EditPlan {
+        if !page.properties.content_model.is_wikitext() {
+            return self.apply(page);
+        }
+        let mut final_text = String::with_capacity(page.wikitext.len());
+        let mut rules_applied = Vec::new();
+        for section in crate::incremental::split_sections(&page.wikitext) {
+            let (text, ids) = self.apply_rules(Some(&page.title), section);
+            final_text.push_str(&text);
+            for id in ids {
+                if !rules_applied.contains(&id) {
+                    rules_applied.push(id);
+                }
+            }
+        }
+        self.build_plan(page, final_text, rules_applied, vec![])
+    }
+
+    /// Plan replacing `page`'s text with `text`, e.g. as edited by hand
+    /// during review. The text gets the same warnings and checks as the
+    /// output of [`apply`](Self::apply), but no rule or fix is credited with
+    /// it, so the summary is the engine's own and the edit is never
+    /// cosmetic-only.
+    pub fn revise(&self, page: &PageContent, text: String) -> EditPlan {
+        let mut plan = self.build_plan(page, text, vec![], vec![]);
+        if page.properties.content_model.is_wikitext() {
+            if let Some(warning) = self.template_loss(&page.wikitext, &plan) {
+                plan.warnings.push(warning);
+            }
+        }
+        plan
+    }
+
+    /// Like [`apply`](Self::apply), then run rules and fixes again over the
+    /// result. If the second pass still changes the text, the plan carries a
+    /// [`Warning::NotIdempotent`] naming what fired again.
+    pub fn verify_idempotent(&self, page: &PageContent) -> EditPlan {
+        let mut plan = self.apply(page);
+        if plan.new_wikitext == page.wikitext || !page.properties.content_model.is_wikitext() {
+            return plan;
+        }
+        let (rules_text, rules) = self.apply_rules(Some(&page.title), &plan.new_wikitext);
+        let (fixes, second_pass) = self.apply_fixes(page, &rules_text);
+        if second_pass != plan.new_wikitext {
+            plan.warnings.push(Warning::NotIdempotent { rules, fixes });
+        }
+        plan
+    }
+
+    /// Run the find-and-replace rules over `text`, returning the new text and
+    /// the IDs of the rules that changed it, in order. Rules scoped away from
+    /// `title` are skipped; with no title every rule runs.
+    pub(crate) fn apply_rules(
+        &self,
+        title: Option<&Title>,
+        text: &str,
+    ) -> (String, Vec<uuid::Uuid>) {
+        // Mask protected regions (nowiki, pre, code, syntaxhighlight, math,
+        // source, HTML comments, templates, File/Image links) so that
+        // find-and-replace rules cannot alter them. General fixes mask
+        // per module, according to what each needs to see.
+        self.apply_rules_masked(title, text, crate::masking::MaskPolicy::DEFAULT, None, None)
+    }
 
+    /// [`apply_rules`](Self::apply_rules) with the regions in `policy`
+    /// protected. If `changes` is given, it is replaced with a map of
+    /// `text` recording each rule's changes, and if `profile` is, each rule
+    /// that runs is added to it.
+    fn apply_rules_masked(
+        &self,
+        title: Option<&Title>,
+        text: &str,
+        policy: crate::masking::MaskPolicy,
+        changes: Option<&mut crate::provenance::ChangeMap>,
+        mut profile: Option<&mut MemoryProfile>,
+    ) -> (String, Vec<uuid::Uuid>) {
+        let mut masked = crate::masking::mask_with(text, policy);
+        let mut rope = Rope::from(std::mem::take(&mut masked.masked));
+        // Changes are followed on the masked text, where each masked region
+        // is a single sentinel, and mapped onto the unmasked text at the end
+        let mut masked_changes = changes
+            .is_some()
+            .then(|| crate::provenance::ChangeMap::new(&rope.as_cow()));
+
+        // Which plain rules can match, rescanned after each change since a
+        // rule's replacement can create a match for a later one
+        let mut possible = self.prefilter.as_ref().map(|prefilter| {
+            prefilter.record_scan();
+            prefilter.scan(&rope)
+        });
+
+        let mut rules_applied = Vec::new();
+        let in_scope =
+            |(_, rule): &(usize, &CompiledRule)| title.is_none_or(|t| rule.scope().matches(t));
+        for (index, rule) in self.compiled_rules.iter().enumerate().filter(in_scope) {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            if let (Some(prefilter), Some(possible)) = (&self.prefilter, &possible) {
+                if !prefilter.check(index, possible) {
+                    continue;
+                }
+            }
+            let before = masked_changes.is_some().then(|| rope.clone());
+            let changed = match profile.as_deref_mut() {
+                Some(profile) => profile.step(&rule.label(), || {
+                    let changed = rule.rewrite(&mut rope);
+                    (changed, rope.len())
+                }),
+                None => rule.rewrite(&mut rope),
+            };
+            if changed {
+                rules_applied.push(rule.id());
+                if let (Some(map), Some(before)) = (masked_changes.as_mut(), before) {
+                    map.record_rope(&before, &rope, &rule.label());
+                }
+                if let Some(prefilter) = &self.prefilter {
+                    possible = Some(prefilter.scan(&rope));
+                }
+            }
+        }
+
+        masked.masked = rope.into_string();
+        let unmasked_changes = masked_changes
+            .and_then(|map| map.unmasked(&masked.unmasked_line_counts(&masked.masked)?));
         // Unmask: restore protected regions. If unmask fails (sentinel
         // missing/duplicated), it returns the original text (fail closed).
-        masked.masked = text;
-        let final_text = masked.unmask();
+        let result = masked.unmask();
+        if let (Some(changes), Some(unmasked)) = (changes, unmasked_changes) {
+            if result != text {
+                *changes = unmasked;
+            }
+        }
+        (result, rules_applied)
+    }
 
-        // Check for warnings
-        if final_text == page.wikitext {
-            warnings.push(Warning::NoChange);
-        } else {
-            let added = final_text.len().saturating_sub(page.wikitext.len());
-            let removed = page.wikitext.len().saturating_sub(final_text.len());
-            if added + removed > 500 {
-                warnings.push(Warning::LargeChange {
-                    added,
-                    removed,
-                    threshold: 500,
+    /// Run the enabled general fixes over `text`; each module masks what it
+    /// must not touch.
+    pub(crate) fn apply_fixes(&self, page: &PageContent, text: &str) -> (Vec<String>, String) {
+        self.fix_registry.apply_all_returning_ids(
+            text,
+            &fix_context(page, &self.cancel),
+            &self.enabled_fixes,
+        )
+    }
+
+    /// A [`Warning::TemplateLoss`] for the templates and parameter values
+    /// `plan` removes from `old`, unless every change behind a removal was
+    /// made by a rule that names the template or parameter. Which steps are
+    /// behind a removal comes from the origins on the plan's diff.
+    fn template_loss(&self, old: &str, plan: &EditPlan) -> Option<Warning> {
+        if plan.new_wikitext == old {
+            return None;
+        }
+        let losses = crate::template_balance::template_losses(old, &plan.new_wikitext);
+        if losses.is_empty() {
+            return None;
+        }
+        let applied: Vec<&CompiledRule> = self
+            .compiled_rules
+            .iter()
+            .filter(|rule| plan.rules_applied.contains(&rule.id()))
+            .collect();
+
+        let mut origins: Vec<String> = Vec::new();
+        // Whether the removal of `needles[0]` is explained by a rule naming
+        // one of `needles`; if not, its origins are added to the warning's
+        let mut unexplained = |needles: &[&str]| {
+            let needle = needles[0].replace('_', " ").to_lowercase();
+            let mut steps: Vec<&str> = Vec::new();
+            for op in &plan.diff_ops {
+                let removed = match op {
+                    DiffOp::Delete { text, .. } => text,
+                    DiffOp::Replace { old_text, .. } => old_text,
+                    _ => continue,
+                };
+                if !removed.replace('_', " ").to_lowercase().contains(&needle) {
+                    continue;
+                }
+                for step in op.origin().into_iter().flat_map(|o| o.split(", ")) {
+                    if !steps.contains(&step) {
+                        steps.push(step);
+                    }
+                }
+            }
+            let targeted = !steps.is_empty()
+                && steps.iter().all(|step| {
+                    applied.iter().any(|rule| {
+                        rule.label() == *step && needles.iter().any(|needle| rule.mentions(needle))
+                    })
                 });
+            if !targeted {
+                for step in steps {
+                    if !origins.iter().any(|o| o == step) {
+                        origins.push(step.to_string());
+                    }
+                }
+            }
+            !targeted
+        };
+
+        let templates: Vec<String> = losses
+            .templates
+            .into_iter()
+            .filter(|name| unexplained(&[name]))
+            .collect();
+        let parameters: Vec<String> = losses
+            .parameters
+            .into_iter()
+            .filter(|(template, param)| unexplained(&[param, template]))
+            .map(|(template, param)| format!("{}|{}", template, param))
+            .collect();
+        if templates.is_empty() && parameters.is_empty() {
+            return None;
+        }
+        Some(Warning::TemplateLoss {
+            templates,
+            parameters,
+            origins,
+        })
+    }
+
+    /// Assemble the plan for replacing `page`'s text with `final_text`.
+    pub(crate) fn build_plan(
+        &self,
+        page: &PageContent,
+        final_text: String,
+        rules_applied: Vec<uuid::Uuid>,
+        fixes_applied: Vec<String>,
+    ) -> EditPlan {
+        let mut warnings = change_warnings(&page.wikitext, &final_text);
+        let mut metadata = std::collections::BTreeMap::new();
+        let mut fragments = Vec::new();
+        if page.properties.content_model.is_wikitext() {
+            let ctx = fix_context(page, &self.cancel);
+            warnings.extend(
+                self.fix_registry
+                    .warnings(&page.wikitext, &final_text, &ctx, &self.enabled_fixes)
+                    .into_iter()
+                    .map(|(fix, message)| Warning::Fix { fix, message }),
+            );
+            metadata = self.fix_registry.page_metadata(&ctx, &self.enabled_fixes);
+            fragments = self
+                .fix_registry
+                .summary_fragments(&ctx, &self.enabled_fixes);
+            let fixes = self.fix_registry.skip_requests(&ctx, &self.enabled_fixes);
+            if !fixes.is_empty() {
+                warnings.push(Warning::SkipRequested { fixes });
+            }
+            let names = crate::references::broken_references(&page.wikitext, &final_text);
+            if !names.is_empty() {
+                warnings.push(Warning::BrokenReferences { names });
+            }
+            let retargeted = rules_applied
+                .iter()
+                .any(|id| self.link_retargeting_rules.contains(id));
+            if !retargeted {
+                let links = crate::link_targets::link_target_changes(&page.wikitext, &final_text);
+                if !links.is_empty() {
+                    warnings.push(Warning::LinkTargetsChanged {
+                        removed: links.removed,
+                        added: links.added,
+                    });
+                }
             }
         }
 
         // Compute diff
         let diff_ops = crate::diff_engine::compute_diff(&page.wikitext, &final_text);
 
-        // Build summary
-        let summary = if summaries.is_empty() {
+        // Build summary from the comments of the rules that applied and the
+        // fragments the fixes added
+        let summaries: Vec<&str> = rules_applied
+            .iter()
+            .filter_map(|id| {
+                self.compiled_rules
+                    .iter()
+                    .find(|rule| rule.id() == *id)
+                    .and_then(CompiledRule::comment)
+            })
+            .chain(fragments.iter().map(String::as_str))
+            .collect();
+        let summary = if let Some(template) = &self.summary_template {
+            let fixes = fixes_applied
+                .iter()
+                .filter_map(|id| {
+                    self.fix_registry
+                        .all_modules()
+                        .iter()
+                        .find(|module| module.id() == id)
+                        .map(|module| module.display_name().to_string())
+                })
+                .collect();
+            let values = SummaryValues {
+                rules: summaries.iter().map(|s| s.to_string()).collect(),
+                fixes,
+                count: rules_applied.len() + fixes_applied.len(),
+            };
+            awb_domain::summary::render(template, &values)
+        } else if summaries.is_empty() {
             "AWB-RS ([[WP:AWB]]) automated edit".to_string()
         } else {
             format!("AWB-RS ([[WP:AWB]]): {}", summaries.join(", "))
@@ -201,14 +1001,9 @@ impl TransformEngine {
         // An edit is cosmetic-only if no rules were applied AND all fix modules
         // that changed text have Cosmetic classification.
         let is_cosmetic_only = if rules_applied.is_empty() && !fixes_applied.is_empty() {
-            fixes_applied.iter().all(|id| {
-                self.fix_registry
-                    .all_modules()
-                    .iter()
-                    .find(|m| m.id() == id)
-                    .map(|m| m.classification() == crate::fix_config::FixClassification::Cosmetic)
-                    .unwrap_or(false)
-            })
+            fixes_applied
+                .iter()
+                .all(|id| self.fix_classification(id) == Some(FixClassification::Cosmetic))
         } else if !rules_applied.is_empty() {
             // User rules are never cosmetic
             false
@@ -226,8 +1021,144 @@ impl TransformEngine {
             summary,
             warnings,
             is_cosmetic_only,
+            metadata,
+        }
+    }
+}
+
+/// Ids of the enabled rules in `rule_set` that change link targets on purpose
+fn link_retargeting_rules(rule_set: &RuleSet) -> Vec<uuid::Uuid> {
+    rule_set
+        .enabled_rules()
+        .filter(|rule| rule.retargets_links)
+        .map(|rule| rule.id)
+        .collect()
+}
+
+/// The pre-filter for `rules`' plain rules, if there are enough of them
+fn plain_prefilter(rules: &[CompiledRule]) -> Option<PlainPrefilter> {
+    let plain = rules
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| match rule {
+            CompiledRule::Plain {
+                find,
+                case_sensitive,
+                ..
+            } => Some((index, find.as_str(), *case_sensitive)),
+            _ => None,
+        });
+    PlainPrefilter::new(rules.len(), plain)
+}
+
+/// Compile `rule` with what `prepared` says about it. With `validate` its
+/// patterns are compiled now, and an invalid one is an error; without, its
+/// regex waits for its first use.
+fn compile_rule(
+    rule: &Rule,
+    prepared: &PreparedRule,
+    validate: bool,
+) -> Result<CompiledRule, TransformError> {
+    let scope = RuleScope::compile(rule)?;
+    let lazy = |pattern: String, case_insensitive: bool| {
+        if validate {
+            LazyRegex::compiled(pattern, case_insensitive).map_err(|e| {
+                TransformError::InvalidRegex {
+                    rule_id: rule.id,
+                    source: e,
+                }
+            })
+        } else {
+            Ok(LazyRegex::deferred(pattern, case_insensitive))
+        }
+    };
+    Ok(match &rule.kind {
+        RuleKind::Plain {
+            find,
+            replace,
+            case_sensitive,
+        } => CompiledRule::Plain {
+            find: find.clone(),
+            replace: replace.clone(),
+            case_sensitive: *case_sensitive,
+            case_insensitive_regex: (!case_sensitive)
+                .then(|| lazy(regex::escape(find), true))
+                .transpose()?,
+            line_local: prepared.line_local,
+            prefixes: prepared.prefixes.clone(),
+            id: rule.id,
+            comment: rule.comment_fragment.clone(),
+            scope,
+        },
+        RuleKind::Regex {
+            pattern,
+            replacement,
+            case_insensitive,
+        } => CompiledRule::Regex {
+            regex: lazy(pattern.clone(), *case_insensitive)?,
+            replacement: replacement.clone(),
+            line_local: prepared.line_local,
+            prefixes: prepared.prefixes.clone(),
+            id: rule.id,
+            comment: rule.comment_fragment.clone(),
+            scope,
+        },
+        RuleKind::JsonPatch { operations } => CompiledRule::JsonPatch {
+            operations: operations.clone(),
+            id: rule.id,
+            comment: rule.comment_fragment.clone(),
+            scope,
+        },
+    })
+}
+
+fn fix_context(page: &PageContent, cancel: &CancellationToken) -> crate::general_fixes::FixContext {
+    crate::general_fixes::FixContext {
+        title: page.title.clone(),
+        namespace: page.title.namespace,
+        is_redirect: page.is_redirect,
+        revision: Some(page.revision),
+        cancel: cancel.clone(),
+    }
+}
+
+/// Apply only `rule_set`'s rules to `text`, without general fixes, e.g. to
+/// preview rules while they are being edited. Namespace and title scoping is
+/// ignored.
+pub fn preview_rules(rule_set: &RuleSet, text: &str) -> Result<String, TransformError> {
+    let engine = TransformEngine::new(
+        rule_set,
+        crate::general_fixes::FixRegistry::new(),
+        std::collections::HashSet::new(),
+    )?;
+    Ok(engine.apply_rules(None, text).0)
+}
+
+/// Whether saving `new` over `current` would leave the page as it is.
+/// MediaWiki strips trailing whitespace before storing a revision, so text
+/// that differs only there is a null edit too: the save creates no revision
+/// but still counts against the account's edit rate limit.
+pub fn is_null_edit(current: &str, new: &str) -> bool {
+    current.trim_end() == new.trim_end()
+}
+
+/// Warnings about replacing `old` with `new`: no change, or a large one.
+pub(crate) fn change_warnings(old: &str, new: &str) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    if is_null_edit(old, new) {
+        warnings.push(Warning::NoChange);
+    } else {
+        let added = new.len().saturating_sub(old.len());
+        let removed = old.len().saturating_sub(new.len());
+        if added + removed > 500 {
+            warnings.push(Warning::LargeChange {
+                added,
+                removed,
+                threshold: 500,
+            });
         }
     }
+    warnings
 }
 
 #[cfg(test)]
@@ -311,6 +1242,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_preview_rules() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        // Masked regions are left alone, and no general fixes run
+        assert_eq!(
+            preview_rules(&ruleset, "teh  cat <!-- teh -->").unwrap(),
+            "the  cat <!-- teh -->"
+        );
+
+        ruleset.add(Rule::new_regex(r"[invalid(", "x", false));
+        assert!(preview_rules(&ruleset, "teh").is_err());
+    }
+
     #[test]
     fn test_transform_engine_multiple_rules() {
         let mut ruleset = RuleSet::new();
@@ -327,6 +1272,68 @@ mod tests {
         assert_eq!(plan.rules_applied.len(), 2);
     }
 
+    #[test]
+    fn test_verify_idempotent() {
+        let mut rule_set = RuleSet::new();
+        rule_set.add(Rule::new_plain("teh", "the", true));
+        let engine = TransformEngine::new(
+            &rule_set,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+        let plan = engine.verify_idempotent(&create_test_page("teh cat"));
+        assert_eq!(plan.new_wikitext, "the cat");
+        assert!(
+            !plan
+                .warnings
+                .iter()
+                .any(|w| matches!(w, Warning::NotIdempotent { .. }))
+        );
+
+        let mut rule_set = RuleSet::new();
+        let growing = Rule::new_plain("a", "aa", true);
+        let growing_id = growing.id;
+        rule_set.add(growing);
+        let engine = TransformEngine::new(
+            &rule_set,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+        let plan = engine.verify_idempotent(&create_test_page("a"));
+        assert_eq!(plan.new_wikitext, "aa");
+        assert!(plan.warnings.iter().any(|w| matches!(
+            w,
+            Warning::NotIdempotent { rules, .. } if rules == &vec![growing_id]
+        )));
+    }
+
+    #[test]
+    fn test_heading_warnings_do_not_outlive_their_plan() {
+        let enabled = HashSet::from(["heading_normalization".to_string()]);
+        let engine = TransformEngine::new(
+            &RuleSet::new(),
+            crate::general_fixes::FixRegistry::with_defaults(),
+            enabled,
+        )
+        .unwrap();
+        let heading_warnings = |plan: &EditPlan| {
+            plan.warnings
+                .iter()
+                .filter(|w| matches!(w, Warning::Fix { fix, .. } if fix == "heading_normalization"))
+                .count()
+        };
+
+        let page = create_test_page("== Early Life ==\nText.\n");
+        let plan = engine.verify_idempotent(&page);
+        assert_eq!(heading_warnings(&plan), 1);
+        // The second pass of the check leaves nothing behind for later plans
+        assert_eq!(heading_warnings(&engine.apply(&page)), 1);
+        let done = create_test_page(&plan.new_wikitext);
+        assert_eq!(heading_warnings(&engine.apply(&done)), 0);
+    }
+
     #[test]
     fn test_transform_engine_no_change_warning() {
         let ruleset = RuleSet::new();
@@ -340,6 +1347,21 @@ mod tests {
         assert!(plan.warnings.iter().any(|w| matches!(w, Warning::NoChange)));
     }
 
+    #[test]
+    fn test_is_null_edit_ignores_trailing_whitespace() {
+        assert!(is_null_edit("text", "text"));
+        assert!(is_null_edit("text\n", "text"));
+        assert!(is_null_edit("text", "text  \n\n"));
+        assert!(!is_null_edit("text", " text"));
+        assert!(!is_null_edit("a\n\nb", "a\nb"));
+
+        assert!(
+            change_warnings("text", "text\n")
+                .iter()
+                .any(|w| matches!(w, Warning::NoChange))
+        );
+    }
+
     #[test]
     fn test_transform_engine_large_change_warning() {
         let mut ruleset = RuleSet::new();
@@ -375,6 +1397,332 @@ mod tests {
         assert!(plan.fixes_applied.len() > 0);
     }
 
+    #[test]
+    fn test_plan_carries_fix_warnings() {
+        let config = crate::fix_config::FixConfig {
+            person_sort_key: crate::fix_config::PersonSortKey::InfoboxOrTitle,
+            ..Default::default()
+        };
+        let registry = crate::general_fixes::FixRegistry::with_config(&config);
+        let enabled = registry.enabled_ids(&config).unwrap();
+        let engine = TransformEngine::new(&RuleSet::new(), registry, enabled).unwrap();
+
+        let mut page = create_test_page("Painter.\n[[Category:1890 deaths]]");
+        page.title = Title::new(Namespace::MAIN, "Vincent van Gogh");
+        let plan = engine.apply(&page);
+        assert!(!plan.new_wikitext.contains("DEFAULTSORT"));
+        assert!(plan.warnings.iter().any(|w| matches!(
+            w,
+            Warning::Fix { fix, .. } if fix == "defaultsort_fix"
+        )));
+    }
+
+    #[test]
+    fn test_diff_names_the_rule_or_fix_behind_each_change() {
+        let mut ruleset = RuleSet::new();
+        let mut teh = Rule::new_plain("teh", "the", true);
+        teh.comment_fragment = Some("typo: teh".to_string());
+        ruleset.add(teh);
+        ruleset.add(Rule::new_regex(r"colou?r", "colour", false));
+        let mut enabled = HashSet::new();
+        enabled.insert("trailing_whitespace".to_string());
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::with_defaults(),
+            enabled,
+        )
+        .unwrap();
+
+        let page = create_test_page(
+            "teh {{cite|teh}} color
+kept
+teh end   
+end
+",
+        );
+        let plan = engine.apply(&page);
+        assert_eq!(
+            plan.new_wikitext,
+            "the {{cite|teh}} colour
+kept
+the end
+end
+"
+        );
+
+        let changed: Vec<(&str, Option<&str>)> = plan
+            .diff_ops
+            .iter()
+            .filter_map(|op| match op {
+                awb_domain::diff::DiffOp::Insert { text, .. } => Some((text.as_str(), op.origin())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                ("the {{cite|teh}} colour\n", Some("typo: teh, colou?r")),
+                ("the end\n", Some("typo: teh, trailing_whitespace")),
+            ]
+        );
+        assert!(
+            plan.diff_ops
+                .iter()
+                .filter(|op| matches!(op, awb_domain::diff::DiffOp::Delete { .. }))
+                .all(|op| op.origin().is_some())
+        );
+    }
+
+    #[test]
+    fn test_is_line_local() {
+        let line_local =
+            |pattern: &str, ci: bool| parse(pattern, ci).is_some_and(|h| is_line_local(&h));
+        assert!(line_local(r"colou?r", false));
+        assert!(line_local(r"\bteh\b", true));
+        assert!(line_local(r"(?m)^[^\n]+ end$", false));
+        assert!(!line_local(r"[^\n]+ end$", false));
+        assert!(!line_local(r"\s+", false));
+        assert!(!line_local(r"a[^x]b", false));
+        assert!(!line_local(r"^\z", false));
+        assert!(!line_local(r"x*", false));
+        assert!(!line_local(r"(?s)a.b", false));
+        assert!(!line_local(r"(unclosed", false));
+    }
+
+    #[test]
+    fn test_match_prefixes() {
+        let prefixes =
+            |pattern: &str, ci: bool| parse(pattern, ci).and_then(|h| match_prefixes(&h));
+        assert_eq!(
+            prefixes(r"\bcolou?r\b", false),
+            Some(vec!["color".to_string(), "colour".to_string()])
+        );
+        assert_eq!(
+            prefixes(r"(?:cat|dog)s?", false),
+            Some(vec!["cat".to_string(), "dog".to_string()])
+        );
+        assert_eq!(prefixes(r"te", true).map(|p| p.len()), Some(4));
+        assert_eq!(prefixes(r"\w+ing", false), None);
+        assert_eq!(prefixes(r"x*", false), None);
+    }
+
+    #[test]
+    fn test_prepared_engine_matches_and_compiles_lazily() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        ruleset.add(Rule::new_plain("Recieve", "receive", false));
+        ruleset.add(Rule::new_regex(r"\bcolou?r\b", "colour", false));
+        ruleset.add(Rule::new_regex(r"\bkilometres?\b", "km", false));
+        let registry = || crate::general_fixes::FixRegistry::new();
+        let (engine, prepared) =
+            TransformEngine::new_prepared(&ruleset, registry(), HashSet::new()).unwrap();
+        let json = serde_json::to_string(&prepared).unwrap();
+        let prepared: PreparedRules = serde_json::from_str(&json).unwrap();
+        let lazy = TransformEngine::with_prepared(&ruleset, &prepared, registry(), HashSet::new())
+            .unwrap();
+
+        let compiled = |engine: &TransformEngine| {
+            engine
+                .compiled_rules
+                .iter()
+                .filter(|rule| match rule {
+                    CompiledRule::Regex { regex, .. } => regex.regex.get().is_some(),
+                    CompiledRule::Plain {
+                        case_insensitive_regex: Some(regex),
+                        ..
+                    } => regex.regex.get().is_some(),
+                    _ => false,
+                })
+                .count()
+        };
+        assert_eq!(compiled(&engine), 3);
+        assert_eq!(compiled(&lazy), 0);
+
+        let page = create_test_page("teh color, RECIEVE it\n");
+        assert_eq!(
+            lazy.apply(&page).new_wikitext,
+            engine.apply(&page).new_wikitext
+        );
+        assert_eq!(lazy.apply(&page).new_wikitext, "the colour, receive it\n");
+        // The page never mentions kilometres, so that regex is not needed
+        assert_eq!(compiled(&lazy), 2);
+    }
+
+    #[test]
+    fn test_prepared_rules_for_other_rules_are_not_used() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_regex(r"colou?r", "colour", false));
+        let (_, prepared) = TransformEngine::new_prepared(
+            &ruleset,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+
+        let mut other = RuleSet::new();
+        other.add(Rule::new_regex(r"(unclosed", "x", false));
+        let result = TransformEngine::with_prepared(
+            &other,
+            &prepared,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        );
+        assert!(matches!(result, Err(TransformError::InvalidRegex { .. })));
+    }
+
+    #[test]
+    fn test_prefilter_skips_plain_rules_without_changing_output() {
+        let mut ruleset = RuleSet::new();
+        // Makes a match for the "teh" rule that runs after it
+        ruleset.add(Rule::new_plain("hte", "teh", true));
+        for i in 0..20 {
+            ruleset.add(Rule::new_plain(format!("typo{i:02}"), "x", true));
+        }
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        ruleset.add(Rule::new_plain("Colour", "colour", false));
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+        assert!(engine.prefilter.is_some());
+        assert!(engine.prefilter_stats().is_empty());
+
+        let plan = engine.apply(&create_test_page("hte COLOUR typo07\n"));
+        assert_eq!(plan.new_wikitext, "the colour x\n");
+        assert_eq!(plan.rules_applied.len(), 4);
+
+        let stats = engine.prefilter_stats();
+        assert_eq!(stats.scans, 1);
+        assert_eq!(stats.rules_checked, 23);
+        assert_eq!(stats.rules_skipped, 19);
+    }
+
+    #[test]
+    fn test_apply_profiled_lists_each_rule_and_fix() {
+        let mut ruleset = RuleSet::new();
+        let mut grow = Rule::new_plain("x", "xxxxxxxxxx", true);
+        grow.comment_fragment = Some("grow".to_string());
+        ruleset.add(grow);
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        let mut enabled = HashSet::new();
+        enabled.insert("trailing_whitespace".to_string());
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::with_defaults(),
+            enabled,
+        )
+        .unwrap();
+
+        let page = create_test_page("xxx teh   \n");
+        let (plan, profile) = engine.apply_profiled(&page);
+        assert_eq!(plan.new_wikitext, engine.apply(&page).new_wikitext);
+        let steps: Vec<(&str, usize)> = profile
+            .steps
+            .iter()
+            .map(|step| (step.step.as_str(), step.output_bytes))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![("grow", 38), ("teh", 38), ("trailing_whitespace", 35)]
+        );
+        assert_eq!(profile.largest_text_bytes, 38);
+    }
+
+    #[test]
+    fn test_cancelled_engine_stops_between_steps() {
+        /// Cancels the run, as Ctrl-C would while it transforms the page
+        struct Interrupt;
+        impl crate::general_fixes::FixModule for Interrupt {
+            fn id(&self) -> &str {
+                "interrupt"
+            }
+            fn display_name(&self) -> &str {
+                "Interrupt"
+            }
+            fn category(&self) -> &str {
+                "Test"
+            }
+            fn description(&self) -> &str {
+                "Cancels the run"
+            }
+            fn runs_before(&self) -> &[&str] {
+                &["trailing_whitespace"]
+            }
+            fn apply<'a>(
+                &self,
+                text: &'a str,
+                context: &crate::general_fixes::FixContext,
+            ) -> Cow<'a, str> {
+                context.cancel.cancel();
+                Cow::Borrowed(text)
+            }
+        }
+
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        let mut registry = crate::general_fixes::FixRegistry::with_defaults();
+        registry.register(Box::new(Interrupt)).unwrap();
+        let enabled: HashSet<String> = ["interrupt", "trailing_whitespace"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let cancel = CancellationToken::new();
+        let engine = TransformEngine::new(&ruleset, registry, enabled)
+            .unwrap()
+            .with_cancellation(cancel.clone());
+
+        // The rules ran, then no fix after the interrupt
+        let page = create_test_page("teh   \n");
+        assert_eq!(engine.apply(&page).new_wikitext, "the   \n");
+        assert!(engine.is_cancelled());
+
+        // Once cancelled, nothing runs at all
+        assert_eq!(engine.apply(&page).new_wikitext, "teh   \n");
+        assert!(cancel.is_cancelled());
+    }
+
+    #[test]
+    fn test_large_page_keeps_provenance_across_chunks() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        // Can match a line break, so it runs on the whole text
+        ruleset.add(Rule::new_regex(r"end\s+fin", "end fin", false));
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::with_defaults(),
+            HashSet::new(),
+        )
+        .unwrap();
+
+        let filler = "Some ordinary text here.\n".repeat(2000);
+        let wikitext =
+            format!("teh start\n{filler}{{{{cite|teh}}}} teh middle\n{filler}end\nfin\n");
+        let plan = engine.apply(&create_test_page(&wikitext));
+        assert_eq!(
+            plan.new_wikitext,
+            format!("the start\n{filler}{{{{cite|teh}}}} the middle\n{filler}end fin\n")
+        );
+
+        let changed: Vec<(&str, Option<&str>)> = plan
+            .diff_ops
+            .iter()
+            .filter_map(|op| match op {
+                awb_domain::diff::DiffOp::Insert { text, .. } => Some((text.as_str(), op.origin())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                ("the start\n", Some("teh")),
+                ("{{cite|teh}} the middle\n", Some("teh")),
+                ("end fin\n", Some("end\\s+fin")),
+            ]
+        );
+    }
+
     #[test]
     fn test_masking_protects_nowiki() {
         let mut ruleset = RuleSet::new();
@@ -435,4 +1783,361 @@ mod tests {
         assert_eq!(plan.new_wikitext, "test text");
         assert_eq!(plan.rules_applied.len(), 0);
     }
+
+    #[test]
+    fn test_rule_namespace_scope() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("foo", "bar", true).with_namespaces([Namespace::TEMPLATE]));
+        ruleset.add(Rule::new_plain("baz", "qux", true));
+
+        let registry = crate::general_fixes::FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let plan = engine.apply(&create_test_page("foo baz"));
+        assert_eq!(plan.new_wikitext, "foo qux");
+        assert_eq!(plan.rules_applied.len(), 1);
+
+        let mut template = create_test_page("foo baz");
+        template.title = Title::new(Namespace::TEMPLATE, "Infobox");
+        let plan = engine.apply(&template);
+        assert_eq!(plan.new_wikitext, "bar qux");
+        assert_eq!(plan.rules_applied.len(), 2);
+    }
+
+    #[test]
+    fn test_rule_title_scope() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("foo", "bar", true).with_title_pattern("^List of "));
+
+        let registry = crate::general_fixes::FixRegistry::new();
+        let engine = TransformEngine::new(&ruleset, registry, HashSet::new()).unwrap();
+
+        let plan = engine.apply(&create_test_page("foo"));
+        assert_eq!(plan.new_wikitext, "foo");
+
+        let mut list = create_test_page("foo");
+        list.title = Title::new(Namespace::MAIN, "List of birds");
+        assert_eq!(engine.apply(&list).new_wikitext, "bar");
+
+        // Previews ignore scoping
+        assert_eq!(preview_rules(&ruleset, "foo").unwrap(), "bar");
+    }
+
+    #[test]
+    fn test_invalid_title_pattern() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("a", "b", true).with_title_pattern("(unclosed"));
+
+        let registry = crate::general_fixes::FixRegistry::new();
+        let result = TransformEngine::new(&ruleset, registry, HashSet::new());
+        assert!(matches!(
+            result,
+            Err(TransformError::InvalidTitlePattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_json_page_gets_only_json_patch_rules() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("CC0", "CC-BY", true));
+        ruleset.add(
+            Rule::new_json_patch(vec![JsonPatchOp::Replace {
+                path: "/license".to_string(),
+                value: serde_json::json!("CC0-1.0"),
+            }])
+            .with_namespaces([Namespace(486)]),
+        );
+        let mut fixes = HashSet::new();
+        fixes.insert("whitespace_cleanup".to_string());
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::with_defaults(),
+            fixes,
+        )
+        .unwrap();
+
+        let mut page = create_test_page("{\"license\": \"CC0\",  \"data\": []}");
+        page.title = Title::new(Namespace(486), "Population.tab");
+        page.properties.content_model = ContentModel::Json;
+        let plan = engine.apply(&page);
+
+        assert_eq!(
+            plan.new_wikitext,
+            "{\n    \"license\": \"CC0-1.0\",\n    \"data\": []\n}"
+        );
+        assert_eq!(plan.rules_applied.len(), 1);
+        assert!(plan.fixes_applied.is_empty());
+
+        // Out of scope, so the page is left byte for byte as it was
+        page.title = Title::new(Namespace::MAIN, "Population.json");
+        let plan = engine.apply(&page);
+        assert_eq!(plan.new_wikitext, page.wikitext);
+        assert!(plan.warnings.iter().any(|w| matches!(w, Warning::NoChange)));
+    }
+
+    #[test]
+    fn test_unsupported_content_model_left_alone() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+
+        let mut page = create_test_page("-- teh module\nreturn {}");
+        page.properties.content_model = ContentModel::Scribunto;
+        let plan = engine.verify_idempotent(&page);
+
+        assert_eq!(plan.new_wikitext, page.wikitext);
+        assert!(plan.warnings.iter().any(|w| matches!(
+            w,
+            Warning::UnsupportedContentModel { model } if *model == ContentModel::Scribunto
+        )));
+    }
+
+    #[test]
+    fn test_talk_plan_only_touches_own_comments() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        ruleset.add(Rule::new_plain("Ann", "Anne", true));
+        let mut fixes = HashSet::new();
+        fixes.insert("whitespace_cleanup".to_string());
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::with_defaults(),
+            fixes,
+        )
+        .unwrap();
+
+        let text = "teh header\n== Cats ==\n\
+                    Ann said teh  cat. [[User:Ann|Ann]] ([[User talk:Ann|talk]]) 09:15, 3 May 2024 (UTC)\n\
+                    :teh reply [[User:Bob|Bob]] 10:00, 3 May 2024 (UTC)\n";
+        let mut page = create_test_page(text);
+        page.title = Title::new(Namespace::TALK, "Cats");
+        let plan = engine.apply_talk(&page, "Ann");
+
+        assert_eq!(
+            plan.new_wikitext,
+            "the header\n== Cats ==\n\
+             Anne said the  cat. [[User:Ann|Ann]] ([[User talk:Ann|talk]]) 09:15, 3 May 2024 (UTC)\n\
+             :teh reply [[User:Bob|Bob]] 10:00, 3 May 2024 (UTC)\n"
+        );
+        assert_eq!(plan.rules_applied.len(), 2);
+        assert!(plan.fixes_applied.is_empty());
+
+        // As someone else, only the header changes
+        let plan = engine.apply_talk(&page, "Carol");
+        assert_eq!(plan.new_wikitext, text.replacen("teh", "the", 1));
+    }
+
+    #[test]
+    fn test_apply_by_section_matches_rules_without_fixes() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        let mut fixes = HashSet::new();
+        fixes.insert("whitespace_cleanup".to_string());
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::with_defaults(),
+            fixes,
+        )
+        .unwrap();
+
+        let page = create_test_page("teh lead\n== A ==\nteh  a\n== B ==\nteh b\n");
+        let plan = engine.apply_by_section(&page);
+        assert_eq!(
+            plan.new_wikitext,
+            "the lead\n== A ==\nthe  a\n== B ==\nthe b\n"
+        );
+        assert_eq!(plan.rules_applied.len(), 1);
+        assert!(plan.fixes_applied.is_empty());
+    }
+
+    #[test]
+    fn test_summary_template() {
+        let mut ruleset = RuleSet::new();
+        let mut teh = Rule::new_plain("teh", "the", true);
+        teh.comment_fragment = Some("typo".to_string());
+        ruleset.add(teh);
+        ruleset.add(Rule::new_plain("colour", "color", true));
+        ruleset.summaries.default = Some("Bot: {rules}; {fixes} ({count})".to_string());
+        let mut enabled = HashSet::new();
+        enabled.insert("trailing_whitespace".to_string());
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::with_defaults(),
+            enabled,
+        )
+        .unwrap();
+
+        let page = create_test_page("teh colour   \n");
+        let plan = engine.apply(&page);
+        assert_eq!(plan.summary, "Bot: typo; Trailing Whitespace (3)");
+
+        // A per-wiki template replaces the default, and None restores the
+        // engine's own summary
+        let engine = engine.with_summary_template(Some("Robô: {rules}".to_string()));
+        assert_eq!(engine.apply(&page).summary, "Robô: typo");
+        let engine = engine.with_summary_template(None);
+        assert_eq!(engine.apply(&page).summary, "AWB-RS ([[WP:AWB]]): typo");
+    }
+
+    #[test]
+    fn test_broken_references_warning() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_regex(
+            r#"<ref name="smith">[^<]*</ref>"#,
+            "",
+            true,
+        ));
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+
+        let page =
+            create_test_page("A.<ref name=\"smith\">Smith.</ref> B.<ref name=\"smith\" />\n");
+        let plan = engine.apply(&page);
+        assert!(plan.warnings.iter().any(|w| matches!(
+            w,
+            Warning::BrokenReferences { names } if names == &vec!["smith".to_string()]
+        )));
+    }
+
+    #[test]
+    fn test_revise_checks_manual_text() {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("Teh", "The", true));
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+
+        let page = create_test_page(
+            "Teh fact.<ref name=\"smith\">Smith.</ref> More.<ref name=\"smith\" />\n",
+        );
+        let plan = engine.apply(&page);
+        assert_eq!(plan.rules_applied.len(), 1);
+
+        // The user deletes the definition while fixing the typo by hand
+        let plan = engine.revise(&page, "The fact. More.<ref name=\"smith\" />\n".to_string());
+        assert!(plan.rules_applied.is_empty());
+        assert!(plan.fixes_applied.is_empty());
+        assert_eq!(plan.summary, "AWB-RS ([[WP:AWB]]) automated edit");
+        assert!(plan.warnings.iter().any(|w| matches!(
+            w,
+            Warning::BrokenReferences { names } if names == &vec!["smith".to_string()]
+        )));
+    }
+
+    #[test]
+    fn test_link_targets_changed_warning() {
+        // Meant to shorten the label, but drops the target
+        let rule = Rule::new_regex(r"\[\[[^|\]]+\|(Foo)\]\]", "[[$1]]", false);
+        let page = create_test_page("[[Foo (band)|Foo]] released [[Foo (album)|an album]].\n");
+        let link_changes = |rule: &Rule| {
+            let mut ruleset = RuleSet::new();
+            ruleset.add(rule.clone());
+            let engine = TransformEngine::new(
+                &ruleset,
+                crate::general_fixes::FixRegistry::new(),
+                HashSet::new(),
+            )
+            .unwrap();
+            engine
+                .apply(&page)
+                .warnings
+                .into_iter()
+                .find(|w| matches!(w, Warning::LinkTargetsChanged { .. }))
+        };
+
+        match link_changes(&rule) {
+            Some(Warning::LinkTargetsChanged { removed, added }) => {
+                assert_eq!(removed, vec!["Foo (band)"]);
+                assert_eq!(added, vec!["Foo"]);
+            }
+            other => panic!("Expected LinkTargetsChanged warning, got {:?}", other),
+        }
+
+        // A rule marked as retargeting links may
+        assert!(link_changes(&rule.with_retargets_links(true)).is_none());
+    }
+
+    #[test]
+    fn test_template_loss_warning_names_the_fix() {
+        /// Drops `{{cn}}` and an infobox line, as a buggy plugin might
+        struct DropTemplates;
+        impl crate::general_fixes::FixModule for DropTemplates {
+            fn id(&self) -> &str {
+                "drop_templates"
+            }
+            fn display_name(&self) -> &str {
+                "Drop templates"
+            }
+            fn category(&self) -> &str {
+                "Test"
+            }
+            fn description(&self) -> &str {
+                "Removes template content"
+            }
+            fn required_visibility(&self) -> crate::masking::MaskPolicy {
+                crate::masking::MaskPolicy::TEMPLATES
+            }
+            fn apply<'a>(
+                &self,
+                text: &'a str,
+                _context: &crate::general_fixes::FixContext,
+            ) -> Cow<'a, str> {
+                Cow::Owned(text.replace("{{cn}}", "").replace("| born = 1815\n", ""))
+            }
+        }
+
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule::new_plain("teh", "the", true));
+        let mut registry = crate::general_fixes::FixRegistry::new();
+        registry.register(Box::new(DropTemplates)).unwrap();
+        let enabled = HashSet::from(["drop_templates".to_string()]);
+        let engine = TransformEngine::new(&ruleset, registry, enabled).unwrap();
+
+        let page = create_test_page("{{Infobox person\n| born = 1815\n}}\nteh text.{{cn}}\n");
+        let plan = engine.apply(&page);
+        assert_eq!(plan.new_wikitext, "{{Infobox person\n}}\nthe text.\n");
+        let loss = plan
+            .warnings
+            .iter()
+            .find(|w| matches!(w, Warning::TemplateLoss { .. }))
+            .unwrap();
+        let Warning::TemplateLoss {
+            templates,
+            parameters,
+            origins,
+        } = loss
+        else {
+            unreachable!()
+        };
+        assert_eq!(templates, &vec!["Cn".to_string()]);
+        assert_eq!(parameters, &vec!["Infobox person|born".to_string()]);
+        assert!(origins.iter().any(|o| o == "drop_templates"));
+
+        // The rule alone loses nothing
+        let engine = TransformEngine::new(
+            &ruleset,
+            crate::general_fixes::FixRegistry::new(),
+            HashSet::new(),
+        )
+        .unwrap();
+        assert!(
+            !engine
+                .apply(&page)
+                .warnings
+                .iter()
+                .any(|w| matches!(w, Warning::TemplateLoss { .. }))
+        );
+    }
 }
@@ -191,6 +191,8 @@ mod tests {
             title: Title::new(Namespace::MAIN, "Test"),
             namespace: Namespace::MAIN,
             is_redirect: false,
+            revision: None,
+            cancel: Default::default(),
         }
     }
 
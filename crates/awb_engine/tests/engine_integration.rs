@@ -143,6 +143,7 @@ fn test_review_state_machine_complete_cycle() {
             summary: format!("Edit {}", i + 1),
             warnings: vec![],
             is_cosmetic_only: false,
+            metadata: Default::default(),
         };
         let effects = machine.transition(ReviewEvent::RulesApplied(plan.clone()));
         assert!(matches!(
@@ -191,6 +192,8 @@ fn test_fix_registry_with_all_default_fixes() {
         title: Title::new(Namespace::MAIN, "Test Article"),
         namespace: Namespace::MAIN,
         is_redirect: false,
+        revision: None,
+        cancel: Default::default(),
     };
 
     // Test content with various issues
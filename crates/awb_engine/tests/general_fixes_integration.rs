@@ -10,6 +10,8 @@ fn test_context(title_name: &str) -> FixContext {
         title: Title::new(Namespace::MAIN, title_name),
         namespace: Namespace::MAIN,
         is_redirect: false,
+        revision: None,
+        cancel: Default::default(),
     }
 }
 
@@ -113,7 +115,7 @@ fn test_unicode_normalization_curly_quotes_in_template() {
 
 #[test]
 fn test_defaultsort_adds_for_diacritics() {
-    let fix = DefaultSortFix;
+    let fix = DefaultSortFix::default();
     let ctx = test_context("Café");
 
     let input = "This is an article about cafés.\n[[Category:Restaurants]]";
@@ -125,7 +127,7 @@ fn test_defaultsort_adds_for_diacritics() {
 
 #[test]
 fn test_defaultsort_skips_if_already_present() {
-    let fix = DefaultSortFix;
+    let fix = DefaultSortFix::default();
     let ctx = test_context("Café");
 
     let input = "{{DEFAULTSORT:Custom}}\n[[Category:Restaurants]]";
@@ -137,7 +139,7 @@ fn test_defaultsort_skips_if_already_present() {
 
 #[test]
 fn test_defaultsort_skips_ascii_only() {
-    let fix = DefaultSortFix;
+    let fix = DefaultSortFix::default();
     let ctx = test_context("Cafe");
 
     let input = "This is an article.\n[[Category:Restaurants]]";
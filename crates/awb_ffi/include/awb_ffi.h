@@ -0,0 +1,226 @@
+/* AWB-RS C API. Generated by cbindgen from crates/awb_ffi; do not edit. */
+
+#ifndef AWB_FFI_H
+#define AWB_FFI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * ABI version implemented by the `awb_v1_*` functions
+ */
+#define AWB_ABI_VERSION 1
+
+/**
+ * Result of a v1 call. Values are fixed for the lifetime of the ABI.
+ */
+typedef enum AwbStatus {
+  AWB_STATUS_OK = 0,
+  AWB_STATUS_INVALID_ARGUMENT = 1,
+  AWB_STATUS_NETWORK_ERROR = 2,
+  AWB_STATUS_AUTHENTICATION_ERROR = 3,
+  AWB_STATUS_NOT_FOUND = 4,
+  AWB_STATUS_PERMISSION_DENIED = 5,
+  AWB_STATUS_PARSE_ERROR = 6,
+  AWB_STATUS_SESSION_NOT_FOUND = 7,
+  AWB_STATUS_LOCK_POISONED = 8,
+  AWB_STATUS_ENGINE_ERROR = 9,
+  AWB_STATUS_PLUGIN_ERROR = 10,
+  AWB_STATUS_RATE_LIMITED = 11,
+  AWB_STATUS_EDIT_CONFLICT = 12,
+  AWB_STATUS_BLOCKED = 13,
+  AWB_STATUS_TOKEN_EXPIRED = 14,
+  AWB_STATUS_PROTECTION = 15,
+  /**
+   * Not a failure: the content matched the current revision, so no edit
+   * was sent
+   */
+  AWB_STATUS_NO_CHANGE = 16,
+} AwbStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns the library version string. Caller must free with awb_free_string().
+ */
+const char *awb_version(void);
+
+/**
+ * Frees a string previously returned by awb_version() or other C API functions.
+ *
+ * # Safety
+ *
+ * The caller must ensure that:
+ * - `ptr` is either null or was previously returned by `awb_version()`
+ * - `ptr` has not been freed or modified since it was returned
+ * - This function is only called once per pointer
+ */
+void awb_free_string(char *ptr);
+
+/**
+ * Returns `AWB_ABI_VERSION` as compiled into the library.
+ */
+uint32_t awb_v1_abi_version(void);
+
+/**
+ * Creates a session and writes its handle to `out_handle`.
+ *
+ * # Safety
+ * String arguments must be null or valid NUL-terminated strings, and
+ * `out_handle` must be null or valid for writes.
+ */
+AwbStatus awb_v1_session_create(const char *wiki_url,
+                                const char *username,
+                                const char *password,
+                                uint64_t *out_handle);
+
+/**
+ * Destroys a session.
+ */
+AwbStatus awb_v1_session_destroy(uint64_t handle);
+
+/**
+ * Logs in with the session's stored credentials.
+ */
+AwbStatus awb_v1_login(uint64_t handle);
+
+/**
+ * Re-fetches the session's edit token.
+ */
+AwbStatus awb_v1_refresh_session(uint64_t handle);
+
+/**
+ * Fetches page titles from `source` (`category`, `search` or `backlinks`)
+ * and writes them to `out_json` as a JSON array of strings.
+ *
+ * # Safety
+ * String arguments must be null or valid NUL-terminated strings, and
+ * `out_json` must be null or valid for writes.
+ */
+AwbStatus awb_v1_fetch_list(uint64_t handle,
+                            const char *source,
+                            const char *query,
+                            char **out_json);
+
+/**
+ * Fetches a page and writes it to `out_json` as a JSON object.
+ *
+ * # Safety
+ * `title` must be null or a valid NUL-terminated string, and `out_json`
+ * must be null or valid for writes.
+ */
+AwbStatus awb_v1_get_page(uint64_t handle, const char *title, char **out_json);
+
+/**
+ * Saves a page. Returns `NoChange` without editing if `content` matches
+ * the current revision.
+ *
+ * # Safety
+ * String arguments must be null or valid NUL-terminated strings.
+ */
+AwbStatus awb_v1_save_page(uint64_t handle,
+                           const char *title,
+                           const char *content,
+                           const char *summary);
+
+/**
+ * Applies the JSON rule set and enabled plugins to `content`, writing the
+ * transform result to `out_json` as a JSON object.
+ *
+ * # Safety
+ * String arguments must be null or valid NUL-terminated strings, and
+ * `out_json` must be null or valid for writes.
+ */
+AwbStatus awb_v1_apply_rules(uint64_t handle,
+                             const char *content,
+                             const char *rules_json,
+                             char **out_json);
+
+/**
+ * Like `awb_v1_apply_rules`, but also runs the general fixes selected by
+ * the JSON fix config.
+ *
+ * # Safety
+ * String arguments must be null or valid NUL-terminated strings, and
+ * `out_json` must be null or valid for writes.
+ */
+AwbStatus awb_v1_apply_rules_with_config(uint64_t handle,
+                                         const char *content,
+                                         const char *rules_json,
+                                         const char *fix_config_json,
+                                         char **out_json);
+
+/**
+ * Writes the available general fixes to `out_json` as a JSON array.
+ *
+ * # Safety
+ * `out_json` must be null or valid for writes.
+ */
+AwbStatus awb_v1_list_fixes(char **out_json);
+
+/**
+ * Writes an HTML diff of the two texts to `out_html`.
+ *
+ * # Safety
+ * String arguments must be null or valid NUL-terminated strings, and
+ * `out_html` must be null or valid for writes.
+ */
+AwbStatus awb_v1_compute_diff(const char *old_text, const char *new_text, char **out_html);
+
+/**
+ * Replaces the loaded plugins with those in `dir`, writing how many were
+ * loaded to `out_count`.
+ *
+ * # Safety
+ * `dir` must be null or a valid NUL-terminated string, and `out_count`
+ * must be null or valid for writes.
+ */
+AwbStatus awb_v1_load_plugins(const char *dir, uint32_t *out_count);
+
+/**
+ * Writes the loaded plugins to `out_json` as a JSON array.
+ *
+ * # Safety
+ * `out_json` must be null or valid for writes.
+ */
+AwbStatus awb_v1_list_plugins(char **out_json);
+
+/**
+ * Enables or disables a loaded plugin. Enabling fails if another enabled
+ * plugin shares its exclusive group.
+ *
+ * # Safety
+ * `name` must be null or a valid NUL-terminated string.
+ */
+AwbStatus awb_v1_set_plugin_enabled(const char *name, bool enabled);
+
+/**
+ * Returns the message of the last failed call on this thread, or null if
+ * the last call succeeded. Free with `awb_v1_string_free`.
+ */
+char *awb_v1_last_error_message(void);
+
+/**
+ * Returns the seconds to wait before retrying after `AWB_STATUS_RATE_LIMITED`,
+ * or 0.
+ */
+uint64_t awb_v1_last_error_retry_after(void);
+
+/**
+ * Frees a string returned by a v1 function.
+ *
+ * # Safety
+ * `ptr` must be null or a string returned by a v1 function that has not
+ * been freed yet.
+ */
+void awb_v1_string_free(char *ptr);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* AWB_FFI_H */
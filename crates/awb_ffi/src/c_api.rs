@@ -41,7 +41,7 @@ use crate::{
 };
 use crate::{create_session as ffi_create_session, destroy_session as ffi_destroy_session};
 use crate::{get_page as ffi_get_page, login as ffi_login, save_page as ffi_save_page};
-use crate::{PageInfo, SessionHandle, TransformResult};
+use crate::{PageInfo, SaveOutcome, SessionHandle, TransformResult};
 use std::ffi::CStr;
 
 /// Creates a new session handle.
@@ -151,7 +151,9 @@ pub unsafe extern "C" fn get_page(handle: SessionHandle, title: *const c_char) -
     }
 }
 
-/// Saves a page with the specified content and summary.
+/// Saves a page with the specified content and summary. Returns 0 on
+/// success, 1 if the content matched the current revision and no edit was
+/// sent, and -1 on failure.
 ///
 /// # Safety
 /// Caller must ensure all string parameters are valid UTF-8.
@@ -182,7 +184,8 @@ pub unsafe extern "C" fn save_page(
     };
 
     match ffi_save_page(handle, title_str, content_str, summary_str) {
-        Ok(_) => 0,
+        Ok(SaveOutcome::Saved) => 0,
+        Ok(SaveOutcome::NoChange) => 1,
         Err(_) => -1,
     }
 }
@@ -291,3 +294,546 @@ pub unsafe extern "C" fn awb_free_string_vec(ptr: *mut Vec<String>) {
         let _ = Box::from_raw(ptr);
     }
 }
+
+// ============================================================================
+// Versioned C API (v1)
+// ============================================================================
+//
+// The `awb_v1_*` functions are the stable C ABI, declared in
+// include/awb_ffi.h (generated by cbindgen from cbindgen.toml). Within ABI
+// version 1 their signatures and behavior do not change: new functions are
+// added alongside, and incompatible changes get an `awb_v2_` prefix.
+//
+// Every function returns an `AwbStatus`. After a failure,
+// `awb_v1_last_error_message` and `awb_v1_last_error_retry_after` describe
+// it on the same thread. Structured results are written to out-parameters
+// as JSON strings, freed with `awb_v1_string_free`.
+
+use crate::{
+    apply_rules_with_config as ffi_apply_rules_with_config, list_fixes as ffi_list_fixes,
+    list_plugins as ffi_list_plugins, load_plugins as ffi_load_plugins,
+    refresh_session as ffi_refresh_session, set_plugin_enabled as ffi_set_plugin_enabled, FfiError,
+};
+use std::cell::RefCell;
+
+/// ABI version implemented by the `awb_v1_*` functions
+pub const AWB_ABI_VERSION: u32 = 1;
+
+/// Result of a v1 call. Values are fixed for the lifetime of the ABI.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AwbStatus {
+    Ok = 0,
+    InvalidArgument = 1,
+    NetworkError = 2,
+    AuthenticationError = 3,
+    NotFound = 4,
+    PermissionDenied = 5,
+    ParseError = 6,
+    SessionNotFound = 7,
+    LockPoisoned = 8,
+    EngineError = 9,
+    PluginError = 10,
+    RateLimited = 11,
+    EditConflict = 12,
+    Blocked = 13,
+    TokenExpired = 14,
+    Protection = 15,
+    /// Not a failure: the content matched the current revision, so no edit
+    /// was sent
+    NoChange = 16,
+}
+
+/// A failed call, kept per thread for the `awb_v1_last_error_*` functions
+struct Failure {
+    status: AwbStatus,
+    message: String,
+    retry_after: u64,
+}
+
+impl Failure {
+    fn invalid(message: impl Into<String>) -> Self {
+        Self {
+            status: AwbStatus::InvalidArgument,
+            message: message.into(),
+            retry_after: 0,
+        }
+    }
+}
+
+impl From<FfiError> for Failure {
+    fn from(error: FfiError) -> Self {
+        let message = error.to_string();
+        let (status, retry_after) = match error {
+            FfiError::NetworkError { .. } => (AwbStatus::NetworkError, 0),
+            FfiError::AuthenticationError => (AwbStatus::AuthenticationError, 0),
+            FfiError::NotFound => (AwbStatus::NotFound, 0),
+            FfiError::PermissionDenied => (AwbStatus::PermissionDenied, 0),
+            FfiError::ParseError { .. } => (AwbStatus::ParseError, 0),
+            FfiError::SessionNotFound => (AwbStatus::SessionNotFound, 0),
+            FfiError::LockPoisoned => (AwbStatus::LockPoisoned, 0),
+            FfiError::EngineError { .. } => (AwbStatus::EngineError, 0),
+            FfiError::PluginError { .. } => (AwbStatus::PluginError, 0),
+            FfiError::RateLimited { retry_after } => (AwbStatus::RateLimited, retry_after),
+            FfiError::EditConflict => (AwbStatus::EditConflict, 0),
+            FfiError::Blocked { .. } => (AwbStatus::Blocked, 0),
+            FfiError::TokenExpired => (AwbStatus::TokenExpired, 0),
+            FfiError::Protection { .. } => (AwbStatus::Protection, 0),
+        };
+        Self {
+            status,
+            message,
+            retry_after,
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<Failure>> = const { RefCell::new(None) };
+}
+
+/// Record the outcome of a call for `awb_v1_last_error_*` and return its status
+fn finish(result: Result<(), Failure>) -> AwbStatus {
+    let (status, failure) = match result {
+        Ok(()) => (AwbStatus::Ok, None),
+        Err(failure) => (failure.status, Some(failure)),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = failure);
+    status
+}
+
+fn run(call: impl FnOnce() -> Result<(), Failure>) -> AwbStatus {
+    finish(call())
+}
+
+unsafe fn read_str(ptr: *const c_char, name: &str) -> Result<String, Failure> {
+    if ptr.is_null() {
+        return Err(Failure::invalid(format!("{} is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(str::to_string)
+        .map_err(|_| Failure::invalid(format!("{} is not valid UTF-8", name)))
+}
+
+fn check_out<T>(out: *mut T) -> Result<(), Failure> {
+    if out.is_null() {
+        Err(Failure::invalid("output pointer is null"))
+    } else {
+        Ok(())
+    }
+}
+
+unsafe fn write_string(out: *mut *mut c_char, value: String) -> Result<(), Failure> {
+    check_out(out)?;
+    let c_string =
+        CString::new(value).map_err(|_| Failure::invalid("result contains a NUL byte"))?;
+    out.write(c_string.into_raw());
+    Ok(())
+}
+
+unsafe fn write_json<T: serde::Serialize>(out: *mut *mut c_char, value: &T) -> Result<(), Failure> {
+    check_out(out)?;
+    let json = serde_json::to_string(value).map_err(|e| Failure {
+        status: AwbStatus::ParseError,
+        message: e.to_string(),
+        retry_after: 0,
+    })?;
+    write_string(out, json)
+}
+
+/// Returns `AWB_ABI_VERSION` as compiled into the library.
+#[unsafe(no_mangle)]
+pub extern "C" fn awb_v1_abi_version() -> u32 {
+    AWB_ABI_VERSION
+}
+
+/// Creates a session and writes its handle to `out_handle`.
+///
+/// # Safety
+/// String arguments must be null or valid NUL-terminated strings, and
+/// `out_handle` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_session_create(
+    wiki_url: *const c_char,
+    username: *const c_char,
+    password: *const c_char,
+    out_handle: *mut u64,
+) -> AwbStatus {
+    run(|| {
+        check_out(out_handle)?;
+        let handle = ffi_create_session(
+            read_str(wiki_url, "wiki_url")?,
+            read_str(username, "username")?,
+            read_str(password, "password")?,
+        )?;
+        out_handle.write(handle.id);
+        Ok(())
+    })
+}
+
+/// Destroys a session.
+#[unsafe(no_mangle)]
+pub extern "C" fn awb_v1_session_destroy(handle: u64) -> AwbStatus {
+    finish(ffi_destroy_session(SessionHandle { id: handle }).map_err(Failure::from))
+}
+
+/// Logs in with the session's stored credentials.
+#[unsafe(no_mangle)]
+pub extern "C" fn awb_v1_login(handle: u64) -> AwbStatus {
+    finish(ffi_login(SessionHandle { id: handle }).map_err(Failure::from))
+}
+
+/// Re-fetches the session's edit token.
+#[unsafe(no_mangle)]
+pub extern "C" fn awb_v1_refresh_session(handle: u64) -> AwbStatus {
+    finish(ffi_refresh_session(SessionHandle { id: handle }).map_err(Failure::from))
+}
+
+/// Fetches page titles from `source` (`category`, `search` or `backlinks`)
+/// and writes them to `out_json` as a JSON array of strings.
+///
+/// # Safety
+/// String arguments must be null or valid NUL-terminated strings, and
+/// `out_json` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_fetch_list(
+    handle: u64,
+    source: *const c_char,
+    query: *const c_char,
+    out_json: *mut *mut c_char,
+) -> AwbStatus {
+    run(|| {
+        check_out(out_json)?;
+        let titles = ffi_fetch_list(
+            SessionHandle { id: handle },
+            read_str(source, "source")?,
+            read_str(query, "query")?,
+        )?;
+        write_json(out_json, &titles)
+    })
+}
+
+/// Fetches a page and writes it to `out_json` as a JSON object.
+///
+/// # Safety
+/// `title` must be null or a valid NUL-terminated string, and `out_json`
+/// must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_get_page(
+    handle: u64,
+    title: *const c_char,
+    out_json: *mut *mut c_char,
+) -> AwbStatus {
+    run(|| {
+        check_out(out_json)?;
+        let page = ffi_get_page(SessionHandle { id: handle }, read_str(title, "title")?)?;
+        write_json(out_json, &page)
+    })
+}
+
+/// Saves a page. Returns `NoChange` without editing if `content` matches
+/// the current revision.
+///
+/// # Safety
+/// String arguments must be null or valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_save_page(
+    handle: u64,
+    title: *const c_char,
+    content: *const c_char,
+    summary: *const c_char,
+) -> AwbStatus {
+    let mut outcome = SaveOutcome::Saved;
+    let status = run(|| {
+        outcome = ffi_save_page(
+            SessionHandle { id: handle },
+            read_str(title, "title")?,
+            read_str(content, "content")?,
+            read_str(summary, "summary")?,
+        )?;
+        Ok(())
+    });
+    match outcome {
+        SaveOutcome::NoChange => AwbStatus::NoChange,
+        SaveOutcome::Saved => status,
+    }
+}
+
+/// Applies the JSON rule set and enabled plugins to `content`, writing the
+/// transform result to `out_json` as a JSON object.
+///
+/// # Safety
+/// String arguments must be null or valid NUL-terminated strings, and
+/// `out_json` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_apply_rules(
+    handle: u64,
+    content: *const c_char,
+    rules_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> AwbStatus {
+    run(|| {
+        check_out(out_json)?;
+        let result = ffi_apply_rules(
+            SessionHandle { id: handle },
+            read_str(content, "content")?,
+            read_str(rules_json, "rules_json")?,
+        )?;
+        write_json(out_json, &result)
+    })
+}
+
+/// Like `awb_v1_apply_rules`, but also runs the general fixes selected by
+/// the JSON fix config.
+///
+/// # Safety
+/// String arguments must be null or valid NUL-terminated strings, and
+/// `out_json` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_apply_rules_with_config(
+    handle: u64,
+    content: *const c_char,
+    rules_json: *const c_char,
+    fix_config_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> AwbStatus {
+    run(|| {
+        check_out(out_json)?;
+        let result = ffi_apply_rules_with_config(
+            SessionHandle { id: handle },
+            read_str(content, "content")?,
+            read_str(rules_json, "rules_json")?,
+            read_str(fix_config_json, "fix_config_json")?,
+        )?;
+        write_json(out_json, &result)
+    })
+}
+
+/// Writes the available general fixes to `out_json` as a JSON array.
+///
+/// # Safety
+/// `out_json` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_list_fixes(out_json: *mut *mut c_char) -> AwbStatus {
+    finish(write_json(out_json, &ffi_list_fixes()))
+}
+
+/// Writes an HTML diff of the two texts to `out_html`.
+///
+/// # Safety
+/// String arguments must be null or valid NUL-terminated strings, and
+/// `out_html` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_compute_diff(
+    old_text: *const c_char,
+    new_text: *const c_char,
+    out_html: *mut *mut c_char,
+) -> AwbStatus {
+    run(|| {
+        check_out(out_html)?;
+        let html = ffi_compute_diff(
+            read_str(old_text, "old_text")?,
+            read_str(new_text, "new_text")?,
+        );
+        write_string(out_html, html)
+    })
+}
+
+/// Replaces the loaded plugins with those in `dir`, writing how many were
+/// loaded to `out_count`.
+///
+/// # Safety
+/// `dir` must be null or a valid NUL-terminated string, and `out_count`
+/// must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_load_plugins(dir: *const c_char, out_count: *mut u32) -> AwbStatus {
+    run(|| {
+        check_out(out_count)?;
+        let count = ffi_load_plugins(read_str(dir, "dir")?)?;
+        out_count.write(count);
+        Ok(())
+    })
+}
+
+/// Writes the loaded plugins to `out_json` as a JSON array.
+///
+/// # Safety
+/// `out_json` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_list_plugins(out_json: *mut *mut c_char) -> AwbStatus {
+    finish(write_json(out_json, &ffi_list_plugins()))
+}
+
+/// Enables or disables a loaded plugin. Enabling fails if another enabled
+/// plugin shares its exclusive group.
+///
+/// # Safety
+/// `name` must be null or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_set_plugin_enabled(
+    name: *const c_char,
+    enabled: bool,
+) -> AwbStatus {
+    run(|| {
+        ffi_set_plugin_enabled(read_str(name, "name")?, enabled)?;
+        Ok(())
+    })
+}
+
+/// Returns the message of the last failed call on this thread, or null if
+/// the last call succeeded. Free with `awb_v1_string_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn awb_v1_last_error_message() -> *mut c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .and_then(|failure| CString::new(failure.message.clone()).ok())
+            .map_or(std::ptr::null_mut(), CString::into_raw)
+    })
+}
+
+/// Returns the seconds to wait before retrying after `AWB_STATUS_RATE_LIMITED`,
+/// or 0.
+#[unsafe(no_mangle)]
+pub extern "C" fn awb_v1_last_error_retry_after() -> u64 {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(0, |failure| failure.retry_after)
+    })
+}
+
+/// Frees a string returned by a v1 function.
+///
+/// # Safety
+/// `ptr` must be null or a string returned by a v1 function that has not
+/// been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn awb_v1_string_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        let _ = CString::from_raw(ptr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take_string(ptr: *mut c_char) -> String {
+        let value = CStr::from_ptr(ptr).to_str().unwrap().to_string();
+        awb_v1_string_free(ptr);
+        value
+    }
+
+    #[test]
+    fn test_v1_compute_diff() {
+        let old = CString::new("a\nb").unwrap();
+        let new = CString::new("a\nc").unwrap();
+        let mut html = std::ptr::null_mut();
+        unsafe {
+            let status = awb_v1_compute_diff(old.as_ptr(), new.as_ptr(), &mut html);
+            assert_eq!(status, AwbStatus::Ok);
+            assert!(take_string(html).contains("<div class='diff'>"));
+        }
+        assert!(awb_v1_last_error_message().is_null());
+    }
+
+    #[test]
+    fn test_v1_null_argument_sets_last_error() {
+        let mut html = std::ptr::null_mut();
+        unsafe {
+            let new = CString::new("a").unwrap();
+            let status = awb_v1_compute_diff(std::ptr::null(), new.as_ptr(), &mut html);
+            assert_eq!(status, AwbStatus::InvalidArgument);
+            assert!(html.is_null());
+            assert!(take_string(awb_v1_last_error_message()).contains("old_text"));
+        }
+    }
+
+    #[test]
+    fn test_v1_session_and_apply_rules() {
+        let url = CString::new("https://en.wikipedia.org/w/api.php").unwrap();
+        let user = CString::new("user").unwrap();
+        let pass = CString::new("pass").unwrap();
+        let mut handle = 0;
+        unsafe {
+            let status =
+                awb_v1_session_create(url.as_ptr(), user.as_ptr(), pass.as_ptr(), &mut handle);
+            assert_eq!(status, AwbStatus::Ok);
+
+            let content = CString::new("Text   \n").unwrap();
+            let rules = CString::new(r#"{"rules":[]}"#).unwrap();
+            let config = CString::new(r#"{"strictness_tier":0}"#).unwrap();
+            let mut json = std::ptr::null_mut();
+            let status = awb_v1_apply_rules_with_config(
+                handle,
+                content.as_ptr(),
+                rules.as_ptr(),
+                config.as_ptr(),
+                &mut json,
+            );
+            assert_eq!(status, AwbStatus::Ok);
+            let result: serde_json::Value = serde_json::from_str(&take_string(json)).unwrap();
+            assert!(!result["fixes_applied"].as_array().unwrap().is_empty());
+
+            assert_eq!(awb_v1_session_destroy(handle), AwbStatus::Ok);
+            assert_eq!(awb_v1_session_destroy(handle), AwbStatus::SessionNotFound);
+        }
+    }
+
+    #[test]
+    fn test_v1_list_fixes_json() {
+        let mut json = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(awb_v1_list_fixes(&mut json), AwbStatus::Ok);
+            let fixes: serde_json::Value = serde_json::from_str(&take_string(json)).unwrap();
+            assert!(fixes
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|fix| fix["id"] == "whitespace_cleanup"));
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_retry_after_reported() {
+        let status = finish(Err(FfiError::RateLimited { retry_after: 42 }.into()));
+        assert_eq!(status, AwbStatus::RateLimited);
+        assert_eq!(awb_v1_last_error_retry_after(), 42);
+    }
+
+    #[test]
+    fn test_header_declares_v1_api() {
+        let header = include_str!("../include/awb_ffi.h");
+        assert!(header.contains(&format!("#define AWB_ABI_VERSION {}", AWB_ABI_VERSION)));
+        for symbol in [
+            "awb_v1_abi_version",
+            "awb_v1_session_create",
+            "awb_v1_session_destroy",
+            "awb_v1_login",
+            "awb_v1_refresh_session",
+            "awb_v1_fetch_list",
+            "awb_v1_get_page",
+            "awb_v1_save_page",
+            "awb_v1_apply_rules",
+            "awb_v1_apply_rules_with_config",
+            "awb_v1_list_fixes",
+            "awb_v1_compute_diff",
+            "awb_v1_load_plugins",
+            "awb_v1_list_plugins",
+            "awb_v1_set_plugin_enabled",
+            "awb_v1_last_error_message",
+            "awb_v1_last_error_retry_after",
+            "awb_v1_string_free",
+        ] {
+            assert!(
+                header.contains(&format!("{}(", symbol)),
+                "{} missing",
+                symbol
+            );
+        }
+        assert!(header.contains("AWB_STATUS_PROTECTION = 15"));
+        assert!(header.contains("AWB_STATUS_NO_CHANGE = 16"));
+    }
+}
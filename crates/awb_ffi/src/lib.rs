@@ -2,17 +2,23 @@
 #![allow(clippy::empty_line_after_doc_comments)]
 
 pub mod c_api;
+mod plugins;
 
 use awb_domain::profile::ThrottlePolicy;
 use awb_domain::rules::RuleSet;
+use awb_domain::session::EditPlan;
+use awb_domain::siteinfo::SiteInfo;
 use awb_domain::types::*;
 use awb_engine::diff_engine;
+use awb_engine::fix_config::{FixClassification, FixConfig};
 use awb_engine::general_fixes::FixRegistry;
-use awb_engine::transform::TransformEngine;
-use awb_mw_api::client::{EditRequest, MediaWikiClient, ReqwestMwClient};
+use awb_engine::transform::{is_null_edit, TransformEngine};
+use awb_mw_api::client::{AssertMode, EditRequest, MediaWikiClient, ReqwestMwClient};
+use awb_mw_api::error::MwApiError;
 use parking_lot::Mutex;
+use plugins::{SharedPlugins, PLUGINS_FIX_ID};
 use secrecy::SecretString;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use url::Url;
 
@@ -23,6 +29,7 @@ pub struct SessionHandle {
     pub id: u64,
 }
 
+#[derive(serde::Serialize)]
 pub struct PageInfo {
     pub page_id: u64,
     pub title: String,
@@ -33,6 +40,7 @@ pub struct PageInfo {
     pub is_redirect: bool,
 }
 
+#[derive(serde::Serialize)]
 pub struct TransformResult {
     pub new_wikitext: String,
     pub rules_applied: Vec<String>,
@@ -42,24 +50,130 @@ pub struct TransformResult {
     pub diff_html: String,
 }
 
+/// What [`save_page`] did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveOutcome {
+    Saved,
+    /// The content matched the current revision, so no edit was sent
+    NoChange,
+}
+
+#[derive(serde::Serialize)]
+pub struct FixInfo {
+    pub id: String,
+    pub display_name: String,
+    pub category: String,
+    pub description: String,
+    /// One of `cosmetic`, `maintenance`, `style_sensitive`, `editorial`
+    pub classification: String,
+    pub min_tier: u8,
+    pub default_enabled: bool,
+}
+
+impl From<awb_engine::general_fixes::FixInfo> for FixInfo {
+    fn from(info: awb_engine::general_fixes::FixInfo) -> Self {
+        Self {
+            id: info.id,
+            display_name: info.display_name,
+            category: info.category,
+            description: info.description,
+            classification: classification_name(info.classification).to_string(),
+            min_tier: info.min_tier,
+            default_enabled: info.default_enabled,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    /// One of `cosmetic`, `maintenance`, `style_sensitive`, `editorial`
+    pub classification: String,
+    pub min_tier: u8,
+}
+
+fn classification_name(classification: FixClassification) -> &'static str {
+    match classification {
+        FixClassification::Cosmetic => "cosmetic",
+        FixClassification::Maintenance => "maintenance",
+        FixClassification::StyleSensitive => "style_sensitive",
+        FixClassification::Editorial => "editorial",
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum FfiError {
-    #[error("Network error: {0}")]
-    NetworkError(String),
+    #[error("Network error: {message}")]
+    NetworkError { message: String },
     #[error("Authentication failed")]
     AuthenticationError,
     #[error("Resource not found")]
     NotFound,
     #[error("Permission denied")]
     PermissionDenied,
-    #[error("Parse error: {0}")]
-    ParseError(String),
+    #[error("Parse error: {message}")]
+    ParseError { message: String },
     #[error("Session not found")]
     SessionNotFound,
     #[error("Lock poisoned")]
     LockPoisoned,
-    #[error("Engine error: {0}")]
-    EngineError(String),
+    #[error("Engine error: {message}")]
+    EngineError { message: String },
+    #[error("Plugin error: {message}")]
+    PluginError { message: String },
+    #[error("Rate limited: retry after {retry_after}s")]
+    RateLimited { retry_after: u64 },
+    #[error("Edit conflict")]
+    EditConflict,
+    #[error("Blocked: {reason}")]
+    Blocked { reason: String },
+    #[error("Token expired")]
+    TokenExpired,
+    #[error("Page is protected: {reason}")]
+    Protection { reason: String },
+}
+
+/// Map an API failure to the matching structured error, falling back to a
+/// `NetworkError` prefixed with `context`.
+fn api_error(context: &str, error: MwApiError) -> FfiError {
+    match error {
+        MwApiError::RateLimited { retry_after } | MwApiError::MaxLag { retry_after } => {
+            FfiError::RateLimited { retry_after }
+        }
+        MwApiError::EditConflict { .. } => FfiError::EditConflict,
+        MwApiError::BadToken => FfiError::TokenExpired,
+        MwApiError::AuthError { .. } => FfiError::AuthenticationError,
+        MwApiError::ApiError { code, info } => match code.as_str() {
+            "blocked" | "autoblocked" | "globalblocking-blockedtext" => {
+                FfiError::Blocked { reason: info }
+            }
+            "protectedpage"
+            | "cascadeprotected"
+            | "protectedtitle"
+            | "protectednamespace"
+            | "protectednamespace-interface" => FfiError::Protection { reason: info },
+            "missingtitle" | "nosuchpageid" => FfiError::NotFound,
+            "permissiondenied" => FfiError::PermissionDenied,
+            "badtoken" => FfiError::TokenExpired,
+            "editconflict" => FfiError::EditConflict,
+            "assertuserfailed" | "assertbotfailed" | "notloggedin" => FfiError::AuthenticationError,
+            _ => FfiError::NetworkError {
+                message: format!("{}: {}", context, MwApiError::ApiError { code, info }),
+            },
+        },
+        other => FfiError::NetworkError {
+            message: format!("{}: {}", context, other),
+        },
+    }
+}
+
+/// Callbacks from the Rust side about a session
+pub trait SessionListener: Send + Sync {
+    /// The session's login has expired or was revoked; log in again with a
+    /// new session.
+    fn relogin_required(&self, handle: SessionHandle, reason: String);
 }
 
 // Session storage with API client
@@ -68,12 +182,16 @@ struct Session {
     username: String,
     password: Option<SecretString>,
     client: Option<Arc<ReqwestMwClient>>,
+    /// Title rules of the wiki, fetched at login
+    site: SiteInfo,
     authenticated: bool,
 }
 
 lazy_static::lazy_static! {
     static ref SESSIONS: Arc<Mutex<HashMap<u64, Session>>> = Arc::new(Mutex::new(HashMap::new()));
     static ref NEXT_SESSION_ID: Arc<Mutex<u64>> = Arc::new(Mutex::new(1));
+    static ref PLUGINS: SharedPlugins = SharedPlugins::default();
+    static ref SESSION_LISTENER: Mutex<Option<Arc<dyn SessionListener>>> = Mutex::new(None);
     static ref TOKIO_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
@@ -88,20 +206,23 @@ pub fn create_session(
 ) -> Result<SessionHandle, FfiError> {
     // Validate that wiki_url is not empty
     if wiki_url.trim().is_empty() {
-        return Err(FfiError::ParseError("wiki_url cannot be empty".to_string()));
+        return Err(FfiError::ParseError {
+            message: "wiki_url cannot be empty".to_string(),
+        });
     }
 
     // Parse the URL
-    let parsed_url = Url::parse(&wiki_url)
-        .map_err(|e| FfiError::ParseError(format!("Invalid wiki URL: {}", e)))?;
+    let parsed_url = Url::parse(&wiki_url).map_err(|e| FfiError::ParseError {
+        message: format!("Invalid wiki URL: {}", e),
+    })?;
 
     let mut sessions = SESSIONS.lock();
     let mut next_id = NEXT_SESSION_ID.lock();
 
     let id = *next_id;
-    *next_id = next_id
-        .checked_add(1)
-        .ok_or(FfiError::EngineError("session ID overflow".into()))?;
+    *next_id = next_id.checked_add(1).ok_or(FfiError::EngineError {
+        message: "session ID overflow".into(),
+    })?;
 
     sessions.insert(
         id,
@@ -110,6 +231,7 @@ pub fn create_session(
             username,
             password: Some(SecretString::new(password.into())),
             client: None,
+            site: SiteInfo::default(),
             authenticated: false,
         },
     );
@@ -133,7 +255,10 @@ pub fn login(handle: SessionHandle) -> Result<(), FfiError> {
 
     // Create the API client if not already created
     let client = ReqwestMwClient::new(session.wiki_url.clone(), ThrottlePolicy::default())
-        .map_err(|e| FfiError::NetworkError(format!("Failed to create API client: {}", e)))?;
+        .map_err(|e| FfiError::NetworkError {
+            message: format!("Failed to create API client: {}", e),
+        })?
+        .with_assert(AssertMode::User);
 
     let client = Arc::new(client);
 
@@ -156,12 +281,18 @@ pub fn login(handle: SessionHandle) -> Result<(), FfiError> {
                 .login_bot_password(&username, password.expose_secret())
                 .await
         })
-        .map_err(|e| FfiError::NetworkError(format!("Login failed: {}", e)))?;
+        .map_err(|e| api_error("Login failed", e))?;
 
     // Fetch CSRF token
     TOKIO_RUNTIME
         .block_on(async { client.fetch_csrf_token().await })
-        .map_err(|e| FfiError::NetworkError(format!("Failed to fetch CSRF token: {}", e)))?;
+        .map_err(|e| api_error("Failed to fetch CSRF token", e))?;
+
+    // Without siteinfo, titles are still read with the default English
+    // namespace names
+    if let Ok(site) = TOKIO_RUNTIME.block_on(async { client.fetch_siteinfo().await }) {
+        session.site = site;
+    }
 
     session.client = Some(client);
     session.authenticated = true;
@@ -169,6 +300,64 @@ pub fn login(handle: SessionHandle) -> Result<(), FfiError> {
     Ok(())
 }
 
+/// Register the listener told when a session needs to log in again,
+/// replacing any previous one.
+pub fn set_session_listener(listener: Box<dyn SessionListener>) {
+    *SESSION_LISTENER.lock() = Some(Arc::from(listener));
+}
+
+/// Re-fetch the session's CSRF token, e.g. after the app has been idle.
+///
+/// If the login has expired the session listener is notified and
+/// `AuthenticationError` is returned.
+pub fn refresh_session(handle: SessionHandle) -> Result<(), FfiError> {
+    let sessions = SESSIONS.lock();
+    let session = sessions.get(&handle.id).ok_or(FfiError::SessionNotFound)?;
+
+    if !session.authenticated {
+        return Err(FfiError::AuthenticationError);
+    }
+
+    let client = session
+        .client
+        .as_ref()
+        .ok_or(FfiError::AuthenticationError)?
+        .clone();
+
+    drop(sessions); // Release lock before async operation
+
+    TOKIO_RUNTIME
+        .block_on(async { client.fetch_csrf_token().await })
+        .map_err(|e| session_error(handle, api_error("Failed to refresh session", e)))?;
+    Ok(())
+}
+
+/// Canonicalize a title given by the app with the session's wiki rules
+fn parse_title(site: &SiteInfo, title: &str) -> Result<Title, FfiError> {
+    Title::parse(site, title).map_err(|e| FfiError::ParseError {
+        message: e.to_string(),
+    })
+}
+
+/// Pass `error` through, first marking the session logged out and notifying
+/// the listener if it means the login is gone.
+fn session_error(handle: SessionHandle, error: FfiError) -> FfiError {
+    if matches!(
+        error,
+        FfiError::AuthenticationError | FfiError::TokenExpired
+    ) {
+        if let Some(session) = SESSIONS.lock().get_mut(&handle.id) {
+            session.authenticated = false;
+        }
+        // Clone out so the listener may replace itself
+        let listener = SESSION_LISTENER.lock().clone();
+        if let Some(listener) = listener {
+            listener.relogin_required(handle, error.to_string());
+        }
+    }
+    error
+}
+
 pub fn fetch_list(
     handle: SessionHandle,
     source: String,
@@ -198,13 +387,13 @@ pub fn fetch_list(
                 "category" => client.list_category_members(&query, limit).await,
                 "search" => client.search_pages(&query, limit).await,
                 "backlinks" => client.get_backlinks(&query, limit).await,
-                _ => Err(awb_mw_api::error::MwApiError::ApiError {
+                _ => Err(MwApiError::ApiError {
                     code: "invalid_source".into(),
                     info: format!("Unknown list source: {}", source),
                 }),
             }
         })
-        .map_err(|e| FfiError::NetworkError(format!("Failed to fetch list: {}", e)))?;
+        .map_err(|e| session_error(handle, api_error("Failed to fetch list", e)))?;
 
     Ok(titles)
 }
@@ -218,19 +407,13 @@ pub fn get_page(handle: SessionHandle, title: String) -> Result<PageInfo, FfiErr
         .as_ref()
         .ok_or(FfiError::AuthenticationError)?
         .clone();
+    let page_title = parse_title(&session.site, &title)?;
 
     drop(sessions); // Release lock before async operation
 
-    let page_title = Title::new(Namespace::MAIN, &title);
-
     let page = TOKIO_RUNTIME
         .block_on(async { client.get_page(&page_title).await })
-        .map_err(|e| match e {
-            awb_mw_api::error::MwApiError::ApiError { code, .. } if code == "missingtitle" => {
-                FfiError::NotFound
-            }
-            _ => FfiError::NetworkError(format!("Failed to fetch page: {}", e)),
-        })?;
+        .map_err(|e| session_error(handle, api_error("Failed to fetch page", e)))?;
 
     Ok(PageInfo {
         page_id: page.page_id.0,
@@ -243,18 +426,142 @@ pub fn get_page(handle: SessionHandle, title: String) -> Result<PageInfo, FfiErr
     })
 }
 
+/// Apply the rules in `rules_json`, then the enabled plugins. No general
+/// fixes run; see [`apply_rules_with_config`].
 pub fn apply_rules(
     handle: SessionHandle,
     content: String,
     rules_json: String,
 ) -> Result<TransformResult, FfiError> {
+    let rule_set = parse_rules(&handle, &rules_json)?;
+    let plan = transform(
+        content,
+        &rule_set,
+        fix_registry(&FixConfig::default())?,
+        HashSet::from([PLUGINS_FIX_ID.to_string()]),
+    )?;
+    Ok(transform_result(plan))
+}
+
+/// List the available general fixes, in run order.
+pub fn list_fixes() -> Vec<FixInfo> {
+    FixRegistry::with_defaults()
+        .fix_infos()
+        .into_iter()
+        .map(FixInfo::from)
+        .collect()
+}
+
+/// Like [`apply_rules`], but also runs the general fixes selected by
+/// `fix_config_json` (a serialized `FixConfig`).
+pub fn apply_rules_with_config(
+    handle: SessionHandle,
+    content: String,
+    rules_json: String,
+    fix_config_json: String,
+) -> Result<TransformResult, FfiError> {
+    let rule_set = parse_rules(&handle, &rules_json)?;
+    let fix_config: FixConfig =
+        serde_json::from_str(&fix_config_json).map_err(|e| FfiError::ParseError {
+            message: format!("Invalid fix config JSON: {}", e),
+        })?;
+
+    let fix_registry = fix_registry(&fix_config)?;
+    let enabled_fixes =
+        fix_registry
+            .enabled_ids(&fix_config)
+            .map_err(|e| FfiError::ParseError {
+                message: format!("Invalid fix config: {}", e),
+            })?;
+
+    let plan = transform(content, &rule_set, fix_registry, enabled_fixes)?;
+    let cosmetic_only = plan.is_cosmetic_only;
+    let mut result = transform_result(plan);
+    if cosmetic_only && !fix_config.allow_cosmetic_only {
+        result
+            .warnings
+            .push("Cosmetic-only edit; not allowed by fix config".to_string());
+    }
+    Ok(result)
+}
+
+/// Replace the loaded plugins with the `.lua` and `.wasm` plugins in `dir`,
+/// returning how many were loaded. Plugins start out enabled.
+pub fn load_plugins(dir: String) -> Result<u32, FfiError> {
+    let count = PLUGINS.load(&dir).map_err(|e| FfiError::PluginError {
+        message: e.to_string(),
+    })?;
+    Ok(u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+/// List the loaded plugins, in load order.
+pub fn list_plugins() -> Vec<PluginInfo> {
+    PLUGINS.with_manager(|manager| {
+        manager
+            .plugin_names()
+            .into_iter()
+            .map(|name| PluginInfo {
+                description: manager
+                    .get_plugin(&name)
+                    .map(|p| p.description().to_string())
+                    .unwrap_or_default(),
+                enabled: manager.is_enabled(&name),
+                classification: classification_name(manager.plugin_classification(&name))
+                    .to_string(),
+                min_tier: manager.plugin_min_tier(&name),
+                name,
+            })
+            .collect()
+    })
+}
+
+pub fn set_plugin_enabled(name: String, enabled: bool) -> Result<(), FfiError> {
+    let found = PLUGINS
+        .with_manager_mut(|manager| {
+            if enabled {
+                manager.enable_plugin(&name)
+            } else {
+                Ok(manager.disable_plugin(&name))
+            }
+        })
+        .map_err(|e| FfiError::PluginError {
+            message: e.to_string(),
+        })?;
+    if found {
+        Ok(())
+    } else {
+        Err(FfiError::NotFound)
+    }
+}
+
+/// The built-in fixes, set up for `config`, plus the loaded plugins.
+fn fix_registry(config: &FixConfig) -> Result<FixRegistry, FfiError> {
+    FixRegistry::builder()
+        .with_config(config)
+        .plugin(Box::new(PLUGINS.clone()))
+        .build()
+        .map_err(|e| FfiError::EngineError {
+            message: format!("Fix registry error: {}", e),
+        })
+}
+
+/// Check the session exists and parse a JSON `RuleSet`.
+fn parse_rules(handle: &SessionHandle, rules_json: &str) -> Result<RuleSet, FfiError> {
     let sessions = SESSIONS.lock();
     let _session = sessions.get(&handle.id).ok_or(FfiError::SessionNotFound)?;
+    drop(sessions);
 
-    // Parse rules from JSON
-    let rule_set: RuleSet = serde_json::from_str(&rules_json)
-        .map_err(|e| FfiError::ParseError(format!("Invalid rules JSON: {}", e)))?;
+    serde_json::from_str(rules_json).map_err(|e| FfiError::ParseError {
+        message: format!("Invalid rules JSON: {}", e),
+    })
+}
 
+fn transform(
+    content: String,
+    rule_set: &RuleSet,
+    fix_registry: FixRegistry,
+    enabled_fixes: HashSet<String>,
+) -> Result<EditPlan, FfiError> {
     // Create a mock page content
     let page = PageContent {
         page_id: PageId(1),
@@ -269,24 +576,27 @@ pub fn apply_rules(
     };
 
     // Apply transformations
-    let fix_registry = FixRegistry::with_defaults();
-    let enabled_fixes = std::collections::HashSet::new();
-    let engine = TransformEngine::new(&rule_set, fix_registry, enabled_fixes)
-        .map_err(|e| FfiError::EngineError(format!("Transform engine error: {}", e)))?;
+    let engine = TransformEngine::new(rule_set, fix_registry, enabled_fixes).map_err(|e| {
+        FfiError::EngineError {
+            message: format!("Transform engine error: {}", e),
+        }
+    })?;
 
-    let plan = engine.apply(&page);
+    Ok(engine.apply(&page))
+}
 
+fn transform_result(plan: EditPlan) -> TransformResult {
     // Convert diff to HTML
     let diff_html = format_diff_as_html(&plan.diff_ops);
 
-    Ok(TransformResult {
+    TransformResult {
         new_wikitext: plan.new_wikitext,
         rules_applied: plan.rules_applied.iter().map(|id| id.to_string()).collect(),
         fixes_applied: plan.fixes_applied,
         summary: plan.summary,
         warnings: plan.warnings.iter().map(|w| format!("{:?}", w)).collect(),
         diff_html,
-    })
+    }
 }
 
 pub fn save_page(
@@ -294,16 +604,22 @@ pub fn save_page(
     title: String,
     content: String,
     summary: String,
-) -> Result<(), FfiError> {
+) -> Result<SaveOutcome, FfiError> {
     // Validate inputs
     if title.is_empty() {
-        return Err(FfiError::ParseError("Title cannot be empty".to_string()));
+        return Err(FfiError::ParseError {
+            message: "Title cannot be empty".to_string(),
+        });
     }
     if content.is_empty() {
-        return Err(FfiError::ParseError("Content cannot be empty".to_string()));
+        return Err(FfiError::ParseError {
+            message: "Content cannot be empty".to_string(),
+        });
     }
     if summary.is_empty() {
-        return Err(FfiError::ParseError("Summary cannot be empty".to_string()));
+        return Err(FfiError::ParseError {
+            message: "Summary cannot be empty".to_string(),
+        });
     }
 
     let sessions = SESSIONS.lock();
@@ -314,15 +630,18 @@ pub fn save_page(
         .as_ref()
         .ok_or(FfiError::AuthenticationError)?
         .clone();
+    let page_title = parse_title(&session.site, &title)?;
 
     drop(sessions); // Release lock before async operation
 
-    let page_title = Title::new(Namespace::MAIN, &title);
-
     // First fetch the page to get base timestamp
     let page = TOKIO_RUNTIME
         .block_on(async { client.get_page(&page_title).await })
-        .map_err(|e| FfiError::NetworkError(format!("Failed to fetch page for edit: {}", e)))?;
+        .map_err(|e| session_error(handle, api_error("Failed to fetch page for edit", e)))?;
+
+    if is_null_edit(&page.wikitext, &content) {
+        return Ok(SaveOutcome::NoChange);
+    }
 
     let edit_request = EditRequest {
         title: page_title,
@@ -333,20 +652,21 @@ pub fn save_page(
         base_timestamp: page.timestamp.to_rfc3339(),
         start_timestamp: chrono::Utc::now().to_rfc3339(),
         section: None,
+        tags: Vec::new(),
+        watchlist: Watchlist::default(),
     };
 
     let response = TOKIO_RUNTIME
         .block_on(async { client.edit_page(&edit_request).await })
-        .map_err(|e| FfiError::NetworkError(format!("Failed to save page: {}", e)))?;
+        .map_err(|e| session_error(handle, api_error("Failed to save page", e)))?;
 
     if response.result != "Success" {
-        return Err(FfiError::NetworkError(format!(
-            "Edit failed: {}",
-            response.result
-        )));
+        return Err(FfiError::NetworkError {
+            message: format!("Edit failed: {}", response.result),
+        });
     }
 
-    Ok(())
+    Ok(SaveOutcome::Saved)
 }
 
 pub fn compute_diff(old_text: String, new_text: String) -> String {
@@ -389,6 +709,13 @@ fn format_diff_as_html(diff_ops: &[awb_domain::diff::DiffOp]) -> String {
                 ));
             }
         }
+        // Badge naming the rule or fix behind the change
+        if let Some(origin) = op.origin() {
+            html.push_str(&format!(
+                "<span class='origin'>{}</span>",
+                html_escape(origin)
+            ));
+        }
     }
 
     html.push_str("</div>");
@@ -459,7 +786,7 @@ mod tests {
         // Empty URL should now be rejected
         assert!(result.is_err());
         match result {
-            Err(FfiError::ParseError(msg)) => assert!(msg.contains("wiki_url")),
+            Err(FfiError::ParseError { message: msg }) => assert!(msg.contains("wiki_url")),
             _ => panic!("Expected ParseError for empty wiki_url"),
         }
     }
@@ -474,7 +801,7 @@ mod tests {
 
         assert!(result.is_err());
         match result {
-            Err(FfiError::ParseError(msg)) => assert!(msg.contains("Invalid wiki URL")),
+            Err(FfiError::ParseError { message: msg }) => assert!(msg.contains("Invalid wiki URL")),
             _ => panic!("Expected ParseError for invalid URL"),
         }
     }
@@ -510,7 +837,7 @@ mod tests {
         // Empty wiki_url should be rejected
         assert!(result.is_err(), "Should reject empty wiki_url");
         match result {
-            Err(FfiError::ParseError(msg)) => assert!(msg.contains("wiki_url")),
+            Err(FfiError::ParseError { message: msg }) => assert!(msg.contains("wiki_url")),
             _ => panic!("Expected ParseError for empty wiki_url"),
         }
     }
@@ -533,7 +860,7 @@ mod tests {
 
         assert!(result.is_err());
         match result {
-            Err(FfiError::ParseError(msg)) => assert!(msg.contains("Title")),
+            Err(FfiError::ParseError { message: msg }) => assert!(msg.contains("Title")),
             _ => panic!("Expected ParseError for empty title"),
         }
     }
@@ -556,7 +883,7 @@ mod tests {
 
         assert!(result.is_err());
         match result {
-            Err(FfiError::ParseError(msg)) => assert!(msg.contains("Content")),
+            Err(FfiError::ParseError { message: msg }) => assert!(msg.contains("Content")),
             _ => panic!("Expected ParseError for empty content"),
         }
     }
@@ -579,7 +906,7 @@ mod tests {
 
         assert!(result.is_err());
         match result {
-            Err(FfiError::ParseError(msg)) => assert!(msg.contains("Summary")),
+            Err(FfiError::ParseError { message: msg }) => assert!(msg.contains("Summary")),
             _ => panic!("Expected ParseError for empty summary"),
         }
     }
@@ -618,6 +945,27 @@ mod tests {
         assert!(diff.contains("line1"));
     }
 
+    #[test]
+    fn test_diff_html_shows_origin_badges() {
+        use awb_domain::diff::DiffOp;
+
+        let ops = vec![
+            DiffOp::Delete {
+                old_range: 0..4,
+                text: "teh\n".to_string(),
+                origin: Some("typo <teh>".to_string()),
+            },
+            DiffOp::Insert {
+                new_range: 0..4,
+                text: "the\n".to_string(),
+                origin: None,
+            },
+        ];
+        let html = format_diff_as_html(&ops);
+        assert!(html.contains("<span class='origin'>typo &lt;teh&gt;</span>"));
+        assert_eq!(html.matches("class='origin'").count(), 1);
+    }
+
     #[test]
     fn test_html_escape_all_special_chars() {
         assert_eq!(html_escape("&"), "&amp;");
@@ -682,14 +1030,154 @@ mod tests {
 
         assert!(result.is_err());
         match result {
-            Err(FfiError::ParseError(_)) => (),
+            Err(FfiError::ParseError { .. }) => (),
             _ => panic!("Expected ParseError for invalid JSON"),
         }
     }
 
+    #[test]
+    fn test_list_fixes() {
+        let fixes = list_fixes();
+        let ws = fixes
+            .iter()
+            .find(|f| f.id == "whitespace_cleanup")
+            .expect("whitespace_cleanup listed");
+        assert_eq!(ws.classification, "cosmetic");
+        assert!(!ws.display_name.is_empty());
+    }
+
+    #[test]
+    fn test_apply_rules_with_config_runs_selected_fixes() {
+        let handle = create_session(
+            "https://en.wikipedia.org/w/api.php".to_string(),
+            "user".to_string(),
+            "pass".to_string(),
+        )
+        .unwrap();
+        let rules_json = r#"{"rules":[]}"#.to_string();
+        let content = "Text   \n".to_string();
+
+        let config = r#"{"strictness_tier":0,"allow_cosmetic_only":true}"#.to_string();
+        let result =
+            apply_rules_with_config(handle, content.clone(), rules_json.clone(), config).unwrap();
+        assert!(result
+            .fixes_applied
+            .contains(&"trailing_whitespace".to_string()));
+        assert_ne!(result.new_wikitext, content);
+
+        let config = r#"{"disabled_fixes":["trailing_whitespace","whitespace_cleanup"]}"#;
+        let result =
+            apply_rules_with_config(handle, content.clone(), rules_json, config.to_string())
+                .unwrap();
+        assert!(!result
+            .fixes_applied
+            .contains(&"trailing_whitespace".to_string()));
+    }
+
+    #[test]
+    fn test_apply_rules_with_config_rejects_bad_config() {
+        let handle = create_session(
+            "https://en.wikipedia.org/w/api.php".to_string(),
+            "user".to_string(),
+            "pass".to_string(),
+        )
+        .unwrap();
+        let rules_json = r#"{"rules":[]}"#.to_string();
+        for config in ["not json", r#"{"disabled_fixes":["bogus"]}"#] {
+            let result = apply_rules_with_config(
+                handle,
+                "content".to_string(),
+                rules_json.clone(),
+                config.to_string(),
+            );
+            assert!(matches!(result, Err(FfiError::ParseError { .. })));
+        }
+    }
+
+    #[test]
+    fn test_plugin_management() {
+        let dir = std::env::temp_dir().join(format!("awb_ffi_plugins_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("ffi_marker.lua"),
+            r#"
+                function transform(text)
+                    return (string.gsub(text, "FFIPLUGINMARKER", "replaced"))
+                end
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(load_plugins(dir.to_string_lossy().into_owned()).unwrap(), 1);
+        let plugins = list_plugins();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].name, "ffi_marker.lua");
+        assert!(plugins[0].enabled);
+
+        let handle = create_session(
+            "https://en.wikipedia.org/w/api.php".to_string(),
+            "user".to_string(),
+            "pass".to_string(),
+        )
+        .unwrap();
+        let rules_json = r#"{"rules":[]}"#.to_string();
+        let result = apply_rules(
+            handle,
+            "FFIPLUGINMARKER text".to_string(),
+            rules_json.clone(),
+        )
+        .unwrap();
+        assert_eq!(result.new_wikitext, "replaced text");
+
+        set_plugin_enabled("ffi_marker.lua".to_string(), false).unwrap();
+        assert!(!list_plugins()[0].enabled);
+        let result = apply_rules(handle, "FFIPLUGINMARKER text".to_string(), rules_json).unwrap();
+        assert_eq!(result.new_wikitext, "FFIPLUGINMARKER text");
+
+        assert!(matches!(
+            set_plugin_enabled("missing".to_string(), true),
+            Err(FfiError::NotFound)
+        ));
+        assert!(matches!(
+            load_plugins(dir.join("missing").to_string_lossy().into_owned()),
+            Err(FfiError::PluginError { .. })
+        ));
+
+        // Two plugins in one exclusive group cannot both be enabled
+        let rival = dir.join("rival");
+        std::fs::create_dir_all(&rival).unwrap();
+        std::fs::write(
+            rival.join("ffi_rival.lua"),
+            "function transform(text) return text end",
+        )
+        .unwrap();
+        for manifest in [
+            dir.join("ffi_marker.lua.toml"),
+            rival.join("ffi_rival.lua.toml"),
+        ] {
+            std::fs::write(manifest, "exclusive_group = \"marker\"\n").unwrap();
+        }
+        assert_eq!(load_plugins(dir.to_string_lossy().into_owned()).unwrap(), 1);
+        PLUGINS.with_manager_mut(|manager| {
+            manager
+                .load_lua_plugin(rival.join("ffi_rival.lua"))
+                .unwrap();
+            manager.disable_plugin("ffi_rival.lua");
+        });
+        assert!(matches!(
+            set_plugin_enabled("ffi_rival.lua".to_string(), true),
+            Err(FfiError::PluginError { .. })
+        ));
+        set_plugin_enabled("ffi_marker.lua".to_string(), false).unwrap();
+        set_plugin_enabled("ffi_rival.lua".to_string(), true).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_ffi_error_display() {
-        let err1 = FfiError::NetworkError("connection failed".to_string());
+        let err1 = FfiError::NetworkError {
+            message: "connection failed".to_string(),
+        };
         assert!(err1.to_string().contains("Network error"));
 
         let err2 = FfiError::AuthenticationError;
@@ -701,7 +1189,9 @@ mod tests {
         let err4 = FfiError::PermissionDenied;
         assert_eq!(err4.to_string(), "Permission denied");
 
-        let err5 = FfiError::ParseError("invalid".to_string());
+        let err5 = FfiError::ParseError {
+            message: "invalid".to_string(),
+        };
         assert!(err5.to_string().contains("Parse error"));
 
         let err6 = FfiError::SessionNotFound;
@@ -710,8 +1200,114 @@ mod tests {
         let err7 = FfiError::LockPoisoned;
         assert_eq!(err7.to_string(), "Lock poisoned");
 
-        let err8 = FfiError::EngineError("transform failed".to_string());
+        let err8 = FfiError::EngineError {
+            message: "transform failed".to_string(),
+        };
         assert!(err8.to_string().contains("Engine error"));
+
+        let err9 = FfiError::RateLimited { retry_after: 30 };
+        assert_eq!(err9.to_string(), "Rate limited: retry after 30s");
+
+        let err10 = FfiError::Protection {
+            reason: "semi-protected".to_string(),
+        };
+        assert!(err10.to_string().contains("semi-protected"));
+    }
+
+    #[test]
+    fn test_api_error_mapping() {
+        assert!(matches!(
+            api_error("ctx", MwApiError::RateLimited { retry_after: 12 }),
+            FfiError::RateLimited { retry_after: 12 }
+        ));
+        assert!(matches!(
+            api_error("ctx", MwApiError::MaxLag { retry_after: 5 }),
+            FfiError::RateLimited { retry_after: 5 }
+        ));
+        assert!(matches!(
+            api_error(
+                "ctx",
+                MwApiError::EditConflict {
+                    base_rev: RevisionId(1),
+                    current_rev: RevisionId(2),
+                }
+            ),
+            FfiError::EditConflict
+        ));
+        assert!(matches!(
+            api_error("ctx", MwApiError::BadToken),
+            FfiError::TokenExpired
+        ));
+
+        let api = |code: &str| MwApiError::ApiError {
+            code: code.to_string(),
+            info: "details".to_string(),
+        };
+        assert!(matches!(
+            api_error("ctx", api("blocked")),
+            FfiError::Blocked { reason } if reason == "details"
+        ));
+        assert!(matches!(
+            api_error("ctx", api("protectedpage")),
+            FfiError::Protection { reason } if reason == "details"
+        ));
+        assert!(matches!(
+            api_error("ctx", api("missingtitle")),
+            FfiError::NotFound
+        ));
+        assert!(matches!(
+            api_error("ctx", api("assertuserfailed")),
+            FfiError::AuthenticationError
+        ));
+        match api_error("Failed to save page", api("readonly")) {
+            FfiError::NetworkError { message } => {
+                assert!(message.starts_with("Failed to save page: "));
+                assert!(message.contains("readonly"));
+            }
+            other => panic!("Expected NetworkError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_session_error_notifies_listener() {
+        struct Recorder(Arc<Mutex<Vec<(u64, String)>>>);
+        impl SessionListener for Recorder {
+            fn relogin_required(&self, handle: SessionHandle, reason: String) {
+                self.0.lock().push((handle.id, reason));
+            }
+        }
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        set_session_listener(Box::new(Recorder(calls.clone())));
+        let handle = create_session(
+            "https://en.wikipedia.org/w/api.php".to_string(),
+            "user".to_string(),
+            "pass".to_string(),
+        )
+        .unwrap();
+        SESSIONS.lock().get_mut(&handle.id).unwrap().authenticated = true;
+
+        // Errors unrelated to the login pass through silently
+        let err = session_error(handle, FfiError::EditConflict);
+        assert!(matches!(err, FfiError::EditConflict));
+        assert!(calls.lock().iter().all(|(id, _)| *id != handle.id));
+
+        let err = session_error(
+            handle,
+            api_error(
+                "ctx",
+                MwApiError::AuthError {
+                    reason: "assertuserfailed".to_string(),
+                },
+            ),
+        );
+        assert!(matches!(err, FfiError::AuthenticationError));
+        assert!(calls.lock().iter().any(|(id, _)| *id == handle.id));
+        assert!(!SESSIONS.lock()[&handle.id].authenticated);
+        assert!(matches!(
+            refresh_session(handle),
+            Err(FfiError::AuthenticationError)
+        ));
     }
 
     #[test]
@@ -0,0 +1,100 @@
+use awb_engine::fix_config::FixClassification;
+use awb_engine::general_fixes::{FixContext, FixModule};
+use awb_engine::masking::MaskPolicy;
+use awb_plugins::{PluginFixModule, PluginManager};
+use parking_lot::Mutex;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// ID the loaded plugins run under in the fix pipeline
+pub(crate) const PLUGINS_FIX_ID: &str = "plugins";
+
+/// The process-wide set of loaded plugins.
+///
+/// Clones share the same plugins, so one can be registered as a fix module
+/// in each transform while the FFI functions manage the set.
+#[derive(Clone)]
+pub(crate) struct SharedPlugins {
+    module: Arc<Mutex<PluginFixModule>>,
+}
+
+impl Default for SharedPlugins {
+    fn default() -> Self {
+        Self {
+            module: Arc::new(Mutex::new(PluginFixModule::new(PluginManager::new()))),
+        }
+    }
+}
+
+impl SharedPlugins {
+    /// Replace the loaded plugins with those in `dir`. On error the
+    /// current plugins are kept.
+    pub(crate) fn load(&self, dir: &str) -> awb_plugins::Result<usize> {
+        let mut manager = PluginManager::new();
+        let count = manager.load_from_directory(dir)?;
+        *self.module.lock() = PluginFixModule::new(manager);
+        Ok(count)
+    }
+
+    pub(crate) fn with_manager<T>(&self, f: impl FnOnce(&PluginManager) -> T) -> T {
+        f(&self.module.lock().manager())
+    }
+
+    pub(crate) fn with_manager_mut<T>(&self, f: impl FnOnce(&mut PluginManager) -> T) -> T {
+        f(self.module.lock().manager_mut())
+    }
+}
+
+impl FixModule for SharedPlugins {
+    fn id(&self) -> &str {
+        PLUGINS_FIX_ID
+    }
+
+    fn display_name(&self) -> &str {
+        "User Plugins"
+    }
+
+    fn category(&self) -> &str {
+        "Plugins"
+    }
+
+    fn description(&self) -> &str {
+        "User-defined plugins (Lua and WASM)"
+    }
+
+    fn apply<'a>(&self, text: &'a str, context: &FixContext) -> Cow<'a, str> {
+        self.module.lock().apply(text, context)
+    }
+
+    fn page_metadata(&self, context: &FixContext) -> Vec<(String, String)> {
+        self.module.lock().page_metadata(context)
+    }
+
+    fn warnings(&self, text: &str, context: &FixContext) -> Vec<String> {
+        self.module.lock().warnings(text, context)
+    }
+
+    fn summary_fragment(&self, context: &FixContext) -> Option<String> {
+        self.module.lock().summary_fragment(context)
+    }
+
+    fn requests_skip(&self, context: &FixContext) -> bool {
+        self.module.lock().requests_skip(context)
+    }
+
+    fn provenance(&self) -> Vec<(String, String)> {
+        self.module.lock().provenance()
+    }
+
+    fn required_visibility(&self) -> MaskPolicy {
+        self.module.lock().required_visibility()
+    }
+
+    fn classification(&self) -> FixClassification {
+        self.module.lock().classification()
+    }
+
+    fn min_tier(&self) -> u8 {
+        self.module.lock().min_tier()
+    }
+}
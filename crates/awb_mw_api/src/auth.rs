@@ -1,4 +1,5 @@
 use crate::error::MwApiError;
+use crate::trace::ApiTrace;
 use reqwest::Client;
 use serde::Deserialize;
 use tracing::info;
@@ -38,19 +39,18 @@ struct Tokens {
     csrf_token: Option<String>,
 }
 
-pub async fn fetch_login_token(client: &Client, api_url: &url::Url) -> Result<String, MwApiError> {
-    let resp: serde_json::Value = client
-        .get(api_url.as_str())
-        .query(&[
-            ("action", "query"),
-            ("meta", "tokens"),
-            ("type", "login"),
-            ("format", "json"),
-        ])
-        .send()
-        .await?
-        .json()
-        .await?;
+pub async fn fetch_login_token(
+    client: &Client,
+    api_url: &url::Url,
+    trace: Option<&ApiTrace>,
+) -> Result<String, MwApiError> {
+    let request = client.get(api_url.as_str()).query(&[
+        ("action", "query"),
+        ("meta", "tokens"),
+        ("type", "login"),
+        ("format", "json"),
+    ]);
+    let resp: serde_json::Value = crate::trace::send(trace, request).await?.json().await?;
 
     resp["query"]["tokens"]["logintoken"]
         .as_str()
@@ -63,24 +63,20 @@ pub async fn fetch_login_token(client: &Client, api_url: &url::Url) -> Result<St
 pub async fn login_bot_password(
     client: &Client,
     api_url: &url::Url,
+    trace: Option<&ApiTrace>,
     username: &str,
     password: &str,
 ) -> Result<(), MwApiError> {
-    let token = fetch_login_token(client, api_url).await?;
+    let token = fetch_login_token(client, api_url, trace).await?;
 
-    let resp: serde_json::Value = client
-        .post(api_url.as_str())
-        .form(&[
-            ("action", "login"),
-            ("lgname", username),
-            ("lgpassword", password),
-            ("lgtoken", &token),
-            ("format", "json"),
-        ])
-        .send()
-        .await?
-        .json()
-        .await?;
+    let request = client.post(api_url.as_str()).form(&[
+        ("action", "login"),
+        ("lgname", username),
+        ("lgpassword", password),
+        ("lgtoken", &token),
+        ("format", "json"),
+    ]);
+    let resp: serde_json::Value = crate::trace::send(trace, request).await?.json().await?;
 
     let result = resp["login"]["result"].as_str().unwrap_or("");
     if result == "Success" {
@@ -95,24 +91,32 @@ pub async fn login_bot_password(
     }
 }
 
-pub async fn fetch_csrf_token(client: &Client, api_url: &url::Url) -> Result<String, MwApiError> {
-    let resp: serde_json::Value = client
-        .get(api_url.as_str())
-        .query(&[
-            ("action", "query"),
-            ("meta", "tokens"),
-            ("type", "csrf"),
-            ("format", "json"),
-        ])
-        .send()
-        .await?
-        .json()
-        .await?;
+/// CSRF token MediaWiki hands out to anonymous users
+const ANONYMOUS_CSRF_TOKEN: &str = "+\\";
+
+pub async fn fetch_csrf_token(
+    client: &Client,
+    api_url: &url::Url,
+    trace: Option<&ApiTrace>,
+) -> Result<String, MwApiError> {
+    let request = client.get(api_url.as_str()).query(&[
+        ("action", "query"),
+        ("meta", "tokens"),
+        ("type", "csrf"),
+        ("format", "json"),
+    ]);
+    let resp: serde_json::Value = crate::trace::send(trace, request).await?.json().await?;
 
-    resp["query"]["tokens"]["csrftoken"]
+    let token = resp["query"]["tokens"]["csrftoken"]
         .as_str()
-        .map(String::from)
         .ok_or_else(|| MwApiError::AuthError {
             reason: "No CSRF token returned".into(),
-        })
+        })?;
+    // Logged-out sessions get the anonymous token, which no edit accepts
+    if token == ANONYMOUS_CSRF_TOKEN {
+        return Err(MwApiError::AuthError {
+            reason: "Session is not logged in".into(),
+        });
+    }
+    Ok(token.to_string())
 }
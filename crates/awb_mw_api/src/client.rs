@@ -2,8 +2,10 @@ use crate::error::MwApiError;
 use crate::oauth::{OAuth1Config, OAuthSession};
 use crate::retry::RetryPolicy;
 use crate::throttle::ThrottleController;
+use crate::trace::ApiTrace;
 use async_trait::async_trait;
-use awb_domain::profile::ThrottlePolicy;
+use awb_domain::profile::{ConnectionOptions, ThrottlePolicy};
+use awb_domain::siteinfo::SiteInfo;
 use awb_domain::types::*;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -18,6 +20,9 @@ pub struct EditRequest {
     pub base_timestamp: String,
     pub start_timestamp: String,
     pub section: Option<u32>,
+    /// Change tags to apply to the edit; each must be defined on the wiki
+    pub tags: Vec<String>,
+    pub watchlist: Watchlist,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -29,6 +34,24 @@ pub struct EditResponse {
     pub new_timestamp: Option<String>,
 }
 
+/// Value for the `assert` parameter sent with write requests, making the
+/// server reject the request instead of editing logged out or without the
+/// bot right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssertMode {
+    User,
+    Bot,
+}
+
+impl AssertMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AssertMode::User => "user",
+            AssertMode::Bot => "bot",
+        }
+    }
+}
+
 /// Authentication state for the client
 #[derive(Debug, Clone)]
 enum AuthState {
@@ -63,16 +86,13 @@ pub struct ReqwestMwClient {
     auth_state: Arc<RwLock<AuthState>>,
     throttle: ThrottleController,
     retry_policy: RetryPolicy,
+    assert: Option<AssertMode>,
+    trace: Option<Arc<ApiTrace>>,
 }
 
 impl ReqwestMwClient {
     pub fn new(api_url: url::Url, policy: ThrottlePolicy) -> Result<Self, MwApiError> {
-        let jar = Arc::new(reqwest::cookie::Jar::default());
-        let http = reqwest::ClientBuilder::new()
-            .cookie_provider(jar)
-            .user_agent("AWB-RS/0.1.0 (https://github.com/thomasvincent/awb-rs; awb-rs@users.noreply.github.com)")
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+        let http = http_client(&ConnectionOptions::default(), None)?;
 
         Ok(Self {
             http,
@@ -84,9 +104,49 @@ impl ReqwestMwClient {
                 max_retries: policy.max_retries,
                 ..Default::default()
             },
+            assert: None,
+            trace: None,
         })
     }
 
+    /// Send `assert=<mode>` with every edit.
+    #[must_use]
+    pub fn with_assert(mut self, assert: AssertMode) -> Self {
+        self.assert = Some(assert);
+        self
+    }
+
+    /// Reach the wiki with a profile's connection settings: extra headers,
+    /// HTTP basic auth, a custom user agent or relaxed TLS checks.
+    /// `basic_auth_password` goes with `options.basic_auth_user`. Call this
+    /// before logging in; the session cookies are not carried over.
+    pub fn with_connection(
+        mut self,
+        options: &ConnectionOptions,
+        basic_auth_password: Option<&str>,
+    ) -> Result<Self, MwApiError> {
+        self.http = http_client(options, basic_auth_password)?;
+        Ok(self)
+    }
+
+    /// Record every request and response, sanitized, to `trace`.
+    #[must_use]
+    pub fn with_trace(mut self, trace: Arc<ApiTrace>) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// The HTTP client behind this one, carrying its connection settings and
+    /// session cookies, for the queries in [`list_endpoints`](crate::list_endpoints)
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Fetch the wiki's namespaces, aliases and interwiki prefixes
+    pub async fn fetch_siteinfo(&self) -> Result<SiteInfo, MwApiError> {
+        crate::siteinfo::fetch_siteinfo(&self.http, &self.api_url, self.trace.as_deref()).await
+    }
+
     /// Apply authentication to a request builder
     async fn apply_auth(
         &self,
@@ -130,10 +190,57 @@ impl ReqwestMwClient {
     }
 }
 
+const DEFAULT_USER_AGENT: &str =
+    "AWB-RS/0.1.0 (https://github.com/thomasvincent/awb-rs; awb-rs@users.noreply.github.com)";
+
+fn http_client(
+    options: &ConnectionOptions,
+    basic_auth_password: Option<&str>,
+) -> Result<reqwest::Client, MwApiError> {
+    use base64::Engine;
+    use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &options.headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| MwApiError::Connection(format!("bad header name '{}'", name)))?;
+        let header_value = HeaderValue::from_str(value)
+            .map_err(|_| MwApiError::Connection(format!("bad value for header '{}'", name)))?;
+        headers.insert(header_name, header_value);
+    }
+    if let Some(user) = &options.basic_auth_user {
+        let credentials = format!("{}:{}", user, basic_auth_password.unwrap_or(""));
+        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+        let mut value = HeaderValue::from_str(&format!("Basic {}", encoded))
+            .map_err(|_| MwApiError::Connection("bad basic auth credentials".into()))?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    if options.accept_invalid_certs {
+        tracing::warn!("TLS certificate verification is disabled for this wiki");
+    }
+
+    let jar = Arc::new(reqwest::cookie::Jar::default());
+    Ok(reqwest::ClientBuilder::new()
+        .cookie_provider(jar)
+        .user_agent(options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+        .default_headers(headers)
+        .danger_accept_invalid_certs(options.accept_invalid_certs)
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?)
+}
+
 #[async_trait]
 impl MediaWikiClient for ReqwestMwClient {
     async fn login_bot_password(&self, username: &str, password: &str) -> Result<(), MwApiError> {
-        crate::auth::login_bot_password(&self.http, &self.api_url, username, password).await?;
+        crate::auth::login_bot_password(
+            &self.http,
+            &self.api_url,
+            self.trace.as_deref(),
+            username,
+            password,
+        )
+        .await?;
         *self.auth_state.write().await = AuthState::BotPassword;
         Ok(())
     }
@@ -154,7 +261,8 @@ impl MediaWikiClient for ReqwestMwClient {
     }
 
     async fn fetch_csrf_token(&self) -> Result<String, MwApiError> {
-        let token = crate::auth::fetch_csrf_token(&self.http, &self.api_url).await?;
+        let token =
+            crate::auth::fetch_csrf_token(&self.http, &self.api_url, self.trace.as_deref()).await?;
         *self.csrf_token.write().await = Some(token.clone());
         Ok(token)
     }
@@ -166,7 +274,10 @@ impl MediaWikiClient for ReqwestMwClient {
             ("action".to_string(), "query".to_string()),
             ("titles".to_string(), title.display.clone()),
             ("prop".to_string(), "revisions|info|pageprops".to_string()),
-            ("rvprop".to_string(), "ids|timestamp|content".to_string()),
+            (
+                "rvprop".to_string(),
+                "ids|timestamp|content|contentmodel".to_string(),
+            ),
             ("rvslots".to_string(), "main".to_string()),
             ("inprop".to_string(), "protection".to_string()),
             ("format".to_string(), "json".to_string()),
@@ -180,7 +291,7 @@ impl MediaWikiClient for ReqwestMwClient {
                     ("action", "query"),
                     ("titles", &title.display),
                     ("prop", "revisions|info|pageprops"),
-                    ("rvprop", "ids|timestamp|content"),
+                    ("rvprop", "ids|timestamp|content|contentmodel"),
                     ("rvslots", "main"),
                     ("inprop", "protection"),
                     ("format", "json"),
@@ -190,7 +301,7 @@ impl MediaWikiClient for ReqwestMwClient {
                 let builder = self
                     .apply_auth(builder, "GET", self.api_url.as_str(), &params)
                     .await?;
-                let http_resp = builder.send().await?;
+                let http_resp = crate::trace::send(self.trace.as_deref(), builder).await?;
 
                 // Check for HTTP 429 Rate Limited before parsing JSON
                 if http_resp.status() == 429 {
@@ -251,6 +362,11 @@ impl MediaWikiClient for ReqwestMwClient {
             .as_str()
             .unwrap_or("")
             .to_string();
+        let content_model = rev["slots"]["main"]["contentmodel"]
+            .as_str()
+            .or_else(|| page["contentmodel"].as_str())
+            .map(ContentModel::from)
+            .unwrap_or_default();
 
         let is_redirect = page.get("redirect").is_some();
 
@@ -301,6 +417,7 @@ impl MediaWikiClient for ReqwestMwClient {
             properties: PageProperties {
                 is_disambig,
                 wikibase_item,
+                content_model,
             },
         })
     }
@@ -343,6 +460,13 @@ impl MediaWikiClient for ReqwestMwClient {
             if let Some(section) = edit.section {
                 params.push(("section".to_string(), section.to_string()));
             }
+            if !edit.tags.is_empty() {
+                params.push(("tags".to_string(), edit.tags.join("|")));
+            }
+            params.push(("watchlist".to_string(), edit.watchlist.as_str().to_string()));
+            if let Some(assert) = self.assert {
+                params.push(("assert".to_string(), assert.as_str().to_string()));
+            }
 
             let resp: serde_json::Value = self
                 .retry_policy
@@ -351,7 +475,7 @@ impl MediaWikiClient for ReqwestMwClient {
                     let builder = self
                         .apply_auth(builder, "POST", self.api_url.as_str(), &params)
                         .await?;
-                    let http_resp = builder.send().await?;
+                    let http_resp = crate::trace::send(self.trace.as_deref(), builder).await?;
 
                     // Check for HTTP 429 Rate Limited before parsing JSON
                     if http_resp.status() == 429 {
@@ -388,6 +512,17 @@ impl MediaWikiClient for ReqwestMwClient {
                         // Already refreshed once — fail
                         Err(MwApiError::BadToken)
                     }
+                    // The session expired or lost its rights; retrying cannot help
+                    "assertuserfailed" | "assertbotfailed" => Err(MwApiError::AuthError {
+                        reason: format!("{}: {}", code, info),
+                    }),
+                    "readonly" => Err(MwApiError::ReadOnly {
+                        reason: error["readonlyreason"]
+                            .as_str()
+                            .filter(|reason| !reason.is_empty())
+                            .map(str::to_string)
+                            .unwrap_or(info),
+                    }),
                     "maxlag" => {
                         let retry_after = info
                             .split_whitespace()
@@ -432,7 +567,7 @@ impl MediaWikiClient for ReqwestMwClient {
                 let builder = self
                     .apply_auth(builder, "POST", self.api_url.as_str(), &params)
                     .await?;
-                let http_resp = builder.send().await?;
+                let http_resp = crate::trace::send(self.trace.as_deref(), builder).await?;
 
                 // Check for HTTP 429 Rate Limited before parsing JSON
                 if http_resp.status() == 429 {
@@ -495,7 +630,7 @@ impl MediaWikiClient for ReqwestMwClient {
                     let builder = self
                         .apply_auth(builder, "GET", self.api_url.as_str(), &params)
                         .await?;
-                    let http_resp = builder.send().await?;
+                    let http_resp = crate::trace::send(self.trace.as_deref(), builder).await?;
 
                     if http_resp.status() == 429 {
                         let retry_after = http_resp
@@ -578,7 +713,7 @@ impl MediaWikiClient for ReqwestMwClient {
                     let builder = self
                         .apply_auth(builder, "GET", self.api_url.as_str(), &params)
                         .await?;
-                    let http_resp = builder.send().await?;
+                    let http_resp = crate::trace::send(self.trace.as_deref(), builder).await?;
 
                     if http_resp.status() == 429 {
                         let retry_after = http_resp
@@ -661,7 +796,7 @@ impl MediaWikiClient for ReqwestMwClient {
                     let builder = self
                         .apply_auth(builder, "GET", self.api_url.as_str(), &params)
                         .await?;
-                    let http_resp = builder.send().await?;
+                    let http_resp = crate::trace::send(self.trace.as_deref(), builder).await?;
 
                     if http_resp.status() == 429 {
                         let retry_after = http_resp
@@ -739,6 +874,21 @@ mod tests {
         assert!(result.is_ok(), "ReqwestMwClient::new should succeed");
     }
 
+    #[test]
+    fn test_with_connection_rejects_bad_header() {
+        let api_url = url::Url::parse("https://wiki.corp.example/api.php").unwrap();
+        let client = ReqwestMwClient::new(api_url, ThrottlePolicy::default()).unwrap();
+        let mut options = ConnectionOptions::default();
+        options
+            .headers
+            .insert("X-Team".to_string(), "docs\nInjected: yes".to_string());
+
+        match client.with_connection(&options, None) {
+            Err(MwApiError::Connection(reason)) => assert!(reason.contains("X-Team")),
+            other => panic!("Expected Connection error, got {:?}", other.err()),
+        }
+    }
+
     #[test]
     fn test_reqwest_mw_client_new_with_invalid_url() {
         // Valid URL construction but testing the client creation
@@ -822,6 +972,8 @@ mod tests {
             base_timestamp: "2024-01-01T00:00:00Z".to_string(),
             start_timestamp: "2024-01-01T00:01:00Z".to_string(),
             section: Some(1),
+            tags: Vec::new(),
+            watchlist: Watchlist::default(),
         };
 
         assert_eq!(edit.title.display, "Test Page");
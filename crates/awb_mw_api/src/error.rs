@@ -25,6 +25,12 @@ pub enum MwApiError {
         current_rev: RevisionId,
     },
 
+    /// The wiki is in read-only mode, e.g. for database maintenance or
+    /// while replicas catch up after heavy lag. `reason` is the one the
+    /// wiki gives.
+    #[error("Wiki is read-only: {reason}")]
+    ReadOnly { reason: String },
+
     #[error("Token expired, refresh needed")]
     BadToken,
 
@@ -37,6 +43,11 @@ pub enum MwApiError {
     #[error("Deserialization: {0}")]
     Deserialize(#[from] serde_json::Error),
 
+    /// A profile's connection settings cannot be applied, e.g. a header
+    /// value with a newline in it
+    #[error("Invalid connection settings: {0}")]
+    Connection(String),
+
     #[error("Network: {0}")]
     Network(#[from] reqwest::Error),
 
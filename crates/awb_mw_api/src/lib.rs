@@ -3,6 +3,10 @@ pub mod client;
 pub mod error;
 pub mod list_endpoints;
 pub mod oauth;
+pub mod query_tools;
 pub mod retry;
+pub mod siteinfo;
 pub mod throttle;
+pub mod trace;
 pub mod typo_fetch;
+pub mod wikidata;
@@ -174,3 +174,472 @@ pub async fn fetch_user_contributions(
 
     Ok(titles)
 }
+
+/// Fetch pages that transclude a template
+///
+/// # Arguments
+/// * `client` - HTTP client to use for the request
+/// * `api_url` - MediaWiki API URL
+/// * `template` - Template name, with or without the `Template:` prefix
+///
+/// # Returns
+/// Vector of page titles embedding the template, including through redirects
+pub async fn fetch_transclusions(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    template: &str,
+) -> Result<Vec<Title>, MwApiError> {
+    let template = template.trim();
+    let template_title = if template
+        .split_once(':')
+        .is_some_and(|(prefix, _)| prefix.eq_ignore_ascii_case("template"))
+    {
+        template.to_string()
+    } else {
+        format!("Template:{}", template)
+    };
+
+    let base_params = [
+        ("action", "query"),
+        ("list", "embeddedin"),
+        ("eititle", &template_title),
+        ("eilimit", "500"),
+    ];
+
+    fetch_all_pages(client, api_url, &base_params, "embeddedin", "eicontinue").await
+}
+
+/// Most titles the API accepts in one `titles=` query
+pub const MAX_TITLES_PER_QUERY: usize = 50;
+
+/// Fetch the current wikitext of up to [`MAX_TITLES_PER_QUERY`] pages in one
+/// request
+///
+/// # Returns
+/// `(title, wikitext)` pairs in the order given; missing pages are left out
+pub async fn fetch_page_texts(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    titles: &[Title],
+) -> Result<Vec<(Title, String)>, MwApiError> {
+    if titles.is_empty() {
+        return Ok(Vec::new());
+    }
+    let joined = titles
+        .iter()
+        .take(MAX_TITLES_PER_QUERY)
+        .map(|t| t.display.as_str())
+        .collect::<Vec<_>>()
+        .join("|");
+    let params = [
+        ("action", "query"),
+        ("prop", "revisions"),
+        ("rvprop", "content"),
+        ("rvslots", "main"),
+        ("titles", &joined),
+        ("formatversion", "2"),
+        ("format", "json"),
+    ];
+
+    let resp: serde_json::Value = client
+        .get(api_url.as_str())
+        .query(&params)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if let Some(error) = resp.get("error") {
+        let code = error["code"].as_str().unwrap_or("unknown").to_string();
+        let info = error["info"].as_str().unwrap_or("").to_string();
+        return Err(MwApiError::ApiError { code, info });
+    }
+
+    let texts: std::collections::HashMap<&str, &str> = resp["query"]["pages"]
+        .as_array()
+        .map(|pages| {
+            pages
+                .iter()
+                .filter_map(|page| {
+                    let title = page["title"].as_str()?;
+                    let text = page["revisions"][0]["slots"]["main"]["content"].as_str()?;
+                    Some((title, text))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(titles
+        .iter()
+        .take(MAX_TITLES_PER_QUERY)
+        .filter_map(|title| {
+            texts
+                .get(title.display.as_str())
+                .map(|text| (title.clone(), text.to_string()))
+        })
+        .collect())
+}
+
+/// Parse the results of a `list=querypage` response into Titles
+pub fn parse_querypage_response(resp: &serde_json::Value) -> Vec<Title> {
+    resp["query"]["querypage"]["results"]
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|item| {
+                    let name = item["title"].as_str()?.to_string();
+                    Some(Title {
+                        namespace: Namespace(item["ns"].as_i64()? as i32),
+                        name: name.clone(),
+                        display: name,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Fetch pages listed on Special:DoubleRedirects
+///
+/// The special page is cached on large wikis, so some entries may already
+/// be fixed; check each page before editing it.
+///
+/// # Arguments
+/// * `client` - HTTP client to use for the request
+/// * `api_url` - MediaWiki API URL
+/// * `limit` - Maximum number of pages to fetch (0 = unlimited)
+pub async fn fetch_double_redirects(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    limit: u32,
+) -> Result<Vec<Title>, MwApiError> {
+    let mut titles = Vec::new();
+    let mut offset: Option<u64> = None;
+
+    loop {
+        let mut params = vec![
+            ("action", "query".to_string()),
+            ("list", "querypage".to_string()),
+            ("qppage", "DoubleRedirects".to_string()),
+            ("qplimit", "500".to_string()),
+            ("format", "json".to_string()),
+        ];
+        if let Some(offset) = offset {
+            params.push(("qpoffset", offset.to_string()));
+        }
+
+        let resp: serde_json::Value = client
+            .get(api_url.as_str())
+            .query(&params)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = resp.get("error") {
+            let code = error["code"].as_str().unwrap_or("unknown").to_string();
+            let info = error["info"].as_str().unwrap_or("").to_string();
+            return Err(MwApiError::ApiError { code, info });
+        }
+
+        titles.extend(parse_querypage_response(&resp));
+        if limit > 0 && titles.len() >= limit as usize {
+            titles.truncate(limit as usize);
+            break;
+        }
+
+        match resp["continue"]["qpoffset"].as_u64() {
+            Some(next) => offset = Some(next),
+            None => break,
+        }
+    }
+
+    Ok(titles)
+}
+
+/// One redirect followed by the API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectHop {
+    pub from: String,
+    pub to: String,
+    /// Section anchor of the target, if any
+    pub fragment: Option<String>,
+}
+
+/// Parse the `redirects` array of a query made with `redirects=1`
+pub fn parse_redirect_hops(resp: &serde_json::Value) -> Vec<RedirectHop> {
+    resp["query"]["redirects"]
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|hop| {
+                    Some(RedirectHop {
+                        from: hop["from"].as_str()?.to_string(),
+                        to: hop["to"].as_str()?.to_string(),
+                        fragment: hop["tofragment"]
+                            .as_str()
+                            .filter(|f| !f.is_empty())
+                            .map(String::from),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Follow redirects from up to [`MAX_TITLES_PER_QUERY`] titles
+///
+/// # Returns
+/// Every redirect on the way, including each step of a chain; titles that
+/// are not redirects contribute nothing
+pub async fn fetch_redirect_hops(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    titles: &[String],
+) -> Result<Vec<RedirectHop>, MwApiError> {
+    if titles.is_empty() {
+        return Ok(Vec::new());
+    }
+    let joined = titles
+        .iter()
+        .take(MAX_TITLES_PER_QUERY)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("|");
+    let params = [
+        ("action", "query"),
+        ("titles", &joined),
+        ("redirects", "1"),
+        ("formatversion", "2"),
+        ("format", "json"),
+    ];
+
+    let resp: serde_json::Value = client
+        .get(api_url.as_str())
+        .query(&params)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if let Some(error) = resp.get("error") {
+        let code = error["code"].as_str().unwrap_or("unknown").to_string();
+        let info = error["info"].as_str().unwrap_or("").to_string();
+        return Err(MwApiError::ApiError { code, info });
+    }
+
+    Ok(parse_redirect_hops(&resp))
+}
+
+/// Article links to and from one page, with what is needed to decide
+/// whether it is an article at all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLinks {
+    /// Main-namespace pages linking here, redirects and the page itself
+    /// excluded, counted up to the query limit
+    pub incoming: usize,
+    /// Main-namespace pages linked from here
+    pub outgoing: usize,
+    pub redirect: bool,
+    pub disambiguation: bool,
+}
+
+/// Parse a combined `prop=info|links|pageprops` and `list=backlinks` query
+/// for a single page. `None` if the page does not exist.
+pub fn parse_page_links(resp: &serde_json::Value) -> Option<PageLinks> {
+    let page = resp["query"]["pages"].as_array()?.first()?;
+    if page["missing"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    let title = page["title"].as_str().unwrap_or("");
+    let incoming = resp["query"]["backlinks"].as_array().map_or(0, |arr| {
+        arr.iter()
+            .filter(|link| link["title"].as_str() != Some(title))
+            .count()
+    });
+    let outgoing = page["links"].as_array().map_or(0, |arr| {
+        arr.iter()
+            .filter(|link| link["title"].as_str() != Some(title))
+            .count()
+    });
+    Some(PageLinks {
+        incoming,
+        outgoing,
+        redirect: page["redirect"].as_bool().unwrap_or(false),
+        disambiguation: page["pageprops"].get("disambiguation").is_some(),
+    })
+}
+
+/// Count main-namespace links to and from `title` in one request
+///
+/// Only the first `limit` links each way are counted, which is enough to
+/// tell none from some.
+///
+/// # Returns
+/// `None` if the page does not exist
+pub async fn fetch_page_links(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    title: &str,
+    limit: u32,
+) -> Result<Option<PageLinks>, MwApiError> {
+    let limit = limit.max(2).to_string();
+    let params = [
+        ("action", "query"),
+        ("titles", title),
+        ("prop", "info|links|pageprops"),
+        ("ppprop", "disambiguation"),
+        ("plnamespace", "0"),
+        ("pllimit", &limit),
+        ("list", "backlinks"),
+        ("bltitle", title),
+        ("blnamespace", "0"),
+        ("blfilterredir", "nonredirects"),
+        ("bllimit", &limit),
+        ("formatversion", "2"),
+        ("format", "json"),
+    ];
+
+    let resp: serde_json::Value = client
+        .get(api_url.as_str())
+        .query(&params)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if let Some(error) = resp.get("error") {
+        let code = error["code"].as_str().unwrap_or("unknown").to_string();
+        let info = error["info"].as_str().unwrap_or("").to_string();
+        return Err(MwApiError::ApiError { code, info });
+    }
+
+    Ok(parse_page_links(&resp))
+}
+
+/// Pages that use a file under this name (uses through redirects to it are
+/// not listed)
+pub async fn fetch_file_usage(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    file: &str,
+) -> Result<Vec<Title>, MwApiError> {
+    let file = file.trim();
+    let file_title = if file.split_once(':').is_some_and(|(prefix, _)| {
+        prefix.eq_ignore_ascii_case("file") || prefix.eq_ignore_ascii_case("image")
+    }) {
+        file.to_string()
+    } else {
+        format!("File:{}", file)
+    };
+
+    let base_params = [
+        ("action", "query"),
+        ("list", "imageusage"),
+        ("iutitle", &file_title),
+        ("iulimit", "500"),
+    ];
+
+    fetch_all_pages(client, api_url, &base_params, "imageusage", "iucontinue").await
+}
+
+/// Most revisions with content the API returns per request
+const MAX_REVISIONS_PER_QUERY: usize = 50;
+
+/// One past revision of a page
+#[derive(Debug, Clone)]
+pub struct PageRevision {
+    pub revision: RevisionId,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub wikitext: String,
+}
+
+/// Parse the revisions of the first page of a `prop=revisions` response.
+/// Revisions whose content is hidden are left out.
+///
+/// # Returns
+/// `None` if the page does not exist
+pub fn parse_revisions(resp: &serde_json::Value) -> Option<Vec<PageRevision>> {
+    let page = resp["query"]["pages"].as_array()?.first()?;
+    if page["missing"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    Some(
+        page["revisions"]
+            .as_array()
+            .map(|revisions| {
+                revisions
+                    .iter()
+                    .filter_map(|rev| {
+                        Some(PageRevision {
+                            revision: RevisionId(rev["revid"].as_u64()?),
+                            timestamp: rev["timestamp"].as_str()?.parse().ok()?,
+                            wikitext: rev["slots"]["main"]["content"].as_str()?.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    )
+}
+
+/// Fetch the last `limit` revisions of `title` with their text, newest
+/// first
+///
+/// # Returns
+/// `None` if the page does not exist
+pub async fn fetch_revisions(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    title: &str,
+    limit: usize,
+) -> Result<Option<Vec<PageRevision>>, MwApiError> {
+    let mut revisions = Vec::new();
+    let mut continue_token: Option<String> = None;
+
+    while revisions.len() < limit {
+        let batch = (limit - revisions.len())
+            .min(MAX_REVISIONS_PER_QUERY)
+            .to_string();
+        let mut params = vec![
+            ("action", "query"),
+            ("titles", title),
+            ("prop", "revisions"),
+            ("rvprop", "ids|timestamp|content"),
+            ("rvslots", "main"),
+            ("rvlimit", &batch),
+            ("formatversion", "2"),
+            ("format", "json"),
+        ];
+        if let Some(token) = &continue_token {
+            params.push(("rvcontinue", token));
+        }
+
+        let resp: serde_json::Value = client
+            .get(api_url.as_str())
+            .query(&params)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = resp.get("error") {
+            let code = error["code"].as_str().unwrap_or("unknown").to_string();
+            let info = error["info"].as_str().unwrap_or("").to_string();
+            return Err(MwApiError::ApiError { code, info });
+        }
+
+        let Some(batch) = parse_revisions(&resp) else {
+            return Ok(None);
+        };
+        revisions.extend(batch);
+
+        continue_token = get_continue_token(&resp, "rvcontinue");
+        if continue_token.is_none() {
+            break;
+        }
+    }
+
+    revisions.truncate(limit);
+    Ok(Some(revisions))
+}
@@ -0,0 +1,387 @@
+//! Page lists from the Toolforge query tools: PetScan queries and Quarry
+//! result sets.
+//!
+//! Both hand back database-style titles (underscores, namespace given
+//! separately); they are normalized here into [`Title`]s as the list API
+//! would return them.
+
+use crate::error::MwApiError;
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::{Namespace, Title};
+use url::Url;
+
+pub const PETSCAN_URL: &str = "https://petscan.wmflabs.org/";
+pub const QUARRY_URL: &str = "https://quarry.wmcloud.org/";
+
+/// Column names Quarry queries commonly use for the title and namespace
+const TITLE_COLUMNS: &[&str] = &["page_title", "title", "rc_title", "cl_from_title"];
+const NAMESPACE_COLUMNS: &[&str] = &["page_namespace", "namespace", "ns", "rc_namespace"];
+
+/// Output format of a Quarry result set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuarryFormat {
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl QuarryFormat {
+    /// Format named by a Quarry output URL or a downloaded file's extension
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+}
+
+/// Build a title from a namespace number, the namespace's local name if
+/// known, and a database-style page name
+fn make_title(namespace: Namespace, prefix: Option<&str>, name: &str) -> Title {
+    match prefix
+        .map(|p| p.replace('_', " "))
+        .filter(|p| !p.is_empty())
+    {
+        Some(prefix) => {
+            let name = name.replace('_', " ").trim().to_string();
+            Title {
+                namespace,
+                display: format!("{}:{}", prefix, name),
+                name,
+            }
+        }
+        None => Title::with_canonical_prefix(namespace, name),
+    }
+}
+
+/// PetScan URL that runs saved query `psid` and returns JSON
+pub fn petscan_url(base: &Url, psid: &str) -> Result<Url, MwApiError> {
+    let psid = psid.trim();
+    if psid.is_empty() || !psid.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(MwApiError::ApiError {
+            code: "badquery".into(),
+            info: format!("PetScan query ID must be a number, got '{}'", psid),
+        });
+    }
+    let mut url = base.clone();
+    url.query_pairs_mut()
+        .append_pair("psid", psid)
+        .append_pair("format", "json")
+        .append_pair("doit", "1");
+    Ok(url)
+}
+
+/// Parse PetScan's JSON output into titles, in result order
+pub fn parse_petscan_json(resp: &serde_json::Value) -> Result<Vec<Title>, MwApiError> {
+    if let Some(error) = resp["error"].as_str() {
+        return Err(MwApiError::ApiError {
+            code: "petscan".into(),
+            info: error.to_string(),
+        });
+    }
+    let combinations = resp["*"].as_array().ok_or_else(|| MwApiError::ApiError {
+        code: "petscan".into(),
+        info: "Response has no result set".into(),
+    })?;
+
+    Ok(combinations
+        .iter()
+        .filter_map(|combination| combination["a"]["*"].as_array())
+        .flatten()
+        .filter_map(|page| {
+            let name = page["title"].as_str()?;
+            let namespace = Namespace(page["namespace"].as_i64().unwrap_or(0) as i32);
+            Some(make_title(namespace, page["nstext"].as_str(), name))
+        })
+        .collect())
+}
+
+/// Where to download a Quarry result set from, and in which format.
+///
+/// Accepts a query ID or query page URL (the latest run's first result set,
+/// as JSON) or a result output URL such as
+/// `https://quarry.wmcloud.org/run/123/output/0/csv`.
+pub fn quarry_result_url(base: &Url, source: &str) -> Result<(Url, QuarryFormat), MwApiError> {
+    let source = source.trim();
+    let bad = || MwApiError::ApiError {
+        code: "badquery".into(),
+        info: format!("Not a Quarry query ID or result URL: '{}'", source),
+    };
+    let latest = |site: &Url, id: &str| {
+        site.join(&format!("/query/{}/result/latest/0/json", id))
+            .map(|url| (url, QuarryFormat::Json))
+            .map_err(|_| bad())
+    };
+
+    if !source.is_empty() && source.bytes().all(|b| b.is_ascii_digit()) {
+        return latest(base, source);
+    }
+
+    let url = Url::parse(source).map_err(|_| bad())?;
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    match segments.as_slice() {
+        ["query", id] => latest(&url, id),
+        [.., "output", _, format] => {
+            let format = QuarryFormat::from_extension(format).ok_or_else(bad)?;
+            Ok((url, format))
+        }
+        _ => Err(bad()),
+    }
+}
+
+/// Parse a Quarry result set into titles.
+///
+/// The title comes from a `page_title` (or `title`) column; a
+/// `page_namespace` (or `namespace`) column, when present, gives its
+/// namespace. Without one, namespace prefixes in the title are recognized.
+pub fn parse_quarry(body: &str, format: QuarryFormat) -> Result<Vec<Title>, MwApiError> {
+    let (headers, rows) = match format {
+        QuarryFormat::Json => {
+            let resp: serde_json::Value = serde_json::from_str(body)?;
+            let headers = resp["headers"]
+                .as_array()
+                .map(|h| {
+                    h.iter()
+                        .map(|v| v.as_str().unwrap_or_default().to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let rows = resp["rows"]
+                .as_array()
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| {
+                            row.as_array()
+                                .map(|cells| cells.iter().map(json_cell).collect())
+                                .unwrap_or_default()
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            (headers, rows)
+        }
+        QuarryFormat::Csv => split_header(body.lines().map(parse_csv_line)),
+        QuarryFormat::Tsv => split_header(
+            body.lines()
+                .map(|line| line.split('\t').map(str::to_string).collect()),
+        ),
+    };
+
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)))
+    };
+    let title_col = match column(TITLE_COLUMNS) {
+        Some(col) => col,
+        None if headers.len() == 1 => 0,
+        None => {
+            return Err(MwApiError::ApiError {
+                code: "quarry".into(),
+                info: format!(
+                    "No title column in result (expected one of: {})",
+                    TITLE_COLUMNS.join(", ")
+                ),
+            });
+        }
+    };
+    let namespace_col = column(NAMESPACE_COLUMNS);
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let name = row.get(title_col).filter(|n| !n.trim().is_empty())?;
+            let namespace = namespace_col
+                .and_then(|col| row.get(col))
+                .and_then(|ns| ns.trim().parse().ok());
+            match namespace {
+                Some(ns) => Some(make_title(Namespace(ns), None, name)),
+                None => Title::parse(&SiteInfo::default(), name).ok(),
+            }
+        })
+        .collect())
+}
+
+fn json_cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn split_header(mut lines: impl Iterator<Item = Vec<String>>) -> (Vec<String>, Vec<Vec<String>>) {
+    let headers = lines.next().unwrap_or_default();
+    (
+        headers,
+        lines
+            .filter(|row| row.iter().any(|c| !c.is_empty()))
+            .collect(),
+    )
+}
+
+/// Split one CSV line, honouring double-quoted fields
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+async fn get_text(client: &reqwest::Client, url: &Url) -> Result<String, MwApiError> {
+    let resp = client.get(url.as_str()).send().await?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(MwApiError::Http {
+            status: status.as_u16(),
+            url: url.to_string(),
+            body: resp.text().await.unwrap_or_default(),
+        });
+    }
+    Ok(resp.text().await?)
+}
+
+/// Run saved PetScan query `psid` and return its pages
+pub async fn fetch_petscan(
+    client: &reqwest::Client,
+    base: &Url,
+    psid: &str,
+) -> Result<Vec<Title>, MwApiError> {
+    let url = petscan_url(base, psid)?;
+    let body = get_text(client, &url).await?;
+    parse_petscan_json(&serde_json::from_str(&body)?)
+}
+
+/// Download a Quarry result set (see [`quarry_result_url`]) and return its
+/// pages
+pub async fn fetch_quarry(
+    client: &reqwest::Client,
+    base: &Url,
+    source: &str,
+) -> Result<Vec<Title>, MwApiError> {
+    let (url, format) = quarry_result_url(base, source)?;
+    let body = get_text(client, &url).await?;
+    parse_quarry(&body, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn displays(titles: &[Title]) -> Vec<&str> {
+        titles.iter().map(|t| t.display.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_petscan_json() {
+        let resp = serde_json::json!({
+            "n": "result",
+            "a": {"query": "..."},
+            "*": [{
+                "n": "combination",
+                "a": {"type": "subset", "*": [
+                    {"id": 1, "namespace": 0, "nstext": "", "title": "Albert_Einstein"},
+                    {"id": 2, "namespace": 14, "nstext": "Category", "title": "German_physicists"},
+                ]}
+            }]
+        });
+        let titles = parse_petscan_json(&resp).unwrap();
+        assert_eq!(
+            displays(&titles),
+            vec!["Albert Einstein", "Category:German physicists"]
+        );
+        assert_eq!(titles[1].namespace, Namespace::CATEGORY);
+        assert_eq!(titles[1].name, "German physicists");
+
+        let err = parse_petscan_json(&serde_json::json!({"error": "No such PSID"}));
+        assert!(err.unwrap_err().to_string().contains("No such PSID"));
+    }
+
+    #[test]
+    fn test_parse_quarry_formats() {
+        let json = r#"{"headers": ["page_namespace", "page_title"],
+            "rows": [[0, "Foo_bar"], [10, "Infobox_person"]]}"#;
+        let csv = "page_title,page_namespace\r\n\"Smith,_John\",0\r\nInfobox_person,10\r\n";
+        let tsv = "title\nFoo_bar\nTemplate:Infobox_person\n";
+
+        assert_eq!(
+            displays(&parse_quarry(json, QuarryFormat::Json).unwrap()),
+            vec!["Foo bar", "Template:Infobox person"]
+        );
+        assert_eq!(
+            displays(&parse_quarry(csv, QuarryFormat::Csv).unwrap()),
+            vec!["Smith, John", "Template:Infobox person"]
+        );
+        let titles = parse_quarry(tsv, QuarryFormat::Tsv).unwrap();
+        assert_eq!(titles[1].namespace, Namespace::TEMPLATE);
+        assert_eq!(titles[1].name, "Infobox person");
+
+        let err = parse_quarry("page_id,rev_id\n1,2\n", QuarryFormat::Csv).unwrap_err();
+        assert!(err.to_string().contains("No title column"));
+    }
+
+    #[test]
+    fn test_quarry_result_url() {
+        let base = Url::parse(QUARRY_URL).unwrap();
+        let latest = "https://quarry.wmcloud.org/query/42/result/latest/0/json";
+        for source in ["42", "https://quarry.wmcloud.org/query/42"] {
+            let (url, format) = quarry_result_url(&base, source).unwrap();
+            assert_eq!(url.as_str(), latest);
+            assert_eq!(format, QuarryFormat::Json);
+        }
+
+        let output = "https://quarry.wmcloud.org/run/7/output/0/csv";
+        let (url, format) = quarry_result_url(&base, output).unwrap();
+        assert_eq!(url.as_str(), output);
+        assert_eq!(format, QuarryFormat::Csv);
+
+        assert!(quarry_result_url(&base, "https://quarry.wmcloud.org/").is_err());
+        assert!(quarry_result_url(&base, "run/7/output/0/xlsx").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_petscan() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .and(query_param("psid", "123456"))
+            .and(query_param("format", "json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "*": [{"a": {"*": [{"namespace": 0, "nstext": "", "title": "Foo"}]}}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let base = Url::parse(&mock_server.uri()).unwrap();
+        let titles = fetch_petscan(&reqwest::Client::new(), &base, "123456")
+            .await
+            .unwrap();
+        assert_eq!(displays(&titles), vec!["Foo"]);
+
+        assert!(
+            fetch_petscan(&reqwest::Client::new(), &base, "abc")
+                .await
+                .is_err()
+        );
+    }
+}
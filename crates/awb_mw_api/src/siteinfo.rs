@@ -0,0 +1,149 @@
+use crate::error::MwApiError;
+use crate::trace::ApiTrace;
+use awb_domain::siteinfo::{NamespaceInfo, SiteInfo};
+use awb_domain::types::Namespace;
+
+/// Build a [`SiteInfo`] from a `meta=siteinfo` response requested with
+/// `siprop=general|namespaces|namespacealiases|interwikimap` and
+/// `formatversion=2`
+pub fn parse_siteinfo(resp: &serde_json::Value) -> Result<SiteInfo, MwApiError> {
+    let query = &resp["query"];
+    let namespaces: Vec<NamespaceInfo> = query["namespaces"]
+        .as_object()
+        .map(|map| {
+            map.values()
+                .filter_map(|ns| {
+                    let id = Namespace(ns["id"].as_i64()? as i32);
+                    let mut info = NamespaceInfo::new(id, ns["name"].as_str()?)
+                        .with_case_sensitive(ns["case"].as_str() == Some("case-sensitive"));
+                    if let Some(canonical) = ns["canonical"].as_str() {
+                        info = info.with_canonical(canonical);
+                    }
+                    Some(info)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if namespaces.is_empty() {
+        return Err(MwApiError::ApiError {
+            code: "siteinfo".into(),
+            info: "Response has no namespaces".into(),
+        });
+    }
+
+    let aliases = query["namespacealiases"]
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|a| {
+                    Some((
+                        a["alias"].as_str()?.to_string(),
+                        Namespace(a["id"].as_i64()? as i32),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (mut interwiki, mut local_interwiki) = (Vec::new(), Vec::new());
+    for entry in query["interwikimap"].as_array().into_iter().flatten() {
+        let Some(prefix) = entry["prefix"].as_str() else {
+            continue;
+        };
+        let prefix = prefix.to_lowercase();
+        if entry["localinterwiki"].as_bool() == Some(true) {
+            local_interwiki.push(prefix.clone());
+        }
+        interwiki.push(prefix);
+    }
+
+    Ok(SiteInfo {
+        namespaces,
+        aliases,
+        interwiki,
+        local_interwiki,
+        lang: query["general"]["lang"].as_str().map(str::to_string),
+    })
+}
+
+/// Fetch the wiki's content language, namespaces, aliases and interwiki
+/// prefixes
+pub async fn fetch_siteinfo(
+    client: &reqwest::Client,
+    api_url: &url::Url,
+    trace: Option<&ApiTrace>,
+) -> Result<SiteInfo, MwApiError> {
+    let request = client.get(api_url.as_str()).query(&[
+        ("action", "query"),
+        ("meta", "siteinfo"),
+        ("siprop", "general|namespaces|namespacealiases|interwikimap"),
+        ("format", "json"),
+        ("formatversion", "2"),
+    ]);
+    let resp: serde_json::Value = crate::trace::send(trace, request).await?.json().await?;
+
+    if let Some(error) = resp.get("error") {
+        return Err(MwApiError::ApiError {
+            code: error["code"].as_str().unwrap_or("unknown").to_string(),
+            info: error["info"].as_str().unwrap_or("").to_string(),
+        });
+    }
+    parse_siteinfo(&resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::types::Title;
+
+    fn enwiki() -> serde_json::Value {
+        serde_json::json!({
+            "query": {
+                "general": {"lang": "en"},
+                "namespaces": {
+                    "0": {"id": 0, "case": "first-letter", "name": ""},
+                    "4": {"id": 4, "case": "first-letter", "name": "Wikipedia", "canonical": "Project"},
+                    "14": {"id": 14, "case": "first-letter", "name": "Category", "canonical": "Category"}
+                },
+                "namespacealiases": [{"id": 4, "alias": "WP"}],
+                "interwikimap": [
+                    {"prefix": "en", "local": true, "localinterwiki": true},
+                    {"prefix": "de", "local": true},
+                    {"prefix": "wikt", "local": true}
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse_siteinfo() {
+        let site = parse_siteinfo(&enwiki()).unwrap();
+        assert_eq!(site.namespaces.len(), 3);
+        assert_eq!(site.prefix(Namespace::PROJECT), Some("Wikipedia"));
+        assert_eq!(site.namespace_by_name("wp"), Some(Namespace::PROJECT));
+        assert_eq!(site.local_interwiki, vec!["en"]);
+        assert!(site.is_interwiki("wikt"));
+        assert_eq!(site.lang.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_titles_with_fetched_siteinfo() {
+        let site = parse_siteinfo(&enwiki()).unwrap();
+        assert_eq!(
+            Title::parse(&site, "WP:AFD").unwrap().display,
+            "Wikipedia:AFD"
+        );
+        assert_eq!(Title::parse(&site, "en:Foo").unwrap().display, "Foo");
+        assert!(Title::parse(&site, "de:Foo").is_err());
+        // Template is not a namespace on this trimmed-down wiki
+        assert_eq!(
+            Title::parse(&site, "Template:X").unwrap().namespace,
+            Namespace::MAIN
+        );
+    }
+
+    #[test]
+    fn test_parse_siteinfo_without_namespaces() {
+        assert!(parse_siteinfo(&serde_json::json!({"query": {}})).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Opt-in recording of API traffic to a HAR file, for debugging
+//! incompatibilities with third-party wikis.
+//!
+//! Every request and response is sanitized with `awb_security` before it
+//! is written: credential parameters, headers and JSON keys (passwords,
+//! tokens, cookies) are replaced whole, and registered secrets are redacted
+//! wherever else they appear.
+
+use awb_security::{redact_field, redact_json, redact_registered};
+use chrono::Utc;
+use reqwest::header::{CONTENT_TYPE, HeaderMap};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Records request/response pairs, keeping `path` a valid HAR 1.2 document
+/// of everything recorded so far
+pub struct ApiTrace {
+    path: PathBuf,
+    entries: Mutex<Vec<Value>>,
+}
+
+impl ApiTrace {
+    /// Start a trace at `path`, replacing any file there
+    pub fn create(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let trace = Self {
+            path: path.into(),
+            entries: Mutex::new(Vec::new()),
+        };
+        trace.write(&[])?;
+        Ok(trace)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of exchanges recorded
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn record(&self, entry: Value) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.push(entry);
+        if let Err(e) = self.write(&entries) {
+            tracing::warn!("Failed to write API trace {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn write(&self, entries: &[Value]) -> std::io::Result<()> {
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "awb-rs", "version": env!("CARGO_PKG_VERSION") },
+                "entries": entries,
+            }
+        });
+        let bytes = serde_json::to_vec_pretty(&har).map_err(std::io::Error::other)?;
+        std::fs::write(&self.path, bytes)
+    }
+}
+
+/// Send `builder`, recording the exchange to `trace` if there is one
+pub(crate) async fn send(
+    trace: Option<&ApiTrace>,
+    builder: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let Some(trace) = trace else {
+        return builder.send().await;
+    };
+    let (client, request) = builder.build_split();
+    let request = request?;
+    let har_req = har_request(&request);
+    let started = Utc::now();
+    let clock = Instant::now();
+
+    let response = client.execute(request).await?;
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    let elapsed_ms = clock.elapsed().as_secs_f64() * 1000.0;
+
+    trace.record(json!({
+        "startedDateTime": started.to_rfc3339(),
+        "time": elapsed_ms,
+        "request": har_req,
+        "response": har_response(status, version, &headers, &body),
+        "cache": {},
+        "timings": { "send": 0, "wait": elapsed_ms, "receive": 0 },
+    }));
+
+    // The body has been read; hand the caller an identical response
+    let mut rebuilt = http::Response::builder().status(status).version(version);
+    if let Some(rebuilt_headers) = rebuilt.headers_mut() {
+        *rebuilt_headers = headers;
+    }
+    Ok(rebuilt
+        .body(body)
+        .expect("status and headers come from a response")
+        .into())
+}
+
+fn har_request(request: &reqwest::Request) -> Value {
+    let mut url = request.url().clone();
+    let query = sanitize_pairs(url.query().unwrap_or("").as_bytes());
+    if !query.is_empty() {
+        url.query_pairs_mut().clear().extend_pairs(&query);
+    }
+
+    let mut entry = json!({
+        "method": request.method().as_str(),
+        "url": url.as_str(),
+        "httpVersion": format!("{:?}", request.version()),
+        "cookies": [],
+        "headers": har_headers(request.headers()),
+        "queryString": name_values(&query),
+        "headersSize": -1,
+        "bodySize": 0,
+    });
+    if let Some(body) = request.body().and_then(reqwest::Body::as_bytes) {
+        let mime_type = content_type(request.headers());
+        let post_data = if mime_type.starts_with("application/x-www-form-urlencoded") {
+            let params = sanitize_pairs(body);
+            let text = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&params)
+                .finish();
+            json!({ "mimeType": mime_type, "params": name_values(&params), "text": text })
+        } else {
+            json!({
+                "mimeType": mime_type,
+                "text": redact_registered(&String::from_utf8_lossy(body)),
+            })
+        };
+        entry["postData"] = post_data;
+        entry["bodySize"] = json!(body.len());
+    }
+    entry
+}
+
+fn har_response(
+    status: reqwest::StatusCode,
+    version: reqwest::Version,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Value {
+    let text = String::from_utf8_lossy(body);
+    let text = match serde_json::from_str::<Value>(&text) {
+        Ok(mut value) => {
+            redact_json(&mut value);
+            value.to_string()
+        }
+        Err(_) => redact_registered(&text),
+    };
+    json!({
+        "status": status.as_u16(),
+        "statusText": status.canonical_reason().unwrap_or(""),
+        "httpVersion": format!("{:?}", version),
+        "cookies": [],
+        "headers": har_headers(headers),
+        "content": {
+            "size": body.len(),
+            "mimeType": content_type(headers),
+            "text": text,
+        },
+        "redirectURL": "",
+        "headersSize": -1,
+        "bodySize": body.len(),
+    })
+}
+
+/// Decode `application/x-www-form-urlencoded` pairs, redacting credentials
+fn sanitize_pairs(encoded: &[u8]) -> Vec<(String, String)> {
+    url::form_urlencoded::parse(encoded)
+        .map(|(name, value)| {
+            let value = redact_field(&name, &value);
+            (name.into_owned(), value)
+        })
+        .collect()
+}
+
+fn har_headers(headers: &HeaderMap) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            json!({
+                "name": name.as_str(),
+                "value": redact_field(name.as_str(), value.to_str().unwrap_or("")),
+            })
+        })
+        .collect()
+}
+
+fn name_values(pairs: &[(String, String)]) -> Vec<Value> {
+    pairs
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn content_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_credentials_stripped() {
+        let request = reqwest::Client::new()
+            .post("https://wiki.example/w/api.php?action=login&format=json")
+            .header("Authorization", "Bearer abcdef")
+            .form(&[
+                ("lgname", "Bot@awb"),
+                ("lgpassword", "hunter2hunter2"),
+                ("lgtoken", "1a2b+\\"),
+            ])
+            .build()
+            .unwrap();
+
+        let entry = har_request(&request);
+        let text = entry.to_string();
+        assert!(!text.contains("hunter2hunter2"));
+        assert!(!text.contains("1a2b"));
+        assert!(!text.contains("abcdef"));
+        assert!(text.contains("Bot@awb"));
+        assert_eq!(entry["method"], "POST");
+        assert_eq!(entry["queryString"][0]["name"], "action");
+        assert_eq!(entry["postData"]["params"][1]["value"], "[REDACTED]");
+    }
+
+    #[test]
+    fn test_response_tokens_stripped() {
+        let mut headers = HeaderMap::new();
+        headers.insert("set-cookie", "session=s3cr3t".parse().unwrap());
+        let body = br#"{"query":{"tokens":{"csrftoken":"c0ffee+\\"}}}"#;
+
+        let entry = har_response(
+            reqwest::StatusCode::OK,
+            reqwest::Version::HTTP_11,
+            &headers,
+            body,
+        );
+        let text = entry.to_string();
+        assert!(!text.contains("c0ffee"));
+        assert!(!text.contains("s3cr3t"));
+        assert_eq!(entry["status"], 200);
+        assert_eq!(entry["content"]["size"], body.len());
+    }
+
+    #[test]
+    fn test_trace_file_is_valid_har() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace = ApiTrace::create(dir.path().join("out.har")).unwrap();
+        assert!(trace.is_empty());
+        trace.record(json!({ "request": {}, "response": {} }));
+
+        let har: Value =
+            serde_json::from_str(&std::fs::read_to_string(trace.path()).unwrap()).unwrap();
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(har["log"]["entries"].as_array().unwrap().len(), 1);
+        assert_eq!(trace.len(), 1);
+    }
+}
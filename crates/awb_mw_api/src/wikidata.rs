@@ -0,0 +1,125 @@
+//! Reading language links from Wikidata items.
+
+use crate::error::MwApiError;
+use crate::list_endpoints::MAX_TITLES_PER_QUERY;
+use std::collections::HashMap;
+
+/// API of wikidata.org
+pub const WIKIDATA_API: &str = "https://www.wikidata.org/w/api.php";
+
+/// Language prefix of a Wikipedia site ID, e.g. `de` for `dewiki` and
+/// `zh-min-nan` for `zh_min_nanwiki`. `None` for sister projects such as
+/// `commonswiki`.
+pub fn site_language(site_id: &str) -> Option<String> {
+    let lang = site_id.strip_suffix("wiki")?.replace('_', "-");
+    let first = lang.split('-').next()?;
+    let is_language = lang == "simple"
+        || ((2..=3).contains(&first.len())
+            && lang.chars().all(|c| c.is_ascii_lowercase() || c == '-'));
+    is_language.then_some(lang)
+}
+
+/// Parse a `wbgetentities` response requested with `props=sitelinks`:
+/// title on `site` → language prefix → title on that wiki. Pages without
+/// an item are left out.
+pub fn parse_sitelinks(
+    resp: &serde_json::Value,
+    site: &str,
+) -> HashMap<String, HashMap<String, String>> {
+    let mut pages = HashMap::new();
+    let Some(entities) = resp["entities"].as_object() else {
+        return pages;
+    };
+    for entity in entities.values() {
+        let Some(sitelinks) = entity["sitelinks"].as_object() else {
+            continue;
+        };
+        let Some(own) = sitelinks.get(site).and_then(|l| l["title"].as_str()) else {
+            continue;
+        };
+        let links = sitelinks
+            .iter()
+            .filter(|(id, _)| id.as_str() != site)
+            .filter_map(|(id, link)| {
+                Some((site_language(id)?, link["title"].as_str()?.to_string()))
+            })
+            .collect();
+        pages.insert(own.to_string(), links);
+    }
+    pages
+}
+
+/// Language links of the Wikidata items connected to `titles` on `site`
+/// (a site ID such as `enwiki`), looked up in batches
+pub async fn fetch_sitelinks(
+    client: &reqwest::Client,
+    wikidata_api: &url::Url,
+    site: &str,
+    titles: &[String],
+) -> Result<HashMap<String, HashMap<String, String>>, MwApiError> {
+    let mut pages = HashMap::new();
+    for batch in titles.chunks(MAX_TITLES_PER_QUERY) {
+        let joined = batch.join("|");
+        let params = [
+            ("action", "wbgetentities"),
+            ("sites", site),
+            ("titles", &joined),
+            ("props", "sitelinks"),
+            ("format", "json"),
+        ];
+
+        let resp: serde_json::Value = client
+            .get(wikidata_api.as_str())
+            .query(&params)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = resp.get("error") {
+            let code = error["code"].as_str().unwrap_or("unknown").to_string();
+            let info = error["info"].as_str().unwrap_or("").to_string();
+            return Err(MwApiError::ApiError { code, info });
+        }
+
+        pages.extend(parse_sitelinks(&resp, site));
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_language() {
+        assert_eq!(site_language("dewiki").as_deref(), Some("de"));
+        assert_eq!(
+            site_language("zh_min_nanwiki").as_deref(),
+            Some("zh-min-nan")
+        );
+        assert_eq!(site_language("simplewiki").as_deref(), Some("simple"));
+        assert_eq!(site_language("commonswiki"), None);
+        assert_eq!(site_language("dewiktionary"), None);
+    }
+
+    #[test]
+    fn test_parse_sitelinks() {
+        let resp = serde_json::json!({
+            "entities": {
+                "Q1": {
+                    "sitelinks": {
+                        "enwiki": { "site": "enwiki", "title": "Foo" },
+                        "dewiki": { "site": "dewiki", "title": "Fu" },
+                        "commonswiki": { "site": "commonswiki", "title": "Category:Foo" }
+                    }
+                },
+                "-1": { "site": "enwiki", "title": "Missing", "missing": "" }
+            }
+        });
+        let pages = parse_sitelinks(&resp, "enwiki");
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages["Foo"].len(), 1);
+        assert_eq!(pages["Foo"]["de"], "Fu");
+    }
+}
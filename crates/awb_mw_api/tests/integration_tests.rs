@@ -1,9 +1,11 @@
-use awb_domain::profile::ThrottlePolicy;
+use awb_domain::profile::{ConnectionOptions, ThrottlePolicy};
 use awb_domain::types::*;
-use awb_mw_api::client::{EditRequest, MediaWikiClient, ReqwestMwClient};
+use awb_mw_api::client::{AssertMode, EditRequest, MediaWikiClient, ReqwestMwClient};
 use awb_mw_api::error::MwApiError;
+use awb_mw_api::trace::ApiTrace;
+use std::sync::Arc;
 use std::time::Duration;
-use wiremock::matchers::{body_string_contains, method, query_param};
+use wiremock::matchers::{basic_auth, body_string_contains, header, method, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 /// Helper to create a test client pointing at a mock server
@@ -103,6 +105,74 @@ async fn test_login_failure() {
     }
 }
 
+#[tokio::test]
+async fn test_trace_records_login_without_credentials() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(query_param("type", "login"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": { "tokens": { "logintoken": "secret_login_token+\\" } }
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=login"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "login": { "result": "Success" } })),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let trace = Arc::new(ApiTrace::create(dir.path().join("out.har")).unwrap());
+    let client = create_test_client(&mock_server.uri()).with_trace(trace.clone());
+    client
+        .login_bot_password("TestBot", "hunter2hunter2")
+        .await
+        .unwrap();
+
+    assert_eq!(trace.len(), 2);
+    let har = std::fs::read_to_string(trace.path()).unwrap();
+    assert!(har.contains("TestBot"));
+    assert!(!har.contains("hunter2hunter2"));
+    assert!(!har.contains("secret_login_token"));
+    let har: serde_json::Value = serde_json::from_str(&har).unwrap();
+    assert_eq!(har["log"]["entries"][1]["request"]["method"], "POST");
+}
+
+#[tokio::test]
+async fn test_connection_options_sent_with_requests() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(query_param("type", "csrf"))
+        .and(basic_auth("gate", "opensesame"))
+        .and(header("X-Team", "docs"))
+        .and(header("User-Agent", "IntranetBot/1.0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": { "tokens": { "csrftoken": "test_csrf_token+\\" } }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let options = ConnectionOptions {
+        headers: [("X-Team".to_string(), "docs".to_string())].into(),
+        basic_auth_user: Some("gate".to_string()),
+        user_agent: Some("IntranetBot/1.0".to_string()),
+        accept_invalid_certs: true,
+    };
+    let client = create_test_client(&mock_server.uri())
+        .with_connection(&options, Some("opensesame"))
+        .unwrap();
+
+    assert_eq!(
+        client.fetch_csrf_token().await.unwrap(),
+        "test_csrf_token+\\"
+    );
+}
+
 #[tokio::test]
 async fn test_fetch_csrf_token() {
     let mock_server = MockServer::start().await;
@@ -265,6 +335,8 @@ async fn test_edit_page_success() {
         base_timestamp: "2024-01-15T10:30:00Z".to_string(),
         start_timestamp: "2024-01-15T10:31:00Z".to_string(),
         section: None,
+        tags: Vec::new(),
+        watchlist: Watchlist::default(),
     };
 
     let result = client.edit_page(&edit_request).await;
@@ -320,6 +392,8 @@ async fn test_edit_page_conflict() {
         base_timestamp: "2024-01-15T10:30:00Z".to_string(),
         start_timestamp: "2024-01-15T10:31:00Z".to_string(),
         section: None,
+        tags: Vec::new(),
+        watchlist: Watchlist::default(),
     };
 
     let result = client.edit_page(&edit_request).await;
@@ -331,6 +405,145 @@ async fn test_edit_page_conflict() {
     }
 }
 
+fn test_edit_request() -> EditRequest {
+    EditRequest {
+        title: Title {
+            namespace: Namespace(0),
+            name: "Test Page".to_string(),
+            display: "Test Page".to_string(),
+        },
+        text: "Updated content".to_string(),
+        summary: "Test edit".to_string(),
+        minor: false,
+        bot: true,
+        base_timestamp: "2024-01-15T10:30:00Z".to_string(),
+        start_timestamp: "2024-01-15T10:31:00Z".to_string(),
+        section: None,
+        tags: Vec::new(),
+        watchlist: Watchlist::default(),
+    }
+}
+
+async fn mount_csrf_token(mock_server: &MockServer, token: &str) {
+    Mock::given(method("GET"))
+        .and(query_param("action", "query"))
+        .and(query_param("meta", "tokens"))
+        .and(query_param("type", "csrf"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "tokens": {
+                    "csrftoken": token
+                }
+            }
+        })))
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_edit_page_sends_assert() {
+    let mock_server = MockServer::start().await;
+    mount_csrf_token(&mock_server, "test_csrf_token+\\").await;
+
+    // Only an edit carrying assert=user succeeds
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .and(body_string_contains("assert=user"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "edit": { "result": "Success", "newrevid": 2 }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = create_test_client(&mock_server.uri()).with_assert(AssertMode::User);
+    let response = client.edit_page(&test_edit_request()).await.unwrap();
+    assert_eq!(response.new_revid, Some(2));
+}
+
+#[tokio::test]
+async fn test_edit_page_sends_tags() {
+    let mock_server = MockServer::start().await;
+    mount_csrf_token(&mock_server, "test_csrf_token+\\").await;
+
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .and(body_string_contains("tags=awb-rs%7Crules-0123abcd"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "edit": { "result": "Success", "newrevid": 3 }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = create_test_client(&mock_server.uri());
+    let edit = EditRequest {
+        tags: vec!["awb-rs".to_string(), "rules-0123abcd".to_string()],
+        ..test_edit_request()
+    };
+    let response = client.edit_page(&edit).await.unwrap();
+    assert_eq!(response.new_revid, Some(3));
+}
+
+#[tokio::test]
+async fn test_edit_page_assert_failure_is_auth_error() {
+    let mock_server = MockServer::start().await;
+    mount_csrf_token(&mock_server, "test_csrf_token+\\").await;
+
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": {
+                "code": "assertuserfailed",
+                "info": "You are no longer logged in"
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = create_test_client(&mock_server.uri()).with_assert(AssertMode::User);
+    match client.edit_page(&test_edit_request()).await {
+        Err(MwApiError::AuthError { reason }) => assert!(reason.contains("assertuserfailed")),
+        other => panic!("Expected AuthError, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_edit_page_readonly_carries_reason() {
+    let mock_server = MockServer::start().await;
+    mount_csrf_token(&mock_server, "test_csrf_token+\\").await;
+
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "error": {
+                "code": "readonly",
+                "info": "The wiki is currently in read-only mode.",
+                "readonlyreason": "Database maintenance, back in 30 minutes"
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = create_test_client(&mock_server.uri());
+    match client.edit_page(&test_edit_request()).await {
+        Err(MwApiError::ReadOnly { reason }) => {
+            assert_eq!(reason, "Database maintenance, back in 30 minutes")
+        }
+        other => panic!("Expected ReadOnly, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_anonymous_csrf_token_rejected() {
+    let mock_server = MockServer::start().await;
+    mount_csrf_token(&mock_server, "+\\").await;
+
+    let client = create_test_client(&mock_server.uri());
+    assert!(matches!(
+        client.fetch_csrf_token().await,
+        Err(MwApiError::AuthError { .. })
+    ));
+}
+
 #[tokio::test]
 async fn test_list_category_members() {
     let mock_server = MockServer::start().await;
@@ -403,6 +616,242 @@ async fn test_list_category_members() {
     assert_eq!(pages[2].display, "Page 3");
 }
 
+#[tokio::test]
+async fn test_fetch_transclusions_and_page_texts() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(query_param("list", "embeddedin"))
+        .and(query_param("eititle", "Template:Infobox person"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "embeddedin": [
+                    { "ns": 0, "title": "Ada Lovelace" },
+                    { "ns": 0, "title": "Gone" }
+                ]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(query_param("prop", "revisions"))
+        .and(query_param("titles", "Ada Lovelace|Gone"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "pages": [
+                    {
+                        "title": "Ada Lovelace",
+                        "revisions": [{ "slots": { "main": { "content": "{{Infobox person}}" } } }]
+                    },
+                    { "title": "Gone", "missing": true }
+                ]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = reqwest::Client::new();
+    let api_url = url::Url::parse(&mock_server.uri()).unwrap();
+
+    let titles =
+        awb_mw_api::list_endpoints::fetch_transclusions(&client, &api_url, "Infobox person")
+            .await
+            .unwrap();
+    assert_eq!(titles.len(), 2);
+
+    let texts = awb_mw_api::list_endpoints::fetch_page_texts(&client, &api_url, &titles)
+        .await
+        .unwrap();
+    assert_eq!(texts.len(), 1, "missing pages are left out");
+    assert_eq!(texts[0].0.display, "Ada Lovelace");
+    assert_eq!(texts[0].1, "{{Infobox person}}");
+}
+
+#[tokio::test]
+async fn test_fetch_double_redirects_and_hops() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(query_param("list", "querypage"))
+        .and(query_param("qppage", "DoubleRedirects"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "querypage": {
+                    "name": "DoubleRedirects",
+                    "results": [{ "value": "0", "ns": 0, "title": "A" }]
+                }
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(query_param("redirects", "1"))
+        .and(query_param("titles", "A"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "redirects": [
+                    { "from": "A", "to": "B" },
+                    { "from": "B", "to": "C", "tofragment": "History" }
+                ],
+                "pages": [{ "ns": 0, "title": "C" }]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = reqwest::Client::new();
+    let api_url = url::Url::parse(&mock_server.uri()).unwrap();
+
+    let pages = awb_mw_api::list_endpoints::fetch_double_redirects(&client, &api_url, 0)
+        .await
+        .unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].display, "A");
+
+    let hops =
+        awb_mw_api::list_endpoints::fetch_redirect_hops(&client, &api_url, &["A".to_string()])
+            .await
+            .unwrap();
+    assert_eq!(hops.len(), 2);
+    assert_eq!(hops[1].to, "C");
+    assert_eq!(hops[1].fragment.as_deref(), Some("History"));
+}
+
+#[tokio::test]
+async fn test_fetch_page_links() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(query_param("list", "backlinks"))
+        .and(query_param("bltitle", "Foo"))
+        .and(query_param("plnamespace", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "pages": [{
+                    "ns": 0,
+                    "title": "Foo",
+                    "links": [{ "ns": 0, "title": "Foo" }, { "ns": 0, "title": "Bar" }]
+                }],
+                "backlinks": [{ "ns": 0, "title": "Foo" }]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = reqwest::Client::new();
+    let api_url = url::Url::parse(&mock_server.uri()).unwrap();
+
+    let links = awb_mw_api::list_endpoints::fetch_page_links(&client, &api_url, "Foo", 10)
+        .await
+        .unwrap()
+        .unwrap();
+    // Self-links count neither way
+    assert_eq!(links.incoming, 0);
+    assert_eq!(links.outgoing, 1);
+    assert!(!links.redirect);
+    assert!(!links.disambiguation);
+}
+
+#[tokio::test]
+async fn test_fetch_file_usage() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(query_param("list", "imageusage"))
+        .and(query_param("iutitle", "File:Old.jpg"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "imageusage": [
+                    { "pageid": 1, "ns": 0, "title": "Foo" },
+                    { "pageid": 2, "ns": 10, "title": "Template:Bar" }
+                ]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = reqwest::Client::new();
+    let api_url = url::Url::parse(&mock_server.uri()).unwrap();
+
+    let titles = awb_mw_api::list_endpoints::fetch_file_usage(&client, &api_url, "Old.jpg")
+        .await
+        .unwrap();
+    assert_eq!(titles.len(), 2);
+    assert_eq!(titles[1].namespace, awb_domain::types::Namespace(10));
+}
+
+#[tokio::test]
+async fn test_fetch_revisions_follows_continuation() {
+    let mock_server = MockServer::start().await;
+
+    let revision = |id: u64, text: &str| {
+        serde_json::json!({
+            "revid": id,
+            "timestamp": "2024-05-01T10:00:00Z",
+            "slots": { "main": { "contentmodel": "wikitext", "content": text } }
+        })
+    };
+    Mock::given(method("GET"))
+        .and(query_param("prop", "revisions"))
+        .and(query_param("rvlimit", "3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "continue": { "rvcontinue": "20240501|1", "continue": "||" },
+            "query": {
+                "pages": [{
+                    "ns": 0,
+                    "title": "Foo",
+                    "revisions": [revision(3, "new"), revision(2, "older")]
+                }]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(query_param("rvlimit", "1"))
+        .and(query_param("rvcontinue", "20240501|1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": {
+                "pages": [{ "ns": 0, "title": "Foo", "revisions": [revision(1, "oldest")] }]
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = reqwest::Client::new();
+    let api_url = url::Url::parse(&mock_server.uri()).unwrap();
+
+    let revisions = awb_mw_api::list_endpoints::fetch_revisions(&client, &api_url, "Foo", 3)
+        .await
+        .unwrap()
+        .unwrap();
+    let ids: Vec<u64> = revisions.iter().map(|rev| rev.revision.0).collect();
+    assert_eq!(ids, vec![3, 2, 1]);
+    assert_eq!(revisions[2].wikitext, "oldest");
+}
+
+#[tokio::test]
+async fn test_fetch_revisions_missing_page() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(query_param("prop", "revisions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "query": { "pages": [{ "ns": 0, "title": "Nope", "missing": true }] }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = reqwest::Client::new();
+    let api_url = url::Url::parse(&mock_server.uri()).unwrap();
+
+    let revisions = awb_mw_api::list_endpoints::fetch_revisions(&client, &api_url, "Nope", 5)
+        .await
+        .unwrap();
+    assert!(revisions.is_none());
+}
+
 #[tokio::test]
 async fn test_retry_on_server_error() {
     let mock_server = MockServer::start().await;
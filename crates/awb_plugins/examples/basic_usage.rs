@@ -87,7 +87,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   Result without exclaim: {}", result2);
 
     println!("\n   Re-enabling 'exclaim' plugin...");
-    manager.enable_plugin("exclaim");
+    manager.enable_plugin("exclaim")?;
 
     let result3 = manager.apply_all("test")?;
     println!("   Result with exclaim: {}\n", result3);
@@ -14,9 +14,24 @@ pub enum PluginError {
     #[error("Plugin execution timed out after {0}s")]
     Timeout(u64),
 
+    #[error("Plugin execution cancelled")]
+    Cancelled,
+
     #[error("Sandboxing violation: {0}")]
     Sandboxed(String),
 
+    #[error("Resource limit exceeded: {0}")]
+    ResourceLimit(String),
+
+    #[error("Plugin verification failed: {0}")]
+    Verification(String),
+
+    #[error("Plugin ordering error: {0}")]
+    Ordering(String),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] awb_storage::StorageError),
+
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
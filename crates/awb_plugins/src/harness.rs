@@ -0,0 +1,310 @@
+use crate::error::{PluginError, Result};
+use crate::manifest::{PluginManifest, PluginTestCase};
+use crate::plugin_manager::load_plugin_file;
+use crate::plugin_trait::Plugin;
+use crate::sandbox::SandboxConfig;
+use awb_engine::diff_engine::{compute_diff, to_unified};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Suffix of a test input file inside a plugin's `tests/` directory
+pub const INPUT_SUFFIX: &str = ".input.wiki";
+/// Suffix of the matching expected-output file
+pub const EXPECTED_SUFFIX: &str = ".expected.wiki";
+
+/// A test case with a display name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedTestCase {
+    pub name: String,
+    pub case: PluginTestCase,
+}
+
+/// Result of running a single test case
+#[derive(Debug, Clone)]
+pub struct CaseReport {
+    pub name: String,
+    /// Output of the first transform, if it succeeded
+    pub output: Option<String>,
+    /// Error raised by the plugin, if any
+    pub error: Option<String>,
+    /// Unified diff from expected to actual output when they differ
+    pub diff: Option<String>,
+    /// Whether transforming the output again left it unchanged
+    pub idempotent: bool,
+    /// Time spent in the first transform
+    pub elapsed: Duration,
+}
+
+impl CaseReport {
+    /// A case passes if the plugin succeeded, matched the expected output
+    /// (when given), and is idempotent
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.diff.is_none() && self.idempotent
+    }
+}
+
+/// Results of running a plugin against all of its test cases
+#[derive(Debug, Clone)]
+pub struct SuiteReport {
+    pub plugin: String,
+    pub cases: Vec<CaseReport>,
+}
+
+impl SuiteReport {
+    pub fn passed(&self) -> bool {
+        self.cases.iter().all(CaseReport::passed)
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.cases.iter().filter(|c| !c.passed()).count()
+    }
+
+    pub fn total_elapsed(&self) -> Duration {
+        self.cases.iter().map(|c| c.elapsed).sum()
+    }
+}
+
+/// A plugin together with the test cases found for it
+pub struct PluginTestSuite {
+    pub plugin: Box<dyn Plugin>,
+    pub cases: Vec<NamedTestCase>,
+}
+
+impl PluginTestSuite {
+    /// Discover a plugin and its test cases.
+    ///
+    /// `path` may be a `.lua`/`.wasm` file or a directory containing exactly
+    /// one plugin file. Cases come from the sidecar manifest's `[[tests]]`
+    /// and from `tests/<case>.input.wiki` files next to the plugin, each
+    /// optionally paired with `tests/<case>.expected.wiki`.
+    pub fn discover(path: &Path) -> Result<Self> {
+        let plugin_path = if path.is_dir() {
+            find_plugin_file(path)?
+        } else {
+            path.to_path_buf()
+        };
+        let plugin = load_plugin_file(&plugin_path, &SandboxConfig::default())?;
+
+        let mut cases = Vec::new();
+        if let Some(manifest) = PluginManifest::load_for(&plugin_path)? {
+            cases.extend(
+                manifest
+                    .tests
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, case)| NamedTestCase {
+                        name: format!("manifest#{}", i + 1),
+                        case,
+                    }),
+            );
+        }
+        if let Some(dir) = plugin_path.parent() {
+            cases.extend(load_case_files(&dir.join("tests"))?);
+        }
+
+        Ok(Self { plugin, cases })
+    }
+
+    /// Run every case against the plugin
+    pub fn run(&self) -> SuiteReport {
+        run_cases(self.plugin.as_ref(), &self.cases)
+    }
+}
+
+/// Find the single plugin file in a directory
+fn find_plugin_file(dir: &Path) -> Result<PathBuf> {
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file()
+            && matches!(
+                path.extension().and_then(|s| s.to_str()),
+                Some("lua") | Some("wasm")
+            )
+        {
+            found.push(path);
+        }
+    }
+    found.sort();
+    match found.len() {
+        1 => Ok(found.remove(0)),
+        0 => Err(PluginError::LoadFailed(format!(
+            "No .lua or .wasm plugin found in {}",
+            dir.display()
+        ))),
+        n => Err(PluginError::LoadFailed(format!(
+            "Expected one plugin in {}, found {}",
+            dir.display(),
+            n
+        ))),
+    }
+}
+
+/// Load `<case>.input.wiki` / `<case>.expected.wiki` pairs, sorted by name
+pub fn load_case_files(dir: &Path) -> Result<Vec<NamedTestCase>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut inputs: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(INPUT_SUFFIX))
+        })
+        .collect();
+    inputs.sort();
+
+    let mut cases = Vec::with_capacity(inputs.len());
+    for input_path in inputs {
+        let file_name = input_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let name = file_name
+            .strip_suffix(INPUT_SUFFIX)
+            .unwrap_or(file_name)
+            .to_string();
+        let expected_path = dir.join(format!("{}{}", name, EXPECTED_SUFFIX));
+        let expected = if expected_path.is_file() {
+            Some(std::fs::read_to_string(&expected_path)?)
+        } else {
+            None
+        };
+        cases.push(NamedTestCase {
+            name,
+            case: PluginTestCase {
+                input: std::fs::read_to_string(&input_path)?,
+                expected,
+            },
+        });
+    }
+    Ok(cases)
+}
+
+/// Run test cases against a plugin, checking expected output and idempotency
+pub fn run_cases(plugin: &dyn Plugin, cases: &[NamedTestCase]) -> SuiteReport {
+    let cases = cases
+        .iter()
+        .map(|named| {
+            let start = Instant::now();
+            let result = plugin.transform(&named.case.input);
+            let elapsed = start.elapsed();
+
+            match result {
+                Ok(output) => {
+                    let diff = named
+                        .case
+                        .expected
+                        .as_ref()
+                        .filter(|expected| **expected != output)
+                        .map(|expected| to_unified(&compute_diff(expected, &output), 3));
+                    let idempotent = plugin.transform(&output).is_ok_and(|again| again == output);
+                    CaseReport {
+                        name: named.name.clone(),
+                        output: Some(output),
+                        error: None,
+                        diff,
+                        idempotent,
+                        elapsed,
+                    }
+                }
+                Err(e) => CaseReport {
+                    name: named.name.clone(),
+                    output: None,
+                    error: Some(e.to_string()),
+                    diff: None,
+                    idempotent: false,
+                    elapsed,
+                },
+            }
+        })
+        .collect();
+
+    SuiteReport {
+        plugin: plugin.name().to_string(),
+        cases,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lua_plugin::LuaPlugin;
+    use crate::sandbox::SandboxConfig;
+
+    fn case(name: &str, input: &str, expected: Option<&str>) -> NamedTestCase {
+        NamedTestCase {
+            name: name.to_string(),
+            case: PluginTestCase {
+                input: input.to_string(),
+                expected: expected.map(str::to_string),
+            },
+        }
+    }
+
+    #[test]
+    fn test_run_cases_reports_diff_and_idempotency() {
+        let upper = LuaPlugin::from_string(
+            "upper",
+            "function transform(text) return string.upper(text) end",
+            SandboxConfig::default(),
+        )
+        .unwrap();
+        let report = run_cases(
+            &upper,
+            &[
+                case("ok", "abc", Some("ABC")),
+                case("wrong", "abc", Some("Abc")),
+            ],
+        );
+        assert!(report.cases[0].passed());
+        assert!(!report.cases[1].passed());
+        assert!(report.cases[1].diff.as_deref().unwrap().contains("+ABC"));
+        assert_eq!(report.failed_count(), 1);
+
+        let appender = LuaPlugin::from_string(
+            "appender",
+            "function transform(text) return text .. '!' end",
+            SandboxConfig::default(),
+        )
+        .unwrap();
+        let report = run_cases(&appender, &[case("grows", "a", Some("a!"))]);
+        assert!(report.cases[0].diff.is_none());
+        assert!(!report.cases[0].idempotent);
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_discover_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("myfix.lua"),
+            "function transform(text) return (text:gsub('teh', 'the')) end",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("myfix.lua.toml"),
+            "[[tests]]\ninput = \"teh cat\"\nexpected = \"the cat\"\n",
+        )
+        .unwrap();
+        let tests = dir.path().join("tests");
+        std::fs::create_dir(&tests).unwrap();
+        std::fs::write(tests.join("basic.input.wiki"), "teh end\n").unwrap();
+        std::fs::write(tests.join("basic.expected.wiki"), "the end\n").unwrap();
+        std::fs::write(tests.join("smoke.input.wiki"), "nothing\n").unwrap();
+
+        let suite = PluginTestSuite::discover(dir.path()).unwrap();
+        let names: Vec<&str> = suite.cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["manifest#1", "basic", "smoke"]);
+        assert!(suite.run().passed());
+    }
+
+    #[test]
+    fn test_discover_requires_single_plugin() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(PluginTestSuite::discover(dir.path()).is_err());
+    }
+}
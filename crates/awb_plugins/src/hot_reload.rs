@@ -0,0 +1,118 @@
+use crate::error::{PluginError, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+use tracing::{debug, warn};
+
+/// Outcome of reloading a single plugin file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReloadEvent {
+    /// The plugin was (re)loaded and passed its manifest tests
+    Reloaded(String),
+    /// The plugin file was deleted and the plugin unloaded
+    Removed(String),
+    /// Reloading failed; the previously loaded version (if any) is kept
+    Failed { path: PathBuf, error: String },
+}
+
+/// Filesystem watcher that collects changed plugin paths between pages.
+pub struct PluginWatcher {
+    // Kept alive for as long as notifications are wanted
+    _watcher: RecommendedWatcher,
+    rx: Receiver<PathBuf>,
+}
+
+impl PluginWatcher {
+    /// Start watching a plugin directory (non-recursively)
+    pub fn new(dir: &Path) -> Result<Self> {
+        let (tx, rx) = channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+                Ok(event) => {
+                    for path in event.paths {
+                        let _ = tx.send(path);
+                    }
+                }
+                Err(e) => warn!("Plugin watcher error: {}", e),
+            })
+            .map_err(|e| PluginError::LoadFailed(format!("Failed to create watcher: {}", e)))?;
+
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| {
+                PluginError::LoadFailed(format!("Failed to watch {}: {}", dir.display(), e))
+            })?;
+
+        debug!("Watching plugin directory: {}", dir.display());
+
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// Drain pending notifications, returning the affected plugin files.
+    ///
+    /// Manifest changes are mapped to the plugin they describe and duplicate
+    /// events for the same file are collapsed.
+    pub fn drain(&self) -> Vec<PathBuf> {
+        let mut paths = BTreeSet::new();
+        while let Ok(path) = self.rx.try_recv() {
+            if let Some(plugin_path) = plugin_path_for(&path) {
+                paths.insert(plugin_path);
+            }
+        }
+        paths.into_iter().collect()
+    }
+}
+
+/// Map a changed file to the plugin file it affects, if any
+pub(crate) fn plugin_path_for(path: &Path) -> Option<PathBuf> {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("lua") | Some("wasm") => Some(path.to_path_buf()),
+        // A manifest, named after the plugin file it describes
+        Some("toml") => {
+            let plugin = path.with_extension("");
+            let is_plugin = matches!(
+                plugin.extension().and_then(|s| s.to_str()),
+                Some("lua" | "wasm")
+            );
+            (is_plugin && plugin.is_file()).then_some(plugin)
+        }
+        // A signature for the plugin or its manifest
+        Some("minisig") => plugin_path_for(&path.with_extension("")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_path_for() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let lua = dir.path().join("dates.lua");
+        let wasm = dir.path().join("dates.wasm");
+        std::fs::write(&lua, "function transform(t) return t end").unwrap();
+        std::fs::write(&wasm, b"\0asm").unwrap();
+
+        assert_eq!(plugin_path_for(&lua), Some(lua.clone()));
+        assert_eq!(
+            plugin_path_for(&dir.path().join("dates.lua.toml")),
+            Some(lua.clone())
+        );
+        assert_eq!(
+            plugin_path_for(&dir.path().join("dates.wasm.toml")),
+            Some(wasm)
+        );
+        assert_eq!(plugin_path_for(&dir.path().join("dates.toml")), None);
+        assert_eq!(plugin_path_for(&dir.path().join("orphan.lua.toml")), None);
+        assert_eq!(
+            plugin_path_for(&dir.path().join("dates.lua.toml.minisig")),
+            Some(lua)
+        );
+        assert_eq!(plugin_path_for(&dir.path().join("notes.txt")), None);
+    }
+}
@@ -32,32 +32,106 @@
 //! end
 //! ```
 //!
+//! A plugin may instead return a table to contribute to the edit summary,
+//! raise warnings, or ask for the page to be skipped:
+//!
+//! ```lua
+//! function transform(text)
+//!     return { text = text, summary = "checked dates", warnings = { "odd date" }, skip = false }
+//! end
+//! ```
+//!
+//! `transform` may also take a second `page` argument with `title`,
+//! `namespace`, `is_redirect`, `revid` and `categories` fields; plugins
+//! declaring only `transform(text)` keep working unchanged.
+//!
+//! ## WASM Components
+//!
+//! Besides core modules that exchange strings through `alloc` and linear
+//! memory, a WASM plugin may be a component implementing the `awb:plugin`
+//! world in `wit/awb-plugin.wit`. Its `transform` export receives the page
+//! context and returns the full outcome, and it may import the `store`
+//! interface; bindings can be generated from the WIT file with
+//! `wit-bindgen` (Rust), `jco` (JavaScript) or `componentize-py` (Python).
+//!
+//! ## Execution Order
+//!
+//! Plugins run in load order (directory entries sorted by name) unless a
+//! sidecar manifest (`uppercase.lua.toml` next to `uppercase.lua`) declares
+//! `runs_after = ["other.lua"]` dependencies or an `exclusive_group`.
+//! Cycles and conflicting enabled plugins are reported as
+//! [`PluginError::Ordering`].
+//!
+//! Manifests may also declare `classification` (`cosmetic`, `maintenance`,
+//! `style_sensitive`, `editorial`) and `min_tier`. Plugins without a
+//! declared tier default to tier 2, so runs at the default strictness do not
+//! execute unvetted third-party transforms.
+//!
+//! ## Hot Reload
+//!
+//! During interactive sessions, [`PluginManager::watch`] observes the plugin
+//! directory and [`PluginManager::reload_changed`] swaps in edited plugins
+//! between pages. A reloaded plugin must pass the `[[tests]]` snippets from
+//! its manifest; failures are reported as [`ReloadEvent::Failed`] and the
+//! previous version stays active.
+//!
 //! ## Sandboxing
 //!
 //! All plugins run in a sandboxed environment with:
 //! - Memory limits (1MB default for Lua)
-//! - Execution time limits (5s default)
+//! - Wall-clock (5s default) and CPU time (2s default) limits per call; time
+//!   spent sleeping or blocked in a host call only counts toward the former
 //! - No filesystem or network access
 //! - Instruction count limits
 //!
+//! Lua plugins run on a pool of up to `lua_pool_size` interpreters (4 by
+//! default) that are reused across pages. Top-level globals are put back as
+//! the script left them after loading before each page, so anything meant
+//! to outlive a page belongs in `mw.store`. [`PluginManager::pool_stats`]
+//! reports how often pages had to wait for a free interpreter.
+//!
+//! With `deterministic` set (see [`SandboxConfig::with_run_id`]), a plugin's
+//! output depends only on the page and the seed: `math.random` is seeded per
+//! page, `math.randomseed` is removed and `pairs` iterates in sorted order.
+//! [`PluginManager::plugin_versions`] gives the source hash of each plugin
+//! for recording alongside the seed.
+//!
 //! ## MediaWiki Helper Functions (Lua)
 //!
 //! Lua plugins have access to `mw` table with helper functions:
 //! - `mw.title(text)` - Extract page title
 //! - `mw.is_redirect(text)` - Check if page is a redirect
 //! - `mw.categories(text)` - Extract all categories
+//! - `mw.regex.match/find/gsub` - Linear-time regex using Rust syntax
+//! - `mw.store.get(key)` / `mw.store.set(key, value)` - Quota-limited state
+//!   shared across pages; cleared at run start unless the manifest sets
+//!   `persist_state = true`
 
 pub mod error;
+pub mod harness;
+pub mod hot_reload;
 pub mod lua_plugin;
+pub mod lua_pool;
+pub mod manifest;
 pub mod plugin_manager;
 pub mod plugin_trait;
+pub mod registry;
 pub mod sandbox;
+pub mod signing;
+pub mod state;
 pub mod wasm_plugin;
 
 // Re-export main types
 pub use error::{PluginError, Result};
+pub use harness::{PluginTestSuite, SuiteReport};
+pub use hot_reload::ReloadEvent;
 pub use lua_plugin::LuaPlugin;
+pub use lua_pool::PoolStats;
+pub use manifest::{PluginManifest, PluginTestCase};
 pub use plugin_manager::{PluginFixModule, PluginManager};
-pub use plugin_trait::{Plugin, PluginType};
+pub use plugin_trait::{PageContext, Plugin, PluginType, TransformOutcome};
+pub use registry::{BundleDescriptor, InstalledPlugin, PluginBundle};
 pub use sandbox::SandboxConfig;
+pub use signing::TrustStore;
+pub use state::PluginState;
 pub use wasm_plugin::WasmPlugin;
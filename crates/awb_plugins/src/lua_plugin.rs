@@ -1,18 +1,177 @@
 use crate::error::{PluginError, Result};
-use crate::plugin_trait::{Plugin, PluginType};
-use crate::sandbox::SandboxConfig;
+use crate::lua_pool::{LuaVmPool, PoolStats};
+use crate::plugin_trait::{PageContext, Plugin, PluginType, TransformOutcome};
+use crate::sandbox::{CallTimer, SandboxConfig, TimeLimit};
+use crate::state::PluginState;
+use awb_domain::warnings::Warning;
+use awb_storage::rule_library::content_hash;
 use mlua::{Lua, Value};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
-use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
-/// A plugin that executes Lua scripts to transform wikitext
+/// `pairs` for deterministic mode: visits keys in a fixed order (numbers,
+/// then strings, each sorted) instead of hash order, which Lua randomizes
+/// per process
+const ORDERED_PAIRS: &str = r#"
+local next, sort, type, tostring = next, table.sort, type, tostring
+local function before(a, b)
+    local ta, tb = type(a), type(b)
+    if ta ~= tb then return ta < tb end
+    if ta == "number" or ta == "string" then return a < b end
+    return tostring(a) < tostring(b)
+end
+pairs = function(t)
+    local keys = {}
+    for k in next, t do keys[#keys + 1] = k end
+    sort(keys, before)
+    local i = 0
+    return function()
+        i = i + 1
+        local k = keys[i]
+        if k ~= nil then return k, t[k] end
+    end, t, nil
+end
+"#;
+
+/// A plugin that executes Lua scripts to transform wikitext.
+///
+/// Each call runs on an interpreter checked out of the plugin's
+/// [`LuaVmPool`], so pages transformed in parallel do not queue on a single
+/// VM. Top-level globals are restored between pages; state meant to carry
+/// over from one page to the next belongs in `mw.store`.
 pub struct LuaPlugin {
     name: String,
     description: String,
+    version: String,
+    state: PluginState,
+    pool: LuaVmPool,
+}
+
+/// One initialized interpreter with a plugin's script loaded
+pub(crate) struct LuaVm {
+    name: String,
     lua: Lua,
     config: SandboxConfig,
-    instruction_counter: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    metadata: PageMetadata,
+    /// The call in progress, read by the instruction hook
+    call: Arc<Mutex<Option<ActiveCall>>>,
+    /// Top-level globals as the script left them after loading
+    baseline: HashMap<String, Value>,
+    /// `math.randomseed`, kept out of the plugin's reach in deterministic
+    /// mode to reseed `math.random` for each page
+    reseed: Option<mlua::Function>,
+}
+
+/// Limits being enforced on the `transform()` call running on a VM
+struct ActiveCall {
+    timer: CallTimer,
+    cancel: Option<CancellationToken>,
+    instruction_limit: Option<u64>,
+    instructions: u64,
+    limit_hit: Option<TimeLimit>,
+}
+
+/// Maximum input length accepted by `mw.regex` functions (2 MB, the largest
+/// page size MediaWiki allows by default)
+const MAX_REGEX_INPUT: usize = 2 * 1024 * 1024;
+
+/// Maximum pattern length accepted by `mw.regex` functions
+const MAX_REGEX_PATTERN: usize = 4096;
+
+/// Number of compiled patterns kept per plugin before the cache is reset
+const REGEX_CACHE_SIZE: usize = 64;
+
+/// Most keys `mw.page.set_property` keeps per page
+const MAX_METADATA_KEYS: usize = 32;
+
+/// Longest key accepted by `mw.page.set_property`
+const MAX_METADATA_KEY: usize = 64;
+
+/// Longest value accepted by `mw.page.set_property`
+const MAX_METADATA_VALUE: usize = 1024;
+
+/// Metadata set with `mw.page.set_property` during one `transform()` call
+#[derive(Clone, Default)]
+struct PageMetadata(Arc<Mutex<BTreeMap<String, String>>>);
+
+impl PageMetadata {
+    fn set(&self, key: String, value: Option<String>) -> mlua::Result<()> {
+        if key.is_empty() || key.len() > MAX_METADATA_KEY {
+            return Err(mlua::Error::RuntimeError(format!(
+                "property key must be 1 to {} bytes",
+                MAX_METADATA_KEY
+            )));
+        }
+        let mut metadata = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(value) = value else {
+            metadata.remove(&key);
+            return Ok(());
+        };
+        if value.len() > MAX_METADATA_VALUE {
+            return Err(mlua::Error::RuntimeError(format!(
+                "property value too long ({} bytes, max: {})",
+                value.len(),
+                MAX_METADATA_VALUE
+            )));
+        }
+        if !metadata.contains_key(&key) && metadata.len() >= MAX_METADATA_KEYS {
+            return Err(mlua::Error::RuntimeError(format!(
+                "too many page properties (max: {})",
+                MAX_METADATA_KEYS
+            )));
+        }
+        metadata.insert(key, value);
+        Ok(())
+    }
+
+    fn take(&self) -> BTreeMap<String, String> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Per-plugin cache of compiled `mw.regex` patterns
+#[derive(Clone, Default)]
+struct RegexCache(Arc<Mutex<HashMap<String, regex::Regex>>>);
+
+impl RegexCache {
+    /// Compile (or fetch) a pattern, enforcing size limits
+    fn get(&self, pattern: &str) -> mlua::Result<regex::Regex> {
+        if pattern.len() > MAX_REGEX_PATTERN {
+            return Err(mlua::Error::RuntimeError(format!(
+                "regex pattern too long ({} bytes, max: {})",
+                pattern.len(),
+                MAX_REGEX_PATTERN
+            )));
+        }
+        let mut cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(re) = cache.get(pattern) {
+            return Ok(re.clone());
+        }
+        let re = regex::RegexBuilder::new(pattern)
+            .size_limit(1 << 20)
+            .dfa_size_limit(1 << 20)
+            .build()
+            .map_err(|e| mlua::Error::RuntimeError(format!("invalid regex: {}", e)))?;
+        if cache.len() >= REGEX_CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), re.clone());
+        Ok(re)
+    }
+}
+
+fn check_regex_input(s: &str) -> mlua::Result<()> {
+    if s.len() > MAX_REGEX_INPUT {
+        return Err(mlua::Error::RuntimeError(format!(
+            "regex input too large ({} bytes, max: {})",
+            s.len(),
+            MAX_REGEX_INPUT
+        )));
+    }
+    Ok(())
 }
 
 /// Convert a serde_json::Value to a Lua value with depth limit to prevent stack overflow
@@ -128,6 +287,11 @@ fn lua_value_to_json_impl(value: &mlua::Value, depth: usize) -> Result<serde_jso
 impl LuaPlugin {
     /// Load a Lua plugin from a file path
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file_with_config(path, SandboxConfig::default())
+    }
+
+    /// Load a Lua plugin from a file with custom configuration
+    pub fn from_file_with_config<P: AsRef<Path>>(path: P, config: SandboxConfig) -> Result<Self> {
         let path = path.as_ref();
         let script = std::fs::read_to_string(path).map_err(|e| {
             PluginError::LoadFailed(format!("Failed to read Lua file {}: {}", path.display(), e))
@@ -140,29 +304,19 @@ impl LuaPlugin {
             .unwrap_or("unknown")
             .to_string();
 
-        Self::from_string(&name, &script, SandboxConfig::default())
+        Self::from_string(&name, &script, config)
     }
 
     /// Load a Lua plugin from a string with custom configuration
     pub fn from_string(name: &str, script: &str, config: SandboxConfig) -> Result<Self> {
-        let lua = Lua::new();
-
-        // Apply sandboxing - remove dangerous modules
-        Self::apply_sandbox(&lua)?;
-
-        // Set memory limit
-        let _ = lua.set_memory_limit(config.memory_limit);
+        let state = PluginState::new(config.state_max_keys, config.state_max_bytes);
 
-        // Add MediaWiki helper functions
-        Self::add_mw_helpers(&lua)?;
-
-        // Load the script
-        lua.load(script)
-            .exec()
-            .map_err(|e| PluginError::LoadFailed(format!("Failed to load Lua script: {}", e)))?;
+        // Load one VM up front so script errors surface here
+        let vm = LuaVm::new(name, script, &config, &state)?;
 
         // Extract description if provided
-        let description = lua
+        let description = vm
+            .lua
             .globals()
             .get::<String>("description")
             .ok()
@@ -170,15 +324,33 @@ impl LuaPlugin {
 
         debug!("Loaded Lua plugin: {} - {}", name, description);
 
+        let (factory_name, factory_script, factory_state) =
+            (name.to_string(), script.to_string(), state.clone());
+        let pool = LuaVmPool::new(
+            vm,
+            config.lua_pool_size,
+            Box::new(move || LuaVm::new(&factory_name, &factory_script, &config, &factory_state)),
+        );
+
         Ok(Self {
             name: name.to_string(),
             description,
-            lua,
-            config,
-            instruction_counter: std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            version: content_hash(script),
+            state,
+            pool,
         })
     }
 
+    /// Run `transform()` on a pooled VM
+    fn execute_transform(
+        &self,
+        input: &str,
+        page: Option<&PageContext>,
+        cancel: Option<CancellationToken>,
+    ) -> Result<TransformOutcome> {
+        self.pool.checkout()?.execute_transform(input, page, cancel)
+    }
+
     /// Apply sandboxing by removing dangerous Lua standard libraries
     fn apply_sandbox(lua: &Lua) -> Result<()> {
         let globals = lua.globals();
@@ -216,6 +388,18 @@ impl LuaPlugin {
         Ok(())
     }
 
+    /// Remove the sources of run-to-run variation left after the sandbox:
+    /// `math.randomseed` (returned so the host can seed each page) and
+    /// hash-ordered `pairs`
+    fn make_deterministic(lua: &Lua) -> Result<mlua::Function> {
+        let math: mlua::Table = lua.globals().get("math")?;
+        let reseed: mlua::Function = math.get("randomseed")?;
+        math.set("randomseed", Value::Nil)?;
+        lua.load(ORDERED_PAIRS).set_name("=ordered_pairs").exec()?;
+        debug!("Lua sandbox is deterministic");
+        Ok(reseed)
+    }
+
     /// Add MediaWiki-specific helper functions to the Lua environment
     fn add_mw_helpers(lua: &Lua) -> Result<()> {
         static TITLE_REGEX: OnceLock<regex::Regex> = OnceLock::new();
@@ -320,35 +504,201 @@ impl LuaPlugin {
         Ok(())
     }
 
-    /// Execute the transform function with instruction count limit
-    fn execute_transform(
-        &self,
-        input: &str,
-        cancel_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
-    ) -> Result<String> {
-        // Reset counter before each execution
-        self.instruction_counter
-            .store(0, std::sync::atomic::Ordering::Relaxed);
-
-        // Set instruction hook if limit is configured or for cancellation
-        let counter = self.instruction_counter.clone();
-        let limit = self.config.instruction_limit;
-        self.lua.set_hook(
+    /// Add `mw.store.get/set` backed by the plugin's quota-limited state
+    fn add_store_helpers(lua: &Lua, state: &PluginState) -> Result<()> {
+        let mw_table: mlua::Table = lua.globals().get("mw")?;
+        let store_table = lua.create_table()?;
+
+        // mw.store.get(key) — returns the stored string or nil
+        let get_state = state.clone();
+        let get_fn = lua.create_function(move |_, key: String| Ok(get_state.get(&key)))?;
+        store_table.set("get", get_fn)?;
+
+        // mw.store.set(key, value) — stores a string (numbers are converted);
+        // nil removes the key. Errors if the quota would be exceeded.
+        let set_state = state.clone();
+        let set_fn = lua.create_function(move |_, (key, value): (String, Option<String>)| {
+            set_state
+                .set(&key, value)
+                .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+        })?;
+        store_table.set("set", set_fn)?;
+
+        mw_table.set("store", store_table)?;
+        Ok(())
+    }
+
+    /// Add `mw.page.set_property` for reporting metadata about the page
+    fn add_page_helpers(lua: &Lua, metadata: &PageMetadata) -> Result<()> {
+        let mw_table: mlua::Table = lua.globals().get("mw")?;
+        let page_table = lua.create_table()?;
+
+        // mw.page.set_property(key, value) — records a string (numbers are
+        // converted) about the page being transformed; nil removes the key
+        let metadata = metadata.clone();
+        let set_fn = lua.create_function(move |_, (key, value): (String, Option<String>)| {
+            metadata.set(key, value)
+        })?;
+        page_table.set("set_property", set_fn)?;
+
+        mw_table.set("page", page_table)?;
+        Ok(())
+    }
+
+    /// Add `mw.regex.match/find/gsub` backed by Rust's linear-time regex engine
+    fn add_regex_helpers(lua: &Lua) -> Result<()> {
+        let mw_table: mlua::Table = lua.globals().get("mw")?;
+        let regex_table = lua.create_table()?;
+        let cache = RegexCache::default();
+
+        // mw.regex.match(s, pattern) — like string.match: returns the capture
+        // groups if the pattern has any, otherwise the whole match; nil if none
+        let match_cache = cache.clone();
+        let match_fn = lua.create_function(move |lua, (s, pattern): (String, String)| {
+            check_regex_input(&s)?;
+            let re = match_cache.get(&pattern)?;
+            let mut values = mlua::MultiValue::new();
+            let Some(caps) = re.captures(&s) else {
+                values.push_back(Value::Nil);
+                return Ok(values);
+            };
+            if caps.len() == 1 {
+                values.push_back(Value::String(lua.create_string(&caps[0])?));
+            } else {
+                for group in caps.iter().skip(1) {
+                    values.push_back(match group {
+                        Some(m) => Value::String(lua.create_string(m.as_str())?),
+                        None => Value::Nil,
+                    });
+                }
+            }
+            Ok(values)
+        })?;
+        regex_table.set("match", match_fn)?;
+
+        // mw.regex.find(s, pattern[, init]) — 1-based inclusive byte offsets
+        // of the first match at or after `init`, or nil
+        let find_cache = cache.clone();
+        let find_fn = lua.create_function(
+            move |_, (s, pattern, init): (String, String, Option<usize>)| {
+                check_regex_input(&s)?;
+                let re = find_cache.get(&pattern)?;
+                let start = init.unwrap_or(1).saturating_sub(1);
+                if start > s.len() || !s.is_char_boundary(start) {
+                    return Ok((None, None));
+                }
+                Ok(re
+                    .find_at(&s, start)
+                    .map(|m| (Some(m.start() + 1), Some(m.end())))
+                    .unwrap_or((None, None)))
+            },
+        )?;
+        regex_table.set("find", find_fn)?;
+
+        // mw.regex.gsub(s, pattern, replacement[, n]) — replaces up to `n`
+        // matches (all if omitted) using `$1`-style references; returns the
+        // new string and the number of replacements
+        let gsub_cache = cache;
+        let gsub_fn = lua.create_function(
+            move |_, (s, pattern, replacement, n): (String, String, String, Option<usize>)| {
+                check_regex_input(&s)?;
+                let re = gsub_cache.get(&pattern)?;
+                let limit = n.unwrap_or(0);
+                let mut count = re.find_iter(&s).count();
+                if limit > 0 {
+                    count = count.min(limit);
+                }
+                let result = re.replacen(&s, limit, replacement.as_str()).into_owned();
+                if result.len() > MAX_REGEX_INPUT {
+                    return Err(mlua::Error::RuntimeError(
+                        "regex replacement result too large".to_string(),
+                    ));
+                }
+                Ok((result, count))
+            },
+        )?;
+        regex_table.set("gsub", gsub_fn)?;
+
+        mw_table.set("regex", regex_table)?;
+        Ok(())
+    }
+}
+
+impl LuaVm {
+    /// Create an interpreter, apply the sandbox and load `script`
+    pub(crate) fn new(
+        name: &str,
+        script: &str,
+        config: &SandboxConfig,
+        state: &PluginState,
+    ) -> Result<Self> {
+        let lua = Lua::new();
+
+        // Apply sandboxing - remove dangerous modules
+        LuaPlugin::apply_sandbox(&lua)?;
+
+        // Set memory limit
+        let _ = lua.set_memory_limit(config.memory_limit);
+
+        // Add MediaWiki helper functions
+        LuaPlugin::add_mw_helpers(&lua)?;
+        LuaPlugin::add_store_helpers(&lua, state)?;
+        LuaPlugin::add_regex_helpers(&lua)?;
+        let metadata = PageMetadata::default();
+        LuaPlugin::add_page_helpers(&lua, &metadata)?;
+        let reseed = if config.deterministic {
+            Some(LuaPlugin::make_deterministic(&lua)?)
+        } else {
+            None
+        };
+
+        // Load the script
+        lua.load(script)
+            .exec()
+            .map_err(|e| PluginError::LoadFailed(format!("Failed to load Lua script: {}", e)))?;
+
+        let mut baseline = HashMap::new();
+        for pair in lua.globals().pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            if let Value::String(key) = key {
+                if let Ok(key) = key.to_str() {
+                    baseline.insert(key.to_string(), value);
+                }
+            }
+        }
+
+        // The hook stays installed for the VM's lifetime and checks whatever
+        // call is in progress
+        let call: Arc<Mutex<Option<ActiveCall>>> = Arc::default();
+        let hook_call = call.clone();
+        lua.set_hook(
             mlua::HookTriggers {
                 every_nth_instruction: Some(1000),
                 ..Default::default()
             },
             move |_lua, _debug| {
-                // Check cancellation flag first
-                if cancel_flag.load(std::sync::atomic::Ordering::Relaxed) {
+                let mut slot = hook_call.lock().unwrap_or_else(|e| e.into_inner());
+                let Some(call) = slot.as_mut() else {
+                    return Ok(mlua::VmState::Continue);
+                };
+                if let Some(exceeded) = call.timer.exceeded() {
+                    call.limit_hit = Some(exceeded);
                     return Err(mlua::Error::RuntimeError(
-                        "Execution cancelled due to timeout".to_string(),
+                        "Execution stopped at sandbox time limit".to_string(),
                     ));
                 }
+                if call
+                    .cancel
+                    .as_ref()
+                    .is_some_and(CancellationToken::is_cancelled)
+                {
+                    return Err(mlua::Error::RuntimeError("Execution cancelled".to_string()));
+                }
 
                 // Check instruction limit if configured
-                if let Some(limit) = limit {
-                    let count = counter.fetch_add(1000, std::sync::atomic::Ordering::Relaxed);
+                if let Some(limit) = call.instruction_limit {
+                    let count = call.instructions;
+                    call.instructions += 1000;
                     if count > limit {
                         return Err(mlua::Error::RuntimeError(
                             "Instruction limit exceeded".to_string(),
@@ -359,31 +709,162 @@ impl LuaPlugin {
             },
         );
 
+        Ok(Self {
+            name: name.to_string(),
+            lua,
+            config: config.clone(),
+            metadata,
+            call,
+            baseline,
+            reseed,
+        })
+    }
+
+    /// Execute the transform function under the instruction, wall-clock and
+    /// CPU time limits, stopping early if `cancel` is cancelled
+    pub(crate) fn execute_transform(
+        &self,
+        input: &str,
+        page: Option<&PageContext>,
+        cancel: Option<CancellationToken>,
+    ) -> Result<TransformOutcome> {
         // Get the transform function
         let globals = self.lua.globals();
         let transform: mlua::Function = globals.get("transform").map_err(|e| {
             PluginError::LoadFailed(format!("transform() function not found: {}", e))
         })?;
 
-        // Call the transform function
-        let result: String = transform
-            .call(input.to_string())
-            .map_err(|e| PluginError::ExecutionFailed(format!("Lua execution error: {}", e)))?;
+        // Call the transform function. Single-argument plugins simply ignore
+        // the page table.
+        let page_value = match page {
+            Some(page) => Value::Table(self.page_table(page)?),
+            None => Value::Nil,
+        };
+        // Metadata left over from a failed call is not this page's
+        self.metadata.take();
+        if let Some(reseed) = &self.reseed {
+            let key = match page {
+                Some(page) => format!("{}:{}", page.namespace, page.title),
+                None => input.to_string(),
+            };
+            reseed.call::<()>(self.config.page_seed(&self.name, &key) as i64)?;
+        }
+
+        // The hook runs on this thread, so the timer's CPU clock measures
+        // only the plugin's own work
+        *self.lock_call() = Some(ActiveCall {
+            timer: CallTimer::start(&self.config),
+            cancel,
+            instruction_limit: self.config.instruction_limit,
+            instructions: 0,
+            limit_hit: None,
+        });
+        let result = transform.call::<Value>((input.to_string(), page_value));
+        let call = self.lock_call().take();
+        let result = result.map_err(|e| match call.and_then(|call| call.limit_hit) {
+            Some(exceeded) => exceeded.error(&self.config),
+            None => PluginError::ExecutionFailed(format!("Lua execution error: {}", e)),
+        })?;
 
-        // Remove hook
-        self.lua.remove_hook();
+        let mut outcome = self.outcome_from_lua(result)?;
+        outcome.metadata = self.metadata.take();
 
         // Check output size limit
         const MAX_OUTPUT_SIZE: usize = 10 * 1024 * 1024; // 10 MB
-        if result.len() > MAX_OUTPUT_SIZE {
+        if outcome.text.len() > MAX_OUTPUT_SIZE {
             return Err(PluginError::ExecutionFailed(format!(
                 "Plugin output exceeds size limit ({} bytes, max: {} bytes)",
-                result.len(),
+                outcome.text.len(),
                 MAX_OUTPUT_SIZE
             )));
         }
 
-        Ok(result)
+        Ok(outcome)
+    }
+
+    fn lock_call(&self) -> std::sync::MutexGuard<'_, Option<ActiveCall>> {
+        self.call.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Restore the top-level globals the script had after loading and drop
+    /// anything left over from the last page
+    pub(crate) fn reset(&self) -> Result<()> {
+        self.metadata.take();
+        let globals = self.lua.globals();
+        let mut added = Vec::new();
+        for pair in globals.pairs::<Value, Value>() {
+            let (key, _) = pair?;
+            if let Value::String(name) = &key {
+                let known = name
+                    .to_str()
+                    .is_ok_and(|name| self.baseline.contains_key(&*name));
+                if !known {
+                    added.push(key);
+                }
+            }
+        }
+        for key in added {
+            globals.raw_set(key, Value::Nil)?;
+        }
+        for (name, value) in &self.baseline {
+            globals.raw_set(name.as_str(), value.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Build the `page` table passed as the second argument to `transform()`
+    fn page_table(&self, page: &PageContext) -> Result<mlua::Table> {
+        let table = self.lua.create_table()?;
+        table.set("title", page.title.as_str())?;
+        table.set("namespace", page.namespace)?;
+        table.set("is_redirect", page.is_redirect)?;
+        table.set("revid", page.revid)?;
+        table.set("categories", page.categories.clone())?;
+        Ok(table)
+    }
+
+    /// Convert the value returned by `transform()` into a [`TransformOutcome`].
+    ///
+    /// A plain string is treated as the new text. A table may carry `text`,
+    /// `summary`, `warnings` (array of strings) and `skip` (boolean) fields.
+    fn outcome_from_lua(&self, value: Value) -> Result<TransformOutcome> {
+        match value {
+            Value::String(s) => Ok(TransformOutcome::text(s.to_str()?.to_string())),
+            Value::Table(t) => {
+                let text: String = t.get("text").map_err(|e| {
+                    PluginError::InvalidReturn(format!("result table missing 'text': {}", e))
+                })?;
+                let summary_fragment: Option<String> = t.get("summary")?;
+                let skip_page: Option<bool> = t.get("skip")?;
+                let messages: Option<Vec<String>> = t.get("warnings")?;
+                let warnings = messages
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|message| Warning::Plugin {
+                        plugin: self.name.clone(),
+                        message,
+                    })
+                    .collect();
+                Ok(TransformOutcome {
+                    text,
+                    summary_fragment: summary_fragment.filter(|s| !s.trim().is_empty()),
+                    warnings,
+                    skip_page: skip_page.unwrap_or(false),
+                    metadata: BTreeMap::new(),
+                })
+            }
+            other => {
+                // Keep Lua's usual coercion (e.g. numbers) for non-table returns
+                let type_name = other.type_name();
+                let text = <String as mlua::FromLua>::from_lua(other, &self.lua).map_err(|_| {
+                    PluginError::InvalidReturn(format!(
+                        "transform() must return a string or table, got {}",
+                        type_name
+                    ))
+                })?;
+                Ok(TransformOutcome::text(text))
+            }
+        }
     }
 }
 
@@ -397,38 +878,43 @@ impl Plugin for LuaPlugin {
     }
 
     fn transform(&self, input: &str) -> Result<String> {
-        // Execute with cancellation flag
-        let cancel_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
-        let done_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
-        let cancel_flag_thread = cancel_flag.clone();
-        let done_flag_thread = done_flag.clone();
-        let cancel_flag_exec = cancel_flag.clone();
-        let timeout = self.config.timeout;
-
-        // Spawn a timeout handler thread that sets the cancellation flag
-        let timeout_handle = std::thread::spawn(move || {
-            let check_interval = std::time::Duration::from_millis(100);
-            let start = std::time::Instant::now();
-            loop {
-                std::thread::sleep(check_interval);
-                if done_flag_thread.load(std::sync::atomic::Ordering::Relaxed) {
-                    break;
-                }
-                if start.elapsed() >= timeout {
-                    cancel_flag_thread.store(true, std::sync::atomic::Ordering::Relaxed);
-                    break;
-                }
-            }
-        });
+        self.transform_outcome(input).map(|outcome| outcome.text)
+    }
+
+    fn transform_outcome(&self, input: &str) -> Result<TransformOutcome> {
+        self.execute_transform(input, None, None)
+    }
+
+    fn transform_with_context(&self, input: &str, page: &PageContext) -> Result<TransformOutcome> {
+        self.execute_transform(input, Some(page), None)
+    }
+
+    fn transform_cancellable(
+        &self,
+        input: &str,
+        page: &PageContext,
+        cancel: &CancellationToken,
+    ) -> Result<TransformOutcome> {
+        if cancel.is_cancelled() {
+            return Err(PluginError::Cancelled);
+        }
+        let result = self.execute_transform(input, Some(page), Some(cancel.clone()));
+        match result {
+            Err(_) if cancel.is_cancelled() => Err(PluginError::Cancelled),
+            result => result,
+        }
+    }
 
-        // Execute in current thread - the Lua hook will check cancel_flag
-        let result = self.execute_transform(input, cancel_flag_exec);
-        done_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+    fn state(&self) -> Option<&PluginState> {
+        Some(&self.state)
+    }
 
-        // Wait for timeout thread to finish
-        let _ = timeout_handle.join();
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(self.pool.stats())
+    }
 
-        result
+    fn version(&self) -> Option<&str> {
+        Some(&self.version)
     }
 
     fn plugin_type(&self) -> PluginType {
@@ -458,6 +944,240 @@ mod tests {
         assert_eq!(result, "HELLO WORLD");
     }
 
+    #[test]
+    fn test_structured_outcome() {
+        let script = r#"
+            function transform(text)
+                return {
+                    text = text .. "!",
+                    summary = "added emphasis",
+                    warnings = { "check punctuation" },
+                }
+            end
+        "#;
+
+        let plugin = LuaPlugin::from_string("outcome", script, SandboxConfig::default()).unwrap();
+        let outcome = plugin.transform_outcome("hello").unwrap();
+        assert_eq!(outcome.text, "hello!");
+        assert_eq!(outcome.summary_fragment.as_deref(), Some("added emphasis"));
+        assert_eq!(outcome.warnings.len(), 1);
+        assert!(matches!(
+            &outcome.warnings[0],
+            Warning::Plugin { plugin, message } if plugin == "outcome" && message == "check punctuation"
+        ));
+        assert!(!outcome.skip_page);
+
+        // The plain transform() view still returns just the text
+        assert_eq!(plugin.transform("hello").unwrap(), "hello!");
+    }
+
+    #[test]
+    fn test_page_set_property() {
+        let script = r#"
+            function transform(text)
+                mw.page.set_property("review", "needs human review")
+                mw.page.set_property("score", 3)
+                mw.page.set_property("draft", "yes")
+                mw.page.set_property("draft", nil)
+                return text
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("classify", script, SandboxConfig::default()).unwrap();
+        let outcome = plugin.transform_outcome("hello").unwrap();
+        assert_eq!(outcome.text, "hello");
+        assert_eq!(
+            outcome.metadata.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("review".to_string(), "needs human review".to_string()),
+                ("score".to_string(), "3".to_string()),
+            ]
+        );
+
+        // Each call starts with no metadata
+        let script = r#"
+            function transform(text)
+                local calls = tonumber(mw.store.get("calls") or "0") + 1
+                mw.store.set("calls", calls)
+                if calls == 1 then mw.page.set_property("first", "yes") end
+                return text
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("once", script, SandboxConfig::default()).unwrap();
+        assert_eq!(plugin.transform_outcome("a").unwrap().metadata.len(), 1);
+        assert!(plugin.transform_outcome("a").unwrap().metadata.is_empty());
+
+        let script = r#"
+            function transform(text)
+                mw.page.set_property("", "x")
+                return text
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("bad", script, SandboxConfig::default()).unwrap();
+        assert!(plugin.transform_outcome("a").is_err());
+    }
+
+    #[test]
+    fn test_structured_outcome_skip_and_invalid() {
+        let script = r#"
+            function transform(text)
+                return { text = text, skip = true }
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("skipper", script, SandboxConfig::default()).unwrap();
+        assert!(plugin.transform_outcome("hello").unwrap().skip_page);
+
+        let script = r#"
+            function transform(text)
+                return true
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("bad", script, SandboxConfig::default()).unwrap();
+        assert!(matches!(
+            plugin.transform_outcome("hello"),
+            Err(PluginError::InvalidReturn(_))
+        ));
+    }
+
+    #[test]
+    fn test_transform_with_page_context() {
+        let script = r#"
+            function transform(text, page)
+                return string.format("%s|%s|%d|%s|%d|%s",
+                    text, page.title, page.namespace, tostring(page.is_redirect),
+                    page.revid, table.concat(page.categories, ","))
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("ctx", script, SandboxConfig::default()).unwrap();
+        let page = PageContext {
+            title: "Example".to_string(),
+            namespace: 0,
+            is_redirect: false,
+            revid: Some(42),
+            categories: vec!["Foo".to_string(), "Bar".to_string()],
+        };
+        let outcome = plugin.transform_with_context("x", &page).unwrap();
+        assert_eq!(outcome.text, "x|Example|0|false|42|Foo,Bar");
+    }
+
+    #[test]
+    fn test_single_argument_plugin_with_context() {
+        let script = r#"
+            function transform(text)
+                return string.upper(text)
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("old", script, SandboxConfig::default()).unwrap();
+        let outcome = plugin
+            .transform_with_context("hello", &PageContext::default())
+            .unwrap();
+        assert_eq!(outcome.text, "HELLO");
+
+        // Two-argument plugins see nil when called without context
+        let script = r#"
+            function transform(text, page)
+                if page == nil then return "none" end
+                return page.title
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("new", script, SandboxConfig::default()).unwrap();
+        assert_eq!(plugin.transform("hello").unwrap(), "none");
+    }
+
+    #[test]
+    fn test_mw_store_persists_across_calls() {
+        let script = r#"
+            function transform(text)
+                local n = tonumber(mw.store.get("count") or "0") + 1
+                mw.store.set("count", n)
+                return text .. n
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("counter", script, SandboxConfig::default()).unwrap();
+        assert_eq!(plugin.transform("a").unwrap(), "a1");
+        assert_eq!(plugin.transform("b").unwrap(), "b2");
+        assert_eq!(plugin.state().unwrap().get("count").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_mw_store_quota() {
+        let script = r#"
+            function transform(text)
+                mw.store.set("big", string.rep("x", 100))
+                return text
+            end
+        "#;
+        let config = SandboxConfig {
+            state_max_bytes: 50,
+            ..SandboxConfig::default()
+        };
+        let plugin = LuaPlugin::from_string("hog", script, config).unwrap();
+        assert!(plugin.transform("a").is_err());
+        assert_eq!(plugin.state().unwrap().used_bytes(), 0);
+    }
+
+    #[test]
+    fn test_mw_regex_match_and_find() {
+        let script = r#"
+            function transform(text)
+                local y, m = mw.regex.match(text, "(\\d{4})-(\\d{2})")
+                local whole = mw.regex.match(text, "\\d+")
+                local s, e = mw.regex.find(text, "\\d{2}", 6)
+                local none = mw.regex.match(text, "zzz")
+                return table.concat({ y, m, whole, s, e, tostring(none) }, ",")
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("re", script, SandboxConfig::default()).unwrap();
+        assert_eq!(
+            plugin.transform("2024-05-01").unwrap(),
+            "2024,05,2024,6,7,nil"
+        );
+    }
+
+    #[test]
+    fn test_mw_regex_gsub() {
+        let script = r##"
+            function transform(text)
+                local all, n = mw.regex.gsub(text, "(\\w+)@", "$1 at ")
+                local first = mw.regex.gsub(text, "@", "#", 1)
+                return all .. "|" .. n .. "|" .. first
+            end
+        "##;
+        let plugin = LuaPlugin::from_string("re", script, SandboxConfig::default()).unwrap();
+        assert_eq!(plugin.transform("a@ b@").unwrap(), "a at  b at |2|a# b@");
+    }
+
+    #[test]
+    fn test_mw_regex_rejects_invalid_and_oversized() {
+        let script = r#"
+            function transform(text)
+                return mw.regex.match(text, "(unclosed") or text
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("re", script, SandboxConfig::default()).unwrap();
+        assert!(plugin.transform("x").is_err());
+
+        // Backreferences are unsupported by design (linear-time engine)
+        let script = r#"
+            function transform(text)
+                return mw.regex.match(text, "(a)\\1") or text
+            end
+        "#;
+        let plugin = LuaPlugin::from_string("re", script, SandboxConfig::default()).unwrap();
+        assert!(plugin.transform("aa").is_err());
+
+        let script = r#"
+            function transform(text)
+                return mw.regex.match(string.rep("a", 3 * 1024 * 1024), "a") or text
+            end
+        "#;
+        let config = SandboxConfig {
+            memory_limit: 64 * 1024 * 1024,
+            ..SandboxConfig::default()
+        };
+        let plugin = LuaPlugin::from_string("re", script, config).unwrap();
+        assert!(plugin.transform("x").is_err());
+    }
+
     #[test]
     fn test_mw_helpers() {
         let script = r#"
@@ -513,6 +1233,118 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_cancellation_stops_running_plugin() {
+        let script = r#"
+            function transform(text)
+                while true do end
+            end
+        "#;
+        // Neither limit would stop it within the test
+        let config = SandboxConfig {
+            timeout: std::time::Duration::from_secs(60),
+            instruction_limit: None,
+            ..SandboxConfig::default()
+        };
+        let plugin = LuaPlugin::from_string("spin", script, config).unwrap();
+        let cancel = CancellationToken::new();
+        let interrupt = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            interrupt.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        let result = plugin.transform_cancellable("test", &PageContext::default(), &cancel);
+        assert!(matches!(result, Err(PluginError::Cancelled)));
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+
+        // An already cancelled run does not start
+        let result = plugin.transform_cancellable("test", &PageContext::default(), &cancel);
+        assert!(matches!(result, Err(PluginError::Cancelled)));
+    }
+
+    #[test]
+    fn test_wall_clock_timeout() {
+        let script = "function transform(text) while true do end end";
+        let config = SandboxConfig {
+            timeout: std::time::Duration::from_millis(100),
+            cpu_time: std::time::Duration::from_secs(60),
+            instruction_limit: None,
+            ..SandboxConfig::default()
+        };
+        let plugin = LuaPlugin::from_string("spin", script, config).unwrap();
+        assert!(matches!(
+            plugin.transform("test"),
+            Err(PluginError::Timeout(1))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cpu_time_limit() {
+        let script = "function transform(text) while true do end end";
+        let config = SandboxConfig {
+            timeout: std::time::Duration::from_secs(60),
+            cpu_time: std::time::Duration::from_millis(100),
+            instruction_limit: None,
+            ..SandboxConfig::default()
+        };
+        let plugin = LuaPlugin::from_string("spin", script, config).unwrap();
+        let err = plugin.transform("test").unwrap_err();
+        assert!(matches!(err, PluginError::ResourceLimit(_)));
+        assert!(
+            err.to_string()
+                .contains("CPU time limit exceeded (max: 100ms)")
+        );
+    }
+
+    #[test]
+    fn test_deterministic_mode() {
+        let script = r#"
+            function transform(text, page)
+                local keys = {}
+                for k in pairs({ zeta = 1, alpha = 2, [2] = 3, mid = 4, [1] = 5 }) do
+                    keys[#keys + 1] = tostring(k)
+                end
+                return table.concat(keys, ",") .. " " .. math.random(1000000)
+                    .. " " .. tostring(math.randomseed)
+            end
+        "#;
+        let page = |title: &str| PageContext {
+            title: title.to_string(),
+            ..PageContext::default()
+        };
+        let run = |run_id: &str, titles: &[&str]| {
+            let config = SandboxConfig::default().with_run_id(run_id);
+            let plugin = LuaPlugin::from_string("random", script, config).unwrap();
+            let mut results: Vec<(String, String)> = titles
+                .iter()
+                .map(|title| {
+                    let text = plugin
+                        .transform_with_context("", &page(title))
+                        .unwrap()
+                        .text;
+                    (title.to_string(), text)
+                })
+                .collect();
+            results.sort();
+            results
+        };
+
+        // Same pages in a different order give the same output
+        let first = run("run-1", &["Foo", "Bar"]);
+        assert_eq!(first, run("run-1", &["Bar", "Foo"]));
+        assert_ne!(first[0].1, first[1].1);
+        assert_ne!(first, run("run-2", &["Foo", "Bar"]));
+        assert!(
+            first[0].1.starts_with("1,2,alpha,mid,zeta "),
+            "{}",
+            first[0].1
+        );
+        assert!(first[0].1.ends_with(" nil"), "{}", first[0].1);
+    }
+
     #[test]
     fn test_categories_helper() {
         let script = r#"
@@ -0,0 +1,211 @@
+use crate::error::Result;
+use crate::lua_plugin::LuaVm;
+use serde::Serialize;
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// How a plugin's pool of Lua interpreters has been used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+    /// Interpreters alive, idle or in use
+    pub vms: usize,
+    /// Calls that took an interpreter from the pool
+    pub checkouts: u64,
+    /// Checkouts that had to wait because every interpreter was busy
+    pub waits: u64,
+    /// Total time checkouts spent waiting
+    pub wait_time: Duration,
+}
+
+type VmFactory = Box<dyn Fn() -> Result<LuaVm> + Send + Sync>;
+
+/// Initialized interpreters for one Lua plugin.
+///
+/// Interpreters are created on demand up to `max_size` and reset, rather
+/// than rebuilt, between pages. Callers beyond `max_size` wait for one to
+/// be returned.
+pub(crate) struct LuaVmPool {
+    inner: Mutex<PoolInner>,
+    returned: Condvar,
+    max_size: usize,
+    factory: VmFactory,
+}
+
+struct PoolInner {
+    idle: Vec<LuaVm>,
+    stats: PoolStats,
+}
+
+impl LuaVmPool {
+    /// Create a pool holding `first`, which may grow to `max_size`
+    /// interpreters made by `factory`
+    pub(crate) fn new(first: LuaVm, max_size: usize, factory: VmFactory) -> Self {
+        Self {
+            inner: Mutex::new(PoolInner {
+                idle: vec![first],
+                stats: PoolStats {
+                    vms: 1,
+                    ..PoolStats::default()
+                },
+            }),
+            returned: Condvar::new(),
+            max_size: max_size.max(1),
+            factory,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take an idle interpreter, creating one if the pool has room and
+    /// waiting for one to be returned otherwise
+    pub(crate) fn checkout(&self) -> Result<PooledVm<'_>> {
+        let mut inner = self.lock();
+        inner.stats.checkouts += 1;
+        let mut waiting_since = None;
+        loop {
+            if let Some(vm) = inner.idle.pop() {
+                Self::stop_waiting(&mut inner.stats, waiting_since);
+                return Ok(PooledVm {
+                    pool: self,
+                    vm: Some(vm),
+                });
+            }
+            if inner.stats.vms < self.max_size {
+                inner.stats.vms += 1;
+                Self::stop_waiting(&mut inner.stats, waiting_since);
+                drop(inner);
+                return match (self.factory)() {
+                    Ok(vm) => {
+                        debug!("Lua VM pool grew to {} interpreters", self.stats().vms);
+                        Ok(PooledVm {
+                            pool: self,
+                            vm: Some(vm),
+                        })
+                    }
+                    Err(e) => {
+                        self.lock().stats.vms -= 1;
+                        self.returned.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            if waiting_since.is_none() {
+                inner.stats.waits += 1;
+                waiting_since = Some(Instant::now());
+            }
+            inner = self.returned.wait(inner).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn stop_waiting(stats: &mut PoolStats, waiting_since: Option<Instant>) {
+        if let Some(since) = waiting_since {
+            stats.wait_time += since.elapsed();
+        }
+    }
+
+    /// Reset `vm` and make it available again; an interpreter that cannot
+    /// be reset is dropped so a fresh one takes its place
+    fn give_back(&self, vm: LuaVm) {
+        let reset = vm.reset();
+        let mut inner = self.lock();
+        match reset {
+            Ok(()) => inner.idle.push(vm),
+            Err(e) => {
+                warn!("Discarding Lua VM that failed to reset: {}", e);
+                inner.stats.vms -= 1;
+            }
+        }
+        drop(inner);
+        self.returned.notify_one();
+    }
+
+    pub(crate) fn stats(&self) -> PoolStats {
+        self.lock().stats
+    }
+}
+
+/// An interpreter checked out of a [`LuaVmPool`], returned when dropped
+pub(crate) struct PooledVm<'a> {
+    pool: &'a LuaVmPool,
+    vm: Option<LuaVm>,
+}
+
+impl Deref for PooledVm<'_> {
+    type Target = LuaVm;
+
+    fn deref(&self) -> &LuaVm {
+        self.vm.as_ref().expect("VM is only taken on drop")
+    }
+}
+
+impl Drop for PooledVm<'_> {
+    fn drop(&mut self) {
+        if let Some(vm) = self.vm.take() {
+            self.pool.give_back(vm);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lua_plugin::LuaPlugin;
+    use crate::plugin_trait::Plugin;
+    use crate::sandbox::SandboxConfig;
+    use std::sync::Arc;
+
+    const COUNTER: &str = r#"
+        seen = 0
+        function transform(text)
+            seen = seen + 1
+            local before = tostring(leaked)
+            leaked = "x"
+            return text .. seen .. before
+        end
+    "#;
+
+    #[test]
+    fn test_globals_reset_between_pages() {
+        let plugin = LuaPlugin::from_string("counter", COUNTER, SandboxConfig::default()).unwrap();
+        assert_eq!(plugin.transform("a").unwrap(), "a1nil");
+        assert_eq!(plugin.transform("b").unwrap(), "b1nil");
+
+        let stats = plugin.pool_stats().unwrap();
+        assert_eq!(stats.vms, 1);
+        assert_eq!(stats.checkouts, 2);
+        assert_eq!(stats.waits, 0);
+    }
+
+    #[test]
+    fn test_pool_is_bounded_across_threads() {
+        let script = r#"
+            function transform(text)
+                local n = 0
+                for i = 1, 200000 do n = n + 1 end
+                return text
+            end
+        "#;
+        let config = SandboxConfig {
+            lua_pool_size: 2,
+            instruction_limit: None,
+            ..SandboxConfig::default()
+        };
+        let plugin = Arc::new(LuaPlugin::from_string("busy", script, config).unwrap());
+        let threads: Vec<_> = (0..6)
+            .map(|i| {
+                let plugin = plugin.clone();
+                std::thread::spawn(move || plugin.transform(&i.to_string()).unwrap())
+            })
+            .collect();
+        for (i, thread) in threads.into_iter().enumerate() {
+            assert_eq!(thread.join().unwrap(), i.to_string());
+        }
+
+        let stats = plugin.pool_stats().unwrap();
+        assert!(stats.vms <= 2, "{:?}", stats);
+        assert_eq!(stats.checkouts, 6);
+    }
+}
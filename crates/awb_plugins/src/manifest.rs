@@ -0,0 +1,219 @@
+use crate::error::{PluginError, Result};
+use crate::plugin_trait::Plugin;
+use awb_engine::fix_config::FixClassification;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Optional metadata describing how a plugin fits into the pipeline.
+///
+/// Manifests live next to the plugin as a sidecar TOML file named after the
+/// whole plugin file, e.g. `dates.lua` is described by `dates.lua.toml`, so
+/// `dates.lua` and `dates.wasm` can each have their own:
+///
+/// ```toml
+/// runs_after = ["cleanup.lua"]
+/// exclusive_group = "date-formatting"
+/// persist_state = false
+/// classification = "style_sensitive"
+/// min_tier = 2
+///
+/// [[tests]]
+/// input = "1 january 2020"
+/// expected = "1 January 2020"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    /// Names of plugins that must run before this one (if loaded)
+    #[serde(default)]
+    pub runs_after: Vec<String>,
+    /// Plugins sharing a group are mutually exclusive; at most one may be enabled
+    #[serde(default)]
+    pub exclusive_group: Option<String>,
+    /// Kind of change the plugin makes; defaults to `maintenance`
+    #[serde(default)]
+    pub classification: Option<FixClassification>,
+    /// Minimum strictness tier (0-3) required to run the plugin; defaults to
+    /// [`DEFAULT_PLUGIN_TIER`]
+    #[serde(default)]
+    pub min_tier: Option<u8>,
+    /// Keep `mw.store` state across runs instead of clearing it at run start
+    #[serde(default)]
+    pub persist_state: bool,
+    /// Small corpus snippets used to validate the plugin after (re)loading
+    #[serde(default)]
+    pub tests: Vec<PluginTestCase>,
+}
+
+/// Tier assigned to plugins that do not declare one. Third-party code is
+/// unvetted, so it only runs when the strictness tier is raised explicitly.
+pub const DEFAULT_PLUGIN_TIER: u8 = 2;
+
+/// A wikitext snippet a plugin must handle without error.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginTestCase {
+    /// Input passed to the plugin
+    pub input: String,
+    /// Expected output; if absent, the plugin only has to succeed
+    #[serde(default)]
+    pub expected: Option<String>,
+}
+
+impl PluginManifest {
+    /// Parse a manifest from a TOML string
+    pub fn from_toml(s: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(s)
+            .map_err(|e| PluginError::LoadFailed(format!("Invalid plugin manifest: {}", e)))?;
+        if let Some(tier) = manifest.min_tier.filter(|&t| t > 3) {
+            return Err(PluginError::LoadFailed(format!(
+                "Invalid plugin manifest: min_tier must be 0-3, got {}",
+                tier
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Declared classification, or `Maintenance` if unspecified
+    pub fn classification(&self) -> FixClassification {
+        self.classification
+            .unwrap_or(FixClassification::Maintenance)
+    }
+
+    /// Declared minimum tier, or [`DEFAULT_PLUGIN_TIER`] if unspecified
+    pub fn min_tier(&self) -> u8 {
+        self.min_tier.unwrap_or(DEFAULT_PLUGIN_TIER)
+    }
+
+    /// Path of the sidecar manifest for a plugin file: its file name with
+    /// `.toml` appended
+    pub fn sidecar_path(plugin_path: &Path) -> PathBuf {
+        let mut name = plugin_path
+            .file_name()
+            .map(OsString::from)
+            .unwrap_or_default();
+        name.push(".toml");
+        plugin_path.with_file_name(name)
+    }
+
+    /// Load the sidecar manifest for a plugin file, if one exists
+    pub fn load_for(plugin_path: &Path) -> Result<Option<Self>> {
+        let path = Self::sidecar_path(plugin_path);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Self::from_toml(&content).map(Some)
+    }
+
+    /// Run the manifest's test cases against a plugin, failing on the first
+    /// error or mismatch
+    pub fn run_tests(&self, plugin: &dyn Plugin) -> Result<()> {
+        for (i, case) in self.tests.iter().enumerate() {
+            let output = plugin.transform(&case.input)?;
+            let Some(expected) = &case.expected else {
+                continue;
+            };
+            if &output != expected {
+                return Err(PluginError::InvalidReturn(format!(
+                    "test case {} for '{}': expected {:?}, got {:?}",
+                    i + 1,
+                    plugin.name(),
+                    expected,
+                    output
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_from_toml() {
+        let manifest = PluginManifest::from_toml(
+            r#"
+runs_after = ["a.lua", "b.wasm"]
+exclusive_group = "dates"
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.runs_after, vec!["a.lua", "b.wasm"]);
+        assert_eq!(manifest.exclusive_group.as_deref(), Some("dates"));
+    }
+
+    #[test]
+    fn test_manifest_tier_and_classification() {
+        let manifest =
+            PluginManifest::from_toml("classification = \"cosmetic\"\nmin_tier = 0").unwrap();
+        assert_eq!(manifest.classification(), FixClassification::Cosmetic);
+        assert_eq!(manifest.min_tier(), 0);
+
+        let defaults = PluginManifest::default();
+        assert_eq!(defaults.classification(), FixClassification::Maintenance);
+        assert_eq!(defaults.min_tier(), DEFAULT_PLUGIN_TIER);
+
+        assert!(PluginManifest::from_toml("min_tier = 4").is_err());
+        assert!(PluginManifest::from_toml("classification = \"bogus\"").is_err());
+    }
+
+    #[test]
+    fn test_manifest_empty_and_unknown_fields() {
+        assert_eq!(
+            PluginManifest::from_toml("").unwrap(),
+            PluginManifest::default()
+        );
+        assert!(PluginManifest::from_toml("bogus = 1").is_err());
+    }
+
+    #[test]
+    fn test_run_tests() {
+        use crate::lua_plugin::LuaPlugin;
+        use crate::sandbox::SandboxConfig;
+
+        let plugin = LuaPlugin::from_string(
+            "upper",
+            "function transform(text) return string.upper(text) end",
+            SandboxConfig::default(),
+        )
+        .unwrap();
+        let manifest = PluginManifest::from_toml(
+            r#"
+[[tests]]
+input = "abc"
+expected = "ABC"
+
+[[tests]]
+input = "no expectation"
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.tests.len(), 2);
+        assert!(manifest.run_tests(&plugin).is_ok());
+
+        let failing = PluginManifest {
+            tests: vec![PluginTestCase {
+                input: "abc".to_string(),
+                expected: Some("abc".to_string()),
+            }],
+            ..Default::default()
+        };
+        assert!(failing.run_tests(&plugin).is_err());
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(
+            PluginManifest::sidecar_path(Path::new("/plugins/dates.lua")),
+            PathBuf::from("/plugins/dates.lua.toml")
+        );
+        assert_eq!(
+            PluginManifest::sidecar_path(Path::new("/plugins/dates.wasm")),
+            PathBuf::from("/plugins/dates.wasm.toml")
+        );
+    }
+}
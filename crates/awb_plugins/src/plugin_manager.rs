@@ -1,19 +1,34 @@
 use crate::error::{PluginError, Result};
+use crate::hot_reload::{PluginWatcher, ReloadEvent};
 use crate::lua_plugin::LuaPlugin;
-use crate::plugin_trait::Plugin;
+use crate::lua_pool::PoolStats;
+use crate::manifest::{DEFAULT_PLUGIN_TIER, PluginManifest};
+use crate::plugin_trait::{PageContext, Plugin, TransformOutcome};
+use crate::registry::{self, InstalledPlugin, PluginBundle};
 use crate::sandbox::SandboxConfig;
+use crate::signing::{TrustStore, TrustedKey, signature_path};
 use crate::wasm_plugin::WasmPlugin;
+use awb_domain::warnings::Warning;
+use awb_engine::fix_config::FixClassification;
 use awb_engine::general_fixes::{FixContext, FixModule};
+use awb_engine::masking::MaskPolicy;
+use awb_storage::PluginStateStore;
 use indexmap::IndexMap;
 use std::borrow::Cow;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
 /// Manages a collection of plugins and integrates them with the AWB fix pipeline
 pub struct PluginManager {
     plugins: IndexMap<String, Box<dyn Plugin>>,
     enabled: IndexMap<String, bool>,
-    #[allow(dead_code)]
+    manifests: IndexMap<String, PluginManifest>,
+    watcher: Option<Mutex<PluginWatcher>>,
+    state_store: Option<PluginStateStore>,
+    trust_store: Option<TrustStore>,
     config: SandboxConfig,
 }
 
@@ -28,13 +43,28 @@ impl PluginManager {
         Self {
             plugins: IndexMap::new(),
             enabled: IndexMap::new(),
+            manifests: IndexMap::new(),
+            watcher: None,
+            state_store: None,
+            trust_store: None,
             config,
         }
     }
 
+    /// Check plugin signatures against `store` when the sandbox config
+    /// sets `require_signed_plugins`
+    #[must_use]
+    pub fn with_trust_store(mut self, store: TrustStore) -> Self {
+        self.trust_store = Some(store);
+        self
+    }
+
     /// Load all plugins from a directory
     ///
-    /// Scans for *.lua and *.wasm files and loads them as plugins
+    /// Scans for *.lua and *.wasm files and loads them as plugins. Files are
+    /// visited in name order so the result does not depend on the filesystem,
+    /// and the manifest-declared execution order is validated once all
+    /// plugins are loaded.
     pub fn load_from_directory<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize> {
         let dir = dir.as_ref();
         if !dir.exists() {
@@ -53,10 +83,12 @@ impl PluginManager {
 
         let mut loaded_count = 0;
 
-        for entry in std::fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.sort();
 
+        for path in paths {
             if path.is_file() {
                 match path.extension().and_then(|s| s.to_str()) {
                     Some("lua") => match self.load_lua_plugin(&path) {
@@ -86,25 +118,377 @@ impl PluginManager {
 
         info!("Loaded {} plugins from {}", loaded_count, dir.display());
 
+        self.execution_order()?;
+
         Ok(loaded_count)
     }
 
-    /// Load a Lua plugin from a file
+    /// Load a Lua plugin from a file, along with its sidecar manifest if present
     pub fn load_lua_plugin<P: AsRef<Path>>(&mut self, path: P) -> Result<String> {
-        let plugin = LuaPlugin::from_file(path)?;
+        let path = path.as_ref();
+        self.check_signed(path)?;
+        let manifest = PluginManifest::load_for(path)?;
+        let plugin = LuaPlugin::from_file_with_config(path, self.config.clone())?;
         let name = plugin.name().to_string();
         self.add_plugin(Box::new(plugin));
+        if let Some(manifest) = manifest {
+            self.set_manifest(&name, manifest);
+        }
         Ok(name)
     }
 
-    /// Load a WASM plugin from a file
+    /// Load a WASM plugin from a file, along with its sidecar manifest if present
     pub fn load_wasm_plugin<P: AsRef<Path>>(&mut self, path: P) -> Result<String> {
-        let plugin = WasmPlugin::from_file(path)?;
-        let name = plugin.name().to_string();
+        let path = path.as_ref();
+        self.check_signed(path)?;
+        let manifest = PluginManifest::load_for(path)?;
+        let name = plugin_file_name(path);
+        let plugin = WasmPlugin::from_file_with_config(path, &name, self.config.clone())?;
         self.add_plugin(Box::new(plugin));
+        if let Some(manifest) = manifest {
+            self.set_manifest(&name, manifest);
+        }
         Ok(name)
     }
 
+    /// When signed plugins are required, check that the plugin file at
+    /// `path` and its sidecar manifest, if any, are signed by a trusted key
+    fn check_signed(&self, path: &Path) -> Result<()> {
+        if !self.config.require_signed_plugins {
+            return Ok(());
+        }
+        let store = self.trust_store.as_ref().ok_or_else(|| {
+            PluginError::Verification(
+                "signed plugins are required but no trust store is configured".to_string(),
+            )
+        })?;
+        let signer = Self::verify_signed(store, path)?;
+        debug!("{} is signed by {}", path.display(), signer.name);
+        Ok(())
+    }
+
+    /// Check that the plugin file at `path` and its sidecar manifest, if
+    /// any, are signed by a key in `store`, returning the plugin's signer
+    fn verify_signed<'a>(store: &'a TrustStore, path: &Path) -> Result<&'a TrustedKey> {
+        let signer = store.verify_file(path)?;
+        let manifest = PluginManifest::sidecar_path(path);
+        if manifest.is_file() {
+            store.verify_file(&manifest)?;
+        }
+        Ok(signer)
+    }
+
+    /// The files that make up an installed plugin: its signatures, sidecar
+    /// manifest and the plugin file itself, in the order they are moved
+    /// into place
+    fn bundle_files(path: &Path) -> [PathBuf; 4] {
+        let manifest = PluginManifest::sidecar_path(path);
+        [
+            signature_path(&manifest),
+            manifest,
+            signature_path(path),
+            path.to_path_buf(),
+        ]
+    }
+
+    /// Verify a downloaded bundle, install it into the plugin directory
+    /// `dir` and load it.
+    ///
+    /// The bundle's signatures are checked against the trust store when it
+    /// carries any or when signed plugins are required, and are installed
+    /// next to the plugin so it loads the same way later. The plugin is loaded and its manifest tests run from a staging
+    /// directory first, so a bundle that fails leaves `dir` untouched.
+    /// Installing over a plugin recorded as installed replaces it (an
+    /// update), but a hand-copied plugin file of the same name is kept.
+    pub fn install_bundle(&mut self, dir: &Path, bundle: &PluginBundle) -> Result<InstalledPlugin> {
+        bundle.verify()?;
+        let name = &bundle.descriptor.name;
+        let mut installed = registry::read_installed(dir)?;
+        let target = dir.join(name);
+        if target.exists() && !installed.contains_key(name) {
+            return Err(PluginError::LoadFailed(format!(
+                "{} exists and was not installed from a bundle",
+                target.display()
+            )));
+        }
+
+        std::fs::create_dir_all(dir)?;
+        let staging = dir.join(format!(".install-{}", name));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir(&staging)?;
+        let staged = staging.join(name);
+        let validated = (|| {
+            let [manifest_signature, manifest, signature, _] = Self::bundle_files(&staged);
+            std::fs::write(&staged, &bundle.source)?;
+            if let Some(contents) = &bundle.manifest {
+                std::fs::write(manifest, contents)?;
+            }
+            if let Some(contents) = &bundle.signature {
+                std::fs::write(signature, contents)?;
+            }
+            if let Some(contents) = &bundle.manifest_signature {
+                std::fs::write(manifest_signature, contents)?;
+            }
+            let signer = match &self.trust_store {
+                Some(store) if self.config.require_signed_plugins || bundle.signature.is_some() => {
+                    Some(Self::verify_signed(store, &staged)?.name.clone())
+                }
+                _ => {
+                    self.check_signed(&staged)?;
+                    None
+                }
+            };
+            Ok((signer, Self::load_validated(&staged, &self.config)?))
+        })();
+        let (signer, (plugin, manifest)) = match validated {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        };
+
+        for (from, to) in Self::bundle_files(&staged)
+            .into_iter()
+            .zip(Self::bundle_files(&target))
+        {
+            if from.exists() {
+                std::fs::rename(&from, &to)?;
+            } else if to.exists() {
+                std::fs::remove_file(&to)?;
+            }
+        }
+        std::fs::remove_dir_all(&staging)?;
+
+        let record = InstalledPlugin::from_bundle(bundle, signer.as_deref());
+        installed.insert(name.clone(), record.clone());
+        registry::write_installed(dir, &installed)?;
+
+        self.remove_plugin(name);
+        self.add_plugin(plugin);
+        if let Some(manifest) = manifest {
+            self.set_manifest(name, manifest);
+        }
+        info!("Installed plugin {} from {}", name, bundle.origin);
+        Ok(record)
+    }
+
+    /// Plugins installed into `dir` from bundles, in name order
+    pub fn installed_plugins(dir: &Path) -> Result<Vec<InstalledPlugin>> {
+        Ok(registry::read_installed(dir)?.into_values().collect())
+    }
+
+    /// Remove a plugin installed into `dir` from a bundle, deleting its
+    /// files and unloading it
+    pub fn uninstall(&mut self, dir: &Path, name: &str) -> Result<InstalledPlugin> {
+        let mut installed = registry::read_installed(dir)?;
+        let record = installed.remove(name).ok_or_else(|| {
+            PluginError::LoadFailed(format!("{} was not installed from a bundle", name))
+        })?;
+
+        for file in Self::bundle_files(&dir.join(name)) {
+            if file.exists() {
+                std::fs::remove_file(&file)?;
+            }
+        }
+        registry::write_installed(dir, &installed)?;
+        self.remove_plugin(name);
+        info!("Removed plugin {}", name);
+        Ok(record)
+    }
+
+    /// Watch a plugin directory for changes.
+    ///
+    /// Changes are not applied immediately; call [`reload_changed`](Self::reload_changed)
+    /// between pages to pick them up.
+    pub fn watch<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        self.watcher = Some(Mutex::new(PluginWatcher::new(dir.as_ref())?));
+        Ok(())
+    }
+
+    /// Stop watching for plugin changes
+    pub fn unwatch(&mut self) {
+        self.watcher = None;
+    }
+
+    /// Whether [`watch`](Self::watch) is active
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Reload plugins whose files changed since the last call.
+    ///
+    /// Returns an empty list if [`watch`](Self::watch) has not been called.
+    pub fn reload_changed(&mut self) -> Vec<ReloadEvent> {
+        let paths = match self.watcher.as_mut() {
+            Some(watcher) => match watcher.get_mut() {
+                Ok(w) => w.drain(),
+                Err(poisoned) => poisoned.into_inner().drain(),
+            },
+            None => return Vec::new(),
+        };
+        self.reload_paths(paths)
+    }
+
+    /// Reload the given plugin files.
+    ///
+    /// Each plugin is re-read together with its manifest and must pass the
+    /// manifest's test cases, and the plugin set must still have a valid
+    /// [`execution_order`](Self::execution_order), before it replaces the
+    /// loaded version. Failures are reported and leave the previous version
+    /// in place. Deleted files unload the plugin. Reloaded plugins keep their
+    /// enabled state and position in the load order.
+    pub fn reload_paths<I>(&mut self, paths: I) -> Vec<ReloadEvent>
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        let mut events = Vec::new();
+        for path in paths {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+
+            if !path.exists() {
+                if self.remove_plugin(&name).is_some() {
+                    info!("Unloaded deleted plugin: {}", name);
+                    events.push(ReloadEvent::Removed(name));
+                }
+                continue;
+            }
+
+            let loaded = self
+                .check_signed(&path)
+                .and_then(|()| Self::load_validated(&path, &self.config));
+            match loaded {
+                Ok((plugin, manifest)) => {
+                    let name = plugin.name().to_string();
+                    let enabled = !self.plugins.contains_key(&name) || self.is_enabled(&name);
+                    // Carry state over so counters survive an edit-reload cycle
+                    if let (Some(old), Some(new)) = (
+                        self.plugins.get(&name).and_then(|p| p.state()),
+                        plugin.state(),
+                    ) {
+                        if let Err(e) = new.restore(old.snapshot()) {
+                            warn!("Dropping state of reloaded plugin '{}': {}", name, e);
+                        }
+                    }
+                    let old_plugin = self.plugins.insert(name.clone(), plugin);
+                    let old_enabled = self.enabled.insert(name.clone(), enabled);
+                    let old_manifest = match manifest {
+                        Some(manifest) => self.manifests.insert(name.clone(), manifest),
+                        None => self.manifests.shift_remove(&name),
+                    };
+                    if let Err(e) = self.execution_order() {
+                        self.restore_plugin(&name, old_plugin, old_enabled, old_manifest);
+                        warn!("Failed to reload plugin {}: {}", path.display(), e);
+                        events.push(ReloadEvent::Failed {
+                            path,
+                            error: e.to_string(),
+                        });
+                        continue;
+                    }
+                    info!("Reloaded plugin: {}", name);
+                    events.push(ReloadEvent::Reloaded(name));
+                }
+                Err(e) => {
+                    warn!("Failed to reload plugin {}: {}", path.display(), e);
+                    events.push(ReloadEvent::Failed {
+                        path,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+        events
+    }
+
+    /// Put back what a rejected reload of `name` replaced, unloading the
+    /// plugin if it was new
+    fn restore_plugin(
+        &mut self,
+        name: &str,
+        plugin: Option<Box<dyn Plugin>>,
+        enabled: Option<bool>,
+        manifest: Option<PluginManifest>,
+    ) {
+        match plugin {
+            Some(plugin) => {
+                self.plugins.insert(name.to_string(), plugin);
+            }
+            None => {
+                self.plugins.shift_remove(name);
+            }
+        }
+        match enabled {
+            Some(enabled) => {
+                self.enabled.insert(name.to_string(), enabled);
+            }
+            None => {
+                self.enabled.shift_remove(name);
+            }
+        }
+        match manifest {
+            Some(manifest) => {
+                self.manifests.insert(name.to_string(), manifest);
+            }
+            None => {
+                self.manifests.shift_remove(name);
+            }
+        }
+    }
+
+    /// Load a plugin file and its manifest, running the manifest tests
+    fn load_validated(
+        path: &Path,
+        config: &SandboxConfig,
+    ) -> Result<(Box<dyn Plugin>, Option<PluginManifest>)> {
+        let manifest = PluginManifest::load_for(path)?;
+        let plugin = load_plugin_file(path, config)?;
+        if let Some(manifest) = &manifest {
+            manifest.run_tests(plugin.as_ref())?;
+        }
+        Ok((plugin, manifest))
+    }
+
+    /// Start a run backed by persistent plugin state.
+    ///
+    /// Plugins whose manifest sets `persist_state` get the state saved by the
+    /// previous run; all other plugins start empty and their saved state is
+    /// discarded. Call [`flush_state`](Self::flush_state) to save state.
+    pub fn attach_state_store(&mut self, store: PluginStateStore) -> Result<()> {
+        for (name, plugin) in &self.plugins {
+            let Some(state) = plugin.state() else {
+                continue;
+            };
+            let persist = self.manifests.get(name).is_some_and(|m| m.persist_state);
+            if persist {
+                state.restore(store.load(name)?)?;
+            } else {
+                state.clear();
+                store.clear(name)?;
+            }
+        }
+        self.state_store = Some(store);
+        Ok(())
+    }
+
+    /// Save every plugin's state to the attached store, if any
+    pub fn flush_state(&self) -> Result<()> {
+        let Some(store) = &self.state_store else {
+            return Ok(());
+        };
+        for (name, plugin) in &self.plugins {
+            if let Some(state) = plugin.state() {
+                store.save(name, &state.snapshot())?;
+            }
+        }
+        Ok(())
+    }
+
     /// Add a plugin to the manager
     pub fn add_plugin(&mut self, plugin: Box<dyn Plugin>) {
         let name = plugin.name().to_string();
@@ -114,20 +498,149 @@ impl PluginManager {
 
     /// Remove a plugin by name
     pub fn remove_plugin(&mut self, name: &str) -> Option<Box<dyn Plugin>> {
-        self.enabled.swap_remove(name);
-        self.plugins.swap_remove(name)
+        self.enabled.shift_remove(name);
+        self.manifests.shift_remove(name);
+        self.plugins.shift_remove(name)
     }
 
-    /// Enable a plugin by name
-    pub fn enable_plugin(&mut self, name: &str) -> bool {
+    /// Attach ordering metadata to a loaded plugin
+    pub fn set_manifest(&mut self, name: &str, manifest: PluginManifest) -> bool {
         if self.plugins.contains_key(name) {
-            self.enabled.insert(name.to_string(), true);
+            self.manifests.insert(name.to_string(), manifest);
             true
         } else {
             false
         }
     }
 
+    /// Get the manifest of a plugin, if one was provided
+    pub fn manifest(&self, name: &str) -> Option<&PluginManifest> {
+        self.manifests.get(name)
+    }
+
+    /// Classification declared by a plugin's manifest (`Maintenance` if none)
+    pub fn plugin_classification(&self, name: &str) -> FixClassification {
+        self.manifests
+            .get(name)
+            .map(PluginManifest::classification)
+            .unwrap_or(FixClassification::Maintenance)
+    }
+
+    /// Minimum strictness tier declared by a plugin's manifest
+    /// ([`DEFAULT_PLUGIN_TIER`] if none)
+    pub fn plugin_min_tier(&self, name: &str) -> u8 {
+        self.manifests
+            .get(name)
+            .map(PluginManifest::min_tier)
+            .unwrap_or(DEFAULT_PLUGIN_TIER)
+    }
+
+    /// Compute the order in which enabled plugins run.
+    ///
+    /// Plugins are topologically sorted by their `runs_after` declarations;
+    /// ties are broken by load order so the result is deterministic.
+    /// Dependencies on plugins that are not loaded or not enabled are ignored.
+    /// Returns an error if the declarations form a cycle or if two enabled
+    /// plugins share an `exclusive_group`.
+    pub fn execution_order(&self) -> Result<Vec<String>> {
+        let nodes: Vec<&str> = self
+            .plugins
+            .keys()
+            .map(String::as_str)
+            .filter(|name| self.is_enabled(name))
+            .collect();
+
+        let mut groups: IndexMap<&str, &str> = IndexMap::new();
+        for &name in &nodes {
+            let Some(group) = self
+                .manifests
+                .get(name)
+                .and_then(|m| m.exclusive_group.as_deref())
+            else {
+                continue;
+            };
+            if let Some(other) = groups.insert(group, name) {
+                return Err(PluginError::Ordering(format!(
+                    "plugins '{}' and '{}' are both enabled but share exclusive group '{}'",
+                    other, name, group
+                )));
+            }
+        }
+
+        let deps: Vec<Vec<usize>> = nodes
+            .iter()
+            .map(|name| {
+                self.manifests
+                    .get(*name)
+                    .map(|m| {
+                        m.runs_after
+                            .iter()
+                            .filter_map(|dep| nodes.iter().position(|n| n == dep))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let mut placed = vec![false; nodes.len()];
+        let mut order = Vec::with_capacity(nodes.len());
+        while order.len() < nodes.len() {
+            let next = (0..nodes.len())
+                .find(|&i| !placed[i] && deps[i].iter().all(|&d| placed[d] || d == i));
+            match next {
+                Some(i) => {
+                    placed[i] = true;
+                    order.push(nodes[i].to_string());
+                }
+                None => {
+                    let remaining: Vec<&str> = (0..nodes.len())
+                        .filter(|&i| !placed[i])
+                        .map(|i| nodes[i])
+                        .collect();
+                    return Err(PluginError::Ordering(format!(
+                        "cyclic runs_after declarations among: {}",
+                        remaining.join(", ")
+                    )));
+                }
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Enable a plugin by name.
+    ///
+    /// Returns `Ok(false)` if no such plugin is loaded, and an error if
+    /// another enabled plugin shares its `exclusive_group`.
+    pub fn enable_plugin(&mut self, name: &str) -> Result<bool> {
+        if !self.plugins.contains_key(name) {
+            return Ok(false);
+        }
+        if let Some(group) = self
+            .manifests
+            .get(name)
+            .and_then(|m| m.exclusive_group.as_deref())
+        {
+            let holder = self.plugins.keys().find(|other| {
+                other.as_str() != name
+                    && self.is_enabled(other)
+                    && self
+                        .manifests
+                        .get(other.as_str())
+                        .and_then(|m| m.exclusive_group.as_deref())
+                        == Some(group)
+            });
+            if let Some(other) = holder {
+                return Err(PluginError::Ordering(format!(
+                    "cannot enable '{}': '{}' is enabled and shares exclusive group '{}'",
+                    name, other, group
+                )));
+            }
+        }
+        self.enabled.insert(name.to_string(), true);
+        Ok(true)
+    }
+
     /// Disable a plugin by name
     pub fn disable_plugin(&mut self, name: &str) -> bool {
         if self.plugins.contains_key(name) {
@@ -155,17 +668,84 @@ impl PluginManager {
 
     /// Apply all enabled plugins to the input text in order
     pub fn apply_all(&self, input: &str) -> Result<String> {
+        self.apply_all_outcome(input).map(|outcome| outcome.text)
+    }
+
+    /// Apply all enabled plugins in [`execution_order`](Self::execution_order)
+    /// and merge their structured outcomes.
+    ///
+    /// Summary fragments from plugins that changed the text are joined with
+    /// `", "`, and warnings and metadata are accumulated; a metadata key set
+    /// by more than one plugin keeps the last value. If any plugin requests
+    /// a skip, the chain stops and the original input is returned with
+    /// `skip_page` set.
+    pub fn apply_all_outcome(&self, input: &str) -> Result<TransformOutcome> {
+        self.run_chain(input, None, None)
+    }
+
+    /// Like [`apply_all_outcome`](Self::apply_all_outcome), but passes page
+    /// information to plugins that accept it
+    pub fn apply_all_with_context(
+        &self,
+        input: &str,
+        page: &PageContext,
+    ) -> Result<TransformOutcome> {
+        self.run_chain(input, Some(page), None)
+    }
+
+    /// Like [`apply_all_with_context`](Self::apply_all_with_context), but
+    /// stops with [`PluginError::Cancelled`] once `cancel` is cancelled,
+    /// both between plugins and, for Lua plugins, during one
+    pub fn apply_all_cancellable(
+        &self,
+        input: &str,
+        page: &PageContext,
+        cancel: &CancellationToken,
+    ) -> Result<TransformOutcome> {
+        self.run_chain(input, Some(page), Some(cancel))
+    }
+
+    fn run_chain(
+        &self,
+        input: &str,
+        page: Option<&PageContext>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<TransformOutcome> {
         let mut result = input.to_string();
+        let mut summaries = Vec::new();
+        let mut warnings = Vec::new();
+        let mut metadata = BTreeMap::new();
 
-        for (name, plugin) in &self.plugins {
-            if self.is_enabled(name) {
-                match plugin.transform(&result) {
-                    Ok(transformed) => {
-                        if transformed != result {
+        for name in self.execution_order()? {
+            if let Some(plugin) = self.plugins.get(&name) {
+                let outcome = match (page, cancel) {
+                    (Some(page), Some(cancel)) => {
+                        plugin.transform_cancellable(&result, page, cancel)
+                    }
+                    (Some(page), None) => plugin.transform_with_context(&result, page),
+                    (None, _) => plugin.transform_outcome(&result),
+                };
+                match outcome {
+                    Ok(outcome) => {
+                        warnings.extend(outcome.warnings);
+                        metadata.extend(outcome.metadata);
+                        if outcome.skip_page {
+                            debug!("Plugin '{}' requested page skip", name);
+                            return Ok(TransformOutcome {
+                                text: input.to_string(),
+                                summary_fragment: None,
+                                warnings,
+                                skip_page: true,
+                                metadata,
+                            });
+                        }
+                        if outcome.text != result {
                             debug!("Plugin '{}' modified text", name);
+                            summaries.extend(outcome.summary_fragment);
                         }
-                        result = transformed;
+                        result = outcome.text;
                     }
+                    Err(PluginError::Cancelled) => return Err(PluginError::Cancelled),
                     Err(e) => {
                         warn!("Plugin '{}' failed: {}", name, e);
                         // Continue with other plugins even if one fails
@@ -174,7 +754,13 @@ impl PluginManager {
             }
         }
 
-        Ok(result)
+        Ok(TransformOutcome {
+            text: result,
+            summary_fragment: (!summaries.is_empty()).then(|| summaries.join(", ")),
+            warnings,
+            skip_page: false,
+            metadata,
+        })
     }
 
     /// Apply a specific plugin by name
@@ -200,6 +786,63 @@ impl PluginManager {
     pub fn enabled_count(&self) -> usize {
         self.enabled.values().filter(|&&v| v).count()
     }
+
+    /// Source hash of each loaded plugin, by name
+    pub fn plugin_versions(&self) -> BTreeMap<String, String> {
+        self.plugins
+            .iter()
+            .filter_map(|(name, plugin)| Some((name.clone(), plugin.version()?.to_string())))
+            .collect()
+    }
+
+    /// Sandbox configuration plugins are loaded with
+    pub fn config(&self) -> &SandboxConfig {
+        &self.config
+    }
+
+    /// Interpreter pool usage of each plugin that has a pool, for spotting
+    /// plugins that parallel runs queue on
+    pub fn pool_stats(&self) -> Vec<(String, PoolStats)> {
+        self.plugins
+            .iter()
+            .filter_map(|(name, plugin)| Some((name.clone(), plugin.pool_stats()?)))
+            .collect()
+    }
+}
+
+/// Name a plugin loaded from `path` goes by: its file name
+fn plugin_file_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// How a plugin warning reads as a fix warning: `<plugin>: <message>`
+fn warning_message(warning: &Warning) -> String {
+    match warning {
+        Warning::Plugin { plugin, message } => format!("{}: {}", plugin, message),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Load a `.lua` or `.wasm` plugin file based on its extension
+pub(crate) fn load_plugin_file(path: &Path, config: &SandboxConfig) -> Result<Box<dyn Plugin>> {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("lua") => Ok(Box::new(LuaPlugin::from_file_with_config(
+            path,
+            config.clone(),
+        )?)),
+        Some("wasm") => Ok(Box::new(WasmPlugin::from_file_with_config(
+            path,
+            &plugin_file_name(path),
+            config.clone(),
+        )?)),
+        _ => Err(PluginError::LoadFailed(format!(
+            "Not a plugin file: {}",
+            path.display()
+        ))),
+    }
 }
 
 impl Default for PluginManager {
@@ -208,15 +851,79 @@ impl Default for PluginManager {
     }
 }
 
-/// Adapter to integrate PluginManager with the AWB FixModule system
+/// Adapter to integrate PluginManager with the AWB FixModule system.
+///
+/// The adapter runs all enabled plugins as one module, so it reports the
+/// most disruptive classification and the highest minimum tier among them.
+/// A run below that tier skips every plugin rather than a subset.
+///
+/// If the manager is [watching](PluginManager::watch) its directory, changed
+/// plugins are reloaded before each page.
 pub struct PluginFixModule {
-    manager: PluginManager,
+    manager: RwLock<PluginManager>,
+    /// What the last run on each page reported besides its text, by title,
+    /// until the engine collects it
+    pages: Mutex<HashMap<String, PageReport>>,
+}
+
+/// The parts of a page's [`TransformOutcome`] the engine collects after the
+/// run
+#[derive(Debug, Default)]
+struct PageReport {
+    metadata: BTreeMap<String, String>,
+    warnings: Vec<String>,
+    summary_fragment: Option<String>,
+    skip_page: bool,
+}
+
+impl PageReport {
+    fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+            && self.warnings.is_empty()
+            && self.summary_fragment.is_none()
+            && !self.skip_page
+    }
 }
 
 impl PluginFixModule {
     /// Create a new PluginFixModule
     pub fn new(manager: PluginManager) -> Self {
-        Self { manager }
+        Self {
+            manager: RwLock::new(manager),
+            pages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reload the plugins that changed on disk, if the manager is watching
+    pub fn reload_changed(&self) -> Vec<ReloadEvent> {
+        if !self.manager().is_watching() {
+            return Vec::new();
+        }
+        self.manager
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .reload_changed()
+    }
+
+    fn record(&self, context: &FixContext, report: PageReport) {
+        let mut pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        if report.is_empty() {
+            pages.remove(&context.title.display);
+        } else {
+            pages.insert(context.title.display.clone(), report);
+        }
+    }
+
+    /// Take one part of the report recorded for the page of `context`,
+    /// dropping the report once nothing is left in it
+    fn take<T>(&self, context: &FixContext, part: impl FnOnce(&mut PageReport) -> T) -> Option<T> {
+        let mut pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        let report = pages.get_mut(&context.title.display)?;
+        let taken = part(report);
+        if report.is_empty() {
+            pages.remove(&context.title.display);
+        }
+        Some(taken)
     }
 
     /// Load plugins from a directory and create a FixModule
@@ -227,13 +934,21 @@ impl PluginFixModule {
     }
 
     /// Get a reference to the underlying plugin manager
-    pub fn manager(&self) -> &PluginManager {
-        &self.manager
+    pub fn manager(&self) -> RwLockReadGuard<'_, PluginManager> {
+        self.manager.read().unwrap_or_else(|e| e.into_inner())
     }
 
     /// Get a mutable reference to the underlying plugin manager
     pub fn manager_mut(&mut self) -> &mut PluginManager {
-        &mut self.manager
+        self.manager.get_mut().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn enabled_names(manager: &PluginManager) -> impl Iterator<Item = &str> {
+        manager
+            .plugins
+            .keys()
+            .map(String::as_str)
+            .filter(|name| manager.is_enabled(name))
     }
 }
 
@@ -254,17 +969,44 @@ impl FixModule for PluginFixModule {
         "User-defined plugins (Lua and WASM)"
     }
 
-    fn apply<'a>(&self, text: &'a str, _context: &FixContext) -> Cow<'a, str> {
-        match self.manager.apply_all(text) {
-            Ok(result) => {
+    fn apply<'a>(&self, text: &'a str, context: &FixContext) -> Cow<'a, str> {
+        self.reload_changed();
+        let page = PageContext::from_fix_context(context, text);
+        let outcome = self
+            .manager()
+            .apply_all_cancellable(text, &page, &context.cancel);
+        match outcome {
+            Ok(TransformOutcome {
+                text: result,
+                summary_fragment,
+                warnings,
+                skip_page,
+                metadata,
+            }) => {
+                let warnings = warnings.iter().map(warning_message).collect();
+                self.record(
+                    context,
+                    PageReport {
+                        metadata,
+                        warnings,
+                        summary_fragment,
+                        skip_page,
+                    },
+                );
                 if result == text {
                     Cow::Borrowed(text)
                 } else {
                     Cow::Owned(result)
                 }
             }
+            Err(PluginError::Cancelled) => {
+                debug!("Plugin execution cancelled");
+                self.record(context, PageReport::default());
+                Cow::Borrowed(text)
+            }
             Err(e) => {
                 warn!("Plugin execution failed: {}", e);
+                self.record(context, PageReport::default());
                 Cow::Borrowed(text)
             }
         }
@@ -273,6 +1015,65 @@ impl FixModule for PluginFixModule {
     fn default_enabled(&self) -> bool {
         true
     }
+
+    /// The source hash of each enabled plugin, as `plugin:<name>`, and the
+    /// seed in deterministic mode
+    fn provenance(&self) -> Vec<(String, String)> {
+        let manager = self.manager();
+        let versions = manager.plugin_versions();
+        let mut provenance: Vec<(String, String)> = Self::enabled_names(&manager)
+            .filter_map(|name| Some((format!("plugin:{}", name), versions.get(name)?.clone())))
+            .collect();
+        let config = manager.config();
+        if config.deterministic {
+            provenance.push(("plugin-seed".to_string(), config.seed.to_string()));
+        }
+        provenance
+    }
+
+    fn page_metadata(&self, context: &FixContext) -> Vec<(String, String)> {
+        self.take(context, |report| std::mem::take(&mut report.metadata))
+            .map(|metadata| metadata.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// The plugins' warnings from their last run on the page, as
+    /// `<plugin>: <message>`
+    fn warnings(&self, _text: &str, context: &FixContext) -> Vec<String> {
+        self.take(context, |report| std::mem::take(&mut report.warnings))
+            .unwrap_or_default()
+    }
+
+    fn summary_fragment(&self, context: &FixContext) -> Option<String> {
+        self.take(context, |report| report.summary_fragment.take())
+            .flatten()
+    }
+
+    fn requests_skip(&self, context: &FixContext) -> bool {
+        self.take(context, |report| std::mem::take(&mut report.skip_page))
+            .unwrap_or(false)
+    }
+
+    /// Plugins receive the page unmasked, as they do outside the fix pipeline
+    fn required_visibility(&self) -> MaskPolicy {
+        MaskPolicy::ALL
+    }
+
+    fn classification(&self) -> FixClassification {
+        let manager = self.manager();
+        Self::enabled_names(&manager)
+            .map(|name| manager.plugin_classification(name))
+            .max()
+            .unwrap_or(FixClassification::Maintenance)
+    }
+
+    fn min_tier(&self) -> u8 {
+        let manager = self.manager();
+        Self::enabled_names(&manager)
+            .map(|name| manager.plugin_min_tier(name))
+            .max()
+            .unwrap_or(DEFAULT_PLUGIN_TIER)
+    }
 }
 
 #[cfg(test)]
@@ -329,7 +1130,7 @@ mod tests {
         let result = manager.apply_all("hello").unwrap();
         assert_eq!(result, "hello"); // No transformation
 
-        manager.enable_plugin("upper");
+        manager.enable_plugin("upper").unwrap();
         let result = manager.apply_all("hello").unwrap();
         assert_eq!(result, "HELLO");
     }
@@ -357,12 +1158,544 @@ mod tests {
             title: Title::new(Namespace::MAIN, "Test"),
             namespace: Namespace::MAIN,
             is_redirect: false,
+            revision: None,
+            cancel: Default::default(),
         };
 
         let result = fix_module.apply("hello world", &context);
         assert_eq!(result, "HELLO WORLD");
     }
 
+    #[test]
+    fn test_plugin_fix_module_tier_and_classification() {
+        let mut manager = PluginManager::new();
+        manager.add_plugin(lua(
+            "upper",
+            "function transform(text) return string.upper(text) end",
+        ));
+        manager.add_plugin(lua("noop", "function transform(text) return text end"));
+        manager.set_manifest(
+            "noop",
+            PluginManifest {
+                classification: Some(FixClassification::Cosmetic),
+                min_tier: Some(0),
+                ..Default::default()
+            },
+        );
+
+        let mut fix_module = PluginFixModule::new(manager);
+        // "upper" has no manifest: Maintenance at the default third-party tier
+        assert_eq!(fix_module.min_tier(), DEFAULT_PLUGIN_TIER);
+        assert_eq!(fix_module.classification(), FixClassification::Maintenance);
+
+        fix_module.manager_mut().disable_plugin("upper");
+        assert_eq!(fix_module.min_tier(), 0);
+        assert_eq!(fix_module.classification(), FixClassification::Cosmetic);
+    }
+
+    #[test]
+    fn test_plugin_fix_module_passes_page_context() {
+        use awb_domain::types::{Namespace, Title};
+
+        let mut manager = PluginManager::new();
+        manager.add_plugin(lua(
+            "ctx",
+            r#"function transform(text, page) return text .. " " .. page.title .. " " .. #page.categories end"#,
+        ));
+        let fix_module = PluginFixModule::new(manager);
+
+        let context = FixContext {
+            title: Title::new(Namespace::MAIN, "Example"),
+            namespace: Namespace::MAIN,
+            is_redirect: false,
+            revision: None,
+            cancel: Default::default(),
+        };
+        let result = fix_module.apply("body [[Category:A]]", &context);
+        assert_eq!(result, "body [[Category:A]] Example 1");
+    }
+
+    #[test]
+    fn test_apply_all_outcome_merges_plugins() {
+        let mut manager = PluginManager::new();
+
+        let script1 = r#"
+            function transform(text)
+                return { text = string.upper(text), summary = "uppercased" }
+            end
+        "#;
+        let script2 = r#"
+            function transform(text)
+                mw.page.set_property("review", "needs human review")
+                return { text = text, summary = "unused", warnings = { "looks odd" } }
+            end
+        "#;
+        manager.add_plugin(Box::new(
+            LuaPlugin::from_string("upper", script1, SandboxConfig::default()).unwrap(),
+        ));
+        manager.add_plugin(Box::new(
+            LuaPlugin::from_string("checker", script2, SandboxConfig::default()).unwrap(),
+        ));
+
+        let outcome = manager.apply_all_outcome("hello").unwrap();
+        assert_eq!(outcome.text, "HELLO");
+        // Only plugins that changed text contribute to the summary
+        assert_eq!(outcome.summary_fragment.as_deref(), Some("uppercased"));
+        assert_eq!(outcome.warnings.len(), 1);
+        assert!(!outcome.skip_page);
+        // Metadata is kept even from plugins that changed nothing
+        assert_eq!(
+            outcome.metadata.get("review").map(String::as_str),
+            Some("needs human review")
+        );
+    }
+
+    #[test]
+    fn test_provenance_names_plugin_sources_and_seed() {
+        let config = SandboxConfig::default().with_run_id("audit-7");
+        let seed = config.seed;
+        let mut manager = PluginManager::with_config(config);
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = "function transform(text) return text end";
+        std::fs::write(dir.path().join("noop.lua"), script).unwrap();
+        manager.load_from_directory(dir.path()).unwrap();
+        manager.add_plugin(lua("off", script));
+        manager.disable_plugin("off");
+
+        let provenance: BTreeMap<_, _> = PluginFixModule::new(manager)
+            .provenance()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            provenance,
+            BTreeMap::from([
+                ("plugin-seed".to_string(), seed.to_string()),
+                (
+                    "plugin:noop.lua".to_string(),
+                    awb_storage::rule_library::content_hash(script)
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_plugin_metadata_reaches_edit_plan() {
+        let mut manager = PluginManager::new();
+        let script = r#"
+            function transform(text, page)
+                if page.namespace == 0 and not string.find(text, "<ref") then
+                    mw.page.set_property("review", "unsourced")
+                end
+                return text
+            end
+        "#;
+        manager.add_plugin(lua("classify", script));
+        let mut registry = awb_engine::general_fixes::FixRegistry::new();
+        registry
+            .register(Box::new(PluginFixModule::new(manager)))
+            .unwrap();
+        let enabled = std::collections::HashSet::from(["plugins".to_string()]);
+        let engine = awb_engine::transform::TransformEngine::new(
+            &awb_domain::rules::RuleSet::new(),
+            registry,
+            enabled,
+        )
+        .unwrap();
+
+        let page = |text: &str| awb_domain::types::PageContent {
+            page_id: awb_domain::types::PageId(1),
+            title: awb_domain::types::Title::new(awb_domain::types::Namespace::MAIN, "Foo"),
+            revision: awb_domain::types::RevisionId(1),
+            timestamp: Default::default(),
+            wikitext: text.to_string(),
+            size_bytes: text.len() as u64,
+            is_redirect: false,
+            protection: Default::default(),
+            properties: Default::default(),
+        };
+        let plan = engine.apply(&page("No sources here."));
+        assert_eq!(plan.new_wikitext, "No sources here.");
+        assert_eq!(
+            plan.metadata.get("review").map(String::as_str),
+            Some("unsourced")
+        );
+        // Nothing is carried over to the next page
+        let plan = engine.apply(&page("Sourced.<ref>Book.</ref>"));
+        assert!(plan.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_engine_passes_revision_to_plugins() {
+        let mut manager = PluginManager::new();
+        manager.add_plugin(lua(
+            "rev",
+            r#"function transform(text, page) return text .. " r" .. tostring(page.revid) end"#,
+        ));
+        let mut registry = awb_engine::general_fixes::FixRegistry::new();
+        registry
+            .register(Box::new(PluginFixModule::new(manager)))
+            .unwrap();
+        let enabled = std::collections::HashSet::from(["plugins".to_string()]);
+        let engine = awb_engine::transform::TransformEngine::new(
+            &awb_domain::rules::RuleSet::new(),
+            registry,
+            enabled,
+        )
+        .unwrap();
+
+        let page = awb_domain::types::PageContent {
+            page_id: awb_domain::types::PageId(1),
+            title: awb_domain::types::Title::new(awb_domain::types::Namespace::MAIN, "Foo"),
+            revision: awb_domain::types::RevisionId(1234),
+            timestamp: Default::default(),
+            wikitext: "Text".to_string(),
+            size_bytes: 4,
+            is_redirect: false,
+            protection: Default::default(),
+            properties: Default::default(),
+        };
+        assert_eq!(engine.apply(&page).new_wikitext, "Text r1234");
+    }
+
+    #[test]
+    fn test_plugin_outcome_reaches_edit_plan() {
+        use awb_domain::warnings::Warning;
+
+        let mut manager = PluginManager::new();
+        let script = r#"
+            function transform(text)
+                if string.find(text, "draft") then
+                    return { text = text, skip = true }
+                end
+                return {
+                    text = string.gsub(text, "colour", "color"),
+                    summary = "US spelling",
+                    warnings = { "check the infobox" },
+                }
+            end
+        "#;
+        manager.add_plugin(lua("spelling", script));
+        let mut registry = awb_engine::general_fixes::FixRegistry::new();
+        registry
+            .register(Box::new(PluginFixModule::new(manager)))
+            .unwrap();
+        let enabled = std::collections::HashSet::from(["plugins".to_string()]);
+        let engine = awb_engine::transform::TransformEngine::new(
+            &awb_domain::rules::RuleSet::new(),
+            registry,
+            enabled,
+        )
+        .unwrap();
+
+        let page = |text: &str| awb_domain::types::PageContent {
+            page_id: awb_domain::types::PageId(1),
+            title: awb_domain::types::Title::new(awb_domain::types::Namespace::MAIN, "Foo"),
+            revision: awb_domain::types::RevisionId(1),
+            timestamp: Default::default(),
+            wikitext: text.to_string(),
+            size_bytes: text.len() as u64,
+            is_redirect: false,
+            protection: Default::default(),
+            properties: Default::default(),
+        };
+        let plan = engine.apply(&page("The colour red."));
+        assert_eq!(plan.new_wikitext, "The color red.");
+        assert_eq!(plan.summary, "AWB-RS ([[WP:AWB]]): US spelling");
+        assert!(plan.warnings.iter().any(|w| matches!(
+            w,
+            Warning::Fix { fix, message } if fix == "plugins" && message == "spelling: check the infobox"
+        )));
+        assert!(
+            !plan
+                .warnings
+                .iter()
+                .any(|w| matches!(w, Warning::SkipRequested { .. }))
+        );
+
+        let plan = engine.apply(&page("A draft."));
+        assert!(plan.warnings.iter().any(|w| matches!(
+            w,
+            Warning::SkipRequested { fixes } if fixes == &["plugins"]
+        )));
+        assert!(
+            !plan
+                .warnings
+                .iter()
+                .any(|w| matches!(w, Warning::Fix { .. }))
+        );
+    }
+
+    #[test]
+    fn test_apply_all_outcome_skip_stops_chain() {
+        let mut manager = PluginManager::new();
+
+        let skip = r#"
+            function transform(text)
+                return { text = text, skip = true }
+            end
+        "#;
+        let upper = r#"
+            function transform(text)
+                return string.upper(text)
+            end
+        "#;
+        manager.add_plugin(Box::new(
+            LuaPlugin::from_string("skip", skip, SandboxConfig::default()).unwrap(),
+        ));
+        manager.add_plugin(Box::new(
+            LuaPlugin::from_string("upper", upper, SandboxConfig::default()).unwrap(),
+        ));
+
+        let outcome = manager.apply_all_outcome("hello").unwrap();
+        assert!(outcome.skip_page);
+        assert_eq!(outcome.text, "hello");
+    }
+
+    fn lua(name: &str, script: &str) -> Box<dyn Plugin> {
+        Box::new(LuaPlugin::from_string(name, script, SandboxConfig::default()).unwrap())
+    }
+
+    #[test]
+    fn test_execution_order_respects_runs_after() {
+        let mut manager = PluginManager::new();
+        manager.add_plugin(lua(
+            "exclaim",
+            r#"function transform(text) return text .. "!" end"#,
+        ));
+        manager.add_plugin(lua(
+            "upper",
+            r#"function transform(text) return string.upper(text) end"#,
+        ));
+        manager.set_manifest(
+            "exclaim",
+            PluginManifest {
+                runs_after: vec!["upper".to_string(), "missing".to_string()],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(manager.execution_order().unwrap(), vec!["upper", "exclaim"]);
+        assert_eq!(manager.apply_all("hi").unwrap(), "HI!");
+    }
+
+    #[test]
+    fn test_execution_order_cycle_is_error() {
+        let mut manager = PluginManager::new();
+        manager.add_plugin(lua("a", "function transform(text) return text end"));
+        manager.add_plugin(lua("b", "function transform(text) return text end"));
+        for (name, dep) in [("a", "b"), ("b", "a")] {
+            manager.set_manifest(
+                name,
+                PluginManifest {
+                    runs_after: vec![dep.to_string()],
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert!(matches!(
+            manager.execution_order(),
+            Err(PluginError::Ordering(_))
+        ));
+        assert!(manager.apply_all("text").is_err());
+
+        // Disabling one side of the cycle resolves it
+        manager.disable_plugin("b");
+        assert_eq!(manager.execution_order().unwrap(), vec!["a"]);
+    }
+
+    #[test]
+    fn test_exclusive_group_conflict() {
+        let mut manager = PluginManager::new();
+        manager.add_plugin(lua("iso", "function transform(text) return text end"));
+        manager.add_plugin(lua("dmy", "function transform(text) return text end"));
+        for name in ["iso", "dmy"] {
+            manager.set_manifest(
+                name,
+                PluginManifest {
+                    exclusive_group: Some("dates".to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert!(matches!(
+            manager.execution_order(),
+            Err(PluginError::Ordering(_))
+        ));
+
+        manager.disable_plugin("dmy");
+        assert_eq!(manager.execution_order().unwrap(), vec!["iso"]);
+
+        // Re-enabling it is refused while the other plugin holds the group
+        assert!(matches!(
+            manager.enable_plugin("dmy"),
+            Err(PluginError::Ordering(_))
+        ));
+        assert!(!manager.is_enabled("dmy"));
+        assert_eq!(manager.execution_order().unwrap(), vec!["iso"]);
+
+        manager.disable_plugin("iso");
+        assert!(manager.enable_plugin("dmy").unwrap());
+        assert!(manager.enable_plugin("dmy").unwrap());
+        assert!(!manager.enable_plugin("missing").unwrap());
+    }
+
+    #[test]
+    fn test_reload_paths_keeps_old_version_on_failure() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("upper.lua");
+        std::fs::write(
+            &path,
+            "function transform(text) return string.upper(text) end",
+        )
+        .unwrap();
+
+        let mut manager = PluginManager::new();
+        manager.load_lua_plugin(&path).unwrap();
+        manager.disable_plugin("upper.lua");
+
+        // A valid edit is picked up and keeps the disabled state
+        std::fs::write(&path, "function transform(text) return text .. '!' end").unwrap();
+        let events = manager.reload_paths(vec![path.clone()]);
+        assert_eq!(events, vec![ReloadEvent::Reloaded("upper.lua".to_string())]);
+        assert!(!manager.is_enabled("upper.lua"));
+        manager.enable_plugin("upper.lua").unwrap();
+        assert_eq!(manager.apply_all("hi").unwrap(), "hi!");
+
+        // A version failing its manifest test is rejected
+        std::fs::write(
+            dir.path().join("upper.lua.toml"),
+            "[[tests]]\ninput = \"a\"\nexpected = \"a?\"\n",
+        )
+        .unwrap();
+        let events = manager.reload_paths(vec![path.clone()]);
+        assert!(matches!(events[0], ReloadEvent::Failed { .. }));
+        assert_eq!(manager.apply_all("hi").unwrap(), "hi!");
+
+        // Syntax errors are reported rather than panicking
+        std::fs::remove_file(dir.path().join("upper.lua.toml")).unwrap();
+        std::fs::write(&path, "function transform(").unwrap();
+        let events = manager.reload_paths(vec![path.clone()]);
+        assert!(matches!(events[0], ReloadEvent::Failed { .. }));
+
+        std::fs::remove_file(&path).unwrap();
+        let events = manager.reload_paths(vec![path]);
+        assert_eq!(events, vec![ReloadEvent::Removed("upper.lua".to_string())]);
+        assert_eq!(manager.plugin_count(), 0);
+    }
+
+    #[test]
+    fn test_reload_paths_keeps_old_set_on_ordering_conflict() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let write = |name: &str, contents: &str| {
+            std::fs::write(dir.path().join(name), contents).unwrap();
+        };
+        write("a.lua", "function transform(text) return text .. 'a' end");
+        write("a.lua.toml", "exclusive_group = \"dates\"\n");
+        write("b.lua", "function transform(text) return text .. 'b' end");
+
+        let mut manager = PluginManager::new();
+        manager.load_from_directory(dir.path()).unwrap();
+        assert_eq!(manager.apply_all("").unwrap(), "ab");
+
+        // The new version would put both plugins in one exclusive group
+        write("b.lua", "function transform(text) return text .. 'B' end");
+        write("b.lua.toml", "exclusive_group = \"dates\"\n");
+        let events = manager.reload_paths(vec![dir.path().join("b.lua")]);
+        assert!(matches!(events[0], ReloadEvent::Failed { .. }));
+        assert!(manager.manifest("b.lua").is_none());
+        assert_eq!(manager.apply_all("").unwrap(), "ab");
+
+        // A new plugin that conflicts is not loaded at all
+        write("c.lua", "function transform(text) return text .. 'c' end");
+        write("c.lua.toml", "exclusive_group = \"dates\"\n");
+        let events = manager.reload_paths(vec![dir.path().join("c.lua")]);
+        assert!(matches!(events[0], ReloadEvent::Failed { .. }));
+        assert_eq!(manager.plugin_names(), vec!["a.lua", "b.lua"]);
+    }
+
+    #[test]
+    fn test_fix_module_reloads_watched_plugins_between_pages() {
+        use awb_domain::types::{Namespace, Title};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("mark.lua");
+        std::fs::write(&path, "function transform(text) return text .. '1' end").unwrap();
+
+        let mut manager = PluginManager::new();
+        manager.load_from_directory(dir.path()).unwrap();
+        manager.watch(dir.path()).unwrap();
+        let module = PluginFixModule::new(manager);
+        let ctx = FixContext {
+            title: Title::new(Namespace::MAIN, "Test"),
+            namespace: Namespace::MAIN,
+            is_redirect: false,
+            revision: None,
+            cancel: Default::default(),
+        };
+        assert_eq!(module.apply("x", &ctx), "x1");
+
+        std::fs::write(&path, "function transform(text) return text .. '2' end").unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while module.apply("x", &ctx) != "x2" {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "edit was not picked up"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_state_store_persistence_opt_in() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let counter = r#"
+            function transform(text)
+                local n = tonumber(mw.store.get("n") or "0") + 1
+                mw.store.set("n", n)
+                return text
+            end
+        "#;
+
+        let run = |expected_persisted: &str, expected_scratch: &str| {
+            let mut manager = PluginManager::new();
+            manager.add_plugin(lua("persisted", counter));
+            manager.add_plugin(lua("scratch", counter));
+            manager.set_manifest(
+                "persisted",
+                PluginManifest {
+                    persist_state: true,
+                    ..Default::default()
+                },
+            );
+            manager
+                .attach_state_store(PluginStateStore::new(dir.path()))
+                .unwrap();
+            manager.apply_all("page").unwrap();
+            manager.flush_state().unwrap();
+
+            let state = |name: &str| {
+                manager
+                    .get_plugin(name)
+                    .and_then(|p| p.state())
+                    .and_then(|s| s.get("n"))
+                    .unwrap()
+            };
+            assert_eq!(state("persisted"), expected_persisted);
+            assert_eq!(state("scratch"), expected_scratch);
+        };
+
+        run("1", "1");
+        run("2", "1");
+    }
+
+    #[test]
+    fn test_reload_changed_without_watch_is_noop() {
+        let mut manager = PluginManager::new();
+        assert!(manager.reload_changed().is_empty());
+    }
+
     #[test]
     fn test_plugin_error_handling() {
         let mut manager = PluginManager::new();
@@ -380,4 +1713,217 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "test"); // Text unchanged due to error
     }
+
+    fn bundle(script: &str, manifest: Option<&str>) -> PluginBundle {
+        use awb_storage::rule_library::content_hash;
+        PluginBundle {
+            descriptor: crate::BundleDescriptor {
+                name: "upper.lua".to_string(),
+                version: Some("1".to_string()),
+                source: "upper.lua".to_string(),
+                sha256: content_hash(script),
+                manifest: manifest.map(|_| "upper.lua.toml".to_string()),
+                manifest_sha256: manifest.map(content_hash),
+                signature: None,
+                manifest_signature: None,
+            },
+            origin: "https://example.org/upper.json".to_string(),
+            source: script.as_bytes().to_vec(),
+            manifest: manifest.map(str::to_string),
+            signature: None,
+            manifest_signature: None,
+        }
+    }
+
+    fn sign_bundle(bundle: &mut PluginBundle, signer: &crate::signing::TestSigner) {
+        bundle.descriptor.signature = Some("upper.lua.minisig".to_string());
+        bundle.signature = Some(signer.sign(&bundle.source, true));
+        if let Some(manifest) = &bundle.manifest {
+            bundle.descriptor.manifest_signature = Some("upper.lua.toml.minisig".to_string());
+            bundle.manifest_signature = Some(signer.sign(manifest.as_bytes(), true));
+        }
+    }
+
+    #[test]
+    fn test_install_update_and_uninstall_bundle() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = PluginManager::new();
+        let upper = "function transform(text) return string.upper(text) end";
+        let manifest = "min_tier = 1\n[[tests]]\ninput = \"a\"\nexpected = \"A\"\n";
+
+        let record = manager
+            .install_bundle(dir.path(), &bundle(upper, Some(manifest)))
+            .unwrap();
+        assert_eq!(record.version.as_deref(), Some("1"));
+        assert_eq!(manager.apply_plugin("upper.lua", "abc").unwrap(), "ABC");
+        assert_eq!(manager.plugin_min_tier("upper.lua"), 1);
+        assert!(dir.path().join("upper.lua.toml").is_file());
+        assert_eq!(
+            PluginManager::installed_plugins(dir.path()).unwrap(),
+            vec![record]
+        );
+
+        // An update without a manifest drops the old sidecar
+        let lower = "function transform(text) return string.lower(text) end";
+        manager
+            .install_bundle(dir.path(), &bundle(lower, None))
+            .unwrap();
+        assert_eq!(manager.apply_plugin("upper.lua", "ABC").unwrap(), "abc");
+        assert!(!dir.path().join("upper.lua.toml").exists());
+
+        let mut fresh = PluginManager::new();
+        assert_eq!(fresh.load_from_directory(dir.path()).unwrap(), 1);
+
+        manager.uninstall(dir.path(), "upper.lua").unwrap();
+        assert_eq!(manager.plugin_count(), 0);
+        assert!(!dir.path().join("upper.lua").exists());
+        assert!(
+            PluginManager::installed_plugins(dir.path())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(manager.uninstall(dir.path(), "upper.lua").is_err());
+    }
+
+    #[test]
+    fn test_install_bundle_rejects_bad_bundles() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = PluginManager::new();
+        let upper = "function transform(text) return string.upper(text) end";
+
+        let mut tampered = bundle(upper, None);
+        tampered.source = b"function transform(text) return '' end".to_vec();
+        assert!(matches!(
+            manager.install_bundle(dir.path(), &tampered),
+            Err(PluginError::Verification(_))
+        ));
+
+        // Failing manifest tests leave the directory untouched
+        let failing = "[[tests]]\ninput = \"a\"\nexpected = \"b\"\n";
+        assert!(
+            manager
+                .install_bundle(dir.path(), &bundle(upper, Some(failing)))
+                .is_err()
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        // A hand-copied plugin of the same name is not overwritten
+        std::fs::write(dir.path().join("upper.lua"), "-- mine").unwrap();
+        assert!(
+            manager
+                .install_bundle(dir.path(), &bundle(upper, None))
+                .is_err()
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("upper.lua")).unwrap(),
+            "-- mine"
+        );
+        assert_eq!(manager.plugin_count(), 0);
+    }
+
+    #[test]
+    fn test_install_signed_bundle() {
+        use crate::signing::{PublicKey, TestSigner};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let signer = TestSigner::new(1);
+        let mut store = TrustStore::load(dir.path().join("keys.toml")).unwrap();
+        store
+            .add(
+                "Publisher",
+                &PublicKey::parse(&signer.public_key()).unwrap(),
+            )
+            .unwrap();
+        let config = SandboxConfig {
+            require_signed_plugins: true,
+            ..SandboxConfig::default()
+        };
+        let plugins = dir.path().join("plugins");
+        let upper = "function transform(text) return string.upper(text) end";
+        let manifest = "[[tests]]\ninput = \"a\"\nexpected = \"A\"\n";
+        let mut manager =
+            PluginManager::with_config(config.clone()).with_trust_store(store.clone());
+
+        // Unsigned bundles and other publishers' bundles are refused
+        assert!(matches!(
+            manager.install_bundle(&plugins, &bundle(upper, Some(manifest))),
+            Err(PluginError::Verification(_))
+        ));
+        let mut untrusted = bundle(upper, Some(manifest));
+        sign_bundle(&mut untrusted, &TestSigner::new(2));
+        assert!(manager.install_bundle(&plugins, &untrusted).is_err());
+        assert!(!plugins.join("upper.lua").exists());
+
+        let mut signed = bundle(upper, Some(manifest));
+        sign_bundle(&mut signed, &signer);
+        let record = manager.install_bundle(&plugins, &signed).unwrap();
+        assert_eq!(record.signed_by.as_deref(), Some("Publisher"));
+        assert!(plugins.join("upper.lua.minisig").is_file());
+        assert!(plugins.join("upper.lua.toml.minisig").is_file());
+
+        // The installed plugin loads under the same policy
+        let mut fresh = PluginManager::with_config(config).with_trust_store(store);
+        assert_eq!(fresh.load_from_directory(&plugins).unwrap(), 1);
+        assert_eq!(fresh.apply_plugin("upper.lua", "abc").unwrap(), "ABC");
+
+        manager.uninstall(&plugins, "upper.lua").unwrap();
+        assert!(!plugins.join("upper.lua.minisig").exists());
+        assert!(!plugins.join("upper.lua.toml.minisig").exists());
+    }
+
+    #[test]
+    fn test_require_signed_plugins() {
+        use crate::signing::{PublicKey, TestSigner, signature_path};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let signer = TestSigner::new(1);
+        let mut store = TrustStore::load(dir.path().join("keys.toml")).unwrap();
+        store
+            .add(
+                "Publisher",
+                &PublicKey::parse(&signer.public_key()).unwrap(),
+            )
+            .unwrap();
+        let config = SandboxConfig {
+            require_signed_plugins: true,
+            ..SandboxConfig::default()
+        };
+
+        let plugins = dir.path().join("plugins");
+        std::fs::create_dir(&plugins).unwrap();
+        let signed = plugins.join("signed.lua");
+        let unsigned = plugins.join("unsigned.lua");
+        for path in [&signed, &unsigned] {
+            std::fs::write(path, "function transform(t) return t end").unwrap();
+        }
+        signer.sign_file(&signed);
+
+        let mut manager =
+            PluginManager::with_config(config.clone()).with_trust_store(store.clone());
+        assert_eq!(manager.load_from_directory(&plugins).unwrap(), 1);
+        assert_eq!(manager.plugin_names(), vec!["signed.lua".to_string()]);
+        assert!(matches!(
+            manager.load_lua_plugin(&unsigned),
+            Err(PluginError::Verification(_))
+        ));
+
+        // An unsigned manifest is refused too, as it can lower the tier
+        std::fs::write(plugins.join("signed.lua.toml"), "min_tier = 0").unwrap();
+        assert!(manager.load_lua_plugin(&signed).is_err());
+        signer.sign_file(&plugins.join("signed.lua.toml"));
+        assert!(manager.load_lua_plugin(&signed).is_ok());
+
+        // Another publisher's key is not trusted
+        TestSigner::new(2).sign_file(&unsigned);
+        assert!(signature_path(&unsigned).is_file());
+        assert!(manager.load_lua_plugin(&unsigned).is_err());
+
+        // Without a trust store nothing loads
+        let mut manager = PluginManager::with_config(config);
+        assert!(manager.load_lua_plugin(&signed).is_err());
+
+        // The policy is off by default
+        let mut manager = PluginManager::new();
+        assert!(manager.load_lua_plugin(&unsigned).is_ok());
+    }
 }
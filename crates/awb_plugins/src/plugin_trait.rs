@@ -1,4 +1,12 @@
 use crate::error::Result;
+use crate::lua_pool::PoolStats;
+use crate::state::PluginState;
+use awb_domain::types::PageContent;
+use awb_domain::warnings::Warning;
+use awb_engine::category::CategoryManager;
+use awb_engine::general_fixes::FixContext;
+use std::collections::BTreeMap;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PluginType {
@@ -7,6 +15,80 @@ pub enum PluginType {
     Native,
 }
 
+/// Structured result of a single plugin invocation.
+///
+/// Plugins that only rewrite text can keep implementing [`Plugin::transform`];
+/// the default [`Plugin::transform_outcome`] wraps the returned string.
+#[derive(Debug, Clone, Default)]
+pub struct TransformOutcome {
+    /// The transformed wikitext
+    pub text: String,
+    /// Fragment appended to the edit summary when the plugin changed the page
+    pub summary_fragment: Option<String>,
+    /// Warnings raised by the plugin, surfaced alongside engine warnings
+    pub warnings: Vec<Warning>,
+    /// If true, the plugin asks for the whole page to be skipped
+    pub skip_page: bool,
+    /// Metadata about the page, e.g. a classification, reported on the edit
+    /// plan whether or not the text changed
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl TransformOutcome {
+    /// Create an outcome carrying only transformed text
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<String> for TransformOutcome {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+/// Read-only information about the page being transformed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageContext {
+    /// Page title without namespace prefix
+    pub title: String,
+    /// Namespace number
+    pub namespace: i32,
+    /// Whether the page is a redirect
+    pub is_redirect: bool,
+    /// Revision the text was loaded from, if known
+    pub revid: Option<u64>,
+    /// Categories present in the wikitext
+    pub categories: Vec<String>,
+}
+
+impl PageContext {
+    /// Build a context from the fix pipeline context and the current text
+    pub fn from_fix_context(ctx: &FixContext, text: &str) -> Self {
+        Self {
+            title: ctx.title.name.clone(),
+            namespace: ctx.namespace.0,
+            is_redirect: ctx.is_redirect,
+            revid: ctx.revision.map(|revision| revision.0),
+            categories: CategoryManager::new().list_categories(text),
+        }
+    }
+
+    /// Build a context from a fetched page
+    pub fn from_page(page: &PageContent) -> Self {
+        Self {
+            title: page.title.name.clone(),
+            namespace: page.title.namespace.0,
+            is_redirect: page.is_redirect,
+            revid: Some(page.revision.0),
+            categories: CategoryManager::new().list_categories(&page.wikitext),
+        }
+    }
+}
+
 /// Core trait that all plugins must implement
 pub trait Plugin: Send + Sync {
     /// Unique identifier for the plugin
@@ -18,6 +100,53 @@ pub trait Plugin: Send + Sync {
     /// Transform input text and return the modified version
     fn transform(&self, input: &str) -> Result<String>;
 
+    /// Transform input text and return a structured outcome.
+    ///
+    /// Defaults to wrapping [`Plugin::transform`] with no summary, warnings, or skip.
+    fn transform_outcome(&self, input: &str) -> Result<TransformOutcome> {
+        self.transform(input).map(TransformOutcome::from)
+    }
+
+    /// Transform input text with access to information about the page.
+    ///
+    /// Defaults to [`Plugin::transform_outcome`], ignoring the context.
+    fn transform_with_context(&self, input: &str, _page: &PageContext) -> Result<TransformOutcome> {
+        self.transform_outcome(input)
+    }
+
+    /// Like [`Plugin::transform_with_context`], stopping with
+    /// [`PluginError::Cancelled`](crate::PluginError::Cancelled) once
+    /// `cancel` is cancelled.
+    ///
+    /// Defaults to checking `cancel` before running, which suits plugins
+    /// whose runs are short or bounded, like WASM plugins by their fuel.
+    fn transform_cancellable(
+        &self,
+        input: &str,
+        page: &PageContext,
+        cancel: &CancellationToken,
+    ) -> Result<TransformOutcome> {
+        if cancel.is_cancelled() {
+            return Err(crate::PluginError::Cancelled);
+        }
+        self.transform_with_context(input, page)
+    }
+
+    /// Cross-invocation state store, if the plugin has one
+    fn state(&self) -> Option<&PluginState> {
+        None
+    }
+
+    /// Usage of the plugin's interpreter pool, if it has one
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+
+    /// Hex SHA-256 of the plugin's source, identifying exactly what ran
+    fn version(&self) -> Option<&str> {
+        None
+    }
+
     /// The type of plugin (Lua, WASM, or Native)
     fn plugin_type(&self) -> PluginType;
 }
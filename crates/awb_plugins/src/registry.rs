@@ -0,0 +1,349 @@
+//! Installing plugins from published bundles.
+//!
+//! A bundle is a JSON descriptor naming a plugin file, its optional sidecar
+//! manifest, their SHA-256 hashes and their minisign signatures:
+//!
+//! ```json
+//! {
+//!   "name": "dates.lua",
+//!   "version": "1.2.0",
+//!   "source": "dates.lua",
+//!   "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+//!   "manifest": "dates.lua.toml",
+//!   "manifest_sha256": "60303ae22b998861bce3b28f33eec1be758a213c86c93c076dbe9f558c11c752",
+//!   "signature": "dates.lua.minisig",
+//!   "manifest_signature": "dates.lua.toml.minisig"
+//! }
+//! ```
+//!
+//! `source`, `manifest` and the signatures are URLs, resolved against the
+//! descriptor's own location when relative. The hashes only prove the files
+//! match the descriptor; the signatures, checked against the
+//! [`TrustStore`](crate::TrustStore), prove who published them. They are
+//! installed next to the plugin, as `minisign -Sm` writes them, so the
+//! plugin loads under
+//! [`require_signed_plugins`](crate::SandboxConfig::require_signed_plugins).
+//!
+//! Installed plugins are recorded in [`INSTALLED_FILE`] in the plugin
+//! directory, so they can be listed, updated from where they came from and
+//! removed. See [`PluginManager::install_bundle`](crate::PluginManager::install_bundle).
+
+use crate::error::{PluginError, Result};
+use awb_storage::rule_library::content_hash;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File in a plugin directory recording the plugins installed from bundles
+pub const INSTALLED_FILE: &str = "installed.json";
+
+/// Description of a published plugin bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleDescriptor {
+    /// File name the plugin is installed under, ending in `.lua` or `.wasm`
+    pub name: String,
+    /// Publisher's version label, shown when listing plugins
+    #[serde(default)]
+    pub version: Option<String>,
+    /// URL of the plugin file
+    pub source: String,
+    /// Hex SHA-256 of the plugin file
+    pub sha256: String,
+    /// URL of the sidecar manifest, if the plugin has one
+    #[serde(default)]
+    pub manifest: Option<String>,
+    /// Hex SHA-256 of the manifest; required with `manifest`
+    #[serde(default)]
+    pub manifest_sha256: Option<String>,
+    /// URL of the plugin file's minisign signature
+    #[serde(default)]
+    pub signature: Option<String>,
+    /// URL of the manifest's minisign signature; requires `signature`
+    #[serde(default)]
+    pub manifest_signature: Option<String>,
+}
+
+impl BundleDescriptor {
+    /// Parse and check a descriptor
+    pub fn from_json(s: &str) -> Result<Self> {
+        let descriptor: Self = serde_json::from_str(s)
+            .map_err(|e| PluginError::Verification(format!("Invalid bundle descriptor: {}", e)))?;
+        if !is_plugin_file_name(&descriptor.name) {
+            return Err(PluginError::Verification(format!(
+                "Invalid plugin name '{}': expected a file name ending in .lua or .wasm",
+                descriptor.name
+            )));
+        }
+        if descriptor.manifest.is_some() != descriptor.manifest_sha256.is_some() {
+            return Err(PluginError::Verification(
+                "manifest and manifest_sha256 must be given together".to_string(),
+            ));
+        }
+        if descriptor.manifest_signature.is_some()
+            && (descriptor.manifest.is_none() || descriptor.signature.is_none())
+        {
+            return Err(PluginError::Verification(
+                "manifest_signature needs a manifest and a signature for the plugin".to_string(),
+            ));
+        }
+        Ok(descriptor)
+    }
+}
+
+/// A downloaded bundle, ready to be verified and installed
+#[derive(Debug, Clone)]
+pub struct PluginBundle {
+    pub descriptor: BundleDescriptor,
+    /// Where the descriptor was fetched from, used again by updates
+    pub origin: String,
+    /// Contents of the plugin file
+    pub source: Vec<u8>,
+    /// Contents of the sidecar manifest
+    pub manifest: Option<String>,
+    /// Contents of the plugin file's `.minisig`
+    pub signature: Option<String>,
+    /// Contents of the manifest's `.minisig`
+    pub manifest_signature: Option<String>,
+}
+
+impl PluginBundle {
+    /// Check the files against the descriptor's hashes. Signatures are
+    /// checked against the trust store when the bundle is installed.
+    pub fn verify(&self) -> Result<()> {
+        check_hash("plugin file", &self.descriptor.sha256, &self.source)?;
+        match (&self.descriptor.manifest_sha256, &self.manifest) {
+            (Some(expected), Some(manifest)) => check_hash("manifest", expected, manifest)?,
+            (None, None) => {}
+            (Some(_), None) => {
+                return Err(PluginError::Verification(
+                    "bundle declares a manifest that was not downloaded".to_string(),
+                ));
+            }
+            (None, Some(_)) => {
+                return Err(PluginError::Verification(
+                    "manifest has no manifest_sha256 to check it against".to_string(),
+                ));
+            }
+        }
+        let downloaded = |declared: &Option<String>, contents: &Option<String>, what: &str| {
+            if declared.is_some() != contents.is_some() {
+                return Err(PluginError::Verification(format!(
+                    "{} does not match the descriptor",
+                    what
+                )));
+            }
+            Ok(())
+        };
+        downloaded(&self.descriptor.signature, &self.signature, "signature")?;
+        downloaded(
+            &self.descriptor.manifest_signature,
+            &self.manifest_signature,
+            "manifest signature",
+        )
+    }
+}
+
+/// Where an installed plugin came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledPlugin {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// URL or path of the bundle descriptor
+    pub origin: String,
+    /// SHA-256 of the installed plugin file
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_sha256: Option<String>,
+    /// Name of the trusted key that signed the plugin, if it was signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_by: Option<String>,
+    pub installed_at: DateTime<Utc>,
+}
+
+impl InstalledPlugin {
+    /// Record for a bundle installed now
+    pub fn from_bundle(bundle: &PluginBundle, signed_by: Option<&str>) -> Self {
+        Self {
+            name: bundle.descriptor.name.clone(),
+            version: bundle.descriptor.version.clone(),
+            origin: bundle.origin.clone(),
+            sha256: normalize_hash(&bundle.descriptor.sha256),
+            manifest_sha256: bundle
+                .descriptor
+                .manifest_sha256
+                .as_deref()
+                .map(normalize_hash),
+            signed_by: signed_by.map(str::to_string),
+            installed_at: Utc::now(),
+        }
+    }
+}
+
+/// The plugins recorded as installed in `dir`, by name
+pub fn read_installed(dir: &Path) -> Result<BTreeMap<String, InstalledPlugin>> {
+    let path = dir.join(INSTALLED_FILE);
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let data = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&data)
+        .map_err(|e| PluginError::LoadFailed(format!("Invalid {}: {}", path.display(), e)))
+}
+
+/// Replace the installed plugin records in `dir`
+pub(crate) fn write_installed(
+    dir: &Path,
+    installed: &BTreeMap<String, InstalledPlugin>,
+) -> Result<()> {
+    let path = dir.join(INSTALLED_FILE);
+    let tmp = path.with_extension("tmp");
+    let data = serde_json::to_string_pretty(installed)
+        .map_err(|e| PluginError::ExecutionFailed(e.to_string()))?;
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Default plugin directory, `~/.awb-rs/plugins`
+pub fn default_plugin_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".awb-rs")
+        .join("plugins")
+}
+
+/// Whether `name` is a bare `.lua` or `.wasm` file name, so installing it
+/// cannot write outside the plugin directory
+fn is_plugin_file_name(name: &str) -> bool {
+    let path = Path::new(name);
+    !name.starts_with('.')
+        && path.file_name().and_then(|n| n.to_str()) == Some(name)
+        && matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("lua" | "wasm")
+        )
+}
+
+fn normalize_hash(hash: &str) -> String {
+    hash.trim()
+        .trim_start_matches("sha256:")
+        .to_ascii_lowercase()
+}
+
+fn check_hash(what: &str, expected: &str, content: impl AsRef<[u8]>) -> Result<()> {
+    let expected = normalize_hash(expected);
+    let found = content_hash(content);
+    if expected == found {
+        Ok(())
+    } else {
+        Err(PluginError::Verification(format!(
+            "{} hash mismatch: expected {}, found {}",
+            what, expected, found
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "function transform(text) return text end";
+
+    fn bundle() -> PluginBundle {
+        PluginBundle {
+            descriptor: BundleDescriptor {
+                name: "noop.lua".to_string(),
+                version: Some("1.0".to_string()),
+                source: "noop.lua".to_string(),
+                sha256: content_hash(SCRIPT),
+                manifest: None,
+                manifest_sha256: None,
+                signature: None,
+                manifest_signature: None,
+            },
+            origin: "https://example.org/noop.json".to_string(),
+            source: SCRIPT.as_bytes().to_vec(),
+            manifest: None,
+            signature: None,
+            manifest_signature: None,
+        }
+    }
+
+    #[test]
+    fn test_descriptor_rejects_unsafe_names() {
+        let json = |name: &str| format!(r#"{{"name": "{}", "source": "x", "sha256": "00"}}"#, name);
+        assert!(BundleDescriptor::from_json(&json("dates.lua")).is_ok());
+        assert!(BundleDescriptor::from_json(&json("dates.wasm")).is_ok());
+        for name in [
+            "../dates.lua",
+            "sub/dates.lua",
+            ".hidden.lua",
+            "dates.sh",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    BundleDescriptor::from_json(&json(name)),
+                    Err(PluginError::Verification(_))
+                ),
+                "{}",
+                name
+            );
+        }
+
+        let unhashed =
+            r#"{"name": "a.lua", "source": "a.lua", "sha256": "00", "manifest": "a.lua.toml"}"#;
+        assert!(BundleDescriptor::from_json(unhashed).is_err());
+        let manifest_only = r#"{"name": "a.lua", "source": "a.lua", "sha256": "00",
+            "manifest": "a.lua.toml", "manifest_sha256": "00",
+            "manifest_signature": "a.lua.toml.minisig"}"#;
+        assert!(BundleDescriptor::from_json(manifest_only).is_err());
+    }
+
+    #[test]
+    fn test_verify_checks_hashes() {
+        let mut good = bundle();
+        good.descriptor.sha256 = format!("sha256:{}", content_hash(SCRIPT).to_uppercase());
+        assert!(good.verify().is_ok());
+
+        let mut tampered = bundle();
+        tampered.source = b"function transform(text) return '' end".to_vec();
+        let err = tampered.verify().unwrap_err();
+        assert!(err.to_string().contains("plugin file hash mismatch"));
+
+        let mut manifest = bundle();
+        manifest.descriptor.manifest_sha256 = Some(content_hash("min_tier = 1"));
+        manifest.manifest = Some("min_tier = 0".to_string());
+        assert!(manifest.verify().is_err());
+        manifest.manifest = Some("min_tier = 1".to_string());
+        assert!(manifest.verify().is_ok());
+    }
+
+    #[test]
+    fn test_verify_needs_the_declared_signatures() {
+        let mut signed = bundle();
+        signed.descriptor.signature = Some("noop.lua.minisig".to_string());
+        assert!(signed.verify().is_err());
+        signed.signature = Some("untrusted comment: x".to_string());
+        assert!(signed.verify().is_ok());
+
+        let mut extra = bundle();
+        extra.signature = Some("untrusted comment: x".to_string());
+        assert!(extra.verify().is_err());
+    }
+
+    #[test]
+    fn test_installed_records_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(read_installed(dir.path()).unwrap().is_empty());
+
+        let record = InstalledPlugin::from_bundle(&bundle(), None);
+        let installed = BTreeMap::from([(record.name.clone(), record.clone())]);
+        write_installed(dir.path(), &installed).unwrap();
+        assert_eq!(read_installed(dir.path()).unwrap(), installed);
+    }
+}
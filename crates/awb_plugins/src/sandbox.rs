@@ -1,16 +1,38 @@
-use std::time::Duration;
+use crate::error::{PluginError, Result};
+use awb_domain::units;
+use awb_storage::rule_library::content_hash;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 /// Maximum allowed memory limit (256MB)
 pub const MAX_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
 
-/// Configuration for plugin sandboxing and resource limits
-#[derive(Debug, Clone)]
+/// Maximum Lua interpreters kept per plugin
+pub const MAX_LUA_POOL_SIZE: usize = 64;
+
+/// Size of a WebAssembly linear memory page
+pub const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+/// Configuration for plugin sandboxing and resource limits.
+///
+/// In TOML, `timeout` and `cpu_time` are written like `"5s"` and the byte
+/// limits like `"16MiB"`; keys left out take their default value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct SandboxConfig {
-    /// Maximum execution time for a plugin
+    /// Maximum wall-clock time for one plugin call
+    #[serde(with = "units::duration")]
     pub timeout: Duration,
 
+    /// Maximum CPU time one plugin call may use. Unlike `timeout`, time the
+    /// plugin spends sleeping or blocked in a host call is not counted.
+    /// Enforced on Unix; elsewhere only `timeout` applies.
+    #[serde(with = "units::duration")]
+    pub cpu_time: Duration,
+
     /// Maximum memory usage in bytes (Lua only)
     /// Default is 16MB, as 1MB is too small for real wiki articles with templates
+    #[serde(with = "units::byte_size")]
     pub memory_limit: usize,
 
     /// Maximum number of instructions (Lua only)
@@ -18,15 +40,52 @@ pub struct SandboxConfig {
 
     /// Maximum fuel for WASM execution
     pub wasm_fuel: u64,
+
+    /// Maximum linear memory a WASM module may use, in 64 KiB pages
+    pub wasm_max_memory_pages: u32,
+
+    /// Maximum number of keys a plugin may keep in its state store
+    pub state_max_keys: usize,
+
+    /// Maximum total size of keys and values in a plugin's state store
+    #[serde(with = "units::byte_size")]
+    pub state_max_bytes: usize,
+
+    /// Most Lua interpreters kept per plugin. Pages transformed in parallel
+    /// beyond this many wait for an interpreter to come free.
+    pub lua_pool_size: usize,
+
+    /// Make plugin output depend only on the page and `seed`: `math.random`
+    /// is reseeded for every page, `math.randomseed` is removed, `pairs`
+    /// visits keys in sorted order, and WASM floating point is made
+    /// portable. Note that the time limits still depend on the machine.
+    pub deterministic: bool,
+
+    /// Seed for plugin random numbers in deterministic mode, usually set
+    /// from the run ID with [`SandboxConfig::with_run_id`]
+    pub seed: u64,
+
+    /// Refuse to load plugins (and their manifests) without a minisign
+    /// signature from a key in the plugin manager's trust store. Bot runs
+    /// always set this.
+    pub require_signed_plugins: bool,
 }
 
 impl Default for SandboxConfig {
     fn default() -> Self {
         Self {
             timeout: Duration::from_secs(5),
+            cpu_time: Duration::from_secs(2),
             memory_limit: 16 * 1024 * 1024, // 16MB - real wiki articles with templates need more than 1MB
             instruction_limit: Some(1_000_000),
             wasm_fuel: 10_000_000,
+            wasm_max_memory_pages: 256, // 16MB, matching the Lua default
+            state_max_keys: 1024,
+            state_max_bytes: 64 * 1024,
+            lua_pool_size: 4,
+            deterministic: false,
+            seed: 0,
+            require_signed_plugins: false,
         }
         .validated()
     }
@@ -41,6 +100,13 @@ impl SandboxConfig {
         }
     }
 
+    /// Parse a sandbox configuration from TOML, capped at safe limits
+    pub fn from_toml(s: &str) -> Result<Self> {
+        let config: Self = toml::from_str(s)
+            .map_err(|e| PluginError::LoadFailed(format!("Invalid sandbox config: {}", e)))?;
+        Ok(config.validated())
+    }
+
     /// Create a configuration with no instruction limits (use with caution)
     /// WARNING: Disables all sandbox limits. For testing only.
     #[cfg(test)]
@@ -58,6 +124,208 @@ impl SandboxConfig {
         if self.memory_limit > MAX_MEMORY_LIMIT {
             self.memory_limit = MAX_MEMORY_LIMIT;
         }
+        let max_pages = (MAX_MEMORY_LIMIT / WASM_PAGE_SIZE) as u32;
+        if self.wasm_max_memory_pages > max_pages {
+            self.wasm_max_memory_pages = max_pages;
+        }
+        self.lua_pool_size = self.lua_pool_size.clamp(1, MAX_LUA_POOL_SIZE);
+        self
+    }
+
+    /// Turn on deterministic mode, seeding plugin random numbers from
+    /// `run_id` so that rerunning with the same ID gives the same output
+    #[must_use]
+    pub fn with_run_id(mut self, run_id: &str) -> Self {
+        self.deterministic = true;
+        self.seed = seed_from(&[run_id]);
         self
     }
+
+    /// Seed for one call of plugin `name` on the page identified by `key`
+    pub(crate) fn page_seed(&self, name: &str, key: &str) -> u64 {
+        seed_from(&[&self.seed.to_string(), name, key])
+    }
+
+    /// Error for a call stopped by the wall-clock `timeout`
+    pub(crate) fn timed_out(&self) -> PluginError {
+        PluginError::Timeout(self.timeout.as_secs_f64().ceil() as u64)
+    }
+
+    /// Error for a call stopped by the `cpu_time` limit
+    pub(crate) fn cpu_time_exceeded(&self) -> PluginError {
+        PluginError::ResourceLimit(format!(
+            "CPU time limit exceeded (max: {})",
+            units::format_duration(self.cpu_time)
+        ))
+    }
+}
+
+/// Stable seed from the SHA-256 of `parts`, kept below 2^63 so it fits TOML
+/// and Lua integers
+fn seed_from(parts: &[&str]) -> u64 {
+    let digest = content_hash(parts.join("\u{0}"));
+    u64::from_str_radix(&digest[..16], 16).expect("SHA-256 digest is hex") >> 1
+}
+
+/// Tracks both time limits for one plugin call.
+///
+/// Must be started on the thread that runs the plugin: CPU time is read from
+/// that thread's clock, so waiting in other threads is never charged to it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CallTimer {
+    started: Instant,
+    cpu_started: Option<Duration>,
+    timeout: Duration,
+    cpu_time: Duration,
+}
+
+/// A time limit a plugin call ran into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TimeLimit {
+    Wall,
+    Cpu,
+}
+
+impl CallTimer {
+    pub(crate) fn start(config: &SandboxConfig) -> Self {
+        Self {
+            started: Instant::now(),
+            cpu_started: thread_cpu_time(),
+            timeout: config.timeout,
+            cpu_time: config.cpu_time,
+        }
+    }
+
+    /// CPU time the calling thread has used since the timer started, or
+    /// `None` where there is no per-thread CPU clock
+    pub(crate) fn cpu_elapsed(&self) -> Option<Duration> {
+        Some(thread_cpu_time()?.saturating_sub(self.cpu_started?))
+    }
+
+    /// The first limit the call has gone over, if any. Must be called on the
+    /// thread that started the timer.
+    pub(crate) fn exceeded(&self) -> Option<TimeLimit> {
+        if self.started.elapsed() >= self.timeout {
+            return Some(TimeLimit::Wall);
+        }
+        if self.cpu_elapsed().is_some_and(|used| used >= self.cpu_time) {
+            return Some(TimeLimit::Cpu);
+        }
+        None
+    }
+}
+
+impl TimeLimit {
+    pub(crate) fn error(self, config: &SandboxConfig) -> PluginError {
+        match self {
+            Self::Wall => config.timed_out(),
+            Self::Cpu => config.cpu_time_exceeded(),
+        }
+    }
+}
+
+/// CPU time used so far by the calling thread
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid, writable timespec for the duration of the call
+    let rc = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+    if rc != 0 {
+        return None;
+    }
+    Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml_with_units() {
+        let config = SandboxConfig::from_toml(
+            r#"
+timeout = "1500ms"
+memory_limit = "32MiB"
+state_max_bytes = "128KiB"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.timeout, Duration::from_millis(1500));
+        assert_eq!(config.memory_limit, 32 << 20);
+        assert_eq!(config.state_max_bytes, 128 << 10);
+        assert_eq!(config.wasm_fuel, SandboxConfig::default().wasm_fuel);
+    }
+
+    #[test]
+    fn test_from_toml_caps_and_reports_errors() {
+        let config = SandboxConfig::from_toml("memory_limit = \"1GiB\"").unwrap();
+        assert_eq!(config.memory_limit, MAX_MEMORY_LIMIT);
+        let config = SandboxConfig::from_toml("lua_pool_size = 0").unwrap();
+        assert_eq!(config.lua_pool_size, 1);
+
+        let err = SandboxConfig::from_toml("timeout = \"5\"").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("timeout"), "{}", message);
+        assert!(message.contains("missing unit"), "{}", message);
+    }
+
+    #[test]
+    fn test_serializes_human_units() {
+        let toml = toml::to_string(&SandboxConfig::default()).unwrap();
+        assert!(toml.contains("timeout = \"5s\""), "{}", toml);
+        assert!(toml.contains("cpu_time = \"2s\""), "{}", toml);
+        assert!(toml.contains("memory_limit = \"16MiB\""), "{}", toml);
+        assert_eq!(
+            SandboxConfig::from_toml(&toml).unwrap().memory_limit,
+            16 << 20
+        );
+    }
+
+    #[test]
+    fn test_run_id_seed_is_stable() {
+        let a = SandboxConfig::default().with_run_id("run-1");
+        let b = SandboxConfig::default().with_run_id("run-1");
+        let c = SandboxConfig::default().with_run_id("run-2");
+        assert!(a.deterministic);
+        assert_eq!(a.seed, b.seed);
+        assert_ne!(a.seed, c.seed);
+        assert_eq!(a.page_seed("p.lua", "Foo"), b.page_seed("p.lua", "Foo"));
+        assert_ne!(a.page_seed("p.lua", "Foo"), a.page_seed("p.lua", "Bar"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sleeping_is_not_cpu_time() {
+        let config = SandboxConfig {
+            timeout: Duration::from_millis(150),
+            cpu_time: Duration::from_millis(100),
+            ..SandboxConfig::default()
+        };
+        let timer = CallTimer::start(&config);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(timer.cpu_elapsed().unwrap() < Duration::from_millis(50));
+        assert_eq!(timer.exceeded(), None);
+
+        std::thread::sleep(Duration::from_millis(150));
+        assert_eq!(timer.exceeded(), Some(TimeLimit::Wall));
+
+        // Spinning uses CPU time as fast as wall-clock time
+        let config = SandboxConfig {
+            timeout: Duration::from_secs(60),
+            ..config
+        };
+        let timer = CallTimer::start(&config);
+        while timer.exceeded().is_none() {
+            std::hint::spin_loop();
+        }
+        assert_eq!(timer.exceeded(), Some(TimeLimit::Cpu));
+    }
 }
@@ -0,0 +1,565 @@
+//! Minisign signatures for plugin files.
+//!
+//! A plugin `dates.lua` is signed by `dates.lua.minisig` next to it, as
+//! written by `minisign -Sm dates.lua`; a sidecar manifest is signed the
+//! same way. Both the prehashed (default) and legacy signature formats are
+//! accepted, and the trusted comment is checked as well.
+//!
+//! Publisher keys are kept in a [`TrustStore`]. When
+//! [`SandboxConfig::require_signed_plugins`](crate::SandboxConfig::require_signed_plugins)
+//! is set, plugins without a valid signature from one of its keys are
+//! refused.
+
+use crate::error::{PluginError, Result};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::signature::{ED25519, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Signature algorithm of keys and legacy signatures
+const ALG_ED25519: [u8; 2] = *b"Ed";
+/// Signature algorithm of signatures over the BLAKE2b-512 hash of the file
+const ALG_ED25519_PREHASHED: [u8; 2] = *b"ED";
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment:";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+/// A minisign public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    key_id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl PublicKey {
+    /// Parse the contents of a minisign `.pub` file, or just its base64 line
+    pub fn parse(text: &str) -> Result<Self> {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_PREFIX))
+            .ok_or_else(|| invalid("public key", "no key found"))?;
+        let bytes = decode(line, "public key")?;
+        if bytes.len() != 42 || bytes[..2] != ALG_ED25519 {
+            return Err(invalid("public key", "not a minisign Ed25519 key"));
+        }
+        let mut key_id = [0u8; 8];
+        let mut key = [0u8; 32];
+        key_id.copy_from_slice(&bytes[2..10]);
+        key.copy_from_slice(&bytes[10..]);
+        Ok(Self { key_id, key })
+    }
+
+    /// Key ID as minisign prints it
+    pub fn key_id(&self) -> String {
+        format_key_id(&self.key_id)
+    }
+
+    /// The key as the base64 line of a minisign `.pub` file
+    pub fn to_base64(&self) -> String {
+        let mut bytes = ALG_ED25519.to_vec();
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(&self.key);
+        BASE64.encode(bytes)
+    }
+
+    /// Check that `signature` was made over `content` with this key
+    pub fn verify(&self, content: &[u8], signature: &Signature) -> Result<()> {
+        if signature.key_id != self.key_id {
+            return Err(PluginError::Verification(format!(
+                "signed with key {}, not {}",
+                format_key_id(&signature.key_id),
+                self.key_id()
+            )));
+        }
+        let key = UnparsedPublicKey::new(&ED25519, self.key);
+        let message = if signature.prehashed {
+            blake2b_512(content).to_vec()
+        } else {
+            content.to_vec()
+        };
+        key.verify(&message, &signature.signature)
+            .map_err(|_| PluginError::Verification("signature does not match".to_string()))?;
+
+        let mut global = signature.signature.to_vec();
+        global.extend_from_slice(signature.trusted_comment.as_bytes());
+        key.verify(&global, &signature.global_signature)
+            .map_err(|_| {
+                PluginError::Verification("trusted comment signature does not match".to_string())
+            })
+    }
+}
+
+/// A minisign signature file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    prehashed: bool,
+    key_id: [u8; 8],
+    signature: [u8; 64],
+    trusted_comment: String,
+    global_signature: [u8; 64],
+}
+
+impl Signature {
+    /// Parse the contents of a `.minisig` file
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().map(|line| line.trim_end_matches('\r'));
+        let mut next = |what| {
+            lines
+                .next()
+                .ok_or_else(|| invalid("signature", &format!("missing {}", what)))
+        };
+        if !next("untrusted comment")?.starts_with(UNTRUSTED_PREFIX) {
+            return Err(invalid("signature", "missing untrusted comment"));
+        }
+        let bytes = decode(next("signature")?, "signature")?;
+        let trusted_comment = next("trusted comment")?
+            .strip_prefix(TRUSTED_PREFIX)
+            .ok_or_else(|| invalid("signature", "missing trusted comment"))?
+            .to_string();
+        let global = decode(next("global signature")?, "signature")?;
+
+        let prehashed = match bytes.get(..2) {
+            Some(alg) if alg == ALG_ED25519_PREHASHED => true,
+            Some(alg) if alg == ALG_ED25519 => false,
+            _ => return Err(invalid("signature", "unsupported algorithm")),
+        };
+        if bytes.len() != 74 || global.len() != 64 {
+            return Err(invalid("signature", "wrong length"));
+        }
+        let mut key_id = [0u8; 8];
+        let mut signature = [0u8; 64];
+        let mut global_signature = [0u8; 64];
+        key_id.copy_from_slice(&bytes[2..10]);
+        signature.copy_from_slice(&bytes[10..]);
+        global_signature.copy_from_slice(&global);
+        Ok(Self {
+            prehashed,
+            key_id,
+            signature,
+            trusted_comment,
+            global_signature,
+        })
+    }
+
+    /// ID of the key that made the signature
+    pub fn key_id(&self) -> String {
+        format_key_id(&self.key_id)
+    }
+
+    /// The signed comment, e.g. a timestamp and file name
+    pub fn trusted_comment(&self) -> &str {
+        &self.trusted_comment
+    }
+}
+
+/// Path of the signature for `path`, e.g. `dates.lua.minisig`
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".minisig");
+    PathBuf::from(name)
+}
+
+/// A publisher key trusted to sign plugins
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    /// Who the key belongs to
+    pub name: String,
+    /// Base64 minisign public key
+    pub public_key: String,
+}
+
+impl TrustedKey {
+    pub fn key(&self) -> Result<PublicKey> {
+        PublicKey::parse(&self.public_key)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrustStoreFile {
+    #[serde(default)]
+    keys: Vec<TrustedKey>,
+}
+
+/// Publisher keys trusted to sign plugins, kept in a TOML file:
+///
+/// ```toml
+/// [[keys]]
+/// name = "Example publisher"
+/// public_key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"
+/// ```
+#[derive(Debug, Clone)]
+pub struct TrustStore {
+    path: PathBuf,
+    keys: Vec<TrustedKey>,
+}
+
+impl TrustStore {
+    /// Load the trust store at `path`; a missing file is an empty store
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file: TrustStoreFile = if path.is_file() {
+            let content = std::fs::read_to_string(&path)?;
+            toml::from_str(&content).map_err(|e| {
+                PluginError::LoadFailed(format!("Invalid trust store {}: {}", path.display(), e))
+            })?
+        } else {
+            TrustStoreFile::default()
+        };
+        for key in &file.keys {
+            key.key()?;
+        }
+        Ok(Self {
+            path,
+            keys: file.keys,
+        })
+    }
+
+    /// Write the store back to its file
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = toml::to_string(&TrustStoreFile {
+            keys: self.keys.clone(),
+        })
+        .map_err(|e| PluginError::ExecutionFailed(e.to_string()))?;
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn keys(&self) -> &[TrustedKey] {
+        &self.keys
+    }
+
+    /// Trust `key` under `name`; fails if a key with its ID is already trusted
+    pub fn add(&mut self, name: &str, key: &PublicKey) -> Result<()> {
+        if let Some(existing) = self.find(&key.key_id()) {
+            return Err(PluginError::Verification(format!(
+                "key {} is already trusted as '{}'",
+                key.key_id(),
+                existing.name
+            )));
+        }
+        self.keys.push(TrustedKey {
+            name: name.to_string(),
+            public_key: key.to_base64(),
+        });
+        Ok(())
+    }
+
+    /// Stop trusting the key with the given ID or name
+    pub fn remove(&mut self, id_or_name: &str) -> Option<TrustedKey> {
+        let index = self.keys.iter().position(|k| key_matches(k, id_or_name))?;
+        Some(self.keys.remove(index))
+    }
+
+    fn find(&self, id_or_name: &str) -> Option<&TrustedKey> {
+        self.keys.iter().find(|k| key_matches(k, id_or_name))
+    }
+
+    /// Check `path` against its `.minisig` signature, returning the trusted
+    /// key that made it
+    pub fn verify_file(&self, path: &Path) -> Result<&TrustedKey> {
+        let sig_path = signature_path(path);
+        if !sig_path.is_file() {
+            return Err(PluginError::Verification(format!(
+                "{} is not signed (no {})",
+                path.display(),
+                sig_path.display()
+            )));
+        }
+        let signature = Signature::parse(&std::fs::read_to_string(&sig_path)?)?;
+        let trusted = self.find(&signature.key_id()).ok_or_else(|| {
+            PluginError::Verification(format!(
+                "{} is signed with untrusted key {}",
+                path.display(),
+                signature.key_id()
+            ))
+        })?;
+        trusted
+            .key()?
+            .verify(&std::fs::read(path)?, &signature)
+            .map_err(|e| PluginError::Verification(format!("{}: {}", path.display(), e)))?;
+        Ok(trusted)
+    }
+}
+
+/// Default trust store, `~/.awb-rs/trusted-keys.toml`
+pub fn default_trust_store_path() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".awb-rs")
+        .join("trusted-keys.toml")
+}
+
+fn key_matches(key: &TrustedKey, id_or_name: &str) -> bool {
+    key.name == id_or_name
+        || key
+            .key()
+            .is_ok_and(|k| k.key_id().eq_ignore_ascii_case(id_or_name))
+}
+
+fn format_key_id(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+fn decode(line: &str, what: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(line.trim())
+        .map_err(|e| invalid(what, &e.to_string()))
+}
+
+fn invalid(what: &str, reason: &str) -> PluginError {
+    PluginError::Verification(format!("Invalid minisign {}: {}", what, reason))
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// BLAKE2b with a 64-byte digest and no key (RFC 7693), which minisign
+/// signs in place of the file
+fn blake2b_512(data: &[u8]) -> [u8; 64] {
+    let mut h = BLAKE2B_IV;
+    h[0] ^= 0x0101_0040;
+
+    // Every block but the last is compressed as a non-final block; the last
+    // (possibly partial or, for empty input, all zero) block is final
+    let full_blocks = data.len().saturating_sub(1) / 128;
+    for (i, block) in data.chunks(128).take(full_blocks).enumerate() {
+        blake2b_compress(&mut h, block, (i as u128 + 1) * 128, false);
+    }
+    let tail = &data[full_blocks * 128..];
+    let mut last = [0u8; 128];
+    last[..tail.len()].copy_from_slice(tail);
+    blake2b_compress(&mut h, &last, data.len() as u128, true);
+
+    let mut out = [0u8; 64];
+    for (chunk, word) in out.chunks_mut(8).zip(h) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+fn blake2b_compress(h: &mut [u64; 8], block: &[u8], counter: u128, last: bool) {
+    let mut m = [0u64; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().expect("8-byte chunk"));
+    }
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+        v[d] = (v[d] ^ v[a]).rotate_right(32);
+        v[c] = v[c].wrapping_add(v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(24);
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+        v[d] = (v[d] ^ v[a]).rotate_right(16);
+        v[c] = v[c].wrapping_add(v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(63);
+    }
+
+    for round in 0..12 {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// A minisign key pair for signing test plugins
+#[cfg(test)]
+pub(crate) struct TestSigner {
+    pair: ring::signature::Ed25519KeyPair,
+    key_id: [u8; 8],
+}
+
+#[cfg(test)]
+impl TestSigner {
+    pub(crate) fn new(key_id: u64) -> Self {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        Self {
+            pair: ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap(),
+            key_id: key_id.to_le_bytes(),
+        }
+    }
+
+    /// Contents of the `.pub` file
+    pub(crate) fn public_key(&self) -> String {
+        use ring::signature::KeyPair;
+        let mut bytes = ALG_ED25519.to_vec();
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(self.pair.public_key().as_ref());
+        format!(
+            "untrusted comment: minisign public key {}\n{}\n",
+            format_key_id(&self.key_id),
+            BASE64.encode(bytes)
+        )
+    }
+
+    /// Contents of the `.minisig` file for `content`
+    pub(crate) fn sign(&self, content: &[u8], prehashed: bool) -> String {
+        let (alg, message) = if prehashed {
+            (ALG_ED25519_PREHASHED, blake2b_512(content).to_vec())
+        } else {
+            (ALG_ED25519, content.to_vec())
+        };
+        let signature = self.pair.sign(&message);
+        let trusted_comment = "timestamp:1700000000\tfile:plugin";
+        let mut global = signature.as_ref().to_vec();
+        global.extend_from_slice(trusted_comment.as_bytes());
+        let mut bytes = alg.to_vec();
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(signature.as_ref());
+        format!(
+            "untrusted comment: signature from minisign secret key\n{}\n{}{}\n{}\n",
+            BASE64.encode(bytes),
+            TRUSTED_PREFIX,
+            trusted_comment,
+            BASE64.encode(self.pair.sign(&global))
+        )
+    }
+
+    /// Write `path.minisig` for the file at `path`
+    pub(crate) fn sign_file(&self, path: &Path) {
+        let content = std::fs::read(path).unwrap();
+        std::fs::write(signature_path(path), self.sign(&content, true)).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_blake2b_512_vectors() {
+        // RFC 7693 Appendix A, and the digest of the empty input
+        assert_eq!(
+            hex(&blake2b_512(b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            hex(&blake2b_512(b"")),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+             d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        // Inputs on and around the block boundary differ from each other
+        let digests: std::collections::HashSet<_> = [127, 128, 129, 256]
+            .into_iter()
+            .map(|n| blake2b_512(&vec![0u8; n]))
+            .collect();
+        assert_eq!(digests.len(), 4);
+    }
+
+    #[test]
+    fn test_verify_prehashed_and_legacy_signatures() {
+        let signer = TestSigner::new(0x0123_4567_89ab_cdef);
+        let key = PublicKey::parse(&signer.public_key()).unwrap();
+        assert_eq!(key.key_id(), "0123456789ABCDEF");
+        assert_eq!(PublicKey::parse(&key.to_base64()).unwrap(), key);
+
+        let content = b"function transform(text) return text end";
+        for prehashed in [true, false] {
+            let signature = Signature::parse(&signer.sign(content, prehashed)).unwrap();
+            assert_eq!(signature.key_id(), key.key_id());
+            assert!(signature.trusted_comment().starts_with("timestamp:"));
+            key.verify(content, &signature).unwrap();
+            assert!(key.verify(b"tampered", &signature).is_err());
+        }
+
+        let other = TestSigner::new(7);
+        let signature = Signature::parse(&other.sign(content, true)).unwrap();
+        let err = key.verify(content, &signature).unwrap_err();
+        assert!(err.to_string().contains("0000000000000007"));
+    }
+
+    #[test]
+    fn test_tampered_trusted_comment_is_rejected() {
+        let signer = TestSigner::new(1);
+        let key = PublicKey::parse(&signer.public_key()).unwrap();
+        let content = b"x";
+        let text = signer
+            .sign(content, true)
+            .replace("file:plugin", "file:other");
+        let signature = Signature::parse(&text).unwrap();
+        assert!(key.verify(content, &signature).is_err());
+    }
+
+    #[test]
+    fn test_trust_store_round_trip_and_verify_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let signer = TestSigner::new(42);
+        let key = PublicKey::parse(&signer.public_key()).unwrap();
+
+        let mut store = TrustStore::load(dir.path().join("keys.toml")).unwrap();
+        store.add("Publisher", &key).unwrap();
+        assert!(store.add("Again", &key).is_err());
+        store.save().unwrap();
+
+        let store = TrustStore::load(dir.path().join("keys.toml")).unwrap();
+        assert_eq!(store.keys().len(), 1);
+
+        let plugin = dir.path().join("dates.lua");
+        std::fs::write(&plugin, "function transform(t) return t end").unwrap();
+        assert!(store.verify_file(&plugin).is_err());
+        signer.sign_file(&plugin);
+        assert_eq!(store.verify_file(&plugin).unwrap().name, "Publisher");
+
+        std::fs::write(&plugin, "function transform(t) return '' end").unwrap();
+        assert!(store.verify_file(&plugin).is_err());
+
+        let mut store = store;
+        assert!(store.remove(&key.key_id().to_lowercase()).is_some());
+        assert!(store.remove("Publisher").is_none());
+    }
+}
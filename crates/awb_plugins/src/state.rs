@@ -0,0 +1,135 @@
+use crate::error::{PluginError, Result};
+use awb_storage::PluginStateMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Small key-value store a plugin can use across pages within a run.
+///
+/// Cloning shares the underlying map, so the host can inspect or persist the
+/// state a plugin writes from inside its sandbox.
+#[derive(Debug, Clone)]
+pub struct PluginState {
+    entries: Arc<Mutex<PluginStateMap>>,
+    max_keys: usize,
+    max_bytes: usize,
+}
+
+impl PluginState {
+    /// Create an empty store with the given quotas
+    pub fn new(max_keys: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(PluginStateMap::new())),
+            max_keys,
+            max_bytes,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PluginStateMap> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn size_of(entries: &PluginStateMap) -> usize {
+        entries.iter().map(|(k, v)| k.len() + v.len()).sum()
+    }
+
+    /// Get the value stored under `key`
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.lock().get(key).cloned()
+    }
+
+    /// Store `value` under `key`, or remove the key if `value` is `None`.
+    ///
+    /// Fails without modifying the store if the write would exceed the key
+    /// or byte quota.
+    pub fn set(&self, key: &str, value: Option<String>) -> Result<()> {
+        let mut entries = self.lock();
+        let Some(value) = value else {
+            entries.remove(key);
+            return Ok(());
+        };
+
+        let existing = entries.get(key).map(|v| key.len() + v.len()).unwrap_or(0);
+        if existing == 0 && entries.len() >= self.max_keys {
+            return Err(PluginError::Sandboxed(format!(
+                "plugin state key quota exceeded (max: {} keys)",
+                self.max_keys
+            )));
+        }
+        let new_size = Self::size_of(&entries) - existing + key.len() + value.len();
+        if new_size > self.max_bytes {
+            return Err(PluginError::Sandboxed(format!(
+                "plugin state size quota exceeded ({} bytes, max: {} bytes)",
+                new_size, self.max_bytes
+            )));
+        }
+
+        entries.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Copy of all stored entries
+    pub fn snapshot(&self) -> PluginStateMap {
+        self.lock().clone()
+    }
+
+    /// Replace all entries, e.g. with state persisted by a previous run
+    pub fn restore(&self, state: PluginStateMap) -> Result<()> {
+        let size = Self::size_of(&state);
+        if state.len() > self.max_keys || size > self.max_bytes {
+            return Err(PluginError::Sandboxed(format!(
+                "persisted plugin state exceeds quota ({} keys, {} bytes)",
+                state.len(),
+                size
+            )));
+        }
+        *self.lock() = state;
+        Ok(())
+    }
+
+    /// Remove all entries
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Total bytes used by keys and values
+    pub fn used_bytes(&self) -> usize {
+        Self::size_of(&self.lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_set_remove() {
+        let state = PluginState::new(10, 1024);
+        assert_eq!(state.get("a"), None);
+        state.set("a", Some("1".to_string())).unwrap();
+        assert_eq!(state.get("a").as_deref(), Some("1"));
+        assert_eq!(state.used_bytes(), 2);
+        state.set("a", None).unwrap();
+        assert_eq!(state.get("a"), None);
+    }
+
+    #[test]
+    fn test_quotas() {
+        let state = PluginState::new(2, 10);
+        state.set("a", Some("1".to_string())).unwrap();
+        state.set("b", Some("2".to_string())).unwrap();
+        assert!(state.set("c", Some("3".to_string())).is_err());
+        // Overwriting an existing key does not count as a new key
+        state.set("a", Some("12345".to_string())).unwrap();
+        assert!(state.set("a", Some("123456789".to_string())).is_err());
+        assert_eq!(state.get("a").as_deref(), Some("12345"));
+    }
+
+    #[test]
+    fn test_clones_share_entries() {
+        let state = PluginState::new(10, 1024);
+        let host = state.clone();
+        state.set("k", Some("v".to_string())).unwrap();
+        assert_eq!(host.snapshot().get("k").map(String::as_str), Some("v"));
+        host.clear();
+        assert_eq!(state.get("k"), None);
+    }
+}
@@ -1,17 +1,147 @@
 use crate::error::{PluginError, Result};
-use crate::plugin_trait::{Plugin, PluginType};
-use crate::sandbox::SandboxConfig;
+use crate::plugin_trait::{PageContext, Plugin, PluginType, TransformOutcome};
+use crate::sandbox::{CallTimer, SandboxConfig, TimeLimit, WASM_PAGE_SIZE};
+use crate::state::PluginState;
+use awb_domain::warnings::Warning;
+use awb_storage::rule_library::content_hash;
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
 use tracing::debug;
+use wasmtime::component::{Component, HasSelf};
 use wasmtime::*;
 
+/// Typed bindings for the `awb:plugin` world in `wit/awb-plugin.wit`
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "plugin",
+    });
+}
+
 /// A plugin that executes WebAssembly modules to transform wikitext
 pub struct WasmPlugin {
     name: String,
     description: String,
+    version: String,
     engine: Engine,
-    module: Module,
+    code: WasmCode,
     config: SandboxConfig,
+    state: PluginState,
+}
+
+/// Compiled plugin code
+enum WasmCode {
+    /// A core module following the `alloc`/`transform` memory convention
+    Module(Module),
+    /// A component implementing the `awb:plugin` world, with its imports
+    /// and exports already checked
+    Component(bindings::PluginPre<HostState>),
+}
+
+/// Data made available to host functions during a WASM call
+struct HostState {
+    state: PluginState,
+    max_memory_bytes: usize,
+    memory_exceeded: bool,
+    time_limit_hit: Option<TimeLimit>,
+}
+
+impl ResourceLimiter for HostState {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if desired > self.max_memory_bytes {
+            self.memory_exceeded = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(desired <= MAX_TABLE_ELEMENTS)
+    }
+}
+
+/// How often a running module checks the sandbox time limits
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Advances an engine's epoch every [`EPOCH_TICK`] until dropped, so that
+/// long-running WASM code regularly stops to check its time limits
+struct EpochTicker {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EpochTicker {
+    fn start(engine: &Engine) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let engine = engine.clone();
+        let handle = std::thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                std::thread::park_timeout(EPOCH_TICK);
+                engine.increment_epoch();
+            }
+        });
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Upper bound on table elements a module may allocate
+const MAX_TABLE_ELEMENTS: usize = 10_000;
+
+/// Upper bound on strings read from guest memory by host functions
+const MAX_HOST_STRING: usize = 1024 * 1024;
+
+impl bindings::awb::plugin::store::Host for HostState {
+    fn get(&mut self, key: String) -> Option<String> {
+        self.state.get(&key)
+    }
+
+    fn set(&mut self, key: String, value: Option<String>) -> std::result::Result<(), String> {
+        self.state.set(&key, value).map_err(|e| e.to_string())
+    }
+}
+
+/// Whether `bytes` is a component rather than a core module, going by the
+/// layer field of the binary header
+fn is_component(bytes: &[u8]) -> bool {
+    bytes.len() >= 8 && bytes[..4] == *b"\0asm" && bytes[6..8] == [1, 0]
+}
+
+/// Read a UTF-8 string from the guest's exported memory
+fn read_guest_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Option<String> {
+    if ptr < 0 || len < 0 || len as usize > MAX_HOST_STRING {
+        return None;
+    }
+    let memory = caller.get_export("memory")?.into_memory()?;
+    let mut buf = vec![0u8; len as usize];
+    memory.read(&*caller, ptr as usize, &mut buf).ok()?;
+    String::from_utf8(buf).ok()
 }
 
 impl WasmPlugin {
@@ -46,42 +176,209 @@ impl WasmPlugin {
         Self::from_bytes(name, &wasm_bytes, config)
     }
 
-    /// Load a WASM plugin from bytes
+    /// Load a WASM plugin from bytes, either a core module or a component
+    /// implementing the `awb:plugin` world
     pub fn from_bytes(name: &str, wasm_bytes: &[u8], config: SandboxConfig) -> Result<Self> {
         // Configure the WASM engine with fuel consumption for resource limiting
         let mut engine_config = Config::new();
         engine_config.consume_fuel(true);
+        engine_config.epoch_interruption(true);
         engine_config.wasm_bulk_memory(true);
         engine_config.wasm_multi_memory(true);
+        if config.deterministic {
+            // The only nondeterminism wasm has without WASI imports
+            engine_config.cranelift_nan_canonicalization(true);
+            engine_config.relaxed_simd_deterministic(true);
+        }
 
         let engine = Engine::new(&engine_config)?;
-        let module = Module::from_binary(&engine, wasm_bytes)?;
+        let code = if is_component(wasm_bytes) {
+            WasmCode::Component(Self::prepare_component(&engine, wasm_bytes)?)
+        } else {
+            WasmCode::Module(Module::from_binary(&engine, wasm_bytes)?)
+        };
 
         debug!("Loaded WASM plugin: {}", name);
 
+        let state = PluginState::new(config.state_max_keys, config.state_max_bytes);
+
         Ok(Self {
             name: name.to_string(),
             description: format!("WASM plugin: {}", name),
+            version: content_hash(wasm_bytes),
             engine,
-            module,
+            code,
             config,
+            state,
         })
     }
 
-    /// Execute the WASM transform function
-    fn execute_transform(&self, input: &str) -> Result<String> {
-        let mut store = Store::new(&self.engine, ());
+    /// Compile a component and check it against the `awb:plugin` world.
+    ///
+    /// Only the `store` interface is provided, so a component importing
+    /// anything else (such as WASI) is rejected here.
+    fn prepare_component(
+        engine: &Engine,
+        wasm_bytes: &[u8],
+    ) -> Result<bindings::PluginPre<HostState>> {
+        let component = Component::from_binary(engine, wasm_bytes)?;
+        let mut linker = component::Linker::new(engine);
+        bindings::Plugin::add_to_linker::<_, HasSelf<_>>(&mut linker, |host| host)?;
+        linker
+            .instantiate_pre(&component)
+            .and_then(bindings::PluginPre::new)
+            .map_err(|e| {
+                PluginError::LoadFailed(format!(
+                    "WASM component does not implement the awb:plugin world: {}",
+                    e
+                ))
+            })
+    }
+
+    /// Register the `awb` host functions available to plugins.
+    ///
+    /// - `store_get(key_ptr, key_len, out_ptr, out_cap) -> i32`: copies up to
+    ///   `out_cap` bytes of the value to `out_ptr` and returns the full value
+    ///   length, or -1 if the key is not set.
+    /// - `store_set(key_ptr, key_len, val_ptr, val_len) -> i32`: stores the
+    ///   value (a negative `val_len` removes the key); returns 0 on success
+    ///   or -1 on invalid input or exceeded quota.
+    fn add_host_functions(linker: &mut Linker<HostState>) -> Result<()> {
+        linker.func_wrap(
+            "awb",
+            "store_get",
+            |mut caller: Caller<'_, HostState>,
+             key_ptr: i32,
+             key_len: i32,
+             out_ptr: i32,
+             out_cap: i32|
+             -> i32 {
+                let Some(key) = read_guest_string(&mut caller, key_ptr, key_len) else {
+                    return -1;
+                };
+                let Some(value) = caller.data().state.get(&key) else {
+                    return -1;
+                };
+                let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+                    return -1;
+                };
+                let n = value.len().min(out_cap.max(0) as usize);
+                if out_ptr < 0
+                    || memory
+                        .write(&mut caller, out_ptr as usize, &value.as_bytes()[..n])
+                        .is_err()
+                {
+                    return -1;
+                }
+                value.len() as i32
+            },
+        )?;
+
+        linker.func_wrap(
+            "awb",
+            "store_set",
+            |mut caller: Caller<'_, HostState>,
+             key_ptr: i32,
+             key_len: i32,
+             val_ptr: i32,
+             val_len: i32|
+             -> i32 {
+                let Some(key) = read_guest_string(&mut caller, key_ptr, key_len) else {
+                    return -1;
+                };
+                let value = if val_len < 0 {
+                    None
+                } else {
+                    match read_guest_string(&mut caller, val_ptr, val_len) {
+                        Some(v) => Some(v),
+                        None => return -1,
+                    }
+                };
+                match caller.data().state.set(&key, value) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        debug!("WASM store_set rejected: {}", e);
+                        -1
+                    }
+                }
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Report fuel exhaustion, denied memory growth and time limits as
+    /// resource limit errors rather than generic WASM failures
+    fn classify_error(&self, store: &Store<HostState>, e: wasmtime::Error) -> PluginError {
+        if let Some(exceeded) = store.data().time_limit_hit {
+            return exceeded.error(&self.config);
+        }
+        if matches!(e.downcast_ref::<Trap>(), Some(Trap::OutOfFuel)) {
+            return PluginError::ResourceLimit("WASM fuel exhausted".to_string());
+        }
+        if store.data().memory_exceeded {
+            return PluginError::ResourceLimit(format!(
+                "WASM memory limit exceeded (max: {} bytes)",
+                store.data().max_memory_bytes
+            ));
+        }
+        PluginError::Wasm(e)
+    }
+
+    /// Create a store enforcing the sandbox's memory, time and fuel limits
+    fn new_store(&self) -> Result<Store<HostState>> {
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                state: self.state.clone(),
+                max_memory_bytes: self.config.wasm_max_memory_pages as usize * WASM_PAGE_SIZE,
+                memory_exceeded: false,
+                time_limit_hit: None,
+            },
+        );
+        store.limiter(|host| host as &mut dyn ResourceLimiter);
+
+        // Check the wall-clock and CPU time limits on every epoch tick. The
+        // callback runs on this thread, so only the plugin's own work counts
+        // as CPU time.
+        let timer = CallTimer::start(&self.config);
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |mut ctx| match timer.exceeded() {
+            Some(exceeded) => {
+                ctx.data_mut().time_limit_hit = Some(exceeded);
+                Ok(UpdateDeadline::Interrupt)
+            }
+            None => Ok(UpdateDeadline::Continue(1)),
+        });
 
         // Set fuel limit for execution
         store.set_fuel(self.config.wasm_fuel).map_err(|e| {
             PluginError::ExecutionFailed(format!("Failed to set fuel limit: {}", e))
         })?;
 
-        // Create a linker and add WASI if needed (minimal for now)
-        let linker = Linker::new(&self.engine);
+        Ok(store)
+    }
+
+    fn log_fuel(&self, store: &Store<HostState>) {
+        if let Ok(remaining) = store.get_fuel() {
+            let consumed = self.config.wasm_fuel.saturating_sub(remaining);
+            debug!("WASM plugin '{}' consumed {} fuel", self.name, consumed);
+        }
+    }
+
+    /// Execute the transform function of a core module
+    fn execute_transform(&self, module: &Module, input: &str) -> Result<String> {
+        let mut store = self.new_store()?;
+        let _ticker = EpochTicker::start(&self.engine);
+
+        // Create a linker with the AWB host functions only (no WASI)
+        let mut linker = Linker::new(&self.engine);
+        Self::add_host_functions(&mut linker)?;
 
         // Instantiate the module
-        let instance = linker.instantiate(&mut store, &self.module)?;
+        let instance = linker
+            .instantiate(&mut store, module)
+            .map_err(|e| self.classify_error(&store, e))?;
 
         // Get the memory export
         let memory = instance.get_memory(&mut store, "memory").ok_or_else(|| {
@@ -111,7 +408,9 @@ impl WasmPlugin {
         // Allocate memory for input string
         let input_bytes = input.as_bytes();
         let input_len = input_bytes.len() as i32;
-        let input_ptr = alloc.call(&mut store, input_len)?;
+        let input_ptr = alloc
+            .call(&mut store, input_len)
+            .map_err(|e| self.classify_error(&store, e))?;
 
         // Write input string to WASM memory
         memory
@@ -119,7 +418,9 @@ impl WasmPlugin {
             .map_err(|e| PluginError::ExecutionFailed(format!("Memory write failed: {}", e)))?;
 
         // Call the transform function
-        let result_ptr = transform.call(&mut store, (input_ptr, input_len))?;
+        let result_ptr = transform
+            .call(&mut store, (input_ptr, input_len))
+            .map_err(|e| self.classify_error(&store, e))?;
 
         // Read the result string from WASM memory
         // The WASM module should return a pointer to a length-prefixed string
@@ -149,14 +450,53 @@ impl WasmPlugin {
         // Convert bytes to string
         let result = String::from_utf8(result_bytes)?;
 
-        // Get remaining fuel to calculate consumption
-        if let Ok(remaining) = store.get_fuel() {
-            let consumed = self.config.wasm_fuel.saturating_sub(remaining);
-            debug!("WASM plugin '{}' consumed {} fuel", self.name, consumed);
-        }
+        self.log_fuel(&store);
 
         Ok(result)
     }
+
+    /// Execute the typed `transform` export of a component
+    fn execute_component(
+        &self,
+        pre: &bindings::PluginPre<HostState>,
+        input: &str,
+        page: &PageContext,
+    ) -> Result<TransformOutcome> {
+        let mut store = self.new_store()?;
+        let _ticker = EpochTicker::start(&self.engine);
+
+        let plugin = pre
+            .instantiate(&mut store)
+            .map_err(|e| self.classify_error(&store, e))?;
+        let page = bindings::PageContext {
+            title: page.title.clone(),
+            namespace: page.namespace,
+            is_redirect: page.is_redirect,
+            revid: page.revid,
+            categories: page.categories.clone(),
+        };
+        let outcome = plugin
+            .call_transform(&mut store, input, &page)
+            .map_err(|e| self.classify_error(&store, e))?
+            .map_err(PluginError::ExecutionFailed)?;
+
+        self.log_fuel(&store);
+
+        Ok(TransformOutcome {
+            text: outcome.text,
+            summary_fragment: outcome.summary.filter(|s| !s.trim().is_empty()),
+            warnings: outcome
+                .warnings
+                .into_iter()
+                .map(|message| Warning::Plugin {
+                    plugin: self.name.clone(),
+                    message,
+                })
+                .collect(),
+            skip_page: outcome.skip,
+            metadata: outcome.metadata.into_iter().collect(),
+        })
+    }
 }
 
 impl Plugin for WasmPlugin {
@@ -169,7 +509,31 @@ impl Plugin for WasmPlugin {
     }
 
     fn transform(&self, input: &str) -> Result<String> {
-        self.execute_transform(input)
+        match &self.code {
+            WasmCode::Module(module) => self.execute_transform(module, input),
+            WasmCode::Component(_) => self.transform_outcome(input).map(|outcome| outcome.text),
+        }
+    }
+
+    fn transform_outcome(&self, input: &str) -> Result<TransformOutcome> {
+        self.transform_with_context(input, &PageContext::default())
+    }
+
+    fn transform_with_context(&self, input: &str, page: &PageContext) -> Result<TransformOutcome> {
+        match &self.code {
+            WasmCode::Module(module) => self
+                .execute_transform(module, input)
+                .map(TransformOutcome::from),
+            WasmCode::Component(pre) => self.execute_component(pre, input, page),
+        }
+    }
+
+    fn state(&self) -> Option<&PluginState> {
+        Some(&self.state)
+    }
+
+    fn version(&self) -> Option<&str> {
+        Some(&self.version)
     }
 
     fn plugin_type(&self) -> PluginType {
@@ -319,6 +683,139 @@ mod tests {
             result.is_err(),
             "Expected fuel exhaustion error, but transform succeeded"
         );
+        assert!(matches!(result, Err(PluginError::ResourceLimit(_))));
+    }
+
+    fn create_spinning_wasm() -> Vec<u8> {
+        wat::parse_str(
+            r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param $size i32) (result i32)
+                    (i32.const 1024)
+                )
+                (func (export "transform") (param $ptr i32) (param $len i32) (result i32)
+                    (loop $spin (br $spin))
+                    (i32.const 0)
+                )
+            )
+        "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_wasm_wall_clock_timeout() {
+        let config = SandboxConfig {
+            timeout: std::time::Duration::from_millis(100),
+            cpu_time: std::time::Duration::from_secs(60),
+            ..SandboxConfig::unlimited()
+        };
+        let plugin = WasmPlugin::from_bytes("spin", &create_spinning_wasm(), config).unwrap();
+        assert!(matches!(
+            plugin.transform("test"),
+            Err(PluginError::Timeout(1))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wasm_cpu_time_limit() {
+        let config = SandboxConfig {
+            timeout: std::time::Duration::from_secs(60),
+            cpu_time: std::time::Duration::from_millis(100),
+            ..SandboxConfig::unlimited()
+        };
+        let plugin = WasmPlugin::from_bytes("spin", &create_spinning_wasm(), config).unwrap();
+        let err = plugin.transform("test").unwrap_err();
+        assert!(matches!(err, PluginError::ResourceLimit(_)));
+        assert!(err.to_string().contains("CPU time limit exceeded"));
+    }
+
+    #[test]
+    fn test_wasm_memory_growth_limit() {
+        // Traps if growing memory by 100 pages (6.4MB) is refused
+        let wat = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "transform") (param i32 i32) (result i32)
+                    (if (i32.eq (memory.grow (i32.const 100)) (i32.const -1))
+                        (then unreachable))
+                    (i32.store (i32.const 2048) (i32.const 0))
+                    (i32.const 2048)
+                )
+            )
+        "#;
+        let wasm_bytes = wat::parse_str(wat).unwrap();
+
+        let config = SandboxConfig {
+            wasm_max_memory_pages: 16,
+            ..Default::default()
+        };
+        let plugin = WasmPlugin::from_bytes("grower", &wasm_bytes, config).unwrap();
+        assert!(matches!(
+            plugin.transform("test"),
+            Err(PluginError::ResourceLimit(_))
+        ));
+
+        // The same module succeeds when the cap allows the growth
+        let plugin =
+            WasmPlugin::from_bytes("grower", &wasm_bytes, SandboxConfig::default()).unwrap();
+        assert_eq!(plugin.transform("test").unwrap(), "");
+    }
+
+    #[test]
+    fn test_wasm_initial_memory_over_limit() {
+        let wat = r#"
+            (module
+                (memory (export "memory") 64)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "transform") (param i32 i32) (result i32) (i32.const 0))
+            )
+        "#;
+        let wasm_bytes = wat::parse_str(wat).unwrap();
+        let config = SandboxConfig {
+            wasm_max_memory_pages: 8,
+            ..Default::default()
+        };
+        let plugin = WasmPlugin::from_bytes("big", &wasm_bytes, config).unwrap();
+        assert!(matches!(
+            plugin.transform("test"),
+            Err(PluginError::ResourceLimit(_))
+        ));
+    }
+
+    #[test]
+    fn test_wasm_store_host_functions() {
+        // Counts invocations in the "n" key, storing the count as one byte
+        let wat = r#"
+            (module
+                (import "awb" "store_get" (func $get (param i32 i32 i32 i32) (result i32)))
+                (import "awb" "store_set" (func $set (param i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "n")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "transform") (param $ptr i32) (param $len i32) (result i32)
+                    ;; Default count '0' at offset 16, overwritten if the key exists
+                    (i32.store8 (i32.const 16) (i32.const 48))
+                    (drop (call $get (i32.const 0) (i32.const 1) (i32.const 16) (i32.const 1)))
+                    (i32.store8 (i32.const 16) (i32.add (i32.load8_u (i32.const 16)) (i32.const 1)))
+                    (drop (call $set (i32.const 0) (i32.const 1) (i32.const 16) (i32.const 1)))
+                    ;; Return the count as a length-prefixed string
+                    (i32.store (i32.const 32) (i32.const 1))
+                    (i32.store8 (i32.const 36) (i32.load8_u (i32.const 16)))
+                    (i32.const 32)
+                )
+            )
+        "#;
+        let wasm_bytes = wat::parse_str(wat).unwrap();
+        let plugin =
+            WasmPlugin::from_bytes("counter", &wasm_bytes, SandboxConfig::default()).unwrap();
+
+        assert_eq!(plugin.transform("x").unwrap(), "1");
+        assert_eq!(plugin.transform("x").unwrap(), "2");
+        assert_eq!(plugin.state().unwrap().get("n").as_deref(), Some("2"));
     }
 
     #[test]
@@ -379,4 +876,177 @@ mod tests {
             }
         }
     }
+
+    /// A component implementing the `awb:plugin` world by hand.
+    ///
+    /// It returns the text of the page it saw last (or the input on the
+    /// first call) and remembers the input in the store under "last". The
+    /// title becomes the summary, redirects are skipped, every page gets
+    /// `kind=component` metadata and an empty page is an error.
+    fn create_test_component() -> Vec<u8> {
+        let wat = r#"
+            (component
+                (import "awb:plugin/store@0.1.0" (instance $store
+                    (export "get" (func (param "key" string) (result (option string))))
+                    (export "set" (func (param "key" string) (param "value" (option string))
+                        (result (result (error string)))))
+                ))
+
+                (core module $libc
+                    (memory (export "memory") 1)
+                    (global $heap (mut i32) (i32.const 1024))
+                    (func (export "cabi_realloc") (param i32 i32) (param $align i32) (param $size i32)
+                        (result i32)
+                        (local $ptr i32)
+                        (local.set $ptr (i32.and
+                            (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+                            (i32.sub (i32.const 0) (local.get $align))))
+                        (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+                        (local.get $ptr)
+                    )
+                )
+                (core instance $libc (instantiate $libc))
+
+                (core func $get (canon lower (func $store "get")
+                    (memory $libc "memory") (realloc (func $libc "cabi_realloc"))))
+                (core func $set (canon lower (func $store "set")
+                    (memory $libc "memory") (realloc (func $libc "cabi_realloc"))))
+
+                (core module $main
+                    (import "libc" "memory" (memory 1))
+                    (import "store" "get" (func $get (param i32 i32 i32)))
+                    (import "store" "set" (func $set (param i32 i32 i32 i32 i32 i32)))
+                    (data (i32.const 16) "kind")
+                    (data (i32.const 32) "component")
+                    (data (i32.const 48) "empty page")
+                    (data (i32.const 64) "last")
+
+                    ;; The result<outcome, string> is written at 256: the
+                    ;; case at 256 and its payload from 260
+                    (func (export "transform")
+                        (param $text i32) (param $text_len i32)
+                        (param $title i32) (param $title_len i32)
+                        (param $namespace i32) (param $redirect i32)
+                        (param $has_revid i32) (param $revid i64)
+                        (param $categories i32) (param $categories_len i32)
+                        (result i32)
+                        (if (i32.eqz (local.get $text_len))
+                            (then
+                                (i32.store8 (i32.const 256) (i32.const 1))
+                                (i32.store (i32.const 260) (i32.const 48))
+                                (i32.store (i32.const 264) (i32.const 10))
+                                (return (i32.const 256))))
+
+                        ;; Previous text into 128, then remember this one
+                        (call $get (i32.const 64) (i32.const 4) (i32.const 128))
+                        (call $set (i32.const 64) (i32.const 4)
+                            (i32.const 1) (local.get $text) (local.get $text_len) (i32.const 144))
+
+                        (i32.store8 (i32.const 256) (i32.const 0))
+                        (if (i32.load8_u (i32.const 128))
+                            (then
+                                (i32.store (i32.const 260) (i32.load (i32.const 132)))
+                                (i32.store (i32.const 264) (i32.load (i32.const 136))))
+                            (else
+                                (i32.store (i32.const 260) (local.get $text))
+                                (i32.store (i32.const 264) (local.get $text_len))))
+                        ;; summary: some(title)
+                        (i32.store8 (i32.const 268) (i32.const 1))
+                        (i32.store (i32.const 272) (local.get $title))
+                        (i32.store (i32.const 276) (local.get $title_len))
+                        ;; warnings: none
+                        (i32.store (i32.const 280) (i32.const 0))
+                        (i32.store (i32.const 284) (i32.const 0))
+                        ;; skip: is-redirect
+                        (i32.store8 (i32.const 288) (local.get $redirect))
+                        ;; metadata: one (key, value) pair at 320
+                        (i32.store (i32.const 292) (i32.const 320))
+                        (i32.store (i32.const 296) (i32.const 1))
+                        (i32.store (i32.const 320) (i32.const 16))
+                        (i32.store (i32.const 324) (i32.const 4))
+                        (i32.store (i32.const 328) (i32.const 32))
+                        (i32.store (i32.const 332) (i32.const 9))
+                        (i32.const 256)
+                    )
+                )
+                (core instance $main (instantiate $main
+                    (with "libc" (instance $libc))
+                    (with "store" (instance
+                        (export "get" (func $get))
+                        (export "set" (func $set))))
+                ))
+
+                (type $page-context (record
+                    (field "title" string)
+                    (field "namespace" s32)
+                    (field "is-redirect" bool)
+                    (field "revid" (option u64))
+                    (field "categories" (list string))))
+                (export $page "page-context" (type $page-context))
+                (type $outcome (record
+                    (field "text" string)
+                    (field "summary" (option string))
+                    (field "warnings" (list string))
+                    (field "skip" bool)
+                    (field "metadata" (list (tuple string string)))))
+                (export $result "outcome" (type $outcome))
+
+                (func (export "transform")
+                    (param "text" string) (param "page" $page)
+                    (result (result $result (error string)))
+                    (canon lift (core func $main "transform")
+                        (memory $libc "memory") (realloc (func $libc "cabi_realloc"))))
+            )
+        "#;
+        wat::parse_str(wat).unwrap()
+    }
+
+    #[test]
+    fn test_wasm_component_plugin() {
+        let plugin = WasmPlugin::from_bytes(
+            "component",
+            &create_test_component(),
+            SandboxConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(plugin.plugin_type(), PluginType::Wasm);
+
+        let page = PageContext {
+            title: "Example".to_string(),
+            is_redirect: true,
+            ..PageContext::default()
+        };
+        let outcome = plugin.transform_with_context("first", &page).unwrap();
+        assert_eq!(outcome.text, "first");
+        assert_eq!(outcome.summary_fragment.as_deref(), Some("Example"));
+        assert!(outcome.skip_page);
+        assert!(outcome.warnings.is_empty());
+        assert_eq!(
+            outcome.metadata.get("kind").map(String::as_str),
+            Some("component")
+        );
+
+        // The store persists across pages
+        assert_eq!(plugin.transform("second").unwrap(), "first");
+        assert_eq!(
+            plugin.state().unwrap().get("last").as_deref(),
+            Some("second")
+        );
+
+        let err = plugin.transform("").unwrap_err();
+        assert!(matches!(err, PluginError::ExecutionFailed(ref m) if m == "empty page"));
+    }
+
+    #[test]
+    fn test_wasm_component_must_implement_world() {
+        let wat = r#"
+            (component
+                (import "wasi:cli/environment@0.2.0" (instance
+                    (export "get-arguments" (func (result (list string))))))
+            )
+        "#;
+        let wasm_bytes = wat::parse_str(wat).unwrap();
+        let result = WasmPlugin::from_bytes("wasi", &wasm_bytes, SandboxConfig::default());
+        assert!(matches!(result, Err(PluginError::LoadFailed(_))));
+    }
 }
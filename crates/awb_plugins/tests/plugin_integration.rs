@@ -154,6 +154,7 @@ fn test_lua_plugin_timeout() {
         memory_limit: 10 * 1024 * 1024, // 10MB
         instruction_limit: Some(1_000_000),
         wasm_fuel: 10_000_000,
+        ..SandboxConfig::default()
     };
 
     let plugin = LuaPlugin::from_string("infinite_loop", script, config).unwrap();
@@ -243,7 +244,7 @@ fn test_plugin_manager_enable_disable() {
     assert_eq!(result, "test2");
 
     // Re-enable plugin1
-    manager.enable_plugin("plugin1");
+    manager.enable_plugin("plugin1").unwrap();
     let result = manager.apply_all("test").unwrap();
     assert_eq!(result, "test12");
 }
@@ -427,6 +428,7 @@ fn test_sandbox_config_custom_timeout() {
         memory_limit: 5 * 1024 * 1024, // 5MB
         instruction_limit: Some(100_000),
         wasm_fuel: 1_000_000,
+        ..SandboxConfig::default()
     };
 
     let script = r#"
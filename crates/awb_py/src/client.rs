@@ -0,0 +1,164 @@
+use crate::to_py;
+use awb_domain::profile::ThrottlePolicy;
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::{Title, Watchlist};
+use awb_mw_api::client::{EditRequest, MediaWikiClient, ReqwestMwClient};
+use awb_mw_api::error::MwApiError;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::future::Future;
+use std::sync::OnceLock;
+
+create_exception!(
+    awb_py,
+    ApiError,
+    PyException,
+    "A MediaWiki API call failed."
+);
+
+fn api_error(error: MwApiError) -> PyErr {
+    ApiError::new_err(error.to_string())
+}
+
+/// MediaWiki API client. Calls block, releasing the GIL while they wait.
+#[pyclass(module = "awb_py")]
+pub(crate) struct Client {
+    inner: ReqwestMwClient,
+    runtime: tokio::runtime::Runtime,
+    /// Namespace names and case rules, fetched on first use
+    site: OnceLock<SiteInfo>,
+}
+
+impl Client {
+    fn block_on<F>(&self, py: Python<'_>, future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        py.allow_threads(|| self.runtime.block_on(future))
+    }
+
+    /// Read a title with the wiki's namespace names, so `Talk:Foo` is in
+    /// the talk namespace
+    fn parse_title(&self, py: Python<'_>, raw: &str) -> PyResult<Title> {
+        let site = match self.site.get() {
+            Some(site) => site,
+            None => {
+                let site = self
+                    .block_on(py, self.inner.fetch_siteinfo())
+                    .map_err(api_error)?;
+                self.site.get_or_init(|| site)
+            }
+        };
+        Title::parse(site, raw)
+            .map_err(|e| PyValueError::new_err(format!("Invalid title '{}': {}", raw, e)))
+    }
+}
+
+#[pymethods]
+impl Client {
+    #[new]
+    fn new(api_url: &str) -> PyResult<Self> {
+        let api_url = url::Url::parse(api_url)
+            .map_err(|e| PyValueError::new_err(format!("Invalid API URL: {}", e)))?;
+        let inner = ReqwestMwClient::new(api_url, ThrottlePolicy::default()).map_err(api_error)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| PyException::new_err(e.to_string()))?;
+        Ok(Self {
+            inner,
+            runtime,
+            site: OnceLock::new(),
+        })
+    }
+
+    /// Log in with a bot password and fetch an edit token
+    fn login_bot_password(&self, py: Python<'_>, username: &str, password: &str) -> PyResult<()> {
+        self.block_on(py, async {
+            self.inner.login_bot_password(username, password).await?;
+            self.inner.fetch_csrf_token().await?;
+            Ok::<_, MwApiError>(())
+        })
+        .map_err(api_error)
+    }
+
+    /// Fetch a page as a dict. The namespace is read from the title's
+    /// prefix, e.g. `Talk:Foo`.
+    fn get_page(&self, py: Python<'_>, title: &str) -> PyResult<PyObject> {
+        let title = self.parse_title(py, title)?;
+        let page = self
+            .block_on(py, self.inner.get_page(&title))
+            .map_err(api_error)?;
+        to_py(py, &page)
+    }
+
+    /// Save a page. `base_timestamp` is the timestamp of the revision the
+    /// edit is based on, as returned by `get_page`.
+    #[pyo3(signature = (title, text, summary, base_timestamp, minor = false, bot = true))]
+    #[allow(clippy::too_many_arguments)]
+    fn edit_page(
+        &self,
+        py: Python<'_>,
+        title: &str,
+        text: &str,
+        summary: &str,
+        base_timestamp: &str,
+        minor: bool,
+        bot: bool,
+    ) -> PyResult<PyObject> {
+        let edit = EditRequest {
+            title: self.parse_title(py, title)?,
+            text: text.to_string(),
+            summary: summary.to_string(),
+            minor,
+            bot,
+            base_timestamp: base_timestamp.to_string(),
+            start_timestamp: chrono::Utc::now().to_rfc3339(),
+            section: None,
+            tags: Vec::new(),
+            watchlist: Watchlist::default(),
+        };
+        let response = self
+            .block_on(py, self.inner.edit_page(&edit))
+            .map_err(api_error)?;
+
+        let dict = PyDict::new_bound(py);
+        dict.set_item("result", response.result)?;
+        dict.set_item("new_revid", response.new_revid)?;
+        dict.set_item("new_timestamp", response.new_timestamp)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Render wikitext to HTML
+    fn parse(&self, py: Python<'_>, wikitext: &str, title: &str) -> PyResult<String> {
+        let title = self.parse_title(py, title)?;
+        self.block_on(py, self.inner.parse_wikitext(wikitext, &title))
+            .map_err(api_error)
+    }
+
+    #[pyo3(signature = (category, limit = 500))]
+    fn category_members(
+        &self,
+        py: Python<'_>,
+        category: &str,
+        limit: u32,
+    ) -> PyResult<Vec<String>> {
+        self.block_on(py, self.inner.list_category_members(category, limit))
+            .map_err(api_error)
+    }
+
+    #[pyo3(signature = (query, limit = 500))]
+    fn search(&self, py: Python<'_>, query: &str, limit: u32) -> PyResult<Vec<String>> {
+        self.block_on(py, self.inner.search_pages(query, limit))
+            .map_err(api_error)
+    }
+
+    #[pyo3(signature = (title, limit = 500))]
+    fn backlinks(&self, py: Python<'_>, title: &str, limit: u32) -> PyResult<Vec<String>> {
+        self.block_on(py, self.inner.get_backlinks(title, limit))
+            .map_err(api_error)
+    }
+}
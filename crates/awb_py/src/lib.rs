@@ -0,0 +1,183 @@
+//! Python bindings for the AWB-RS engine.
+//!
+//! Exposes the transformation pipeline, the general fix registry, diff
+//! utilities and the MediaWiki client as the `awb_py` module. Structured
+//! values (edit plans, diffs, fix metadata, pages) are returned as plain
+//! dicts and lists.
+
+mod client;
+
+use awb_domain::rules::RuleSet;
+use awb_domain::types::*;
+use awb_engine::diff_engine;
+use awb_engine::fix_config::FixConfig;
+use awb_engine::general_fixes::FixContext;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+
+/// Convert a serializable value to Python objects by way of JSON
+pub(crate) fn to_py<T: serde::Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py
+        .import_bound("json")?
+        .call_method1("loads", (json,))?
+        .unbind())
+}
+
+fn from_json<T: DeserializeOwned>(json: &str, what: &str) -> PyResult<T> {
+    serde_json::from_str(json)
+        .map_err(|e| PyValueError::new_err(format!("Invalid {} JSON: {}", what, e)))
+}
+
+/// A page holding `wikitext`, for running the engine outside a wiki
+fn page(title: &str, namespace: i32, wikitext: &str) -> PageContent {
+    PageContent {
+        page_id: PageId(0),
+        title: Title::new(Namespace(namespace), title),
+        revision: RevisionId(0),
+        timestamp: chrono::Utc::now(),
+        wikitext: wikitext.to_string(),
+        size_bytes: wikitext.len() as u64,
+        is_redirect: false,
+        protection: ProtectionInfo::default(),
+        properties: PageProperties::default(),
+    }
+}
+
+/// The general fix modules, in run order.
+#[pyclass(module = "awb_py")]
+struct FixRegistry {
+    inner: awb_engine::general_fixes::FixRegistry,
+}
+
+#[pymethods]
+impl FixRegistry {
+    #[new]
+    fn new() -> Self {
+        Self {
+            inner: awb_engine::general_fixes::FixRegistry::with_defaults(),
+        }
+    }
+
+    /// Metadata of every fix as a list of dicts, in run order
+    fn fix_infos(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.inner.fix_infos())
+    }
+
+    fn known_ids(&self) -> Vec<String> {
+        self.inner
+            .fix_infos()
+            .into_iter()
+            .map(|info| info.id)
+            .collect()
+    }
+
+    /// IDs of the fixes a serialized `FixConfig` allows to run
+    fn enabled_ids(&self, fix_config_json: &str) -> PyResult<Vec<String>> {
+        let config: FixConfig = from_json(fix_config_json, "fix config")?;
+        let ids = self
+            .inner
+            .enabled_ids(&config)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(ids.into_iter().collect())
+    }
+
+    /// Run the fixes in `enabled_ids` over `text`, returning the IDs of the
+    /// fixes that changed it and the new text
+    #[pyo3(signature = (text, title, enabled_ids, namespace = 0, is_redirect = false))]
+    fn apply(
+        &self,
+        py: Python<'_>,
+        text: &str,
+        title: &str,
+        enabled_ids: HashSet<String>,
+        namespace: i32,
+        is_redirect: bool,
+    ) -> (Vec<String>, String) {
+        let ctx = FixContext {
+            title: Title::new(Namespace(namespace), title),
+            namespace: Namespace(namespace),
+            is_redirect,
+            revision: None,
+            cancel: Default::default(),
+        };
+        py.allow_threads(|| self.inner.apply_all_returning_ids(text, &ctx, &enabled_ids))
+    }
+}
+
+/// Find-and-replace rules plus general fixes, as run on each page.
+#[pyclass(module = "awb_py")]
+struct TransformEngine {
+    inner: awb_engine::transform::TransformEngine,
+}
+
+#[pymethods]
+impl TransformEngine {
+    /// `rules_json` is a serialized `RuleSet`. General fixes run as selected
+    /// by `fix_config_json` (a serialized `FixConfig`), or not at all when it
+    /// is omitted.
+    #[new]
+    #[pyo3(signature = (rules_json = None, fix_config_json = None))]
+    fn new(rules_json: Option<&str>, fix_config_json: Option<&str>) -> PyResult<Self> {
+        let rule_set = match rules_json {
+            Some(json) => from_json(json, "rules")?,
+            None => RuleSet::new(),
+        };
+        let registry = awb_engine::general_fixes::FixRegistry::with_defaults();
+        let enabled_fixes = match fix_config_json {
+            Some(json) => {
+                let config: FixConfig = from_json(json, "fix config")?;
+                registry
+                    .enabled_ids(&config)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?
+            }
+            None => HashSet::new(),
+        };
+        let inner = awb_engine::transform::TransformEngine::new(&rule_set, registry, enabled_fixes)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Transform a page's wikitext, returning the edit plan as a dict with
+    /// `new_wikitext`, `rules_applied`, `fixes_applied`, `diff_ops`,
+    /// `summary`, `warnings` and `is_cosmetic_only`
+    #[pyo3(signature = (title, wikitext, namespace = 0))]
+    fn apply(
+        &self,
+        py: Python<'_>,
+        title: &str,
+        wikitext: &str,
+        namespace: i32,
+    ) -> PyResult<PyObject> {
+        let page = page(title, namespace, wikitext);
+        let plan = py.allow_threads(|| self.inner.apply(&page));
+        to_py(py, &plan)
+    }
+}
+
+/// Diff two texts, returning the list of diff operations
+#[pyfunction]
+fn compute_diff(py: Python<'_>, old: &str, new: &str) -> PyResult<PyObject> {
+    to_py(py, &diff_engine::compute_diff(old, new))
+}
+
+/// Diff two texts in unified format
+#[pyfunction]
+#[pyo3(signature = (old, new, context_lines = 3))]
+fn unified_diff(old: &str, new: &str, context_lines: usize) -> String {
+    diff_engine::to_unified(&diff_engine::compute_diff(old, new), context_lines)
+}
+
+#[pymodule]
+fn awb_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<TransformEngine>()?;
+    m.add_class::<FixRegistry>()?;
+    m.add_class::<client::Client>()?;
+    m.add("ApiError", m.py().get_type_bound::<client::ApiError>())?;
+    m.add_function(wrap_pyfunction!(compute_diff, m)?)?;
+    m.add_function(wrap_pyfunction!(unified_diff, m)?)?;
+    Ok(())
+}
@@ -1,8 +1,12 @@
+use crate::redaction::register_secret;
 use fs2::FileExt;
 use keyring::Entry;
 use std::path::PathBuf;
+use std::str::FromStr;
 use thiserror::Error;
 
+const OAUTH_TOKEN_SUFFIX: &str = "_oauth_token";
+
 #[derive(Debug, Error)]
 pub enum CredentialError {
     #[error("Credential not found for profile {0}")]
@@ -15,6 +19,10 @@ pub enum CredentialError {
     Io(#[from] std::io::Error),
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[error("Unknown credential backend '{0}': expected memory|file|keyring")]
+    UnknownBackend(String),
+    #[error("Credential for profile {0} did not read back intact")]
+    VerificationFailed(String),
 }
 
 /// Reject writes to symlink targets to prevent symlink swap attacks.
@@ -42,16 +50,98 @@ pub trait CredentialPort: Send + Sync {
 
     /// Store OAuth tokens (stored as JSON)
     fn get_oauth_token(&self, profile_id: &str) -> Result<String, CredentialError> {
-        self.get_password(&format!("{}_oauth_token", profile_id))
+        self.get_password(&format!("{}{}", profile_id, OAUTH_TOKEN_SUFFIX))
     }
 
     fn set_oauth_token(&self, profile_id: &str, token_json: &str) -> Result<(), CredentialError> {
-        self.set_password(&format!("{}_oauth_token", profile_id), token_json)
+        self.set_password(&format!("{}{}", profile_id, OAUTH_TOKEN_SUFFIX), token_json)
     }
 
     fn delete_oauth_token(&self, profile_id: &str) -> Result<(), CredentialError> {
-        self.delete_password(&format!("{}_oauth_token", profile_id))
+        self.delete_password(&format!("{}{}", profile_id, OAUTH_TOKEN_SUFFIX))
+    }
+
+    /// Profiles with a stored password or OAuth token. The OS keychain
+    /// cannot enumerate its entries, so not every store supports this.
+    fn profile_ids(&self) -> Result<Vec<String>, CredentialError> {
+        Err(CredentialError::Backend(
+            "this store cannot list its entries".into(),
+        ))
+    }
+
+    /// Remove the password and OAuth token of each profile, e.g. once they
+    /// have been moved to another store
+    fn wipe(&self, profile_ids: &[String]) -> Result<(), CredentialError> {
+        for id in profile_ids {
+            self.delete_password(id)?;
+            self.delete_oauth_token(id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where credentials are kept, selected by the `credential_backend` preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CredentialBackend {
+    /// Process memory only; nothing survives a restart
+    InMemory,
+    /// `~/.awb-rs/credentials.json`, readable by the owner only
+    File,
+    /// The OS keychain
+    Keyring,
+}
+
+impl CredentialBackend {
+    pub const ALL: [Self; 3] = [Self::InMemory, Self::File, Self::Keyring];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::InMemory => "memory",
+            Self::File => "file",
+            Self::Keyring => "keyring",
+        }
     }
+
+    /// Open the store this backend names
+    pub fn open(self) -> Result<Box<dyn CredentialPort>, CredentialError> {
+        Ok(match self {
+            Self::InMemory => Box::new(InMemoryCredentialStore::new()),
+            Self::File => Box::new(FileCredentialStore::new()?),
+            Self::Keyring => Box::new(KeyringCredentialStore::new()),
+        })
+    }
+}
+
+impl std::fmt::Display for CredentialBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CredentialBackend {
+    type Err = CredentialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|backend| backend.as_str() == s)
+            .ok_or_else(|| CredentialError::UnknownBackend(s.to_string()))
+    }
+}
+
+/// Profile IDs behind a set of store keys, with OAuth token keys folded into
+/// their profile
+fn profile_ids_from_keys<'a>(keys: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut ids: Vec<String> = keys
+        .map(|key| {
+            key.strip_suffix(OAUTH_TOKEN_SUFFIX)
+                .unwrap_or(key)
+                .to_string()
+        })
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
 }
 
 /// In-memory credential store for testing.
@@ -80,6 +170,7 @@ impl CredentialPort for InMemoryCredentialStore {
             .map_err(|_| CredentialError::Backend("lock poisoned".into()))?
             .get(profile_id)
             .cloned()
+            .inspect(|secret| register_secret(secret))
             .ok_or_else(|| CredentialError::NotFound(profile_id.to_string()))
     }
     fn set_password(&self, profile_id: &str, password: &str) -> Result<(), CredentialError> {
@@ -96,6 +187,14 @@ impl CredentialPort for InMemoryCredentialStore {
             .remove(profile_id);
         Ok(())
     }
+
+    fn profile_ids(&self) -> Result<Vec<String>, CredentialError> {
+        let store = self
+            .store
+            .lock()
+            .map_err(|_| CredentialError::Backend("lock poisoned".into()))?;
+        Ok(profile_ids_from_keys(store.keys()))
+    }
 }
 
 /// File-based credential store that persists credentials to disk
@@ -186,6 +285,41 @@ impl FileCredentialStore {
 
         Ok(())
     }
+
+    /// Take the exclusive lock guarding read-modify-write cycles
+    fn lock(&self) -> Result<std::fs::File, CredentialError> {
+        // Ensure parent directory exists before creating lock file
+        if let Some(parent) = self.credentials_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let lock_path = self.credentials_path.with_extension("lock");
+        let lock_file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&lock_path)?;
+        lock_file.lock_exclusive()?;
+        Ok(lock_file)
+    }
+
+    /// Overwrite the credentials file with zeros in place, so the old
+    /// secrets do not survive in its data blocks once it is replaced
+    fn zero_fill(&self) -> Result<(), CredentialError> {
+        use std::io::Write;
+
+        if !self.credentials_path.exists() {
+            return Ok(());
+        }
+        reject_symlink(&self.credentials_path)?;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&self.credentials_path)?;
+        let len = file.metadata()?.len() as usize;
+        file.write_all(&vec![0u8; len])?;
+        file.sync_all()?;
+        Ok(())
+    }
 }
 
 // Note: Default implementation removed to avoid panics.
@@ -197,23 +331,12 @@ impl CredentialPort for FileCredentialStore {
         credentials
             .get(profile_id)
             .cloned()
+            .inspect(|secret| register_secret(secret))
             .ok_or_else(|| CredentialError::NotFound(profile_id.to_string()))
     }
 
     fn set_password(&self, profile_id: &str, password: &str) -> Result<(), CredentialError> {
-        // Ensure parent directory exists before creating lock file
-        if let Some(parent) = self.credentials_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let lock_path = self.credentials_path.with_extension("lock");
-        let lock_file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&lock_path)?;
-        lock_file.lock_exclusive()?;
-
+        let _lock = self.lock()?;
         let mut credentials = self.load()?;
         credentials.insert(profile_id.to_string(), password.to_string());
         self.save(&credentials)?;
@@ -222,25 +345,37 @@ impl CredentialPort for FileCredentialStore {
     }
 
     fn delete_password(&self, profile_id: &str) -> Result<(), CredentialError> {
-        // Ensure parent directory exists before creating lock file
-        if let Some(parent) = self.credentials_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let lock_path = self.credentials_path.with_extension("lock");
-        let lock_file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&lock_path)?;
-        lock_file.lock_exclusive()?;
-
+        let _lock = self.lock()?;
         let mut credentials = self.load()?;
         credentials.remove(profile_id);
         self.save(&credentials)?;
         // lock released on drop
         Ok(())
     }
+
+    fn profile_ids(&self) -> Result<Vec<String>, CredentialError> {
+        Ok(profile_ids_from_keys(self.load()?.keys()))
+    }
+
+    fn wipe(&self, profile_ids: &[String]) -> Result<(), CredentialError> {
+        let _lock = self.lock()?;
+        let mut credentials = self.load()?;
+        for id in profile_ids {
+            credentials.remove(id);
+            credentials.remove(&format!("{}{}", id, OAUTH_TOKEN_SUFFIX));
+        }
+
+        self.zero_fill()?;
+        if credentials.is_empty() {
+            if self.credentials_path.exists() {
+                std::fs::remove_file(&self.credentials_path)?;
+            }
+        } else {
+            self.save(&credentials)?;
+        }
+        // lock released on drop
+        Ok(())
+    }
 }
 
 /// OS keychain-backed credential store using the keyring crate
@@ -272,7 +407,7 @@ impl Default for KeyringCredentialStore {
 impl CredentialPort for KeyringCredentialStore {
     fn get_password(&self, profile_id: &str) -> Result<String, CredentialError> {
         let entry = self.entry(profile_id)?;
-        entry.get_password().map_err(|e| match e {
+        let password = entry.get_password().map_err(|e| match e {
             keyring::Error::NoEntry => CredentialError::NotFound(profile_id.to_string()),
             keyring::Error::PlatformFailure(ref err) => {
                 let err_msg = err.to_string().to_lowercase();
@@ -283,7 +418,9 @@ impl CredentialPort for KeyringCredentialStore {
                 }
             }
             _ => CredentialError::Backend(format!("Keyring error: {}", e)),
-        })
+        })?;
+        register_secret(&password);
+        Ok(password)
     }
 
     fn set_password(&self, profile_id: &str, password: &str) -> Result<(), CredentialError> {
@@ -561,6 +698,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_file_credential_store_profile_ids_and_wipe() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let credentials_path = temp_dir.path().join("credentials.json");
+        let store = FileCredentialStore {
+            credentials_path: credentials_path.clone(),
+        };
+
+        store.set_password("a", "pw-a").unwrap();
+        store.set_oauth_token("a", "{}").unwrap();
+        store.set_oauth_token("b", "{}").unwrap();
+        assert_eq!(store.profile_ids().unwrap(), vec!["a", "b"]);
+
+        store.wipe(&["a".to_string()]).unwrap();
+        assert_eq!(store.profile_ids().unwrap(), vec!["b"]);
+        assert!(
+            !std::fs::read_to_string(&credentials_path)
+                .unwrap()
+                .contains("pw-a")
+        );
+
+        store.wipe(&["b".to_string()]).unwrap();
+        assert!(!credentials_path.exists(), "Empty store should be removed");
+    }
+
+    #[test]
+    fn test_credential_backend_parse_roundtrip() {
+        for backend in CredentialBackend::ALL {
+            assert_eq!(
+                backend.as_str().parse::<CredentialBackend>().unwrap(),
+                backend
+            );
+        }
+        assert!(matches!(
+            "vault".parse::<CredentialBackend>(),
+            Err(CredentialError::UnknownBackend(_))
+        ));
+    }
+
     // --- KeyringCredentialStore Tests ---
 
     #[test]
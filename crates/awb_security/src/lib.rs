@@ -1,8 +1,12 @@
 pub mod credential;
+pub mod migration;
 pub mod redaction;
 
 pub use credential::{
-    CredentialError, CredentialPort, FileCredentialStore, InMemoryCredentialStore,
-    KeyringCredentialStore,
+    CredentialBackend, CredentialError, CredentialPort, FileCredentialStore,
+    InMemoryCredentialStore, KeyringCredentialStore,
+};
+pub use migration::{MigrationReport, migrate_credentials};
+pub use redaction::{
+    is_sensitive_key, redact_field, redact_json, redact_registered, redact_secrets, register_secret,
 };
-pub use redaction::redact_secrets;
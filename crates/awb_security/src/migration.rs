@@ -0,0 +1,137 @@
+use crate::credential::{CredentialError, CredentialPort};
+
+/// Profiles moved by [`migrate_credentials`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Profiles whose password was moved
+    pub passwords: Vec<String>,
+    /// Profiles whose OAuth token was moved
+    pub oauth_tokens: Vec<String>,
+}
+
+impl MigrationReport {
+    /// Every profile with at least one moved credential
+    pub fn profile_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .passwords
+            .iter()
+            .chain(&self.oauth_tokens)
+            .cloned()
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Copy the password and OAuth token of each profile from `from` to `to`,
+/// read each back from `to`, then wipe the moved profiles from `from`.
+///
+/// The source is only wiped once every copy has been verified, so an error
+/// part way through leaves all credentials in the source.
+pub fn migrate_credentials(
+    from: &dyn CredentialPort,
+    to: &dyn CredentialPort,
+    profile_ids: &[String],
+) -> Result<MigrationReport, CredentialError> {
+    let mut report = MigrationReport::default();
+
+    for id in profile_ids {
+        if let Some(password) = found(from.get_password(id))? {
+            to.set_password(id, &password)?;
+            verify(id, &password, to.get_password(id))?;
+            report.passwords.push(id.clone());
+        }
+        if let Some(token) = found(from.get_oauth_token(id))? {
+            to.set_oauth_token(id, &token)?;
+            verify(id, &token, to.get_oauth_token(id))?;
+            report.oauth_tokens.push(id.clone());
+        }
+    }
+
+    from.wipe(&report.profile_ids())?;
+    Ok(report)
+}
+
+/// A missing credential is not an error; the profile may only have one kind
+fn found(result: Result<String, CredentialError>) -> Result<Option<String>, CredentialError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(CredentialError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn verify(
+    profile_id: &str,
+    expected: &str,
+    read_back: Result<String, CredentialError>,
+) -> Result<(), CredentialError> {
+    match read_back {
+        Ok(value) if value == expected => Ok(()),
+        Ok(_) | Err(CredentialError::NotFound(_)) => {
+            Err(CredentialError::VerificationFailed(profile_id.to_string()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::InMemoryCredentialStore;
+
+    /// Store that accepts writes but reads back something else
+    struct Corrupting;
+
+    impl CredentialPort for Corrupting {
+        fn get_password(&self, _: &str) -> Result<String, CredentialError> {
+            Ok("garbled".to_string())
+        }
+        fn set_password(&self, _: &str, _: &str) -> Result<(), CredentialError> {
+            Ok(())
+        }
+        fn delete_password(&self, _: &str) -> Result<(), CredentialError> {
+            Ok(())
+        }
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_migrate_moves_passwords_and_tokens() {
+        let from = InMemoryCredentialStore::new();
+        let to = InMemoryCredentialStore::new();
+        from.set_password("enwiki", "bot-password").unwrap();
+        from.set_oauth_token("dewiki", r#"{"access_token":"t"}"#)
+            .unwrap();
+
+        let report =
+            migrate_credentials(&from, &to, &ids(&["enwiki", "dewiki", "frwiki"])).unwrap();
+
+        assert_eq!(report.passwords, ids(&["enwiki"]));
+        assert_eq!(report.oauth_tokens, ids(&["dewiki"]));
+        assert_eq!(to.get_password("enwiki").unwrap(), "bot-password");
+        assert_eq!(
+            to.get_oauth_token("dewiki").unwrap(),
+            r#"{"access_token":"t"}"#
+        );
+        assert!(from.profile_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_failed_verification_keeps_source() {
+        let from = InMemoryCredentialStore::new();
+        from.set_password("enwiki", "bot-password").unwrap();
+
+        let result = migrate_credentials(&from, &Corrupting, &ids(&["enwiki"]));
+
+        assert!(matches!(
+            result,
+            Err(CredentialError::VerificationFailed(id)) if id == "enwiki"
+        ));
+        assert_eq!(from.get_password("enwiki").unwrap(), "bot-password");
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::RwLock;
+
 /// Minimum secret length to avoid false-positive redaction of short substrings.
 const MIN_SECRET_LEN: usize = 8;
 
@@ -15,6 +17,78 @@ pub fn redact_secrets(input: &str, secrets: &[&str]) -> String {
     result
 }
 
+/// Secrets registered for process-wide redaction, longest first so a secret
+/// containing another is replaced whole
+static REGISTERED: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Register a secret to be stripped by [`redact_registered`], e.g. from log
+/// output. Secrets too short to redact safely are ignored.
+///
+/// The credential stores register every secret they return.
+pub fn register_secret(secret: &str) {
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut registered = REGISTERED.write().unwrap_or_else(|e| e.into_inner());
+    if !registered.iter().any(|s| s == secret) {
+        registered.push(secret.to_string());
+        registered.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    }
+}
+
+/// Redacts every secret passed to [`register_secret`] from a string.
+pub fn redact_registered(input: &str) -> String {
+    let registered = REGISTERED.read().unwrap_or_else(|e| e.into_inner());
+    let secrets: Vec<&str> = registered.iter().map(String::as_str).collect();
+    redact_secrets(input, &secrets)
+}
+
+/// Parameter, header and JSON key names whose values are credentials
+const SENSITIVE_KEY_PARTS: &[&str] = &["token", "password", "secret", "signature"];
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Whether a value named `name` (a request parameter, header or JSON key)
+/// holds a credential, e.g. `lgpassword`, `csrftoken` or `Authorization`.
+pub fn is_sensitive_key(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_HEADERS.contains(&name.as_str())
+        || SENSITIVE_KEY_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// The value of `name` as it may be written out: replaced whole if the name
+/// marks a credential, otherwise with registered secrets redacted.
+pub fn redact_field(name: &str, value: &str) -> String {
+    if is_sensitive_key(name) {
+        "[REDACTED]".to_string()
+    } else {
+        redact_registered(value)
+    }
+}
+
+/// Redact a JSON document in place: values under credential keys are
+/// replaced whole, and registered secrets are redacted from other strings.
+pub fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive_key(key) {
+                    *value = serde_json::Value::String("[REDACTED]".to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        serde_json::Value::String(text) => *text = redact_registered(text),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,6 +115,44 @@ mod tests {
         assert_eq!(result, input, "Short secrets should not be redacted");
     }
 
+    #[test]
+    fn test_registered_secrets_redacted_longest_first() {
+        register_secret("registered-secret");
+        register_secret("registered-secret-extended");
+        register_secret("short");
+
+        let result = redact_registered("a=registered-secret-extended b=registered-secret c=short");
+        assert_eq!(result, "a=[REDACTED] b=[REDACTED] c=short");
+    }
+
+    #[test]
+    fn test_sensitive_fields_redacted_whole() {
+        assert!(is_sensitive_key("lgpassword"));
+        assert!(is_sensitive_key("token"));
+        assert!(is_sensitive_key("Set-Cookie"));
+        assert!(is_sensitive_key("oauth_signature"));
+        assert!(!is_sensitive_key("title"));
+        assert!(!is_sensitive_key("meta"));
+
+        // Short credentials are too short for redact_secrets but still go
+        assert_eq!(redact_field("lgtoken", "ab+\\"), "[REDACTED]");
+        assert_eq!(redact_field("title", "Foo"), "Foo");
+    }
+
+    #[test]
+    fn test_redact_json_by_key() {
+        register_secret("json-registered-secret");
+        let mut value = serde_json::json!({
+            "query": { "tokens": { "csrftoken": "abc+\\" } },
+            "login": { "result": "Success", "lgusername": "Bot" },
+            "warnings": ["leaked json-registered-secret"],
+        });
+        redact_json(&mut value);
+        assert_eq!(value["query"]["tokens"], "[REDACTED]");
+        assert_eq!(value["login"]["lgusername"], "Bot");
+        assert_eq!(value["warnings"][0], "leaked [REDACTED]");
+    }
+
     #[test]
     fn test_exactly_min_length_secret() {
         let input = "key=12345678";
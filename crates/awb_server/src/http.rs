@@ -0,0 +1,327 @@
+//! REST API over the job queue.
+//!
+//! | Method | Path                | Description                     |
+//! |--------|---------------------|---------------------------------|
+//! | GET    | `/health`           | Liveness check, no auth         |
+//! | POST   | `/jobs`             | Submit a [`JobSpec`]            |
+//! | GET    | `/jobs`             | List jobs                       |
+//! | GET    | `/jobs/{id}`        | Job status                      |
+//! | GET    | `/jobs/{id}/report` | Bot report of a finished job    |
+//!
+//! Every route but `/health` requires `Authorization: Bearer <token>`.
+
+use crate::jobs::{JobQueue, JobSpec, QueueError};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Body, Bytes};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode, header};
+use hyper_util::rt::TokioIo;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+/// Largest request body accepted, in bytes
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// Shared state of the server: the queue and the accepted API tokens.
+pub struct AppState {
+    queue: Arc<JobQueue>,
+    tokens: Vec<SecretString>,
+}
+
+impl AppState {
+    pub fn new(queue: Arc<JobQueue>, tokens: Vec<SecretString>) -> Self {
+        Self { queue, tokens }
+    }
+
+    fn authorized<B>(&self, req: &Request<B>) -> bool {
+        let Some(token) = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        self.tokens
+            .iter()
+            .any(|known| constant_time_eq(known.expose_secret().as_bytes(), token.as_bytes()))
+    }
+}
+
+/// Compare without short-circuiting, so response timing does not reveal how
+/// much of a token matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Accept connections on `listener` until it fails.
+pub async fn serve(listener: TcpListener, state: Arc<AppState>) -> std::io::Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            let service = service_fn(move |req| {
+                let state = Arc::clone(&state);
+                async move { Ok::<_, Infallible>(handle(&state, req).await) }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("Connection from {} ended: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Route a single request.
+pub async fn handle<B>(state: &AppState, req: Request<B>) -> Response<Full<Bytes>>
+where
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let response = match (&method, segments.as_slice()) {
+        (&Method::GET, ["health"]) => json(StatusCode::OK, &serde_json::json!({ "status": "ok" })),
+        _ if !state.authorized(&req) => {
+            let mut response = error(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token");
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                "Bearer".parse().expect("valid header"),
+            );
+            response
+        }
+        (&Method::POST, ["jobs"]) => submit(state, req).await,
+        (&Method::GET, ["jobs"]) => json(StatusCode::OK, &state.queue.list()),
+        (&Method::GET, ["jobs", id]) => match parse_id(id) {
+            Some(id) => match state.queue.get(id) {
+                Some(job) => json(StatusCode::OK, &job),
+                None => queue_error(QueueError::NotFound(id)),
+            },
+            None => error(StatusCode::BAD_REQUEST, "Invalid job ID"),
+        },
+        (&Method::GET, ["jobs", id, "report"]) => match parse_id(id) {
+            Some(id) => match state.queue.report(id) {
+                Ok(report) => json(StatusCode::OK, &report),
+                Err(e) => queue_error(e),
+            },
+            None => error(StatusCode::BAD_REQUEST, "Invalid job ID"),
+        },
+        (_, ["jobs"]) | (_, ["jobs", ..]) => {
+            error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+        }
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    };
+    tracing::debug!("{} {} -> {}", method, path, response.status());
+    response
+}
+
+async fn submit<B>(state: &AppState, req: Request<B>) -> Response<Full<Bytes>>
+where
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let body = match Limited::new(req.into_body(), MAX_BODY_BYTES)
+        .collect()
+        .await
+    {
+        Ok(body) => body.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            return error(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large");
+        }
+        Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+    let spec: JobSpec = match serde_json::from_slice(&body) {
+        Ok(spec) => spec,
+        Err(e) => return error(StatusCode::BAD_REQUEST, &format!("Invalid job: {}", e)),
+    };
+    match state.queue.submit(spec) {
+        Ok(job) => json(StatusCode::ACCEPTED, &job),
+        Err(e) => queue_error(e),
+    }
+}
+
+fn parse_id(id: &str) -> Option<Uuid> {
+    Uuid::parse_str(id).ok()
+}
+
+fn queue_error(e: QueueError) -> Response<Full<Bytes>> {
+    let status = match &e {
+        QueueError::Invalid(_) => StatusCode::BAD_REQUEST,
+        QueueError::Full(_) => StatusCode::TOO_MANY_REQUESTS,
+        QueueError::NotFound(_) => StatusCode::NOT_FOUND,
+        QueueError::NotFinished(_) | QueueError::NoReport(_) => StatusCode::CONFLICT,
+    };
+    error(status, &e.to_string())
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+fn json<T: Serialize>(status: StatusCode, value: &T) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(value).expect("serializable response");
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(body)))
+        .expect("valid response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{JobError, JobExecutor, JobLimits};
+    use async_trait::async_trait;
+    use awb_bot::BotReport;
+
+    struct InstantExecutor;
+
+    #[async_trait]
+    impl JobExecutor for InstantExecutor {
+        async fn execute(&self, spec: JobSpec) -> Result<BotReport, JobError> {
+            let mut report = BotReport::new(chrono::Utc::now());
+            report.pages_processed = spec.pages.len();
+            Ok(report)
+        }
+    }
+
+    fn state() -> AppState {
+        let queue = JobQueue::new(Arc::new(InstantExecutor), JobLimits::default());
+        AppState::new(queue, vec![SecretString::new("secret".into())])
+    }
+
+    fn request(
+        method: Method,
+        path: &str,
+        body: &str,
+        token: Option<&str>,
+    ) -> Request<Full<Bytes>> {
+        let mut builder = Request::builder().method(method).uri(path);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        builder
+            .body(Full::new(Bytes::from(body.to_string())))
+            .unwrap()
+    }
+
+    async fn body_json(response: Response<Full<Bytes>>) -> serde_json::Value {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokex"));
+        assert!(!constant_time_eq(b"token", b"tok"));
+    }
+
+    #[tokio::test]
+    async fn test_health_needs_no_token() {
+        let response = handle(&state(), request(Method::GET, "/health", "", None)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_missing_or_wrong_token() {
+        let state = state();
+        let response = handle(&state, request(Method::GET, "/jobs", "", None)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        let response = handle(&state, request(Method::GET, "/jobs", "", Some("nope"))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_submit_poll_and_report() {
+        let state = state();
+        let response = handle(
+            &state,
+            request(
+                Method::POST,
+                "/jobs",
+                r#"{"pages":["A","B"]}"#,
+                Some("secret"),
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let job = body_json(response).await;
+        let id = job["id"].as_str().unwrap().to_string();
+        assert_eq!(job["page_count"], 2);
+
+        let mut status = String::new();
+        for _ in 0..200 {
+            let response = handle(
+                &state,
+                request(Method::GET, &format!("/jobs/{}", id), "", Some("secret")),
+            )
+            .await;
+            status = body_json(response).await["status"]
+                .as_str()
+                .unwrap()
+                .to_string();
+            if status == "completed" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert_eq!(status, "completed");
+
+        let response = handle(
+            &state,
+            request(
+                Method::GET,
+                &format!("/jobs/{}/report", id),
+                "",
+                Some("secret"),
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["pages_processed"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_error_statuses() {
+        let state = state();
+        let token = Some("secret");
+
+        let response = handle(&state, request(Method::POST, "/jobs", "{", token)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = handle(
+            &state,
+            request(Method::POST, "/jobs", r#"{"pages":[]}"#, token),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let path = format!("/jobs/{}", Uuid::new_v4());
+        let response = handle(&state, request(Method::GET, &path, "", token)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = handle(&state, request(Method::GET, "/jobs/42/report", "", token)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = handle(&state, request(Method::DELETE, "/jobs", "", token)).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let response = handle(&state, request(Method::GET, "/elsewhere", "", token)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
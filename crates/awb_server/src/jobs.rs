@@ -0,0 +1,505 @@
+use async_trait::async_trait;
+use awb_bot::bot_runner::BotError;
+use awb_bot::{BotConfig, BotReport, BotRunner};
+use awb_domain::page_list::PageList;
+use awb_domain::profile::ThrottlePolicy;
+use awb_domain::rules::RuleSet;
+use awb_engine::fix_config::FixConfig;
+use awb_engine::general_fixes::FixRegistry;
+use awb_engine::transform::TransformEngine;
+use awb_mw_api::client::{MediaWikiClient, ReqwestMwClient};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use url::Url;
+use uuid::Uuid;
+
+pub type JobId = Uuid;
+
+/// A bot run submitted over the API: the pages to visit and the profile to
+/// edit them with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub pages: Vec<String>,
+    #[serde(default)]
+    pub rules: RuleSet,
+    #[serde(default)]
+    pub fix_config: FixConfig,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub max_edits: Option<u32>,
+    #[serde(default)]
+    pub skip_on_warning: bool,
+}
+
+impl JobSpec {
+    /// Build the transform engine for this job's rules and fix config
+    pub fn engine(&self) -> Result<TransformEngine, JobError> {
+        let registry = FixRegistry::with_config(&self.fix_config);
+        let enabled = registry
+            .enabled_ids(&self.fix_config)
+            .map_err(|e| JobError::Invalid(e.to_string()))?;
+        TransformEngine::new(&self.rules, registry, enabled)
+            .map_err(|e| JobError::Invalid(e.to_string()))
+    }
+
+    /// The pages as canonical titles
+    pub fn page_list(&self) -> Result<PageList, JobError> {
+        PageList::try_from(self.pages.clone()).map_err(|e| JobError::Invalid(e.to_string()))
+    }
+
+    fn validate(&self) -> Result<(), JobError> {
+        if self.pages.is_empty() {
+            return Err(JobError::Invalid("Page list is empty".to_string()));
+        }
+        self.page_list()?;
+        self.engine().map(|_| ())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JobError {
+    #[error("Invalid job: {0}")]
+    Invalid(String),
+
+    #[error("Login failed: {0}")]
+    Login(String),
+
+    #[error("Bot error: {source}")]
+    Bot {
+        source: BotError,
+        /// What the bot had done before it stopped
+        report: Box<BotReport>,
+    },
+}
+
+impl JobError {
+    /// The partial report of a run that stopped early, if it got that far
+    pub fn partial_report(&self) -> Option<&BotReport> {
+        match self {
+            JobError::Bot { report, .. } => Some(report),
+            _ => None,
+        }
+    }
+}
+
+/// Runs a job to completion. The server uses [`BotExecutor`]; tests can
+/// substitute their own.
+#[async_trait]
+pub trait JobExecutor: Send + Sync + 'static {
+    async fn execute(&self, spec: JobSpec) -> Result<BotReport, JobError>;
+}
+
+/// Runs jobs with [`BotRunner`] against a single wiki, logging in with a bot
+/// password for each job.
+pub struct BotExecutor {
+    wiki: Url,
+    username: String,
+    password: SecretString,
+    throttle: ThrottlePolicy,
+    bot_config: BotConfig,
+}
+
+impl BotExecutor {
+    pub fn new(wiki: Url, username: impl Into<String>, password: SecretString) -> Self {
+        Self {
+            wiki,
+            username: username.into(),
+            password,
+            throttle: ThrottlePolicy::default(),
+            bot_config: BotConfig::default(),
+        }
+    }
+
+    /// Base bot configuration; each job's dry-run, edit limit and warning
+    /// settings are applied on top of it
+    #[must_use]
+    pub fn with_bot_config(mut self, config: BotConfig) -> Self {
+        self.bot_config = config;
+        self
+    }
+
+    #[must_use]
+    pub fn with_throttle_policy(mut self, policy: ThrottlePolicy) -> Self {
+        self.throttle = policy;
+        self
+    }
+}
+
+#[async_trait]
+impl JobExecutor for BotExecutor {
+    async fn execute(&self, spec: JobSpec) -> Result<BotReport, JobError> {
+        let engine = spec.engine()?;
+        let pages = spec.page_list()?;
+        let client = ReqwestMwClient::new(self.wiki.clone(), self.throttle.clone())
+            .map_err(|e| JobError::Login(e.to_string()))?;
+        client
+            .login_bot_password(&self.username, self.password.expose_secret())
+            .await
+            .map_err(|e| JobError::Login(e.to_string()))?;
+        client
+            .fetch_csrf_token()
+            .await
+            .map_err(|e| JobError::Login(e.to_string()))?;
+
+        let mut config = self
+            .bot_config
+            .clone()
+            .with_dry_run(spec.dry_run)
+            .with_skip_on_warning(spec.skip_on_warning);
+        if let Some(max) = spec.max_edits {
+            config = config.with_max_edits(max);
+        }
+
+        let mut runner = BotRunner::new(config, client, engine, pages);
+        runner.add_secret(self.password.expose_secret().to_string());
+        match runner.run().await {
+            Ok(report) => Ok(report),
+            Err(source) => Err(JobError::Bot {
+                source,
+                report: Box::new(runner.report().clone()),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed)
+    }
+}
+
+/// A submitted job and its progress. The report is served separately.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: JobId,
+    pub status: JobStatus,
+    pub page_count: usize,
+    pub dry_run: bool,
+    pub submitted_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    #[serde(skip)]
+    pub report: Option<BotReport>,
+}
+
+/// Limits on how much work the queue accepts.
+#[derive(Debug, Clone, Copy)]
+pub struct JobLimits {
+    /// Jobs that may run at the same time
+    pub max_concurrent: usize,
+    /// Jobs that may wait for a slot before submissions are refused
+    pub max_queued: usize,
+    /// Finished jobs kept for polling; the oldest are dropped first
+    pub max_retained: usize,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 2,
+            max_queued: 32,
+            max_retained: 100,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QueueError {
+    #[error(transparent)]
+    Invalid(#[from] JobError),
+
+    #[error("Job queue is full ({0} jobs waiting)")]
+    Full(usize),
+
+    #[error("Job not found: {0}")]
+    NotFound(JobId),
+
+    #[error("Job {0} has not finished")]
+    NotFinished(JobId),
+
+    #[error("Job failed before producing a report: {0}")]
+    NoReport(String),
+}
+
+/// In-memory job queue running at most `max_concurrent` jobs at a time.
+pub struct JobQueue {
+    jobs: Mutex<HashMap<JobId, Job>>,
+    slots: Arc<Semaphore>,
+    executor: Arc<dyn JobExecutor>,
+    limits: JobLimits,
+}
+
+impl JobQueue {
+    pub fn new(executor: Arc<dyn JobExecutor>, limits: JobLimits) -> Arc<Self> {
+        Arc::new(Self {
+            jobs: Mutex::new(HashMap::new()),
+            slots: Arc::new(Semaphore::new(limits.max_concurrent.max(1))),
+            executor,
+            limits,
+        })
+    }
+
+    /// Validate and enqueue a job. It starts as soon as a slot is free.
+    pub fn submit(self: &Arc<Self>, spec: JobSpec) -> Result<Job, QueueError> {
+        spec.validate()?;
+
+        let job = {
+            let mut jobs = self.jobs.lock();
+            let queued = jobs
+                .values()
+                .filter(|job| job.status == JobStatus::Queued)
+                .count();
+            if queued >= self.limits.max_queued {
+                return Err(QueueError::Full(queued));
+            }
+            let job = Job {
+                id: Uuid::new_v4(),
+                status: JobStatus::Queued,
+                page_count: spec.pages.len(),
+                dry_run: spec.dry_run,
+                submitted_at: Utc::now(),
+                started_at: None,
+                finished_at: None,
+                error: None,
+                report: None,
+            };
+            jobs.insert(job.id, job.clone());
+            job
+        };
+
+        tracing::info!(job = %job.id, pages = job.page_count, "Job queued");
+        let queue = Arc::clone(self);
+        let id = job.id;
+        tokio::spawn(async move { queue.run_job(id, spec).await });
+        Ok(job)
+    }
+
+    async fn run_job(&self, id: JobId, spec: JobSpec) {
+        let Ok(_slot) = Arc::clone(&self.slots).acquire_owned().await else {
+            return;
+        };
+        self.update(id, |job| {
+            job.status = JobStatus::Running;
+            job.started_at = Some(Utc::now());
+        });
+        tracing::info!(job = %id, "Job started");
+
+        let result = self.executor.execute(spec).await;
+        self.update(id, |job| {
+            job.finished_at = Some(Utc::now());
+            match result {
+                Ok(report) => {
+                    job.status = JobStatus::Completed;
+                    job.report = Some(report);
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e.to_string());
+                    job.report = e.partial_report().cloned();
+                }
+            }
+            tracing::info!(job = %id, status = ?job.status, "Job finished");
+        });
+        self.prune();
+    }
+
+    fn update(&self, id: JobId, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().get_mut(&id) {
+            f(job);
+        }
+    }
+
+    /// Drop the oldest finished jobs beyond `max_retained`
+    fn prune(&self) {
+        let mut jobs = self.jobs.lock();
+        let mut finished: Vec<(DateTime<Utc>, JobId)> = jobs
+            .values()
+            .filter(|job| job.status.is_finished())
+            .map(|job| (job.finished_at.unwrap_or(job.submitted_at), job.id))
+            .collect();
+        if finished.len() <= self.limits.max_retained {
+            return;
+        }
+        finished.sort();
+        let excess = finished.len() - self.limits.max_retained;
+        for (_, id) in finished.into_iter().take(excess) {
+            jobs.remove(&id);
+        }
+    }
+
+    pub fn get(&self, id: JobId) -> Option<Job> {
+        self.jobs.lock().get(&id).cloned()
+    }
+
+    /// All known jobs, oldest submission first
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.jobs.lock().values().cloned().collect();
+        jobs.sort_by_key(|job| job.submitted_at);
+        jobs
+    }
+
+    /// The report of a finished job
+    pub fn report(&self, id: JobId) -> Result<BotReport, QueueError> {
+        let jobs = self.jobs.lock();
+        let job = jobs.get(&id).ok_or(QueueError::NotFound(id))?;
+        match (&job.report, job.status.is_finished()) {
+            (Some(report), true) => Ok(report.clone()),
+            (None, true) => Err(QueueError::NoReport(job.error.clone().unwrap_or_default())),
+            (_, false) => Err(QueueError::NotFinished(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::sync::Notify;
+
+    /// Holds every job until released, tracking how many run at once.
+    struct GateExecutor {
+        release: Notify,
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl GateExecutor {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                release: Notify::new(),
+                running: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl JobExecutor for GateExecutor {
+        async fn execute(&self, spec: JobSpec) -> Result<BotReport, JobError> {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            self.release.notified().await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            let mut report = BotReport::new(Utc::now());
+            report.pages_processed = spec.pages.len();
+            Ok(report)
+        }
+    }
+
+    fn spec(pages: &[&str]) -> JobSpec {
+        serde_json::from_value(serde_json::json!({ "pages": pages })).unwrap()
+    }
+
+    async fn wait_for(queue: &JobQueue, id: JobId, status: JobStatus) {
+        for _ in 0..200 {
+            if queue.get(id).map(|job| job.status) == Some(status) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("job {} never reached {:?}", id, status);
+    }
+
+    #[test]
+    fn test_spec_defaults() {
+        let spec = spec(&["A"]);
+        assert!(spec.rules.rules.is_empty());
+        assert!(!spec.dry_run);
+        assert!(spec.engine().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_specs() {
+        let queue = JobQueue::new(GateExecutor::new(), JobLimits::default());
+        assert!(matches!(
+            queue.submit(spec(&[])),
+            Err(QueueError::Invalid(_))
+        ));
+
+        let mut bad = spec(&["A"]);
+        bad.fix_config
+            .enabled_fixes
+            .insert("no_such_fix".to_string());
+        assert!(matches!(queue.submit(bad), Err(QueueError::Invalid(_))));
+        assert!(matches!(
+            queue.submit(spec(&["A", "de:B"])),
+            Err(QueueError::Invalid(_))
+        ));
+        assert!(queue.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_and_queue_limits() {
+        let executor = GateExecutor::new();
+        let limits = JobLimits {
+            max_concurrent: 1,
+            max_queued: 1,
+            max_retained: 10,
+        };
+        let queue = JobQueue::new(executor.clone(), limits);
+
+        let first = queue.submit(spec(&["A"])).unwrap();
+        wait_for(&queue, first.id, JobStatus::Running).await;
+        let second = queue.submit(spec(&["B", "C"])).unwrap();
+        assert!(matches!(
+            queue.submit(spec(&["D"])),
+            Err(QueueError::Full(1))
+        ));
+        assert!(matches!(
+            queue.report(second.id),
+            Err(QueueError::NotFinished(_))
+        ));
+
+        executor.release.notify_one();
+        wait_for(&queue, first.id, JobStatus::Completed).await;
+        wait_for(&queue, second.id, JobStatus::Running).await;
+        executor.release.notify_one();
+        wait_for(&queue, second.id, JobStatus::Completed).await;
+
+        assert_eq!(executor.peak.load(Ordering::SeqCst), 1);
+        assert_eq!(queue.report(second.id).unwrap().pages_processed, 2);
+        assert_eq!(queue.list().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_prunes_oldest_finished_jobs() {
+        let executor = GateExecutor::new();
+        let limits = JobLimits {
+            max_concurrent: 1,
+            max_queued: 4,
+            max_retained: 1,
+        };
+        let queue = JobQueue::new(executor.clone(), limits);
+
+        let first = queue.submit(spec(&["A"])).unwrap();
+        wait_for(&queue, first.id, JobStatus::Running).await;
+        executor.release.notify_one();
+        wait_for(&queue, first.id, JobStatus::Completed).await;
+
+        let second = queue.submit(spec(&["B"])).unwrap();
+        wait_for(&queue, second.id, JobStatus::Running).await;
+        executor.release.notify_one();
+        wait_for(&queue, second.id, JobStatus::Completed).await;
+
+        assert!(queue.get(first.id).is_none());
+        assert!(queue.get(second.id).is_some());
+    }
+}
@@ -0,0 +1,14 @@
+//! REST job server for AWB-RS.
+//!
+//! Clients submit a page list and editing profile as a job, poll its status
+//! and fetch the bot report once it finishes. Jobs run through
+//! [`awb_bot::BotRunner`] with a cap on how many run at once.
+
+pub mod http;
+pub mod jobs;
+
+pub use http::{AppState, serve};
+pub use jobs::{
+    BotExecutor, Job, JobError, JobExecutor, JobId, JobLimits, JobQueue, JobSpec, JobStatus,
+    QueueError,
+};
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use awb_bot::BotConfig;
+use awb_server::{AppState, BotExecutor, JobLimits, JobQueue};
+use clap::Parser;
+use secrecy::SecretString;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use url::Url;
+
+/// Environment variable holding the comma-separated API tokens
+const TOKENS_ENV: &str = "AWB_SERVER_TOKENS";
+/// Environment variable holding the bot password
+const PASSWORD_ENV: &str = "AWB_BOT_PASSWORD";
+
+#[derive(Parser)]
+#[command(name = "awb-server")]
+#[command(version, about = "REST job server for AWB-RS bot runs", long_about = None)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    bind: SocketAddr,
+
+    /// Wiki API URL jobs run against
+    #[arg(long)]
+    wiki: Url,
+
+    /// Bot username (the password is read from AWB_BOT_PASSWORD)
+    #[arg(long)]
+    username: String,
+
+    /// Bot name for {{bots}}/{{nobots}} compliance
+    #[arg(long, default_value = "AWB-RS")]
+    bot_name: String,
+
+    /// Jobs that may run at the same time
+    #[arg(long, default_value = "2")]
+    max_concurrent_jobs: usize,
+
+    /// Jobs that may wait for a slot before submissions are refused
+    #[arg(long, default_value = "32")]
+    max_queued_jobs: usize,
+
+    /// Finished jobs kept for polling
+    #[arg(long, default_value = "100")]
+    max_retained_jobs: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    awb_telemetry::init_telemetry(&awb_telemetry::TelemetryConfig::default())?;
+
+    let args = Args::parse();
+
+    let tokens: Vec<SecretString> = std::env::var(TOKENS_ENV)
+        .with_context(|| format!("{} must list at least one API token", TOKENS_ENV))?
+        .split(',')
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .map(|token| SecretString::new(token.into()))
+        .collect();
+    if tokens.is_empty() {
+        anyhow::bail!("{} must list at least one API token", TOKENS_ENV);
+    }
+    let password = std::env::var(PASSWORD_ENV)
+        .with_context(|| format!("{} must hold the bot password", PASSWORD_ENV))?;
+
+    let executor = BotExecutor::new(args.wiki, args.username, SecretString::new(password.into()))
+        .with_bot_config(BotConfig::new().with_bot_name(args.bot_name));
+    let limits = JobLimits {
+        max_concurrent: args.max_concurrent_jobs,
+        max_queued: args.max_queued_jobs,
+        max_retained: args.max_retained_jobs,
+    };
+    let queue = JobQueue::new(Arc::new(executor), limits);
+    let state = Arc::new(AppState::new(queue, tokens));
+
+    let listener = tokio::net::TcpListener::bind(args.bind)
+        .await
+        .with_context(|| format!("Failed to bind {}", args.bind))?;
+    tracing::info!("Listening on {}", args.bind);
+    awb_server::serve(listener, state).await?;
+    Ok(())
+}
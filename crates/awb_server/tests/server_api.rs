@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use awb_bot::BotReport;
+use awb_server::{AppState, JobError, JobExecutor, JobLimits, JobQueue, JobSpec};
+use secrecy::SecretString;
+use std::sync::Arc;
+use std::time::Duration;
+
+struct CountingExecutor;
+
+#[async_trait]
+impl JobExecutor for CountingExecutor {
+    async fn execute(&self, spec: JobSpec) -> Result<BotReport, JobError> {
+        let mut report = BotReport::new(chrono::Utc::now());
+        report.pages_processed = spec.pages.len();
+        report.pages_skipped = spec.pages.len();
+        Ok(report)
+    }
+}
+
+async fn start_server() -> String {
+    let queue = JobQueue::new(Arc::new(CountingExecutor), JobLimits::default());
+    let state = Arc::new(AppState::new(
+        queue,
+        vec![SecretString::new("t0ken".into())],
+    ));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(awb_server::serve(listener, state));
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_job_lifecycle_over_http() {
+    let base = start_server().await;
+    let http = reqwest::Client::new();
+
+    let health = http.get(format!("{}/health", base)).send().await.unwrap();
+    assert_eq!(health.status(), 200);
+
+    let unauthorized = http.get(format!("{}/jobs", base)).send().await.unwrap();
+    assert_eq!(unauthorized.status(), 401);
+
+    let submitted: serde_json::Value = http
+        .post(format!("{}/jobs", base))
+        .bearer_auth("t0ken")
+        .json(&serde_json::json!({
+            "pages": ["Alpha", "Beta", "Gamma"],
+            "dry_run": true,
+            "fix_config": { "strictness_tier": 0 }
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let id = submitted["id"].as_str().unwrap();
+    assert_eq!(submitted["dry_run"], true);
+
+    let mut job = serde_json::Value::Null;
+    for _ in 0..200 {
+        job = http
+            .get(format!("{}/jobs/{}", base, id))
+            .bearer_auth("t0ken")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if job["status"] == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(job["status"], "completed");
+
+    let report: serde_json::Value = http
+        .get(format!("{}/jobs/{}/report", base, id))
+        .bearer_auth("t0ken")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["pages_processed"], 3);
+
+    let jobs: Vec<serde_json::Value> = http
+        .get(format!("{}/jobs", base))
+        .bearer_auth("t0ken")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+}
@@ -0,0 +1,240 @@
+//! On-disk cache of prepared rule sets.
+//!
+//! Building an engine for a library of thousands of rules means checking
+//! and analysing every regex before the first page is touched. The result
+//! of that work, [`PreparedRules`], is saved here under a hash of the rule
+//! set, so the next start with the same rules skips it and compiles each
+//! regex only when a page may match it. Changing any rule changes the hash,
+//! so a stale entry is never used; old entries are pruned as new ones are
+//! written.
+
+use crate::error::StorageError;
+use crate::rule_library::content_hash;
+use awb_domain::rules::RuleSet;
+use awb_engine::general_fixes::FixRegistry;
+use awb_engine::transform::{PreparedRules, TransformEngine, TransformError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Bumped when the saved format or the way rules are analysed changes
+const FORMAT_VERSION: u32 = 1;
+
+/// Entries kept in the cache directory; older ones are removed
+const MAX_ENTRIES: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedRules {
+    key: String,
+    prepared: PreparedRules,
+}
+
+/// On-disk cache of [`PreparedRules`], keyed by rule set.
+pub struct CompiledRuleCache {
+    dir: PathBuf,
+}
+
+impl CompiledRuleCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Cache key for `rule_set`: a hash of its rules together with the
+    /// engine version and format they were prepared with
+    pub fn key(rule_set: &RuleSet) -> String {
+        let rules = serde_json::to_string(rule_set).unwrap_or_default();
+        content_hash(format!(
+            "{}\n{}\n{}",
+            env!("CARGO_PKG_VERSION"),
+            FORMAT_VERSION,
+            rules
+        ))
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("compiled-{}.json", &key[..16]))
+    }
+
+    /// The prepared rules saved for `rule_set`, if any
+    pub fn read(&self, rule_set: &RuleSet) -> Option<PreparedRules> {
+        let key = Self::key(rule_set);
+        let data = std::fs::read_to_string(self.path_for(&key)).ok()?;
+        let cached: CachedRules = serde_json::from_str(&data).ok()?;
+        (cached.key == key).then_some(cached.prepared)
+    }
+
+    /// Save `prepared` for `rule_set`, then prune the oldest entries
+    pub fn write(&self, rule_set: &RuleSet, prepared: &PreparedRules) -> Result<(), StorageError> {
+        std::fs::create_dir_all(&self.dir)?;
+        let cached = CachedRules {
+            key: Self::key(rule_set),
+            prepared: prepared.clone(),
+        };
+        let path = self.path_for(&cached.key);
+        let tmp = path.with_extension("tmp");
+        let json =
+            serde_json::to_string(&cached).map_err(|e| StorageError::Serialize(e.to_string()))?;
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &path)?;
+        self.prune()
+    }
+
+    /// Remove all but the [`MAX_ENTRIES`] most recently written entries
+    fn prune(&self) -> Result<(), StorageError> {
+        let mut entries: Vec<_> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with("compiled-") && name.ends_with(".json")
+            })
+            .filter_map(|entry| {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+                Some((modified, entry.path()))
+            })
+            .collect();
+        if entries.len() <= MAX_ENTRIES {
+            return Ok(());
+        }
+        entries.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        for (_, path) in entries.drain(MAX_ENTRIES..) {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Build the engine for `rule_set`, from the saved prepared rules when
+    /// there are some, or else preparing and saving them. Failing to save
+    /// is logged, not an error: the engine works the same either way.
+    pub fn engine(
+        &self,
+        rule_set: &RuleSet,
+        fix_registry: FixRegistry,
+        enabled_fixes: HashSet<String>,
+    ) -> Result<TransformEngine, TransformError> {
+        if let Some(prepared) = self.read(rule_set) {
+            return TransformEngine::with_prepared(
+                rule_set,
+                &prepared,
+                fix_registry,
+                enabled_fixes,
+            );
+        }
+        let (engine, prepared) =
+            TransformEngine::new_prepared(rule_set, fix_registry, enabled_fixes)?;
+        if let Err(e) = self.write(rule_set, &prepared) {
+            tracing::warn!("Failed to cache prepared rules: {}", e);
+        }
+        Ok(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awb_domain::rules::Rule;
+    use awb_domain::types::*;
+    use tempfile::TempDir;
+
+    fn page(wikitext: &str) -> PageContent {
+        PageContent {
+            page_id: PageId(1),
+            title: Title::new(Namespace::MAIN, "Test"),
+            revision: RevisionId(1),
+            timestamp: chrono::Utc::now(),
+            wikitext: wikitext.to_string(),
+            size_bytes: wikitext.len() as u64,
+            is_redirect: false,
+            protection: ProtectionInfo::default(),
+            properties: PageProperties::default(),
+        }
+    }
+
+    fn rules(patterns: &[&str]) -> RuleSet {
+        let mut set = RuleSet::new();
+        for pattern in patterns {
+            set.add(Rule::new_regex(*pattern, "x", false));
+        }
+        set
+    }
+
+    #[test]
+    fn test_engine_saves_then_reuses_prepared_rules() {
+        let dir = TempDir::new().unwrap();
+        let cache = CompiledRuleCache::new(dir.path());
+        let set = rules(&[r"colou?r", r"\bteh\b"]);
+        assert!(cache.read(&set).is_none());
+
+        cache
+            .engine(&set, FixRegistry::new(), HashSet::new())
+            .unwrap();
+        let saved = cache.read(&set).expect("saved");
+
+        let engine = cache
+            .engine(&set, FixRegistry::new(), HashSet::new())
+            .unwrap();
+        assert_eq!(cache.read(&set), Some(saved));
+        assert_eq!(engine.apply(&page("teh color")).new_wikitext, "x x");
+    }
+
+    #[test]
+    fn test_changed_rules_miss_the_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache = CompiledRuleCache::new(dir.path());
+        let set = rules(&[r"colou?r"]);
+        cache
+            .engine(&set, FixRegistry::new(), HashSet::new())
+            .unwrap();
+
+        let mut changed = set.clone();
+        changed.rules[0].enabled = false;
+        assert_ne!(
+            CompiledRuleCache::key(&set),
+            CompiledRuleCache::key(&changed)
+        );
+        assert!(cache.read(&changed).is_none());
+        assert!(cache.read(&set).is_some());
+    }
+
+    #[test]
+    fn test_invalid_rules_are_not_cached() {
+        let dir = TempDir::new().unwrap();
+        let cache = CompiledRuleCache::new(dir.path());
+        let set = rules(&[r"(unclosed"]);
+        assert!(
+            cache
+                .engine(&set, FixRegistry::new(), HashSet::new())
+                .is_err()
+        );
+        assert!(cache.read(&set).is_none());
+    }
+
+    #[test]
+    fn test_corrupt_entry_is_ignored_and_replaced() {
+        let dir = TempDir::new().unwrap();
+        let cache = CompiledRuleCache::new(dir.path());
+        let set = rules(&[r"colou?r"]);
+        let path = cache.path_for(&CompiledRuleCache::key(&set));
+        std::fs::write(&path, "not json").unwrap();
+        assert!(cache.read(&set).is_none());
+
+        cache
+            .engine(&set, FixRegistry::new(), HashSet::new())
+            .unwrap();
+        assert!(cache.read(&set).is_some());
+    }
+
+    #[test]
+    fn test_prunes_old_entries() {
+        let dir = TempDir::new().unwrap();
+        let cache = CompiledRuleCache::new(dir.path());
+        for i in 0..MAX_ENTRIES + 3 {
+            let set = rules(&[&format!("rule{}", i)]);
+            cache
+                .engine(&set, FixRegistry::new(), HashSet::new())
+                .unwrap();
+        }
+        let entries = std::fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(entries, MAX_ENTRIES);
+    }
+}
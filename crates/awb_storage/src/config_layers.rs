@@ -0,0 +1,277 @@
+//! Layered preferences.
+//!
+//! The `[preferences]` table holds the global defaults. A `[wikis."<host>"]`
+//! table overrides them for one wiki and a `[profile_preferences.<id>]` table
+//! overrides both for one profile. Override tables only list the keys they
+//! change; nested tables such as `fix_config` are merged key by key, while
+//! arrays and other values replace the value below them whole.
+
+use crate::config_store::Preferences;
+use crate::error::StorageError;
+use std::collections::BTreeMap;
+use std::fmt;
+use toml::{Table, Value};
+
+/// Layer a preference value was taken from, lowest precedence first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreferenceSource {
+    Global,
+    /// A `[wikis."<host>"]` table
+    Wiki(String),
+    /// A `[profile_preferences.<id>]` table
+    Profile(String),
+}
+
+impl fmt::Display for PreferenceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::Wiki(host) => write!(f, "wiki {}", host),
+            Self::Profile(id) => write!(f, "profile {}", id),
+        }
+    }
+}
+
+/// Preferences after all layers are merged
+#[derive(Debug, Clone)]
+pub struct EffectivePreferences {
+    pub preferences: Preferences,
+    /// Layer that last set each overridden key, by dotted path
+    /// (e.g. `fix_config.strictness_tier`); other keys are global
+    pub overrides: BTreeMap<String, PreferenceSource>,
+}
+
+impl EffectivePreferences {
+    /// Layer the value at `path` came from
+    pub fn source(&self, path: &str) -> PreferenceSource {
+        self.overrides
+            .get(path)
+            .cloned()
+            .unwrap_or(PreferenceSource::Global)
+    }
+
+    /// Every leaf value with its dotted path, in key order
+    pub fn entries(&self) -> Result<Vec<(String, Value)>, StorageError> {
+        let mut entries = Vec::new();
+        flatten(&Table::try_from(&self.preferences)?, "", &mut entries);
+        Ok(entries)
+    }
+}
+
+/// Apply `layers` to `global` in order, so later layers win. Keys that
+/// [`Preferences`] does not have are rejected rather than ignored, and the
+/// merged result must pass [`Preferences::validate`].
+pub fn merge_preferences(
+    global: &Preferences,
+    layers: &[(PreferenceSource, &Table)],
+) -> Result<EffectivePreferences, StorageError> {
+    let mut merged = Table::try_from(global)?;
+    let mut overrides = BTreeMap::new();
+    for (source, layer) in layers {
+        merge_table(&mut merged, layer, "", source, &mut overrides)?;
+    }
+
+    let preferences: Preferences = merged
+        .try_into()
+        .map_err(|e: toml::de::Error| StorageError::Deserialize(e.message().to_string()))?;
+    preferences.validate()?;
+    Ok(EffectivePreferences {
+        preferences,
+        overrides,
+    })
+}
+
+fn merge_table(
+    base: &mut Table,
+    layer: &Table,
+    prefix: &str,
+    source: &PreferenceSource,
+    overrides: &mut BTreeMap<String, PreferenceSource>,
+) -> Result<(), StorageError> {
+    for (key, value) in layer {
+        let path = format!("{}{}", prefix, key);
+        let Some(current) = base.get_mut(key) else {
+            // Unset optional settings inside a table (e.g.
+            // `fix_config.spell_check`) are not written out; deserializing
+            // the result still rejects unknown names there
+            if !prefix.is_empty() {
+                let mut entries = Vec::new();
+                match value {
+                    Value::Table(inner) => flatten(inner, &format!("{}.", path), &mut entries),
+                    _ => entries.push((path, value.clone())),
+                }
+                for (path, _) in entries {
+                    overrides.insert(path, source.clone());
+                }
+                base.insert(key.clone(), value.clone());
+                continue;
+            }
+            return Err(StorageError::Deserialize(format!(
+                "unknown preference '{}' in {} overrides",
+                path, source
+            )));
+        };
+        match (current, value) {
+            (Value::Table(current), Value::Table(value)) => {
+                merge_table(current, value, &format!("{}.", path), source, overrides)?;
+            }
+            (current, value) => {
+                *current = value.clone();
+                overrides.insert(path, source.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn flatten(table: &Table, prefix: &str, entries: &mut Vec<(String, Value)>) {
+    for (key, value) in table {
+        let path = format!("{}{}", prefix, key);
+        match value {
+            Value::Table(inner) => flatten(inner, &format!("{}.", path), entries),
+            _ => entries.push((path, value.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(source: &str) -> Table {
+        source.parse().unwrap()
+    }
+
+    #[test]
+    fn test_later_layers_win() {
+        let wiki = table("theme = \"dark\"\ndiff_context_lines = 5");
+        let profile = table("diff_context_lines = 8");
+        let effective = merge_preferences(
+            &Preferences::default(),
+            &[
+                (PreferenceSource::Wiki("en.wikipedia.org".into()), &wiki),
+                (PreferenceSource::Profile("enwiki".into()), &profile),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(effective.preferences.theme, "dark");
+        assert_eq!(effective.preferences.diff_context_lines, 8);
+        assert_eq!(effective.preferences.log_level, "info");
+        assert_eq!(
+            effective.source("theme"),
+            PreferenceSource::Wiki("en.wikipedia.org".into())
+        );
+        assert_eq!(
+            effective.source("diff_context_lines"),
+            PreferenceSource::Profile("enwiki".into())
+        );
+        assert_eq!(effective.source("log_level"), PreferenceSource::Global);
+    }
+
+    #[test]
+    fn test_nested_tables_merge_and_arrays_replace() {
+        let mut global = Preferences::default();
+        global.fix_config.allow_cosmetic_only = true;
+        global.fix_config.disabled_fixes.insert("a".to_string());
+        global.fix_config.disabled_fixes.insert("b".to_string());
+        let wiki = table("[fix_config]\nstrictness_tier = 2\ndisabled_fixes = [\"c\"]");
+
+        let effective =
+            merge_preferences(&global, &[(PreferenceSource::Wiki("w".into()), &wiki)]).unwrap();
+
+        let fixes = &effective.preferences.fix_config;
+        assert_eq!(fixes.strictness_tier, 2);
+        assert!(fixes.allow_cosmetic_only, "untouched nested key kept");
+        assert_eq!(
+            fixes.disabled_fixes.iter().collect::<Vec<_>>(),
+            vec!["c"],
+            "arrays are replaced, not appended"
+        );
+        assert_eq!(
+            effective.source("fix_config.strictness_tier"),
+            PreferenceSource::Wiki("w".into())
+        );
+        assert_eq!(
+            effective.source("fix_config.allow_cosmetic_only"),
+            PreferenceSource::Global
+        );
+    }
+
+    #[test]
+    fn test_unset_optional_settings_can_be_overridden() {
+        let wiki = table("[fix_config.spell_check]\ndictionary = \"de_DE.dic\"");
+
+        let effective = merge_preferences(
+            &Preferences::default(),
+            &[(PreferenceSource::Wiki("de".into()), &wiki)],
+        )
+        .unwrap();
+
+        let spell_check = effective
+            .preferences
+            .fix_config
+            .spell_check
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            spell_check.dictionary,
+            std::path::PathBuf::from("de_DE.dic")
+        );
+        assert_eq!(
+            effective.source("fix_config.spell_check.dictionary"),
+            PreferenceSource::Wiki("de".into())
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_invalid_overrides_are_rejected() {
+        let typo = table("them = \"dark\"");
+        let err = merge_preferences(
+            &Preferences::default(),
+            &[(PreferenceSource::Profile("enwiki".into()), &typo)],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("'them' in profile enwiki"));
+
+        let invalid = table("theme = \"neon\"");
+        assert!(
+            merge_preferences(
+                &Preferences::default(),
+                &[(PreferenceSource::Global, &invalid)],
+            )
+            .is_err()
+        );
+
+        let nested_typo = table("[fix_config]\nstrictnes_tier = 2");
+        assert!(
+            merge_preferences(
+                &Preferences::default(),
+                &[(PreferenceSource::Global, &nested_typo)],
+            )
+            .is_err()
+        );
+
+        let wrong_type = table("fix_config = 3");
+        assert!(
+            merge_preferences(
+                &Preferences::default(),
+                &[(PreferenceSource::Global, &wrong_type)],
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_no_layers_is_global() {
+        let effective = merge_preferences(&Preferences::default(), &[]).unwrap();
+        assert!(effective.overrides.is_empty());
+        assert!(
+            effective
+                .entries()
+                .unwrap()
+                .iter()
+                .any(|(path, _)| path == "fix_config.strictness_tier")
+        );
+    }
+}
@@ -0,0 +1,720 @@
+//! Schema checks for config/profile TOML files.
+//!
+//! serde stops at the first problem and reports it without context. These
+//! checks walk the parsed document instead, so every problem is reported with
+//! its line and column, and misspelt keys or values come with a suggestion.
+
+use crate::config_layers::{PreferenceSource, merge_preferences};
+use crate::config_store::{CREDENTIAL_BACKENDS, ConfigFile, DIFF_MODES, LOG_LEVELS, THEMES};
+use crate::error::StorageError;
+use awb_domain::types::WATCHLIST_MODES;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+use toml_edit::{ImDocument, Item, TableLike};
+
+const TOP_KEYS: &[&str] = &[
+    "preferences",
+    "profiles",
+    "rules",
+    "wikis",
+    "profile_preferences",
+];
+const PREFERENCE_KEYS: &[&str] = &[
+    "default_profile",
+    "theme",
+    "diff_mode",
+    "diff_context_lines",
+    "auto_save_interval_secs",
+    "confirm_large_change_threshold",
+    "log_level",
+    "fix_config",
+    "credential_backend",
+    "watchlist",
+];
+const FIX_CONFIG_KEYS: &[&str] = &[
+    "strictness_tier",
+    "enabled_fixes",
+    "disabled_fixes",
+    "allow_cosmetic_only",
+    "dated_templates",
+    "interlanguage_order",
+    "person_sort_key",
+    "heading_case_exceptions",
+    "spell_check",
+];
+const SPELL_CHECK_KEYS: &[&str] = &["dictionary", "auto_fix_confidence"];
+const PROFILE_KEYS: &[&str] = &[
+    "id",
+    "name",
+    "api_url",
+    "auth_method",
+    "default_namespaces",
+    "throttle_policy",
+    "connection",
+];
+const CONNECTION_KEYS: &[&str] = &[
+    "headers",
+    "basic_auth_user",
+    "user_agent",
+    "accept_invalid_certs",
+];
+const THROTTLE_KEYS: &[&str] = &["min_edit_interval", "maxlag", "max_retries", "backoff_base"];
+const AUTH_TYPES: &[&str] = &["BotPassword", "OAuth1", "OAuth2"];
+const RULE_SET_KEYS: &[&str] = &["rules"];
+const RULE_KEYS: &[&str] = &[
+    "id",
+    "enabled",
+    "order",
+    "kind",
+    "comment_fragment",
+    "namespaces",
+    "title_pattern",
+    "retargets_links",
+];
+const RULE_KINDS: &[&str] = &["Plain", "Regex", "JsonPatch", "json_patch"];
+const PLAIN_KEYS: &[&str] = &["find", "replace", "case_sensitive"];
+const REGEX_KEYS: &[&str] = &["pattern", "replacement", "case_insensitive"];
+const JSON_PATCH_KEYS: &[&str] = &["operations"];
+
+/// One problem found in a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based line of the offending key or value
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    /// Dotted path of the offending key, e.g. `preferences.theme`
+    pub path: String,
+    pub message: String,
+    /// Closest accepted key or value when the input looks misspelt
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean '{}'?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check a config file's source; an empty result means it loads cleanly
+pub fn validate_config(source: &str) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        source,
+        diagnostics: Vec::new(),
+    };
+
+    let doc = match ImDocument::parse(source) {
+        Ok(doc) => doc,
+        Err(e) => {
+            checker.report(e.span(), "", e.message().trim(), None);
+            return checker.diagnostics;
+        }
+    };
+
+    checker.check_root(doc.as_table());
+    if checker.diagnostics.is_empty() {
+        // Anything the walk above does not cover (missing keys, wrong
+        // types, numeric ranges) still surfaces, with a position if serde
+        // has one
+        match toml::from_str::<ConfigFile>(source) {
+            Ok(config) => {
+                if let Err(StorageError::Deserialize(message)) = config.preferences.validate() {
+                    let span = doc
+                        .as_table()
+                        .get_key_value("preferences")
+                        .and_then(|(key, _)| key.span());
+                    checker.report(span, "preferences", message, None);
+                    return checker.diagnostics;
+                }
+                // Each override table must also give valid preferences once
+                // applied over the global ones
+                for (layer, overrides) in [
+                    ("wikis", &config.wikis),
+                    ("profile_preferences", &config.profile_preferences),
+                ] {
+                    for (name, table) in overrides {
+                        let source = PreferenceSource::Global;
+                        if let Err(e) = merge_preferences(&config.preferences, &[(source, table)]) {
+                            let span = table_at(doc.as_table(), layer)
+                                .and_then(|t| t.get_key_value(name))
+                                .and_then(|(key, _)| key.span());
+                            let message = match e {
+                                StorageError::Deserialize(message) => message,
+                                e => e.to_string(),
+                            };
+                            checker.report(span, &format!("{}.{}", layer, name), message, None);
+                        }
+                    }
+                }
+            }
+            Err(e) => checker.report(e.span(), "", e.message().trim(), None),
+        }
+    }
+    checker.diagnostics
+}
+
+struct Checker<'a> {
+    source: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn report(
+        &mut self,
+        span: Option<Range<usize>>,
+        path: &str,
+        message: impl Into<String>,
+        suggestion: Option<&str>,
+    ) {
+        let (line, column) = line_column(self.source, span.map_or(0, |s| s.start));
+        self.diagnostics.push(Diagnostic {
+            line,
+            column,
+            path: path.to_string(),
+            message: message.into(),
+            suggestion: suggestion.map(str::to_string),
+        });
+    }
+
+    fn check_root(&mut self, root: &dyn TableLike) {
+        self.check_keys(root, "", TOP_KEYS);
+
+        if let Some(prefs) = table_at(root, "preferences") {
+            self.check_preferences(prefs, "preferences");
+        }
+        for layer in ["wikis", "profile_preferences"] {
+            let Some(overrides) = table_at(root, layer) else {
+                continue;
+            };
+            for (name, item) in overrides.iter() {
+                if let Some(prefs) = item.as_table_like() {
+                    self.check_preferences(prefs, &format!("{}.{}", layer, name));
+                }
+            }
+        }
+        if let Some(profiles) = table_at(root, "profiles") {
+            for (id, item) in profiles.iter() {
+                if let Some(profile) = item.as_table_like() {
+                    self.check_profile(id, profile);
+                }
+            }
+        }
+        if let Some(rule_sets) = table_at(root, "rules") {
+            for (id, item) in rule_sets.iter() {
+                let Some(rule_set) = item.as_table_like() else {
+                    continue;
+                };
+                let path = format!("rules.{}", id);
+                self.check_keys(rule_set, &path, RULE_SET_KEYS);
+                if let Some(rules) = rule_set.get("rules") {
+                    for (i, rule) in array_tables(rules).into_iter().enumerate() {
+                        self.check_rule(&format!("{}.rules[{}]", path, i), rule);
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_preferences(&mut self, prefs: &dyn TableLike, path: &str) {
+        self.check_keys(prefs, path, PREFERENCE_KEYS);
+        self.check_choice(prefs, path, "theme", THEMES);
+        self.check_choice(prefs, path, "diff_mode", DIFF_MODES);
+        self.check_choice(prefs, path, "log_level", LOG_LEVELS);
+        self.check_choice(prefs, path, "credential_backend", CREDENTIAL_BACKENDS);
+        self.check_choice(prefs, path, "watchlist", WATCHLIST_MODES);
+
+        let Some(fix_config) = table_at(prefs, "fix_config") else {
+            return;
+        };
+        let path = &format!("{}.fix_config", path);
+        self.check_keys(fix_config, path, FIX_CONFIG_KEYS);
+
+        if let Some(tier) = fix_config.get("strictness_tier") {
+            if let Some(value) = tier.as_integer().filter(|v| !(0..=3).contains(v)) {
+                self.report(
+                    tier.span(),
+                    &format!("{}.strictness_tier", path),
+                    format!("strictness_tier {} out of range 0..=3", value),
+                    None,
+                );
+            }
+        }
+        if let Some(spell_check) = table_at(fix_config, "spell_check") {
+            let spell_path = format!("{}.spell_check", path);
+            self.check_keys(spell_check, &spell_path, SPELL_CHECK_KEYS);
+            if let Some(confidence) = spell_check.get("auto_fix_confidence") {
+                if let Some(value) = confidence.as_integer().filter(|v| !(0..=100).contains(v)) {
+                    self.report(
+                        confidence.span(),
+                        &format!("{}.auto_fix_confidence", spell_path),
+                        format!("auto_fix_confidence {} out of range 0..=100", value),
+                        None,
+                    );
+                }
+            }
+        }
+
+        let enabled: HashSet<&str> = fix_config
+            .get("enabled_fixes")
+            .and_then(Item::as_array)
+            .map(|fixes| fixes.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        if let Some(disabled) = fix_config.get("disabled_fixes").and_then(Item::as_array) {
+            for value in disabled.iter() {
+                if let Some(id) = value.as_str().filter(|id| enabled.contains(id)) {
+                    self.report(
+                        value.span(),
+                        &format!("{}.disabled_fixes", path),
+                        format!(
+                            "fix '{}' is listed in both enabled_fixes and disabled_fixes",
+                            id
+                        ),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_profile(&mut self, id: &str, profile: &dyn TableLike) {
+        let path = format!("profiles.{}", id);
+        self.check_keys(profile, &path, PROFILE_KEYS);
+
+        if let Some((item, inner)) = profile
+            .get("id")
+            .and_then(|item| Some((item, item.as_str()?)))
+            .filter(|(_, inner)| *inner != id)
+        {
+            self.report(
+                item.span(),
+                &format!("{}.id", path),
+                format!(
+                    "profile id '{}' does not match its table name '{}'",
+                    inner, id
+                ),
+                Some(id),
+            );
+        }
+
+        if let Some(throttle) = table_at(profile, "throttle_policy") {
+            self.check_keys(
+                throttle,
+                &format!("{}.throttle_policy", path),
+                THROTTLE_KEYS,
+            );
+        }
+        if let Some(connection) = table_at(profile, "connection") {
+            self.check_keys(connection, &format!("{}.connection", path), CONNECTION_KEYS);
+        }
+
+        let Some(auth) = table_at(profile, "auth_method") else {
+            return;
+        };
+        let auth_path = format!("{}.auth_method", path);
+        let allowed: &[&str] = match auth.get("type").and_then(Item::as_str) {
+            Some("BotPassword") => &["type", "username"],
+            Some("OAuth1") => &[
+                "type",
+                "consumer_key",
+                "consumer_secret",
+                "access_token",
+                "access_secret",
+            ],
+            Some("OAuth2") => &["type", "client_id", "client_secret"],
+            _ => {
+                self.check_choice(auth, &auth_path, "type", AUTH_TYPES);
+                return;
+            }
+        };
+        self.check_keys(auth, &auth_path, allowed);
+    }
+
+    fn check_rule(&mut self, path: &str, rule: &dyn TableLike) {
+        self.check_keys(rule, path, RULE_KEYS);
+
+        if let Some(item) = rule
+            .get("title_pattern")
+            .filter(|item| item.as_str() == Some(""))
+        {
+            self.report(
+                item.span(),
+                &format!("{}.title_pattern", path),
+                "empty title_pattern; remove it to match every title",
+                None,
+            );
+        }
+
+        let Some(kind) = table_at(rule, "kind") else {
+            return;
+        };
+        let kind_path = format!("{}.kind", path);
+        self.check_keys(kind, &kind_path, RULE_KINDS);
+        if kind.iter().filter(|(k, _)| RULE_KINDS.contains(k)).count() > 1 {
+            let span = rule.get_key_value("kind").and_then(|(key, _)| key.span());
+            self.report(
+                span,
+                &kind_path,
+                "a rule is only one of Plain, Regex or JsonPatch",
+                None,
+            );
+        }
+
+        for (variant, keys, text_key) in [
+            ("Plain", PLAIN_KEYS, "find"),
+            ("Regex", REGEX_KEYS, "pattern"),
+        ] {
+            let Some(body) = table_at(kind, variant) else {
+                continue;
+            };
+            let variant_path = format!("{}.{}", kind_path, variant);
+            self.check_keys(body, &variant_path, keys);
+            if let Some(item) = body.get(text_key).filter(|item| item.as_str() == Some("")) {
+                self.report(
+                    item.span(),
+                    &format!("{}.{}", variant_path, text_key),
+                    format!("empty {}; the rule would match everywhere", text_key),
+                    None,
+                );
+            }
+        }
+        for variant in ["JsonPatch", "json_patch"] {
+            if let Some(body) = table_at(kind, variant) {
+                self.check_keys(body, &format!("{}.{}", kind_path, variant), JSON_PATCH_KEYS);
+            }
+        }
+    }
+
+    /// Report every key of `table` not in `allowed`
+    fn check_keys(&mut self, table: &dyn TableLike, path: &str, allowed: &[&str]) {
+        for (key, _) in table.iter() {
+            if allowed.contains(&key) {
+                continue;
+            }
+            let span = table.get_key_value(key).and_then(|(k, _)| k.span());
+            self.report(
+                span,
+                &join(path, key),
+                format!("unknown key '{}'", key),
+                closest(key, allowed),
+            );
+        }
+    }
+
+    /// Report `key` if it is present but not one of `allowed`
+    fn check_choice(&mut self, table: &dyn TableLike, path: &str, key: &str, allowed: &[&str]) {
+        let Some((k, item)) = table.get_key_value(key) else {
+            return;
+        };
+        match item.as_str() {
+            Some(value) if allowed.contains(&value) => {}
+            Some(value) => self.report(
+                item.span(),
+                &join(path, key),
+                format!("invalid value '{}': expected {}", value, allowed.join("|")),
+                closest(value, allowed),
+            ),
+            None => self.report(
+                item.span().or_else(|| k.span()),
+                &join(path, key),
+                format!("expected a string, one of {}", allowed.join("|")),
+                None,
+            ),
+        }
+    }
+}
+
+/// Sub-table under `key`, whether written as `[section]` or inline
+fn table_at<'a>(table: &'a dyn TableLike, key: &str) -> Option<&'a dyn TableLike> {
+    table.get(key).and_then(Item::as_table_like)
+}
+
+/// Tables of an array, whether written as `[[section]]` or inline
+fn array_tables(item: &Item) -> Vec<&dyn TableLike> {
+    if let Some(tables) = item.as_array_of_tables() {
+        return tables.iter().map(|t| t as &dyn TableLike).collect();
+    }
+    item.as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_inline_table())
+                .map(|t| t as &dyn TableLike)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// 1-based line and column of a byte offset
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+/// Accepted candidate closest to `input`, if it is close enough to be a typo
+fn closest<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    candidates
+        .iter()
+        .map(|c| (edit_distance(&input, &c.to_lowercase()), *c))
+        .filter(|(distance, c)| *distance <= 2.max(c.len() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+/// Levenshtein distance over characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_store::{Preferences, TomlConfigStore};
+    use awb_domain::rules::{Rule, RuleSet};
+
+    const VALID: &str = r#"
+[preferences]
+default_profile = "enwiki"
+theme = "dark"
+diff_mode = "unified"
+diff_context_lines = 3
+auto_save_interval_secs = 30
+confirm_large_change_threshold = 500
+log_level = "info"
+"#;
+
+    #[test]
+    fn test_valid_config_has_no_diagnostics() {
+        assert_eq!(validate_config(VALID), vec![]);
+    }
+
+    #[test]
+    fn test_saved_config_has_no_diagnostics() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let store = TomlConfigStore::new(&path);
+        store.save_preferences(&Preferences::default()).unwrap();
+        let mut rules = RuleSet::new();
+        rules.add(Rule::new_plain("teh", "the", true));
+        rules.add(Rule::new_regex(r"(\d+)px", "$1 px", false).with_title_pattern("^List"));
+        store.save_rules("enwiki", &rules).unwrap();
+
+        let source = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(validate_config(&source), vec![]);
+    }
+
+    #[test]
+    fn test_unknown_key_reports_position_and_suggestion() {
+        let source = VALID.replace("log_level", "log_levle");
+        let diagnostics = validate_config(&source);
+        assert_eq!(diagnostics.len(), 1);
+        let d = &diagnostics[0];
+        assert_eq!((d.line, d.column), (9, 1));
+        assert_eq!(d.path, "preferences.log_levle");
+        assert_eq!(d.suggestion.as_deref(), Some("log_level"));
+        assert_eq!(
+            d.to_string(),
+            "9:1: preferences.log_levle: unknown key 'log_levle' (did you mean 'log_level'?)"
+        );
+    }
+
+    #[test]
+    fn test_watchlist_choice_is_checked() {
+        let source = format!(
+            "{}watchlist = \"nochange\"\n\n[profile_preferences.enwiki]\nwatchlist = \"nochnage\"\n",
+            VALID
+        );
+        let diagnostics = validate_config(&source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "profile_preferences.enwiki.watchlist");
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("nochange"));
+    }
+
+    #[test]
+    fn test_override_tables_are_checked() {
+        let source = format!(
+            "{}\n[wikis.\"de.wikipedia.org\"]\ntheme = \"drak\"\n\n[profile_preferences.enwiki]\ndif_mode = \"inline\"\n",
+            VALID
+        );
+        let diagnostics = validate_config(&source);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].path, "wikis.de.wikipedia.org.theme");
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("dark"));
+        assert_eq!(diagnostics[1].path, "profile_preferences.enwiki.dif_mode");
+        assert_eq!(diagnostics[1].suggestion.as_deref(), Some("diff_mode"));
+
+        let out_of_range = format!(
+            "{}\n[wikis.\"de.wikipedia.org\"]\ndiff_context_lines = 0\n",
+            VALID
+        );
+        let diagnostics = validate_config(&out_of_range);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "wikis.de.wikipedia.org");
+        assert_eq!(diagnostics[0].line, 11);
+    }
+
+    #[test]
+    fn test_profile_connection_keys_are_checked() {
+        let source = format!(
+            "{}\n[profiles.intranet.connection]\nuser_agent = \"IntranetBot/1.0\"\naccept_invalid_cert = true\n\n[profiles.intranet.connection.headers]\nX-Anything = \"ok\"\n",
+            VALID
+        );
+        let diagnostics = validate_config(&source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].path,
+            "profiles.intranet.connection.accept_invalid_cert"
+        );
+        assert_eq!(
+            diagnostics[0].suggestion.as_deref(),
+            Some("accept_invalid_certs")
+        );
+    }
+
+    #[test]
+    fn test_invalid_choice_suggests_closest_value() {
+        let source = VALID.replace("\"dark\"", "\"drak\"");
+        let diagnostics = validate_config(&source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (4, 9));
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("dark"));
+    }
+
+    #[test]
+    fn test_invalid_tier_and_conflicting_fixes() {
+        let source = format!(
+            "{}\n[preferences.fix_config]\nstrictness_tier = 7\n\
+             enabled_fixes = [\"a\", \"b\"]\ndisabled_fixes = [\"b\"]\n",
+            VALID
+        );
+        let messages: Vec<String> = validate_config(&source)
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "strictness_tier 7 out of range 0..=3".to_string(),
+                "fix 'b' is listed in both enabled_fixes and disabled_fixes".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spell_check_is_checked() {
+        let source = format!(
+            "{}
+[wikis.\"de.wikipedia.org\".fix_config.spell_check]
+             dictionary = \"de_DE.dic\"
+auto_fix_confidence = 150
+language = \"de\"
+",
+            VALID
+        );
+        let diagnostics = validate_config(&source);
+        let paths: Vec<&str> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "wikis.de.wikipedia.org.fix_config.spell_check.language",
+                "wikis.de.wikipedia.org.fix_config.spell_check.auto_fix_confidence",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_patterns_are_reported() {
+        let source = format!(
+            "{}\n[[rules.enwiki.rules]]\nid = \"00000000-0000-0000-0000-000000000000\"\n\
+             enabled = true\norder = 0\ntitle_pattern = \"\"\n\
+             [rules.enwiki.rules.kind.Regex]\npattern = \"\"\nreplacement = \"x\"\n\
+             case_insensitive = false\n",
+            VALID
+        );
+        let paths: Vec<String> = validate_config(&source)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "rules.enwiki.rules[0].title_pattern".to_string(),
+                "rules.enwiki.rules[0].kind.Regex.pattern".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_patch_rules_are_checked() {
+        let rule = |key: &str| {
+            format!(
+                "{}\n[[rules.enwiki.rules]]\nid = \"00000000-0000-0000-0000-000000000000\"\n\
+                 enabled = true\norder = 0\n\
+                 [[rules.enwiki.rules.kind.JsonPatch.{}]]\nop = \"replace\"\n\
+                 path = \"/license\"\nvalue = \"CC0-1.0\"\n",
+                VALID, key
+            )
+        };
+        assert!(validate_config(&rule("operations")).is_empty());
+
+        let paths: Vec<String> = validate_config(&rule("operatons"))
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert!(paths.contains(&"rules.enwiki.rules[0].kind.JsonPatch.operatons".to_string()));
+    }
+
+    #[test]
+    fn test_syntax_error_has_position() {
+        let diagnostics = validate_config("[preferences]\ntheme = \n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 2);
+    }
+
+    #[test]
+    fn test_serde_errors_are_still_reported() {
+        let diagnostics = validate_config(&VALID.replace("diff_context_lines = 3", ""));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("diff_context_lines"));
+
+        let diagnostics = validate_config(&VALID.replace("lines = 3", "lines = 0"));
+        assert_eq!(diagnostics[0].path, "preferences");
+    }
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(closest("Dark", THEMES), Some("dark"));
+        assert_eq!(closest("sidebyside", DIFF_MODES), Some("side-by-side"));
+        assert_eq!(closest("purple", THEMES), None);
+    }
+}
@@ -1,9 +1,23 @@
+use crate::config_layers::{EffectivePreferences, PreferenceSource, merge_preferences};
 use crate::error::StorageError;
 use awb_domain::profile::Profile;
+use awb_domain::rules::RuleSet;
+use awb_domain::types::Watchlist;
+use awb_engine::fix_config::FixConfig;
 use fs2::FileExt;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// Accepted values of [`Preferences::theme`]
+pub const THEMES: &[&str] = &["system", "dark", "light"];
+/// Accepted values of [`Preferences::diff_mode`]
+pub const DIFF_MODES: &[&str] = &["side-by-side", "unified", "inline"];
+/// Accepted values of [`Preferences::log_level`]
+pub const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+/// Accepted values of [`Preferences::credential_backend`]
+pub const CREDENTIAL_BACKENDS: &[&str] = &["keyring", "file", "memory"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Preferences {
     pub default_profile: String,
@@ -13,6 +27,19 @@ pub struct Preferences {
     pub auto_save_interval_secs: u32,
     pub confirm_large_change_threshold: u32,
     pub log_level: String,
+    /// General fixes selected in the UI
+    #[serde(default)]
+    pub fix_config: FixConfig,
+    /// Where passwords and OAuth tokens are stored
+    #[serde(default = "default_credential_backend")]
+    pub credential_backend: String,
+    /// What saving a page does to the account's watchlist
+    #[serde(default)]
+    pub watchlist: Watchlist,
+}
+
+fn default_credential_backend() -> String {
+    "keyring".to_string()
 }
 
 impl Default for Preferences {
@@ -25,41 +52,31 @@ impl Default for Preferences {
             auto_save_interval_secs: 30,
             confirm_large_change_threshold: 500,
             log_level: "info".to_string(),
+            fix_config: FixConfig::default(),
+            credential_backend: default_credential_backend(),
+            watchlist: Watchlist::default(),
         }
     }
 }
 
 impl Preferences {
     pub fn validate(&self) -> Result<(), StorageError> {
-        // Validate log_level
-        match self.log_level.as_str() {
-            "trace" | "debug" | "info" | "warn" | "error" => {}
-            other => {
-                return Err(StorageError::Deserialize(format!(
-                    "invalid log_level '{}': expected trace|debug|info|warn|error",
-                    other
-                )));
-            }
-        }
-
-        // Validate diff_mode
-        match self.diff_mode.as_str() {
-            "side-by-side" | "unified" | "inline" => {}
-            other => {
-                return Err(StorageError::Deserialize(format!(
-                    "invalid diff_mode '{}': expected side-by-side|unified|inline",
-                    other
-                )));
-            }
-        }
-
-        // Validate theme
-        match self.theme.as_str() {
-            "system" | "dark" | "light" => {}
-            other => {
+        for (field, value, allowed) in [
+            ("log_level", &self.log_level, LOG_LEVELS),
+            ("diff_mode", &self.diff_mode, DIFF_MODES),
+            ("theme", &self.theme, THEMES),
+            (
+                "credential_backend",
+                &self.credential_backend,
+                CREDENTIAL_BACKENDS,
+            ),
+        ] {
+            if !allowed.contains(&value.as_str()) {
                 return Err(StorageError::Deserialize(format!(
-                    "invalid theme '{}': expected system|dark|light",
-                    other
+                    "invalid {} '{}': expected {}",
+                    field,
+                    value,
+                    allowed.join("|")
                 )));
             }
         }
@@ -86,15 +103,31 @@ impl Preferences {
             )));
         }
 
+        if self.fix_config.strictness_tier > 3 {
+            return Err(StorageError::Deserialize(format!(
+                "fix_config.strictness_tier {} out of range 0..=3",
+                self.fix_config.strictness_tier
+            )));
+        }
+
         Ok(())
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct ConfigFile {
-    preferences: Preferences,
+pub(crate) struct ConfigFile {
+    pub(crate) preferences: Preferences,
+    #[serde(default)]
+    pub(crate) profiles: std::collections::HashMap<String, Profile>,
+    /// Find-and-replace rules, keyed by profile ID
     #[serde(default)]
-    profiles: std::collections::HashMap<String, Profile>,
+    pub(crate) rules: std::collections::HashMap<String, RuleSet>,
+    /// Preference overrides, keyed by wiki host (e.g. `en.wikipedia.org`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) wikis: HashMap<String, toml::Table>,
+    /// Preference overrides, keyed by profile ID; these win over `wikis`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) profile_preferences: HashMap<String, toml::Table>,
 }
 
 pub struct TomlConfigStore {
@@ -111,6 +144,9 @@ impl TomlConfigStore {
             return Ok(ConfigFile {
                 preferences: Preferences::default(),
                 profiles: std::collections::HashMap::new(),
+                rules: std::collections::HashMap::new(),
+                wikis: HashMap::new(),
+                profile_preferences: HashMap::new(),
             });
         }
         let data = std::fs::read_to_string(&self.path)?;
@@ -148,15 +184,8 @@ impl TomlConfigStore {
         Ok(())
     }
 
-    pub fn load_preferences(&self) -> Result<Preferences, StorageError> {
-        let prefs = self.load_file()?.preferences;
-        prefs.validate()?;
-        Ok(prefs)
-    }
-
-    pub fn save_preferences(&self, prefs: &Preferences) -> Result<(), StorageError> {
-        prefs.validate()?;
-
+    /// Load, modify and save the config file under an exclusive lock
+    fn update_file(&self, update: impl FnOnce(&mut ConfigFile)) -> Result<(), StorageError> {
         // Ensure parent directory exists before creating lock file
         if let Some(parent) = self.path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -171,12 +200,61 @@ impl TomlConfigStore {
         lock_file.lock_exclusive()?;
 
         let mut config = self.load_file()?;
-        config.preferences = prefs.clone();
+        update(&mut config);
         self.save_file(&config)?;
         // lock released on drop
         Ok(())
     }
 
+    pub fn load_preferences(&self) -> Result<Preferences, StorageError> {
+        let prefs = self.load_file()?.preferences;
+        prefs.validate()?;
+        Ok(prefs)
+    }
+
+    pub fn save_preferences(&self, prefs: &Preferences) -> Result<(), StorageError> {
+        prefs.validate()?;
+        self.update_file(|config| config.preferences = prefs.clone())
+    }
+
+    /// Global preferences with the overrides for `wiki` and then
+    /// `profile_id` applied. Without an explicit `wiki`, the host of the
+    /// profile's API URL selects the wiki overrides.
+    pub fn load_effective_preferences(
+        &self,
+        wiki: Option<&str>,
+        profile_id: Option<&str>,
+    ) -> Result<EffectivePreferences, StorageError> {
+        let config = self.load_file()?;
+        config.preferences.validate()?;
+
+        let profile_overrides = match profile_id {
+            Some(id) => {
+                let overrides = config.profile_preferences.get(id);
+                if overrides.is_none() && !config.profiles.contains_key(id) {
+                    return Err(StorageError::NotFound(id.to_string()));
+                }
+                overrides.map(|table| (PreferenceSource::Profile(id.to_string()), table))
+            }
+            None => None,
+        };
+        let wiki = wiki.map(str::to_string).or_else(|| {
+            profile_id
+                .and_then(|id| config.profiles.get(id))
+                .and_then(|profile| profile.api_url.host_str().map(str::to_string))
+        });
+        let wiki_overrides = wiki.and_then(|host| {
+            let table = config.wikis.get(&host)?;
+            Some((PreferenceSource::Wiki(host), table))
+        });
+
+        let layers: Vec<_> = wiki_overrides
+            .into_iter()
+            .chain(profile_overrides)
+            .collect();
+        merge_preferences(&config.preferences, &layers)
+    }
+
     pub fn load_profile(&self, id: &str) -> Result<Profile, StorageError> {
         let config = self.load_file()?;
         config
@@ -187,30 +265,30 @@ impl TomlConfigStore {
     }
 
     pub fn save_profile(&self, profile: &Profile) -> Result<(), StorageError> {
-        // Ensure parent directory exists before creating lock file
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let lock_path = self.path.with_extension("lock");
-        let lock_file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&lock_path)?;
-        lock_file.lock_exclusive()?;
-
-        let mut config = self.load_file()?;
-        config.profiles.insert(profile.id.clone(), profile.clone());
-        self.save_file(&config)?;
-        // lock released on drop
-        Ok(())
+        self.update_file(|config| {
+            config.profiles.insert(profile.id.clone(), profile.clone());
+        })
     }
 
     pub fn list_profiles(&self) -> Result<Vec<Profile>, StorageError> {
         let config = self.load_file()?;
         Ok(config.profiles.into_values().collect())
     }
+
+    /// Rules saved for a profile; empty if none have been saved
+    pub fn load_rules(&self, profile_id: &str) -> Result<RuleSet, StorageError> {
+        Ok(self
+            .load_file()?
+            .rules
+            .remove(profile_id)
+            .unwrap_or_default())
+    }
+
+    pub fn save_rules(&self, profile_id: &str, rules: &RuleSet) -> Result<(), StorageError> {
+        self.update_file(|config| {
+            config.rules.insert(profile_id.to_string(), rules.clone());
+        })
+    }
 }
 
 #[cfg(test)]
@@ -245,6 +323,9 @@ mod tests {
             auto_save_interval_secs: 60,
             confirm_large_change_threshold: 1000,
             log_level: "debug".to_string(),
+            fix_config: FixConfig::default(),
+            credential_backend: "file".to_string(),
+            watchlist: Watchlist::Unwatch,
         };
 
         // Save preferences
@@ -258,9 +339,11 @@ mod tests {
         assert_eq!(loaded_prefs.theme, "dark");
         assert_eq!(loaded_prefs.diff_mode, "unified");
         assert_eq!(loaded_prefs.diff_context_lines, 5);
+        assert_eq!(loaded_prefs.watchlist, Watchlist::Unwatch);
         assert_eq!(loaded_prefs.auto_save_interval_secs, 60);
         assert_eq!(loaded_prefs.confirm_large_change_threshold, 1000);
         assert_eq!(loaded_prefs.log_level, "debug");
+        assert_eq!(loaded_prefs.credential_backend, "file");
     }
 
     #[test]
@@ -302,7 +385,7 @@ mod tests {
 
     #[test]
     fn test_profile_save_and_load() {
-        use awb_domain::profile::{AuthMethod, Profile, ThrottlePolicy};
+        use awb_domain::profile::{AuthMethod, ConnectionOptions, Profile, ThrottlePolicy};
         use awb_domain::types::Namespace;
         use std::collections::HashSet;
         use std::time::Duration;
@@ -328,6 +411,7 @@ mod tests {
                 max_retries: 3,
                 backoff_base: Duration::from_secs(2),
             },
+            connection: ConnectionOptions::default(),
         };
 
         // Save profile
@@ -365,7 +449,7 @@ mod tests {
 
     #[test]
     fn test_list_profiles() {
-        use awb_domain::profile::{AuthMethod, Profile, ThrottlePolicy};
+        use awb_domain::profile::{AuthMethod, ConnectionOptions, Profile, ThrottlePolicy};
         use awb_domain::types::Namespace;
         use std::collections::HashSet;
         use std::time::Duration;
@@ -392,6 +476,7 @@ mod tests {
                 max_retries: 3,
                 backoff_base: Duration::from_secs(2),
             },
+            connection: ConnectionOptions::default(),
         };
 
         let profile2 = Profile {
@@ -408,6 +493,7 @@ mod tests {
                 max_retries: 3,
                 backoff_base: Duration::from_secs(2),
             },
+            connection: ConnectionOptions::default(),
         };
 
         store.save_profile(&profile1).unwrap();
@@ -498,6 +584,13 @@ mod tests {
         assert!(prefs.validate().is_err());
     }
 
+    #[test]
+    fn test_validate_rejects_invalid_credential_backend() {
+        let mut prefs = Preferences::default();
+        prefs.credential_backend = "vault".to_string();
+        assert!(prefs.validate().is_err());
+    }
+
     #[test]
     fn test_validate_accepts_valid_themes() {
         let mut prefs = Preferences::default();
@@ -511,4 +604,113 @@ mod tests {
         prefs.theme = "light".to_string();
         assert!(prefs.validate().is_ok());
     }
+
+    #[test]
+    fn test_rules_roundtrip_per_profile() {
+        use awb_domain::rules::Rule;
+
+        let temp_dir = TempDir::new().unwrap();
+        let store = TomlConfigStore::new(temp_dir.path().join("config.toml"));
+        assert!(store.load_rules("enwiki").unwrap().rules.is_empty());
+
+        let mut rules = RuleSet::new();
+        rules.add(Rule::new_plain("teh", "the", true));
+        rules.add(Rule::new_regex(r"(\d+)px", "$1 px", false));
+        store.save_rules("enwiki", &rules).unwrap();
+        store.save_preferences(&Preferences::default()).unwrap();
+
+        let loaded = store.load_rules("enwiki").unwrap();
+        assert_eq!(loaded.rules.len(), 2);
+        assert_eq!(loaded.rules[0].id, rules.rules[0].id);
+        assert!(store.load_rules("dewiki").unwrap().rules.is_empty());
+    }
+
+    #[test]
+    fn test_fix_config_persisted_in_preferences() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TomlConfigStore::new(temp_dir.path().join("config.toml"));
+
+        let mut prefs = Preferences::default();
+        prefs.fix_config.strictness_tier = 2;
+        prefs.fix_config.set_enabled("whitespace_cleanup", false);
+        store.save_preferences(&prefs).unwrap();
+
+        let loaded = store.load_preferences().unwrap();
+        assert_eq!(loaded.fix_config, prefs.fix_config);
+
+        prefs.fix_config.strictness_tier = 9;
+        assert!(store.save_preferences(&prefs).is_err());
+    }
+
+    #[test]
+    fn test_effective_preferences_follow_profile_wiki() {
+        use awb_domain::profile::{AuthMethod, ConnectionOptions, Profile, ThrottlePolicy};
+        use std::collections::HashSet;
+        use std::io::Write;
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let store = TomlConfigStore::new(&config_path);
+        store.save_preferences(&Preferences::default()).unwrap();
+        store
+            .save_profile(&Profile {
+                id: "dewiki".to_string(),
+                name: "German Wikipedia".to_string(),
+                api_url: url::Url::parse("https://de.wikipedia.org/w/api.php").unwrap(),
+                auth_method: AuthMethod::BotPassword {
+                    username: "Bot".to_string(),
+                },
+                default_namespaces: HashSet::new(),
+                throttle_policy: ThrottlePolicy {
+                    min_edit_interval: Duration::from_secs(5),
+                    maxlag: 5,
+                    max_retries: 3,
+                    backoff_base: Duration::from_secs(2),
+                },
+                connection: ConnectionOptions::default(),
+            })
+            .unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&config_path)
+            .unwrap();
+        writeln!(
+            file,
+            "\n[wikis.\"de.wikipedia.org\"]\ntheme = \"dark\"\nlog_level = \"warn\"\n\n\
+             [profile_preferences.dewiki]\nlog_level = \"debug\""
+        )
+        .unwrap();
+
+        let global = store.load_effective_preferences(None, None).unwrap();
+        assert_eq!(global.preferences.theme, "system");
+
+        let wiki = store
+            .load_effective_preferences(Some("de.wikipedia.org"), None)
+            .unwrap();
+        assert_eq!(wiki.preferences.theme, "dark");
+        assert_eq!(wiki.preferences.log_level, "warn");
+
+        let profile = store
+            .load_effective_preferences(None, Some("dewiki"))
+            .unwrap();
+        assert_eq!(profile.preferences.theme, "dark");
+        assert_eq!(profile.preferences.log_level, "debug");
+        assert_eq!(
+            profile.source("log_level"),
+            PreferenceSource::Profile("dewiki".into())
+        );
+
+        // Overrides survive a save through the store
+        store.save_preferences(&Preferences::default()).unwrap();
+        let profile = store
+            .load_effective_preferences(None, Some("dewiki"))
+            .unwrap();
+        assert_eq!(profile.preferences.log_level, "debug");
+
+        assert!(matches!(
+            store.load_effective_preferences(None, Some("frwiki")),
+            Err(StorageError::NotFound(_))
+        ));
+    }
 }
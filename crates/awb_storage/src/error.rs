@@ -14,6 +14,16 @@ pub enum StorageError {
     NotFound(String),
     #[error("Invalid session ID: {0}")]
     InvalidSessionId(String),
+    #[error("Invalid plugin name: {0}")]
+    InvalidPluginName(String),
+    #[error("Failed to fetch {0}")]
+    RemoteFetch(String),
+    #[error("Content hash mismatch for {title}: expected {expected}, found {found}")]
+    HashMismatch {
+        title: String,
+        expected: String,
+        found: String,
+    },
 }
 
 impl From<serde_json::Error> for StorageError {
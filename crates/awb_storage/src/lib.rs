@@ -1,7 +1,17 @@
+pub mod compiled_rules;
+pub mod config_layers;
+pub mod config_schema;
 pub mod config_store;
 pub mod error;
+pub mod plugin_state_store;
+pub mod rule_library;
 pub mod session_store;
 
+pub use compiled_rules::CompiledRuleCache;
+pub use config_layers::{EffectivePreferences, PreferenceSource, merge_preferences};
+pub use config_schema::{Diagnostic, validate_config};
 pub use config_store::{Preferences, TomlConfigStore};
 pub use error::StorageError;
-pub use session_store::{JsonSessionStore, SessionStore};
+pub use plugin_state_store::{PluginStateMap, PluginStateStore};
+pub use rule_library::{LoadedRules, ProfileSource, RuleLibraryCache, RuleLibraryOptions};
+pub use session_store::{JsonSessionStore, ReviewSessionFile, SessionStore};
@@ -0,0 +1,104 @@
+use crate::error::StorageError;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Key-value state saved by a single plugin.
+pub type PluginStateMap = BTreeMap<String, String>;
+
+/// JSON file store for plugin key-value state, one file per plugin.
+///
+/// Writes go through a temp file and rename so a crash mid-write never
+/// leaves a truncated state file behind.
+pub struct PluginStateStore {
+    dir: PathBuf,
+}
+
+impl PluginStateStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Validate plugin name to prevent path traversal attacks
+    fn validate_plugin_name(name: &str) -> Result<(), StorageError> {
+        if name.is_empty()
+            || name.starts_with('.')
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+        {
+            return Err(StorageError::InvalidPluginName(format!(
+                "'{}' may only contain alphanumerics, hyphens, underscores, and periods",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    fn state_path(&self, plugin: &str) -> Result<PathBuf, StorageError> {
+        Self::validate_plugin_name(plugin)?;
+        Ok(self.dir.join(format!("{}.state.json", plugin)))
+    }
+
+    /// Load the saved state of a plugin; missing state is an empty map
+    pub fn load(&self, plugin: &str) -> Result<PluginStateMap, StorageError> {
+        let path = self.state_path(plugin)?;
+        if !path.exists() {
+            return Ok(PluginStateMap::new());
+        }
+        let data = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Save the state of a plugin, replacing any previous state
+    pub fn save(&self, plugin: &str, state: &PluginStateMap) -> Result<(), StorageError> {
+        let path = self.state_path(plugin)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(state)
+            .map_err(|e| StorageError::Serialize(e.to_string()))?;
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Delete the saved state of a plugin, if any
+    pub fn clear(&self, plugin: &str) -> Result<(), StorageError> {
+        let path = self.state_path(plugin)?;
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load_clear() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = PluginStateStore::new(dir.path());
+
+        assert!(store.load("counter.lua").unwrap().is_empty());
+
+        let mut state = PluginStateMap::new();
+        state.insert("seen".to_string(), "3".to_string());
+        store.save("counter.lua", &state).unwrap();
+        assert_eq!(store.load("counter.lua").unwrap(), state);
+
+        store.clear("counter.lua").unwrap();
+        assert!(store.load("counter.lua").unwrap().is_empty());
+        // Clearing twice is fine
+        store.clear("counter.lua").unwrap();
+    }
+
+    #[test]
+    fn test_rejects_path_traversal() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = PluginStateStore::new(dir.path());
+        assert!(store.load("../evil").is_err());
+        assert!(store.save(".hidden", &PluginStateMap::new()).is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! Rule libraries maintained on a wiki page.
+//!
+//! A profile named `wiki:User:Foo/awb-rules.json` loads its [`RuleSet`] from
+//! that page (JSON, or TOML when the title ends in `.toml`). Fetched pages
+//! are cached on disk, per wiki, and re-fetched once older than the refresh
+//! interval.
+//! Because anyone who can edit the page can change the rules, the page
+//! content can be pinned to a SHA-256 hash.
+
+use crate::error::StorageError;
+use awb_domain::rules::RuleSet;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
+
+/// Prefix marking a profile that lives on a wiki page
+pub const WIKI_PREFIX: &str = "wiki:";
+
+/// Where a profile comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileSource {
+    /// A local TOML config file
+    File(PathBuf),
+    /// A wiki page holding a rule set
+    Wiki(String),
+}
+
+impl FromStr for ProfileSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(WIKI_PREFIX) {
+            Some(title) if title.trim().is_empty() => {
+                Err("wiki: profile needs a page title".to_string())
+            }
+            Some(title) => Ok(Self::Wiki(title.trim().to_string())),
+            None => Ok(Self::File(PathBuf::from(s))),
+        }
+    }
+}
+
+impl std::fmt::Display for ProfileSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Wiki(title) => write!(f, "{}{}", WIKI_PREFIX, title),
+        }
+    }
+}
+
+/// Hex SHA-256 of `content`
+pub fn content_hash(content: impl AsRef<[u8]>) -> String {
+    Sha256::digest(content.as_ref())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Parse a rule set from page content, as TOML if `title` ends in `.toml`
+/// and JSON otherwise
+pub fn parse_rules(title: &str, content: &str) -> Result<RuleSet, StorageError> {
+    if title.to_ascii_lowercase().ends_with(".toml") {
+        Ok(toml::from_str(content)?)
+    } else {
+        Ok(serde_json::from_str(content)?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleLibraryOptions {
+    /// Expected SHA-256 of the page content (hex, optionally `sha256:`-prefixed)
+    pub pin: Option<String>,
+    /// How long a cached copy is used before the page is fetched again
+    pub refresh: Duration,
+}
+
+impl Default for RuleLibraryOptions {
+    fn default() -> Self {
+        Self {
+            pin: None,
+            refresh: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// A rule set loaded from a wiki page.
+#[derive(Debug, Clone)]
+pub struct LoadedRules {
+    pub rules: RuleSet,
+    /// SHA-256 of the page content the rules were parsed from
+    pub sha256: String,
+    pub fetched_at: DateTime<Utc>,
+    /// True if the cached copy was used instead of fetching the page
+    pub from_cache: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedPage {
+    wiki: String,
+    title: String,
+    sha256: String,
+    fetched_at: DateTime<Utc>,
+    content: String,
+}
+
+/// On-disk cache of rule library pages.
+pub struct RuleLibraryCache {
+    dir: PathBuf,
+}
+
+impl RuleLibraryCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path_for(&self, wiki: &str, title: &str) -> PathBuf {
+        let key = content_hash(format!("{}\n{}", wiki, title));
+        self.dir.join(format!("rules-{}.json", &key[..16]))
+    }
+
+    fn read(&self, wiki: &Url, title: &str) -> Option<CachedPage> {
+        let data = std::fs::read_to_string(self.path_for(wiki.as_str(), title)).ok()?;
+        let cached: CachedPage = serde_json::from_str(&data).ok()?;
+        // Discard copies that were edited on disk or belong to another page
+        (cached.wiki == wiki.as_str()
+            && cached.title == title
+            && content_hash(&cached.content) == cached.sha256)
+            .then_some(cached)
+    }
+
+    fn write(&self, page: &CachedPage) -> Result<(), StorageError> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(&page.wiki, &page.title);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(page)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Load the rules on `title` at the wiki whose API is `wiki`, calling
+    /// `fetch` for the page content when there is no fresh cached copy. A
+    /// stale copy is used if fetching fails.
+    pub async fn load<F, Fut>(
+        &self,
+        wiki: &Url,
+        title: &str,
+        options: &RuleLibraryOptions,
+        fetch: F,
+    ) -> Result<LoadedRules, StorageError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, String>>,
+    {
+        let cached = self
+            .read(wiki, title)
+            .filter(|page| check_pin(title, &page.sha256, options).is_ok());
+        if let Some(page) = &cached {
+            let age = Utc::now().signed_duration_since(page.fetched_at);
+            if age.to_std().is_ok_and(|age| age < options.refresh) {
+                return loaded(title, page, true);
+            }
+        }
+
+        match fetch().await {
+            Ok(content) => {
+                let page = CachedPage {
+                    wiki: wiki.to_string(),
+                    title: title.to_string(),
+                    sha256: content_hash(&content),
+                    fetched_at: Utc::now(),
+                    content,
+                };
+                check_pin(title, &page.sha256, options)?;
+                let rules = loaded(title, &page, false)?;
+                self.write(&page)?;
+                Ok(rules)
+            }
+            Err(e) => match &cached {
+                Some(page) => {
+                    tracing::warn!(
+                        "Failed to fetch {}: {}; using copy from {}",
+                        title,
+                        e,
+                        page.fetched_at
+                    );
+                    loaded(title, page, true)
+                }
+                None => Err(StorageError::RemoteFetch(format!("{}: {}", title, e))),
+            },
+        }
+    }
+}
+
+fn loaded(title: &str, page: &CachedPage, from_cache: bool) -> Result<LoadedRules, StorageError> {
+    Ok(LoadedRules {
+        rules: parse_rules(title, &page.content)?,
+        sha256: page.sha256.clone(),
+        fetched_at: page.fetched_at,
+        from_cache,
+    })
+}
+
+fn check_pin(title: &str, sha256: &str, options: &RuleLibraryOptions) -> Result<(), StorageError> {
+    let Some(pin) = &options.pin else {
+        return Ok(());
+    };
+    let expected = pin
+        .trim()
+        .trim_start_matches("sha256:")
+        .to_ascii_lowercase();
+    if expected == sha256 {
+        Ok(())
+    } else {
+        Err(StorageError::HashMismatch {
+            title: title.to_string(),
+            expected,
+            found: sha256.to_string(),
+        })
+    }
+}
+
+/// Default cache directory, `~/.awb-rs/rule-cache`
+pub fn default_cache_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".awb-rs")
+        .join("rule-cache")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    const RULES_JSON: &str = r#"{"rules":[{
+        "id": "00000000-0000-0000-0000-000000000001",
+        "enabled": true,
+        "order": 0,
+        "kind": { "Plain": { "find": "teh", "replace": "the", "case_sensitive": true } },
+        "comment_fragment": null
+    }]}"#;
+
+    fn wiki() -> Url {
+        Url::parse("https://en.wikipedia.org/w/api.php").unwrap()
+    }
+
+    fn no_refresh() -> RuleLibraryOptions {
+        RuleLibraryOptions {
+            pin: None,
+            refresh: Duration::from_secs(3600),
+        }
+    }
+
+    #[test]
+    fn test_profile_source_parse() {
+        assert_eq!(
+            "wiki:User:Foo/awb-rules.json".parse::<ProfileSource>(),
+            Ok(ProfileSource::Wiki("User:Foo/awb-rules.json".to_string()))
+        );
+        assert_eq!(
+            "profile.toml".parse::<ProfileSource>(),
+            Ok(ProfileSource::File(PathBuf::from("profile.toml")))
+        );
+        assert!("wiki:".parse::<ProfileSource>().is_err());
+    }
+
+    #[test]
+    fn test_parse_rules_by_extension() {
+        let rules = parse_rules("User:Foo/rules.json", RULES_JSON).unwrap();
+        assert_eq!(rules.rules.len(), 1);
+
+        let toml = toml::to_string(&rules).unwrap();
+        let from_toml = parse_rules("User:Foo/rules.TOML", &toml).unwrap();
+        assert_eq!(from_toml.rules.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetches_then_uses_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache = RuleLibraryCache::new(dir.path());
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            async { Ok(RULES_JSON.to_string()) }
+        };
+
+        let first = cache
+            .load(&wiki(), "User:Foo/r.json", &no_refresh(), fetch)
+            .await
+            .unwrap();
+        assert!(!first.from_cache);
+        assert_eq!(first.sha256, content_hash(RULES_JSON));
+
+        let second = cache
+            .load(&wiki(), "User:Foo/r.json", &no_refresh(), fetch)
+            .await
+            .unwrap();
+        assert!(second.from_cache);
+        assert_eq!(second.rules.rules.len(), 1);
+        assert_eq!(fetches.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_refetches_when_stale_and_falls_back_on_error() {
+        let dir = TempDir::new().unwrap();
+        let cache = RuleLibraryCache::new(dir.path());
+        let options = RuleLibraryOptions {
+            pin: None,
+            refresh: Duration::ZERO,
+        };
+
+        cache
+            .load(&wiki(), "User:Foo/r.json", &options, || async {
+                Ok(RULES_JSON.to_string())
+            })
+            .await
+            .unwrap();
+        let stale = cache
+            .load(&wiki(), "User:Foo/r.json", &options, || async {
+                Err("offline".to_string())
+            })
+            .await
+            .unwrap();
+        assert!(stale.from_cache);
+
+        let missing = cache
+            .load(&wiki(), "User:Foo/other.json", &options, || async {
+                Err("offline".to_string())
+            })
+            .await;
+        assert!(matches!(missing, Err(StorageError::RemoteFetch(_))));
+    }
+
+    #[tokio::test]
+    async fn test_pin_mismatch_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        let cache = RuleLibraryCache::new(dir.path());
+        let pinned = RuleLibraryOptions {
+            pin: Some(format!(
+                "sha256:{}",
+                content_hash(RULES_JSON).to_uppercase()
+            )),
+            ..no_refresh()
+        };
+        assert!(
+            cache
+                .load(&wiki(), "User:Foo/r.json", &pinned, || async {
+                    Ok(RULES_JSON.to_string())
+                })
+                .await
+                .is_ok()
+        );
+
+        let wrong = RuleLibraryOptions {
+            pin: Some(content_hash("something else")),
+            ..no_refresh()
+        };
+        let result = cache
+            .load(&wiki(), "User:Foo/r.json", &wrong, || async {
+                Ok(RULES_JSON.to_string())
+            })
+            .await;
+        assert!(matches!(result, Err(StorageError::HashMismatch { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_rules_are_not_cached() {
+        let dir = TempDir::new().unwrap();
+        let cache = RuleLibraryCache::new(dir.path());
+        let result = cache
+            .load(&wiki(), "User:Foo/r.json", &no_refresh(), || async {
+                Ok("not json".to_string())
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(cache.read(&wiki(), "User:Foo/r.json").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cache_is_per_wiki() {
+        let dir = TempDir::new().unwrap();
+        let cache = RuleLibraryCache::new(dir.path());
+        let other = Url::parse("https://de.wikipedia.org/w/api.php").unwrap();
+        cache
+            .load(&wiki(), "User:Foo/r.json", &no_refresh(), || async {
+                Ok(RULES_JSON.to_string())
+            })
+            .await
+            .unwrap();
+
+        // The same title on another wiki is fetched from that wiki
+        let result = cache
+            .load(&other, "User:Foo/r.json", &no_refresh(), || async {
+                Ok(r#"{"rules":[]}"#.to_string())
+            })
+            .await
+            .unwrap();
+        assert!(!result.from_cache);
+        assert!(result.rules.rules.is_empty());
+
+        let first = cache
+            .load(&wiki(), "User:Foo/r.json", &no_refresh(), || async {
+                Err("offline".to_string())
+            })
+            .await
+            .unwrap();
+        assert!(first.from_cache);
+        assert_eq!(first.rules.rules.len(), 1);
+    }
+}
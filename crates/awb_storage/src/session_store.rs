@@ -1,7 +1,9 @@
 use crate::error::StorageError;
 use async_trait::async_trait;
-use awb_domain::session::SessionState;
-use std::path::PathBuf;
+use awb_domain::session::{EditDecision, SessionState};
+use awb_engine::review::{ReviewSnapshot, ReviewState, SessionStats};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 /// Reject writes to symlink targets to prevent symlink swap attacks.
 ///
@@ -20,6 +22,39 @@ fn reject_symlink(path: &std::path::Path) -> Result<(), StorageError> {
     }
 }
 
+/// Crash-safe write: write to `temp`, fsync, then atomically rename over
+/// `final_path`.
+async fn write_atomic(temp: &Path, final_path: &Path, data: &str) -> Result<(), StorageError> {
+    reject_symlink(final_path)?;
+    reject_symlink(temp)?;
+    tokio::fs::write(temp, data).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = std::fs::Permissions::from_mode(0o600);
+        tokio::fs::set_permissions(temp, perms).await?;
+    }
+    // fsync temp file to ensure data is durable before rename
+    // Note: On Windows, file syncing can be problematic due to exclusive access issues,
+    // but the rename operation itself provides atomicity guarantees.
+    #[cfg(not(windows))]
+    {
+        let file = tokio::fs::File::open(temp).await?;
+        file.sync_all().await?;
+    }
+    tokio::fs::rename(temp, final_path).await?;
+    // fsync parent directory to ensure the rename is durable (Unix only)
+    #[cfg(unix)]
+    {
+        if let Some(parent) = final_path.parent() {
+            if let Ok(dir) = tokio::fs::File::open(parent).await {
+                let _ = dir.sync_all().await;
+            }
+        }
+    }
+    Ok(())
+}
+
 #[async_trait]
 pub trait SessionStore: Send + Sync {
     async fn save(&self, session: &SessionState) -> Result<(), StorageError>;
@@ -79,35 +114,7 @@ impl SessionStore for JsonSessionStore {
             .map_err(|e| StorageError::Serialize(e.to_string()))?;
         let temp = self.temp_path(&session.session_id)?;
         let final_path = self.session_path(&session.session_id)?;
-        reject_symlink(&final_path)?;
-        reject_symlink(&temp)?;
-        // Crash-safe: write to temp, fsync, then atomic rename
-        tokio::fs::write(&temp, &json).await?;
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let perms = std::fs::Permissions::from_mode(0o600);
-            tokio::fs::set_permissions(&temp, perms).await?;
-        }
-        // fsync temp file to ensure data is durable before rename
-        // Note: On Windows, file syncing can be problematic due to exclusive access issues,
-        // but the rename operation itself provides atomicity guarantees.
-        #[cfg(not(windows))]
-        {
-            let file = tokio::fs::File::open(&temp).await?;
-            file.sync_all().await?;
-        }
-        tokio::fs::rename(&temp, &final_path).await?;
-        // fsync parent directory to ensure the rename is durable (Unix only)
-        #[cfg(unix)]
-        {
-            if let Some(parent) = final_path.parent() {
-                if let Ok(dir) = tokio::fs::File::open(parent).await {
-                    let _ = dir.sync_all().await;
-                }
-            }
-        }
-        Ok(())
+        write_atomic(&temp, &final_path, &json).await
     }
 
     async fn load(&self, id: &str) -> Result<SessionState, StorageError> {
@@ -164,6 +171,118 @@ impl SessionStore for JsonSessionStore {
     }
 }
 
+/// File extension for saved review sessions
+pub const REVIEW_SESSION_EXTENSION: &str = "awbsession";
+
+/// Current `.awbsession` format version.
+///
+/// Version 1 files are bare [`SessionState`] JSON as written by
+/// [`JsonSessionStore`]; they are migrated on load.
+pub const REVIEW_SESSION_VERSION: u32 = 2;
+
+/// An in-progress review saved to a `.awbsession` file: the session's
+/// configuration plus the review state machine, statistics and decisions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSessionFile {
+    pub version: u32,
+    pub session: SessionState,
+    pub review: ReviewSnapshot,
+}
+
+impl ReviewSessionFile {
+    pub fn new(session: SessionState, review: ReviewSnapshot) -> Self {
+        Self {
+            version: REVIEW_SESSION_VERSION,
+            session,
+            review,
+        }
+    }
+
+    /// Save to `path` with the same crash-safe write as [`JsonSessionStore`]
+    pub async fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| StorageError::Serialize(e.to_string()))?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        write_atomic(Path::new(&temp), path, &json).await
+    }
+
+    /// Load from `path`, migrating older versions
+    pub async fn load(path: &Path) -> Result<Self, StorageError> {
+        reject_symlink(path)?;
+        let data = tokio::fs::read_to_string(path).await?;
+        Self::from_json(&data)
+    }
+
+    /// Parse a session file of any supported version
+    pub fn from_json(data: &str) -> Result<Self, StorageError> {
+        let value: serde_json::Value = serde_json::from_str(data)?;
+        let version = match value.get("version") {
+            Some(v) => v
+                .as_u64()
+                .ok_or_else(|| StorageError::Deserialize("invalid session version".to_string()))?
+                as u32,
+            // Version 1 had no wrapper, only the session's own schema_version
+            None if value.get("schema_version").is_some() => 1,
+            None => {
+                return Err(StorageError::Deserialize(
+                    "not an AWB session file".to_string(),
+                ));
+            }
+        };
+        match version {
+            1 => Ok(Self::migrate_v1(serde_json::from_value(value)?)),
+            REVIEW_SESSION_VERSION => Ok(serde_json::from_value(value)?),
+            found => Err(StorageError::SchemaMismatch {
+                found,
+                expected: REVIEW_SESSION_VERSION,
+            }),
+        }
+    }
+
+    /// Reconstruct review state from a version 1 session: resume by fetching
+    /// the current page, with statistics counted from the recorded decisions.
+    fn migrate_v1(session: SessionState) -> Self {
+        let count = |wanted: fn(&EditDecision) -> bool| {
+            session
+                .decisions
+                .iter()
+                .filter(|d| wanted(&d.decision))
+                .count()
+        };
+        let stats = SessionStats {
+            total: session.page_list.len(),
+            saved: count(|d| matches!(d, EditDecision::Save)),
+            skipped: count(|d| matches!(d, EditDecision::Skip)),
+            errors: 0,
+            unchanged: 0,
+            elapsed_secs: 0.0,
+        };
+        let state = if session.current_index < session.page_list.len() {
+            ReviewState::FetchingPage {
+                index: session.current_index,
+            }
+        } else {
+            ReviewState::Completed {
+                stats: stats.clone(),
+            }
+        };
+        let review = ReviewSnapshot {
+            state,
+            page_list: session.page_list.clone(),
+            current_index: session.current_index,
+            stats,
+            skipped_pages: Default::default(),
+            unchanged_pages: Default::default(),
+            decisions: session.decisions.clone(),
+        };
+        Self::new(session, review)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,4 +325,77 @@ mod tests {
         let loaded = store.load("test123").await.unwrap();
         assert_eq!(loaded.session_id, "test123");
     }
+
+    #[tokio::test]
+    async fn test_review_session_file_roundtrip() {
+        use awb_domain::types::{Namespace, Title};
+        use awb_engine::review::{ReviewEvent, ReviewStateMachine};
+        use tempfile::TempDir;
+
+        let mut machine = ReviewStateMachine::new();
+        machine.transition(ReviewEvent::Start);
+        machine.transition(ReviewEvent::ListLoaded(vec![
+            Title::new(Namespace::MAIN, "A"),
+            Title::new(Namespace::MAIN, "B"),
+        ]));
+
+        let dir = TempDir::new().unwrap();
+        let path = dir
+            .path()
+            .join(format!("review.{}", REVIEW_SESSION_EXTENSION));
+        let file = ReviewSessionFile::new(SessionState::new("profile"), machine.snapshot());
+        file.save(&path).await.unwrap();
+
+        let loaded = ReviewSessionFile::load(&path).await.unwrap();
+        assert_eq!(loaded.version, REVIEW_SESSION_VERSION);
+        assert_eq!(loaded.session.profile_id, "profile");
+        assert_eq!(loaded.review.page_list.len(), 2);
+        assert!(matches!(
+            loaded.review.state,
+            ReviewState::FetchingPage { index: 0 }
+        ));
+    }
+
+    #[test]
+    fn test_review_session_migrates_v1() {
+        use awb_domain::session::PageDecision;
+        use awb_domain::types::{Namespace, PageId, Title};
+
+        let mut session = SessionState::new("profile");
+        session.page_list = vec![
+            Title::new(Namespace::MAIN, "A"),
+            Title::new(Namespace::MAIN, "B"),
+            Title::new(Namespace::MAIN, "C"),
+        ];
+        session.current_index = 2;
+        for decision in [EditDecision::Save, EditDecision::Skip] {
+            session.decisions.push(PageDecision {
+                page_id: PageId(1),
+                decision,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+        let v1 = serde_json::to_string(&session).unwrap();
+
+        let file = ReviewSessionFile::from_json(&v1).unwrap();
+        assert_eq!(file.version, REVIEW_SESSION_VERSION);
+        assert_eq!(file.review.stats.saved, 1);
+        assert_eq!(file.review.stats.skipped, 1);
+        assert!(matches!(
+            file.review.state,
+            ReviewState::FetchingPage { index: 2 }
+        ));
+    }
+
+    #[test]
+    fn test_review_session_rejects_newer_version() {
+        let err = ReviewSessionFile::from_json(r#"{"version": 99}"#).unwrap_err();
+        assert!(matches!(
+            err,
+            StorageError::SchemaMismatch {
+                found: 99,
+                expected: REVIEW_SESSION_VERSION
+            }
+        ));
+    }
 }
@@ -1,4 +1,4 @@
-use awb_domain::profile::{AuthMethod, Profile, ThrottlePolicy};
+use awb_domain::profile::{AuthMethod, ConnectionOptions, Profile, ThrottlePolicy};
 use awb_domain::rules::{Rule, RuleSet};
 use awb_domain::session::{SessionState, SkipCondition};
 use awb_domain::types::Namespace;
@@ -130,6 +130,7 @@ fn test_toml_config_store_preferences() {
         auto_save_interval_secs: 60,
         confirm_large_change_threshold: 1000,
         log_level: "debug".to_string(),
+        ..Preferences::default()
     };
 
     // Save preferences
@@ -174,6 +175,7 @@ fn test_toml_config_store_profiles() {
             max_retries: 3,
             backoff_base: Duration::from_secs(2),
         },
+        connection: ConnectionOptions::default(),
     };
 
     // Save profile
@@ -207,6 +209,7 @@ fn test_toml_config_store_list_profiles() {
         },
         default_namespaces: HashSet::new(),
         throttle_policy: ThrottlePolicy::default(),
+        connection: ConnectionOptions::default(),
     };
 
     let profile2 = Profile {
@@ -218,6 +221,7 @@ fn test_toml_config_store_list_profiles() {
         },
         default_namespaces: HashSet::new(),
         throttle_policy: ThrottlePolicy::default(),
+        connection: ConnectionOptions::default(),
     };
 
     store.save_profile(&profile1).unwrap();
@@ -247,6 +251,7 @@ async fn test_combined_session_and_config_storage() {
         },
         default_namespaces: HashSet::new(),
         throttle_policy: ThrottlePolicy::default(),
+        connection: ConnectionOptions::default(),
     };
     config_store.save_profile(&profile).unwrap();
 
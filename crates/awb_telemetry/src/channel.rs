@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+
+/// A tracing event captured for display, e.g. in a UI log pane
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub timestamp: DateTime<Utc>,
+    pub level: Level,
+    pub target: String,
+    /// The event's message followed by its other fields as `key=value`
+    pub message: String,
+}
+
+impl LogRecord {
+    /// One-line rendering: `HH:MM:SS LEVEL target: message`
+    pub fn format(&self) -> String {
+        format!(
+            "{} {:>5} {}: {}",
+            self.timestamp.format("%H:%M:%S"),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// Layer forwarding every event to a bounded channel.
+///
+/// Events are dropped rather than blocking the logging thread when the
+/// receiver falls behind or has gone away.
+pub struct ChannelLayer {
+    sender: SyncSender<LogRecord>,
+}
+
+/// Create a [`ChannelLayer`] and the receiver its records arrive on,
+/// buffering at most `capacity` undelivered records.
+pub fn channel_layer(capacity: usize) -> (ChannelLayer, Receiver<LogRecord>) {
+    let (sender, receiver) = sync_channel(capacity);
+    (ChannelLayer { sender }, receiver)
+}
+
+impl<S: Subscriber> Layer<S> for ChannelLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let record = LogRecord {
+            timestamp: Utc::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: awb_security::redact_registered(&visitor.finish()),
+        };
+        // Full or disconnected: drop the record
+        let _ = self.sender.try_send(record);
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.message.is_empty() {
+            self.fields.trim_start().to_string()
+        } else {
+            self.message + &self.fields
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn test_events_forwarded_with_fields() {
+        let (layer, receiver) = channel_layer(16);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(page = "Foo", "throttled for {}s", 5);
+            tracing::info!(count = 3);
+        });
+
+        let warn = receiver.try_recv().unwrap();
+        assert_eq!(warn.level, Level::WARN);
+        assert_eq!(warn.message, "throttled for 5s page=Foo");
+        assert!(warn.format().contains(" WARN "));
+
+        let info = receiver.try_recv().unwrap();
+        assert_eq!(info.message, "count=3");
+    }
+
+    #[test]
+    fn test_registered_secrets_redacted() {
+        awb_security::register_secret("channel-secret-123");
+        let (layer, receiver) = channel_layer(16);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(body = "channel-secret-123", "login failed");
+        });
+        assert_eq!(
+            receiver.try_recv().unwrap().message,
+            "login failed body=[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_full_channel_drops_events() {
+        let (layer, receiver) = channel_layer(1);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("first");
+            tracing::info!("second");
+        });
+        assert_eq!(receiver.try_recv().unwrap().message, "first");
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -1,7 +1,17 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize)]
+/// Version of the JSON layout of [`EventRecord`]. Bump it when a field is
+/// renamed or removed or changes type; adding an optional field does not
+/// need a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Serialized as an object whose `type` names the variant, e.g.
+/// `{"type":"PageProcessed","title":"Foo",...}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum TelemetryEvent {
     SessionStarted {
         profile: String,
@@ -11,6 +21,12 @@ pub enum TelemetryEvent {
         title: String,
         outcome: String,
         duration_ms: u64,
+        /// Size of the page's wikitext when it was fetched
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size_bytes: Option<u64>,
+        /// Metadata plugins set on the page, e.g. a review classification
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
         timestamp: DateTime<Utc>,
     },
     RuleApplied {
@@ -24,8 +40,34 @@ pub enum TelemetryEvent {
         duration_ms: u64,
         timestamp: DateTime<Utc>,
     },
+    EditSaved {
+        title: String,
+        revid: Option<u64>,
+        summary: String,
+        /// Change tags the edit was saved with
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+        timestamp: DateTime<Utc>,
+    },
+    Throttled {
+        /// What the wait is for, e.g. `edit_delay` or `maxlag`
+        reason: String,
+        wait_ms: u64,
+        timestamp: DateTime<Utc>,
+    },
+    /// The wiki refused an edit because it is read-only; the run pauses
+    /// for `wait_ms` before trying again
+    ReadOnly {
+        /// Reason given by the wiki, e.g. database maintenance
+        reason: String,
+        wait_ms: u64,
+        timestamp: DateTime<Utc>,
+    },
     Warning {
         message: String,
+        /// Page the warning is about, if any
+        #[serde(default)]
+        title: Option<String>,
         timestamp: DateTime<Utc>,
     },
     Error {
@@ -43,7 +85,130 @@ pub enum TelemetryEvent {
     },
 }
 
+/// Coarse category of a [`TelemetryEvent`], used to filter exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Session,
+    Page,
+    Edit,
+    Rule,
+    Api,
+    Warning,
+    Error,
+    Throttle,
+}
+
+impl EventKind {
+    pub const ALL: [Self; 8] = [
+        Self::Session,
+        Self::Page,
+        Self::Edit,
+        Self::Rule,
+        Self::Api,
+        Self::Warning,
+        Self::Error,
+        Self::Throttle,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Session => "session",
+            Self::Page => "page",
+            Self::Edit => "edit",
+            Self::Rule => "rule",
+            Self::Api => "api",
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Throttle => "throttle",
+        }
+    }
+}
+
+impl std::fmt::Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|k| k.as_str()).collect();
+                format!("unknown event type '{}': expected {}", s, names.join("|"))
+            })
+    }
+}
+
+/// An event as exported: the event's fields plus the schema version, e.g.
+/// `{"schema_version":1,"type":"SessionStarted","profile":"enwiki",...}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub event: TelemetryEvent,
+}
+
+impl From<TelemetryEvent> for EventRecord {
+    fn from(event: TelemetryEvent) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            event,
+        }
+    }
+}
+
 impl TelemetryEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::SessionStarted { .. } | Self::SessionCompleted { .. } => EventKind::Session,
+            Self::PageProcessed { .. } => EventKind::Page,
+            Self::EditSaved { .. } => EventKind::Edit,
+            Self::RuleApplied { .. } => EventKind::Rule,
+            Self::ApiCall { .. } => EventKind::Api,
+            Self::Warning { .. } => EventKind::Warning,
+            Self::Error { .. } => EventKind::Error,
+            Self::Throttled { .. } | Self::ReadOnly { .. } => EventKind::Throttle,
+        }
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            Self::SessionStarted { timestamp, .. }
+            | Self::PageProcessed { timestamp, .. }
+            | Self::RuleApplied { timestamp, .. }
+            | Self::ApiCall { timestamp, .. }
+            | Self::EditSaved { timestamp, .. }
+            | Self::Throttled { timestamp, .. }
+            | Self::ReadOnly { timestamp, .. }
+            | Self::Warning { timestamp, .. }
+            | Self::Error { timestamp, .. }
+            | Self::SessionCompleted { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Page the event is about, if any
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            Self::PageProcessed { title, .. } | Self::EditSaved { title, .. } => Some(title),
+            Self::Warning { title, .. } => title.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Metadata plugins set on the page the event is about
+    pub fn metadata(&self) -> Option<&BTreeMap<String, String>> {
+        match self {
+            Self::PageProcessed { metadata, .. } => Some(metadata),
+            _ => None,
+        }
+    }
+
     pub fn session_started(profile: impl Into<String>) -> Self {
         Self::SessionStarted {
             profile: profile.into(),
@@ -67,3 +232,68 @@ impl TelemetryEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_json_is_flat_and_versioned() {
+        let timestamp = "2024-05-01T12:00:00Z".parse().unwrap();
+        let record = EventRecord::from(TelemetryEvent::EditSaved {
+            title: "Foo".to_string(),
+            revid: Some(42),
+            summary: "typo".to_string(),
+            tags: Vec::new(),
+            timestamp,
+        });
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schema_version": 1,
+                "type": "EditSaved",
+                "title": "Foo",
+                "revid": 42,
+                "summary": "typo",
+                "timestamp": "2024-05-01T12:00:00Z",
+            })
+        );
+        let back: EventRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(back, record);
+    }
+
+    #[test]
+    fn test_edit_tags_are_optional() {
+        let timestamp = "2024-05-01T12:00:00Z".parse().unwrap();
+        let event = TelemetryEvent::EditSaved {
+            title: "Foo".to_string(),
+            revid: Some(42),
+            summary: "typo".to_string(),
+            tags: vec!["awb-rs".to_string()],
+            timestamp,
+        };
+        let json = serde_json::to_value(EventRecord::from(event.clone())).unwrap();
+        assert_eq!(json["tags"], serde_json::json!(["awb-rs"]));
+
+        // Records written before edits were tagged still read
+        let old: EventRecord = serde_json::from_value(serde_json::json!({
+            "schema_version": 1,
+            "type": "EditSaved",
+            "title": "Foo",
+            "revid": 42,
+            "summary": "typo",
+            "timestamp": "2024-05-01T12:00:00Z",
+        }))
+        .unwrap();
+        assert!(matches!(old.event, TelemetryEvent::EditSaved { tags, .. } if tags.is_empty()));
+    }
+
+    #[test]
+    fn test_event_kind_parse() {
+        for kind in EventKind::ALL {
+            assert_eq!(kind.as_str().parse::<EventKind>().unwrap(), kind);
+        }
+        assert!("pages".parse::<EventKind>().is_err());
+    }
+}
@@ -1,5 +1,7 @@
-use crate::events::TelemetryEvent;
-use std::io::Write;
+use crate::events::{EventKind, EventRecord, SCHEMA_VERSION, TelemetryEvent};
+use crate::redact::RedactingWriter;
+use chrono::{DateTime, Utc};
+use std::io::{BufRead, Write};
 
 pub enum ExportFormat {
     Csv,
@@ -7,15 +9,69 @@ pub enum ExportFormat {
     Json,
 }
 
+/// Which events [`export_log_filtered`] writes; the default keeps everything
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    /// Event types to keep (empty = all)
+    pub kinds: Vec<EventKind>,
+    /// Keep events at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Keep events before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Keep only events about this page
+    pub title: Option<String>,
+    /// Keep only page events with this metadata, written `key` or
+    /// `key=value`
+    pub metadata: Option<String>,
+}
+
+impl ExportFilter {
+    pub fn matches(&self, event: &TelemetryEvent) -> bool {
+        let timestamp = event.timestamp();
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind()))
+            && self.since.is_none_or(|since| timestamp >= since)
+            && self.until.is_none_or(|until| timestamp < until)
+            && self
+                .title
+                .as_deref()
+                .is_none_or(|title| event.title() == Some(title))
+            && self.metadata.as_deref().is_none_or(|wanted| {
+                let (key, value) = match wanted.split_once('=') {
+                    Some((key, value)) => (key, Some(value)),
+                    None => (wanted, None),
+                };
+                event
+                    .metadata()
+                    .and_then(|metadata| metadata.get(key))
+                    .is_some_and(|v| value.is_none_or(|value| v == value))
+            })
+    }
+}
+
 pub fn export_log(
     events: &[TelemetryEvent],
     format: ExportFormat,
     writer: &mut dyn Write,
 ) -> Result<(), std::io::Error> {
+    export_log_filtered(events, format, &ExportFilter::default(), writer)
+}
+
+/// Write the events `filter` keeps. JSON output has one [`EventRecord`] per
+/// line, laid out as described by [`json_schema`](crate::schema::json_schema).
+pub fn export_log_filtered(
+    events: &[TelemetryEvent],
+    format: ExportFormat,
+    filter: &ExportFilter,
+    writer: &mut dyn Write,
+) -> Result<(), std::io::Error> {
+    // Registered secrets never reach the exported file
+    let mut writer = RedactingWriter::new(writer);
+    let writer = &mut writer;
+    let events = events.iter().filter(|event| filter.matches(event));
     match format {
         ExportFormat::Json => {
             for event in events {
-                serde_json::to_writer(&mut *writer, event)?;
+                serde_json::to_writer(&mut *writer, &EventRecord::from(event.clone()))?;
                 writeln!(writer)?;
             }
         }
@@ -27,10 +83,151 @@ pub fn export_log(
         ExportFormat::Csv => {
             writeln!(writer, "type,timestamp,details")?;
             for event in events {
-                let json = serde_json::to_string(event).unwrap_or_default();
-                writeln!(writer, "{}", json)?;
+                let record = serde_json::to_value(EventRecord::from(event.clone()))?;
+                writeln!(
+                    writer,
+                    "{},{},\"{}\"",
+                    record["type"].as_str().unwrap_or_default(),
+                    event.timestamp().to_rfc3339(),
+                    record.to_string().replace('"', "\"\"")
+                )?;
             }
         }
     }
-    Ok(())
+    writer.flush()
+}
+
+/// Read events from a JSON export, one record per line. Records written by
+/// a newer schema version are refused rather than misread.
+pub fn read_events(reader: impl BufRead) -> Result<Vec<TelemetryEvent>, std::io::Error> {
+    let invalid = |line: usize, message: String| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("line {}: {}", line, message),
+        )
+    };
+
+    let mut events = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: serde_json::Value =
+            serde_json::from_str(&line).map_err(|e| invalid(index + 1, e.to_string()))?;
+        let version = value["schema_version"].as_u64().unwrap_or(0);
+        if version > u64::from(SCHEMA_VERSION) {
+            return Err(invalid(
+                index + 1,
+                format!(
+                    "schema version {} is newer than supported version {}",
+                    version, SCHEMA_VERSION
+                ),
+            ));
+        }
+        let record: EventRecord =
+            serde_json::from_value(value).map_err(|e| invalid(index + 1, e.to_string()))?;
+        events.push(record.event);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    fn events() -> Vec<TelemetryEvent> {
+        vec![
+            TelemetryEvent::SessionStarted {
+                profile: "enwiki".into(),
+                timestamp: at(9),
+            },
+            TelemetryEvent::PageProcessed {
+                title: "Foo".into(),
+                outcome: "edited".into(),
+                duration_ms: 10,
+                size_bytes: None,
+                metadata: [("review".to_string(), "needs human review".to_string())].into(),
+                timestamp: at(10),
+            },
+            TelemetryEvent::Warning {
+                message: "large change".into(),
+                title: Some("Bar".into()),
+                timestamp: at(11),
+            },
+            TelemetryEvent::EditSaved {
+                title: "Foo".into(),
+                revid: Some(7),
+                summary: "typo".into(),
+                tags: Vec::new(),
+                timestamp: at(12),
+            },
+        ]
+    }
+
+    fn export(filter: &ExportFilter) -> Vec<TelemetryEvent> {
+        let mut buf = Vec::new();
+        export_log_filtered(&events(), ExportFormat::Json, filter, &mut buf).unwrap();
+        read_events(buf.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_filter_by_kind_time_and_title() {
+        assert_eq!(export(&ExportFilter::default()), events());
+
+        let pages = export(&ExportFilter {
+            kinds: vec![EventKind::Page, EventKind::Edit],
+            ..ExportFilter::default()
+        });
+        assert_eq!(pages.len(), 2);
+
+        let window = export(&ExportFilter {
+            since: Some(at(10)),
+            until: Some(at(12)),
+            ..ExportFilter::default()
+        });
+        assert_eq!(window, events()[1..3].to_vec());
+
+        let foo = export(&ExportFilter {
+            title: Some("Foo".into()),
+            ..ExportFilter::default()
+        });
+        assert!(foo.iter().all(|e| e.title() == Some("Foo")));
+        assert_eq!(foo.len(), 2);
+
+        let flagged = |metadata: &str| {
+            export(&ExportFilter {
+                metadata: Some(metadata.into()),
+                ..ExportFilter::default()
+            })
+        };
+        assert_eq!(flagged("review"), events()[1..2].to_vec());
+        assert_eq!(flagged("review=needs human review").len(), 1);
+        assert!(flagged("review=ok").is_empty());
+    }
+
+    #[test]
+    fn test_read_refuses_newer_schema() {
+        let line = format!(
+            r#"{{"schema_version":{},"type":"SessionStarted","profile":"x","timestamp":"2024-05-01T00:00:00Z"}}"#,
+            SCHEMA_VERSION + 1
+        );
+        let err = read_events(line.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_csv_rows_are_quoted() {
+        let mut buf = Vec::new();
+        export_log(&events()[..1], ExportFormat::Csv, &mut buf).unwrap();
+        let output = String::from_utf8(buf).unwrap();
+        let row = output.lines().nth(1).unwrap();
+        assert!(row.starts_with("SessionStarted,2024-05-01T09:00:00+00:00,\"{"));
+        assert!(row.contains(r#"""profile"":""enwiki"""#));
+    }
 }
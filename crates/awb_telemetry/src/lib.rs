@@ -1,7 +1,15 @@
+pub mod channel;
 pub mod events;
 pub mod export;
+pub mod redact;
+pub mod schema;
 pub mod setup;
+pub mod sqlite;
 
-pub use events::TelemetryEvent;
-pub use export::{ExportFormat, export_log};
+pub use channel::{ChannelLayer, LogRecord, channel_layer};
+pub use events::{EventKind, EventRecord, SCHEMA_VERSION, TelemetryEvent};
+pub use export::{ExportFilter, ExportFormat, export_log, export_log_filtered, read_events};
+pub use redact::{Redacting, RedactingWriter, redacting};
+pub use schema::json_schema;
 pub use setup::{TelemetryConfig, TelemetryError, init_telemetry};
+pub use sqlite::{QueryResult, SqliteError, export_sqlite, query_sqlite};
@@ -0,0 +1,134 @@
+//! Keeps registered secrets out of log output.
+//!
+//! A tracing layer cannot rewrite an event for the layers next to it, so
+//! redaction happens where text leaves the process: [`Redacting`] wraps the
+//! writer of a `fmt` layer, and [`ChannelLayer`](crate::ChannelLayer) and
+//! [`export_log`](crate::export_log) redact what they emit. Secrets come from
+//! [`awb_security::register_secret`], which the credential stores call for
+//! every secret they hand out.
+
+use awb_security::redact_registered;
+use std::io::{self, Write};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// [`MakeWriter`] whose writers strip registered secrets from everything
+/// written through them, including event and span fields
+#[derive(Debug, Clone)]
+pub struct Redacting<M> {
+    inner: M,
+}
+
+/// Wrap `make_writer`, e.g. `redacting(std::io::stdout)`
+pub fn redacting<M>(make_writer: M) -> Redacting<M> {
+    Redacting { inner: make_writer }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter::new(self.inner.make_writer())
+    }
+}
+
+/// Writer buffering its input and passing it on redacted when flushed or
+/// dropped, so a secret split across writes is still caught.
+///
+/// `fmt` layers make one writer per event, so each event is redacted whole.
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+        }
+    }
+
+    fn emit(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let text = redact_registered(&String::from_utf8_lossy(&self.buf));
+        self.buf.clear();
+        self.inner.write_all(text.as_bytes())
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.emit()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::prelude::*;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fmt_layer_output_is_redacted() {
+        awb_security::register_secret("hunter2-bot-password");
+        let captured = Captured::default();
+        let sink = captured.clone();
+        let layer = tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(redacting(move || sink.clone()));
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("login", password = "hunter2-bot-password");
+            let _guard = span.enter();
+            tracing::warn!(
+                token = "hunter2-bot-password",
+                "login with hunter2-bot-password"
+            );
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("login with [REDACTED]"), "{}", output);
+        assert!(!output.contains("hunter2"), "{}", output);
+    }
+
+    #[test]
+    fn test_secret_split_across_writes_is_redacted() {
+        awb_security::register_secret("split-secret-value");
+        let mut out = Vec::new();
+        {
+            let mut writer = RedactingWriter::new(&mut out);
+            writer.write_all(b"value=split-sec").unwrap();
+            writer.write_all(b"ret-value\n").unwrap();
+        }
+        assert_eq!(out, b"value=[REDACTED]\n");
+    }
+}
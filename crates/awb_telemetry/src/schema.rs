@@ -0,0 +1,197 @@
+//! JSON Schema of exported telemetry records.
+
+use crate::events::SCHEMA_VERSION;
+use serde_json::{Map, Value, json};
+
+/// Fields of each event type besides `schema_version`, `type` and
+/// `timestamp`, with their JSON type; a trailing `?` marks a field that may
+/// be null or missing
+const EVENT_FIELDS: &[(&str, &[(&str, &str)])] = &[
+    ("SessionStarted", &[("profile", "string")]),
+    (
+        "PageProcessed",
+        &[
+            ("title", "string"),
+            ("outcome", "string"),
+            ("duration_ms", "integer"),
+            ("size_bytes", "integer?"),
+            ("metadata", "object?"),
+        ],
+    ),
+    (
+        "RuleApplied",
+        &[("rule_id", "string"), ("matches", "integer")],
+    ),
+    (
+        "ApiCall",
+        &[
+            ("endpoint", "string"),
+            ("status", "integer"),
+            ("duration_ms", "integer"),
+        ],
+    ),
+    (
+        "EditSaved",
+        &[
+            ("title", "string"),
+            ("revid", "integer?"),
+            ("summary", "string"),
+            ("tags", "array?"),
+        ],
+    ),
+    ("Throttled", &[("reason", "string"), ("wait_ms", "integer")]),
+    ("ReadOnly", &[("reason", "string"), ("wait_ms", "integer")]),
+    ("Warning", &[("message", "string"), ("title", "string?")]),
+    ("Error", &[("message", "string"), ("context", "string")]),
+    (
+        "SessionCompleted",
+        &[
+            ("total", "integer"),
+            ("saved", "integer"),
+            ("skipped", "integer"),
+            ("errors", "integer"),
+            ("elapsed_secs", "number"),
+        ],
+    ),
+];
+
+/// JSON Schema (draft 2020-12) that every line of a JSON export validates
+/// against
+pub fn json_schema() -> Value {
+    let variants: Vec<Value> = EVENT_FIELDS
+        .iter()
+        .map(|(name, fields)| {
+            let mut properties = Map::new();
+            properties.insert("schema_version".into(), json!({ "const": SCHEMA_VERSION }));
+            properties.insert("type".into(), json!({ "const": name }));
+            properties.insert(
+                "timestamp".into(),
+                json!({ "type": "string", "format": "date-time" }),
+            );
+            let mut required = vec!["schema_version", "type", "timestamp"];
+            for (field, ty) in fields.iter() {
+                match ty.strip_suffix('?') {
+                    Some(ty) => {
+                        properties.insert(field.to_string(), json!({ "type": [ty, "null"] }));
+                    }
+                    None => {
+                        properties.insert(field.to_string(), json!({ "type": ty }));
+                        required.push(field);
+                    }
+                }
+            }
+            json!({
+                "title": name,
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false,
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("AWB-RS telemetry event, schema version {}", SCHEMA_VERSION),
+        "oneOf": variants,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{EventRecord, TelemetryEvent};
+    use chrono::Utc;
+    use std::collections::HashSet;
+
+    fn samples() -> Vec<TelemetryEvent> {
+        let timestamp = Utc::now();
+        vec![
+            TelemetryEvent::session_started("enwiki"),
+            TelemetryEvent::PageProcessed {
+                title: "Foo".into(),
+                outcome: "edited".into(),
+                duration_ms: 5,
+                size_bytes: Some(2048),
+                metadata: [("review".to_string(), "ok".to_string())].into(),
+                timestamp,
+            },
+            TelemetryEvent::RuleApplied {
+                rule_id: "r1".into(),
+                matches: 2,
+                timestamp,
+            },
+            TelemetryEvent::ApiCall {
+                endpoint: "edit".into(),
+                status: 200,
+                duration_ms: 40,
+                timestamp,
+            },
+            TelemetryEvent::EditSaved {
+                title: "Foo".into(),
+                revid: None,
+                summary: "typo".into(),
+                tags: vec!["awb-rs".into()],
+                timestamp,
+            },
+            TelemetryEvent::Throttled {
+                reason: "maxlag".into(),
+                wait_ms: 5000,
+                timestamp,
+            },
+            TelemetryEvent::ReadOnly {
+                reason: "database maintenance".into(),
+                wait_ms: 60_000,
+                timestamp,
+            },
+            TelemetryEvent::Warning {
+                message: "large change".into(),
+                title: Some("Foo".into()),
+                timestamp,
+            },
+            TelemetryEvent::Error {
+                message: "timeout".into(),
+                context: "api_call".into(),
+                timestamp,
+            },
+            TelemetryEvent::session_completed(1, 1, 0, 0, 2.5),
+        ]
+    }
+
+    #[test]
+    fn test_every_event_matches_its_schema() {
+        let schema = json_schema();
+        let variants = schema["oneOf"].as_array().unwrap();
+        let mut seen = HashSet::new();
+
+        for event in samples() {
+            let json = serde_json::to_value(EventRecord::from(event)).unwrap();
+            let object = json.as_object().unwrap();
+            let name = object["type"].as_str().unwrap();
+            let variant = variants
+                .iter()
+                .find(|v| v["title"] == name)
+                .unwrap_or_else(|| panic!("no schema for {}", name));
+
+            let properties = variant["properties"].as_object().unwrap();
+            for key in object.keys() {
+                assert!(
+                    properties.contains_key(key),
+                    "{}.{} not in schema",
+                    name,
+                    key
+                );
+            }
+            for key in variant["required"].as_array().unwrap() {
+                assert!(
+                    object.contains_key(key.as_str().unwrap()),
+                    "{} lacks {}",
+                    name,
+                    key
+                );
+            }
+            seen.insert(name.to_string());
+        }
+        assert_eq!(seen.len(), variants.len());
+    }
+}
@@ -28,6 +28,7 @@ impl Default for TelemetryConfig {
 }
 
 pub fn init_telemetry(config: &TelemetryConfig) -> Result<(), TelemetryError> {
+    use crate::redact::redacting;
     use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
     let filter =
@@ -35,7 +36,11 @@ pub fn init_telemetry(config: &TelemetryConfig) -> Result<(), TelemetryError> {
 
     tracing_subscriber::registry()
         .with(filter)
-        .with(fmt::layer().with_target(true))
+        .with(
+            fmt::layer()
+                .with_target(true)
+                .with_writer(redacting(std::io::stdout)),
+        )
         .try_init()
         .map_err(|e| TelemetryError::Init(e.to_string()))?;
 
@@ -0,0 +1,195 @@
+//! SQLite export of telemetry events, so long histories can be analysed
+//! with SQL instead of scripts over JSON lines.
+
+use crate::events::{EventRecord, TelemetryEvent};
+use crate::export::ExportFilter;
+use awb_security::redact_registered;
+use chrono::SecondsFormat;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, params};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SqliteError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// One row per event. `details` holds the full JSON record, so fields
+/// without a column are reachable through `json_extract(details, '$.field')`.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS events (
+        id INTEGER PRIMARY KEY,
+        schema_version INTEGER NOT NULL,
+        type TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        title TEXT,
+        details TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_timestamp ON events (timestamp);
+    CREATE INDEX IF NOT EXISTS events_kind ON events (kind);
+";
+
+/// Append the events `filter` keeps to the SQLite database at `path`,
+/// creating it if needed. Returns the number of rows written.
+pub fn export_sqlite(
+    events: &[TelemetryEvent],
+    filter: &ExportFilter,
+    path: &Path,
+) -> Result<usize, SqliteError> {
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    let mut written = 0;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO events (schema_version, type, kind, timestamp, title, details)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for event in events.iter().filter(|event| filter.matches(event)) {
+            let record = serde_json::to_value(EventRecord::from(event.clone()))?;
+            insert.execute(params![
+                record["schema_version"].as_u64().unwrap_or_default(),
+                record["type"].as_str().unwrap_or_default(),
+                event.kind().as_str(),
+                // Fixed-width UTC so text order is time order and SQLite's
+                // date functions can read it
+                event
+                    .timestamp()
+                    .to_rfc3339_opts(SecondsFormat::Millis, true),
+                event.title(),
+                // Registered secrets never reach the exported file
+                redact_registered(&record.to_string()),
+            ])?;
+            written += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(written)
+}
+
+/// Column names and stringified rows of a query result
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Run `sql` against an exported database. The database is opened
+/// read-only, so a query can never change the history it reads.
+pub fn query_sqlite(path: &Path, sql: &str) -> Result<QueryResult, SqliteError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(sql)?;
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut cursor = stmt.query([])?;
+    while let Some(row) = cursor.next()? {
+        let mut values = Vec::with_capacity(columns.len());
+        for index in 0..columns.len() {
+            values.push(match row.get_ref(index)? {
+                ValueRef::Null => "NULL".to_string(),
+                ValueRef::Integer(value) => value.to_string(),
+                ValueRef::Real(value) => value.to_string(),
+                ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+                ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()),
+            });
+        }
+        rows.push(values);
+    }
+
+    Ok(QueryResult { columns, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventKind;
+    use chrono::{TimeZone, Utc};
+
+    fn edit(title: &str, day: u32) -> TelemetryEvent {
+        TelemetryEvent::EditSaved {
+            title: title.into(),
+            revid: Some(u64::from(day)),
+            summary: "typo".into(),
+            tags: Vec::new(),
+            timestamp: Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap(),
+        }
+    }
+
+    fn history() -> Vec<TelemetryEvent> {
+        vec![
+            TelemetryEvent::session_started("enwiki"),
+            edit("Foo", 1),
+            edit("Bar", 1),
+            edit("Foo", 2),
+            TelemetryEvent::Error {
+                message: "timeout".into(),
+                context: "api_call".into(),
+                timestamp: Utc::now(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_edits_per_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("events.db");
+
+        let filter = ExportFilter {
+            kinds: vec![EventKind::Edit],
+            ..ExportFilter::default()
+        };
+        assert_eq!(export_sqlite(&history(), &filter, &db).unwrap(), 3);
+
+        let result = query_sqlite(
+            &db,
+            "SELECT date(timestamp) AS day, count(*) AS edits FROM events
+             GROUP BY day ORDER BY day",
+        )
+        .unwrap();
+        assert_eq!(result.columns, vec!["day", "edits"]);
+        assert_eq!(
+            result.rows,
+            vec![vec!["2024-05-01", "2"], vec!["2024-05-02", "1"]]
+        );
+
+        let revid = query_sqlite(
+            &db,
+            "SELECT json_extract(details, '$.revid') FROM events WHERE title = 'Bar'",
+        )
+        .unwrap();
+        assert_eq!(revid.rows, vec![vec!["1"]]);
+    }
+
+    #[test]
+    fn test_export_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("events.db");
+
+        export_sqlite(&history(), &ExportFilter::default(), &db).unwrap();
+        export_sqlite(&history(), &ExportFilter::default(), &db).unwrap();
+
+        let result = query_sqlite(&db, "SELECT count(*) FROM events").unwrap();
+        assert_eq!(result.rows, vec![vec!["10"]]);
+    }
+
+    #[test]
+    fn test_query_is_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("events.db");
+        export_sqlite(&history(), &ExportFilter::default(), &db).unwrap();
+
+        assert!(query_sqlite(&db, "DELETE FROM events").is_err());
+        assert!(query_sqlite(&dir.path().join("missing.db"), "SELECT 1").is_err());
+    }
+}
@@ -54,6 +54,8 @@ fn test_telemetry_all_event_variants_serialize() {
             title: "Test Page".into(),
             outcome: "saved".into(),
             duration_ms: 150,
+            size_bytes: None,
+            metadata: Default::default(),
             timestamp: chrono::Utc::now(),
         },
         TelemetryEvent::RuleApplied {
@@ -69,6 +71,7 @@ fn test_telemetry_all_event_variants_serialize() {
         },
         TelemetryEvent::Warning {
             message: "Large change detected".into(),
+            title: None,
             timestamp: chrono::Utc::now(),
         },
         TelemetryEvent::Error {
@@ -93,6 +96,8 @@ fn test_telemetry_export_json() {
             title: "Page 1".into(),
             outcome: "saved".into(),
             duration_ms: 100,
+            size_bytes: None,
+            metadata: Default::default(),
             timestamp: chrono::Utc::now(),
         },
     ];
@@ -145,6 +150,8 @@ fn test_telemetry_export_multiple_events_json() {
             title: format!("Page {}", i),
             outcome: if i % 2 == 0 { "saved" } else { "skipped" }.into(),
             duration_ms: i * 10,
+            size_bytes: None,
+            metadata: Default::default(),
             timestamp: chrono::Utc::now(),
         });
     }
@@ -193,6 +200,8 @@ fn test_telemetry_export_preserves_order() {
             title: format!("Page {}", i),
             outcome: "saved".into(),
             duration_ms: i * 100,
+            size_bytes: None,
+            metadata: Default::default(),
             timestamp: chrono::Utc::now(),
         });
     }
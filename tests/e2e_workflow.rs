@@ -66,6 +66,7 @@ fn test_full_edit_workflow_without_network() {
             max_retries: 3,
             backoff_base: Duration::from_secs(2),
         },
+        connection: Default::default(),
     };
 
     // Step 2: Build TransformEngine from profile rules
@@ -272,16 +273,12 @@ Line with trailing spaces
     assert!(plan.new_wikitext.contains("'''bold'''"));
     assert!(plan.new_wikitext.contains("''italic''"));
 
-    // Citation templates are inside {{...}} which are masked (protected) regions.
-    // The masking engine correctly prevents fixes from modifying template content,
-    // so accessdate/deadurl remain unchanged inside the template.
+    // Citation formatting asks to see templates, so deprecated parameters
+    // inside {{cite web}} are renamed
     assert!(
-        plan.new_wikitext.contains("accessdate"),
-        "Template content should be protected by masking"
-    );
-    assert!(
-        plan.new_wikitext.contains("deadurl"),
-        "Template content should be protected by masking"
+        plan.new_wikitext
+            .contains("{{cite web|access-date=2020-01-01|url-status=dead}}"),
+        "Citation formatting should see unmasked templates"
     );
 
     // General fixes should have been applied
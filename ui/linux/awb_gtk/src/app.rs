@@ -1,6 +1,11 @@
 use gtk::prelude::*;
 use gtk::{gio, glib};
 use libadwaita as adw;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+
+use awb_telemetry::LogRecord;
 
 use crate::views::main_window::MainWindow;
 
@@ -12,6 +17,15 @@ pub struct AwbApplication {
 
 impl AwbApplication {
     pub fn new() -> Self {
+        Self::build(None)
+    }
+
+    /// Create the application with its log pane fed from `receiver`
+    pub fn with_log_receiver(receiver: Receiver<LogRecord>) -> Self {
+        Self::build(Some(receiver))
+    }
+
+    fn build(log_receiver: Option<Receiver<LogRecord>>) -> Self {
         // Create the application
         let app = adw::Application::builder()
             .application_id(APP_ID)
@@ -23,17 +37,18 @@ impl AwbApplication {
             tracing::info!("AWB GTK application starting up");
         });
 
-        // Connect activate signal
-        app.connect_activate(Self::on_activate);
+        // Connect activate signal; only the first window gets the log receiver
+        let log_receiver = Rc::new(RefCell::new(log_receiver));
+        app.connect_activate(move |app| Self::on_activate(app, log_receiver.borrow_mut().take()));
 
         Self { app }
     }
 
-    fn on_activate(app: &adw::Application) {
+    fn on_activate(app: &adw::Application, log_receiver: Option<Receiver<LogRecord>>) {
         tracing::info!("Application activated");
 
         // Create and present the main window
-        let window = MainWindow::new(app);
+        let window = MainWindow::new(app, log_receiver);
         window.present();
     }
 
@@ -8,11 +8,18 @@ use gtk::glib;
 
 #[cfg(target_os = "linux")]
 fn main() -> glib::ExitCode {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+
+    // Initialize tracing, mirroring events into the log pane
+    let (log_layer, log_receiver) = awb_telemetry::channel_layer(1024);
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(fmt::layer().with_writer(awb_telemetry::redacting(std::io::stdout)))
+        .with(log_layer)
+        .init();
 
     // Create the GTK application
-    let app = app::AwbApplication::new();
+    let app = app::AwbApplication::with_log_receiver(log_receiver);
 
     // Run the application
     app.run()
@@ -107,6 +107,11 @@ impl EditorView {
         &self.container
     }
 
+    /// Buffer holding the page source being edited
+    pub fn source_buffer(&self) -> gtk::TextBuffer {
+        self.source_view.buffer()
+    }
+
     #[allow(dead_code)]
     pub fn set_text(&self, text: &str) {
         if let Ok(buffer) = self.source_view.buffer().downcast::<gtk::TextBuffer>() {
@@ -0,0 +1,129 @@
+use adw::prelude::*;
+use awb_engine::fix_config::FixClassification;
+use awb_engine::general_fixes::{FixInfo, FixRegistry};
+use awb_storage::{Preferences, TomlConfigStore};
+use gtk::prelude::*;
+use libadwaita as adw;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Preferences dialog for choosing general fixes, grouped by category.
+///
+/// Every change is written straight to the `fix_config` in Preferences.
+pub struct FixSelectionDialog {
+    dialog: adw::PreferencesDialog,
+    parent: adw::ApplicationWindow,
+}
+
+impl FixSelectionDialog {
+    pub fn new(parent: &adw::ApplicationWindow, store: Rc<TomlConfigStore>) -> Self {
+        let prefs = store.load_preferences().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load preferences: {}", e);
+            Preferences::default()
+        });
+        let prefs = Rc::new(RefCell::new(prefs));
+        let save = {
+            let prefs = prefs.clone();
+            move || {
+                if let Err(e) = store.save_preferences(&prefs.borrow()) {
+                    tracing::error!("Failed to save fix selection: {}", e);
+                }
+            }
+        };
+
+        let dialog = adw::PreferencesDialog::builder()
+            .title("General Fixes")
+            .build();
+        let page = adw::PreferencesPage::new();
+
+        // Strictness tier
+        let tier_group = adw::PreferencesGroup::new();
+        let tier_row = adw::ActionRow::builder()
+            .title("Strictness tier")
+            .subtitle("Fixes above this tier are not run")
+            .build();
+        let tier_spin = gtk::SpinButton::with_range(0.0, 3.0, 1.0);
+        tier_spin.set_valign(gtk::Align::Center);
+        tier_spin.set_value(f64::from(prefs.borrow().fix_config.strictness_tier));
+        tier_row.add_suffix(&tier_spin);
+        tier_group.add(&tier_row);
+        page.add(&tier_group);
+
+        // One group of checkboxes per category
+        let mut by_category: BTreeMap<String, Vec<FixInfo>> = BTreeMap::new();
+        for info in FixRegistry::with_defaults().fix_infos() {
+            by_category
+                .entry(info.category.clone())
+                .or_default()
+                .push(info);
+        }
+
+        let mut tiered_rows = Vec::new();
+        for (category, infos) in by_category {
+            let group = adw::PreferencesGroup::builder().title(&category).build();
+            for info in infos {
+                let row = adw::ActionRow::builder()
+                    .title(&info.display_name)
+                    .subtitle(format!(
+                        "{}\n{} · tier {}",
+                        info.description,
+                        classification_label(info.classification),
+                        info.min_tier
+                    ))
+                    .build();
+                // Checked means selected; the tier gate is shown by sensitivity
+                let check = gtk::CheckButton::builder()
+                    .active(prefs.borrow().fix_config.allows(&info.id, 0))
+                    .valign(gtk::Align::Center)
+                    .build();
+                row.add_suffix(&check);
+                row.set_activatable_widget(Some(&check));
+                row.set_sensitive(info.min_tier <= prefs.borrow().fix_config.strictness_tier);
+
+                let prefs = prefs.clone();
+                let save = save.clone();
+                let id = info.id.clone();
+                check.connect_toggled(move |check| {
+                    prefs
+                        .borrow_mut()
+                        .fix_config
+                        .set_enabled(&id, check.is_active());
+                    save();
+                });
+
+                group.add(&row);
+                tiered_rows.push((row, info.min_tier));
+            }
+            page.add(&group);
+        }
+
+        tier_spin.connect_value_changed(move |spin| {
+            let tier = spin.value_as_int().clamp(0, 3) as u8;
+            prefs.borrow_mut().fix_config.strictness_tier = tier;
+            for (row, min_tier) in &tiered_rows {
+                row.set_sensitive(*min_tier <= tier);
+            }
+            save();
+        });
+
+        dialog.add(&page);
+        Self {
+            dialog,
+            parent: parent.clone(),
+        }
+    }
+
+    pub fn present(&self) {
+        self.dialog.present(Some(&self.parent));
+    }
+}
+
+fn classification_label(classification: FixClassification) -> &'static str {
+    match classification {
+        FixClassification::Cosmetic => "Cosmetic",
+        FixClassification::Maintenance => "Maintenance",
+        FixClassification::StyleSensitive => "Style-sensitive",
+        FixClassification::Editorial => "Editorial",
+    }
+}
@@ -0,0 +1,179 @@
+use awb_telemetry::LogRecord;
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+use tracing::Level;
+
+/// Records kept for re-filtering; older ones are discarded
+const MAX_RECORDS: usize = 5000;
+/// How often new records are pulled from the channel
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Level filter choices, most to least severe
+const LEVELS: [(&str, Level); 5] = [
+    ("Error", Level::ERROR),
+    ("Warn", Level::WARN),
+    ("Info", Level::INFO),
+    ("Debug", Level::DEBUG),
+    ("Trace", Level::TRACE),
+];
+
+struct Inner {
+    records: RefCell<VecDeque<LogRecord>>,
+    /// Most verbose level shown
+    max_level: Cell<Level>,
+    text_view: gtk::TextView,
+}
+
+impl Inner {
+    fn visible<'a>(&self, records: impl Iterator<Item = &'a LogRecord>) -> String {
+        let max_level = self.max_level.get();
+        let mut text = String::new();
+        for record in records.filter(|r| r.level <= max_level) {
+            text.push_str(&record.format());
+            text.push('\n');
+        }
+        text
+    }
+
+    fn rerender(&self) {
+        let text = self.visible(self.records.borrow().iter());
+        self.text_view.buffer().set_text(&text);
+    }
+
+    fn append(&self, new: Vec<LogRecord>) {
+        let text = self.visible(new.iter());
+        let mut records = self.records.borrow_mut();
+        let overflow = (records.len() + new.len()).saturating_sub(MAX_RECORDS);
+        records.extend(new);
+        if overflow > 0 {
+            records.drain(..overflow);
+            drop(records);
+            self.rerender();
+        } else if !text.is_empty() {
+            let buffer = self.text_view.buffer();
+            buffer.insert(&mut buffer.end_iter(), &text);
+        }
+        self.text_view
+            .scroll_to_mark(&self.text_view.buffer().get_insert(), 0.0, false, 0.0, 1.0);
+    }
+}
+
+/// Live log pane showing tracing events from a channel layer, with a level
+/// filter and copy-to-clipboard.
+pub struct LogPane {
+    container: gtk::Box,
+    _inner: Rc<Inner>,
+}
+
+impl LogPane {
+    pub fn new(receiver: Option<Receiver<LogRecord>>) -> Self {
+        let container = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+
+        let toolbar = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .margin_start(5)
+            .margin_end(5)
+            .margin_top(5)
+            .margin_bottom(5)
+            .build();
+        toolbar.append(
+            &gtk::Label::builder()
+                .label("Log")
+                .hexpand(true)
+                .xalign(0.0)
+                .build(),
+        );
+
+        let level_names: Vec<&str> = LEVELS.iter().map(|(name, _)| *name).collect();
+        let level_dropdown = gtk::DropDown::from_strings(&level_names);
+        level_dropdown.set_selected(2); // Info
+        level_dropdown.set_tooltip_text(Some("Minimum level shown"));
+        toolbar.append(&level_dropdown);
+
+        let copy_button = gtk::Button::builder()
+            .icon_name("edit-copy-symbolic")
+            .tooltip_text("Copy log to clipboard")
+            .build();
+        toolbar.append(&copy_button);
+        container.append(&toolbar);
+
+        let text_view = gtk::TextView::builder()
+            .monospace(true)
+            .editable(false)
+            .cursor_visible(false)
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .build();
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&text_view)
+            .min_content_height(120)
+            .vexpand(true)
+            .build();
+        container.append(&scrolled);
+
+        let inner = Rc::new(Inner {
+            records: RefCell::new(VecDeque::new()),
+            max_level: Cell::new(Level::INFO),
+            text_view,
+        });
+
+        let weak = Rc::downgrade(&inner);
+        level_dropdown.connect_selected_notify(move |dropdown| {
+            let Some(inner) = weak.upgrade() else {
+                return;
+            };
+            if let Some((_, level)) = LEVELS.get(dropdown.selected() as usize) {
+                inner.max_level.set(*level);
+                inner.rerender();
+            }
+        });
+
+        let weak = Rc::downgrade(&inner);
+        copy_button.connect_clicked(move |button| {
+            if let Some(inner) = weak.upgrade() {
+                let buffer = inner.text_view.buffer();
+                let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+                button.clipboard().set_text(&text);
+            }
+        });
+
+        if let Some(receiver) = receiver {
+            let weak = Rc::downgrade(&inner);
+            glib::timeout_add_local(POLL_INTERVAL, move || {
+                let Some(inner) = weak.upgrade() else {
+                    return glib::ControlFlow::Break;
+                };
+                let mut new = Vec::new();
+                loop {
+                    match receiver.try_recv() {
+                        Ok(record) => new.push(record),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            inner.append(new);
+                            return glib::ControlFlow::Break;
+                        }
+                    }
+                }
+                if !new.is_empty() {
+                    inner.append(new);
+                }
+                glib::ControlFlow::Continue
+            });
+        }
+
+        Self {
+            container,
+            _inner: inner,
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+}
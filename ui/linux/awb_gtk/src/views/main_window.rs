@@ -2,8 +2,16 @@ use adw::prelude::*;
 use gtk::prelude::*;
 use gtk::{gio, glib};
 use libadwaita as adw;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+
+use awb_storage::TomlConfigStore;
+use awb_telemetry::LogRecord;
 
 use super::editor_view::EditorView;
+use super::fix_selection::FixSelectionDialog;
+use super::log_pane::LogPane;
 use super::login_dialog::LoginDialog;
 use super::page_list::PageList;
 use super::rule_editor::RuleEditor;
@@ -13,14 +21,24 @@ pub struct MainWindow {
     _page_list: PageList,
     _editor: EditorView,
     _rule_editor: RuleEditor,
+    _log_pane: LogPane,
     #[allow(dead_code)]
     status_label: gtk::Label,
     #[allow(dead_code)]
     progress_bar: gtk::ProgressBar,
 }
 
+/// Config file shared with the CLI: `~/.awb-rs/config.toml`
+fn config_path() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".awb-rs")
+        .join("config.toml")
+}
+
 impl MainWindow {
-    pub fn new(app: &adw::Application) -> Self {
+    pub fn new(app: &adw::Application, log_receiver: Option<Receiver<LogRecord>>) -> Self {
         // Create the main window
         let window = adw::ApplicationWindow::builder()
             .application(app)
@@ -47,6 +65,7 @@ impl MainWindow {
         let edit_menu = gio::Menu::new();
         edit_menu.append(Some("Apply Rules"), Some("app.apply_rules"));
         edit_menu.append(Some("Preview Changes"), Some("app.preview"));
+        edit_menu.append(Some("General Fixes..."), Some("app.fixes"));
         menu.append_submenu(Some("Edit"), &edit_menu);
 
         let help_menu = gio::Menu::new();
@@ -96,8 +115,18 @@ impl MainWindow {
         editor_box.append(editor.widget());
         center_right_paned.set_start_child(Some(&editor_box));
 
-        // Right: Rule editor
-        let rule_editor = RuleEditor::new();
+        // Right: Rule editor, previewing against the page in the editor
+        let config_store = Rc::new(TomlConfigStore::new(config_path()));
+        let profile_id = config_store
+            .load_preferences()
+            .unwrap_or_default()
+            .default_profile;
+        let rule_editor = RuleEditor::new(config_store.clone(), profile_id);
+        let rule_editor_for_page = rule_editor.clone();
+        editor.source_buffer().connect_changed(move |buffer| {
+            let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+            rule_editor_for_page.set_test_text(&text);
+        });
         let right_box = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .width_request(300)
@@ -108,6 +137,17 @@ impl MainWindow {
 
         main_paned.set_end_child(Some(&center_right_paned));
 
+        // Bottom: live log pane below the three panels
+        let log_pane = LogPane::new(log_receiver);
+        let vertical_paned = gtk::Paned::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .wide_handle(true)
+            .vexpand(true)
+            .start_child(&main_paned)
+            .end_child(log_pane.widget())
+            .resize_end_child(false)
+            .build();
+
         // Create status bar at bottom
         let status_bar = gtk::Box::builder()
             .orientation(gtk::Orientation::Horizontal)
@@ -138,7 +178,7 @@ impl MainWindow {
 
         let toolbar_view = adw::ToolbarView::new();
         toolbar_view.add_top_bar(&header_bar);
-        toolbar_view.set_content(Some(&main_paned));
+        toolbar_view.set_content(Some(&vertical_paned));
         toolbar_view.add_bottom_bar(&status_bar);
 
         content_box.append(&toolbar_view);
@@ -154,6 +194,15 @@ impl MainWindow {
         });
         app.add_action(&login_action);
 
+        let fixes_action = gio::SimpleAction::new("fixes", None);
+        let window_weak = window.downgrade();
+        fixes_action.connect_activate(move |_, _| {
+            if let Some(window) = window_weak.upgrade() {
+                FixSelectionDialog::new(&window, config_store.clone()).present();
+            }
+        });
+        app.add_action(&fixes_action);
+
         let quit_action = gio::SimpleAction::new("quit", None);
         quit_action.connect_activate(glib::clone!(
             #[weak]
@@ -188,6 +237,7 @@ impl MainWindow {
             _page_list: page_list,
             _editor: editor,
             _rule_editor: rule_editor,
+            _log_pane: log_pane,
             status_label,
             progress_bar,
         }
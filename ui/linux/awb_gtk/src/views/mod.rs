@@ -1,6 +1,10 @@
 #[cfg(target_os = "linux")]
 pub mod editor_view;
 #[cfg(target_os = "linux")]
+pub mod fix_selection;
+#[cfg(target_os = "linux")]
+pub mod log_pane;
+#[cfg(target_os = "linux")]
 pub mod login_dialog;
 #[cfg(target_os = "linux")]
 pub mod main_window;
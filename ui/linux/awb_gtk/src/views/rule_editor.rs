@@ -1,13 +1,172 @@
+use awb_domain::rules::{Rule, RuleKind, RuleSet};
+use awb_engine::transform::preview_rules;
+use awb_storage::{StorageError, TomlConfigStore};
 use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
 
+/// Widgets editing a single rule
+struct RuleRow {
+    row: gtk::ListBoxRow,
+    /// The rule as loaded, keeping its ID and summary comment
+    rule: Rule,
+    enabled: gtk::CheckButton,
+    find: gtk::Entry,
+    replace: gtk::Entry,
+    regex: gtk::CheckButton,
+    case_sensitive: gtk::CheckButton,
+}
+
+impl RuleRow {
+    fn new(rule: Rule) -> Self {
+        let rule_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .margin_start(5)
+            .margin_end(5)
+            .margin_top(5)
+            .margin_bottom(5)
+            .build();
+
+        let enabled = gtk::CheckButton::builder()
+            .active(rule.enabled)
+            .tooltip_text("Enabled")
+            .build();
+        rule_box.append(&enabled);
+
+        let find = gtk::Entry::builder()
+            .placeholder_text("Find pattern...")
+            .hexpand(true)
+            .build();
+        rule_box.append(&find);
+
+        let replace = gtk::Entry::builder()
+            .placeholder_text("Replace with...")
+            .hexpand(true)
+            .build();
+        rule_box.append(&replace);
+
+        let regex = gtk::CheckButton::builder()
+            .tooltip_text("Use regex")
+            .label(".*")
+            .build();
+        rule_box.append(&regex);
+
+        let case_sensitive = gtk::CheckButton::builder()
+            .tooltip_text("Case sensitive")
+            .label("Aa")
+            .build();
+        rule_box.append(&case_sensitive);
+
+        match &rule.kind {
+            RuleKind::Plain {
+                find: f,
+                replace: r,
+                case_sensitive: cs,
+            } => {
+                find.set_text(f);
+                replace.set_text(r);
+                case_sensitive.set_active(*cs);
+            }
+            RuleKind::Regex {
+                pattern,
+                replacement,
+                case_insensitive,
+            } => {
+                find.set_text(pattern);
+                replace.set_text(replacement);
+                regex.set_active(true);
+                case_sensitive.set_active(!case_insensitive);
+            }
+            RuleKind::JsonPatch { operations } => {
+                // Shown for reference; JSON patches are edited in the rule file
+                find.set_text(&serde_json::to_string(operations).unwrap_or_default());
+                for widget in [
+                    find.upcast_ref::<gtk::Widget>(),
+                    replace.upcast_ref(),
+                    regex.upcast_ref(),
+                    case_sensitive.upcast_ref(),
+                ] {
+                    widget.set_sensitive(false);
+                }
+            }
+        }
+
+        let row = gtk::ListBoxRow::builder().child(&rule_box).build();
+        Self {
+            row,
+            rule,
+            enabled,
+            find,
+            replace,
+            regex,
+            case_sensitive,
+        }
+    }
+
+    /// The rule as currently shown in the widgets
+    fn to_rule(&self) -> Rule {
+        let find = self.find.text().to_string();
+        let replace = self.replace.text().to_string();
+        let case_sensitive = self.case_sensitive.is_active();
+        let mut rule = self.rule.clone();
+        rule.enabled = self.enabled.is_active();
+        if matches!(rule.kind, RuleKind::JsonPatch { .. }) {
+            return rule;
+        }
+        rule.kind = if self.regex.is_active() {
+            RuleKind::Regex {
+                pattern: find,
+                replacement: replace,
+                case_insensitive: !case_sensitive,
+            }
+        } else {
+            RuleKind::Plain {
+                find,
+                replace,
+                case_sensitive,
+            }
+        };
+        rule
+    }
+
+    /// Call `on_change` whenever any of the row's inputs change
+    fn connect_changed(&self, on_change: impl Fn() + Clone + 'static) {
+        let f = on_change.clone();
+        self.enabled.connect_toggled(move |_| f());
+        let f = on_change.clone();
+        self.find.connect_changed(move |_| f());
+        let f = on_change.clone();
+        self.replace.connect_changed(move |_| f());
+        let f = on_change.clone();
+        self.regex.connect_toggled(move |_| f());
+        self.case_sensitive.connect_toggled(move |_| on_change());
+    }
+}
+
+struct Inner {
+    list_box: gtk::ListBox,
+    rows: RefCell<Vec<RuleRow>>,
+    test_view: gtk::TextView,
+    preview_view: gtk::TextView,
+    status_label: gtk::Label,
+    store: Rc<TomlConfigStore>,
+    profile_id: String,
+}
+
+/// Find-and-replace rule editor with a live preview.
+///
+/// Edits are previewed against the text in the test area (the loaded page,
+/// or anything typed there) and saved to the profile's rules in the config
+/// file.
+#[derive(Clone)]
 pub struct RuleEditor {
     container: gtk::Box,
-    #[allow(dead_code)]
-    list_box: gtk::ListBox,
+    inner: Rc<Inner>,
 }
 
 impl RuleEditor {
-    pub fn new() -> Self {
+    pub fn new(store: Rc<TomlConfigStore>, profile_id: impl Into<String>) -> Self {
         let container = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .vexpand(true)
@@ -47,6 +206,12 @@ impl RuleEditor {
             .build();
         toolbar.append(&down_button);
 
+        let save_button = gtk::Button::builder()
+            .icon_name("document-save-symbolic")
+            .tooltip_text("Save rules to profile")
+            .build();
+        toolbar.append(&save_button);
+
         container.append(&toolbar);
 
         // ListBox for rules
@@ -62,77 +227,219 @@ impl RuleEditor {
         scrolled.set_child(Some(&list_box));
         container.append(&scrolled);
 
+        // Test area and preview
+        let test_view = gtk::TextView::builder()
+            .monospace(true)
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .build();
+        let preview_view = gtk::TextView::builder()
+            .monospace(true)
+            .editable(false)
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .build();
+        for (label, view) in [("Test text", &test_view), ("Preview", &preview_view)] {
+            container.append(&gtk::Label::builder().label(label).xalign(0.0).build());
+            container.append(
+                &gtk::ScrolledWindow::builder()
+                    .child(view)
+                    .min_content_height(100)
+                    .vexpand(true)
+                    .build(),
+            );
+        }
+
+        let status_label = gtk::Label::builder().xalign(0.0).wrap(true).build();
+        container.append(&status_label);
+
+        let inner = Rc::new(Inner {
+            list_box,
+            rows: RefCell::new(Vec::new()),
+            test_view,
+            preview_view,
+            status_label,
+            store,
+            profile_id: profile_id.into(),
+        });
+
+        // Load the profile's saved rules
+        match inner.store.load_rules(&inner.profile_id) {
+            Ok(rule_set) => {
+                for rule in rule_set.rules {
+                    Self::add_rule(&inner, rule);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load rules for {}: {}", inner.profile_id, e);
+            }
+        }
+
         // Connect button actions
-        let list_box_weak = list_box.downgrade();
+        let weak = Rc::downgrade(&inner);
         add_button.connect_clicked(move |_| {
-            if let Some(list_box) = list_box_weak.upgrade() {
-                Self::add_rule(&list_box);
+            if let Some(inner) = weak.upgrade() {
+                let row = Self::add_rule(&inner, Rule::new_plain("", "", true));
+                inner.list_box.select_row(Some(&row));
             }
         });
 
-        let list_box_weak2 = list_box.downgrade();
+        let weak = Rc::downgrade(&inner);
         remove_button.connect_clicked(move |_| {
-            if let Some(list_box) = list_box_weak2.upgrade() {
-                if let Some(row) = list_box.selected_row() {
-                    list_box.remove(&row);
+            if let Some(inner) = weak.upgrade() {
+                if let Some(index) = Self::selected_index(&inner) {
+                    let removed = inner.rows.borrow_mut().remove(index);
+                    inner.list_box.remove(&removed.row);
+                    Self::refresh_preview(&inner);
                 }
             }
         });
 
-        Self {
-            container,
-            list_box,
-        }
+        let weak = Rc::downgrade(&inner);
+        up_button.connect_clicked(move |_| {
+            if let Some(inner) = weak.upgrade() {
+                Self::move_selected(&inner, -1);
+            }
+        });
+
+        let weak = Rc::downgrade(&inner);
+        down_button.connect_clicked(move |_| {
+            if let Some(inner) = weak.upgrade() {
+                Self::move_selected(&inner, 1);
+            }
+        });
+
+        let weak = Rc::downgrade(&inner);
+        save_button.connect_clicked(move |_| {
+            if let Some(inner) = weak.upgrade() {
+                match Self::save_inner(&inner) {
+                    Ok(()) => inner.status_label.set_label("Rules saved"),
+                    Err(e) => inner
+                        .status_label
+                        .set_label(&format!("Failed to save rules: {}", e)),
+                }
+            }
+        });
+
+        let on_change = Self::preview_callback(Rc::downgrade(&inner));
+        inner
+            .test_view
+            .buffer()
+            .connect_changed(move |_| on_change());
+
+        Self::refresh_preview(&inner);
+        Self { container, inner }
     }
 
     pub fn widget(&self) -> &gtk::Box {
         &self.container
     }
 
-    fn add_rule(list_box: &gtk::ListBox) {
-        let rule_row = gtk::Box::builder()
-            .orientation(gtk::Orientation::Horizontal)
-            .spacing(5)
-            .margin_start(5)
-            .margin_end(5)
-            .margin_top(5)
-            .margin_bottom(5)
-            .build();
+    /// Replace the test text, e.g. with the currently loaded page
+    pub fn set_test_text(&self, text: &str) {
+        self.inner.test_view.buffer().set_text(text);
+    }
 
-        let enabled_check = gtk::CheckButton::builder().active(true).build();
-        rule_row.append(&enabled_check);
+    /// The rules as currently edited, in list order
+    #[allow(dead_code)]
+    pub fn rule_set(&self) -> RuleSet {
+        Self::collect_rules(&self.inner)
+    }
 
-        let pattern_entry = gtk::Entry::builder()
-            .placeholder_text("Find pattern...")
-            .hexpand(true)
-            .build();
-        rule_row.append(&pattern_entry);
+    /// Save the edited rules to the profile
+    #[allow(dead_code)]
+    pub fn save(&self) -> Result<(), StorageError> {
+        Self::save_inner(&self.inner)
+    }
 
-        let replacement_entry = gtk::Entry::builder()
-            .placeholder_text("Replace with...")
-            .hexpand(true)
-            .build();
-        rule_row.append(&replacement_entry);
+    #[allow(dead_code)]
+    pub fn clear_rules(&self) {
+        for row in self.inner.rows.borrow_mut().drain(..) {
+            self.inner.list_box.remove(&row.row);
+        }
+        Self::refresh_preview(&self.inner);
+    }
 
-        let regex_check = gtk::CheckButton::builder()
-            .tooltip_text("Use regex")
-            .label(".*")
-            .build();
-        rule_row.append(&regex_check);
+    fn add_rule(inner: &Rc<Inner>, rule: Rule) -> gtk::ListBoxRow {
+        let rule_row = RuleRow::new(rule);
+        rule_row.connect_changed(Self::preview_callback(Rc::downgrade(inner)));
+        inner.list_box.append(&rule_row.row);
+        let row = rule_row.row.clone();
+        inner.rows.borrow_mut().push(rule_row);
+        Self::refresh_preview(inner);
+        row
+    }
 
-        list_box.append(&rule_row);
+    fn selected_index(inner: &Inner) -> Option<usize> {
+        let selected = inner.list_box.selected_row()?;
+        inner.rows.borrow().iter().position(|r| r.row == selected)
     }
 
-    #[allow(dead_code)]
-    pub fn clear_rules(&self) {
-        while let Some(row) = self.list_box.first_child() {
-            self.list_box.remove(&row);
+    /// Move the selected rule `delta` places up (negative) or down
+    fn move_selected(inner: &Rc<Inner>, delta: isize) {
+        let Some(index) = Self::selected_index(inner) else {
+            return;
+        };
+        let target = index.saturating_add_signed(delta);
+        let mut rows = inner.rows.borrow_mut();
+        if target == index || target >= rows.len() {
+            return;
         }
+        rows.swap(index, target);
+        let row = rows[target].row.clone();
+        drop(rows);
+
+        inner.list_box.remove(&row);
+        inner.list_box.insert(&row, target as i32);
+        inner.list_box.select_row(Some(&row));
+        Self::refresh_preview(inner);
+    }
+
+    fn collect_rules(inner: &Inner) -> RuleSet {
+        let mut rule_set = RuleSet::new();
+        for row in inner.rows.borrow().iter() {
+            rule_set.add(row.to_rule());
+        }
+        rule_set
+    }
+
+    fn save_inner(inner: &Inner) -> Result<(), StorageError> {
+        inner
+            .store
+            .save_rules(&inner.profile_id, &Self::collect_rules(inner))
     }
-}
 
-impl Default for RuleEditor {
-    fn default() -> Self {
-        Self::new()
+    fn preview_callback(weak: Weak<Inner>) -> impl Fn() + Clone + 'static {
+        move || {
+            if let Some(inner) = weak.upgrade() {
+                Self::refresh_preview(&inner);
+            }
+        }
+    }
+
+    /// Re-run the rules over the test text and show the result
+    fn refresh_preview(inner: &Inner) {
+        let buffer = inner.test_view.buffer();
+        let text = buffer
+            .text(&buffer.start_iter(), &buffer.end_iter(), false)
+            .to_string();
+        let rule_set = Self::collect_rules(inner);
+
+        match preview_rules(&rule_set, &text) {
+            Ok(preview) => {
+                let status = if preview == text {
+                    "No changes".to_string()
+                } else {
+                    format!(
+                        "Text changed ({} enabled rules)",
+                        rule_set.enabled_rules().count()
+                    )
+                };
+                inner.preview_view.buffer().set_text(&preview);
+                inner.status_label.set_label(&status);
+            }
+            Err(e) => {
+                inner.preview_view.buffer().set_text("");
+                inner.status_label.set_label(&e.to_string());
+            }
+        }
     }
 }
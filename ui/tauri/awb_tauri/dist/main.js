@@ -0,0 +1,160 @@
+// Review frontend. All state lives in the Rust ReviewSession; every command
+// returns a fresh view, which render() draws.
+
+const { invoke } = window.__TAURI__.core;
+const dialog = window.__TAURI__.dialog;
+
+const $ = (id) => document.getElementById(id);
+const sessionFilter = [{ name: "AWB session", extensions: ["awbsession"] }];
+
+// Which buttons apply in each review state
+const actions = {
+  awaiting_decision: ["save", "skip", "apply-edit", "back", "pause", "stop"],
+  fetching_page: ["retry", "back", "stop"],
+  paused: ["resume", "stop"],
+  error: ["resume", "back", "stop"],
+};
+
+function showMessage(text, isError) {
+  const message = $("message");
+  message.hidden = !text;
+  message.textContent = text ?? "";
+  message.classList.toggle("error", Boolean(isError));
+}
+
+async function call(command, args) {
+  try {
+    render(await invoke(command, args));
+  } catch (error) {
+    showMessage(String(error), true);
+    // A failed fetch still moves the review on; show where it stopped
+    try {
+      render(await invoke("get_review"), true);
+    } catch {
+      // Not logged in yet
+    }
+  }
+}
+
+function describeWarning(warning) {
+  if (typeof warning === "string") return warning;
+  const [kind, detail] = Object.entries(warning)[0];
+  return detail.message ? `${kind}: ${detail.message}` : kind;
+}
+
+function renderDiff(ops) {
+  const diff = $("diff");
+  diff.replaceChildren();
+  const span = (className, text, origin) => {
+    const element = document.createElement("span");
+    element.className = className;
+    element.textContent = text;
+    if (origin) element.title = origin;
+    diff.append(element);
+  };
+  for (const op of ops) {
+    const [kind, detail] = Object.entries(op)[0];
+    if (kind === "Equal") span("equal", detail.text);
+    if (kind === "Insert") span("insert", detail.text, detail.origin);
+    if (kind === "Delete") span("delete", detail.text, detail.origin);
+    if (kind === "Replace") {
+      span("delete", detail.old_text, detail.origin);
+      span("insert", detail.new_text, detail.origin);
+    }
+  }
+}
+
+function render(view, keepMessage) {
+  $("login").hidden = true;
+  $("list").hidden = false;
+  document.querySelector("main").hidden = false;
+
+  const plan = view.plan;
+  $("title").textContent = view.title ?? "No page";
+  $("progress").textContent = view.total ? `${Math.min(view.index + 1, view.total)} of ${view.total}` : "";
+  $("text").value = plan?.new_wikitext ?? "";
+  $("text").disabled = !plan;
+  $("summary").value = plan?.summary ?? "";
+  renderDiff(plan?.diff_ops ?? []);
+
+  const notes = [
+    ...(plan?.warnings ?? []),
+    ...view.notices,
+  ].map(describeWarning);
+  if (view.error) {
+    showMessage(`Save failed: ${view.error}`, true);
+  } else if (!keepMessage) {
+    showMessage(notes.join("; "), false);
+  }
+
+  const allowed = actions[view.state] ?? [];
+  for (const button of document.querySelectorAll("main .bar button")) {
+    button.disabled = !allowed.includes(button.id);
+  }
+  $("save-session").disabled = view.state === "idle";
+
+  const { saved, skipped, unchanged, errors } = view.stats;
+  $("stats").textContent =
+    `${view.state.replaceAll("_", " ")} · ${saved} saved · ${skipped} skipped · ` +
+    `${unchanged} unchanged · ${errors} errors` +
+    (view.session_path ? ` · ${view.session_path}` : "");
+}
+
+async function loadFixes() {
+  const list = $("fixes");
+  list.replaceChildren();
+  for (const fix of await invoke("list_fixes")) {
+    const item = document.createElement("li");
+    const label = document.createElement("label");
+    const box = document.createElement("input");
+    box.type = "checkbox";
+    box.checked = fix.enabled;
+    box.addEventListener("change", () =>
+      call("set_fix_enabled", { id: fix.id, enabled: box.checked }),
+    );
+    label.append(box, ` ${fix.display_name}`);
+    label.title = fix.description;
+    item.append(label);
+    list.append(item);
+  }
+}
+
+$("login").addEventListener("submit", async (event) => {
+  event.preventDefault();
+  const form = new FormData(event.target);
+  await call("login", {
+    wikiUrl: form.get("wikiUrl"),
+    username: form.get("username"),
+    password: form.get("password"),
+  });
+  if (!$("list").hidden) await loadFixes();
+});
+
+$("list").addEventListener("submit", (event) => {
+  event.preventDefault();
+  const form = new FormData(event.target);
+  call("start_review", { source: form.get("source"), query: form.get("query"), rules: null });
+});
+
+$("save").addEventListener("click", () => call("decide", { decision: "Save" }));
+$("skip").addEventListener("click", () => call("decide", { decision: "Skip" }));
+$("pause").addEventListener("click", () => call("decide", { decision: "Pause" }));
+$("apply-edit").addEventListener("click", () =>
+  call("decide", { decision: { ManualEdit: $("text").value } }),
+);
+for (const command of ["back", "resume", "retry", "stop"]) {
+  $(command).addEventListener("click", () => call(command));
+}
+
+$("save-session").addEventListener("click", async () => {
+  const path = await dialog.save({ filters: sessionFilter });
+  if (path) await call("save_session", { path });
+});
+
+$("open-session").addEventListener("click", async () => {
+  const path = await dialog.open({ filters: sessionFilter });
+  if (path) {
+    await call("open_session", { path });
+    await loadFixes();
+  }
+});
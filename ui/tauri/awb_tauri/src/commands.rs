@@ -0,0 +1,135 @@
+//! Tauri IPC commands. Each returns the updated [`ReviewView`], or the
+//! error message, for the frontend to render.
+
+use crate::error::AppError;
+use crate::review::{FixToggle, ListSource, ReviewSession, ReviewView};
+use awb_domain::rules::RuleSet;
+use awb_domain::session::EditDecision;
+use std::path::PathBuf;
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// The logged-in review session, if any
+#[derive(Default)]
+pub struct AppState {
+    review: Mutex<Option<ReviewSession>>,
+}
+
+#[tauri::command]
+pub async fn login(
+    state: State<'_, AppState>,
+    wiki_url: String,
+    username: String,
+    password: String,
+) -> Result<ReviewView, AppError> {
+    let mut review = ReviewSession::connect(&wiki_url, &username, &password).await?;
+    let view = review.view();
+    *state.review.lock().await = Some(review);
+    Ok(view)
+}
+
+#[tauri::command]
+pub async fn get_review(state: State<'_, AppState>) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn list_fixes(state: State<'_, AppState>) -> Result<Vec<FixToggle>, AppError> {
+    let guard = state.review.lock().await;
+    let review = guard.as_ref().ok_or(AppError::NotLoggedIn)?;
+    Ok(review.fixes())
+}
+
+#[tauri::command]
+pub async fn set_fix_enabled(
+    state: State<'_, AppState>,
+    id: String,
+    enabled: bool,
+) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.set_fix_enabled(&id, enabled)?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn start_review(
+    state: State<'_, AppState>,
+    source: ListSource,
+    query: String,
+    rules: Option<RuleSet>,
+) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review
+        .start(source, &query, rules.unwrap_or_default())
+        .await?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn decide(
+    state: State<'_, AppState>,
+    decision: EditDecision,
+) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.decide(decision).await?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn back(state: State<'_, AppState>) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.back().await?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn resume(state: State<'_, AppState>) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.resume().await?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn stop(state: State<'_, AppState>) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.stop().await?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn retry(state: State<'_, AppState>) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.retry().await?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn save_session(
+    state: State<'_, AppState>,
+    path: PathBuf,
+) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.save_to(path).await?;
+    Ok(review.view())
+}
+
+#[tauri::command]
+pub async fn open_session(
+    state: State<'_, AppState>,
+    path: PathBuf,
+) -> Result<ReviewView, AppError> {
+    let mut guard = state.review.lock().await;
+    let review = guard.as_mut().ok_or(AppError::NotLoggedIn)?;
+    review.open(path).await?;
+    Ok(review.view())
+}
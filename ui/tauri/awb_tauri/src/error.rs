@@ -0,0 +1,33 @@
+use awb_engine::fix_config::FixConfigError;
+use awb_engine::transform::TransformError;
+use awb_mw_api::error::MwApiError;
+use awb_storage::StorageError;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("Not logged in")]
+    NotLoggedIn,
+    #[error("Invalid wiki URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("No page is being reviewed")]
+    NoPage,
+    #[error("Edit failed: {0}")]
+    EditFailed(String),
+    #[error(transparent)]
+    Api(#[from] MwApiError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    FixConfig(#[from] FixConfigError),
+    #[error(transparent)]
+    Transform(#[from] TransformError),
+}
+
+/// Commands reject with the message, which is all the frontend shows
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
@@ -0,0 +1,33 @@
+//! Cross-platform review GUI for AWB-RS.
+//!
+//! A webview frontend (`dist/`) talks over Tauri IPC to [`commands`], which
+//! wrap a [`review::ReviewSession`]: the engine's review state machine run
+//! against a wiki, with diff preview, general fix toggles and `.awbsession`
+//! persistence. The session does not depend on Tauri, so the same review
+//! can later be served to a browser by the HTTP server.
+
+pub mod commands;
+pub mod error;
+pub mod review;
+
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .manage(commands::AppState::default())
+        .invoke_handler(tauri::generate_handler![
+            commands::login,
+            commands::get_review,
+            commands::list_fixes,
+            commands::set_fix_enabled,
+            commands::start_review,
+            commands::decide,
+            commands::back,
+            commands::resume,
+            commands::stop,
+            commands::retry,
+            commands::save_session,
+            commands::open_session,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running the AWB-RS review app");
+}
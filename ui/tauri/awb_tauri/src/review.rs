@@ -0,0 +1,598 @@
+//! A review session: the engine's [`ReviewStateMachine`] with its side
+//! effects carried out against a wiki.
+//!
+//! Nothing here depends on Tauri. Each operation runs the machine until it
+//! needs the user again, and [`ReviewSession::view`] describes the result
+//! as plain serializable data, so another transport can expose the same
+//! operations.
+
+use crate::error::AppError;
+use awb_domain::profile::ThrottlePolicy;
+use awb_domain::rules::RuleSet;
+use awb_domain::session::{EditDecision, EditOutcome, EditPlan, EditResult, SessionState};
+use awb_domain::siteinfo::SiteInfo;
+use awb_domain::types::{RevisionId, Title, Watchlist};
+use awb_domain::warnings::Warning;
+use awb_engine::fix_config::FixConfig;
+use awb_engine::general_fixes::{FixInfo, FixRegistry};
+use awb_engine::review::{
+    ReviewEvent, ReviewSideEffect, ReviewState, ReviewStateMachine, SessionStats,
+};
+use awb_engine::transform::TransformEngine;
+use awb_mw_api::client::{AssertMode, EditRequest, MediaWikiClient, ReqwestMwClient};
+use awb_storage::ReviewSessionFile;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Most titles fetched from a list source
+const LIST_LIMIT: u32 = 500;
+
+/// Where the pages to review come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSource {
+    Category,
+    Search,
+    Backlinks,
+}
+
+/// A general fix and whether the current selection runs it
+#[derive(Debug, Clone, Serialize)]
+pub struct FixToggle {
+    #[serde(flatten)]
+    pub info: FixInfo,
+    pub enabled: bool,
+}
+
+/// Everything the frontend shows about the review
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewView {
+    /// `idle`, `fetching_page`, `awaiting_decision`, `paused`, `completed`,
+    /// `error` and so on, after [`ReviewState`]'s variants
+    pub state: &'static str,
+    pub error: Option<String>,
+    pub index: usize,
+    pub total: usize,
+    pub title: Option<String>,
+    /// The page under review, with its proposed text and diff
+    pub plan: Option<EditPlan>,
+    pub stats: SessionStats,
+    /// Warnings raised since the last view
+    pub notices: Vec<Warning>,
+    /// The session file the review is saved to, if any
+    pub session_path: Option<PathBuf>,
+}
+
+pub struct ReviewSession {
+    client: Arc<dyn MediaWikiClient>,
+    site: SiteInfo,
+    machine: ReviewStateMachine,
+    /// Rules, list and decisions, as written to the session file
+    session: SessionState,
+    fix_config: FixConfig,
+    engine: TransformEngine,
+    /// The plan presented for review, if one is
+    current: Option<EditPlan>,
+    notices: Vec<Warning>,
+    /// Where `PersistSession` writes; set once the review is saved or opened
+    path: Option<PathBuf>,
+}
+
+impl ReviewSession {
+    /// Log in to the wiki at `wiki_url` with a bot password
+    pub async fn connect(wiki_url: &str, username: &str, password: &str) -> Result<Self, AppError> {
+        let url = url::Url::parse(wiki_url)?;
+        let client =
+            ReqwestMwClient::new(url, ThrottlePolicy::default())?.with_assert(AssertMode::User);
+        client.login_bot_password(username, password).await?;
+        client.fetch_csrf_token().await?;
+        // Without siteinfo, titles are still read with the default English
+        // namespace names
+        let site = client.fetch_siteinfo().await.unwrap_or_default();
+        Self::new(Arc::new(client), site)
+    }
+
+    pub fn new(client: Arc<dyn MediaWikiClient>, site: SiteInfo) -> Result<Self, AppError> {
+        let session = SessionState::new("default");
+        let fix_config = FixConfig::default();
+        let engine = build_engine(&session.rule_set, &fix_config)?;
+        Ok(Self {
+            client,
+            site,
+            machine: ReviewStateMachine::new(),
+            session,
+            fix_config,
+            engine,
+            current: None,
+            notices: Vec::new(),
+            path: None,
+        })
+    }
+
+    /// Fetch the list and present its first page, replacing any review in
+    /// progress
+    pub async fn start(
+        &mut self,
+        source: ListSource,
+        query: &str,
+        rules: RuleSet,
+    ) -> Result<(), AppError> {
+        self.engine = build_engine(&rules, &self.fix_config)?;
+        self.session = SessionState::new("default");
+        self.session.rule_set = rules;
+        self.machine = ReviewStateMachine::new();
+        self.current = None;
+        self.path = None;
+
+        self.machine.transition(ReviewEvent::Start);
+        let names = match source {
+            ListSource::Category => self.client.list_category_members(query, LIST_LIMIT).await,
+            ListSource::Search => self.client.search_pages(query, LIST_LIMIT).await,
+            ListSource::Backlinks => self.client.get_backlinks(query, LIST_LIMIT).await,
+        };
+        let names = match names {
+            Ok(names) => names,
+            Err(e) => {
+                self.machine = ReviewStateMachine::new();
+                return Err(e.into());
+            }
+        };
+        let titles = names
+            .iter()
+            .filter_map(|name| match Title::parse(&self.site, name) {
+                Ok(title) => Some(title),
+                Err(e) => {
+                    tracing::warn!("Skipping {name}: {e}");
+                    None
+                }
+            })
+            .collect();
+        let effects = self.machine.transition(ReviewEvent::ListLoaded(titles));
+        self.run(effects).await
+    }
+
+    /// Save, skip, pause or replace the text of the page under review
+    pub async fn decide(&mut self, decision: EditDecision) -> Result<(), AppError> {
+        if !matches!(self.machine.state(), ReviewState::AwaitingDecision { .. }) {
+            return Err(AppError::NoPage);
+        }
+        let effects = self.machine.transition(ReviewEvent::UserDecision(decision));
+        self.run(effects).await
+    }
+
+    /// Return to the previous page
+    pub async fn back(&mut self) -> Result<(), AppError> {
+        let effects = self.machine.transition(ReviewEvent::Back);
+        self.run(effects).await
+    }
+
+    /// Continue after a pause or a failed save
+    pub async fn resume(&mut self) -> Result<(), AppError> {
+        let effects = self.machine.transition(ReviewEvent::Resume);
+        self.run(effects).await
+    }
+
+    pub async fn stop(&mut self) -> Result<(), AppError> {
+        let effects = self.machine.transition(ReviewEvent::Stop);
+        self.run(effects).await
+    }
+
+    /// Fetch the current page again after fetching it failed
+    pub async fn retry(&mut self) -> Result<(), AppError> {
+        if let ReviewState::FetchingPage { index } = *self.machine.state() {
+            if let Some(title) = self.machine.page_list.get(index).cloned() {
+                return self.run(vec![ReviewSideEffect::FetchPage(title)]).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn fixes(&self) -> Vec<FixToggle> {
+        FixRegistry::with_config(&self.fix_config)
+            .fix_infos()
+            .into_iter()
+            .map(|info| FixToggle {
+                enabled: self.fix_config.allows(&info.id, info.min_tier),
+                info,
+            })
+            .collect()
+    }
+
+    /// Turn a general fix on or off and re-plan the page under review from
+    /// its fetched text. A manual edit of that page is discarded.
+    pub fn set_fix_enabled(&mut self, id: &str, enabled: bool) -> Result<(), AppError> {
+        let mut config = self.fix_config.clone();
+        config.set_enabled(id, enabled);
+        self.engine = build_engine(&self.session.rule_set, &config)?;
+        self.fix_config = config;
+
+        if let ReviewState::AwaitingDecision { plan } = &mut self.machine.state {
+            let revised = self.engine.apply(&plan.page);
+            **plan = revised.clone();
+            self.current = Some(revised);
+        }
+        Ok(())
+    }
+
+    /// Write the review to `path`, and keep it there as the review goes on
+    pub async fn save_to(&mut self, path: PathBuf) -> Result<(), AppError> {
+        self.session_file()?.save(&path).await?;
+        self.path = Some(path);
+        Ok(())
+    }
+
+    /// Resume the review saved in `path`
+    pub async fn open(&mut self, path: PathBuf) -> Result<(), AppError> {
+        let file = ReviewSessionFile::load(&path).await?;
+        let mut fix_config = FixConfig::default();
+        if !file.session.general_fixes_enabled.is_empty() {
+            fix_config.enabled_fixes = file.session.general_fixes_enabled.iter().cloned().collect();
+        }
+        let engine = build_engine(&file.session.rule_set, &fix_config)?;
+        let (machine, effects) = ReviewStateMachine::restore(file.review);
+
+        self.fix_config = fix_config;
+        self.engine = engine;
+        self.machine = machine;
+        self.session = file.session;
+        self.current = None;
+        self.path = Some(path);
+        self.run(effects).await
+    }
+
+    /// Describe the review, taking the warnings raised since the last call
+    pub fn view(&mut self) -> ReviewView {
+        let (state, error) = match self.machine.state() {
+            ReviewState::Idle => ("idle", None),
+            ReviewState::LoadingList => ("loading_list", None),
+            ReviewState::FetchingPage { .. } => ("fetching_page", None),
+            ReviewState::ApplyingRules { .. } => ("applying_rules", None),
+            ReviewState::AwaitingDecision { .. } => ("awaiting_decision", None),
+            ReviewState::Saving { .. } => ("saving", None),
+            ReviewState::Paused { .. } => ("paused", None),
+            ReviewState::Completed { .. } => ("completed", None),
+            ReviewState::Error { error, .. } => ("error", Some(error.clone())),
+        };
+        ReviewView {
+            state,
+            error,
+            index: self.machine.current_index,
+            total: self.machine.page_list.len(),
+            title: self
+                .machine
+                .page_list
+                .get(self.machine.current_index)
+                .map(|title| title.display.clone()),
+            plan: self.current.clone(),
+            stats: self.machine.stats().clone(),
+            notices: std::mem::take(&mut self.notices),
+            session_path: self.path.clone(),
+        }
+    }
+
+    /// Carry out side effects, and those they lead to, until the machine
+    /// waits for the user.
+    ///
+    /// A failed fetch stops here and leaves the page to [`retry`](Self::retry);
+    /// a failed save is reported to the machine, which waits in its error
+    /// state.
+    async fn run(&mut self, effects: Vec<ReviewSideEffect>) -> Result<(), AppError> {
+        let mut queue = VecDeque::from(effects);
+        while let Some(effect) = queue.pop_front() {
+            match effect {
+                ReviewSideEffect::FetchPage(title) => {
+                    self.current = None;
+                    let page = self.client.get_page(&title).await?;
+                    queue.extend(self.machine.transition(ReviewEvent::PageFetched(page)));
+                }
+                ReviewSideEffect::PrefetchPage(title) => {
+                    // The page is fetched again when reached if this fails
+                    match self.client.get_page(&title).await {
+                        Ok(page) => {
+                            let plan = self.engine.apply(&page);
+                            queue.extend(self.machine.transition(ReviewEvent::PagePrepared(plan)));
+                        }
+                        Err(e) => tracing::debug!("Prefetching {title} failed: {e}"),
+                    }
+                }
+                ReviewSideEffect::ApplyRules(page) => {
+                    let plan = self.engine.apply(&page);
+                    queue.extend(self.machine.transition(ReviewEvent::RulesApplied(plan)));
+                }
+                ReviewSideEffect::ReviseEdit { page, text } => {
+                    let plan = self.engine.revise(&page, text);
+                    queue.extend(self.machine.transition(ReviewEvent::RulesApplied(plan)));
+                }
+                ReviewSideEffect::PresentForReview(plan) => {
+                    self.current = Some(plan);
+                }
+                ReviewSideEffect::ExecuteEdit {
+                    title,
+                    new_text,
+                    summary,
+                    watchlist,
+                } => {
+                    let event = match self.save(title, new_text, summary, watchlist).await {
+                        Ok(result) => ReviewEvent::SaveComplete(result),
+                        Err(e) => ReviewEvent::SaveFailed(e.to_string()),
+                    };
+                    self.current = None;
+                    queue.extend(self.machine.transition(event));
+                }
+                ReviewSideEffect::PersistSession => {
+                    if let Some(path) = &self.path {
+                        self.session_file()?.save(path).await?;
+                    }
+                }
+                ReviewSideEffect::EmitWarning(warning) => self.notices.push(warning),
+                ReviewSideEffect::ShowComplete(_) => self.current = None,
+            }
+        }
+        Ok(())
+    }
+
+    async fn save(
+        &self,
+        title: Title,
+        text: String,
+        summary: String,
+        watchlist: Watchlist,
+    ) -> Result<EditResult, AppError> {
+        let page = &self.current.as_ref().ok_or(AppError::NoPage)?.page;
+        let request = EditRequest {
+            title,
+            text,
+            summary,
+            minor: true,
+            bot: false,
+            base_timestamp: page.timestamp.to_rfc3339(),
+            start_timestamp: Utc::now().to_rfc3339(),
+            section: None,
+            tags: Vec::new(),
+            watchlist,
+        };
+        let response = self.client.edit_page(&request).await?;
+        if response.result != "Success" {
+            return Err(AppError::EditFailed(response.result));
+        }
+        let new_revision = response.new_revid.map(RevisionId);
+        Ok(EditResult {
+            page_id: page.page_id,
+            new_revision,
+            outcome: match new_revision {
+                Some(revision) => EditOutcome::Saved { revision },
+                None => EditOutcome::NoChange,
+            },
+            timestamp: Utc::now(),
+        })
+    }
+
+    fn session_file(&self) -> Result<ReviewSessionFile, AppError> {
+        let mut session = self.session.clone();
+        session.page_list = self.machine.page_list.clone();
+        session.current_index = self.machine.current_index;
+        session.decisions = self.machine.decisions().to_vec();
+        let mut enabled: Vec<String> = FixRegistry::with_config(&self.fix_config)
+            .enabled_ids(&self.fix_config)?
+            .into_iter()
+            .collect();
+        enabled.sort();
+        session.general_fixes_enabled = enabled;
+        session.updated_at = Utc::now();
+        Ok(ReviewSessionFile::new(session, self.machine.snapshot()))
+    }
+}
+
+fn build_engine(rules: &RuleSet, config: &FixConfig) -> Result<TransformEngine, AppError> {
+    let registry = FixRegistry::with_config(config);
+    let enabled = registry.enabled_ids(config)?;
+    Ok(TransformEngine::new(rules, registry, enabled)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use awb_domain::types::{Namespace, PageContent, PageId, PageProperties, ProtectionInfo};
+    use awb_mw_api::client::EditResponse;
+    use awb_mw_api::error::MwApiError;
+    use awb_mw_api::oauth::{OAuth1Config, OAuthSession};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    struct MockClient {
+        pages: HashMap<String, PageContent>,
+        edits: Mutex<Vec<String>>,
+    }
+
+    impl MockClient {
+        fn new(pages: &[(&str, &str)]) -> Self {
+            let pages = pages
+                .iter()
+                .enumerate()
+                .map(|(i, (title, text))| {
+                    let page = PageContent {
+                        page_id: PageId(i as u64 + 1),
+                        title: Title::new(Namespace::MAIN, *title),
+                        revision: RevisionId(100),
+                        timestamp: Utc::now(),
+                        wikitext: text.to_string(),
+                        size_bytes: text.len() as u64,
+                        is_redirect: false,
+                        protection: ProtectionInfo::default(),
+                        properties: PageProperties::default(),
+                    };
+                    (title.to_string(), page)
+                })
+                .collect();
+            Self {
+                pages,
+                edits: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl MediaWikiClient for MockClient {
+        async fn login_bot_password(
+            &self,
+            _username: &str,
+            _password: &str,
+        ) -> Result<(), MwApiError> {
+            Ok(())
+        }
+
+        async fn login_oauth1(&self, _config: OAuth1Config) -> Result<(), MwApiError> {
+            Ok(())
+        }
+
+        async fn login_oauth2(&self, _session: OAuthSession) -> Result<(), MwApiError> {
+            Ok(())
+        }
+
+        async fn fetch_csrf_token(&self) -> Result<String, MwApiError> {
+            Ok("mocktoken".to_string())
+        }
+
+        async fn get_page(&self, title: &Title) -> Result<PageContent, MwApiError> {
+            self.pages
+                .get(&title.display)
+                .cloned()
+                .ok_or_else(|| MwApiError::ApiError {
+                    code: "notfound".to_string(),
+                    info: "Page not found".to_string(),
+                })
+        }
+
+        async fn edit_page(&self, edit: &EditRequest) -> Result<EditResponse, MwApiError> {
+            self.edits.lock().unwrap().push(edit.text.clone());
+            Ok(EditResponse {
+                result: "Success".to_string(),
+                new_revid: Some(101),
+                new_timestamp: Some(Utc::now().to_rfc3339()),
+            })
+        }
+
+        async fn parse_wikitext(
+            &self,
+            _wikitext: &str,
+            _title: &Title,
+        ) -> Result<String, MwApiError> {
+            Ok(String::new())
+        }
+
+        async fn list_category_members(
+            &self,
+            _category: &str,
+            _limit: u32,
+        ) -> Result<Vec<String>, MwApiError> {
+            let mut titles: Vec<String> = self.pages.keys().cloned().collect();
+            titles.sort();
+            Ok(titles)
+        }
+
+        async fn search_pages(&self, _query: &str, _limit: u32) -> Result<Vec<String>, MwApiError> {
+            Ok(vec![])
+        }
+
+        async fn get_backlinks(
+            &self,
+            _title: &str,
+            _limit: u32,
+        ) -> Result<Vec<String>, MwApiError> {
+            Ok(vec![])
+        }
+    }
+
+    fn rules() -> RuleSet {
+        let mut rules = RuleSet::new();
+        rules.add(awb_domain::rules::Rule::new_plain("colour", "color", true));
+        rules
+    }
+
+    async fn started(client: &Arc<MockClient>) -> ReviewSession {
+        let mut review = ReviewSession::new(client.clone(), SiteInfo::default()).unwrap();
+        review
+            .start(ListSource::Category, "Test pages", rules())
+            .await
+            .unwrap();
+        review
+    }
+
+    #[tokio::test]
+    async fn test_save_advances_to_next_page() {
+        let client = Arc::new(MockClient::new(&[
+            ("Alpha", "The colour red.\n"),
+            ("Beta", "The colour blue.\n"),
+        ]));
+        let mut review = started(&client).await;
+
+        let view = review.view();
+        assert_eq!(view.state, "awaiting_decision");
+        assert_eq!(view.title.as_deref(), Some("Alpha"));
+        assert_eq!(view.plan.unwrap().new_wikitext, "The color red.\n");
+
+        review.decide(EditDecision::Save).await.unwrap();
+        let view = review.view();
+        assert_eq!(view.title.as_deref(), Some("Beta"));
+        assert_eq!(view.stats.saved, 1);
+        assert_eq!(client.edits.lock().unwrap()[0], "The color red.\n");
+
+        review.decide(EditDecision::Skip).await.unwrap();
+        let view = review.view();
+        assert_eq!(view.state, "completed");
+        assert!(view.plan.is_none());
+        assert_eq!(view.stats.skipped, 1);
+        assert_eq!(client.edits.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fix_toggle_replans_current_page() {
+        let client = Arc::new(MockClient::new(&[("Alpha", "The colour red.")]));
+        let mut review = started(&client).await;
+        assert_eq!(review.view().plan.unwrap().new_wikitext, "The color red.\n");
+
+        review.set_fix_enabled("whitespace_cleanup", false).unwrap();
+        assert!(
+            !review
+                .fixes()
+                .iter()
+                .any(|fix| fix.info.id == "whitespace_cleanup" && fix.enabled)
+        );
+        assert_eq!(review.view().plan.unwrap().new_wikitext, "The color red.");
+
+        assert!(review.set_fix_enabled("no_such_fix", false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_saved_session_resumes_at_same_page() {
+        let client = Arc::new(MockClient::new(&[
+            ("Alpha", "The colour red.\n"),
+            ("Beta", "The colour blue.\n"),
+            ("Gamma", "The colour green.\n"),
+        ]));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("review.awbsession");
+
+        let mut review = started(&client).await;
+        review.save_to(path.clone()).await.unwrap();
+        review.decide(EditDecision::Skip).await.unwrap();
+        // Pausing persists the review to the file it was saved to
+        review.decide(EditDecision::Pause).await.unwrap();
+
+        let mut resumed = ReviewSession::new(client.clone(), SiteInfo::default()).unwrap();
+        resumed.open(path).await.unwrap();
+        let view = resumed.view();
+        assert_eq!(view.state, "paused");
+        assert_eq!(view.title.as_deref(), Some("Beta"));
+        assert_eq!(view.stats.skipped, 1);
+
+        resumed.resume().await.unwrap();
+        let view = resumed.view();
+        assert_eq!(view.title.as_deref(), Some("Gamma"));
+        assert_eq!(view.plan.unwrap().new_wikitext, "The color green.\n");
+    }
+}
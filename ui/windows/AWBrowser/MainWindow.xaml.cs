@@ -1,5 +1,9 @@
+using System;
+using System.ComponentModel;
+using System.Threading.Tasks;
 using System.Windows;
 using System.Windows.Input;
+using AWBrowser.Models;
 using AWBrowser.ViewModels;
 using AWBrowser.Views;
 
@@ -10,7 +14,12 @@ namespace AWBrowser;
 /// </summary>
 public partial class MainWindow : Window
 {
-    private SessionViewModel? _viewModel;
+    private const string DiffStyle =
+        "<style>body{font-family:Consolas,monospace;font-size:12px;white-space:pre-wrap;}" +
+        ".delete{background:#FFE0E0;text-decoration:line-through;}" +
+        ".insert{background:#E0FFE0;}</style>";
+
+    private readonly SessionViewModel _viewModel;
 
     public MainWindow()
     {
@@ -18,14 +27,67 @@ public partial class MainWindow : Window
 
         // Initialize view model
         _viewModel = new SessionViewModel();
+        _viewModel.PropertyChanged += ViewModel_PropertyChanged;
         DataContext = _viewModel;
+        Closed += (_, _) => _viewModel.Dispose();
+    }
+
+    private void ViewModel_PropertyChanged(object? sender, PropertyChangedEventArgs e)
+    {
+        // WebBrowser.Source cannot be bound to an HTML string
+        if (e.PropertyName == nameof(SessionViewModel.DiffHtml))
+        {
+            DiffView.NavigateToString(
+                $"<html><head><meta charset=\"utf-8\">{DiffStyle}</head><body>{_viewModel.DiffHtml}</body></html>");
+        }
+    }
+
+    /// <summary>
+    /// Runs a workflow step, reporting FFI failures instead of crashing the UI.
+    /// </summary>
+    private async Task RunStep(Func<Task> step)
+    {
+        try
+        {
+            await step();
+        }
+        catch (AwbException ex) when (ex.Status == AwbStatus.RateLimited)
+        {
+            MessageBox.Show($"{ex.Message}\n\nRetry in {ex.RetryAfterSeconds} seconds.", "Rate Limited",
+                MessageBoxButton.OK, MessageBoxImage.Warning);
+        }
+        catch (AwbException ex)
+        {
+            MessageBox.Show(ex.Message, ex.Status.ToString(),
+                MessageBoxButton.OK, MessageBoxImage.Error);
+        }
+        catch (InvalidOperationException ex)
+        {
+            MessageBox.Show(ex.Message, "AWBrowser",
+                MessageBoxButton.OK, MessageBoxImage.Information);
+        }
+    }
+
+    /// <summary>
+    /// Moves the list selection on, which loads the next page for review.
+    /// </summary>
+    private void AdvanceToNextPage()
+    {
+        PageModel? next = _viewModel.NextPage();
+        if (next is null)
+        {
+            _viewModel.StatusMessage = "Reached the end of the list";
+            return;
+        }
+        PageListBox.SelectedItem = next;
+        PageListBox.ScrollIntoView(next);
     }
 
     // ============================================================================
     // Menu Handlers
     // ============================================================================
 
-    private void Login_Click(object sender, RoutedEventArgs e)
+    private async void Login_Click(object sender, RoutedEventArgs e)
     {
         var loginWindow = new LoginWindow
         {
@@ -34,23 +96,23 @@ public partial class MainWindow : Window
 
         if (loginWindow.ShowDialog() == true)
         {
-            // TODO: Create session and login using FFI
-            StatusText.Text = $"Logged in as {loginWindow.Username} to {loginWindow.WikiUrl}";
+            await RunStep(() => _viewModel.LoginAsync(
+                loginWindow.WikiUrl, loginWindow.Username, loginWindow.Password));
         }
     }
 
     private void Open_Executed(object sender, ExecutedRoutedEventArgs e)
     {
-        // TODO: Open page dialog
-        MessageBox.Show("Open page functionality to be implemented", "Not Implemented",
-            MessageBoxButton.OK, MessageBoxImage.Information);
+        GetPage_Click(sender, e);
     }
 
-    private void Save_Executed(object sender, ExecutedRoutedEventArgs e)
+    private async void Save_Executed(object sender, ExecutedRoutedEventArgs e)
     {
-        // TODO: Save page via FFI
-        MessageBox.Show("Save page functionality to be implemented", "Not Implemented",
-            MessageBoxButton.OK, MessageBoxImage.Information);
+        await RunStep(async () =>
+        {
+            await _viewModel.SaveAsync();
+            AdvanceToNextPage();
+        });
     }
 
     private void Close_Executed(object sender, ExecutedRoutedEventArgs e)
@@ -68,18 +130,18 @@ public partial class MainWindow : Window
         // TODO: Toggle rules panel visibility
     }
 
-    private void ApplyRules_Click(object sender, RoutedEventArgs e)
+    private async void ApplyRules_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Apply rules via FFI
-        MessageBox.Show("Apply rules functionality to be implemented", "Not Implemented",
-            MessageBoxButton.OK, MessageBoxImage.Information);
+        await RunStep(_viewModel.ApplyRulesAsync);
     }
 
-    private void PreviewChanges_Click(object sender, RoutedEventArgs e)
+    private async void PreviewChanges_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Generate diff preview
-        MessageBox.Show("Preview functionality to be implemented", "Not Implemented",
-            MessageBoxButton.OK, MessageBoxImage.Information);
+        await RunStep(async () =>
+        {
+            await _viewModel.PreviewAsync();
+            EditorTabs.SelectedIndex = 1;
+        });
     }
 
     private void Settings_Click(object sender, RoutedEventArgs e)
@@ -105,30 +167,49 @@ public partial class MainWindow : Window
     // Toolbar Handlers
     // ============================================================================
 
-    private void FetchList_Click(object sender, RoutedEventArgs e)
+    private async void FetchList_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Show fetch list dialog
-        MessageBox.Show("Fetch list functionality to be implemented", "Not Implemented",
-            MessageBoxButton.OK, MessageBoxImage.Information);
+        if (string.IsNullOrWhiteSpace(_viewModel.ListQuery))
+        {
+            MessageBox.Show("Enter a category, search query or page to fetch", "Fetch List",
+                MessageBoxButton.OK, MessageBoxImage.Information);
+            return;
+        }
+
+        await RunStep(async () =>
+        {
+            await _viewModel.FetchListAsync();
+            AdvanceToNextPage();
+        });
     }
 
-    private void GetPage_Click(object sender, RoutedEventArgs e)
+    private async void GetPage_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Get single page
-        MessageBox.Show("Get page functionality to be implemented", "Not Implemented",
-            MessageBoxButton.OK, MessageBoxImage.Information);
+        // Reload the selected page, discarding edits made during review
+        if (PageListBox.SelectedItem is PageModel page)
+        {
+            await RunStep(() => _viewModel.LoadPageAsync(page));
+        }
+    }
+
+    private void Skip_Click(object sender, RoutedEventArgs e)
+    {
+        AdvanceToNextPage();
     }
 
     // ============================================================================
     // Page List Handlers
     // ============================================================================
 
-    private void PageListBox_SelectionChanged(object sender, System.Windows.Controls.SelectionChangedEventArgs e)
+    private async void PageListBox_SelectionChanged(object sender, System.Windows.Controls.SelectionChangedEventArgs e)
     {
-        // TODO: Load selected page into editor
-        if (PageListBox.SelectedItem != null)
+        if (PageListBox.SelectedItem is PageModel page)
         {
-            StatusText.Text = $"Loaded: {PageListBox.SelectedItem}";
+            await RunStep(async () =>
+            {
+                await _viewModel.LoadPageAsync(page);
+                EditorTabs.SelectedIndex = 1;
+            });
         }
     }
 
@@ -138,21 +219,43 @@ public partial class MainWindow : Window
 
     private void AddRule_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Add new rule to grid
+        var rule = new RuleModel();
+        _viewModel.Rules.Add(rule);
+        RulesGrid.SelectedItem = rule;
     }
 
     private void RemoveRule_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Remove selected rule
+        if (RulesGrid.SelectedItem is RuleModel rule)
+        {
+            _viewModel.Rules.Remove(rule);
+        }
     }
 
     private void MoveRuleUp_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Move selected rule up
+        MoveSelectedRule(-1);
     }
 
     private void MoveRuleDown_Click(object sender, RoutedEventArgs e)
     {
-        // TODO: Move selected rule down
+        MoveSelectedRule(1);
+    }
+
+    // Rules run in list order
+    private void MoveSelectedRule(int offset)
+    {
+        if (RulesGrid.SelectedItem is not RuleModel rule)
+        {
+            return;
+        }
+
+        int index = _viewModel.Rules.IndexOf(rule);
+        int target = index + offset;
+        if (target >= 0 && target < _viewModel.Rules.Count)
+        {
+            _viewModel.Rules.Move(index, target);
+            RulesGrid.SelectedItem = rule;
+        }
     }
 }
@@ -49,7 +49,114 @@ public static class NativeMethods
     }
 
     // ============================================================================
-    // Session Management
+    // Versioned C ABI (awb_v1_*)
+    // ============================================================================
+    //
+    // Every v1 function returns an AwbStatus. Strings cross the boundary as
+    // UTF-8, and structured results are JSON written to out-parameters that
+    // must be freed with awb_v1_string_free(). The error message of a failed
+    // call is thread-local, so read it on the thread that made the call.
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern uint awb_v1_abi_version();
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_session_create(
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string wikiUrl,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string username,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string password,
+        out ulong handle);
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_session_destroy(ulong handle);
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_login(ulong handle);
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_fetch_list(
+        ulong handle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string source,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string query,
+        out IntPtr json);
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_get_page(
+        ulong handle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string title,
+        out IntPtr json);
+
+    /// <summary>
+    /// Returns <see cref="AwbStatus.NoChange"/> without editing when the
+    /// content matches the current revision.
+    /// </summary>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_save_page(
+        ulong handle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string title,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string content,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string summary);
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_apply_rules(
+        ulong handle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string content,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string rulesJson,
+        out IntPtr json);
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern AwbStatus awb_v1_compute_diff(
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string oldText,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string newText,
+        out IntPtr html);
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr awb_v1_last_error_message();
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern ulong awb_v1_last_error_retry_after();
+
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern void awb_v1_string_free(IntPtr ptr);
+
+    /// <summary>
+    /// Throws an <see cref="AwbException"/> carrying the last error message
+    /// unless <paramref name="status"/> is Ok or one of <paramref name="allowed"/>.
+    /// </summary>
+    public static AwbStatus Check(AwbStatus status, params AwbStatus[] allowed)
+    {
+        if (status == AwbStatus.Ok || Array.IndexOf(allowed, status) >= 0)
+        {
+            return status;
+        }
+
+        string message = TakeString(awb_v1_last_error_message()) ?? status.ToString();
+        ulong retryAfter = awb_v1_last_error_retry_after();
+        throw new AwbException(status, message, retryAfter);
+    }
+
+    /// <summary>
+    /// Copies a UTF-8 string returned by a v1 function and frees it.
+    /// </summary>
+    public static string? TakeString(IntPtr ptr)
+    {
+        if (ptr == IntPtr.Zero)
+        {
+            return null;
+        }
+
+        try
+        {
+            return Marshal.PtrToStringUTF8(ptr);
+        }
+        finally
+        {
+            awb_v1_string_free(ptr);
+        }
+    }
+
+    // ============================================================================
+    // Legacy Session API (superseded by awb_v1_*)
     // ============================================================================
 
     /// <summary>
@@ -202,6 +309,50 @@ public static class NativeMethods
     }
 }
 
+/// <summary>
+/// Status codes returned by the awb_v1_* functions (AWB_STATUS_* in awb_ffi.h).
+/// </summary>
+public enum AwbStatus : uint
+{
+    Ok = 0,
+    InvalidArgument = 1,
+    NetworkError = 2,
+    AuthenticationError = 3,
+    NotFound = 4,
+    PermissionDenied = 5,
+    ParseError = 6,
+    SessionNotFound = 7,
+    LockPoisoned = 8,
+    EngineError = 9,
+    PluginError = 10,
+    RateLimited = 11,
+    EditConflict = 12,
+    Blocked = 13,
+    TokenExpired = 14,
+    Protection = 15,
+    NoChange = 16,
+}
+
+/// <summary>
+/// A failed awb_v1_* call.
+/// </summary>
+public class AwbException : Exception
+{
+    public AwbStatus Status { get; }
+
+    /// <summary>
+    /// Seconds to wait before retrying when <see cref="Status"/> is RateLimited, otherwise 0.
+    /// </summary>
+    public ulong RetryAfterSeconds { get; }
+
+    public AwbException(AwbStatus status, string message, ulong retryAfterSeconds)
+        : base(message)
+    {
+        Status = status;
+        RetryAfterSeconds = retryAfterSeconds;
+    }
+}
+
 /// <summary>
 /// Represents a session handle returned from create_session.
 /// </summary>
@@ -1,7 +1,10 @@
 using System;
+using System.Collections.Generic;
 using System.Collections.ObjectModel;
 using System.ComponentModel;
+using System.Linq;
 using System.Runtime.CompilerServices;
+using System.Threading.Tasks;
 using AWBrowser.Models;
 
 namespace AWBrowser.ViewModels;
@@ -10,13 +13,21 @@ namespace AWBrowser.ViewModels;
 /// View model for the main session state.
 /// Implements MVVM pattern for data binding.
 /// </summary>
-public class SessionViewModel : INotifyPropertyChanged
+public class SessionViewModel : INotifyPropertyChanged, IDisposable
 {
+    private WikiSession? _session;
     private string _wikiUrl = string.Empty;
     private string _username = string.Empty;
     private bool _isLoggedIn;
     private string _statusMessage = "Ready";
     private int _progressValue;
+    private string _listSource = "category";
+    private string _listQuery = string.Empty;
+    private PageModel? _currentPage;
+    private string _originalText = string.Empty;
+    private string _diffHtml = string.Empty;
+    private string _editSummary = string.Empty;
+    private bool _isBusy;
 
     public event PropertyChangedEventHandler? PropertyChanged;
 
@@ -60,9 +71,214 @@ public class SessionViewModel : INotifyPropertyChanged
         set => SetField(ref _progressValue, value);
     }
 
+    /// <summary>
+    /// List source passed to awb_v1_fetch_list: "category", "search" or "backlinks".
+    /// </summary>
+    public string ListSource
+    {
+        get => _listSource;
+        set => SetField(ref _listSource, value);
+    }
+
+    public string ListQuery
+    {
+        get => _listQuery;
+        set => SetField(ref _listQuery, value);
+    }
+
+    /// <summary>
+    /// The page under review. Its Wikitext holds the proposed text.
+    /// </summary>
+    public PageModel? CurrentPage
+    {
+        get => _currentPage;
+        private set => SetField(ref _currentPage, value);
+    }
+
+    public string DiffHtml
+    {
+        get => _diffHtml;
+        private set => SetField(ref _diffHtml, value);
+    }
+
+    public string EditSummary
+    {
+        get => _editSummary;
+        set => SetField(ref _editSummary, value);
+    }
+
+    public bool IsBusy
+    {
+        get => _isBusy;
+        private set => SetField(ref _isBusy, value);
+    }
+
     public ObservableCollection<PageModel> Pages { get; }
     public ObservableCollection<RuleModel> Rules { get; }
 
+    // ============================================================================
+    // Workflow: login, fetch a list, review each page, save or skip
+    // ============================================================================
+    //
+    // FFI calls block, so each runs on the thread pool. AwbExceptions are
+    // left for the view to report.
+
+    public async Task LoginAsync(string wikiUrl, string username, string password)
+    {
+        var session = await RunAsync($"Logging in to {wikiUrl}...",
+            () => WikiSession.Login(wikiUrl, username, password));
+
+        _session?.Dispose();
+        _session = session;
+        WikiUrl = wikiUrl;
+        Username = username;
+        IsLoggedIn = true;
+        StatusMessage = $"Logged in as {username} to {wikiUrl}";
+    }
+
+    public async Task FetchListAsync()
+    {
+        var session = RequireSession();
+        string source = ListSource;
+        string query = ListQuery;
+        var titles = await RunAsync($"Fetching {source} list for '{query}'...",
+            () => session.FetchList(source, query));
+
+        Pages.Clear();
+        foreach (string title in titles)
+        {
+            Pages.Add(new PageModel { Title = title });
+        }
+        ClearReview();
+        ProgressValue = 0;
+        StatusMessage = $"Fetched {Pages.Count} pages";
+    }
+
+    /// <summary>
+    /// Loads the page and runs the rules over it, ready for review.
+    /// </summary>
+    public async Task LoadPageAsync(PageModel page)
+    {
+        var session = RequireSession();
+        var rules = Rules.ToList();
+        var (info, result) = await RunAsync($"Loading {page.Title}...", () =>
+        {
+            PageInfo fetched = session.GetPage(page.Title);
+            return (fetched, session.ApplyRules(fetched.Wikitext, rules));
+        });
+
+        page.PageId = info.PageId;
+        page.Revision = info.Revision;
+        page.Timestamp = info.Timestamp;
+        page.SizeBytes = info.SizeBytes;
+        page.IsRedirect = info.IsRedirect;
+        page.Wikitext = result.NewWikitext;
+        page.IsModified = result.NewWikitext != info.Wikitext;
+
+        _originalText = info.Wikitext;
+        CurrentPage = page;
+        DiffHtml = result.DiffHtml;
+        EditSummary = result.Summary;
+        StatusMessage = page.IsModified
+            ? $"{page.Title}: {result.RulesApplied.Length} rules, {result.FixesApplied.Length} fixes applied"
+            : $"{page.Title}: no changes";
+    }
+
+    /// <summary>
+    /// Re-runs the rules over the text being reviewed.
+    /// </summary>
+    public async Task ApplyRulesAsync()
+    {
+        var session = RequireSession();
+        var page = CurrentPage ?? throw new InvalidOperationException("No page loaded");
+        var rules = Rules.ToList();
+        string text = page.Wikitext;
+        var result = await RunAsync("Applying rules...", () => session.ApplyRules(text, rules));
+
+        page.Wikitext = result.NewWikitext;
+        await PreviewAsync();
+        if (!string.IsNullOrEmpty(result.Summary))
+        {
+            EditSummary = result.Summary;
+        }
+    }
+
+    /// <summary>
+    /// Recomputes the diff between the loaded revision and the text under review.
+    /// </summary>
+    public async Task PreviewAsync()
+    {
+        var page = CurrentPage ?? throw new InvalidOperationException("No page loaded");
+        string original = _originalText;
+        string text = page.Wikitext;
+        DiffHtml = await RunAsync("Computing diff...", () => WikiSession.ComputeDiff(original, text));
+        page.IsModified = text != original;
+        StatusMessage = "Ready";
+    }
+
+    /// <summary>
+    /// Saves the page under review. Returns false if the wiki reported no change.
+    /// </summary>
+    public async Task<bool> SaveAsync()
+    {
+        var session = RequireSession();
+        var page = CurrentPage ?? throw new InvalidOperationException("No page loaded");
+        string text = page.Wikitext;
+        string summary = EditSummary;
+        bool saved = await RunAsync($"Saving {page.Title}...",
+            () => session.Save(page.Title, text, summary));
+
+        page.IsModified = false;
+        StatusMessage = saved ? $"Saved {page.Title}" : $"{page.Title}: no change to save";
+        return saved;
+    }
+
+    /// <summary>
+    /// The page after the one under review, or null at the end of the list.
+    /// </summary>
+    public PageModel? NextPage()
+    {
+        int index = CurrentPage is null ? -1 : Pages.IndexOf(CurrentPage);
+        ProgressValue = Pages.Count == 0 ? 0 : (index + 1) * 100 / Pages.Count;
+        return index + 1 < Pages.Count ? Pages[index + 1] : null;
+    }
+
+    public void Dispose()
+    {
+        _session?.Dispose();
+        _session = null;
+    }
+
+    private WikiSession RequireSession() =>
+        _session ?? throw new InvalidOperationException("Log in first");
+
+    private void ClearReview()
+    {
+        CurrentPage = null;
+        _originalText = string.Empty;
+        DiffHtml = string.Empty;
+        EditSummary = string.Empty;
+    }
+
+    private async Task<T> RunAsync<T>(string status, Func<T> call)
+    {
+        StatusMessage = status;
+        IsBusy = true;
+        try
+        {
+            return await Task.Run(call);
+        }
+        catch (Exception)
+        {
+            StatusMessage = "Ready";
+            throw;
+        }
+        finally
+        {
+            IsBusy = false;
+        }
+    }
+
     // ============================================================================
     // INotifyPropertyChanged Implementation
     // ============================================================================
@@ -0,0 +1,146 @@
+using System;
+using System.Collections.Generic;
+using System.Linq;
+using System.Text.Json;
+using System.Text.Json.Serialization;
+using AWBrowser.Models;
+
+namespace AWBrowser;
+
+/// <summary>
+/// A wiki session backed by the awb_v1_* C ABI.
+/// Calls block on network I/O, so run them off the UI thread.
+/// </summary>
+public sealed class WikiSession : IDisposable
+{
+    private static readonly JsonSerializerOptions JsonOptions = new()
+    {
+        PropertyNamingPolicy = JsonNamingPolicy.SnakeCaseLower,
+        DefaultIgnoreCondition = JsonIgnoreCondition.WhenWritingNull,
+    };
+
+    private ulong _handle;
+
+    private WikiSession(ulong handle)
+    {
+        _handle = handle;
+    }
+
+    /// <summary>
+    /// Creates a session and logs in with the given credentials.
+    /// </summary>
+    public static WikiSession Login(string wikiUrl, string username, string password)
+    {
+        NativeMethods.Check(NativeMethods.awb_v1_session_create(wikiUrl, username, password, out ulong handle));
+        var session = new WikiSession(handle);
+        try
+        {
+            NativeMethods.Check(NativeMethods.awb_v1_login(handle));
+        }
+        catch
+        {
+            session.Dispose();
+            throw;
+        }
+        return session;
+    }
+
+    /// <summary>
+    /// Fetches page titles from <paramref name="source"/>: "category", "search" or "backlinks".
+    /// </summary>
+    public string[] FetchList(string source, string query)
+    {
+        NativeMethods.Check(NativeMethods.awb_v1_fetch_list(Handle, source, query, out IntPtr json));
+        return Deserialize<string[]>(json);
+    }
+
+    public PageInfo GetPage(string title)
+    {
+        NativeMethods.Check(NativeMethods.awb_v1_get_page(Handle, title, out IntPtr json));
+        return Deserialize<PageInfo>(json);
+    }
+
+    /// <summary>
+    /// Runs the enabled rules over <paramref name="content"/>.
+    /// </summary>
+    public TransformResult ApplyRules(string content, IEnumerable<RuleModel> rules)
+    {
+        string rulesJson = JsonSerializer.Serialize(RuleSetJson.From(rules), JsonOptions);
+        NativeMethods.Check(NativeMethods.awb_v1_apply_rules(Handle, content, rulesJson, out IntPtr json));
+        return Deserialize<TransformResult>(json);
+    }
+
+    /// <summary>
+    /// Saves the page. Returns false when the content matched the current
+    /// revision and no edit was made.
+    /// </summary>
+    public bool Save(string title, string content, string summary)
+    {
+        AwbStatus status = NativeMethods.Check(
+            NativeMethods.awb_v1_save_page(Handle, title, content, summary),
+            AwbStatus.NoChange);
+        return status == AwbStatus.Ok;
+    }
+
+    public static string ComputeDiff(string oldText, string newText)
+    {
+        NativeMethods.Check(NativeMethods.awb_v1_compute_diff(oldText, newText, out IntPtr html));
+        return NativeMethods.TakeString(html) ?? string.Empty;
+    }
+
+    public void Dispose()
+    {
+        if (_handle != 0)
+        {
+            NativeMethods.awb_v1_session_destroy(_handle);
+            _handle = 0;
+        }
+    }
+
+    private ulong Handle => _handle != 0
+        ? _handle
+        : throw new ObjectDisposedException(nameof(WikiSession));
+
+    private static T Deserialize<T>(IntPtr json)
+    {
+        string text = NativeMethods.TakeString(json)
+            ?? throw new InvalidOperationException("FFI call returned no JSON");
+        return JsonSerializer.Deserialize<T>(text, JsonOptions)
+            ?? throw new InvalidOperationException($"FFI call returned invalid {typeof(T).Name} JSON");
+    }
+
+    // Mirrors awb_domain::rules::RuleSet. RuleKind is externally tagged, so
+    // each rule's kind is an object with a single "Plain" or "Regex" key.
+    private sealed record RuleSetJson(List<RuleJson> Rules)
+    {
+        public static RuleSetJson From(IEnumerable<RuleModel> rules) =>
+            new(rules
+                .Where(r => !string.IsNullOrEmpty(r.Pattern))
+                .Select((r, i) => new RuleJson(
+                    Guid.NewGuid(),
+                    r.Enabled,
+                    (uint)i,
+                    r.IsRegex
+                        ? new Dictionary<string, object>
+                        {
+                            ["Regex"] = new RegexKind(r.Pattern, r.Replacement, !r.CaseSensitive),
+                        }
+                        : new Dictionary<string, object>
+                        {
+                            ["Plain"] = new PlainKind(r.Pattern, r.Replacement, r.CaseSensitive),
+                        },
+                    string.IsNullOrEmpty(r.Name) ? null : r.Name))
+                .ToList());
+    }
+
+    private sealed record RuleJson(
+        Guid Id,
+        bool Enabled,
+        uint Order,
+        Dictionary<string, object> Kind,
+        string? CommentFragment);
+
+    private sealed record PlainKind(string Find, string Replace, bool CaseSensitive);
+
+    private sealed record RegexKind(string Pattern, string Replacement, bool CaseInsensitive);
+}